@@ -0,0 +1,37 @@
+#!/usr/bin/env bash
+# Check that the library builds under each supported feature set
+#
+# The core library must build without the CLI, TUI or WebSocket
+# dependencies; this catches a core module importing a gated one. The
+# library's own tests are compiled without features too, since a test
+# module can reach for a gated item just as easily.
+#
+# Usage:
+#   ./scripts/check_features.sh [extra cargo check args...]
+
+set -euo pipefail
+
+cd "$(dirname "$0")/.."
+
+FEATURE_SETS=(
+    "--no-default-features"
+    "--no-default-features --features ws"
+    "--no-default-features --features tui"
+    "--no-default-features --features ws,tui"
+    "--no-default-features --features cli"
+    "--no-default-features --features test-support"
+)
+
+for features in "${FEATURE_SETS[@]}"; do
+    echo "==> cargo check --lib $features"
+    # shellcheck disable=SC2086
+    cargo check --lib $features "$@"
+done
+
+echo "==> cargo test --no-run --lib --no-default-features"
+cargo test --no-run --lib --no-default-features "$@"
+
+echo "==> cargo check --all-targets (default features)"
+cargo check --all-targets "$@"
+
+echo "All feature sets build"
@@ -350,7 +350,7 @@ run_test "generate-key-pair" \
 # generate-vault-address: Generate vault address from key
 run_test "generate-vault-address" \
     "cargo run -q --release -- generate-vault-address" \
-    "Vault address.*1111[a-zA-Z0-9]+"
+    "Vault address.*1111 [a-zA-Z0-9]{4}"
 
 # ============================================
 # NODE INSPECTION COMMANDS (HTTP)
@@ -445,7 +445,7 @@ echo -e "${BLUE}--- Transfer Commands ---${NC}"
 # Uses ConnectionManager with full_deploy_and_wait (deploy -> finalize -> read)
 echo -n "Testing transfer... "
 TRANSFER_START=$(date +%s.%N)
-if cargo run -q --release -- transfer --to-address 111127RX5ZgiAdRaQy4AWy57RdvAAckdELReEBxzvWYVvdnR32PiHA --amount 1 -H $HOST -p $GRPC_PORT --http-port $HTTP_PORT --observer-port $OBSERVER_GRPC --max-wait 120 --check-interval 2 > "$OUTPUT" 2>&1; then
+if cargo run -q --release -- transfer --yes --to-address 111127RX5ZgiAdRaQy4AWy57RdvAAckdELReEBxzvWYVvdnR32PiHA --amount 1 -H $HOST -p $GRPC_PORT --http-port $HTTP_PORT --observer-port $OBSERVER_GRPC --max-wait 120 --check-interval 2 > "$OUTPUT" 2>&1; then
     TRANSFER_END=$(date +%s.%N)
     TRANSFER_MS=$(echo "($TRANSFER_END - $TRANSFER_START) * 1000" | bc | cut -d. -f1)
     save_log "transfer"
@@ -544,7 +544,7 @@ TA_WS_PID=$!
 sleep 3  # Let WS connect
 
 # Submit a transfer
-cargo run -q --release -- transfer --to-address 111127RX5ZgiAdRaQy4AWy57RdvAAckdELReEBxzvWYVvdnR32PiHA --amount 1 -H $HOST -p $GRPC_PORT --http-port $HTTP_PORT --observer-port $OBSERVER_GRPC --max-wait 120 --check-interval 2 > /dev/null 2>&1 || true
+cargo run -q --release -- transfer --yes --to-address 111127RX5ZgiAdRaQy4AWy57RdvAAckdELReEBxzvWYVvdnR32PiHA --amount 1 -H $HOST -p $GRPC_PORT --http-port $HTTP_PORT --observer-port $OBSERVER_GRPC --max-wait 120 --check-interval 2 > /dev/null 2>&1 || true
 
 # Wait for WS to capture events (up to remaining time)
 wait $TA_WS_PID 2>/dev/null || true
@@ -1,15 +1,132 @@
-use clap::{ArgAction, Parser, Subcommand};
+use clap::{ArgAction, Args, Parser, Subcommand};
 use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::block_follow::Until;
+use crate::ports::{self, NodeRole};
+use crate::staking::PosArg;
+use crate::vault::RevAmount;
+use crate::verification::VerifyTarget;
+
+pub use crate::key_guard::DEV_PRIVATE_KEY;
+
+/// Default node host for every command
+pub const DEFAULT_HOST: &str = "localhost";
+/// gRPC port of the first validator in the standard shard, for commands that write
+pub const DEFAULT_GRPC_PORT: u16 = ports::VALIDATOR1.grpc_internal;
+/// HTTP port of the first validator in the standard shard, for commands that write
+pub const DEFAULT_HTTP_PORT: u16 = ports::VALIDATOR1.http;
+/// gRPC port of the read-only observer node, for commands that read
+pub const OBSERVER_GRPC_PORT: u16 = ports::OBSERVER.grpc_internal;
+/// HTTP port of the read-only observer node, for commands that read
+pub const OBSERVER_HTTP_PORT: u16 = ports::OBSERVER.http;
+/// HTTP (and WebSocket events) port of the bootstrap node
+pub const BOOTSTRAP_HTTP_PORT: u16 = ports::BOOTSTRAP.http;
+/// Nodes `network-health` queries at once
+pub const DEFAULT_HEALTH_CONCURRENCY: usize = 8;
+
+/// `--node-role` gRPC port defaults, in the form clap's conditional defaults
+/// take; `test_node_role_sets_ports_from_matrix` checks them against [`ports`]
+const ROLE_GRPC_PORTS: [(&str, &str, Option<&str>); 5] = [
+    ("node_role", "bootstrap", Some("40402")),
+    ("node_role", "validator1", Some("40412")),
+    ("node_role", "validator2", Some("40422")),
+    ("node_role", "validator3", Some("40432")),
+    ("node_role", "observer", Some("40452")),
+];
+
+/// `--node-role` HTTP port defaults, like [`ROLE_GRPC_PORTS`]
+const ROLE_HTTP_PORTS: [(&str, &str, Option<&str>); 5] = [
+    ("node_role", "bootstrap", Some("40403")),
+    ("node_role", "validator1", Some("40413")),
+    ("node_role", "validator2", Some("40423")),
+    ("node_role", "validator3", Some("40433")),
+    ("node_role", "observer", Some("40453")),
+];
+
+/// `--http-port` defaults of the commands that pair an explore-deploy over
+/// HTTP with gRPC queries: the `--node-role` default, else the HTTP port of the
+/// standard shard node `--port` belongs to, so both halves reach the same node
+/// (`test_pos_http_ports_match_matrix` checks them against [`ports`])
+const POS_HTTP_PORTS: [(&str, &str, Option<&str>); 15] = [
+    ("node_role", "bootstrap", Some("40403")),
+    ("node_role", "validator1", Some("40413")),
+    ("node_role", "validator2", Some("40423")),
+    ("node_role", "validator3", Some("40433")),
+    ("node_role", "observer", Some("40453")),
+    ("port", "40401", Some("40403")),
+    ("port", "40402", Some("40403")),
+    ("port", "40411", Some("40413")),
+    ("port", "40412", Some("40413")),
+    ("port", "40421", Some("40423")),
+    ("port", "40422", Some("40423")),
+    ("port", "40431", Some("40433")),
+    ("port", "40432", Some("40433")),
+    ("port", "40451", Some("40453")),
+    ("port", "40452", Some("40453")),
+];
+
+/// Parse a duration flag where a bare number means seconds (e.g. 30, 30s, 5m, 1h30m)
+///
+/// Every interval and timeout flag goes through this or [`parse_millis`];
+/// `test_duration_flags_use_shared_parser` lists them.
+pub fn parse_seconds(value: &str) -> Result<Duration, String> {
+    crate::utils::parse_duration(value, "s")
+}
 
-/// Well-known bootstrap validator private key used in dev/test Docker setups.
-/// NOT for production use.
-pub const DEV_PRIVATE_KEY: &str =
-    "5f668a7ee96d944a4494cc947e4005e172d7ab3461ee5538f1f2a45a835e9657";
+/// Parse a duration flag where a bare number means milliseconds (e.g. 200, 500ms, 1s)
+pub fn parse_millis(value: &str) -> Result<Duration, String> {
+    crate::utils::parse_duration(value, "ms")
+}
 
 /// Command-line interface for interacting with F1r3fly nodes
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 pub struct Cli {
+    /// Skip confirmation prompts for transfers and staking (required when stdin is not a terminal)
+    #[arg(short = 'y', long, global = true)]
+    pub yes: bool,
+
+    /// Directory for local CLI state such as history, caches and timings
+    /// (default: FIREFLY_CONFIG_DIR, then ~/.config/node_cli)
+    #[arg(long, global = true, value_name = "DIR")]
+    pub config_dir: Option<PathBuf>,
+
+    /// Print only results, warnings and errors, without progress lines
+    #[arg(long, global = true)]
+    pub quiet: bool,
+
+    /// Skip node version checks for features such as websocket events and
+    /// blocks-by-height (for forks with nonstandard version strings)
+    #[arg(long, global = true)]
+    pub assume_capabilities: bool,
+
+    /// Do not reuse results of exploratory deploys pinned to the same block
+    #[arg(long, global = true)]
+    pub no_query_cache: bool,
+
+    /// Pinned exploratory deploy results kept in memory per client
+    #[arg(long, global = true, value_name = "N", default_value_t = crate::query_cache::DEFAULT_CAPACITY)]
+    pub query_cache_size: usize,
+
+    /// Print the settings the command runs with and where each came from
+    /// (flag, env, node-role, config.toml or default) before running it
+    #[arg(long, global = true)]
+    pub print_config: bool,
+
+    /// Print the settings as for --print-config and exit without running the command
+    #[arg(long, global = true)]
+    pub print_config_only: bool,
+
+    /// Characters of hashes and keys kept in tables and pretty output before
+    /// they are cut with "…" (default: 16, or 8 in narrow columns such as the DAG's)
+    #[arg(long, global = true, value_name = "N", conflicts_with = "full")]
+    pub truncate: Option<usize>,
+
+    /// Show hashes and keys in full in tables and pretty output
+    #[arg(long, global = true)]
+    pub full: bool,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -23,20 +140,36 @@ pub enum Commands {
     Propose(ProposeArgs),
 
     /// Deploy Rholang code, wait for finalization, and read result
+    #[command(visible_alias = "dw")]
     DeployAndWait(DeployAndWaitArgs),
 
+    /// Resume waiting for a deploy already sent, until its block is finalized
+    WaitForDeploy(WaitForDeployArgs),
+
     /// Read data at a deploy ID from a specific block
     GetData(GetDataArgs),
 
+    /// Read data at a public or unforgeable name
+    ///
+    /// Searches back from the main-chain tip for the newest block with data at
+    /// the name, or reads at --block-hash.
+    DataAtName(DataAtNameArgs),
+
     /// Check if a block is finalized
     IsFinalized(IsFinalizedArgs),
 
     /// Execute Rholang code without committing to the blockchain (exploratory deployment)
+    #[command(visible_alias = "xd")]
     ExploratoryDeploy(ExploratoryDeployArgs),
 
     /// Estimate phlogiston cost of Rholang code without deploying
     EstimateCost(ExploratoryDeployArgs),
 
+    /// Report a Rholang file's size and complexity against the deploy limits
+    ///
+    /// Exits non-zero when the term is over a limit, for use in CI.
+    Analyze(AnalyzeArgs),
+
     /// Generate a public key from a private key
     GeneratePublicKey(GeneratePublicKeyArgs),
 
@@ -53,12 +186,13 @@ pub enum Commands {
     Blocks(BlocksArgs),
 
     /// Get current validator bonds from PoS contract
-    Bonds(HttpArgs),
+    Bonds(ValidatorListArgs),
 
     /// Get active validators from PoS contract
-    ActiveValidators(HttpArgs),
+    ActiveValidators(ValidatorListArgs),
 
     /// Check wallet balance for a specific address
+    #[command(visible_alias = "wb")]
     WalletBalance(WalletBalanceArgs),
 
     /// Check if a validator is bonded
@@ -70,10 +204,17 @@ pub enum Commands {
     /// Bond a new validator to the network (dynamic validator addition)
     BondValidator(BondValidatorArgs),
 
+    /// Increase the stake of an already bonded validator
+    AddStake(AddStakeArgs),
+
+    /// Withdraw a validator's bond, leaving the validator set after quarantine
+    UnbondValidator(UnbondValidatorArgs),
+
     /// Check network health across multiple nodes
     NetworkHealth(NetworkHealthArgs),
 
     /// Get the last finalized block
+    #[command(visible_alias = "lfb")]
     LastFinalizedBlock(HttpArgs),
 
     /// Get blocks in the main chain
@@ -82,12 +223,37 @@ pub enum Commands {
     /// Transfer tokens between addresses
     Transfer(TransferArgs),
 
+    /// Pay every address in a CSV file of address,amount[,memo] rows
+    ///
+    /// All rows go out in one deploy by default, each row reporting its own
+    /// result; --sequential sends one transfer deploy per row instead.
+    /// --dry-run prints the Rholang without deploying.
+    BatchTransfer(BatchTransferArgs),
+
     /// Run load test by sending multiple transfers and tracking orphan rate
     LoadTest(LoadTestArgs),
 
+    /// Generate test wallets and fund them from the signing key
+    ///
+    /// Writes a manifest of keys and addresses before sending anything, funds
+    /// the wallets a chunk per deploy, then checks each balance. An interrupted
+    /// or partly failed run continues with --resume. Dev nodes only.
+    CreateTestWallets(CreateTestWalletsArgs),
+
+    /// Time the deploy pipeline with a no-op deploy, per stage
+    ///
+    /// Repeats --count rounds and reports min/p50/p95 for each stage. With
+    /// --baseline, the first run saves the results and later runs compare
+    /// against them. --read-only deploys nothing and times exploratory and
+    /// status calls instead.
+    LatencyProbe(LatencyProbeArgs),
+
     /// Get a specific deploy by ID
     GetDeploy(GetDeployArgs),
 
+    /// Resubmit a stuck deploy's term with a higher phlo price
+    BumpDeploy(BumpDeployArgs),
+
     /// Get current epoch information and status
     EpochInfo(PosQueryArgs),
 
@@ -100,6 +266,13 @@ pub enum Commands {
     /// Get network-wide consensus health overview
     NetworkConsensus(PosQueryArgs),
 
+    /// Call a PoS contract method and print its answer
+    ///
+    /// For PoS values no other command shows, e.g. getMinimumBond or
+    /// getMaximumBond. Arguments that parse as integers are passed as ints,
+    /// the rest (or anything in double quotes) as strings.
+    PosQuery(PosMethodArgs),
+
     /// Get blocks by height range
     GetBlocksByHeight(GetBlocksByHeightArgs),
 
@@ -109,35 +282,427 @@ pub enum Commands {
     /// Watch real-time node events via WebSocket
     WatchEvents(WatchEventsArgs),
 
+    /// Report deploys from, and transfers to, watched keys or vault addresses as blocks are added
+    WatchWallet(WatchWalletArgs),
+
+    /// Inspect the CLI's own configuration
+    Config(ConfigArgs),
+
+    /// Reconcile a funder account's balance against the deploys that moved it
+    ///
+    /// Attributes every deploy signed by the funder, or transferring to it, to
+    /// its deployer, keeps an expected balance of start + credits - debits - phlo
+    /// and compares it with the node's balance each interval. Raises a drift
+    /// alarm when the two differ by more than the tolerance on consecutive
+    /// checks, and prints a per-deployer spend table on Ctrl+C.
+    FunderAudit(FunderAuditArgs),
+
     /// Interactive DAG visualization with real-time updates
     Dag(DagArgs),
 
+    /// Shard dashboard: node health, finality, recent blocks and alerts
+    ///
+    /// Checks every node in the topology, samples the followed node's last
+    /// finalized block and main chain, follows its added blocks, and raises
+    /// alerts for finalization stalls, reorgs and equivocations.
+    Dashboard(DashboardArgs),
+
     /// Get transfer information from a block's deploys
     BlockTransfers(BlockTransfersArgs),
+
+    /// Search the local deploy history
+    History(HistoryArgs),
+
+    /// Publish contracts in the on-chain registry
+    Registry(RegistryArgs),
+
+    /// Compare a block's pre/post state hashes across nodes
+    CompareState(CompareStateArgs),
+
+    /// Export a block height range as NDJSON for analytics ingestion
+    ///
+    /// Each block line has the fields block_hash, block_number, sender, seq_num,
+    /// timestamp, shard_id, parents, pre_state_hash, post_state_hash, deploy_count,
+    /// bonds_count and deploys. Each deploy has sig, deployer, term, timestamp,
+    /// phlo_price, phlo_limit, valid_after_block_number, cost, errored and
+    /// system_deploy_error.
+    ///
+    /// With --deploys separate, --output names a directory that receives
+    /// blocks.ndjson (block lines without deploys) and deploys.ndjson (one line
+    /// per deploy, with block_hash and block_number added as the foreign key).
+    ExportChain(ExportChainArgs),
+
+    /// Block-time and throughput statistics over a height range
+    ///
+    /// Reports inter-block time (average, median, p95), deploys per block and per
+    /// second, phlo consumed and each validator's share of blocks. Ranges that
+    /// span a fork are measured along the main chain only.
+    ChainStats(ChainStatsArgs),
+
+    /// Report validators that created more than one block with the same sequence number
+    ///
+    /// Scans a height range and lists each conflicting block with its height and
+    /// whether it is on the main chain; exits non-zero if any is found. With
+    /// --live, follows added blocks instead and reports a conflict as soon as the
+    /// second block arrives.
+    EquivocationCheck(EquivocationCheckArgs),
+
+    /// Show one validator's blocks in sequence-number order
+    ///
+    /// Lists the validator's blocks in a height range by seq_num with the
+    /// previous block each one cites in its justifications, and reports missing
+    /// sequence numbers, sequence numbers used by more than one block and the
+    /// average production interval.
+    ValidatorChain(ValidatorChainArgs),
+
+    /// Follow a block from creation to finality, timing each stage
+    ///
+    /// Prints what the node already knows about the block, then reports each
+    /// stage it reaches (created, added, finalized) as events arrive, with the
+    /// time since the stage before. Stops once the block reaches --until;
+    /// exits with code 4 if the block is orphaned.
+    FollowBlock(FollowBlockArgs),
+
+    /// Summarize locally recorded command timings (enable recording with FIREFLY_METRICS=1)
+    ///
+    /// Shows p50/p95 per command, node and phase over a time window. With
+    /// --compare, the window is compared with the one before it and phases whose
+    /// p50 or p95 grew by more than --threshold percent are flagged.
+    Stats(StatsArgs),
+
+    /// POST a sample receipt to the webhook, to check the receiver and the shared secret
+    WebhookTest(WebhookTestArgs),
 }
 
-#[derive(Parser, Debug)]
-pub struct DeployAndWaitArgs {
-    /// Rholang file to deploy
+/// Where the term comes from (shared by deploy-family commands and exploratory-deploy)
+#[derive(Args, Debug, Clone)]
+#[group(id = "source", required = true, multiple = false)]
+pub struct SourceArgs {
+    /// File holding the term; `-` reads it from stdin
     #[arg(short, long)]
-    pub file: String,
+    pub file: Option<PathBuf>,
 
-    /// Private key for deploy (defaults to well-known dev key)
-    #[arg(short = 'k', long = "private-key")]
-    pub private_key: Option<String>,
+    /// The term itself, instead of a file
+    #[arg(long)]
+    pub code: Option<String>,
+}
+
+impl SourceArgs {
+    /// The file, stdin or inline code the flags name
+    pub fn source(&self) -> crate::payload::Source {
+        crate::payload::Source::new(self.file.as_deref(), self.code.as_deref())
+    }
+}
+
+/// Payload language flags (shared by deploy-family commands)
+#[derive(Args, Debug, Clone)]
+pub struct LanguageArgs {
+    /// Language of the payload file (rholang, metta, wasm). Binary languages are
+    /// read as raw bytes or base64 text and sent base64-encoded.
+    #[arg(long, default_value = crate::payload::DEFAULT_LANGUAGE)]
+    pub language: String,
+
+    /// Accept a --language the CLI does not know about
+    #[arg(long)]
+    pub language_unchecked: bool,
+}
+
+impl LanguageArgs {
+    /// Validated, lower-cased language name
+    pub fn resolve(&self) -> crate::error::Result<String> {
+        crate::payload::validate_language(&self.language, self.language_unchecked)
+    }
+}
+
+/// Flags controlling the local deploy history log (shared by deploy-family commands)
+#[derive(Args, Debug, Clone, Default)]
+pub struct HistoryFlags {
+    /// Record this deploy in the local history file (default: FIREFLY_HISTORY env var)
+    #[arg(long, overrides_with = "no_history")]
+    pub history: bool,
+
+    /// Do not record this deploy in the local history file
+    #[arg(long = "no-history", overrides_with = "history")]
+    pub no_history: bool,
+}
+
+impl HistoryFlags {
+    /// Resolve the flags against the configured default
+    pub fn enabled(&self) -> bool {
+        if self.no_history {
+            false
+        } else {
+            self.history || crate::history::enabled_by_default()
+        }
+    }
+}
+
+/// Finalization cross-check on independent nodes (shared by transfer and deploy-and-wait)
+#[derive(Args, Debug, Clone, Default)]
+pub struct VerifyArgs {
+    /// After finalization, confirm the block on this node too (host:httpPort, repeatable)
+    #[arg(long = "verify-on", value_name = "HOST:HTTP_PORT")]
+    pub verify_on: Vec<VerifyTarget>,
 
-    /// Node hostname
-    #[arg(short = 'H', long = "host", default_value = "localhost")]
+    /// Fail when a --verify-on node cannot be reached instead of reporting "unverified"
+    #[arg(long, requires = "verify_on")]
+    pub require_verification: bool,
+}
+
+/// Validator node connection (shared by commands that deploy over gRPC)
+#[derive(Args, Debug, Clone, PartialEq, Eq)]
+pub struct NodeConnArgs {
+    /// Host address
+    #[arg(short = 'H', long, default_value = DEFAULT_HOST)]
     pub host: String,
 
-    /// gRPC port for deploy operations
-    #[arg(short = 'p', long = "port", default_value_t = 40412)]
-    pub port: u16,
+    /// gRPC port number for deploys and queries
+    #[arg(short = 'p', long = "grpc-port", visible_alias = "port", default_value_t = DEFAULT_GRPC_PORT, default_value_ifs = ROLE_GRPC_PORTS)]
+    pub grpc_port: u16,
+
+    /// HTTP port for status queries
+    #[arg(long = "http-port", default_value_t = DEFAULT_HTTP_PORT, default_value_ifs = ROLE_HTTP_PORTS)]
+    pub http_port: u16,
+
+    /// Standard shard node whose ports to use when none are given
+    #[arg(long = "node-role")]
+    pub node_role: Option<NodeRole>,
+}
+
+impl Default for NodeConnArgs {
+    fn default() -> Self {
+        Self {
+            host: DEFAULT_HOST.to_string(),
+            grpc_port: DEFAULT_GRPC_PORT,
+            http_port: DEFAULT_HTTP_PORT,
+            node_role: None,
+        }
+    }
+}
+
+/// Node connection for commands that only read, defaulting to the observer
+#[derive(Args, Debug, Clone, PartialEq, Eq)]
+pub struct ReadNodeConnArgs {
+    /// Host address
+    #[arg(short = 'H', long, default_value = DEFAULT_HOST)]
+    pub host: String,
+
+    /// gRPC port number for queries
+    #[arg(short = 'p', long = "grpc-port", visible_alias = "port", default_value_t = OBSERVER_GRPC_PORT, default_value_ifs = ROLE_GRPC_PORTS)]
+    pub grpc_port: u16,
 
     /// HTTP port for status queries
-    #[arg(long = "http-port", default_value_t = 40413)]
+    #[arg(long = "http-port", default_value_t = OBSERVER_HTTP_PORT, default_value_ifs = ROLE_HTTP_PORTS)]
     pub http_port: u16,
 
+    /// Standard shard node whose ports to use when none are given
+    #[arg(long = "node-role")]
+    pub node_role: Option<NodeRole>,
+}
+
+impl Default for ReadNodeConnArgs {
+    fn default() -> Self {
+        Self {
+            host: DEFAULT_HOST.to_string(),
+            grpc_port: OBSERVER_GRPC_PORT,
+            http_port: OBSERVER_HTTP_PORT,
+            node_role: None,
+        }
+    }
+}
+
+impl ReadNodeConnArgs {
+    /// The same connection as the shared node group
+    pub fn conn(&self) -> NodeConnArgs {
+        NodeConnArgs {
+            host: self.host.clone(),
+            grpc_port: self.grpc_port,
+            http_port: self.http_port,
+            node_role: self.node_role,
+        }
+    }
+}
+
+/// PoS contract verification for commands that move stake
+#[derive(Args, Debug, Clone)]
+pub struct PosVerifyArgs {
+    /// Check that rho:system:pos on the observer is the expected contract before
+    /// trusting its bond and balance answers: off, warn or refuse
+    #[arg(long = "verify-pos", default_value = "refuse")]
+    pub verify_pos: crate::staking::PosVerifyMode,
+
+    /// Topology file with the expected PoS contract per shard
+    /// (default: topology.json in the config directory)
+    #[arg(long = "topology")]
+    pub topology: Option<PathBuf>,
+}
+
+impl Default for PosVerifyArgs {
+    fn default() -> Self {
+        Self {
+            verify_pos: crate::staking::PosVerifyMode::Refuse,
+            topology: None,
+        }
+    }
+}
+
+/// Observer (read-only) node used for finalization, bond and balance checks
+#[derive(Args, Debug, Clone)]
+pub struct ObserverConnArgs {
+    /// Observer node host (falls back to main host if not specified)
+    #[arg(long = "observer-host")]
+    pub observer_host: Option<String>,
+
+    /// Observer node gRPC port (falls back to 40452 if not specified)
+    #[arg(long = "observer-port", alias = "readonly-port")]
+    pub observer_port: Option<u16>,
+
+    /// Observer node HTTP port for bond and balance checks
+    #[arg(long = "observer-http-port", default_value_t = OBSERVER_HTTP_PORT)]
+    pub observer_http_port: u16,
+}
+
+impl Default for ObserverConnArgs {
+    fn default() -> Self {
+        Self {
+            observer_host: None,
+            observer_port: None,
+            observer_http_port: OBSERVER_HTTP_PORT,
+        }
+    }
+}
+
+impl ObserverConnArgs {
+    /// Observer host, or the main node's host when none was given
+    pub fn host<'a>(&'a self, node: &'a NodeConnArgs) -> &'a str {
+        self.observer_host.as_deref().unwrap_or(&node.host)
+    }
+
+    /// Observer gRPC port, or the standard observer port when none was given
+    pub fn grpc_port(&self) -> u16 {
+        self.observer_port.unwrap_or(OBSERVER_GRPC_PORT)
+    }
+}
+
+/// Key used to sign deploys and gRPC requests
+#[derive(Args, Debug, Clone)]
+pub struct SigningArgs {
+    /// Private key in hex format (defaults to the well-known dev key)
+    #[arg(short = 'k', long = "private-key", default_value = DEV_PRIVATE_KEY)]
+    pub private_key: String,
+}
+
+impl Default for SigningArgs {
+    fn default() -> Self {
+        Self {
+            private_key: DEV_PRIVATE_KEY.to_string(),
+        }
+    }
+}
+
+/// Guard against signing with a well-known dev key for a remote node
+#[derive(Args, Debug, Clone, Default)]
+pub struct KeyGuardArgs {
+    /// Sign with a well-known dev key even though the node is not local
+    #[arg(long = "allow-insecure-key", default_value_t = false)]
+    pub allow_insecure_key: bool,
+}
+
+/// Limits a term's size and complexity are checked against before deploying
+#[derive(Args, Debug, Clone, Default)]
+pub struct TermLimitArgs {
+    /// Largest term in bytes (default: max_term_bytes in config.toml, else 4 MiB)
+    #[arg(long)]
+    pub max_term_bytes: Option<u64>,
+
+    /// Largest approximate node count (default: max_term_nodes in config.toml, else 100000)
+    #[arg(long)]
+    pub max_term_nodes: Option<u64>,
+}
+
+impl TermLimitArgs {
+    pub fn resolve(&self) -> crate::term_analysis::Limits {
+        crate::term_analysis::Limits::resolve(self.max_term_bytes, self.max_term_nodes)
+    }
+}
+
+/// Where to POST the receipt when a wait ends
+#[derive(Args, Debug, Clone, Default)]
+pub struct WebhookArgs {
+    /// POST the JSON receipt here when the wait ends, signed with webhook_secret
+    /// (default: webhook_url in config.toml)
+    #[arg(long, value_name = "URL")]
+    pub webhook_url: Option<String>,
+}
+
+impl WebhookArgs {
+    pub fn resolve(&self) -> Option<crate::webhook::Webhook> {
+        crate::webhook::Webhook::resolve(self.webhook_url.as_deref())
+    }
+}
+
+/// `--poll-initial` and `--poll-max`: how the inclusion and finalization polls back off
+///
+/// Polls start `--poll-initial` apart and the gap doubles up to `--poll-max`.
+/// The waits still end after `--max-wait` (or `--max-attempts` times the
+/// interval), with as many polls as fit.
+#[derive(Args, Debug, Clone, Default, PartialEq, Eq)]
+pub struct PollArgs {
+    /// First interval between polls (default: --check-interval; a bare number is seconds)
+    #[arg(long = "poll-initial", value_parser = parse_seconds)]
+    pub poll_initial: Option<Duration>,
+
+    /// Longest interval between polls (default: 4 times the first; a bare number is seconds)
+    #[arg(long = "poll-max", value_parser = parse_seconds)]
+    pub poll_max: Option<Duration>,
+}
+
+impl PollArgs {
+    /// Delays between polls for a command whose `--check-interval` is `check_interval`
+    pub fn backoff(&self, check_interval: Duration) -> crate::utils::Backoff {
+        crate::utils::Backoff::up_to(self.poll_initial.unwrap_or(check_interval), self.poll_max)
+    }
+}
+
+/// How long to wait for a deploy to be included and finalized
+#[derive(Args, Debug, Clone)]
+pub struct WaitBehaviorArgs {
+    /// Maximum wait for deploy finalization (e.g. 300s, 5m; a bare number is seconds)
+    #[arg(long = "max-wait", default_value = "300s", value_parser = parse_seconds)]
+    pub max_wait: Duration,
+
+    /// Interval between deploy status checks (e.g. 5s, 500ms; a bare number is seconds)
+    #[arg(long = "check-interval", default_value = "5s", value_parser = parse_seconds)]
+    pub check_interval: Duration,
+
+    #[command(flatten)]
+    pub poll: PollArgs,
+}
+
+impl Default for WaitBehaviorArgs {
+    fn default() -> Self {
+        Self {
+            max_wait: Duration::from_secs(300),
+            check_interval: Duration::from_secs(5),
+            poll: PollArgs::default(),
+        }
+    }
+}
+
+#[derive(Parser, Debug)]
+pub struct DeployAndWaitArgs {
+    #[command(flatten)]
+    pub source: SourceArgs,
+
+    #[command(flatten)]
+    pub signing: SigningArgs,
+
+    #[command(flatten)]
+    pub key_guard: KeyGuardArgs,
+
+    #[command(flatten)]
+    pub node: NodeConnArgs,
+
     /// Use bigger phlo limit (100,000,000 instead of 50,000)
     #[arg(long = "bigger-phlo")]
     pub bigger_phlo: bool,
@@ -146,35 +711,78 @@ pub struct DeployAndWaitArgs {
     #[arg(long, default_value_t = false)]
     pub propose: bool,
 
-    /// Maximum seconds to wait for deploy inclusion in a block
-    #[arg(long = "max-wait", default_value_t = 60)]
-    pub max_wait: u64,
+    /// Maximum wait for deploy inclusion in a block (e.g. 60s, 2m; a bare number is seconds)
+    #[arg(long = "max-wait", default_value = "60s", value_parser = parse_seconds)]
+    pub max_wait: Duration,
 
-    /// Maximum seconds to wait for block finalization
-    #[arg(long = "finalization-timeout", default_value_t = 30)]
-    pub finalization_timeout: u64,
+    /// Maximum wait for block finalization (a bare number is seconds)
+    #[arg(long = "finalization-timeout", default_value = "30s", value_parser = parse_seconds)]
+    pub finalization_timeout: Duration,
 
-    /// Check interval in seconds
-    #[arg(long = "check-interval", default_value_t = 2)]
-    pub check_interval: u64,
+    /// After finalization, wait until this many more blocks are finalized on top
+    #[arg(long = "confirm-depth")]
+    pub confirm_depth: Option<u32>,
 
-    /// Observer node host for finalization checks (falls back to main host if not specified)
-    #[arg(long = "observer-host")]
-    pub observer_host: Option<String>,
+    /// Stop once the deploy is in a block and print the block hash, without
+    /// waiting for finalization or reading the deploy's result
+    #[arg(long, conflicts_with_all = ["finalization_timeout", "confirm_depth", "verify_on"])]
+    pub no_wait_finalization: bool,
 
-    /// Observer node gRPC port for finalization checks (falls back to 40452 if not specified)
-    #[arg(long = "observer-port")]
-    pub observer_port: Option<u16>,
+    /// Interval between status checks (a bare number is seconds)
+    #[arg(long = "check-interval", default_value = "2s", value_parser = parse_seconds)]
+    pub check_interval: Duration,
+
+    #[command(flatten)]
+    pub poll: PollArgs,
+
+    #[command(flatten)]
+    pub observer: ObserverConnArgs,
 
     /// Expiration timestamp in milliseconds (Unix epoch). Deploy becomes invalid after this time.
     /// Use 0 or omit for no expiration.
     #[arg(long)]
     pub expiration: Option<i64>,
 
-    /// Expiration duration in seconds from now. Deploy becomes invalid after this duration.
-    /// Mutually exclusive with --expiration.
-    #[arg(long, conflicts_with = "expiration")]
-    pub expires_in: Option<u64>,
+    /// Expiration as a duration from now (e.g. 90s, 10m; a bare number is seconds).
+    /// Deploy becomes invalid after this duration. Mutually exclusive with --expiration.
+    #[arg(long, conflicts_with = "expiration", value_parser = parse_seconds)]
+    pub expires_in: Option<Duration>,
+
+    /// Deploy lifespan in blocks, for nodes that do not report one in /api/status.
+    /// Waiting stops once the tip passes the deploy's valid-after block plus this.
+    #[arg(long)]
+    pub deploy_lifespan: Option<i64>,
+
+    /// Print the node's raw message when it rejects the deploy, and the term diagnostics
+    #[arg(short, long)]
+    pub verbose: bool,
+
+    #[command(flatten)]
+    pub term_limits: TermLimitArgs,
+
+    /// Deploy even when the term is over a size or complexity limit
+    #[arg(long)]
+    pub force: bool,
+
+    /// Skip the gRPC and HTTP connectivity check before deploying
+    #[arg(long)]
+    pub skip_preflight: bool,
+
+    /// Ring the bell and run the configured notify_command when the wait ends
+    #[arg(long)]
+    pub notify: bool,
+
+    #[command(flatten)]
+    pub webhook: WebhookArgs,
+
+    #[command(flatten)]
+    pub verify: VerifyArgs,
+
+    #[command(flatten)]
+    pub language: LanguageArgs,
+
+    #[command(flatten)]
+    pub history: HistoryFlags,
 }
 
 #[derive(Parser, Debug)]
@@ -187,21 +795,38 @@ pub struct GetDataArgs {
     #[arg(short = 'b', long = "block-hash")]
     pub block_hash: String,
 
-    /// Private key (defaults to well-known dev key)
-    #[arg(
-        short = 'k',
-        long = "private-key",
-        default_value = "5f668a7ee96d944a4494cc947e4005e172d7ab3461ee5538f1f2a45a835e9657"
-    )]
-    pub private_key: String,
+    #[command(flatten)]
+    pub signing: SigningArgs,
 
-    /// Node hostname
-    #[arg(short = 'H', long = "host", default_value = "localhost")]
-    pub host: String,
+    #[command(flatten)]
+    pub node: ReadNodeConnArgs,
+}
 
-    /// gRPC port
-    #[arg(short = 'p', long = "port", default_value_t = 40412)]
-    pub port: u16,
+/// Arguments for data-at-name command
+#[derive(Parser, Debug)]
+pub struct DataAtNameArgs {
+    /// Name to read: the string of a public name (`results` for `@"results"`),
+    /// or with --unforgeable an unforgeable name's hex id
+    #[arg(short = 'n', long)]
+    pub name: String,
+
+    /// Read --name as the hex id of an unforgeable name
+    #[arg(long)]
+    pub unforgeable: bool,
+
+    /// Main-chain blocks to search back from the tip
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    pub depth: u32,
+
+    /// Read at this block instead of searching the main chain
+    #[arg(short = 'b', long = "block-hash", conflicts_with = "depth")]
+    pub block_hash: Option<String>,
+
+    #[command(flatten)]
+    pub node: ReadNodeConnArgs,
+
+    #[command(flatten)]
+    pub query_key: QueryKeyArgs,
 }
 
 #[derive(Parser, Debug)]
@@ -210,13 +835,8 @@ pub struct GetDeployArgs {
     #[arg(short = 'd', long = "deploy-id")]
     pub deploy_id: String,
 
-    /// Node hostname
-    #[arg(short = 'H', long = "host", default_value = "localhost")]
-    pub host: String,
-
-    /// HTTP port for API queries
-    #[arg(long = "http-port", default_value_t = 40413)]
-    pub http_port: u16,
+    #[command(flatten)]
+    pub node: ReadNodeConnArgs,
 
     /// Output format (json, pretty, summary)
     #[arg(short = 'f', long = "format", default_value = "pretty")]
@@ -225,29 +845,34 @@ pub struct GetDeployArgs {
     /// Show full deploy details
     #[arg(long = "verbose")]
     pub verbose: bool,
+
+    /// Heights searched when the deploy ID is a prefix
+    #[arg(long, default_value_t = crate::prefix::DEFAULT_SEARCH_DEPTH)]
+    pub search_depth: i64,
+
+    /// On a failed lookup, skip the hint about what the identifier looks like and the
+    /// extra request checking it
+    #[arg(long)]
+    pub no_hints: bool,
+
+    #[command(flatten)]
+    pub query_key: QueryKeyArgs,
 }
 
 /// Arguments for deploy and full-deploy commands
 #[derive(Parser)]
 pub struct DeployArgs {
-    /// Path to the Rholang file to deploy
-    #[arg(short, long)]
-    pub file: PathBuf,
+    #[command(flatten)]
+    pub source: SourceArgs,
 
-    /// Private key in hex format
-    #[arg(
-        long,
-        default_value = "5f668a7ee96d944a4494cc947e4005e172d7ab3461ee5538f1f2a45a835e9657"
-    )]
-    pub private_key: String,
+    #[command(flatten)]
+    pub signing: SigningArgs,
 
-    /// Host address
-    #[arg(short = 'H', long, default_value = "localhost")]
-    pub host: String,
+    #[command(flatten)]
+    pub key_guard: KeyGuardArgs,
 
-    /// gRPC port number
-    #[arg(short, long, default_value_t = 40412)]
-    pub port: u16,
+    #[command(flatten)]
+    pub node: NodeConnArgs,
 
     /// Use bigger phlo limit
     #[arg(short, long, default_value_t = false)]
@@ -258,29 +883,121 @@ pub struct DeployArgs {
     #[arg(long)]
     pub expiration: Option<i64>,
 
-    /// Expiration duration in seconds from now. Deploy becomes invalid after this duration.
-    /// Mutually exclusive with --expiration.
-    #[arg(long, conflicts_with = "expiration")]
-    pub expires_in: Option<u64>,
+    /// Expiration as a duration from now (e.g. 90s, 10m; a bare number is seconds).
+    /// Deploy becomes invalid after this duration. Mutually exclusive with --expiration.
+    #[arg(long, conflicts_with = "expiration", value_parser = parse_seconds)]
+    pub expires_in: Option<Duration>,
+
+    /// Print the node's raw message when it rejects the deploy, and the term diagnostics
+    #[arg(short, long)]
+    pub verbose: bool,
+
+    #[command(flatten)]
+    pub term_limits: TermLimitArgs,
+
+    /// Deploy even when the term is over a size or complexity limit
+    #[arg(long)]
+    pub force: bool,
+
+    #[command(flatten)]
+    pub language: LanguageArgs,
+
+    #[command(flatten)]
+    pub history: HistoryFlags,
+}
+
+/// Arguments for wait-for-deploy command
+#[derive(Parser)]
+pub struct WaitForDeployArgs {
+    /// Deploy ID (signature) of a deploy already sent to the network
+    #[arg(value_name = "DEPLOY_ID")]
+    pub deploy_id: String,
+
+    #[command(flatten)]
+    pub signing: SigningArgs,
+
+    #[command(flatten)]
+    pub node: NodeConnArgs,
+
+    #[command(flatten)]
+    pub observer: ObserverConnArgs,
+
+    /// Polls for block inclusion; the finalization wait gets as long
+    #[arg(short, long, default_value_t = 60)]
+    pub max_attempts: u32,
+
+    /// Interval between polls (a bare number is seconds)
+    #[arg(long = "check-interval", default_value = "5s", value_parser = parse_seconds)]
+    pub check_interval: Duration,
+
+    #[command(flatten)]
+    pub poll: PollArgs,
+}
+
+impl WaitForDeployArgs {
+    /// Longest the inclusion wait, and then the finalization wait, may take
+    pub fn max_wait(&self) -> Duration {
+        self.check_interval.max(crate::utils::MIN_POLL_INTERVAL) * self.max_attempts
+    }
+}
+
+/// Arguments for bump-deploy command
+#[derive(Parser)]
+pub struct BumpDeployArgs {
+    /// Deploy ID of the stuck deploy
+    #[arg(short = 'd', long = "deploy-id")]
+    pub deploy_id: String,
+
+    /// Phlo price for the replacement deploy (deploys are normally signed at 1)
+    #[arg(long, value_parser = clap::value_parser!(i64).range(2..))]
+    pub phlo_price: i64,
+
+    /// File holding the original term, when it cannot be recovered from the local history
+    #[arg(short, long)]
+    pub file: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub signing: SigningArgs,
+
+    #[command(flatten)]
+    pub key_guard: KeyGuardArgs,
+
+    #[command(flatten)]
+    pub node: NodeConnArgs,
+
+    /// Use bigger phlo limit
+    #[arg(short, long, default_value_t = false)]
+    pub bigger_phlo: bool,
+
+    /// Print the node's raw message when it rejects the deploy
+    #[arg(short, long)]
+    pub verbose: bool,
+
+    #[command(flatten)]
+    pub language: LanguageArgs,
+
+    #[command(flatten)]
+    pub history: HistoryFlags,
 }
 
 /// Arguments for propose command
 #[derive(Parser)]
 pub struct ProposeArgs {
-    /// Private key in hex format
-    #[arg(
-        long,
-        default_value = "5f668a7ee96d944a4494cc947e4005e172d7ab3461ee5538f1f2a45a835e9657"
-    )]
-    pub private_key: String,
+    #[command(flatten)]
+    pub signing: SigningArgs,
 
-    /// Host address
-    #[arg(short = 'H', long, default_value = "localhost")]
-    pub host: String,
+    #[command(flatten)]
+    pub node: NodeConnArgs,
 
-    /// gRPC port number
-    #[arg(short, long, default_value_t = 40412)]
-    pub port: u16,
+    /// When the node refuses because the key is not an active validator, look
+    /// for a local key that is one
+    #[arg(long)]
+    pub suggest: bool,
+
+    /// Directory of private key files checked by --suggest
+    /// (default: keys in the config directory)
+    #[arg(long, requires = "suggest")]
+    pub keystore: Option<PathBuf>,
 }
 
 /// Arguments for is-finalized command
@@ -290,51 +1007,45 @@ pub struct IsFinalizedArgs {
     #[arg(short, long)]
     pub block_hash: String,
 
-    /// Private key in hex format
-    #[arg(
-        long,
-        default_value = "5f668a7ee96d944a4494cc947e4005e172d7ab3461ee5538f1f2a45a835e9657"
-    )]
-    pub private_key: String,
-
-    /// Host address
-    #[arg(short = 'H', long, default_value = "localhost")]
-    pub host: String,
+    #[command(flatten)]
+    pub signing: SigningArgs,
 
-    /// gRPC port number
-    #[arg(short, long, default_value_t = 40412)]
-    pub port: u16,
+    #[command(flatten)]
+    pub node: ReadNodeConnArgs,
 
     /// Maximum number of retry attempts
     #[arg(short, long, default_value_t = 12)]
     pub max_attempts: u32,
 
-    /// Delay between retries in seconds
-    #[arg(short, long, default_value_t = 5)]
-    pub retry_delay: u64,
+    /// Delay between retries (a bare number is seconds)
+    #[arg(short, long, default_value = "5s", value_parser = parse_seconds)]
+    pub retry_delay: Duration,
+
+    /// After finalization, wait until this many more blocks are finalized on top
+    #[arg(long = "confirm-depth")]
+    pub confirm_depth: Option<u32>,
+
+    /// Heights searched when the block hash is a prefix
+    #[arg(long, default_value_t = crate::prefix::DEFAULT_SEARCH_DEPTH)]
+    pub search_depth: i64,
+
+    /// On a failed lookup, skip the hint about what the identifier looks like and the
+    /// extra request checking it
+    #[arg(long)]
+    pub no_hints: bool,
 }
 
 /// Arguments for exploratory-deploy command
 #[derive(Parser)]
 pub struct ExploratoryDeployArgs {
-    /// Path to the Rholang file to execute
-    #[arg(short, long)]
-    pub file: PathBuf,
-
-    /// Private key in hex format
-    #[arg(
-        long,
-        default_value = "5f668a7ee96d944a4494cc947e4005e172d7ab3461ee5538f1f2a45a835e9657"
-    )]
-    pub private_key: String,
+    #[command(flatten)]
+    pub source: SourceArgs,
 
-    /// Host address
-    #[arg(short = 'H', long, default_value = "localhost")]
-    pub host: String,
+    #[command(flatten)]
+    pub signing: SigningArgs,
 
-    /// gRPC port number
-    #[arg(short, long, default_value_t = 40412)]
-    pub port: u16,
+    #[command(flatten)]
+    pub node: ReadNodeConnArgs,
 
     /// Block hash to use as reference (optional)
     #[arg(short, long)]
@@ -352,7 +1063,7 @@ pub struct GeneratePublicKeyArgs {
     #[arg(
         short,
         long,
-        default_value = "5f668a7ee96d944a4494cc947e4005e172d7ab3461ee5538f1f2a45a835e9657"
+        default_value = DEV_PRIVATE_KEY
     )]
     pub private_key: String,
 
@@ -375,6 +1086,10 @@ pub struct GenerateKeyPairArgs {
     /// Output directory for saved keys (default: current directory)
     #[arg(short, long, default_value = ".")]
     pub output_dir: String,
+
+    /// Also draw the vault address as a QR code in the terminal
+    #[arg(long, default_value_t = false)]
+    pub qr: bool,
 }
 
 /// Arguments for generate-vault-address command
@@ -387,34 +1102,169 @@ pub struct GenerateVaultAddressArgs {
     /// Private key in hex format (will derive public key from this)
     #[arg(
         long,
-        default_value = "5f668a7ee96d944a4494cc947e4005e172d7ab3461ee5538f1f2a45a835e9657",
+        default_value = DEV_PRIVATE_KEY,
         conflicts_with = "public_key"
     )]
     pub private_key: Option<String>,
+
+    /// Also draw the vault address as a QR code in the terminal
+    #[arg(long, default_value_t = false)]
+    pub qr: bool,
 }
 
-/// Arguments for HTTP-based commands (status, bonds, metrics)
-#[derive(Parser)]
-pub struct HttpArgs {
-    /// Host address
-    #[arg(short = 'H', long, default_value = "localhost")]
-    pub host: String,
+/// Validator identity mapping used to show names next to public keys
+#[derive(Args, Debug, Clone, Default)]
+pub struct IdentityArgs {
+    /// CSV or TOML file mapping validator keys to names (default: FIREFLY_IDENTITIES env var or config dir)
+    #[arg(long)]
+    pub identities: Option<PathBuf>,
+}
 
-    /// HTTP port number (not gRPC port)
-    #[arg(short, long, default_value_t = 40453)]
-    pub port: u16,
+impl IdentityArgs {
+    /// Load the mapping from the flag or the configured default
+    pub fn load(&self) -> crate::error::Result<crate::identities::Identities> {
+        crate::identities::Identities::load_or_default(self.identities.as_deref())
+    }
 }
 
-/// Arguments for blocks command
-#[derive(Parser)]
-pub struct BlocksArgs {
-    /// Host address
-    #[arg(short = 'H', long, default_value = "localhost")]
-    pub host: String,
+/// Recording and replaying the raw WebSocket event stream
+#[derive(Args, Debug, Clone, Default)]
+pub struct EventDumpArgs {
+    /// Append every raw event frame, with the time it arrived, to this NDJSON file
+    #[arg(long, value_name = "FILE")]
+    pub dump_events: Option<PathBuf>,
 
-    /// HTTP port number (not gRPC port)
-    #[arg(short, long, default_value_t = 40413)]
-    pub port: u16,
+    /// Replay frames from a --dump-events file instead of connecting to the node
+    #[arg(long, value_name = "FILE", conflicts_with = "dump_events")]
+    pub from_dump: Option<PathBuf>,
+
+    /// Replay pace relative to the recording (2 = twice as fast, 0 = no pauses)
+    #[arg(long, default_value_t = 1.0, requires = "from_dump", value_parser = parse_replay_speed)]
+    pub replay_speed: f64,
+}
+
+fn parse_replay_speed(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(speed) if speed.is_finite() && speed >= 0.0 => Ok(speed),
+        _ => Err(format!("'{}' is not a replay speed (0 or more)", value)),
+    }
+}
+
+/// Reference block selection for PoS queries pinned to a single block
+#[derive(Args, Debug, Clone, Default)]
+pub struct ReferenceArgs {
+    /// Block the PoS queries are evaluated against: tip or lfb (last finalized block)
+    #[arg(long, default_value = "tip")]
+    pub reference: crate::pinned::ReferenceBlock,
+
+    /// Times to re-resolve the reference block when it disappears mid-query
+    #[arg(long, default_value_t = crate::pinned::DEFAULT_MAX_REFRESHES)]
+    pub reference_retries: u32,
+
+    /// Run each PoS query as its own exploratory deploy instead of one batched term
+    #[arg(long)]
+    pub no_batch: bool,
+}
+
+impl ReferenceArgs {
+    /// Pinned query client for `api`, resolving the LFB over `http_port`
+    pub fn client<'a>(
+        &self,
+        api: &'a crate::f1r3fly_api::F1r3flyApi<'a>,
+        http_port: u16,
+    ) -> crate::pinned::PinnedQueryClient<'a> {
+        crate::pinned::PinnedQueryClient::new(api, http_port, self.reference)
+            .with_max_refreshes(self.reference_retries)
+            .with_batching(!self.no_batch)
+    }
+}
+
+/// Arguments for HTTP-based commands (status, bonds, metrics)
+#[derive(Parser)]
+pub struct HttpArgs {
+    /// Host address
+    #[arg(short = 'H', long, default_value = DEFAULT_HOST)]
+    pub host: String,
+
+    /// HTTP port number (not gRPC port)
+    #[arg(short, long, default_value_t = OBSERVER_HTTP_PORT, default_value_ifs = ROLE_HTTP_PORTS)]
+    pub port: u16,
+
+    /// Standard shard node whose ports to use when none are given
+    #[arg(long = "node-role")]
+    pub node_role: Option<NodeRole>,
+
+    #[command(flatten)]
+    pub identity: IdentityArgs,
+
+    #[command(flatten)]
+    pub output: OutputArgs,
+}
+
+/// `--output` for the commands that can print JSON
+#[derive(Parser, Debug, Clone)]
+pub struct OutputArgs {
+    /// Output format: text, or json for JSON on stdout and nothing else
+    /// (errors are written to stderr as JSON)
+    #[arg(long, default_value = "text", value_parser = ["text", "json"])]
+    pub output: String,
+
+    /// Same as --output json, kept for scripts written against the older flag
+    #[arg(long, hide = true, conflicts_with = "output")]
+    pub json: bool,
+}
+
+impl OutputArgs {
+    pub fn is_json(&self) -> bool {
+        self.json || self.output == "json"
+    }
+}
+
+/// `--private-key` for query commands that read over gRPC
+///
+/// Reads are never signed, so any key will do; without one a throwaway key is
+/// generated rather than the dev key being used.
+#[derive(Parser, Debug, Clone, Default)]
+pub struct QueryKeyArgs {
+    /// Private key to open the query client with (hex); a throwaway key when not given
+    #[arg(long = "private-key")]
+    pub private_key: Option<String>,
+}
+
+/// Arguments for bonds and active-validators
+#[derive(Parser)]
+pub struct ValidatorListArgs {
+    #[command(flatten)]
+    pub http: HttpArgs,
+
+    /// Show the vault address of each validator's key
+    #[arg(long)]
+    pub show_addresses: bool,
+
+    /// Also show each validator's vault balance, read from the same node (implies
+    /// --show-addresses)
+    #[arg(long)]
+    pub with_balances: bool,
+
+    /// Balance queries in flight at once, with --with-balances
+    #[arg(long, default_value_t = crate::staking::DEFAULT_BALANCE_CONCURRENCY, value_parser = clap::value_parser!(usize).range(1..))]
+    pub concurrency: usize,
+}
+
+/// Arguments for blocks command
+#[derive(Parser)]
+pub struct BlocksArgs {
+    /// Host address
+    #[arg(short = 'H', long, default_value = DEFAULT_HOST)]
+    pub host: String,
+
+    /// HTTP port number (not gRPC port)
+    #[arg(short, long, default_value_t = OBSERVER_HTTP_PORT, default_value_ifs = ROLE_HTTP_PORTS)]
+    pub port: u16,
+
+    /// Standard shard node whose ports to use when none are given
+    #[arg(long = "node-role")]
+    pub node_role: Option<NodeRole>,
 
     /// Number of recent blocks to fetch (default: 5)
     #[arg(short, long, default_value_t = 5)]
@@ -423,41 +1273,47 @@ pub struct BlocksArgs {
     /// Specific block hash to fetch (optional)
     #[arg(short, long)]
     pub block_hash: Option<String>,
+
+    /// On a failed lookup, skip the hint about what the identifier looks like and the
+    /// extra request checking it
+    #[arg(long)]
+    pub no_hints: bool,
+
+    /// Output format for recent blocks: json, or table with date separators
+    #[arg(short, long, default_value = "json", value_parser = ["json", "table"])]
+    pub format: String,
+
+    /// Start a new table group at each day or hour boundary
+    #[arg(long, default_value = "day")]
+    pub granularity: crate::block_groups::Granularity,
+
+    #[command(flatten)]
+    pub query_key: QueryKeyArgs,
 }
 
 /// Arguments for show-main-chain command
 #[derive(Parser)]
 pub struct ShowMainChainArgs {
-    /// Host address
-    #[arg(short = 'H', long, default_value = "localhost")]
-    pub host: String,
-
-    /// gRPC port number
-    #[arg(short, long, default_value_t = 40412)]
-    pub port: u16,
+    #[command(flatten)]
+    pub node: ReadNodeConnArgs,
 
     /// Number of blocks to fetch from main chain (default: 10)
     #[arg(short, long, default_value_t = 10)]
     pub depth: u32,
 
-    /// Private key in hex format (required for gRPC)
-    #[arg(
-        long,
-        default_value = "5f668a7ee96d944a4494cc947e4005e172d7ab3461ee5538f1f2a45a835e9657"
-    )]
-    pub private_key: String,
+    /// Start a new group at each day or hour boundary
+    #[arg(long, default_value = "day")]
+    pub granularity: crate::block_groups::Granularity,
+
+    #[command(flatten)]
+    pub signing: SigningArgs,
 }
 
 /// Arguments for get-blocks-by-height command
 #[derive(Parser)]
 pub struct GetBlocksByHeightArgs {
-    /// Host address
-    #[arg(short = 'H', long, default_value = "localhost")]
-    pub host: String,
-
-    /// gRPC port number
-    #[arg(short, long, default_value_t = 40412)]
-    pub port: u16,
+    #[command(flatten)]
+    pub node: ReadNodeConnArgs,
 
     /// Start block number (inclusive)
     #[arg(short, long)]
@@ -467,60 +1323,97 @@ pub struct GetBlocksByHeightArgs {
     #[arg(short, long)]
     pub end_block_number: i64,
 
-    /// Private key in hex format (required for gRPC)
-    #[arg(
-        long,
-        default_value = "5f668a7ee96d944a4494cc947e4005e172d7ab3461ee5538f1f2a45a835e9657"
-    )]
-    pub private_key: String,
+    #[command(flatten)]
+    pub signing: SigningArgs,
 }
 
 /// Arguments for wallet-balance command
 #[derive(Parser)]
 pub struct WalletBalanceArgs {
     /// Host address
-    #[arg(short = 'H', long, default_value = "localhost")]
+    #[arg(short = 'H', long, default_value = DEFAULT_HOST)]
     pub host: String,
 
     /// gRPC port number (requires read-only node)
-    #[arg(short, long, default_value_t = 40452)]
+    #[arg(short, long, default_value_t = OBSERVER_GRPC_PORT, default_value_ifs = ROLE_GRPC_PORTS)]
     pub port: u16,
 
-    /// Wallet address to check balance for
-    #[arg(short = 'a', long)]
-    pub address: String,
+    /// Standard shard node whose ports to use when none are given
+    #[arg(long = "node-role")]
+    pub node_role: Option<NodeRole>,
+
+    /// Wallet address to check balance for (repeatable, comma-separated)
+    #[arg(
+        short = 'a',
+        long = "address",
+        value_delimiter = ',',
+        required_unless_present = "addresses_file"
+    )]
+    pub addresses: Vec<String>,
+
+    /// File of wallet addresses, one per line; blank lines and `#` comments are skipped
+    #[arg(long)]
+    pub addresses_file: Option<PathBuf>,
+
+    /// Balance queries in flight at once when several addresses are given
+    #[arg(long, default_value_t = crate::staking::DEFAULT_BALANCE_CONCURRENCY, value_parser = clap::value_parser!(usize).range(1..))]
+    pub concurrency: usize,
+
+    /// Serve the balance from the local cache while it is still valid (single address only)
+    #[arg(long, default_value_t = false)]
+    pub cache: bool,
+
+    /// Cache entries younger than this are served without checking the chain tip (e.g. 30s, 5m)
+    #[arg(long, default_value = "30s", value_parser = parse_seconds)]
+    pub max_age: Duration,
+
+    /// Ignore any cached value and query the node (the cache is still updated)
+    #[arg(long, default_value_t = false)]
+    pub refresh: bool,
+
+    /// Also draw the address as a QR code in the terminal (single address only)
+    #[arg(long, default_value_t = false)]
+    pub qr: bool,
+
+    #[command(flatten)]
+    pub query_key: QueryKeyArgs,
+
+    #[command(flatten)]
+    pub output: OutputArgs,
 }
 
 /// Arguments for bond-status command
 #[derive(Parser)]
 pub struct BondStatusArgs {
     /// Host address
-    #[arg(short = 'H', long, default_value = "localhost")]
+    #[arg(short = 'H', long, default_value = DEFAULT_HOST)]
     pub host: String,
 
     /// HTTP port number (same as other inspection commands)
-    #[arg(short, long, default_value_t = 40413)]
+    #[arg(short, long, default_value_t = OBSERVER_HTTP_PORT, default_value_ifs = ROLE_HTTP_PORTS)]
     pub port: u16,
 
+    /// Standard shard node whose ports to use when none are given
+    #[arg(long = "node-role")]
+    pub node_role: Option<NodeRole>,
+
     /// Public key to check bond status for
     #[arg(short = 'k', long)]
     pub public_key: String,
+
+    /// Also print the full bonds response
+    #[arg(long, conflicts_with_all = ["json", "output"])]
+    pub raw: bool,
+
+    #[command(flatten)]
+    pub output: OutputArgs,
 }
 
 /// Arguments for bond-validator command
 #[derive(Parser)]
 pub struct BondValidatorArgs {
-    /// Host address
-    #[arg(short = 'H', long, default_value = "localhost")]
-    pub host: String,
-
-    /// gRPC port number for deploy
-    #[arg(short, long, default_value_t = 40412)]
-    pub port: u16,
-
-    /// HTTP port for status queries
-    #[arg(long = "http-port", default_value_t = 40413)]
-    pub http_port: u16,
+    #[command(flatten)]
+    pub node: NodeConnArgs,
 
     /// Stake amount for the validator (required)
     #[arg(short, long)]
@@ -530,41 +1423,163 @@ pub struct BondValidatorArgs {
     #[arg(long)]
     pub private_key: String,
 
+    #[command(flatten)]
+    pub key_guard: KeyGuardArgs,
+
     /// Also propose a block after bonding
     #[arg(long, default_value_t = false, action = ArgAction::Set, value_parser = clap::value_parser!(bool))]
     pub propose: bool,
 
-    /// Maximum wait time in seconds for deploy finalization
-    #[arg(long = "max-wait", default_value_t = 300)]
-    pub max_wait: u64,
+    #[command(flatten)]
+    pub wait: WaitBehaviorArgs,
 
-    /// Check interval in seconds for deploy status
-    #[arg(long = "check-interval", default_value_t = 5)]
-    pub check_interval: u64,
+    /// Maximum wait for block finalization (e.g. 10m; a bare number is seconds).
+    /// Defaults to --max-wait.
+    #[arg(long = "finalization-timeout", value_parser = parse_seconds)]
+    pub finalization_timeout: Option<Duration>,
 
-    /// Observer node host for finalization checks (falls back to main host if not specified)
-    #[arg(long = "observer-host")]
-    pub observer_host: Option<String>,
+    /// Stop once the bond deploy is in a block and print the block hash,
+    /// without waiting for finalization
+    #[arg(long, conflicts_with = "finalization_timeout")]
+    pub no_wait_finalization: bool,
 
-    /// Observer node gRPC port for finalization checks (falls back to 40452 if not specified)
-    #[arg(long = "observer-port")]
-    pub observer_port: Option<u16>,
+    #[command(flatten)]
+    pub observer: ObserverConnArgs,
+
+    #[command(flatten)]
+    pub pos: PosVerifyArgs,
+
+    /// Expiration timestamp in milliseconds (Unix epoch). Deploy becomes invalid after this time.
+    /// Use 0 or omit for no expiration.
+    #[arg(long)]
+    pub expiration: Option<i64>,
+
+    /// Expiration as a duration from now (e.g. 90s, 10m; a bare number is seconds).
+    /// Deploy becomes invalid after this duration. Mutually exclusive with --expiration.
+    #[arg(long, conflicts_with = "expiration", value_parser = parse_seconds)]
+    pub expires_in: Option<Duration>,
+
+    /// Print the node's raw message when it rejects the deploy
+    #[arg(short, long)]
+    pub verbose: bool,
+
+    /// Skip the gRPC and HTTP connectivity check before deploying
+    #[arg(long)]
+    pub skip_preflight: bool,
+
+    #[command(flatten)]
+    pub history: HistoryFlags,
+}
+
+/// Arguments for add-stake command
+#[derive(Parser)]
+pub struct AddStakeArgs {
+    #[command(flatten)]
+    pub node: NodeConnArgs,
+
+    /// Amount to add to the validator's current stake (required)
+    #[arg(short, long)]
+    pub amount: u64,
+
+    /// Private key of the bonded validator signing the deploy (hex format)
+    #[arg(long)]
+    pub private_key: String,
+
+    #[command(flatten)]
+    pub key_guard: KeyGuardArgs,
+
+    /// Also propose a block after the deploy
+    #[arg(long, default_value_t = false, action = ArgAction::Set, value_parser = clap::value_parser!(bool))]
+    pub propose: bool,
+
+    #[command(flatten)]
+    pub wait: WaitBehaviorArgs,
+
+    #[command(flatten)]
+    pub observer: ObserverConnArgs,
+
+    #[command(flatten)]
+    pub pos: PosVerifyArgs,
+
+    /// Expiration timestamp in milliseconds (Unix epoch). Deploy becomes invalid after this time.
+    /// Use 0 or omit for no expiration.
+    #[arg(long)]
+    pub expiration: Option<i64>,
+
+    /// Expiration as a duration from now (e.g. 90s, 10m; a bare number is seconds).
+    /// Deploy becomes invalid after this duration. Mutually exclusive with --expiration.
+    #[arg(long, conflicts_with = "expiration", value_parser = parse_seconds)]
+    pub expires_in: Option<Duration>,
+
+    /// Print the node's raw message when it rejects the deploy
+    #[arg(short, long)]
+    pub verbose: bool,
+
+    /// Skip the gRPC and HTTP connectivity check before deploying
+    #[arg(long)]
+    pub skip_preflight: bool,
+
+    #[command(flatten)]
+    pub history: HistoryFlags,
+}
+
+/// Arguments for unbond-validator command
+#[derive(Parser)]
+pub struct UnbondValidatorArgs {
+    #[command(flatten)]
+    pub node: NodeConnArgs,
+
+    /// Private key of the bonded validator signing the deploy (hex format) - determines which validator is unbonded
+    #[arg(long)]
+    pub private_key: String,
+
+    #[command(flatten)]
+    pub key_guard: KeyGuardArgs,
+
+    /// Also propose a block after the deploy
+    #[arg(long, default_value_t = false, action = ArgAction::Set, value_parser = clap::value_parser!(bool))]
+    pub propose: bool,
+
+    #[command(flatten)]
+    pub wait: WaitBehaviorArgs,
+
+    /// Maximum wait for block finalization (e.g. 10m; a bare number is seconds).
+    /// Defaults to --max-wait.
+    #[arg(long = "finalization-timeout", value_parser = parse_seconds)]
+    pub finalization_timeout: Option<Duration>,
+
+    #[command(flatten)]
+    pub observer: ObserverConnArgs,
+
+    #[command(flatten)]
+    pub pos: PosVerifyArgs,
 
     /// Expiration timestamp in milliseconds (Unix epoch). Deploy becomes invalid after this time.
     /// Use 0 or omit for no expiration.
     #[arg(long)]
     pub expiration: Option<i64>,
 
-    /// Expiration duration in seconds from now. Deploy becomes invalid after this duration.
-    /// Mutually exclusive with --expiration.
-    #[arg(long, conflicts_with = "expiration")]
-    pub expires_in: Option<u64>,
+    /// Expiration as a duration from now (e.g. 90s, 10m; a bare number is seconds).
+    /// Deploy becomes invalid after this duration. Mutually exclusive with --expiration.
+    #[arg(long, conflicts_with = "expiration", value_parser = parse_seconds)]
+    pub expires_in: Option<Duration>,
+
+    /// Print the node's raw message when it rejects the deploy
+    #[arg(short, long)]
+    pub verbose: bool,
+
+    /// Skip the gRPC and HTTP connectivity check before deploying
+    #[arg(long)]
+    pub skip_preflight: bool,
+
+    #[command(flatten)]
+    pub history: HistoryFlags,
 }
 
 /// Arguments for network-health command
 #[derive(Parser)]
 pub struct NetworkHealthArgs {
-    /// Check standard F1r3fly shard ports (bootstrap, validator1, validator2, observer)
+    /// Check the HTTP ports of the standard shard (bootstrap, validator1-3, observer)
     #[arg(short, long, default_value_t = true, action = ArgAction::Set, value_parser = clap::value_parser!(bool))]
     pub standard_ports: bool,
 
@@ -573,7 +1588,7 @@ pub struct NetworkHealthArgs {
     pub custom_ports: Option<String>,
 
     /// Host address
-    #[arg(short = 'H', long, default_value = "localhost")]
+    #[arg(short = 'H', long, default_value = DEFAULT_HOST)]
     pub host: String,
 
     /// Enable recursive peer discovery to find all peers in the network
@@ -584,6 +1599,22 @@ pub struct NetworkHealthArgs {
     #[arg(short = 'n', long, default_value = "20")]
     pub max_peers: i32,
 
+    /// Nodes queried at once
+    #[arg(long, default_value_t = DEFAULT_HEALTH_CONCURRENCY, value_parser = clap::value_parser!(usize).range(1..))]
+    pub concurrency: usize,
+
+    /// How long to wait for each node's /status before counting it as down (a bare number is seconds)
+    #[arg(long, alias = "timeout-secs", default_value = "5s", value_parser = parse_seconds)]
+    pub timeout: Duration,
+
+    /// Exit non-zero when fewer than this many nodes are healthy
+    #[arg(long, value_name = "N")]
+    pub min_healthy: Option<usize>,
+
+    /// Exit non-zero when less than this percentage of nodes are healthy
+    #[arg(long, value_name = "PERCENT", value_parser = clap::value_parser!(u8).range(0..=100))]
+    pub min_healthy_pct: Option<u8>,
+
     /// Print more details about the results
     #[arg(short, long)]
     pub verbose: bool,
@@ -591,6 +1622,9 @@ pub struct NetworkHealthArgs {
     /// Print underlying HTTP requests and responses
     #[arg(long)]
     pub debug: bool,
+
+    #[command(flatten)]
+    pub output: OutputArgs,
 }
 
 /// Arguments for transfer command
@@ -600,62 +1634,159 @@ pub struct TransferArgs {
     #[arg(short, long)]
     pub to_address: String,
 
-    /// Amount to transfer
-    #[arg(short, long)]
-    pub amount: u64,
+    /// Amount to transfer in tokens, e.g. 1 or 0.25 (up to 8 decimal places)
+    #[arg(short, long, required_unless_present = "amount_dust")]
+    pub amount: Option<RevAmount>,
 
-    /// Private key for signing the transfer (hex format)
-    #[arg(
-        long,
-        default_value = "5f668a7ee96d944a4494cc947e4005e172d7ab3461ee5538f1f2a45a835e9657"
-    )]
-    pub private_key: String,
+    /// Amount to transfer in dust (1 token = 100,000,000 dust), instead of --amount
+    #[arg(long, conflicts_with = "amount")]
+    pub amount_dust: Option<u64>,
 
-    /// Host address
-    #[arg(short = 'H', long, default_value = "localhost")]
-    pub host: String,
+    #[command(flatten)]
+    pub signing: SigningArgs,
 
-    /// gRPC port number for deploy
-    #[arg(short, long, default_value_t = 40412)]
-    pub port: u16,
+    #[command(flatten)]
+    pub key_guard: KeyGuardArgs,
 
-    /// HTTP port for status queries
-    #[arg(long = "http-port", default_value_t = 40413)]
-    pub http_port: u16,
+    #[command(flatten)]
+    pub node: NodeConnArgs,
 
     /// Use bigger phlo limit (recommended for transfers)
     #[arg(short, long, default_value_t = true)]
     pub bigger_phlo: bool,
 
+    /// Transfers above this many tokens are confirmed by typing the amount instead of "yes"
+    #[arg(long = "confirm-above", default_value_t = 1000)]
+    pub confirm_above: u64,
+
+    /// Soft cap in tokens: above it the amount must be typed, and --yes alone is refused
+    /// (default: max_transfer_rev in config.toml, else no cap)
+    #[arg(long, value_name = "TOKENS")]
+    pub max_transfer: Option<u64>,
+
+    /// Send a transfer above the soft cap, with --yes or after confirming normally
+    #[arg(long)]
+    pub allow_large_transfer: bool,
+
+    /// Warn when the amount is above this percentage of the sender's balance; 0 disables
+    /// (default: balance_warn_percent in config.toml, else 50)
+    #[arg(long, value_name = "PERCENT")]
+    pub balance_warn_percent: Option<u8>,
+
+    /// Skip the balance and recent-transfer checks before confirming
+    #[arg(long)]
+    pub no_amount_checks: bool,
+
+    /// Send even when the sender's balance does not cover the amount plus phlo
+    #[arg(long)]
+    pub force: bool,
+
+    /// Rholang transfer template to deploy instead of the built-in one, with the
+    /// {{from}}, {{to}} and {{amount}} placeholders (amount in dust)
+    #[arg(long, value_name = "PATH")]
+    pub template_file: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub output: OutputArgs,
+
     /// Also propose a block after transfer
     #[arg(long, default_value_t = false, action = ArgAction::Set, value_parser = clap::value_parser!(bool))]
     pub propose: bool,
 
-    /// Maximum wait time in seconds for deploy finalization
-    #[arg(long = "max-wait", default_value_t = 300)]
-    pub max_wait: u64,
-
-    /// Check interval in seconds for deploy status
-    #[arg(long = "check-interval", default_value_t = 5)]
-    pub check_interval: u64,
+    #[command(flatten)]
+    pub wait: WaitBehaviorArgs,
 
-    /// Observer node host for finalization checks (falls back to main host if not specified)
-    #[arg(long = "observer-host")]
-    pub observer_host: Option<String>,
+    /// After finalization, wait until this many more blocks are finalized on top
+    #[arg(long = "confirm-depth")]
+    pub confirm_depth: Option<u32>,
 
-    /// Observer node gRPC port for finalization checks (falls back to 40452 if not specified)
-    #[arg(long = "observer-port")]
-    pub observer_port: Option<u16>,
+    #[command(flatten)]
+    pub observer: ObserverConnArgs,
 
     /// Expiration timestamp in milliseconds (Unix epoch). Deploy becomes invalid after this time.
     /// Use 0 or omit for no expiration.
     #[arg(long)]
     pub expiration: Option<i64>,
 
-    /// Expiration duration in seconds from now. Deploy becomes invalid after this duration.
-    /// Mutually exclusive with --expiration.
-    #[arg(long, conflicts_with = "expiration")]
-    pub expires_in: Option<u64>,
+    /// Expiration as a duration from now (e.g. 90s, 10m; a bare number is seconds).
+    /// Deploy becomes invalid after this duration. Mutually exclusive with --expiration.
+    #[arg(long, conflicts_with = "expiration", value_parser = parse_seconds)]
+    pub expires_in: Option<Duration>,
+
+    /// Deploy lifespan in blocks, for nodes that do not report one in /api/status.
+    /// Waiting stops once the tip passes the deploy's valid-after block plus this.
+    #[arg(long)]
+    pub deploy_lifespan: Option<i64>,
+
+    /// Print the node's raw message when it rejects the deploy
+    #[arg(short, long)]
+    pub verbose: bool,
+
+    /// Skip the gRPC and HTTP connectivity check before deploying
+    #[arg(long)]
+    pub skip_preflight: bool,
+
+    /// Ring the bell and run the configured notify_command when the wait ends
+    #[arg(long)]
+    pub notify: bool,
+
+    #[command(flatten)]
+    pub webhook: WebhookArgs,
+
+    #[command(flatten)]
+    pub verify: VerifyArgs,
+
+    #[command(flatten)]
+    pub history: HistoryFlags,
+}
+
+impl TransferArgs {
+    /// `--amount`, or `--amount-dust` when given instead
+    pub fn transfer_amount(&self) -> RevAmount {
+        match self.amount_dust {
+            Some(dust) => RevAmount::from_dust(dust),
+            None => self.amount.unwrap_or_default(),
+        }
+    }
+}
+
+/// Arguments for batch-transfer command
+#[derive(Parser)]
+pub struct BatchTransferArgs {
+    /// CSV file of address,amount[,memo] rows, amounts in tokens
+    #[arg(short, long)]
+    pub file: PathBuf,
+
+    /// Send one transfer deploy per row instead of one deploy for all rows
+    #[arg(long)]
+    pub sequential: bool,
+
+    /// Print the generated Rholang without deploying
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Send even when the sender's balance does not cover the total plus phlo
+    #[arg(long)]
+    pub force: bool,
+
+    /// Skip the gRPC and HTTP connectivity check before deploying
+    #[arg(long)]
+    pub skip_preflight: bool,
+
+    #[command(flatten)]
+    pub signing: SigningArgs,
+
+    #[command(flatten)]
+    pub key_guard: KeyGuardArgs,
+
+    #[command(flatten)]
+    pub node: NodeConnArgs,
+
+    #[command(flatten)]
+    pub observer: ObserverConnArgs,
+
+    #[command(flatten)]
+    pub wait: WaitBehaviorArgs,
 }
 
 /// Arguments for load-test command
@@ -665,92 +1796,297 @@ pub struct LoadTestArgs {
     #[arg(long)]
     pub to_address: String,
 
-    /// Number of transfers to send
+    /// Number of transfers to send (batches, with --mode propose-batches)
     #[arg(long, default_value_t = 20)]
     pub num_tests: u32,
 
-    /// Amount per transfer
-    #[arg(long, default_value_t = 1)]
-    pub amount: u64,
+    /// sequential: one transfer at a time, waiting for inclusion; propose-batches:
+    /// submit --batch-size transfers, propose on the same node, count what was included
+    #[arg(long, default_value_t = crate::load_batches::LoadMode::Sequential)]
+    pub mode: crate::load_batches::LoadMode,
 
-    /// Seconds between tests
-    #[arg(long, default_value_t = 10)]
-    pub interval: u64,
+    /// Transfers submitted before each propose, with --mode propose-batches
+    #[arg(long, default_value_t = crate::load_batches::DEFAULT_BATCH_SIZE, value_parser = clap::value_parser!(usize).range(1..))]
+    pub batch_size: usize,
 
-    /// Private key for signing (hex format)
-    #[arg(
-        long,
-        default_value = "5f668a7ee96d944a4494cc947e4005e172d7ab3461ee5538f1f2a45a835e9657"
-    )]
-    pub private_key: String,
+    /// Amount per transfer in tokens, e.g. 1 or 0.25 (up to 8 decimal places)
+    #[arg(long, default_value = "1")]
+    pub amount: RevAmount,
 
-    /// Host address
-    #[arg(short = 'H', long, default_value = "localhost")]
-    pub host: String,
+    /// Amount per transfer in dust (1 token = 100,000,000 dust), instead of --amount
+    #[arg(long, conflicts_with = "amount")]
+    pub amount_dust: Option<u64>,
 
-    /// gRPC port number
-    #[arg(short, long, default_value_t = 40412)]
-    pub port: u16,
+    /// Time between tests (a bare number is seconds)
+    #[arg(long, default_value = "10s", value_parser = parse_seconds)]
+    pub interval: Duration,
 
-    /// HTTP port for status queries
-    #[arg(long = "http-port", default_value_t = 40413)]
-    pub http_port: u16,
+    #[command(flatten)]
+    pub signing: SigningArgs,
+
+    #[command(flatten)]
+    pub key_guard: KeyGuardArgs,
 
-    /// Check interval in seconds for deploy status (fast mode)
-    #[arg(long = "check-interval", default_value_t = 1)]
-    pub check_interval: u64,
+    #[command(flatten)]
+    pub node: NodeConnArgs,
+
+    /// Interval between deploy status checks, fast mode (a bare number is seconds)
+    #[arg(long = "check-interval", default_value = "1s", value_parser = parse_seconds)]
+    pub check_interval: Duration,
+
+    #[command(flatten)]
+    pub poll: PollArgs,
 
     /// Max depth to check main chain for orphan detection
     #[arg(long = "chain-depth", default_value_t = 200)]
     pub chain_depth: u32,
 
-    /// Read-only gRPC port for balance queries (requires read-only node)
-    #[arg(long = "readonly-port", default_value_t = 40452)]
-    pub readonly_port: u16,
+    // Read-only node for balance queries (--readonly-port is an alias of --observer-port)
+    #[command(flatten)]
+    pub observer: ObserverConnArgs,
 
-    /// Maximum time in seconds to wait for block inclusion
-    #[arg(long = "inclusion-timeout", default_value_t = 120)]
-    pub inclusion_timeout: u64,
+    /// Maximum wait for block inclusion (a bare number is seconds)
+    #[arg(long = "inclusion-timeout", default_value = "120s", value_parser = parse_seconds)]
+    pub inclusion_timeout: Duration,
 
-    /// Maximum time in seconds to wait for block finalization
-    #[arg(long = "finalization-timeout", default_value_t = 120)]
-    pub finalization_timeout: u64,
+    /// Maximum wait for block finalization (a bare number is seconds)
+    #[arg(long = "finalization-timeout", default_value = "120s", value_parser = parse_seconds)]
+    pub finalization_timeout: Duration,
 }
 
-/// Arguments for validator-status command
-#[derive(Parser)]
-pub struct ValidatorStatusArgs {
-    /// Validator public key to check (hex format)
-    #[arg(short = 'k', long)]
-    pub public_key: String,
+impl LoadTestArgs {
+    /// `--amount`, or `--amount-dust` when given instead
+    pub fn transfer_amount(&self) -> RevAmount {
+        self.amount_dust.map_or(self.amount, RevAmount::from_dust)
+    }
+}
 
-    /// Host address
-    #[arg(short = 'H', long, default_value = "localhost")]
-    pub host: String,
+/// Arguments for analyze command
+#[derive(Parser, Debug)]
+pub struct AnalyzeArgs {
+    /// Rholang file to analyze
+    pub file: PathBuf,
 
-    /// gRPC port number (use 40452 for observer/read-only node)
-    #[arg(short, long, default_value_t = 40452)]
-    pub port: u16,
+    #[command(flatten)]
+    pub term_limits: TermLimitArgs,
 
-    /// HTTP port number for explore-deploy queries
-    #[arg(long = "http-port", default_value_t = 40453)]
-    pub http_port: u16,
+    #[command(flatten)]
+    pub output: OutputArgs,
 }
 
-/// Arguments for PoS contract query commands (epoch-info, network-consensus, epoch-rewards)
+/// Arguments for create-test-wallets command
 #[derive(Parser)]
-pub struct PosQueryArgs {
-    /// Host address
-    #[arg(short = 'H', long, default_value = "localhost")]
-    pub host: String,
+pub struct CreateTestWalletsArgs {
+    /// Number of wallets to generate
+    #[arg(short = 'n', long, required_unless_present = "resume")]
+    pub count: Option<usize>,
+
+    /// Tokens to send to each wallet
+    #[arg(short = 'a', long, default_value_t = 100)]
+    pub amount: u64,
+
+    /// Manifest file (.json), or a directory for the manifest and one file per wallet
+    #[arg(short = 'o', long)]
+    pub output: PathBuf,
+
+    /// Continue an existing manifest: fund what is unfunded and verify the rest
+    #[arg(long)]
+    pub resume: bool,
+
+    /// Wallets funded by each deploy
+    #[arg(long, default_value_t = crate::test_wallets::DEFAULT_CHUNK_SIZE, value_parser = clap::value_parser!(usize).range(1..))]
+    pub chunk_size: usize,
+
+    /// Balance queries in flight at once while verifying
+    #[arg(long, default_value_t = crate::test_wallets::DEFAULT_VERIFY_CONCURRENCY, value_parser = clap::value_parser!(usize).range(1..))]
+    pub concurrency: usize,
+
+    /// Run against a node that is neither local nor marked dev in the topology
+    #[arg(long)]
+    pub allow_non_dev: bool,
+
+    /// Topology file whose `environment` marks a remote dev shard
+    #[arg(long)]
+    pub topology: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub signing: SigningArgs,
+
+    #[command(flatten)]
+    pub key_guard: KeyGuardArgs,
+
+    #[command(flatten)]
+    pub node: NodeConnArgs,
+
+    #[command(flatten)]
+    pub observer: ObserverConnArgs,
+
+    #[command(flatten)]
+    pub wait: WaitBehaviorArgs,
+}
+
+/// Arguments for latency-probe command
+#[derive(Parser)]
+pub struct LatencyProbeArgs {
+    /// Number of probe rounds
+    #[arg(long, default_value_t = 5)]
+    pub count: u32,
+
+    /// Time between rounds (a bare number is seconds)
+    #[arg(long, default_value = "2s", value_parser = parse_seconds)]
+    pub interval: Duration,
+
+    /// Propose a block after each deploy (for nodes without auto-propose)
+    #[arg(long)]
+    pub propose: bool,
+
+    /// Only time exploratory deploys and status calls; nothing is deployed
+    #[arg(long = "read-only")]
+    pub read_only: bool,
+
+    /// Baseline file: written if missing, otherwise compared against
+    #[arg(long)]
+    pub baseline: Option<PathBuf>,
+
+    /// Overwrite the baseline with this run after comparing
+    #[arg(long = "update-baseline", requires = "baseline")]
+    pub update_baseline: bool,
+
+    /// Percent increase in p50 or p95 over the baseline flagged as a regression
+    #[arg(long, default_value_t = 20.0)]
+    pub threshold: f64,
+
+    #[command(flatten)]
+    pub signing: SigningArgs,
+
+    #[command(flatten)]
+    pub key_guard: KeyGuardArgs,
+
+    #[command(flatten)]
+    pub node: NodeConnArgs,
+
+    #[command(flatten)]
+    pub observer: ObserverConnArgs,
+
+    /// Interval between inclusion and finalization checks (a bare number is seconds)
+    #[arg(long = "check-interval", default_value = "1s", value_parser = parse_seconds)]
+    pub check_interval: Duration,
+
+    /// Maximum wait for block inclusion (a bare number is seconds)
+    #[arg(long = "inclusion-timeout", default_value = "120s", value_parser = parse_seconds)]
+    pub inclusion_timeout: Duration,
+
+    /// Maximum wait for block finalization (a bare number is seconds)
+    #[arg(long = "finalization-timeout", default_value = "120s", value_parser = parse_seconds)]
+    pub finalization_timeout: Duration,
+}
+
+/// Arguments for validator-status command
+#[derive(Parser)]
+pub struct ValidatorStatusArgs {
+    /// Validator public key to check (hex format)
+    #[arg(short = 'k', long)]
+    pub public_key: String,
+
+    /// Host address
+    #[arg(short = 'H', long, default_value = DEFAULT_HOST)]
+    pub host: String,
 
     /// gRPC port number (use 40452 for observer/read-only node)
-    #[arg(short, long, default_value_t = 40452)]
+    #[arg(short, long, default_value_t = OBSERVER_GRPC_PORT, default_value_ifs = ROLE_GRPC_PORTS)]
     pub port: u16,
 
-    /// HTTP port number for explore-deploy queries
-    #[arg(long = "http-port", default_value_t = 40453)]
+    /// HTTP port number for explore-deploy queries (follows --port on the standard shard)
+    #[arg(long = "http-port", default_value_t = OBSERVER_HTTP_PORT, default_value_ifs = POS_HTTP_PORTS)]
     pub http_port: u16,
+
+    /// Standard shard node whose ports to use when none are given
+    #[arg(long = "node-role")]
+    pub node_role: Option<NodeRole>,
+
+    #[command(flatten)]
+    pub identity: IdentityArgs,
+
+    #[command(flatten)]
+    pub pinned: ReferenceArgs,
+
+    /// Walk through each check behind the status with the numbers used, ending with a conclusion
+    #[arg(long)]
+    pub explain: bool,
+
+    /// Recent blocks scanned for the validator as sender (with --explain)
+    #[arg(long, default_value_t = 50)]
+    pub recent_blocks: i64,
+
+    #[command(flatten)]
+    pub query_key: QueryKeyArgs,
+}
+
+/// Arguments for PoS contract query commands (epoch-info, network-consensus, epoch-rewards)
+#[derive(Parser)]
+pub struct PosQueryArgs {
+    /// Host address
+    #[arg(short = 'H', long, default_value = DEFAULT_HOST)]
+    pub host: String,
+
+    /// gRPC port number (use 40452 for observer/read-only node)
+    #[arg(short, long, default_value_t = OBSERVER_GRPC_PORT, default_value_ifs = ROLE_GRPC_PORTS)]
+    pub port: u16,
+
+    /// HTTP port number for explore-deploy queries (follows --port on the standard shard)
+    #[arg(long = "http-port", default_value_t = OBSERVER_HTTP_PORT, default_value_ifs = POS_HTTP_PORTS)]
+    pub http_port: u16,
+
+    /// Standard shard node whose ports to use when none are given
+    #[arg(long = "node-role")]
+    pub node_role: Option<NodeRole>,
+
+    #[command(flatten)]
+    pub identity: IdentityArgs,
+
+    #[command(flatten)]
+    pub pinned: ReferenceArgs,
+
+    #[command(flatten)]
+    pub query_key: QueryKeyArgs,
+}
+
+/// Arguments for pos-query command
+#[derive(Parser)]
+pub struct PosMethodArgs {
+    /// PoS contract method to call, e.g. getMinimumBond
+    pub method: String,
+
+    /// Arguments to pass before the return channel
+    #[arg(allow_negative_numbers = true)]
+    pub args: Vec<PosArg>,
+
+    /// Host address
+    #[arg(short = 'H', long, default_value = DEFAULT_HOST)]
+    pub host: String,
+
+    /// gRPC port number (use 40452 for observer/read-only node)
+    #[arg(short, long, default_value_t = OBSERVER_GRPC_PORT, default_value_ifs = ROLE_GRPC_PORTS)]
+    pub port: u16,
+
+    /// HTTP port number of the same node (follows --port on the standard shard)
+    #[arg(long = "http-port", default_value_t = OBSERVER_HTTP_PORT, default_value_ifs = POS_HTTP_PORTS)]
+    pub http_port: u16,
+
+    /// Standard shard node whose ports to use when none are given
+    #[arg(long = "node-role")]
+    pub node_role: Option<NodeRole>,
+
+    /// Evaluate against the state after this block instead of the latest
+    #[arg(short, long)]
+    pub block_hash: Option<String>,
+
+    /// Print the node's Rholang expression as returned instead of plain JSON
+    #[arg(long)]
+    pub raw: bool,
+
+    #[command(flatten)]
+    pub query_key: QueryKeyArgs,
 }
 
 /// Arguments for get-node-id command
@@ -769,7 +2105,7 @@ pub struct GetNodeIdArgs {
     pub format: String,
 
     /// Node hostname for rnode-url format
-    #[arg(short = 'H', long, default_value = "localhost")]
+    #[arg(short = 'H', long, default_value = DEFAULT_HOST)]
     pub host: String,
 
     /// Protocol port for rnode-url format
@@ -785,13 +2121,17 @@ pub struct GetNodeIdArgs {
 #[derive(Parser, Debug)]
 pub struct WatchEventsArgs {
     /// Host address
-    #[arg(short = 'H', long, default_value = "localhost")]
+    #[arg(short = 'H', long, default_value = DEFAULT_HOST)]
     pub host: String,
 
     /// HTTP port number for WebSocket connection
-    #[arg(long, default_value_t = 40403)]
+    #[arg(long, default_value_t = BOOTSTRAP_HTTP_PORT, default_value_ifs = ROLE_HTTP_PORTS)]
     pub http_port: u16,
 
+    /// Standard shard node whose ports to use when none are given
+    #[arg(long = "node-role")]
+    pub node_role: Option<NodeRole>,
+
     /// Filter events by type: created, added, finalized (or finalised), transfers, genesis, lifecycle
     #[arg(short, long)]
     pub filter: Option<String>,
@@ -799,33 +2139,237 @@ pub struct WatchEventsArgs {
     /// Retry reconnection indefinitely until manually killed (Ctrl+C)
     #[arg(long, default_value_t = false)]
     pub retry_forever: bool,
+
+    /// Node to watch alongside the others, as host:port or name=host:port (repeatable, comma-separated)
+    #[arg(short = 'n', long = "node", value_delimiter = ',', conflicts_with_all = ["from_dump", "dump_events"])]
+    pub nodes: Vec<String>,
+
+    /// JSON topology file listing nodes to watch, as with --node
+    #[arg(short = 't', long = "topology", conflicts_with_all = ["from_dump", "dump_events"])]
+    pub topology: Option<PathBuf>,
+
+    /// With several nodes, report a block one node has that another has not after this long
+    #[arg(long = "lag-threshold", default_value = "10s", value_parser = parse_seconds)]
+    pub lag_threshold: Duration,
+
+    #[command(flatten)]
+    pub dump: EventDumpArgs,
+}
+
+/// Arguments for watch-wallet command
+#[derive(Parser, Debug)]
+pub struct WatchWalletArgs {
+    /// Deployer public key (hex) or vault address to watch (repeatable, comma-separated)
+    #[arg(short, long = "watch", value_delimiter = ',', required = true)]
+    pub watch: Vec<crate::wallet_watch::WatchTarget>,
+
+    /// Host address
+    #[arg(short = 'H', long, default_value = DEFAULT_HOST)]
+    pub host: String,
+
+    /// HTTP port for block queries
+    #[arg(long, default_value_t = OBSERVER_HTTP_PORT, default_value_ifs = ROLE_HTTP_PORTS)]
+    pub http_port: u16,
+
+    /// WebSocket port for real-time events
+    #[arg(long, default_value_t = BOOTSTRAP_HTTP_PORT, default_value_ifs = ROLE_HTTP_PORTS)]
+    pub ws_port: u16,
+
+    /// Standard shard node whose ports to use when none are given
+    #[arg(long = "node-role")]
+    pub node_role: Option<NodeRole>,
+
+    /// Scan blocks from this height to the current tip before following new blocks
+    #[arg(long)]
+    pub from_height: Option<i64>,
+
+    /// Shell command run for each match, with the match as a JSON line on stdin
+    #[arg(long)]
+    pub notify_command: Option<String>,
+
+    #[command(flatten)]
+    pub query_key: QueryKeyArgs,
+}
+
+/// Arguments for config command
+#[derive(Parser, Debug)]
+pub struct ConfigArgs {
+    #[command(subcommand)]
+    pub action: ConfigAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigAction {
+    /// Print the settings a command line would run with, and where each came from
+    ///
+    /// Hosts, ports, key source, timeouts and output flags are listed with their
+    /// provenance: flag, env <VAR>, node-role <role>, config.toml or default.
+    /// Private keys are never printed. The command itself is not run.
+    Effective(ConfigEffectiveArgs),
+}
+
+/// Arguments for config effective
+#[derive(Parser, Debug)]
+pub struct ConfigEffectiveArgs {
+    /// The command line to resolve, e.g. `transfer -t 1111... -a 5 --node-role validator1`
+    #[arg(
+        required = true,
+        trailing_var_arg = true,
+        allow_hyphen_values = true,
+        value_name = "COMMAND"
+    )]
+    pub command: Vec<String>,
+}
+
+/// Arguments for funder-audit command
+#[derive(Parser, Debug)]
+pub struct FunderAuditArgs {
+    /// Funder public key (hex) or vault address
+    #[arg(short = 'a', long = "address")]
+    pub funder: crate::wallet_watch::WatchTarget,
+
+    /// Host address
+    #[arg(short = 'H', long, default_value = DEFAULT_HOST)]
+    pub host: String,
+
+    /// HTTP port for block and balance queries
+    #[arg(long, default_value_t = OBSERVER_HTTP_PORT, default_value_ifs = ROLE_HTTP_PORTS)]
+    pub http_port: u16,
+
+    /// WebSocket port for real-time events
+    #[arg(long, default_value_t = BOOTSTRAP_HTTP_PORT, default_value_ifs = ROLE_HTTP_PORTS)]
+    pub ws_port: u16,
+
+    /// Standard shard node whose ports to use when none are given
+    #[arg(long = "node-role")]
+    pub node_role: Option<NodeRole>,
+
+    /// Start from the balance before this height and scan up to the tip before following new blocks
+    #[arg(long)]
+    pub from_height: Option<i64>,
+
+    /// How often to check the balance and print a summary
+    #[arg(long, default_value = "30s", value_parser = parse_seconds)]
+    pub interval: Duration,
+
+    /// Difference in dust between expected and actual balance that is not drift
+    #[arg(long, default_value_t = crate::funder_audit::DEFAULT_TOLERANCE)]
+    pub tolerance: u64,
+
+    /// Recent attributions kept in memory and shown with a drift alarm
+    #[arg(long, default_value_t = crate::funder_audit::DEFAULT_WINDOW)]
+    pub window: usize,
+
+    #[command(flatten)]
+    pub output: OutputArgs,
+
+    #[command(flatten)]
+    pub identity: IdentityArgs,
+
+    #[command(flatten)]
+    pub query_key: QueryKeyArgs,
 }
 
 #[derive(Parser, Debug)]
 pub struct DagArgs {
     /// Host address
-    #[arg(short = 'H', long, default_value = "localhost")]
+    #[arg(short = 'H', long, default_value = DEFAULT_HOST)]
     pub host: String,
 
     /// HTTP port for block queries
-    #[arg(long, default_value_t = 40413)]
+    #[arg(long, default_value_t = OBSERVER_HTTP_PORT, default_value_ifs = ROLE_HTTP_PORTS)]
     pub http_port: u16,
 
     /// WebSocket port for real-time events
-    #[arg(long, default_value_t = 40403)]
+    #[arg(long, default_value_t = BOOTSTRAP_HTTP_PORT, default_value_ifs = ROLE_HTTP_PORTS)]
     pub ws_port: u16,
 
+    /// Standard shard node whose ports to use when none are given
+    #[arg(long = "node-role")]
+    pub node_role: Option<NodeRole>,
+
     /// Initial number of blocks to load
     #[arg(short, long, default_value_t = 50)]
     pub depth: usize,
 
     /// Disable real-time updates (static view)
-    #[arg(long, default_value_t = false)]
+    #[arg(long, default_value_t = false, conflicts_with = "from_dump")]
     pub no_live: bool,
 
-    /// Show deploy counts inline
-    #[arg(long, default_value_t = true)]
-    pub show_deploys: bool,
+    /// Show deploy counts inline (default: the saved setting, else true)
+    #[arg(long, num_args = 0..=1, default_missing_value = "true")]
+    pub show_deploys: Option<bool>,
+
+    /// Use colors (default: the saved setting, else true)
+    #[arg(long, num_args = 0..=1, default_missing_value = "true")]
+    pub color: Option<bool>,
+
+    /// Minimum time between DAG relayouts; events in between are coalesced
+    /// (e.g. 500ms, 1s; a bare number is milliseconds; default: the saved setting, else 200ms)
+    #[arg(long, value_parser = parse_millis)]
+    pub relayout_interval: Option<Duration>,
+
+    /// Forget the display settings saved by earlier sessions
+    #[arg(long, default_value_t = false)]
+    pub reset_ui: bool,
+
+    /// Mark blocks with deploys signed by this public key (repeatable)
+    #[arg(long = "highlight-deployer", value_name = "PUBKEY")]
+    pub highlight_deployer: Vec<crate::wallet_watch::WatchTarget>,
+
+    /// Mark blocks with deploys from this vault address (repeatable)
+    #[arg(long = "highlight-address", value_name = "REV_ADDRESS")]
+    pub highlight_address: Vec<crate::wallet_watch::WatchTarget>,
+
+    #[command(flatten)]
+    pub identity: IdentityArgs,
+
+    #[command(flatten)]
+    pub dump: EventDumpArgs,
+}
+
+/// Arguments for dashboard command
+#[derive(Parser, Debug)]
+pub struct DashboardArgs {
+    /// Host of the node followed for finality and blocks
+    #[arg(short = 'H', long, default_value = DEFAULT_HOST)]
+    pub host: String,
+
+    /// HTTP port for finality and block queries
+    #[arg(long, default_value_t = OBSERVER_HTTP_PORT, default_value_ifs = ROLE_HTTP_PORTS)]
+    pub http_port: u16,
+
+    /// WebSocket port for block events
+    #[arg(long, default_value_t = BOOTSTRAP_HTTP_PORT, default_value_ifs = ROLE_HTTP_PORTS)]
+    pub ws_port: u16,
+
+    /// Standard shard node whose ports to use when none are given
+    #[arg(long = "node-role")]
+    pub node_role: Option<NodeRole>,
+
+    /// Node whose health is shown, as host:port or name=host:port (repeatable, comma-separated)
+    #[arg(short = 'n', long = "node", value_delimiter = ',')]
+    pub nodes: Vec<String>,
+
+    /// JSON topology file listing the nodes whose health is shown
+    /// (default: topology.json in the config directory, else the standard shard on --host)
+    #[arg(short = 't', long = "topology")]
+    pub topology: Option<PathBuf>,
+
+    /// Time between health and finality refreshes (e.g. 5s, 1m)
+    #[arg(long, default_value = "5s", value_parser = parse_seconds)]
+    pub refresh_interval: Duration,
+
+    /// Raise a stall alert when the last finalized block has not moved for this long
+    #[arg(long, default_value = "1m", value_parser = parse_seconds)]
+    pub stall_timeout: Duration,
+
+    /// Heights below the highest block seen that are matched for equivocations
+    #[arg(long, default_value_t = crate::equivocation::DEFAULT_WINDOW)]
+    pub window: i64,
+
+    #[command(flatten)]
+    pub query_key: QueryKeyArgs,
 }
 
 /// Arguments for block-transfers command
@@ -835,14 +2379,2630 @@ pub struct BlockTransfersArgs {
     pub block_hash: String,
 
     /// Host address
-    #[arg(short = 'H', long, default_value = "localhost")]
+    #[arg(short = 'H', long, default_value = DEFAULT_HOST)]
     pub host: String,
 
     /// HTTP port number
-    #[arg(short, long, default_value_t = 40403)]
+    #[arg(short, long, default_value_t = OBSERVER_HTTP_PORT, default_value_ifs = ROLE_HTTP_PORTS)]
     pub port: u16,
 
+    /// Standard shard node whose ports to use when none are given
+    #[arg(long = "node-role")]
+    pub node_role: Option<NodeRole>,
+
     /// Show all deploys, not just those with transfers
     #[arg(long = "all-deploys", default_value_t = false)]
     pub all_deploys: bool,
 }
+
+/// Arguments for history command
+#[derive(Parser, Debug)]
+pub struct HistoryArgs {
+    #[command(subcommand)]
+    pub action: HistoryAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum HistoryAction {
+    /// List recorded deploys
+    List {
+        /// Only show records newer than this window (e.g. 30m, 24h, 7d)
+        #[arg(long)]
+        since: Option<String>,
+    },
+
+    /// Find records whose deploy ID or block hash starts with a prefix
+    Search {
+        /// Deploy ID or block hash prefix
+        prefix: String,
+    },
+
+    /// Show the full record for a deploy ID or block hash prefix
+    Show {
+        /// Deploy ID or block hash prefix
+        reference: String,
+
+        /// Re-check the deploy's current status on the recorded node
+        #[arg(long, default_value_t = false)]
+        check: bool,
+
+        #[command(flatten)]
+        query_key: QueryKeyArgs,
+    },
+}
+
+/// Arguments for registry command
+#[derive(Parser, Debug)]
+pub struct RegistryArgs {
+    #[command(subcommand)]
+    pub action: RegistryAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum RegistryAction {
+    /// Register a contract at the deterministic `rho:id:` URI of the signing key
+    ///
+    /// The contract is inserted with `rho:registry:insertSigned:secp256k1`,
+    /// signed for the deploy's timestamp and the given version. A later
+    /// insert with a higher version replaces it at the same URI.
+    InsertSigned(InsertSignedArgs),
+
+    /// Show what is registered at a URI
+    ///
+    /// Exits with code 7 when nothing is registered, and with code 8 when
+    /// --expect-version is given and the entry has another version.
+    Lookup(RegistryLookupArgs),
+}
+
+/// Arguments for registry insert-signed
+#[derive(Parser, Debug)]
+pub struct InsertSignedArgs {
+    /// Rholang file defining the contract, listening on the name given by --name
+    #[arg(short, long)]
+    pub file: PathBuf,
+
+    /// Name the contract listens on; the file runs in its scope and a
+    /// read-only bundle of it is registered
+    #[arg(long, default_value = crate::registry::DEFAULT_CONTRACT_NAME)]
+    pub name: String,
+
+    /// Version signed with the contract; must be higher than the one already at the URI
+    #[arg(long = "contract-version", value_name = "N", default_value_t = 0)]
+    pub contract_version: i64,
+
+    #[command(flatten)]
+    pub signing: SigningArgs,
+
+    #[command(flatten)]
+    pub key_guard: KeyGuardArgs,
+
+    #[command(flatten)]
+    pub node: NodeConnArgs,
+
+    /// Maximum wait for deploy inclusion in a block (e.g. 60s, 2m; a bare number is seconds)
+    #[arg(long = "max-wait", default_value = "60s", value_parser = parse_seconds)]
+    pub max_wait: Duration,
+
+    /// Interval between status checks (a bare number is seconds)
+    #[arg(long = "check-interval", default_value = "2s", value_parser = parse_seconds)]
+    pub check_interval: Duration,
+
+    #[command(flatten)]
+    pub poll: PollArgs,
+
+    /// Print the deploy term and the URI without deploying
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+/// Arguments for registry lookup
+#[derive(Parser, Debug)]
+pub struct RegistryLookupArgs {
+    /// URI to look up: `rho:id:...` or a builtin such as `rho:vault:system`
+    pub uri: String,
+
+    /// Look up at this block instead of the node's latest state
+    #[arg(short = 'b', long = "block-hash")]
+    pub block_hash: Option<String>,
+
+    /// Fail unless the entry is registered with this version
+    #[arg(long = "expect-version", value_name = "N")]
+    pub expect_version: Option<i64>,
+
+    #[command(flatten)]
+    pub node: ReadNodeConnArgs,
+
+    #[command(flatten)]
+    pub query_key: QueryKeyArgs,
+}
+
+/// Arguments for compare-state command
+#[derive(Parser, Debug)]
+pub struct CompareStateArgs {
+    /// Block hash to compare
+    #[arg(short = 'b', long = "block-hash")]
+    pub block_hash: String,
+
+    /// Node HTTP endpoint as host:port or name=host:port (repeatable, comma-separated)
+    #[arg(short = 'n', long = "node", value_delimiter = ',')]
+    pub nodes: Vec<String>,
+
+    /// JSON topology file listing the nodes to compare
+    #[arg(short = 't', long = "topology")]
+    pub topology: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub query_key: QueryKeyArgs,
+}
+
+/// Arguments for export-chain command
+#[derive(Parser, Debug)]
+pub struct ExportChainArgs {
+    /// Host address
+    #[arg(short = 'H', long, default_value = DEFAULT_HOST)]
+    pub host: String,
+
+    /// HTTP port number (not gRPC port)
+    #[arg(short, long, default_value_t = OBSERVER_HTTP_PORT, default_value_ifs = ROLE_HTTP_PORTS)]
+    pub port: u16,
+
+    /// Standard shard node whose ports to use when none are given
+    #[arg(long = "node-role")]
+    pub node_role: Option<NodeRole>,
+
+    /// First block height to export
+    #[arg(long)]
+    pub start: i64,
+
+    /// Last block height to export (inclusive)
+    #[arg(long)]
+    pub end: i64,
+
+    /// Output file (default: stdout), or the output directory with --deploys separate
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Deploy layout: inline (nested in each block line) or separate (blocks.ndjson + deploys.ndjson)
+    #[arg(long, default_value = "inline")]
+    pub deploys: crate::export::DeployLayout,
+
+    /// Resume an interrupted export at this height, appending to the existing output
+    #[arg(long)]
+    pub resume_from: Option<i64>,
+
+    /// Heights fetched per ranged-blocks request
+    #[arg(long, default_value_t = crate::export::DEFAULT_CHUNK_SIZE)]
+    pub chunk_size: i64,
+
+    /// Only export blocks with a failed system deploy (CloseBlock, Slash, ...)
+    #[arg(long, default_value_t = false)]
+    pub system_errors_only: bool,
+}
+
+/// Arguments for chain-stats command
+#[derive(Parser, Debug)]
+pub struct ChainStatsArgs {
+    /// Host address
+    #[arg(short = 'H', long, default_value = DEFAULT_HOST)]
+    pub host: String,
+
+    /// HTTP port number (not gRPC port)
+    #[arg(short, long, default_value_t = OBSERVER_HTTP_PORT, default_value_ifs = ROLE_HTTP_PORTS)]
+    pub port: u16,
+
+    /// Standard shard node whose ports to use when none are given
+    #[arg(long = "node-role")]
+    pub node_role: Option<NodeRole>,
+
+    /// First block height to include
+    #[arg(long, conflicts_with = "last")]
+    pub start: Option<i64>,
+
+    /// Last block height to include (default: the current tip)
+    #[arg(long)]
+    pub end: Option<i64>,
+
+    /// Include the trailing N heights ending at --end or the tip
+    #[arg(long)]
+    pub last: Option<i64>,
+
+    /// Heights fetched per ranged-blocks request
+    #[arg(long, default_value_t = crate::export::DEFAULT_CHUNK_SIZE)]
+    pub chunk_size: i64,
+
+    /// Skip fetching block details for the phlo total
+    #[arg(long, default_value_t = false)]
+    pub skip_phlo: bool,
+
+    #[command(flatten)]
+    pub output: OutputArgs,
+
+    /// List blocks with failed system deploys instead of throughput statistics
+    #[arg(long, default_value_t = false)]
+    pub system_errors_only: bool,
+
+    #[command(flatten)]
+    pub query_key: QueryKeyArgs,
+}
+
+/// Arguments for equivocation-check command
+#[derive(Parser, Debug)]
+pub struct EquivocationCheckArgs {
+    /// Host address
+    #[arg(short = 'H', long, default_value = DEFAULT_HOST)]
+    pub host: String,
+
+    /// HTTP port number (not gRPC port)
+    #[arg(short, long, default_value_t = OBSERVER_HTTP_PORT, default_value_ifs = ROLE_HTTP_PORTS)]
+    pub port: u16,
+
+    /// First block height to scan
+    #[arg(long, conflicts_with = "last")]
+    pub start: Option<i64>,
+
+    /// Last block height to scan (default: the current tip)
+    #[arg(long)]
+    pub end: Option<i64>,
+
+    /// Scan the trailing N heights ending at --end or the tip
+    #[arg(long)]
+    pub last: Option<i64>,
+
+    /// Heights fetched per ranged-blocks request
+    #[arg(long, default_value_t = crate::export::DEFAULT_CHUNK_SIZE)]
+    pub chunk_size: i64,
+
+    /// Follow added blocks from the event stream instead of scanning a range
+    #[arg(long, default_value_t = false, conflicts_with_all = ["start", "end", "last"])]
+    pub live: bool,
+
+    /// WebSocket port for --live
+    #[arg(long, default_value_t = BOOTSTRAP_HTTP_PORT, default_value_ifs = ROLE_HTTP_PORTS)]
+    pub ws_port: u16,
+
+    /// Standard shard node whose ports to use when none are given
+    #[arg(long = "node-role")]
+    pub node_role: Option<NodeRole>,
+
+    /// With --live, heights below the highest block seen that are still matched;
+    /// older sequence numbers are forgotten
+    #[arg(long, default_value_t = crate::equivocation::DEFAULT_WINDOW)]
+    pub window: i64,
+
+    #[command(flatten)]
+    pub query_key: QueryKeyArgs,
+}
+
+/// Arguments for validator-chain command
+#[derive(Parser, Debug)]
+pub struct ValidatorChainArgs {
+    /// Public key of the validator (hex)
+    pub validator: String,
+
+    /// Host address
+    #[arg(short = 'H', long, default_value = DEFAULT_HOST)]
+    pub host: String,
+
+    /// HTTP port number (not gRPC port)
+    #[arg(short, long, default_value_t = OBSERVER_HTTP_PORT, default_value_ifs = ROLE_HTTP_PORTS)]
+    pub port: u16,
+
+    /// Standard shard node whose ports to use when none are given
+    #[arg(long = "node-role")]
+    pub node_role: Option<NodeRole>,
+
+    /// First block height to scan
+    #[arg(long, conflicts_with = "last")]
+    pub start: Option<i64>,
+
+    /// Last block height to scan (default: the current tip)
+    #[arg(long)]
+    pub end: Option<i64>,
+
+    /// Scan the trailing N heights ending at --end or the tip
+    #[arg(long)]
+    pub last: Option<i64>,
+
+    /// Heights fetched per ranged-blocks request
+    #[arg(long, default_value_t = crate::export::DEFAULT_CHUNK_SIZE)]
+    pub chunk_size: i64,
+
+    #[command(flatten)]
+    pub output: OutputArgs,
+
+    #[command(flatten)]
+    pub query_key: QueryKeyArgs,
+}
+
+/// Arguments for follow-block command
+#[derive(Parser, Debug)]
+pub struct FollowBlockArgs {
+    /// Hash of the block to follow
+    pub block_hash: String,
+
+    #[command(flatten)]
+    pub node: ReadNodeConnArgs,
+
+    /// WebSocket port for block events
+    #[arg(long, default_value_t = BOOTSTRAP_HTTP_PORT, default_value_ifs = ROLE_HTTP_PORTS)]
+    pub ws_port: u16,
+
+    /// Stage to stop at: added or finalized
+    #[arg(long, default_value = "finalized")]
+    pub until: Until,
+
+    /// How long to wait for the block to reach --until (a bare number is seconds)
+    #[arg(long, default_value = "300s", value_parser = parse_seconds)]
+    pub timeout: Duration,
+
+    /// Time between polls of the node, alongside the event stream (a bare number is seconds)
+    #[arg(long, default_value = "5s", value_parser = parse_seconds)]
+    pub check_interval: Duration,
+
+    #[command(flatten)]
+    pub query_key: QueryKeyArgs,
+}
+
+/// Arguments for stats command
+#[derive(Parser, Debug)]
+pub struct StatsArgs {
+    /// Window to summarize, ending now (e.g. 24h, 7d, 2w; a bare number is days)
+    #[arg(long, default_value = "7d")]
+    pub window: String,
+
+    /// Compare the window with the one of the same length before it
+    #[arg(long, default_value_t = false)]
+    pub compare: bool,
+
+    /// Only show this command (e.g. deploy-and-wait)
+    #[arg(long)]
+    pub command: Option<String>,
+
+    /// Percent increase in p50 or p95 flagged as a regression
+    #[arg(long, default_value_t = 20.0)]
+    pub threshold: f64,
+
+    /// Samples needed in both windows before a change is flagged
+    #[arg(long, default_value_t = 5)]
+    pub min_samples: usize,
+}
+
+#[derive(Parser, Debug)]
+pub struct WebhookTestArgs {
+    #[command(flatten)]
+    pub webhook: WebhookArgs,
+}
+
+/// Curated short names, as (alias, command); each is a `visible_alias` above
+pub const COMMAND_ALIASES: &[(&str, &str)] = &[
+    ("dw", "deploy-and-wait"),
+    ("lfb", "last-finalized-block"),
+    ("wb", "wallet-balance"),
+    ("xd", "exploratory-deploy"),
+];
+
+/// Candidates listed for a mistyped subcommand
+pub const SUGGESTIONS_SHOWN: usize = 3;
+
+/// A subcommand close to what was typed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandSuggestion {
+    pub name: String,
+    pub aliases: Vec<String>,
+    /// First line of the command's help
+    pub about: String,
+    /// Edit distance from the typed name to the command's name or nearest alias
+    pub distance: usize,
+}
+
+/// The `limit` subcommands nearest to `typed` by edit distance, nearest first
+///
+/// Names and aliases are compared case-insensitively, and a name that starts
+/// with at least three typed characters counts as distance 0, so
+/// `exploratory` finds `exploratory-deploy`. Ties go to the alphabetically
+/// first name.
+pub fn suggest_commands(typed: &str, limit: usize) -> Vec<CommandSuggestion> {
+    use clap::CommandFactory;
+
+    let typed = typed.to_ascii_lowercase();
+    let cli = Cli::command();
+    let mut suggestions: Vec<CommandSuggestion> = cli
+        .get_subcommands()
+        .filter(|sub| !sub.is_hide_set())
+        .map(|sub| {
+            let aliases: Vec<String> = sub.get_visible_aliases().map(str::to_string).collect();
+            let distance = std::iter::once(sub.get_name())
+                .chain(aliases.iter().map(String::as_str))
+                .map(|name| {
+                    if typed.len() >= 3 && name.starts_with(&typed) {
+                        0
+                    } else {
+                        edit_distance(&typed, name)
+                    }
+                })
+                .min()
+                .unwrap_or(usize::MAX);
+            CommandSuggestion {
+                name: sub.get_name().to_string(),
+                aliases,
+                about: sub
+                    .get_about()
+                    .map(|about| about.to_string())
+                    .unwrap_or_default(),
+                distance,
+            }
+        })
+        .collect();
+    suggestions.sort_by(|a, b| (a.distance, &a.name).cmp(&(b.distance, &b.name)));
+    suggestions.truncate(limit);
+    suggestions
+}
+
+/// The subcommand name clap rejected, if `error` is an unknown subcommand
+pub fn unrecognized_subcommand(error: &clap::Error) -> Option<String> {
+    use clap::error::{ContextKind, ContextValue, ErrorKind};
+
+    if error.kind() != ErrorKind::InvalidSubcommand {
+        return None;
+    }
+    match error.get(ContextKind::InvalidSubcommand)? {
+        ContextValue::String(typed) => Some(typed.clone()),
+        _ => None,
+    }
+}
+
+/// Error text for an unknown subcommand, listing the nearest commands
+pub fn unrecognized_subcommand_message(typed: &str) -> String {
+    let suggestions = suggest_commands(typed, SUGGESTIONS_SHOWN);
+    let labels: Vec<String> = suggestions
+        .iter()
+        .map(|s| {
+            if s.aliases.is_empty() {
+                s.name.clone()
+            } else {
+                format!("{} ({})", s.name, s.aliases.join(", "))
+            }
+        })
+        .collect();
+    let width = labels.iter().map(String::len).max().unwrap_or(0);
+    let mut lines = vec![
+        format!("error: unrecognized subcommand '{}'", typed),
+        String::new(),
+        "Did you mean one of these?".to_string(),
+    ];
+    for (label, suggestion) in labels.iter().zip(&suggestions) {
+        lines.push(format!("  {:<width$}  {}", label, suggestion.about));
+    }
+    lines.push(String::new());
+    lines.push("For a list of commands, try '--help'.".to_string());
+    lines.join("\n")
+}
+
+/// Levenshtein distance between `a` and `b`, by character
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    fn parse(args: &[&str]) -> Commands {
+        let argv = std::iter::once("node_cli").chain(args.iter().copied());
+        match Cli::try_parse_from(argv) {
+            Ok(cli) => cli.command,
+            Err(e) => panic!("{:?} failed to parse: {}", args, e),
+        }
+    }
+
+    /// Connection group of every command that talks to a node over gRPC
+    fn node_conn(command: &Commands) -> NodeConnArgs {
+        match command {
+            Commands::Deploy(a) => a.node.clone(),
+            Commands::Propose(a) => a.node.clone(),
+            Commands::DeployAndWait(a) => a.node.clone(),
+            Commands::WaitForDeploy(a) => a.node.clone(),
+            Commands::GetData(a) => a.node.conn(),
+            Commands::DataAtName(a) => a.node.conn(),
+            Commands::IsFinalized(a) => a.node.conn(),
+            Commands::ExploratoryDeploy(a) | Commands::EstimateCost(a) => a.node.conn(),
+            Commands::ShowMainChain(a) => a.node.conn(),
+            Commands::GetBlocksByHeight(a) => a.node.conn(),
+            Commands::Transfer(a) => a.node.clone(),
+            Commands::BatchTransfer(a) => a.node.clone(),
+            Commands::LoadTest(a) => a.node.clone(),
+            Commands::CreateTestWallets(a) => a.node.clone(),
+            Commands::LatencyProbe(a) => a.node.clone(),
+            Commands::BondValidator(a) => a.node.clone(),
+            Commands::AddStake(a) => a.node.clone(),
+            Commands::UnbondValidator(a) => a.node.clone(),
+            Commands::Registry(a) => match &a.action {
+                RegistryAction::InsertSigned(a) => a.node.clone(),
+                RegistryAction::Lookup(a) => a.node.conn(),
+            },
+            Commands::GetDeploy(a) => a.node.conn(),
+            Commands::BumpDeploy(a) => a.node.clone(),
+            Commands::FollowBlock(a) => a.node.conn(),
+            _ => panic!("command has no node connection flags"),
+        }
+    }
+
+    /// Commands that deploy or propose, connecting to validator1 by default
+    const NODE_COMMANDS: &[&[&str]] = &[
+        &["deploy", "-f", "x.rho"],
+        &["propose"],
+        &["deploy-and-wait", "-f", "x.rho"],
+        &["wait-for-deploy", "abc"],
+        &["transfer", "-t", "1111abc", "-a", "5"],
+        &["batch-transfer", "-f", "pay.csv"],
+        &["load-test", "--to-address", "1111abc"],
+        &["create-test-wallets", "-n", "3", "-o", "w.json"],
+        &["latency-probe"],
+        &[
+            "bond-validator",
+            "--stake",
+            "10",
+            "--private-key",
+            DEV_PRIVATE_KEY,
+        ],
+        &[
+            "add-stake",
+            "--amount",
+            "10",
+            "--private-key",
+            DEV_PRIVATE_KEY,
+        ],
+        &["unbond-validator", "--private-key", DEV_PRIVATE_KEY],
+        &["bump-deploy", "-d", "abc", "--phlo-price", "2"],
+        &["registry", "insert-signed", "-f", "c.rho"],
+    ];
+
+    /// Commands with the node connection group that only read, connecting to
+    /// the observer by default
+    const READ_NODE_COMMANDS: &[&[&str]] = &[
+        &["get-data", "-d", "abc", "-b", "def"],
+        &["is-finalized", "-b", "def"],
+        &["data-at-name", "-n", "results"],
+        &["exploratory-deploy", "-f", "x.rho"],
+        &["estimate-cost", "-f", "x.rho"],
+        &["show-main-chain"],
+        &["get-blocks-by-height", "-s", "1", "-e", "2"],
+        &["get-deploy", "-d", "abc"],
+        &["follow-block", "abc"],
+        &["registry", "lookup", "rho:vault:system"],
+    ];
+
+    /// Read commands with their own port flags
+    const PORT_COMMANDS: &[&[&str]] = &[
+        &["status"],
+        &["bonds"],
+        &["active-validators"],
+        &["metrics"],
+        &["last-finalized-block"],
+        &["blocks"],
+        &["bond-status", "-k", "04aa"],
+        &["wallet-balance", "-a", "1111abc"],
+        &["validator-status", "-k", "04aa"],
+        &["epoch-info"],
+        &["epoch-rewards"],
+        &["network-consensus"],
+        &["pos-query", "getMinimumBond"],
+        &["block-transfers", "abc"],
+        &["export-chain", "--start", "1", "--end", "2"],
+        &["chain-stats", "--last", "10"],
+        &["equivocation-check", "--live"],
+        &["validator-chain", "04aa", "--last", "10"],
+        &["watch-events"],
+        &[
+            "watch-wallet",
+            "-w",
+            "1111AtahZeefej4tvVR6ti9TJtv8yxLebvyDoX4tKnbX9DZGQCExL",
+        ],
+        &[
+            "funder-audit",
+            "-a",
+            "1111AtahZeefej4tvVR6ti9TJtv8yxLebvyDoX4tKnbX9DZGQCExL",
+        ],
+        &["dag"],
+        &["dashboard"],
+    ];
+
+    /// gRPC, HTTP and WebSocket ports a command connects to, where it has them
+    fn ports_of(command: &Commands) -> (Option<u16>, Option<u16>, Option<u16>) {
+        match command {
+            Commands::Status(a) | Commands::Metrics(a) | Commands::LastFinalizedBlock(a) => {
+                (None, Some(a.port), None)
+            }
+            Commands::Bonds(a) | Commands::ActiveValidators(a) => (None, Some(a.http.port), None),
+            Commands::Blocks(a) => (None, Some(a.port), None),
+            Commands::BondStatus(a) => (None, Some(a.port), None),
+            Commands::WalletBalance(a) => (Some(a.port), None, None),
+            Commands::ValidatorStatus(a) => (Some(a.port), Some(a.http_port), None),
+            Commands::EpochInfo(a) | Commands::EpochRewards(a) | Commands::NetworkConsensus(a) => {
+                (Some(a.port), Some(a.http_port), None)
+            }
+            Commands::PosQuery(a) => (Some(a.port), Some(a.http_port), None),
+            Commands::BlockTransfers(a) => (None, Some(a.port), None),
+            Commands::ExportChain(a) => (None, Some(a.port), None),
+            Commands::ChainStats(a) => (None, Some(a.port), None),
+            Commands::EquivocationCheck(a) => (None, Some(a.port), Some(a.ws_port)),
+            Commands::ValidatorChain(a) => (None, Some(a.port), None),
+            // Its HTTP port is only used for the event stream
+            Commands::WatchEvents(a) => (None, None, Some(a.http_port)),
+            Commands::WatchWallet(a) => (None, Some(a.http_port), Some(a.ws_port)),
+            Commands::FunderAudit(a) => (None, Some(a.http_port), Some(a.ws_port)),
+            Commands::Dag(a) => (None, Some(a.http_port), Some(a.ws_port)),
+            Commands::Dashboard(a) => (None, Some(a.http_port), Some(a.ws_port)),
+            Commands::FollowBlock(a) => (
+                Some(a.node.grpc_port),
+                Some(a.node.http_port),
+                Some(a.ws_port),
+            ),
+            other => {
+                let node = node_conn(other);
+                (Some(node.grpc_port), Some(node.http_port), None)
+            }
+        }
+    }
+
+    /// Ports of `node`, for the kinds of port `actual` has
+    fn expected_ports(
+        actual: (Option<u16>, Option<u16>, Option<u16>),
+        node: ports::ShardNode,
+        ws_node: ports::ShardNode,
+    ) -> (Option<u16>, Option<u16>, Option<u16>) {
+        (
+            actual.0.map(|_| node.grpc_internal),
+            actual.1.map(|_| node.http),
+            actual.2.map(|_| ws_node.http),
+        )
+    }
+
+    #[test]
+    fn test_cli_has_no_conflicting_flags() {
+        // Panics on duplicate ids, shorts or longs within any subcommand
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_node_connection_defaults_match_across_commands() {
+        for args in NODE_COMMANDS {
+            assert_eq!(
+                node_conn(&parse(args)),
+                NodeConnArgs::default(),
+                "{:?}",
+                args
+            );
+        }
+        for args in READ_NODE_COMMANDS {
+            assert_eq!(
+                node_conn(&parse(args)),
+                ReadNodeConnArgs::default().conn(),
+                "{:?}",
+                args
+            );
+        }
+    }
+
+    #[test]
+    fn test_port_defaults_follow_matrix() {
+        for args in NODE_COMMANDS {
+            let actual = ports_of(&parse(args));
+            let expected = expected_ports(actual, ports::VALIDATOR1, ports::BOOTSTRAP);
+            assert_eq!(actual, expected, "{:?}", args);
+        }
+        for args in READ_NODE_COMMANDS.iter().chain(PORT_COMMANDS) {
+            let actual = ports_of(&parse(args));
+            let expected = expected_ports(actual, ports::OBSERVER, ports::BOOTSTRAP);
+            assert_eq!(actual, expected, "{:?}", args);
+        }
+    }
+
+    #[test]
+    fn test_node_role_sets_ports_from_matrix() {
+        let all = NODE_COMMANDS
+            .iter()
+            .chain(READ_NODE_COMMANDS)
+            .chain(PORT_COMMANDS);
+        for node in ports::STANDARD_SHARD {
+            for args in all.clone() {
+                let argv: Vec<&str> = args
+                    .iter()
+                    .copied()
+                    .chain(["--node-role", node.role.name()])
+                    .collect();
+                let actual = ports_of(&parse(&argv));
+                assert_eq!(actual, expected_ports(actual, node, node), "{:?}", argv);
+            }
+        }
+
+        // A port given on the command line beats the role
+        let argv = [
+            "deploy",
+            "-f",
+            "x.rho",
+            "--node-role",
+            "observer",
+            "-p",
+            "1234",
+        ];
+        let node = node_conn(&parse(&argv));
+        assert_eq!(node.node_role, Some(NodeRole::Observer));
+        assert_eq!(
+            (node.grpc_port, node.http_port),
+            (1234, ports::OBSERVER.http)
+        );
+        let argv = ["node_cli", "status", "--node-role", "validator4"];
+        assert!(Cli::try_parse_from(argv).is_err());
+    }
+
+    #[test]
+    fn test_pos_http_ports_match_matrix() {
+        let mut expected: Vec<(&str, String, String)> = Vec::new();
+        for node in ports::STANDARD_SHARD {
+            let http = node.http.to_string();
+            expected.push(("node_role", node.role.name().to_string(), http.clone()));
+        }
+        for node in ports::STANDARD_SHARD {
+            for grpc in [node.grpc_external, node.grpc_internal] {
+                expected.push(("port", grpc.to_string(), node.http.to_string()));
+            }
+        }
+        let actual: Vec<(&str, String, String)> = POS_HTTP_PORTS
+            .iter()
+            .map(|&(arg, value, http)| {
+                (arg, value.to_string(), http.unwrap_or_default().to_string())
+            })
+            .collect();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_pos_http_port_follows_grpc_port() {
+        let commands: [&[&str]; 5] = [
+            &["validator-status", "-k", "04aa"],
+            &["epoch-info"],
+            &["epoch-rewards"],
+            &["network-consensus"],
+            &["pos-query", "getMinimumBond"],
+        ];
+        for args in commands {
+            for node in ports::STANDARD_SHARD {
+                for grpc in [node.grpc_external, node.grpc_internal] {
+                    let grpc = grpc.to_string();
+                    let argv: Vec<&str> = args.iter().copied().chain(["-p", &grpc]).collect();
+                    let actual = ports_of(&parse(&argv));
+                    assert_eq!(actual.1, Some(node.http), "{:?}", argv);
+                }
+            }
+
+            // Off the standard shard, and with both ports given, nothing is derived
+            let argv: Vec<&str> = args.iter().copied().chain(["-p", "50051"]).collect();
+            assert_eq!(ports_of(&parse(&argv)).1, Some(OBSERVER_HTTP_PORT));
+            let argv: Vec<&str> = args
+                .iter()
+                .copied()
+                .chain(["-p", "40412", "--http-port", "8080"])
+                .collect();
+            assert_eq!(ports_of(&parse(&argv)).1, Some(8080));
+        }
+
+        // The role still wins over a port it did not set
+        let argv = ["epoch-info", "--node-role", "validator2", "-p", "40412"];
+        assert_eq!(ports_of(&parse(&argv)).1, Some(ports::VALIDATOR2.http));
+    }
+
+    #[test]
+    fn test_node_connection_flags_and_aliases() {
+        for args in NODE_COMMANDS.iter().chain(READ_NODE_COMMANDS) {
+            for port_flag in ["-p", "--port", "--grpc-port"] {
+                let argv: Vec<&str> = args
+                    .iter()
+                    .copied()
+                    .chain(["-H", "node1", port_flag, "1234", "--http-port", "5678"])
+                    .collect();
+                let node = node_conn(&parse(&argv));
+                assert_eq!(node.host, "node1", "{:?}", argv);
+                assert_eq!(node.grpc_port, 1234, "{:?}", argv);
+                assert_eq!(node.http_port, 5678, "{:?}", argv);
+            }
+        }
+    }
+
+    #[test]
+    fn test_signing_key_defaults_to_dev_key() {
+        match parse(&["deploy", "-f", "x.rho"]) {
+            Commands::Deploy(a) => assert_eq!(a.signing.private_key, DEV_PRIVATE_KEY),
+            _ => unreachable!(),
+        }
+        match parse(&["propose", "-k", "ab12"]) {
+            Commands::Propose(a) => assert_eq!(a.signing.private_key, "ab12"),
+            _ => unreachable!(),
+        }
+        match parse(&[
+            "transfer",
+            "-t",
+            "1111abc",
+            "-a",
+            "5",
+            "--private-key",
+            "ab12",
+        ]) {
+            Commands::Transfer(a) => assert_eq!(a.signing.private_key, "ab12"),
+            _ => unreachable!(),
+        }
+        // Staking commands have no default key
+        assert!(Cli::try_parse_from(["node_cli", "bond-validator", "--stake", "10"]).is_err());
+        assert!(Cli::try_parse_from(["node_cli", "unbond-validator"]).is_err());
+    }
+
+    #[test]
+    fn test_wait_and_observer_defaults_match_across_commands() {
+        let groups = |command: Commands| match command {
+            Commands::Transfer(a) => (a.wait, a.observer),
+            Commands::BondValidator(a) => (a.wait, a.observer),
+            Commands::AddStake(a) => (a.wait, a.observer),
+            Commands::UnbondValidator(a) => (a.wait, a.observer),
+            _ => unreachable!(),
+        };
+        let staking: &[&[&str]] = &[
+            &["transfer", "-t", "1111abc", "-a", "5"],
+            &[
+                "bond-validator",
+                "--stake",
+                "10",
+                "--private-key",
+                DEV_PRIVATE_KEY,
+            ],
+            &[
+                "add-stake",
+                "--amount",
+                "10",
+                "--private-key",
+                DEV_PRIVATE_KEY,
+            ],
+            &["unbond-validator", "--private-key", DEV_PRIVATE_KEY],
+        ];
+        for args in staking {
+            let (wait, observer) = groups(parse(args));
+            assert_eq!(wait.max_wait, Duration::from_secs(300), "{:?}", args);
+            assert_eq!(wait.check_interval, Duration::from_secs(5), "{:?}", args);
+            assert_eq!(wait.poll, PollArgs::default(), "{:?}", args);
+            assert_eq!(observer.observer_host, None, "{:?}", args);
+            assert_eq!(observer.grpc_port(), OBSERVER_GRPC_PORT, "{:?}", args);
+            assert_eq!(
+                observer.observer_http_port, OBSERVER_HTTP_PORT,
+                "{:?}",
+                args
+            );
+        }
+    }
+
+    #[test]
+    fn test_registry_insert_signed_args() {
+        match parse(&["registry", "insert-signed", "-f", "c.rho"]) {
+            Commands::Registry(RegistryArgs {
+                action: RegistryAction::InsertSigned(a),
+            }) => {
+                assert_eq!(a.file, PathBuf::from("c.rho"));
+                assert_eq!(a.name, "contract");
+                assert_eq!(a.contract_version, 0);
+                assert_eq!(a.signing.private_key, DEV_PRIVATE_KEY);
+                assert!(!a.dry_run);
+            }
+            _ => unreachable!(),
+        }
+        let argv = [
+            "registry",
+            "insert-signed",
+            "-f",
+            "c.rho",
+            "--name",
+            "counter",
+            "--contract-version",
+            "4",
+            "--dry-run",
+        ];
+        match parse(&argv) {
+            Commands::Registry(RegistryArgs {
+                action: RegistryAction::InsertSigned(a),
+            }) => {
+                assert_eq!(a.name, "counter");
+                assert_eq!(a.contract_version, 4);
+                assert!(a.dry_run);
+            }
+            _ => unreachable!(),
+        }
+        assert!(Cli::try_parse_from(["node_cli", "registry", "insert-signed"]).is_err());
+    }
+
+    #[test]
+    fn test_registry_lookup_args() {
+        match parse(&["registry", "lookup", "rho:id:abc"]) {
+            Commands::Registry(RegistryArgs {
+                action: RegistryAction::Lookup(a),
+            }) => {
+                assert_eq!(a.uri, "rho:id:abc");
+                assert_eq!(a.block_hash, None);
+                assert_eq!(a.expect_version, None);
+            }
+            _ => unreachable!(),
+        }
+        let argv = [
+            "registry",
+            "lookup",
+            "rho:id:abc",
+            "-b",
+            "a47bdb40",
+            "--expect-version",
+            "3",
+        ];
+        match parse(&argv) {
+            Commands::Registry(RegistryArgs {
+                action: RegistryAction::Lookup(a),
+            }) => {
+                assert_eq!(a.block_hash.as_deref(), Some("a47bdb40"));
+                assert_eq!(a.expect_version, Some(3));
+            }
+            _ => unreachable!(),
+        }
+        assert!(Cli::try_parse_from(["node_cli", "registry", "lookup"]).is_err());
+    }
+
+    #[test]
+    fn test_transfer_template_file() {
+        match parse(&["transfer", "-t", "1111abc", "-a", "5"]) {
+            Commands::Transfer(a) => assert_eq!(a.template_file, None),
+            _ => unreachable!(),
+        }
+        let argv = [
+            "transfer",
+            "-t",
+            "1111abc",
+            "-a",
+            "5",
+            "--template-file",
+            "my.rho",
+        ];
+        match parse(&argv) {
+            Commands::Transfer(a) => assert_eq!(a.template_file, Some(PathBuf::from("my.rho"))),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_transfer_amounts() {
+        let amount = |extra: &[&str]| {
+            let mut argv = vec!["transfer", "-t", "1111abc"];
+            argv.extend_from_slice(extra);
+            match parse(&argv) {
+                Commands::Transfer(a) => a.transfer_amount().dust(),
+                _ => unreachable!(),
+            }
+        };
+        assert_eq!(amount(&["-a", "5"]), 500_000_000);
+        assert_eq!(amount(&["-a", "0.25"]), 25_000_000);
+        assert_eq!(amount(&["--amount-dust", "7"]), 7);
+
+        let fails = |extra: &[&str]| {
+            let mut argv = vec!["node_cli", "transfer", "-t", "1111abc"];
+            argv.extend_from_slice(extra);
+            Cli::try_parse_from(argv).is_err()
+        };
+        assert!(fails(&[]));
+        assert!(fails(&["-a", "1", "--amount-dust", "7"]));
+        assert!(fails(&["-a", "1.999999999"]));
+        assert!(fails(&["-a", "184467440738"]));
+
+        match parse(&["load-test", "--to-address", "1111abc", "--amount", "0.5"]) {
+            Commands::LoadTest(a) => assert_eq!(a.transfer_amount().dust(), 50_000_000),
+            _ => unreachable!(),
+        }
+        match parse(&["load-test", "--to-address", "1111abc", "--amount-dust", "9"]) {
+            Commands::LoadTest(a) => assert_eq!(a.transfer_amount().dust(), 9),
+            _ => unreachable!(),
+        }
+        match parse(&["load-test", "--to-address", "1111abc"]) {
+            Commands::LoadTest(a) => assert_eq!(a.transfer_amount().dust(), 100_000_000),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_poll_args() {
+        match parse(&[
+            "deploy-and-wait",
+            "-f",
+            "x.rho",
+            "--poll-initial",
+            "500ms",
+            "--poll-max",
+            "10",
+        ]) {
+            Commands::DeployAndWait(a) => {
+                let backoff = a.poll.backoff(a.check_interval);
+                assert_eq!(backoff.initial, Duration::from_millis(500));
+                assert_eq!(backoff.max, Duration::from_secs(10));
+            }
+            _ => unreachable!(),
+        }
+        match parse(&["transfer", "-t", "1111abc", "-a", "5", "--poll-max", "1m"]) {
+            Commands::Transfer(a) => {
+                let backoff = a.wait.poll.backoff(a.wait.check_interval);
+                assert_eq!(backoff.initial, Duration::from_secs(5));
+                assert_eq!(backoff.max, Duration::from_secs(60));
+            }
+            _ => unreachable!(),
+        }
+        match parse(&["load-test", "--to-address", "1111abc"]) {
+            Commands::LoadTest(a) => {
+                assert_eq!(a.poll.backoff(a.check_interval).max, Duration::from_secs(4))
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_no_wait_finalization_args() {
+        match parse(&["deploy-and-wait", "-f", "x.rho", "--no-wait-finalization"]) {
+            Commands::DeployAndWait(a) => assert!(a.no_wait_finalization),
+            _ => unreachable!(),
+        }
+        for flag in [
+            "--confirm-depth=2",
+            "--finalization-timeout=5m",
+            "--verify-on=obs:40453",
+        ] {
+            let argv = [
+                "node_cli",
+                "deploy-and-wait",
+                "-f",
+                "x.rho",
+                "--no-wait-finalization",
+                flag,
+            ];
+            assert!(Cli::try_parse_from(argv).is_err(), "{}", flag);
+        }
+
+        let bond = |extra: &[&str]| {
+            let base = ["bond-validator", "-s", "10", "--private-key", "aa"];
+            parse(&[base.as_slice(), extra].concat())
+        };
+        match bond(&[]) {
+            Commands::BondValidator(a) => {
+                assert_eq!(a.finalization_timeout, None);
+                assert!(!a.no_wait_finalization);
+            }
+            _ => unreachable!(),
+        }
+        match bond(&["--finalization-timeout", "10m"]) {
+            Commands::BondValidator(a) => {
+                assert_eq!(a.finalization_timeout, Some(Duration::from_secs(600)))
+            }
+            _ => unreachable!(),
+        }
+        match bond(&["--no-wait-finalization"]) {
+            Commands::BondValidator(a) => assert!(a.no_wait_finalization),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_wait_for_deploy_args() {
+        match parse(&[
+            "wait-for-deploy",
+            "3045ab",
+            "-m",
+            "3",
+            "--check-interval",
+            "2s",
+        ]) {
+            Commands::WaitForDeploy(a) => {
+                assert_eq!(a.deploy_id, "3045ab");
+                assert_eq!(a.max_wait(), Duration::from_secs(6));
+            }
+            _ => unreachable!(),
+        }
+        match parse(&["wait-for-deploy", "3045ab"]) {
+            Commands::WaitForDeploy(a) => assert_eq!(a.max_wait(), Duration::from_secs(300)),
+            _ => unreachable!(),
+        }
+        assert!(Cli::try_parse_from(["node_cli", "wait-for-deploy"]).is_err());
+    }
+
+    #[test]
+    fn test_batch_transfer_args() {
+        match parse(&["batch-transfer", "-f", "pay.csv"]) {
+            Commands::BatchTransfer(a) => {
+                assert_eq!(a.file, PathBuf::from("pay.csv"));
+                assert!(!a.sequential && !a.dry_run && !a.force);
+                assert_eq!(a.signing.private_key, DEV_PRIVATE_KEY);
+            }
+            _ => unreachable!(),
+        }
+        match parse(&[
+            "batch-transfer",
+            "-f",
+            "pay.csv",
+            "--sequential",
+            "--dry-run",
+        ]) {
+            Commands::BatchTransfer(a) => assert!(a.sequential && a.dry_run),
+            _ => unreachable!(),
+        }
+        assert!(Cli::try_parse_from(["node_cli", "batch-transfer"]).is_err());
+    }
+
+    #[test]
+    fn test_observer_flags_and_readonly_port_alias() {
+        match parse(&[
+            "transfer",
+            "-t",
+            "1111abc",
+            "-a",
+            "5",
+            "--observer-host",
+            "obs",
+            "--observer-port",
+            "50000",
+        ]) {
+            Commands::Transfer(a) => {
+                assert_eq!(a.observer.host(&a.node), "obs");
+                assert_eq!(a.observer.grpc_port(), 50000);
+                assert!(!a.force);
+            }
+            _ => unreachable!(),
+        }
+        match parse(&[
+            "load-test",
+            "--to-address",
+            "1111abc",
+            "--readonly-port",
+            "40462",
+        ]) {
+            Commands::LoadTest(a) => {
+                assert_eq!(a.observer.host(&a.node), DEFAULT_HOST);
+                assert_eq!(a.observer.grpc_port(), 40462);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_term_source_flags() {
+        use crate::payload::Source;
+
+        let sources = |argv: &[&str]| match parse(argv) {
+            Commands::Deploy(a) => a.source.source(),
+            Commands::DeployAndWait(a) => a.source.source(),
+            Commands::ExploratoryDeploy(a) | Commands::EstimateCost(a) => a.source.source(),
+            _ => unreachable!(),
+        };
+        for command in [
+            "deploy",
+            "deploy-and-wait",
+            "exploratory-deploy",
+            "estimate-cost",
+        ] {
+            assert_eq!(
+                sources(&[command, "-f", "x.rho"]),
+                Source::File(PathBuf::from("x.rho"))
+            );
+            assert_eq!(sources(&[command, "--file", "-"]), Source::Stdin);
+            assert_eq!(
+                sources(&[command, "--code", "new r in { r!(1 + 1) }"]),
+                Source::Inline("new r in { r!(1 + 1) }".to_string())
+            );
+
+            // Exactly one of --file and --code
+            for argv in [
+                vec!["node_cli", command],
+                vec!["node_cli", command, "-f", "x.rho", "--code", "Nil"],
+            ] {
+                assert!(Cli::try_parse_from(&argv).is_err(), "{:?}", argv);
+            }
+        }
+    }
+
+    #[test]
+    fn test_data_at_name_args() {
+        match parse(&["data-at-name", "--name", "results"]) {
+            Commands::DataAtName(a) => {
+                assert_eq!(
+                    (a.name.as_str(), a.unforgeable, a.depth),
+                    ("results", false, 1)
+                );
+                assert_eq!(a.block_hash, None);
+            }
+            _ => unreachable!(),
+        }
+        match parse(&["data-at-name", "-n", "c0ffee", "--unforgeable", "-b", "abc"]) {
+            Commands::DataAtName(a) => {
+                assert!(a.unforgeable);
+                assert_eq!(a.block_hash.as_deref(), Some("abc"));
+            }
+            _ => unreachable!(),
+        }
+        for argv in [
+            ["node_cli", "data-at-name", "-n", "x", "--depth", "0"].as_slice(),
+            &[
+                "node_cli",
+                "data-at-name",
+                "-n",
+                "x",
+                "--depth",
+                "5",
+                "-b",
+                "abc",
+            ],
+        ] {
+            assert!(Cli::try_parse_from(argv).is_err(), "{:?}", argv);
+        }
+    }
+
+    #[test]
+    fn test_pos_query_args() {
+        match parse(&["pos-query", "getMinimumBond"]) {
+            Commands::PosQuery(a) => {
+                assert_eq!(a.method, "getMinimumBond");
+                assert!(a.args.is_empty());
+                assert_eq!((a.block_hash, a.raw), (None, false));
+            }
+            _ => unreachable!(),
+        }
+        match parse(&[
+            "pos-query",
+            "getRewards",
+            "-5",
+            "04aa",
+            "-b",
+            "abc",
+            "--raw",
+        ]) {
+            Commands::PosQuery(a) => {
+                assert_eq!(a.args, [PosArg::Int(-5), PosArg::Str("04aa".into())]);
+                assert_eq!(a.block_hash.as_deref(), Some("abc"));
+                assert!(a.raw);
+            }
+            _ => unreachable!(),
+        }
+        assert!(Cli::try_parse_from(["node_cli", "pos-query"]).is_err());
+    }
+
+    #[test]
+    fn test_network_health_limits() {
+        match parse(&["network-health"]) {
+            Commands::NetworkHealth(a) => {
+                assert_eq!(a.concurrency, DEFAULT_HEALTH_CONCURRENCY);
+                assert_eq!(a.timeout, Duration::from_secs(5));
+            }
+            _ => unreachable!(),
+        }
+        match parse(&[
+            "network-health",
+            "--concurrency",
+            "32",
+            "--timeout-secs",
+            "2",
+        ]) {
+            Commands::NetworkHealth(a) => {
+                assert_eq!(a.concurrency, 32);
+                assert_eq!(a.timeout, Duration::from_secs(2));
+            }
+            _ => unreachable!(),
+        }
+        assert!(Cli::try_parse_from(["node_cli", "network-health", "--concurrency", "0"]).is_err());
+        match parse(&[
+            "network-health",
+            "--output",
+            "json",
+            "--min-healthy",
+            "4",
+            "--min-healthy-pct",
+            "75",
+        ]) {
+            Commands::NetworkHealth(a) => {
+                assert!(a.output.is_json());
+                assert_eq!((a.min_healthy, a.min_healthy_pct), (Some(4), Some(75)));
+            }
+            _ => unreachable!(),
+        }
+        assert!(
+            Cli::try_parse_from(["node_cli", "network-health", "--min-healthy-pct", "101"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_wallet_balance_addresses() {
+        match parse(&[
+            "wallet-balance",
+            "-a",
+            "1111aa,1111bb",
+            "--address",
+            "1111cc",
+        ]) {
+            Commands::WalletBalance(a) => {
+                assert_eq!(a.addresses, ["1111aa", "1111bb", "1111cc"]);
+                assert_eq!(a.concurrency, crate::staking::DEFAULT_BALANCE_CONCURRENCY);
+            }
+            _ => unreachable!(),
+        }
+        match parse(&["wallet-balance", "--addresses-file", "vaults.txt"]) {
+            Commands::WalletBalance(a) => {
+                assert!(a.addresses.is_empty());
+                assert_eq!(a.addresses_file, Some(PathBuf::from("vaults.txt")));
+            }
+            _ => unreachable!(),
+        }
+        assert!(Cli::try_parse_from(["node_cli", "wallet-balance"]).is_err());
+    }
+
+    #[test]
+    fn test_query_commands_take_an_optional_key() {
+        match parse(&["wallet-balance", "-a", "1111abc"]) {
+            Commands::WalletBalance(a) => assert_eq!(a.query_key.private_key, None),
+            _ => unreachable!(),
+        }
+        match parse(&["validator-status", "-k", "04aa", "--private-key", "ab12"]) {
+            Commands::ValidatorStatus(a) => {
+                assert_eq!(a.public_key, "04aa");
+                assert_eq!(a.query_key.private_key.as_deref(), Some("ab12"));
+            }
+            _ => unreachable!(),
+        }
+        match parse(&["network-consensus", "--private-key", "ab12"]) {
+            Commands::NetworkConsensus(a) => {
+                assert_eq!(a.query_key.private_key.as_deref(), Some("ab12"))
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_read_only_commands_default_to_observer() {
+        match parse(&["wallet-balance", "-a", "1111abc"]) {
+            Commands::WalletBalance(a) => assert_eq!(a.port, OBSERVER_GRPC_PORT),
+            _ => unreachable!(),
+        }
+        match parse(&["epoch-info"]) {
+            Commands::EpochInfo(a) => {
+                assert_eq!(
+                    (a.port, a.http_port),
+                    (OBSERVER_GRPC_PORT, OBSERVER_HTTP_PORT)
+                )
+            }
+            _ => unreachable!(),
+        }
+        match parse(&["validator-status", "-k", "04aa"]) {
+            Commands::ValidatorStatus(a) => {
+                assert_eq!(
+                    (a.port, a.http_port),
+                    (OBSERVER_GRPC_PORT, OBSERVER_HTTP_PORT)
+                )
+            }
+            _ => unreachable!(),
+        }
+        match parse(&["status"]) {
+            Commands::Status(a) => assert_eq!(a.port, OBSERVER_HTTP_PORT),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_verify_on_is_repeatable() {
+        let argv = [
+            "transfer",
+            "-t",
+            "1111abc",
+            "-a",
+            "5",
+            "--verify-on",
+            "obs1:40453",
+            "--verify-on",
+            "obs2:40403",
+            "--require-verification",
+        ];
+        match parse(&argv) {
+            Commands::Transfer(a) => {
+                let nodes: Vec<String> = a.verify.verify_on.iter().map(|t| t.to_string()).collect();
+                assert_eq!(nodes, ["obs1:40453", "obs2:40403"]);
+                assert!(a.verify.require_verification);
+            }
+            _ => unreachable!(),
+        }
+
+        let argv = [
+            "node_cli",
+            "deploy-and-wait",
+            "-f",
+            "x.rho",
+            "--verify-on",
+            "obs1",
+        ];
+        assert!(Cli::try_parse_from(argv).is_err());
+        let argv = [
+            "node_cli",
+            "deploy-and-wait",
+            "-f",
+            "x.rho",
+            "--require-verification",
+        ];
+        assert!(Cli::try_parse_from(argv).is_err());
+    }
+
+    #[test]
+    fn test_watch_wallet_targets() {
+        use crate::utils::CryptoUtils;
+        let secret_key = CryptoUtils::decode_private_key(DEV_PRIVATE_KEY).unwrap();
+        let key =
+            CryptoUtils::serialize_public_key(&CryptoUtils::derive_public_key(&secret_key), false);
+        let argv = [
+            "watch-wallet",
+            "-w",
+            &format!(
+                "{},1111AtahZeefej4tvVR6ti9TJtv8yxLebvyDoX4tKnbX9DZGQCExL",
+                key
+            ),
+            "--from-height",
+            "100",
+        ];
+        match parse(&argv) {
+            Commands::WatchWallet(a) => {
+                assert_eq!(a.watch.len(), 2);
+                assert_eq!(a.from_height, Some(100));
+            }
+            _ => unreachable!(),
+        }
+        assert!(Cli::try_parse_from(["node_cli", "watch-wallet"]).is_err());
+        assert!(Cli::try_parse_from(["node_cli", "watch-wallet", "-w", "nope"]).is_err());
+    }
+
+    #[test]
+    fn test_truncation_flags() {
+        use crate::truncation::Truncation;
+        let policy = |argv: &[&str]| {
+            let cli = Cli::try_parse_from(argv).unwrap();
+            Truncation::from_flags(cli.truncate, cli.full)
+        };
+        assert_eq!(policy(&["node_cli", "bonds"]), Truncation::PerField);
+        assert_eq!(policy(&["node_cli", "bonds", "--full"]), Truncation::Full);
+        assert_eq!(
+            policy(&["node_cli", "--truncate", "12", "show-main-chain"]),
+            Truncation::Width(12)
+        );
+        assert!(Cli::try_parse_from(["node_cli", "bonds", "--full", "--truncate", "8"]).is_err());
+    }
+
+    #[test]
+    fn test_print_config_flags() {
+        let cli = Cli::try_parse_from(["node_cli", "status", "--print-config"]).unwrap();
+        assert!(cli.print_config && !cli.print_config_only);
+        let cli = Cli::try_parse_from(["node_cli", "--print-config-only", "status"]).unwrap();
+        assert!(cli.print_config_only);
+
+        // Everything after `config effective` is the command line to resolve
+        match parse(&[
+            "config",
+            "effective",
+            "transfer",
+            "-t",
+            "1111abc",
+            "-a",
+            "5",
+            "--node-role",
+            "validator1",
+        ]) {
+            Commands::Config(ConfigArgs {
+                action: ConfigAction::Effective(a),
+            }) => {
+                assert_eq!(a.command[0], "transfer");
+                assert_eq!(a.command.len(), 7);
+            }
+            _ => unreachable!(),
+        }
+        assert!(Cli::try_parse_from(["node_cli", "config", "effective"]).is_err());
+    }
+
+    #[test]
+    fn test_funder_audit_flags() {
+        match parse(&[
+            "funder-audit",
+            "-a",
+            "1111AtahZeefej4tvVR6ti9TJtv8yxLebvyDoX4tKnbX9DZGQCExL",
+        ]) {
+            Commands::FunderAudit(a) => {
+                assert_eq!(a.interval, Duration::from_secs(30));
+                assert_eq!(a.tolerance, crate::funder_audit::DEFAULT_TOLERANCE);
+                assert_eq!(a.from_height, None);
+                assert!(!a.output.is_json());
+            }
+            _ => unreachable!(),
+        }
+        match parse(&[
+            "funder-audit",
+            "-a",
+            "1111AtahZeefej4tvVR6ti9TJtv8yxLebvyDoX4tKnbX9DZGQCExL",
+            "--interval",
+            "5m",
+            "--tolerance",
+            "0",
+            "--from-height",
+            "12",
+            "--json",
+        ]) {
+            Commands::FunderAudit(a) => {
+                assert_eq!(a.interval, Duration::from_secs(300));
+                assert_eq!(a.tolerance, 0);
+                assert_eq!(a.from_height, Some(12));
+                assert!(a.output.is_json());
+            }
+            _ => unreachable!(),
+        }
+        assert!(Cli::try_parse_from(["node_cli", "funder-audit"]).is_err());
+    }
+
+    #[test]
+    fn test_load_test_modes() {
+        use crate::load_batches::LoadMode;
+        match parse(&["load-test", "--to-address", "1111abc"]) {
+            Commands::LoadTest(a) => {
+                assert_eq!(a.mode, LoadMode::Sequential);
+                assert_eq!(a.batch_size, crate::load_batches::DEFAULT_BATCH_SIZE);
+            }
+            _ => unreachable!(),
+        }
+        match parse(&[
+            "load-test",
+            "--to-address",
+            "1111abc",
+            "--mode",
+            "propose-batches",
+            "--batch-size",
+            "25",
+        ]) {
+            Commands::LoadTest(a) => {
+                assert_eq!(a.mode, LoadMode::ProposeBatches);
+                assert_eq!(a.batch_size, 25);
+            }
+            _ => unreachable!(),
+        }
+        assert!(Cli::try_parse_from([
+            "node_cli",
+            "load-test",
+            "--to-address",
+            "1111abc",
+            "--mode",
+            "parallel"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_term_limit_flags() {
+        match parse(&["deploy", "-f", "x.rho"]) {
+            Commands::Deploy(a) => {
+                assert_eq!(a.term_limits.max_term_bytes, None);
+                assert!(!a.force);
+            }
+            _ => unreachable!(),
+        }
+        match parse(&[
+            "deploy-and-wait",
+            "-f",
+            "x.rho",
+            "--max-term-nodes",
+            "500",
+            "--force",
+        ]) {
+            Commands::DeployAndWait(a) => {
+                assert_eq!(a.term_limits.max_term_nodes, Some(500));
+                assert!(a.force);
+            }
+            _ => unreachable!(),
+        }
+        match parse(&["analyze", "big.rho", "--max-term-bytes", "1000", "--json"]) {
+            Commands::Analyze(a) => {
+                assert_eq!(a.file, PathBuf::from("big.rho"));
+                assert_eq!(a.term_limits.max_term_bytes, Some(1000));
+                assert!(a.output.is_json());
+            }
+            _ => unreachable!(),
+        }
+        assert!(Cli::try_parse_from(["node_cli", "analyze"]).is_err());
+    }
+
+    #[test]
+    fn test_create_test_wallets_flags() {
+        match parse(&["create-test-wallets", "-n", "50", "-o", "wallets"]) {
+            Commands::CreateTestWallets(a) => {
+                assert_eq!(a.count, Some(50));
+                assert_eq!(a.amount, 100);
+                assert_eq!(a.chunk_size, crate::test_wallets::DEFAULT_CHUNK_SIZE);
+                assert_eq!(a.signing.private_key, DEV_PRIVATE_KEY);
+                assert!(!a.resume && !a.allow_non_dev);
+            }
+            _ => unreachable!(),
+        }
+        match parse(&["create-test-wallets", "-o", "w.json", "--resume"]) {
+            Commands::CreateTestWallets(a) => assert_eq!(a.count, None),
+            _ => unreachable!(),
+        }
+        assert!(Cli::try_parse_from(["node_cli", "create-test-wallets", "-o", "w.json"]).is_err());
+        assert!(Cli::try_parse_from([
+            "node_cli",
+            "create-test-wallets",
+            "-n",
+            "3",
+            "-o",
+            "w.json",
+            "--chunk-size",
+            "0"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_transfer_guard_flags() {
+        match parse(&["transfer", "-t", "1111abc", "-a", "5"]) {
+            Commands::Transfer(a) => {
+                assert_eq!(a.max_transfer, None);
+                assert_eq!(a.balance_warn_percent, None);
+                assert!(!a.allow_large_transfer && !a.no_amount_checks);
+            }
+            _ => unreachable!(),
+        }
+        match parse(&[
+            "transfer",
+            "-t",
+            "1111abc",
+            "-a",
+            "5",
+            "--max-transfer",
+            "100",
+            "--balance-warn-percent",
+            "0",
+            "--allow-large-transfer",
+            "--no-amount-checks",
+        ]) {
+            Commands::Transfer(a) => {
+                assert_eq!(a.max_transfer, Some(100));
+                assert_eq!(a.balance_warn_percent, Some(0));
+                assert!(a.allow_large_transfer && a.no_amount_checks);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_no_hints_flag() {
+        match parse(&["get-deploy", "-d", "3045", "--no-hints"]) {
+            Commands::GetDeploy(a) => assert!(a.no_hints),
+            _ => unreachable!(),
+        }
+        match parse(&["is-finalized", "-b", "ab12"]) {
+            Commands::IsFinalized(a) => assert!(!a.no_hints),
+            _ => unreachable!(),
+        }
+        match parse(&["blocks", "-b", "ab12", "--no-hints"]) {
+            Commands::Blocks(a) => assert!(a.no_hints),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_confirm_depth() {
+        match parse(&[
+            "transfer",
+            "-t",
+            "1111abc",
+            "-a",
+            "5",
+            "--confirm-depth",
+            "3",
+        ]) {
+            Commands::Transfer(a) => assert_eq!(a.confirm_depth, Some(3)),
+            _ => unreachable!(),
+        }
+        match parse(&["deploy-and-wait", "-f", "x.rho"]) {
+            Commands::DeployAndWait(a) => assert_eq!(a.confirm_depth, None),
+            _ => unreachable!(),
+        }
+        match parse(&["is-finalized", "-b", "def", "--confirm-depth", "0"]) {
+            Commands::IsFinalized(a) => assert_eq!(a.confirm_depth, Some(0)),
+            _ => unreachable!(),
+        }
+        let argv = [
+            "node_cli",
+            "is-finalized",
+            "-b",
+            "def",
+            "--confirm-depth",
+            "-1",
+        ];
+        assert!(Cli::try_parse_from(argv).is_err());
+    }
+
+    #[test]
+    fn test_deploy_language_flags() {
+        match parse(&["deploy", "-f", "x.rho"]) {
+            Commands::Deploy(a) => assert_eq!(a.language.resolve().unwrap(), "rholang"),
+            _ => unreachable!(),
+        }
+        match parse(&["deploy-and-wait", "-f", "m.wasm", "--language", "WASM"]) {
+            Commands::DeployAndWait(a) => assert_eq!(a.language.resolve().unwrap(), "wasm"),
+            _ => unreachable!(),
+        }
+        match parse(&["deploy", "-f", "x.bin", "--language", "custom"]) {
+            Commands::Deploy(a) => assert!(a.language.resolve().is_err()),
+            _ => unreachable!(),
+        }
+        let argv = [
+            "deploy",
+            "-f",
+            "x.bin",
+            "--language",
+            "custom",
+            "--language-unchecked",
+        ];
+        match parse(&argv) {
+            Commands::Deploy(a) => assert_eq!(a.language.resolve().unwrap(), "custom"),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_chain_stats_range_flags() {
+        match parse(&["chain-stats", "--last", "1000", "--json"]) {
+            Commands::ChainStats(a) => {
+                assert_eq!(a.last, Some(1000));
+                assert_eq!((a.start, a.end), (None, None));
+                assert!(a.output.is_json());
+                assert!(!a.system_errors_only);
+            }
+            _ => unreachable!(),
+        }
+        let argv = ["node_cli", "chain-stats", "--start", "1", "--last", "5"];
+        assert!(Cli::try_parse_from(argv).is_err());
+    }
+
+    #[test]
+    fn test_system_errors_only_flags() {
+        match parse(&["chain-stats", "--last", "10", "--system-errors-only"]) {
+            Commands::ChainStats(a) => assert!(a.system_errors_only),
+            _ => unreachable!(),
+        }
+        let argv = [
+            "export-chain",
+            "--start",
+            "0",
+            "--end",
+            "9",
+            "--system-errors-only",
+        ];
+        match parse(&argv) {
+            Commands::ExportChain(a) => assert!(a.system_errors_only),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_equivocation_check_modes() {
+        match parse(&["equivocation-check", "--live", "--window", "200"]) {
+            Commands::EquivocationCheck(a) => {
+                assert!(a.live);
+                assert_eq!(a.window, 200);
+            }
+            _ => unreachable!(),
+        }
+        let argv = ["node_cli", "equivocation-check", "--live", "--last", "50"];
+        assert!(Cli::try_parse_from(argv).is_err());
+    }
+
+    #[test]
+    fn test_allow_insecure_key_on_signing_commands() {
+        for args in NODE_COMMANDS.iter().filter(|args| args[0] != "propose") {
+            let argv: Vec<&str> = args
+                .iter()
+                .copied()
+                .chain(["--allow-insecure-key"])
+                .collect();
+            let argv = std::iter::once("node_cli").chain(argv);
+            assert!(Cli::try_parse_from(argv).is_ok(), "{:?}", args);
+        }
+        let argv = [
+            "node_cli",
+            "is-finalized",
+            "-b",
+            "def",
+            "--allow-insecure-key",
+        ];
+        assert!(Cli::try_parse_from(argv).is_err());
+    }
+
+    #[test]
+    fn test_webhook_flags() {
+        let url = "https://hooks.example/firefly";
+        match parse(&["transfer", "-t", "1111abc", "-a", "5", "--webhook-url", url]) {
+            Commands::Transfer(a) => assert_eq!(a.webhook.webhook_url.as_deref(), Some(url)),
+            _ => panic!("expected transfer"),
+        }
+        match parse(&["deploy-and-wait", "-f", "x.rho", "--webhook-url", url]) {
+            Commands::DeployAndWait(a) => {
+                assert_eq!(a.webhook.webhook_url.as_deref(), Some(url))
+            }
+            _ => panic!("expected deploy-and-wait"),
+        }
+        match parse(&["webhook-test"]) {
+            Commands::WebhookTest(a) => assert_eq!(a.webhook.webhook_url, None),
+            _ => panic!("expected webhook-test"),
+        }
+    }
+
+    #[test]
+    fn test_output_flag() {
+        match parse(&["status"]) {
+            Commands::Status(a) => assert!(!a.output.is_json()),
+            _ => panic!("expected status"),
+        }
+        match parse(&["lfb", "--output", "json"]) {
+            Commands::LastFinalizedBlock(a) => assert!(a.output.is_json()),
+            _ => panic!("expected last-finalized-block"),
+        }
+        match parse(&["active-validators", "--output", "json", "--with-balances"]) {
+            Commands::ActiveValidators(a) => assert!(a.http.output.is_json()),
+            _ => panic!("expected active-validators"),
+        }
+        match parse(&["wb", "-a", "1111abc", "--output", "json"]) {
+            Commands::WalletBalance(a) => assert!(a.output.is_json()),
+            _ => panic!("expected wallet-balance"),
+        }
+        let argv = ["node_cli", "metrics", "--output", "yaml"];
+        assert!(Cli::try_parse_from(argv).is_err());
+    }
+
+    #[test]
+    fn test_follow_block_flags() {
+        match parse(&["follow-block", "abc"]) {
+            Commands::FollowBlock(a) => {
+                assert_eq!(a.block_hash, "abc");
+                assert_eq!(a.until, Until::Finalized);
+                assert_eq!(a.timeout, Duration::from_secs(300));
+            }
+            _ => unreachable!(),
+        }
+        match parse(&["follow-block", "abc", "--until", "added", "--timeout", "60"]) {
+            Commands::FollowBlock(a) => {
+                assert_eq!(a.until, Until::Added);
+                assert_eq!(a.timeout, Duration::from_secs(60));
+            }
+            _ => unreachable!(),
+        }
+        let argv = ["node_cli", "follow-block", "abc", "--until", "included"];
+        assert!(Cli::try_parse_from(argv).is_err());
+    }
+
+    #[test]
+    fn test_dag_display_flags_are_optional() {
+        match parse(&["dag"]) {
+            Commands::Dag(a) => {
+                assert_eq!(a.show_deploys, None);
+                assert_eq!(a.relayout_interval, None);
+                assert!(!a.reset_ui);
+            }
+            _ => unreachable!(),
+        }
+        match parse(&["dag", "--show-deploys", "--color", "false", "--reset-ui"]) {
+            Commands::Dag(a) => {
+                assert_eq!(a.show_deploys, Some(true));
+                assert_eq!(a.color, Some(false));
+                assert!(a.reset_ui);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_validator_chain_args() {
+        match parse(&["validator-chain", "04aa", "--last", "200", "--json"]) {
+            Commands::ValidatorChain(a) => {
+                assert_eq!(a.validator, "04aa");
+                assert_eq!(a.last, Some(200));
+                assert!(a.output.is_json());
+            }
+            _ => unreachable!(),
+        }
+        assert!(Cli::try_parse_from(["node_cli", "validator-chain", "--last", "5"]).is_err());
+    }
+
+    #[test]
+    fn test_config_dir_is_global() {
+        let cli =
+            Cli::try_parse_from(["node_cli", "history", "list", "--config-dir", "/tmp/state"])
+                .unwrap();
+        assert_eq!(cli.config_dir, Some(PathBuf::from("/tmp/state")));
+        let cli =
+            Cli::try_parse_from(["node_cli", "--config-dir", "/tmp/state", "history", "list"])
+                .unwrap();
+        assert_eq!(cli.config_dir, Some(PathBuf::from("/tmp/state")));
+    }
+
+    #[test]
+    fn test_validator_status_explain_flags() {
+        match parse(&["validator-status", "-k", "04aa", "--explain"]) {
+            Commands::ValidatorStatus(a) => {
+                assert!(a.explain);
+                assert_eq!(a.recent_blocks, 50);
+            }
+            _ => unreachable!(),
+        }
+        match parse(&["validator-status", "-k", "04aa", "--recent-blocks", "200"]) {
+            Commands::ValidatorStatus(a) => {
+                assert!(!a.explain);
+                assert_eq!(a.recent_blocks, 200);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_pos_queries_batch_by_default() {
+        match parse(&["network-consensus"]) {
+            Commands::NetworkConsensus(a) => assert!(!a.pinned.no_batch),
+            _ => unreachable!(),
+        }
+        match parse(&["epoch-info", "--no-batch"]) {
+            Commands::EpochInfo(a) => assert!(a.pinned.no_batch),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_query_cache_flags_are_global() {
+        let cli = Cli::try_parse_from(["node_cli", "bonds"]).unwrap();
+        assert!(!cli.no_query_cache);
+        assert_eq!(cli.query_cache_size, crate::query_cache::DEFAULT_CAPACITY);
+        let cli = Cli::try_parse_from([
+            "node_cli",
+            "bonds",
+            "--no-query-cache",
+            "--query-cache-size",
+            "16",
+        ])
+        .unwrap();
+        assert!(cli.no_query_cache);
+        assert_eq!(cli.query_cache_size, 16);
+    }
+
+    #[test]
+    fn test_quiet_is_global() {
+        let cli = Cli::try_parse_from(["node_cli", "deploy", "-f", "contract.rho"]).unwrap();
+        assert!(!cli.quiet);
+        let cli =
+            Cli::try_parse_from(["node_cli", "deploy", "-f", "contract.rho", "--quiet"]).unwrap();
+        assert!(cli.quiet);
+    }
+
+    #[test]
+    fn test_assume_capabilities_is_global() {
+        let cli = Cli::try_parse_from(["node_cli", "chain-stats", "--last", "5"]).unwrap();
+        assert!(!cli.assume_capabilities);
+        let cli = Cli::try_parse_from([
+            "node_cli",
+            "--assume-capabilities",
+            "chain-stats",
+            "--last",
+            "5",
+        ])
+        .unwrap();
+        assert!(cli.assume_capabilities);
+    }
+
+    #[test]
+    fn test_bond_status_output_flags() {
+        match parse(&["bond-status", "-k", "04aa", "--raw"]) {
+            Commands::BondStatus(a) => assert!(a.raw && !a.output.is_json()),
+            _ => unreachable!(),
+        }
+        let argv = ["node_cli", "bond-status", "-k", "04aa", "--raw", "--json"];
+        assert!(Cli::try_parse_from(argv).is_err());
+        let argv = [
+            "node_cli",
+            "bond-status",
+            "-k",
+            "04aa",
+            "--raw",
+            "--output",
+            "json",
+        ];
+        assert!(Cli::try_parse_from(argv).is_err());
+    }
+
+    fn output_of(command: &Commands) -> &OutputArgs {
+        match command {
+            Commands::BondStatus(a) => &a.output,
+            Commands::Transfer(a) => &a.output,
+            Commands::Analyze(a) => &a.output,
+            Commands::FunderAudit(a) => &a.output,
+            Commands::ChainStats(a) => &a.output,
+            Commands::ValidatorChain(a) => &a.output,
+            Commands::Status(a) => &a.output,
+            _ => panic!("command has no output flags"),
+        }
+    }
+
+    #[test]
+    fn test_read_commands_take_a_query_key() {
+        const ADDRESS: &str = "1111AtahZeefej4tvVR6ti9TJtv8yxLebvyDoX4tKnbX9DZGQCExL";
+        let commands: &[&[&str]] = &[
+            &["get-deploy", "-d", "abc"],
+            &["blocks", "-b", "abc"],
+            &["chain-stats", "--last", "10"],
+            &["validator-chain", "04aa", "--last", "10"],
+            &["equivocation-check", "--live"],
+            &["dashboard"],
+            &["compare-state", "-b", "abc", "-n", "a:1,b:2"],
+            &["follow-block", "abc"],
+            &["funder-audit", "-a", ADDRESS],
+            &["watch-wallet", "-w", ADDRESS],
+            &["history", "show", "abc", "--check"],
+            &["pos-query", "getMinimumBond"],
+        ];
+        let key_of = |command: Commands| match command {
+            Commands::GetDeploy(a) => a.query_key,
+            Commands::Blocks(a) => a.query_key,
+            Commands::ChainStats(a) => a.query_key,
+            Commands::ValidatorChain(a) => a.query_key,
+            Commands::EquivocationCheck(a) => a.query_key,
+            Commands::Dashboard(a) => a.query_key,
+            Commands::CompareState(a) => a.query_key,
+            Commands::FollowBlock(a) => a.query_key,
+            Commands::FunderAudit(a) => a.query_key,
+            Commands::WatchWallet(a) => a.query_key,
+            Commands::PosQuery(a) => a.query_key,
+            Commands::History(HistoryArgs {
+                action: HistoryAction::Show { query_key, .. },
+            }) => query_key,
+            _ => panic!("command has no query key"),
+        };
+        for args in commands {
+            assert_eq!(key_of(parse(args)).private_key, None, "{:?}", args);
+            let argv = [*args, &["--private-key", "aa11"][..]].concat();
+            assert_eq!(
+                key_of(parse(&argv)).private_key.as_deref(),
+                Some("aa11"),
+                "{:?}",
+                argv
+            );
+        }
+    }
+
+    #[test]
+    fn test_json_is_an_alias_of_output_json() {
+        let commands: &[&[&str]] = &[
+            &["bond-status", "-k", "04aa"],
+            &["transfer", "-t", "1111", "-a", "1"],
+            &["analyze", "big.rho"],
+            &[
+                "funder-audit",
+                "-a",
+                "1111AtahZeefej4tvVR6ti9TJtv8yxLebvyDoX4tKnbX9DZGQCExL",
+            ],
+            &["chain-stats", "--last", "10"],
+            &["validator-chain", "04aa", "--last", "10"],
+            &["status"],
+        ];
+        for args in commands {
+            for flags in [&["--json"][..], &["--output", "json"][..]] {
+                let argv = [*args, flags].concat();
+                assert!(output_of(&parse(&argv)).is_json(), "{:?}", argv);
+            }
+            assert!(!output_of(&parse(args)).is_json(), "{:?}", args);
+            let argv = [*args, &["--json", "--output", "text"][..]].concat();
+            assert!(
+                Cli::try_parse_from(std::iter::once("node_cli").chain(argv.iter().copied()))
+                    .is_err(),
+                "{:?}",
+                argv
+            );
+        }
+        let help = Cli::command()
+            .find_subcommand_mut("chain-stats")
+            .unwrap()
+            .render_long_help()
+            .to_string();
+        assert!(help.contains("--output"));
+        assert!(!help.contains("--json"));
+    }
+
+    #[test]
+    fn test_block_list_granularity() {
+        use crate::block_groups::Granularity;
+        match parse(&["blocks", "-f", "table", "--granularity", "hour"]) {
+            Commands::Blocks(a) => {
+                assert_eq!(a.format, "table");
+                assert_eq!(a.granularity, Granularity::Hour);
+            }
+            _ => unreachable!(),
+        }
+        match parse(&["show-main-chain"]) {
+            Commands::ShowMainChain(a) => assert_eq!(a.granularity, Granularity::Day),
+            _ => unreachable!(),
+        }
+        let argv = ["node_cli", "blocks", "--format", "csv"];
+        assert!(Cli::try_parse_from(argv).is_err());
+        let argv = ["node_cli", "show-main-chain", "--granularity", "week"];
+        assert!(Cli::try_parse_from(argv).is_err());
+    }
+
+    #[test]
+    fn test_verify_pos_defaults_to_refuse() {
+        use crate::staking::PosVerifyMode;
+        match parse(&["add-stake", "-a", "10", "--private-key", "aa"]) {
+            Commands::AddStake(a) => {
+                assert_eq!(a.pos.verify_pos, PosVerifyMode::Refuse);
+                assert!(a.pos.topology.is_none());
+            }
+            _ => unreachable!(),
+        }
+        let argv = [
+            "bond-validator",
+            "-s",
+            "10",
+            "--private-key",
+            "aa",
+            "--verify-pos",
+            "warn",
+            "--topology",
+            "shard.json",
+        ];
+        match parse(&argv) {
+            Commands::BondValidator(a) => {
+                assert_eq!(a.pos.verify_pos, PosVerifyMode::Warn);
+                assert_eq!(a.pos.topology, Some(PathBuf::from("shard.json")));
+            }
+            _ => unreachable!(),
+        }
+        let argv = [
+            "node_cli",
+            "add-stake",
+            "-a",
+            "1",
+            "--private-key",
+            "aa",
+            "--verify-pos",
+            "no",
+        ];
+        assert!(Cli::try_parse_from(argv).is_err());
+    }
+
+    #[test]
+    fn test_bump_deploy_flags() {
+        match parse(&["bump-deploy", "-d", "3045abc", "--phlo-price", "5"]) {
+            Commands::BumpDeploy(a) => {
+                assert_eq!(a.deploy_id, "3045abc");
+                assert_eq!(a.phlo_price, 5);
+                assert!(a.file.is_none());
+                assert!(!a.bigger_phlo);
+            }
+            _ => unreachable!(),
+        }
+        match parse(&[
+            "bump-deploy",
+            "-d",
+            "3045abc",
+            "--phlo-price",
+            "3",
+            "-f",
+            "x.rho",
+            "-b",
+        ]) {
+            Commands::BumpDeploy(a) => {
+                assert_eq!(a.file.as_deref(), Some(std::path::Path::new("x.rho")));
+                assert!(a.bigger_phlo);
+            }
+            _ => unreachable!(),
+        }
+        // The replacement must outbid the original's price of 1
+        let argv = [
+            "node_cli",
+            "bump-deploy",
+            "-d",
+            "3045abc",
+            "--phlo-price",
+            "1",
+        ];
+        assert!(Cli::try_parse_from(argv).is_err());
+        let argv = ["node_cli", "bump-deploy", "-d", "3045abc"];
+        assert!(Cli::try_parse_from(argv).is_err());
+    }
+
+    #[test]
+    fn test_propose_suggest_flags() {
+        match parse(&["propose"]) {
+            Commands::Propose(a) => assert!(!a.suggest && a.keystore.is_none()),
+            _ => unreachable!(),
+        }
+        match parse(&["propose", "--suggest", "--keystore", "keys"]) {
+            Commands::Propose(a) => {
+                assert!(a.suggest);
+                assert_eq!(a.keystore, Some(PathBuf::from("keys")));
+            }
+            _ => unreachable!(),
+        }
+        let argv = ["node_cli", "propose", "--keystore", "keys"];
+        assert!(Cli::try_parse_from(argv).is_err());
+    }
+
+    #[test]
+    fn test_event_dump_flags() {
+        match parse(&["watch-events", "--dump-events", "events.ndjson"]) {
+            Commands::WatchEvents(a) => {
+                assert_eq!(a.dump.dump_events, Some(PathBuf::from("events.ndjson")));
+                assert!(a.dump.from_dump.is_none());
+                assert_eq!(a.dump.replay_speed, 1.0);
+            }
+            _ => unreachable!(),
+        }
+        match parse(&["dag", "--from-dump", "events.ndjson", "--replay-speed", "0"]) {
+            Commands::Dag(a) => {
+                assert_eq!(a.dump.from_dump, Some(PathBuf::from("events.ndjson")));
+                assert_eq!(a.dump.replay_speed, 0.0);
+            }
+            _ => unreachable!(),
+        }
+        for argv in [
+            &["watch-events", "--replay-speed", "2"][..],
+            &["watch-events", "--from-dump", "a", "--dump-events", "b"],
+            &["watch-events", "--from-dump", "a", "--replay-speed", "-1"],
+            &["dag", "--from-dump", "a", "--no-live"],
+        ] {
+            let argv = std::iter::once("node_cli").chain(argv.iter().copied());
+            assert!(Cli::try_parse_from(argv).is_err());
+        }
+    }
+
+    #[test]
+    fn test_watch_events_nodes() {
+        match parse(&["watch-events"]) {
+            Commands::WatchEvents(a) => {
+                assert!(a.nodes.is_empty() && a.topology.is_none());
+                assert_eq!(a.lag_threshold, Duration::from_secs(10));
+            }
+            _ => unreachable!(),
+        }
+        match parse(&[
+            "watch-events",
+            "--node",
+            "validator1=localhost:40413,observer=localhost:40453",
+            "-n",
+            "localhost:40423",
+            "--lag-threshold",
+            "30s",
+        ]) {
+            Commands::WatchEvents(a) => {
+                assert_eq!(a.nodes.len(), 3);
+                assert_eq!(a.lag_threshold, Duration::from_secs(30));
+            }
+            _ => unreachable!(),
+        }
+        let argv = [
+            "node_cli",
+            "watch-events",
+            "--node",
+            "localhost:40413",
+            "--from-dump",
+            "a",
+        ];
+        assert!(Cli::try_parse_from(argv).is_err());
+    }
+
+    #[test]
+    fn test_dashboard_flags() {
+        match parse(&["dashboard"]) {
+            Commands::Dashboard(a) => {
+                assert!(a.nodes.is_empty() && a.topology.is_none());
+                assert_eq!(a.refresh_interval, Duration::from_secs(5));
+                assert_eq!(a.stall_timeout, Duration::from_secs(60));
+            }
+            _ => unreachable!(),
+        }
+        match parse(&["dashboard", "-n", "v1=localhost:40413,localhost:40423"]) {
+            Commands::Dashboard(a) => assert_eq!(a.nodes.len(), 2),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_latency_probe_flags() {
+        match parse(&["latency-probe"]) {
+            Commands::LatencyProbe(a) => {
+                assert_eq!(a.count, 5);
+                assert!(!a.read_only && !a.propose);
+                assert!(a.baseline.is_none());
+            }
+            _ => unreachable!(),
+        }
+        match parse(&[
+            "latency-probe",
+            "--read-only",
+            "--count",
+            "20",
+            "--baseline",
+            "probe.json",
+        ]) {
+            Commands::LatencyProbe(a) => {
+                assert!(a.read_only);
+                assert_eq!(a.count, 20);
+                assert_eq!(a.baseline, Some(PathBuf::from("probe.json")));
+            }
+            _ => unreachable!(),
+        }
+        // Nothing to update without a baseline file
+        let argv = ["node_cli", "latency-probe", "--update-baseline"];
+        assert!(Cli::try_parse_from(argv).is_err());
+    }
+
+    /// Every interval and timeout flag, with the arguments its command needs
+    const DURATION_FLAGS: &[(&[&str], &[&str])] = &[
+        (&["deploy", "-f", "x.rho"], &["expires-in"]),
+        (
+            &["deploy-and-wait", "-f", "x.rho"],
+            &[
+                "max-wait",
+                "finalization-timeout",
+                "check-interval",
+                "expires-in",
+            ],
+        ),
+        (&["is-finalized", "-b", "abc"], &["retry-delay"]),
+        (
+            &["create-test-wallets", "-n", "3", "-o", "w.json"],
+            &["max-wait", "check-interval"],
+        ),
+        (&["wallet-balance", "-a", "1111abc"], &["max-age"]),
+        (
+            &["transfer", "-t", "1111abc", "-a", "5"],
+            &["max-wait", "check-interval", "expires-in"],
+        ),
+        (
+            &["bond-validator", "--stake", "10", "--private-key", "aa"],
+            &["max-wait", "check-interval", "expires-in"],
+        ),
+        (
+            &["add-stake", "-a", "10", "--private-key", "aa"],
+            &["max-wait", "check-interval", "expires-in"],
+        ),
+        (
+            &["load-test", "--to-address", "1111abc"],
+            &[
+                "interval",
+                "check-interval",
+                "inclusion-timeout",
+                "finalization-timeout",
+            ],
+        ),
+        (
+            &["latency-probe"],
+            &[
+                "interval",
+                "check-interval",
+                "inclusion-timeout",
+                "finalization-timeout",
+            ],
+        ),
+        (&["dag"], &["relayout-interval"]),
+        (&["dashboard"], &["refresh-interval", "stall-timeout"]),
+        (
+            &[
+                "funder-audit",
+                "-a",
+                "1111AtahZeefej4tvVR6ti9TJtv8yxLebvyDoX4tKnbX9DZGQCExL",
+            ],
+            &["interval"],
+        ),
+        (&["follow-block", "abc"], &["timeout", "check-interval"]),
+        (&["network-health"], &["timeout"]),
+        (&["watch-events"], &["lag-threshold"]),
+    ];
+
+    fn is_duration_flag(long: &str) -> bool {
+        ["interval", "timeout", "wait", "delay"]
+            .iter()
+            .any(|word| long.contains(word))
+            || matches!(long, "expires-in" | "max-age")
+    }
+
+    #[test]
+    fn test_qr_flags() {
+        for argv in [
+            &["generate-key-pair", "--qr"][..],
+            &["generate-vault-address", "--qr"],
+            &["wallet-balance", "-a", "1111abc", "--qr"],
+        ] {
+            let qr = match parse(argv) {
+                Commands::GenerateKeyPair(a) => a.qr,
+                Commands::GenerateVaultAddress(a) => a.qr,
+                Commands::WalletBalance(a) => a.qr,
+                _ => unreachable!(),
+            };
+            assert!(qr, "{:?}", argv);
+        }
+        match parse(&["generate-vault-address"]) {
+            Commands::GenerateVaultAddress(a) => assert!(!a.qr),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_duration_flags_use_shared_parser() {
+        // Every flag that looks like a duration is listed...
+        for sub in Cli::command().get_subcommands() {
+            for arg in sub.get_arguments() {
+                let Some(long) = arg.get_long().filter(|long| is_duration_flag(long)) else {
+                    continue;
+                };
+                let listed = DURATION_FLAGS
+                    .iter()
+                    .any(|(base, flags)| base[0] == sub.get_name() && flags.contains(&long));
+                assert!(
+                    listed,
+                    "{} --{} is not in DURATION_FLAGS",
+                    sub.get_name(),
+                    long
+                );
+            }
+        }
+        // ...and accepts duration strings, which a plain integer parser would not
+        for (base, flags) in DURATION_FLAGS {
+            for flag in *flags {
+                let flag = format!("--{}", flag);
+                let argv = |value: &str| {
+                    let mut argv = vec!["node_cli"];
+                    argv.extend_from_slice(base);
+                    argv.extend([flag.as_str(), value]);
+                    Cli::try_parse_from(argv)
+                };
+                for value in ["1m30s", "500ms", "90"] {
+                    assert!(argv(value).is_ok(), "{} {} {}", base[0], flag, value);
+                }
+                assert!(argv("5x").is_err(), "{} {} 5x", base[0], flag);
+            }
+        }
+    }
+
+    #[test]
+    fn test_bare_numbers_keep_their_unit() {
+        match parse(&["is-finalized", "-b", "abc", "-r", "7"]) {
+            Commands::IsFinalized(a) => assert_eq!(a.retry_delay, Duration::from_secs(7)),
+            _ => unreachable!(),
+        }
+        match parse(&["dag", "--relayout-interval", "250"]) {
+            Commands::Dag(a) => assert_eq!(a.relayout_interval, Some(Duration::from_millis(250))),
+            _ => unreachable!(),
+        }
+        match parse(&[
+            "deploy-and-wait",
+            "-f",
+            "x.rho",
+            "--max-wait",
+            "1h30m",
+            "--check-interval",
+            "500ms",
+        ]) {
+            Commands::DeployAndWait(a) => {
+                assert_eq!(a.max_wait, Duration::from_secs(5_400));
+                assert_eq!(a.check_interval, Duration::from_millis(500));
+                assert_eq!(a.finalization_timeout, Duration::from_secs(30));
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_aliases_parse_to_the_full_command() {
+        let same = |alias: &[&str], full: &[&str]| {
+            assert_eq!(
+                std::mem::discriminant(&parse(alias)),
+                std::mem::discriminant(&parse(full)),
+                "{:?}",
+                alias
+            );
+        };
+        same(
+            &["wb", "-a", "1111abc"],
+            &["wallet-balance", "-a", "1111abc"],
+        );
+        same(
+            &["xd", "-f", "x.rho"],
+            &["exploratory-deploy", "-f", "x.rho"],
+        );
+        same(&["lfb"], &["last-finalized-block"]);
+        same(&["dw", "-f", "x.rho"], &["deploy-and-wait", "-f", "x.rho"]);
+
+        // The table lists exactly the visible aliases
+        let cli = Cli::command();
+        let mut aliases: Vec<(String, String)> = cli
+            .get_subcommands()
+            .flat_map(|sub| {
+                sub.get_visible_aliases()
+                    .map(|alias| (alias.to_string(), sub.get_name().to_string()))
+            })
+            .collect();
+        aliases.sort();
+        let table: Vec<(String, String)> = COMMAND_ALIASES
+            .iter()
+            .map(|(alias, name)| (alias.to_string(), name.to_string()))
+            .collect();
+        assert_eq!(aliases, table);
+    }
+
+    #[test]
+    fn test_suggestions_for_typos() {
+        let names = |typed: &str| -> Vec<String> {
+            suggest_commands(typed, SUGGESTIONS_SHOWN)
+                .into_iter()
+                .map(|s| s.name)
+                .collect()
+        };
+        assert_eq!(names("walet-balance")[0], "wallet-balance");
+        assert_eq!(names("show-mainchain")[0], "show-main-chain");
+        assert_eq!(names("bonds-status")[0], "bond-status");
+        assert_eq!(names("stauts")[..2], ["stats", "status"]);
+        assert_eq!(names("exploratory")[0], "exploratory-deploy");
+        assert_eq!(
+            names("validator")[..2],
+            ["validator-chain", "validator-status"]
+        );
+        // Aliases count, whatever the case
+        assert_eq!(names("WB")[0], "wallet-balance");
+        assert_eq!(names("lbf")[0], "last-finalized-block");
+        assert_eq!(names("zzz").len(), SUGGESTIONS_SHOWN);
+
+        let top = &suggest_commands("walet-balance", 1)[0];
+        assert_eq!(top.distance, 1);
+        assert_eq!(top.aliases, vec!["wb".to_string()]);
+        assert_eq!(top.about, "Check wallet balance for a specific address");
+    }
+
+    #[test]
+    fn test_unrecognized_subcommand_message() {
+        let err = Cli::try_parse_from(["node_cli", "--quiet", "walet-balance", "-a", "x"])
+            .err()
+            .unwrap();
+        assert_eq!(
+            unrecognized_subcommand(&err).as_deref(),
+            Some("walet-balance")
+        );
+        let other = Cli::try_parse_from(["node_cli", "status", "--bogus"])
+            .err()
+            .unwrap();
+        assert_eq!(unrecognized_subcommand(&other), None);
+
+        let message = unrecognized_subcommand_message("walet-balance");
+        let lines: Vec<&str> = message.lines().collect();
+        assert_eq!(lines[0], "error: unrecognized subcommand 'walet-balance'");
+        assert_eq!(lines[2], "Did you mean one of these?");
+        assert!(lines[3].starts_with("  wallet-balance (wb)  Check wallet balance"));
+        assert_eq!(lines.len(), 3 + SUGGESTIONS_SHOWN + 2);
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("stats", "stats"), 0);
+        assert_eq!(edit_distance("stauts", "status"), 2);
+    }
+}
@@ -0,0 +1,216 @@
+//! On-disk wallet balance cache
+//!
+//! Stores the last balance seen for each node + address pair together with the
+//! block height it was computed at, so pollers can skip the exploratory deploy
+//! while the chain tip has not moved. The cache file is a versioned JSON
+//! document in the config directory, read and written through [`crate::store`].
+//! An unreadable or corrupt file is dropped and rebuilt.
+
+use crate::store::{self, Document, FileLock};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const CACHE_FILE_NAME: &str = "balance_cache.json";
+const CACHE_SCHEMA_VERSION: u32 = 1;
+const ENTRIES_FIELD: &str = "entries";
+
+/// A cached balance for one address on one node
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CachedBalance {
+    pub balance: String,
+    /// Chain tip height when the balance was computed
+    pub block_number: i64,
+    /// Unix timestamp in milliseconds when the balance was fetched
+    pub fetched_at: i64,
+}
+
+impl CachedBalance {
+    pub fn new(balance: String, block_number: i64, fetched_at: i64) -> Self {
+        Self {
+            balance,
+            block_number,
+            fetched_at,
+        }
+    }
+
+    /// True if the entry was fetched less than `max_age` before `now_ms`
+    pub fn within_max_age(&self, now_ms: i64, max_age: Duration) -> bool {
+        let age_ms = now_ms.saturating_sub(self.fetched_at);
+        age_ms >= 0 && (age_ms as u128) < max_age.as_millis()
+    }
+
+    /// True if the chain tip has not advanced past the cached height
+    pub fn current_at(&self, tip: i64) -> bool {
+        tip <= self.block_number
+    }
+
+    /// Whether the entry can be served given the current time and, if known, the chain tip
+    pub fn is_usable(&self, now_ms: i64, max_age: Duration, tip: Option<i64>) -> bool {
+        self.within_max_age(now_ms, max_age) || tip.is_some_and(|tip| self.current_at(tip))
+    }
+}
+
+/// Balance cache backed by a JSON file
+#[derive(Debug, Clone)]
+pub struct BalanceCache {
+    path: PathBuf,
+}
+
+impl BalanceCache {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Cache in the default config directory, if one can be resolved
+    pub fn open_default() -> Option<Self> {
+        crate::utils::config_dir().map(|dir| Self::new(dir.join(CACHE_FILE_NAME)))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Cache key for an address queried through a node
+    pub fn key(host: &str, port: u16, address: &str) -> String {
+        format!("{}:{}/{}", host, port, address)
+    }
+
+    /// Look up a cached balance
+    pub fn get(&self, key: &str) -> std::io::Result<Option<CachedBalance>> {
+        let _lock = FileLock::acquire(&self.path)?;
+        let document = match store::read_document(&self.path) {
+            Ok(document) => document,
+            Err(e) => {
+                tracing::warn!(path = %self.path.display(), "Corrupt balance cache, rebuilding: {}", e);
+                None
+            }
+        };
+        Ok(document
+            .map(|document| Self::entries(&document))
+            .unwrap_or_default()
+            .remove(key))
+    }
+
+    /// Store a balance, preserving entries written by other processes
+    pub fn put(&self, key: &str, entry: CachedBalance) -> std::io::Result<()> {
+        store::update_document(&self.path, CACHE_SCHEMA_VERSION, |document| {
+            // Unversioned caches keyed entries at the top level; start over
+            if document.schema_version() == 0 {
+                *document = Document::new(CACHE_SCHEMA_VERSION);
+            }
+            let mut entries = Self::entries(document);
+            entries.insert(key.to_string(), entry);
+            document.set(ENTRIES_FIELD, &entries)
+        })
+    }
+
+    fn entries(document: &Document) -> HashMap<String, CachedBalance> {
+        document.get(ENTRIES_FIELD).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::SystemTime;
+
+    fn entry(block_number: i64, fetched_at: i64) -> CachedBalance {
+        CachedBalance::new("1000".to_string(), block_number, fetched_at)
+    }
+
+    fn temp_cache(name: &str) -> BalanceCache {
+        let dir = std::env::temp_dir().join(format!("node_cli_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        BalanceCache::new(dir.join(CACHE_FILE_NAME))
+    }
+
+    #[test]
+    fn test_within_max_age() {
+        let cached = entry(10, 1_000_000);
+        let max_age = Duration::from_secs(30);
+        assert!(cached.within_max_age(1_000_000, max_age));
+        assert!(cached.within_max_age(1_029_999, max_age));
+        assert!(!cached.within_max_age(1_030_000, max_age));
+        // Clock moved backwards: do not trust the entry's age
+        assert!(!cached.within_max_age(999_000, max_age));
+    }
+
+    #[test]
+    fn test_current_at_tip() {
+        let cached = entry(10, 0);
+        assert!(cached.current_at(9));
+        assert!(cached.current_at(10));
+        assert!(!cached.current_at(11));
+    }
+
+    #[test]
+    fn test_is_usable() {
+        let cached = entry(10, 1_000_000);
+        let max_age = Duration::from_secs(30);
+        let old = 1_000_000 + 60_000;
+
+        // Young entry is served without knowing the tip
+        assert!(cached.is_usable(1_010_000, max_age, None));
+        // Old entry, tip unknown: refetch
+        assert!(!cached.is_usable(old, max_age, None));
+        // Old entry, tip has not moved: still valid
+        assert!(cached.is_usable(old, max_age, Some(10)));
+        // Old entry, tip advanced: refetch
+        assert!(!cached.is_usable(old, max_age, Some(11)));
+    }
+
+    #[test]
+    fn test_put_and_get() {
+        let cache = temp_cache("balance_cache");
+        let key = BalanceCache::key("localhost", 40452, "1111abc");
+        assert_eq!(cache.get(&key).unwrap(), None);
+
+        cache.put(&key, entry(5, 42)).unwrap();
+        cache
+            .put(
+                &BalanceCache::key("localhost", 40452, "1111def"),
+                entry(6, 43),
+            )
+            .unwrap();
+        assert_eq!(cache.get(&key).unwrap(), Some(entry(5, 42)));
+        assert!(!cache.path().with_extension("json.lock").exists());
+
+        let _ = std::fs::remove_dir_all(cache.path().parent().unwrap());
+    }
+
+    #[test]
+    fn test_corrupt_cache_is_rebuilt() {
+        let cache = temp_cache("balance_cache_corrupt");
+        std::fs::create_dir_all(cache.path().parent().unwrap()).unwrap();
+        std::fs::write(cache.path(), b"{ not json").unwrap();
+
+        let key = BalanceCache::key("localhost", 40452, "1111abc");
+        assert_eq!(cache.get(&key).unwrap(), None);
+        cache.put(&key, entry(7, 1)).unwrap();
+        assert_eq!(cache.get(&key).unwrap(), Some(entry(7, 1)));
+
+        let _ = std::fs::remove_dir_all(cache.path().parent().unwrap());
+    }
+
+    #[test]
+    fn test_stale_lock_is_broken() {
+        let cache = temp_cache("balance_cache_lock");
+        std::fs::create_dir_all(cache.path().parent().unwrap()).unwrap();
+        let lock = cache.path().with_extension("json.lock");
+        std::fs::write(&lock, b"").unwrap();
+        let old = SystemTime::now() - store::STALE_LOCK_AGE - Duration::from_secs(1);
+        std::fs::File::options()
+            .write(true)
+            .open(&lock)
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+
+        cache.put("k", entry(1, 1)).unwrap();
+        assert!(!lock.exists());
+
+        let _ = std::fs::remove_dir_all(cache.path().parent().unwrap());
+    }
+}
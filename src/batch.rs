@@ -0,0 +1,180 @@
+//! Several exploratory queries in one node round trip
+//!
+//! Each query is a term of the form `new return, ... in { ... }` that answers
+//! on `return`. [`build_batch_term`] nests every query under its own `return`
+//! and forwards the answer to a shared channel tagged with the query's index,
+//! so the node evaluates them all in one exploratory deploy. The answers come
+//! back as `(index, value)` tuples in no particular order; [`demultiplex`]
+//! splits them into one response per query, shaped like the response the
+//! query would have had on its own.
+
+use crate::error::{NodeCliError, Result};
+use crate::rholang_helpers::convert_rholang_to_json;
+
+/// Channel the batched term answers on; the first name of the outer `new`
+const BATCH_RETURN: &str = "batchReturn";
+
+/// Body of a query term with its leading `new return` removed
+///
+/// `new return, a, b in { P }` becomes `new a, b in { P }` and
+/// `new return in { P }` becomes `P`, so the caller can rebind `return`.
+fn strip_return_binding(term: &str) -> Result<String> {
+    let invalid = || {
+        NodeCliError::parse_error(&format!(
+            "Cannot batch a query that does not start with `new return`: {}",
+            term
+        ))
+    };
+    let term = term.trim();
+    let rest = term.strip_prefix("new").ok_or_else(invalid)?.trim_start();
+    let rest = rest
+        .strip_prefix("return")
+        .ok_or_else(invalid)?
+        .trim_start();
+
+    if let Some(names) = rest.strip_prefix(',') {
+        return Ok(format!("new {}", names.trim_start()));
+    }
+    let body = rest
+        .strip_prefix("in")
+        .map(str::trim)
+        .and_then(|b| b.strip_prefix('{'))
+        .and_then(|b| b.strip_suffix('}'))
+        .ok_or_else(invalid)?;
+    Ok(body.trim().to_string())
+}
+
+/// One term running every query and sending `(index, result)` on a shared channel
+pub fn build_batch_term(terms: &[&str]) -> Result<String> {
+    let branches = terms
+        .iter()
+        .enumerate()
+        .map(|(index, term)| {
+            Ok(format!(
+                " new return in {{\n  for (@result <- return) {{ {}!(({}, result)) }} |\n  {}\n }}",
+                BATCH_RETURN,
+                index,
+                strip_return_binding(term)?
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(format!(
+        "new {} in {{\n{}\n}}",
+        BATCH_RETURN,
+        branches.join(" |\n")
+    ))
+}
+
+/// Split a batched explore-deploy response into one response per query
+///
+/// Every returned response is a copy of `response` whose `expr` holds only
+/// that query's answer. Fails if an answer is missing or not an indexed
+/// tuple, which callers treat as a reason to run the queries separately.
+pub fn demultiplex(response: &serde_json::Value, count: usize) -> Result<Vec<serde_json::Value>> {
+    let exprs = response
+        .get("expr")
+        .and_then(|e| e.as_array())
+        .ok_or_else(|| NodeCliError::parse_error("Batched response has no expr list"))?;
+
+    let mut answers: Vec<Option<serde_json::Value>> = vec![None; count];
+    for expr in exprs {
+        let pair = expr
+            .get("ExprTuple")
+            .and_then(|t| t.get("data"))
+            .and_then(|d| d.as_array())
+            .filter(|d| d.len() == 2)
+            .ok_or_else(|| {
+                NodeCliError::parse_error(&format!("Batched answer is not a pair: {}", expr))
+            })?;
+        let index = convert_rholang_to_json(&pair[0])
+            .ok()
+            .and_then(|i| i.as_u64())
+            .map(|i| i as usize)
+            .filter(|&i| i < count)
+            .ok_or_else(|| {
+                NodeCliError::parse_error(&format!("Batched answer has a bad index: {}", pair[0]))
+            })?;
+        answers[index].get_or_insert_with(|| pair[1].clone());
+    }
+
+    answers
+        .into_iter()
+        .enumerate()
+        .map(|(index, answer)| {
+            let answer = answer.ok_or_else(|| {
+                NodeCliError::parse_error(&format!("Batched query {} returned no data", index))
+            })?;
+            let mut single = response.clone();
+            single["expr"] = serde_json::Value::Array(vec![answer]);
+            Ok(single)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const BONDS: &str = r#"new return, rl(`rho:registry:lookup`), poSCh in {
+ rl!(`rho:system:pos`, *poSCh) |
+ for(@(_, PoS) <- poSCh) {
+ @PoS!("getBonds", *return)
+ }
+ }"#;
+
+    #[test]
+    fn test_build_batch_term() {
+        let term = build_batch_term(&[BONDS, "new return in { return!(7) }"]).unwrap();
+        assert!(term.starts_with("new batchReturn in {"));
+        assert!(term.contains("for (@result <- return) { batchReturn!((0, result)) }"));
+        assert!(term.contains("for (@result <- return) { batchReturn!((1, result)) }"));
+        // The PoS query keeps its other names; the literal one loses its binder
+        assert!(term.contains("new rl(`rho:registry:lookup`), poSCh in {"));
+        assert!(term.contains("  return!(7)\n"));
+        assert_eq!(term.matches("new return in {").count(), 2);
+        assert_eq!(term.matches('{').count(), term.matches('}').count());
+    }
+
+    #[test]
+    fn test_build_batch_term_rejects_other_shapes() {
+        assert!(build_batch_term(&["@0!(1)"]).is_err());
+        assert!(build_batch_term(&["new returned in { Nil }"]).is_err());
+        assert!(build_batch_term(&["new return in Nil"]).is_err());
+    }
+
+    fn pair(index: i64, value: serde_json::Value) -> serde_json::Value {
+        json!({"ExprTuple": {"data": [{"ExprInt": {"data": index}}, value]}})
+    }
+
+    #[test]
+    fn test_demultiplex_out_of_order() {
+        let response = json!({
+            "expr": [
+                pair(2, json!({"ExprInt": {"data": 50}})),
+                pair(0, json!({"ExprMap": {"data": []}})),
+                pair(1, json!({"ExprSet": {"data": []}})),
+            ],
+            "block": {"blockHash": "b1", "blockNumber": 9}
+        });
+        let results = demultiplex(&response, 3).unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0]["expr"], json!([{"ExprMap": {"data": []}}]));
+        assert_eq!(results[1]["expr"], json!([{"ExprSet": {"data": []}}]));
+        assert_eq!(results[2]["expr"], json!([{"ExprInt": {"data": 50}}]));
+        assert!(results.iter().all(|r| r["block"]["blockNumber"] == 9));
+    }
+
+    #[test]
+    fn test_demultiplex_rejects_incomplete_answers() {
+        let missing = json!({"expr": [pair(0, json!({"ExprInt": {"data": 1}}))]});
+        let err = demultiplex(&missing, 2).unwrap_err();
+        assert!(err.to_string().contains("Batched query 1 returned no data"));
+
+        let out_of_range = json!({"expr": [pair(5, json!({"ExprInt": {"data": 1}}))]});
+        assert!(demultiplex(&out_of_range, 2).is_err());
+
+        let not_a_pair = json!({"expr": [{"ExprInt": {"data": 1}}]});
+        assert!(demultiplex(&not_a_pair, 1).is_err());
+    }
+}
@@ -0,0 +1,340 @@
+//! Paying many recipients from a CSV file (`batch-transfer`)
+//!
+//! Each row is `address,amount[,memo]` with the amount in tokens. A header
+//! row starting with `address` is skipped, as are blank lines and lines
+//! starting with `#`. Every row is checked before anything is sent, and all
+//! problems are reported together.
+//!
+//! By default all rows go out in one deploy built by
+//! [`build_payout_rholang`](crate::vault::build_payout_rholang), which reports
+//! each row's result on the deploy ID. An errored deploy applies none of its
+//! transfers, so every row it carried fails together. With `--sequential`
+//! each row is its own transfer deploy and fails on its own.
+
+use crate::error::{NodeCliError, Result};
+use crate::utils::{address_checksum_ok, is_base58};
+use crate::vault::{validate_address, RevAmount, SENDER_VAULT_ROW};
+use serde::Serialize;
+
+/// One recipient from the CSV file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Payout {
+    /// Line of the file, from 1
+    pub line: usize,
+    pub address: String,
+    pub amount: RevAmount,
+    /// Free text carried into the report; not sent on chain
+    pub memo: Option<String>,
+}
+
+/// What happened to one row
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum RowOutcome {
+    /// The transfer went through
+    Sent { deploy_id: String },
+    /// The transfer did not go through, in a deploy that otherwise ran
+    Failed {
+        deploy_id: Option<String>,
+        error: String,
+    },
+    /// The deploy carrying the row failed, so none of its transfers applied
+    DeployFailed {
+        deploy_id: Option<String>,
+        error: String,
+    },
+    /// The deploy finalized without errors but reported nothing for the row
+    Unconfirmed { deploy_id: String },
+}
+
+impl RowOutcome {
+    pub fn is_failed(&self) -> bool {
+        matches!(
+            self,
+            RowOutcome::Failed { .. } | RowOutcome::DeployFailed { .. }
+        )
+    }
+}
+
+/// A CSV field without surrounding whitespace and double quotes
+fn field(raw: &str) -> &str {
+    let raw = raw.trim();
+    raw.strip_prefix('"')
+        .and_then(|inner| inner.strip_suffix('"'))
+        .unwrap_or(raw)
+        .trim()
+}
+
+/// Rows of a batch-transfer CSV file, all checked
+///
+/// Addresses must be vault addresses made of base58 characters only, since
+/// they are written into the Rholang term, and must pass their checksum:
+/// `findOrCreate` would otherwise make a vault at a mistyped address and the
+/// tokens sent there could never be spent. Amounts must be above zero.
+pub fn parse_payouts(text: &str) -> Result<Vec<Payout>> {
+    let mut payouts = Vec::new();
+    let mut problems = Vec::new();
+    for (index, raw) in text.lines().enumerate() {
+        let line = index + 1;
+        let trimmed = raw.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let mut fields = trimmed.splitn(3, ',');
+        let address = field(fields.next().unwrap_or_default());
+        if payouts.is_empty() && problems.is_empty() && address.eq_ignore_ascii_case("address") {
+            continue;
+        }
+        let Some(amount) = fields.next().map(field) else {
+            problems.push(format!("line {}: expected address,amount[,memo]", line));
+            continue;
+        };
+        let memo = fields.next().map(field).filter(|m| !m.is_empty());
+
+        if let Err(e) = validate_address(address) {
+            problems.push(format!("line {}: {}", line, e));
+            continue;
+        }
+        if !is_base58(address) {
+            problems.push(format!(
+                "line {}: '{}' is not a base58 vault address",
+                line, address
+            ));
+            continue;
+        }
+        if !address_checksum_ok(address) {
+            problems.push(format!(
+                "line {}: '{}' fails its checksum; check it for a typo",
+                line, address
+            ));
+            continue;
+        }
+        let amount = match amount.parse::<RevAmount>() {
+            Ok(amount) if amount.dust() == 0 => {
+                problems.push(format!("line {}: amount must be more than 0", line));
+                continue;
+            }
+            Ok(amount) => amount,
+            Err(e) => {
+                problems.push(format!("line {}: {}", line, e));
+                continue;
+            }
+        };
+        payouts.push(Payout {
+            line,
+            address: address.to_string(),
+            amount,
+            memo: memo.map(str::to_string),
+        });
+    }
+    if !problems.is_empty() {
+        return Err(NodeCliError::config_invalid_value(
+            "file",
+            &problems.join("; "),
+        ));
+    }
+    if payouts.is_empty() {
+        return Err(NodeCliError::config_invalid_value(
+            "file",
+            "no rows to pay; expected address,amount[,memo] lines",
+        ));
+    }
+    Ok(payouts)
+}
+
+/// Dust sent by all `payouts`; errors if it does not fit in a `u64`
+pub fn total_dust(payouts: &[Payout]) -> Result<u64> {
+    payouts
+        .iter()
+        .try_fold(0u64, |total, p| total.checked_add(p.amount.dust()))
+        .ok_or_else(|| {
+            NodeCliError::config_invalid_value(
+                "file",
+                &format!("the amounts add up to more than {} dust", u64::MAX),
+            )
+        })
+}
+
+/// Outcome of each of `count` rows sent in one combined deploy
+///
+/// `results` is what the term sent to its deploy ID, as JSON:
+/// `[row, [true, null]]` or `[row, [false, reason]]`. A sender vault
+/// failure fails every row; a row with no result is unconfirmed.
+pub fn combined_outcomes(
+    count: usize,
+    deploy_id: &str,
+    results: &[serde_json::Value],
+) -> Vec<RowOutcome> {
+    let mut outcomes: Vec<Option<RowOutcome>> = vec![None; count];
+    let mut sender_error = None;
+    for result in results {
+        let (Some(row), Some(succeeded)) = (
+            result.get(0).and_then(|v| v.as_i64()),
+            result
+                .get(1)
+                .and_then(|r| r.get(0))
+                .and_then(|v| v.as_bool()),
+        ) else {
+            tracing::debug!("Ignoring unexpected batch result: {}", result);
+            continue;
+        };
+        let reason = result
+            .get(1)
+            .and_then(|r| r.get(1))
+            .and_then(|v| v.as_str())
+            .unwrap_or("transfer refused")
+            .to_string();
+        if row == SENDER_VAULT_ROW {
+            sender_error = Some(format!("sender vault error: {}", reason));
+            continue;
+        }
+        let Some(slot) = usize::try_from(row)
+            .ok()
+            .and_then(|row| outcomes.get_mut(row))
+        else {
+            tracing::debug!("Ignoring batch result for unknown row {}", row);
+            continue;
+        };
+        *slot = Some(if succeeded {
+            RowOutcome::Sent {
+                deploy_id: deploy_id.to_string(),
+            }
+        } else {
+            RowOutcome::Failed {
+                deploy_id: Some(deploy_id.to_string()),
+                error: reason,
+            }
+        });
+    }
+    outcomes
+        .into_iter()
+        .map(|outcome| match (outcome, &sender_error) {
+            (Some(outcome), _) => outcome,
+            (None, Some(error)) => RowOutcome::Failed {
+                deploy_id: Some(deploy_id.to_string()),
+                error: error.clone(),
+            },
+            (None, None) => RowOutcome::Unconfirmed {
+                deploy_id: deploy_id.to_string(),
+            },
+        })
+        .collect()
+}
+
+/// Dust moved by the rows that were sent
+pub fn dust_sent(payouts: &[Payout], outcomes: &[RowOutcome]) -> u128 {
+    payouts
+        .iter()
+        .zip(outcomes)
+        .filter(|(_, outcome)| matches!(outcome, RowOutcome::Sent { .. }))
+        .map(|(payout, _)| payout.amount.dust() as u128)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const A: &str = "1111AtahZeefej4tvVR6ti9TJtv8yxLebT31SCEVDCKMNikBk5r3g";
+    const B: &str = "111127RX5ZgiAdRaQy4AWy57RdvAAckdELReEBxzvWYVvdnR32PiHA";
+
+    #[test]
+    fn test_parse_payouts() {
+        let text = format!(
+            "address,amount_rev,memo\n\
+             # March payroll\n\
+             {A},1.5,\"alice, contractor\"\n\
+             \n\
+             \"{B}\" , 20\n"
+        );
+        let payouts = parse_payouts(&text).unwrap();
+        assert_eq!(payouts.len(), 2);
+        assert_eq!(payouts[0].line, 3);
+        assert_eq!(payouts[0].amount.dust(), 150_000_000);
+        assert_eq!(payouts[0].memo.as_deref(), Some("alice, contractor"));
+        assert_eq!(payouts[1].address, B);
+        assert_eq!(payouts[1].memo, None);
+        assert_eq!(total_dust(&payouts).unwrap(), 2_150_000_000);
+    }
+
+    #[test]
+    fn test_parse_payouts_reports_every_bad_row() {
+        let text = format!(
+            "{A},1\n\
+             2222notavault0000000000000000000000000000000,1\n\
+             {A}\n\
+             {A},0\n\
+             {A},1.123456789\n\
+             1111\"}}|evil{{0000000000000000000000000000000000,1\n\
+             {typo},1\n\
+             {zero},1\n",
+            typo = A.replacen('Z', 'Y', 1),
+            zero = A.replacen('Z', "0", 1),
+        );
+        let err = parse_payouts(&text).unwrap_err().to_string();
+        for line in [
+            "line 2:", "line 3:", "line 4:", "line 5:", "line 6:", "line 7:", "line 8:",
+        ] {
+            assert!(err.contains(line), "{} missing from {}", line, err);
+        }
+        assert!(err.contains("line 7: '1111AtahYeef"), "{}", err);
+        assert!(err.contains("fails its checksum"), "{}", err);
+        assert!(err.contains("line 8: '1111Atah0eef"), "{}", err);
+        assert!(err.contains("is not a base58 vault address"), "{}", err);
+        assert!(!err.contains("line 1:"), "{}", err);
+        assert!(parse_payouts("address,amount\n# nothing\n").is_err());
+    }
+
+    #[test]
+    fn test_total_dust_overflow() {
+        let payout = |amount: &str| Payout {
+            line: 1,
+            address: A.to_string(),
+            amount: amount.parse().unwrap(),
+            memo: None,
+        };
+        let max = payout("184467440737.09551615");
+        assert_eq!(total_dust(&[max.clone()]).unwrap(), u64::MAX);
+        assert!(total_dust(&[max, payout("0.00000001")]).is_err());
+    }
+
+    #[test]
+    fn test_combined_outcomes() {
+        let results = [
+            json!([0, [true, null]]),
+            json!([2, [false, "Insufficient funds"]]),
+            json!("stray"),
+            json!([7, [true, null]]),
+        ];
+        let outcomes = combined_outcomes(3, "d1", &results);
+        assert_eq!(
+            outcomes,
+            [
+                RowOutcome::Sent {
+                    deploy_id: "d1".to_string()
+                },
+                RowOutcome::Unconfirmed {
+                    deploy_id: "d1".to_string()
+                },
+                RowOutcome::Failed {
+                    deploy_id: Some("d1".to_string()),
+                    error: "Insufficient funds".to_string()
+                },
+            ]
+        );
+
+        let sender = combined_outcomes(2, "d1", &[json!([-1, [false, "no vault"]])]);
+        assert!(sender.iter().all(|o| matches!(
+            o,
+            RowOutcome::Failed { error, .. } if error == "sender vault error: no vault"
+        )));
+    }
+
+    #[test]
+    fn test_dust_sent_counts_sent_rows_only() {
+        let payouts = parse_payouts(&format!("{A},1\n{B},2\n")).unwrap();
+        let outcomes = combined_outcomes(2, "d1", &[json!([1, [true, null]])]);
+        assert_eq!(dust_sent(&payouts, &outcomes), 200_000_000);
+    }
+}
@@ -0,0 +1,583 @@
+//! Following one block from creation to finality, for `follow-block`
+//!
+//! A block goes through three stages: created by its proposer, added to the
+//! node's DAG, and finalized. [`BlockFollower`] collects what is known about
+//! each stage from two sources. Block events are timed when they arrive;
+//! polls of the node only bound when a stage happened, since they find it
+//! already reached. A more precise time replaces a vaguer one, so an event
+//! still queued when the first poll ran refines that poll's bound.
+//!
+//! A block the last finalized block has reached or passed in height without
+//! being finalized is orphaned: another block was finalized in its place and
+//! it never will be.
+
+use crate::error::{NodeCliError, Result};
+use crate::events::{BlockEvent, BlockEventKind};
+use crate::f1r3fly_api::{BlockSummary, F1r3flyApi};
+use crate::timeline::{render_stages, summarize_stages, StageTime};
+use std::fmt;
+use std::future::Future;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+/// A stage of a block's lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Created,
+    Added,
+    Finalized,
+}
+
+impl Stage {
+    pub fn name(self) -> &'static str {
+        match self {
+            Stage::Created => "created",
+            Stage::Added => "added",
+            Stage::Finalized => "finalized",
+        }
+    }
+
+    fn index(self) -> usize {
+        match self {
+            Stage::Created => 0,
+            Stage::Added => 1,
+            Stage::Finalized => 2,
+        }
+    }
+}
+
+/// Stage after which `follow-block` stops, for `--until`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Until {
+    Added,
+    Finalized,
+}
+
+impl Until {
+    pub fn stage(self) -> Stage {
+        match self {
+            Until::Added => Stage::Added,
+            Until::Finalized => Stage::Finalized,
+        }
+    }
+}
+
+impl fmt::Display for Until {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.stage().name())
+    }
+}
+
+impl FromStr for Until {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "added" => Ok(Until::Added),
+            "finalized" => Ok(Until::Finalized),
+            _ => Err(format!(
+                "unknown stage '{}' (expected added or finalized)",
+                s
+            )),
+        }
+    }
+}
+
+/// What one poll of the node found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockProbe {
+    /// Header of the block; `None` while the node does not know it
+    pub block: Option<BlockSummary>,
+    pub finalized: bool,
+    pub last_finalized: Option<BlockSummary>,
+}
+
+/// Poll the node for the block's header and finality
+///
+/// The last finalized block is read first, so a block found unfinalized
+/// afterwards really was passed over at that height.
+pub async fn probe_block(
+    api: &F1r3flyApi<'_>,
+    block_hash: &str,
+    http_port: u16,
+) -> Result<BlockProbe> {
+    let last_finalized = api.get_last_finalized_block(http_port).await?;
+    let block = api.get_block_summary(block_hash, http_port).await?;
+    let finalized = match block {
+        Some(_) => api.is_finalized(block_hash, 1, Duration::ZERO).await?,
+        None => false,
+    };
+    Ok(BlockProbe {
+        block,
+        finalized,
+        last_finalized,
+    })
+}
+
+/// Stage times of one block, as far as they are known
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockFollower {
+    block_hash: String,
+    height: Option<i64>,
+    times: [StageTime; 3],
+}
+
+impl BlockFollower {
+    pub fn new(block_hash: &str) -> Self {
+        Self {
+            block_hash: block_hash.to_string(),
+            height: None,
+            times: [StageTime::Pending; 3],
+        }
+    }
+
+    pub fn block_hash(&self) -> &str {
+        &self.block_hash
+    }
+
+    pub fn height(&self) -> Option<i64> {
+        self.height
+    }
+
+    pub fn time(&self, stage: Stage) -> StageTime {
+        self.times[stage.index()]
+    }
+
+    /// The three stages in order with their times
+    pub fn stages(&self) -> [(&'static str, StageTime); 3] {
+        [Stage::Created, Stage::Added, Stage::Finalized].map(|s| (s.name(), self.time(s)))
+    }
+
+    /// True once the block has reached `until`
+    pub fn reached(&self, until: Until) -> bool {
+        self.time(until.stage()) != StageTime::Pending
+    }
+
+    /// One line per stage with the durations between them
+    pub fn render(&self) -> Vec<String> {
+        render_stages(&self.stages())
+    }
+
+    /// The line of one stage, as [`Self::render`] shows it
+    pub fn describe(&self, stage: Stage) -> String {
+        self.render().swap_remove(stage.index())
+    }
+
+    pub fn summary(&self) -> String {
+        summarize_stages(&self.stages())
+    }
+
+    /// Record an event; returns the stages it reached, in order
+    ///
+    /// Events for other blocks are ignored.
+    pub fn observe_event(&mut self, event: &BlockEvent) -> Vec<Stage> {
+        if event.block_hash != self.block_hash {
+            return Vec::new();
+        }
+        if self.height.is_none() {
+            self.height = event.block_number;
+        }
+        let at = event.received_at;
+        let mut reached = Vec::new();
+        match event.kind {
+            BlockEventKind::Created => self.settle(Stage::Created, StageTime::At(at), &mut reached),
+            BlockEventKind::Added => self.settle(Stage::Added, StageTime::At(at), &mut reached),
+            BlockEventKind::Finalised => {
+                // A block is added before it can be finalized
+                self.settle(Stage::Added, StageTime::NoLaterThan(at), &mut reached);
+                self.settle(Stage::Finalized, StageTime::At(at), &mut reached);
+            }
+        }
+        reached
+    }
+
+    /// Record a poll made at `now`; returns the stages it reached, in order
+    ///
+    /// The header's timestamp is the creation time. Stages found already
+    /// reached happened no later than the poll.
+    pub fn observe_probe(&mut self, probe: &BlockProbe, now: i64) -> Vec<Stage> {
+        let mut reached = Vec::new();
+        let Some(block) = &probe.block else {
+            return reached;
+        };
+        self.height = Some(block.block_number);
+        if self.time(Stage::Created) == StageTime::Pending {
+            reached.push(Stage::Created);
+        }
+        // Signed into the block by its creator, so preferred over an event time
+        self.times[Stage::Created.index()] = StageTime::At(block.timestamp);
+        self.settle(Stage::Added, StageTime::NoLaterThan(now), &mut reached);
+        if probe.finalized {
+            self.settle(Stage::Finalized, StageTime::NoLaterThan(now), &mut reached);
+        }
+        reached
+    }
+
+    /// Height of the last finalized block if it shows this block orphaned
+    pub fn orphaned_by(&self, probe: &BlockProbe) -> Option<i64> {
+        let height = self.height?;
+        let last_finalized = probe.last_finalized.as_ref()?.block_number;
+        let orphaned = !probe.finalized
+            && self.time(Stage::Finalized) == StageTime::Pending
+            && last_finalized >= height;
+        orphaned.then_some(last_finalized)
+    }
+
+    /// Keep the more precise of the current and new time, noting a stage
+    /// reached for the first time
+    fn settle(&mut self, stage: Stage, time: StageTime, reached: &mut Vec<Stage>) {
+        let current = &mut self.times[stage.index()];
+        if precision(&time) > precision(current) {
+            if *current == StageTime::Pending {
+                reached.push(stage);
+            }
+            *current = time;
+        }
+    }
+}
+
+fn precision(time: &StageTime) -> u8 {
+    match time {
+        StageTime::Pending => 0,
+        StageTime::Unknown => 1,
+        StageTime::NoLaterThan(_) => 2,
+        StageTime::At(_) => 3,
+    }
+}
+
+/// When to stop following
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FollowWait {
+    pub until: Until,
+    /// Time between polls of the node; must not be zero
+    pub poll_interval: Duration,
+    /// When `--timeout` runs out
+    pub deadline: Instant,
+}
+
+/// How following a block ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FollowOutcome {
+    /// The block reached the `--until` stage
+    Reached,
+    Orphaned {
+        height: i64,
+        last_finalized: i64,
+    },
+    TimedOut,
+}
+
+impl FollowOutcome {
+    /// `Reached` as success, the rest as the errors `follow-block` exits with
+    pub fn into_result(self, block_hash: &str, until: Until, timeout: Duration) -> Result<()> {
+        match self {
+            FollowOutcome::Reached => Ok(()),
+            FollowOutcome::Orphaned {
+                height,
+                last_finalized,
+            } => Err(NodeCliError::block_orphaned(
+                block_hash,
+                height,
+                last_finalized,
+            )),
+            FollowOutcome::TimedOut => Err(NodeCliError::General(format!(
+                "block {} not {} within {}s",
+                block_hash,
+                until,
+                timeout.as_secs()
+            ))),
+        }
+    }
+}
+
+/// Follow the block until it reaches `wait.until`, is orphaned or the
+/// deadline passes
+///
+/// Events are taken from `events` as they arrive and the node is polled with
+/// `probe` every `wait.poll_interval`, and straight away when an event
+/// arrives before the block's header is known. Once the event stream closes
+/// polling carries on alone. `on_reached` is called for each stage as it is
+/// first reached.
+pub async fn follow<P, F>(
+    follower: &mut BlockFollower,
+    events: &mut mpsc::UnboundedReceiver<BlockEvent>,
+    mut probe: P,
+    wait: FollowWait,
+    mut on_reached: impl FnMut(&BlockFollower, Stage),
+) -> Result<FollowOutcome>
+where
+    P: FnMut() -> F,
+    F: Future<Output = Result<BlockProbe>>,
+{
+    let start = tokio::time::Instant::now();
+    let mut poll = tokio::time::interval_at(start + wait.poll_interval, wait.poll_interval);
+    let timeout = tokio::time::sleep_until(tokio::time::Instant::from_std(wait.deadline));
+    tokio::pin!(timeout);
+    let mut events_open = true;
+
+    loop {
+        if follower.reached(wait.until) {
+            return Ok(FollowOutcome::Reached);
+        }
+
+        let mut poll_due = false;
+        tokio::select! {
+            event = events.recv(), if events_open => match event {
+                Some(event) => {
+                    let reached = follower.observe_event(&event);
+                    for stage in &reached {
+                        on_reached(follower, *stage);
+                    }
+                    poll_due = !reached.is_empty()
+                        && follower.time(Stage::Created) == StageTime::Pending;
+                }
+                None => events_open = false,
+            },
+            _ = poll.tick() => poll_due = true,
+            _ = &mut timeout => return Ok(FollowOutcome::TimedOut),
+        }
+
+        if poll_due {
+            let probe = probe().await?;
+            let now = chrono::Utc::now().timestamp_millis();
+            for stage in follower.observe_probe(&probe, now) {
+                on_reached(follower, stage);
+            }
+            if let (Some(height), Some(last_finalized)) =
+                (follower.height(), follower.orphaned_by(&probe))
+            {
+                return Ok(FollowOutcome::Orphaned {
+                    height,
+                    last_finalized,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::EXIT_BLOCK_ORPHANED;
+
+    const T0: i64 = 1_767_225_600_000; // 2026-01-01 00:00:00 UTC
+
+    fn summary(hash: &str, number: i64, timestamp: i64) -> BlockSummary {
+        BlockSummary::from_json(&serde_json::json!({
+            "blockHash": hash,
+            "blockNumber": number,
+            "timestamp": timestamp,
+        }))
+        .unwrap()
+    }
+
+    fn event(kind: BlockEventKind, hash: &str, received_at: i64) -> BlockEvent {
+        BlockEvent {
+            kind,
+            block_hash: hash.to_string(),
+            block_number: Some(10),
+            received_at,
+        }
+    }
+
+    fn wait(until: Until, poll_interval: Duration, timeout: Duration) -> FollowWait {
+        FollowWait {
+            until,
+            poll_interval,
+            deadline: Instant::now() + timeout,
+        }
+    }
+
+    /// A node that always answers with the same poll result
+    fn fixed_probe(probe: BlockProbe) -> impl FnMut() -> std::future::Ready<Result<BlockProbe>> {
+        move || std::future::ready(Ok(probe.clone()))
+    }
+
+    #[test]
+    fn test_precise_times_replace_bounds() {
+        let mut follower = BlockFollower::new("b1");
+        let probe = BlockProbe {
+            block: Some(summary("b1", 10, T0)),
+            finalized: false,
+            last_finalized: Some(summary("lfb", 9, T0)),
+        };
+        assert_eq!(
+            follower.observe_probe(&probe, T0 + 5_000),
+            [Stage::Created, Stage::Added]
+        );
+        assert_eq!(
+            follower.time(Stage::Added),
+            StageTime::NoLaterThan(T0 + 5_000)
+        );
+        assert_eq!(follower.orphaned_by(&probe), None);
+
+        // An event queued before the poll refines the bound without
+        // reporting the stage again
+        let added = event(BlockEventKind::Added, "b1", T0 + 2_000);
+        assert!(follower.observe_event(&added).is_empty());
+        assert_eq!(follower.time(Stage::Added), StageTime::At(T0 + 2_000));
+        assert!(follower
+            .observe_event(&event(BlockEventKind::Finalised, "other", T0))
+            .is_empty());
+        assert!(!follower.reached(Until::Finalized));
+
+        // Another block finalized at its height
+        let passed = BlockProbe {
+            last_finalized: Some(summary("lfb", 10, T0 + 9_000)),
+            ..probe
+        };
+        assert_eq!(follower.orphaned_by(&passed), Some(10));
+        let finalized = BlockProbe {
+            finalized: true,
+            ..passed
+        };
+        assert_eq!(follower.orphaned_by(&finalized), None);
+
+        assert_eq!("added".parse::<Until>(), Ok(Until::Added));
+        assert!("included".parse::<Until>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_follow_events_to_finality() {
+        let (tx, mut events) = mpsc::unbounded_channel();
+        tx.send(event(BlockEventKind::Added, "b1", T0 + 2_000))
+            .unwrap();
+        tx.send(event(BlockEventKind::Added, "other", T0 + 3_000))
+            .unwrap();
+        tx.send(event(BlockEventKind::Finalised, "b1", T0 + 62_000))
+            .unwrap();
+        let probe = fixed_probe(BlockProbe {
+            block: Some(summary("b1", 10, T0)),
+            finalized: false,
+            last_finalized: Some(summary("lfb", 9, T0)),
+        });
+
+        let mut follower = BlockFollower::new("b1");
+        let mut printed = Vec::new();
+        let outcome = follow(
+            &mut follower,
+            &mut events,
+            probe,
+            wait(
+                Until::Finalized,
+                Duration::from_secs(3600),
+                Duration::from_secs(10),
+            ),
+            |f, stage| printed.push(f.describe(stage)),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(outcome, FollowOutcome::Reached);
+        // The header is fetched as soon as the block is added
+        assert_eq!(
+            printed,
+            [
+                "added      2026-01-01 00:00:02.000 UTC",
+                "created    2026-01-01 00:00:00.000 UTC",
+                "finalized  2026-01-01 00:01:02.000 UTC  (+1m)",
+            ]
+        );
+        assert_eq!(
+            follower.render()[1],
+            "added      2026-01-01 00:00:02.000 UTC  (+2s)"
+        );
+        assert_eq!(
+            follower.summary(),
+            "created → added: 2s, added → finalized: 1m"
+        );
+        assert!(outcome
+            .into_result("b1", Until::Finalized, Duration::from_secs(10))
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_follow_until_added() {
+        let (tx, mut events) = mpsc::unbounded_channel();
+        tx.send(event(BlockEventKind::Created, "b1", T0 + 500))
+            .unwrap();
+        tx.send(event(BlockEventKind::Added, "b1", T0 + 1_500))
+            .unwrap();
+        let mut follower = BlockFollower::new("b1");
+        let outcome = follow(
+            &mut follower,
+            &mut events,
+            fixed_probe(BlockProbe {
+                block: None,
+                finalized: false,
+                last_finalized: None,
+            }),
+            wait(
+                Until::Added,
+                Duration::from_secs(3600),
+                Duration::from_secs(10),
+            ),
+            |_, _| {},
+        )
+        .await
+        .unwrap();
+        assert_eq!(outcome, FollowOutcome::Reached);
+        assert_eq!(
+            follower.summary(),
+            "created → added: 1s, added → finalized: pending"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_orphaned_and_timed_out_exit_codes() {
+        // Event stream closed: polling carries on alone
+        let (_, mut events) = mpsc::unbounded_channel();
+        let mut follower = BlockFollower::new("b1");
+        let outcome = follow(
+            &mut follower,
+            &mut events,
+            fixed_probe(BlockProbe {
+                block: Some(summary("b1", 10, T0)),
+                finalized: false,
+                last_finalized: Some(summary("lfb", 11, T0 + 9_000)),
+            }),
+            wait(
+                Until::Finalized,
+                Duration::from_millis(5),
+                Duration::from_secs(10),
+            ),
+            |_, _| {},
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            outcome,
+            FollowOutcome::Orphaned {
+                height: 10,
+                last_finalized: 11
+            }
+        );
+        let err = outcome
+            .into_result("b1", Until::Finalized, Duration::from_secs(10))
+            .unwrap_err();
+        assert_eq!(err.exit_code(), EXIT_BLOCK_ORPHANED);
+        assert!(err.to_string().contains("height 10"), "{}", err);
+
+        let (_tx, mut events) = mpsc::unbounded_channel();
+        let outcome = follow(
+            &mut BlockFollower::new("b1"),
+            &mut events,
+            fixed_probe(BlockProbe {
+                block: None,
+                finalized: false,
+                last_finalized: None,
+            }),
+            wait(Until::Finalized, Duration::from_secs(3600), Duration::ZERO),
+            |_, _| {},
+        )
+        .await
+        .unwrap();
+        assert_eq!(outcome, FollowOutcome::TimedOut);
+        let err = outcome
+            .into_result("b1", Until::Added, Duration::from_secs(30))
+            .unwrap_err();
+        assert_eq!(err.exit_code(), 1);
+        assert_eq!(err.to_string(), "block b1 not added within 30s");
+    }
+}
@@ -0,0 +1,295 @@
+//! Day and hour separators for block listings (`--granularity`)
+//!
+//! Table output of `blocks` and `show-main-chain` is split into groups by the
+//! UTC day (or hour) of each block's timestamp, with a separator row wherever
+//! consecutive blocks cross a boundary and a count per group at the end.
+//!
+//! Block timestamps come from validator clocks and need not follow height
+//! order. A fork block is grouped by the timestamp of the main-chain block at
+//! its height, and a block whose group lies behind the current one in listing
+//! order stays in the current group, so a group is never announced twice.
+
+use chrono::{TimeZone, Utc};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Width of the boundary that starts a new group
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Granularity {
+    #[default]
+    Day,
+    Hour,
+}
+
+impl FromStr for Granularity {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "day" => Ok(Granularity::Day),
+            "hour" => Ok(Granularity::Hour),
+            other => Err(format!(
+                "unknown granularity '{}' (expected day or hour)",
+                other
+            )),
+        }
+    }
+}
+
+impl Granularity {
+    /// Group label for a timestamp in Unix epoch milliseconds, e.g. `2024-06-19`
+    /// or `2024-06-19 14:00`
+    pub fn label(self, timestamp_ms: i64) -> String {
+        let Some(time) = Utc.timestamp_millis_opt(timestamp_ms).single() else {
+            return timestamp_ms.to_string();
+        };
+        match self {
+            Granularity::Day => time.format("%Y-%m-%d").to_string(),
+            Granularity::Hour => time.format("%Y-%m-%d %H:00").to_string(),
+        }
+    }
+
+    /// Start of the group holding `timestamp_ms`, used to order groups
+    fn floor(self, timestamp_ms: i64) -> i64 {
+        let width = match self {
+            Granularity::Day => 86_400_000,
+            Granularity::Hour => 3_600_000,
+        };
+        timestamp_ms.div_euclid(width) * width
+    }
+
+    /// Summary heading noun
+    fn unit(self) -> &'static str {
+        match self {
+            Granularity::Day => "day",
+            Granularity::Hour => "hour",
+        }
+    }
+}
+
+/// What grouping needs to know about a listed block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockTime {
+    pub block_number: i64,
+    /// Unix epoch milliseconds
+    pub timestamp: i64,
+    pub main_chain: bool,
+}
+
+/// One row of a grouped listing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Row<'a, T> {
+    /// Index into [`Partition::groups`] of the group starting here
+    Separator(usize),
+    Block(&'a T),
+}
+
+/// A group and the number of blocks in it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Group {
+    pub label: String,
+    pub blocks: usize,
+}
+
+/// Blocks in listing order with separators, and the groups in the same order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Partition<'a, T> {
+    pub rows: Vec<Row<'a, T>>,
+    pub groups: Vec<Group>,
+}
+
+/// Group `blocks`, kept in the given order, by `granularity`
+///
+/// `time` describes each block. Listings may run in either height direction;
+/// the direction is taken from the first and last block.
+pub fn partition<T>(
+    blocks: &[T],
+    granularity: Granularity,
+    time: impl Fn(&T) -> BlockTime,
+) -> Partition<'_, T> {
+    let times: Vec<BlockTime> = blocks.iter().map(&time).collect();
+    let main_at: HashMap<i64, i64> = times
+        .iter()
+        .filter(|t| t.main_chain)
+        .map(|t| (t.block_number, t.timestamp))
+        .collect();
+    let descending = match (times.first(), times.last()) {
+        (Some(first), Some(last)) => first.block_number > last.block_number,
+        _ => false,
+    };
+
+    let mut partition = Partition {
+        rows: Vec::with_capacity(blocks.len() + 1),
+        groups: Vec::new(),
+    };
+    let mut current: Option<i64> = None;
+    for (block, time) in blocks.iter().zip(&times) {
+        let anchor = main_at
+            .get(&time.block_number)
+            .copied()
+            .unwrap_or(time.timestamp);
+        let start = granularity.floor(anchor);
+        let ahead = match current {
+            None => true,
+            Some(current) if descending => start < current,
+            Some(current) => start > current,
+        };
+        if ahead {
+            current = Some(start);
+            partition.rows.push(Row::Separator(partition.groups.len()));
+            partition.groups.push(Group {
+                label: granularity.label(anchor),
+                blocks: 0,
+            });
+        }
+        if let Some(group) = partition.groups.last_mut() {
+            group.blocks += 1;
+        }
+        partition.rows.push(Row::Block(block));
+    }
+    partition
+}
+
+/// Separator row for `label`, dimmed when `styled`
+pub fn separator(label: &str, styled: bool) -> String {
+    let line = format!("── {} ──", label);
+    if styled {
+        format!("\x1b[2m{}\x1b[0m", line)
+    } else {
+        line
+    }
+}
+
+/// Trailing lines counting blocks per group
+pub fn summary(groups: &[Group], granularity: Granularity) -> Vec<String> {
+    let mut lines = vec![format!(" Blocks per {}:", granularity.unit())];
+    lines.extend(
+        groups
+            .iter()
+            .map(|group| format!("  {}  {}", group.label, group.blocks)),
+    );
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2024-06-19 00:00:00 UTC
+    const DAY: i64 = 1_718_755_200_000;
+    const HOUR: i64 = 3_600_000;
+
+    fn main(block_number: i64, timestamp: i64) -> BlockTime {
+        BlockTime {
+            block_number,
+            timestamp,
+            main_chain: true,
+        }
+    }
+
+    fn fork(block_number: i64, timestamp: i64) -> BlockTime {
+        BlockTime {
+            main_chain: false,
+            ..main(block_number, timestamp)
+        }
+    }
+
+    /// Rows as separator labels and block numbers
+    fn render(blocks: &[BlockTime], granularity: Granularity) -> Vec<String> {
+        let partition = partition(blocks, granularity, |b| *b);
+        partition
+            .rows
+            .iter()
+            .map(|row| match row {
+                Row::Separator(i) => partition.groups[*i].label.clone(),
+                Row::Block(b) => format!("#{}", b.block_number),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_separators_at_boundaries() {
+        let blocks = [
+            main(1, DAY - HOUR),
+            main(2, DAY - 1),
+            main(3, DAY),
+            main(4, DAY + 2 * HOUR),
+        ];
+        assert_eq!(
+            render(&blocks, Granularity::Day),
+            ["2024-06-18", "#1", "#2", "2024-06-19", "#3", "#4"]
+        );
+        assert_eq!(
+            render(&blocks, Granularity::Hour),
+            [
+                "2024-06-18 23:00",
+                "#1",
+                "#2",
+                "2024-06-19 00:00",
+                "#3",
+                "2024-06-19 02:00",
+                "#4"
+            ]
+        );
+
+        // Newest first, as show-main-chain lists them
+        let mut newest_first = blocks;
+        newest_first.reverse();
+        let partition = partition(&newest_first, Granularity::Day, |b| *b);
+        assert_eq!(
+            partition.groups,
+            [
+                Group {
+                    label: "2024-06-19".to_string(),
+                    blocks: 2
+                },
+                Group {
+                    label: "2024-06-18".to_string(),
+                    blocks: 2
+                },
+            ]
+        );
+        assert!(render(&[], Granularity::Day).is_empty());
+    }
+
+    #[test]
+    fn test_out_of_order_timestamps() {
+        let blocks = [
+            main(1, DAY - HOUR),
+            main(2, DAY + HOUR),
+            // A fork block stamped before midnight follows its main-chain block
+            fork(2, DAY - 2 * HOUR),
+            // A main-chain clock running behind does not reopen the day before
+            main(3, DAY - 1),
+            main(4, DAY + 3 * HOUR),
+        ];
+        assert_eq!(
+            render(&blocks, Granularity::Day),
+            ["2024-06-18", "#1", "2024-06-19", "#2", "#2", "#3", "#4"]
+        );
+
+        // A fork block with no main-chain block listed keeps its own timestamp
+        let orphan = [main(1, DAY - HOUR), fork(2, DAY + HOUR)];
+        assert_eq!(
+            render(&orphan, Granularity::Day),
+            ["2024-06-18", "#1", "2024-06-19", "#2"]
+        );
+    }
+
+    #[test]
+    fn test_summary_and_separator() {
+        let groups = [Group {
+            label: "2024-06-19".to_string(),
+            blocks: 3,
+        }];
+        assert_eq!(
+            summary(&groups, Granularity::Day),
+            [" Blocks per day:", "  2024-06-19  3"]
+        );
+        assert_eq!(separator("2024-06-19", false), "── 2024-06-19 ──");
+        assert_eq!(
+            separator("2024-06-19", true),
+            "\x1b[2m── 2024-06-19 ──\x1b[0m"
+        );
+    }
+}
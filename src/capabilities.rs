@@ -0,0 +1,350 @@
+//! Node version detection and feature gating
+//!
+//! Older F1r3node releases lack some endpoints the CLI relies on, and calling
+//! them surfaces as a bare 404 or gRPC `UNIMPLEMENTED`. Commands that need one
+//! of those endpoints call [`require`] first: the node's version is read from
+//! `/api/status` once per process and compared against [`CAPABILITIES`], so an
+//! old node fails fast with the version it reports and the version needed.
+//!
+//! A node whose version cannot be fetched or parsed is not gated; the command
+//! runs and reports whatever the node returns. `--assume-capabilities` skips
+//! the gate for forks with nonstandard version strings.
+
+use crate::error::{NodeCliError, Result};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::OnceCell;
+
+/// A node release, parsed from the status version string
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct NodeVersion {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+impl NodeVersion {
+    pub const fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// First `MAJOR.MINOR[.PATCH]` in a version string
+    ///
+    /// Accepts product prefixes, a leading `v`, and pre-release, build or git
+    /// suffixes (`RChain Node 0.12.8 (abc123)`, `v0.5.0-3-g1a2b3c4`,
+    /// `0.13.0-alpha3+build.7`). Digits inside words such as `F1r3fly` or a
+    /// git hash are not mistaken for a version.
+    pub fn parse(raw: &str) -> Option<Self> {
+        let bytes = raw.as_bytes();
+        (0..bytes.len()).find_map(|start| {
+            let at_boundary = |i: usize| i == 0 || !bytes[i - 1].is_ascii_alphanumeric();
+            let begins = bytes[start].is_ascii_digit()
+                && (at_boundary(start)
+                    || (start >= 1
+                        && matches!(bytes[start - 1], b'v' | b'V')
+                        && at_boundary(start - 1)));
+            if !begins || (start > 0 && bytes[start - 1] == b'.') {
+                return None;
+            }
+            Self::parse_numbers(&raw[start..])
+        })
+    }
+
+    fn parse_numbers(text: &str) -> Option<Self> {
+        let end = text
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(text.len());
+        let mut parts = text[..end].split('.').map(|p| p.parse::<u64>());
+        let major = parts.next()?.ok()?;
+        let minor = parts.next()?.ok()?;
+        let patch = match parts.next() {
+            Some(patch) => patch.ok()?,
+            None => 0,
+        };
+        Some(Self::new(major, minor, patch))
+    }
+
+    /// Version string from the `version` field of `/api/status`
+    ///
+    /// Nodes report either a plain string or an object such as
+    /// `{"api": "1", "node": "RChain Node 0.12.8 (DEV)"}`.
+    pub fn from_status(version: &serde_json::Value) -> Option<Self> {
+        match version {
+            serde_json::Value::String(s) => Self::parse(s),
+            serde_json::Value::Object(fields) => fields
+                .get("node")
+                .and_then(|v| v.as_str())
+                .and_then(Self::parse)
+                .or_else(|| {
+                    fields
+                        .values()
+                        .filter_map(|v| v.as_str())
+                        .find_map(Self::parse)
+                }),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for NodeVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.patch == 0 {
+            write!(f, "{}.{}", self.major, self.minor)
+        } else {
+            write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+        }
+    }
+}
+
+/// Node features that not every release provides
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Capability {
+    /// `/ws/events` event stream
+    WebsocketEvents,
+    /// `/api/blocks/{start}/{end}` ranged block listing
+    BlocksByHeight,
+    /// Pending deploy pool inspection
+    DeployPool,
+    /// `findDeploy` gRPC lookup
+    FindDeploy,
+    /// System deploys (PreCharge, Refund, CloseBlock, Slash) in block responses
+    SystemDeploys,
+}
+
+/// Each capability, how it is described to users, and the first release that has it
+pub const CAPABILITIES: &[(Capability, &str, NodeVersion)] = &[
+    (
+        Capability::FindDeploy,
+        "findDeploy",
+        NodeVersion::new(0, 9, 0),
+    ),
+    (
+        Capability::BlocksByHeight,
+        "blocks-by-height",
+        NodeVersion::new(0, 9, 12),
+    ),
+    (
+        Capability::WebsocketEvents,
+        "websocket events",
+        NodeVersion::new(0, 10, 0),
+    ),
+    (
+        Capability::DeployPool,
+        "deploy pool inspection",
+        NodeVersion::new(0, 13, 0),
+    ),
+    (
+        Capability::SystemDeploys,
+        "system deploy reporting",
+        NodeVersion::new(0, 14, 0),
+    ),
+];
+
+impl Capability {
+    fn entry(self) -> &'static (Capability, &'static str, NodeVersion) {
+        CAPABILITIES
+            .iter()
+            .find(|(capability, _, _)| *capability == self)
+            .expect("every capability has a table entry")
+    }
+
+    pub fn feature(self) -> &'static str {
+        self.entry().1
+    }
+
+    /// First node release providing this capability
+    pub fn min_version(self) -> NodeVersion {
+        self.entry().2
+    }
+
+    pub fn supported_by(self, version: &NodeVersion) -> bool {
+        *version >= self.min_version()
+    }
+}
+
+/// Fail if `version` is known to predate `capability`
+pub fn check(version: Option<&NodeVersion>, capability: Capability) -> Result<()> {
+    match version {
+        Some(version) if !capability.supported_by(version) => {
+            Err(NodeCliError::unsupported_feature(
+                &version.to_string(),
+                capability.feature(),
+                &capability.min_version().to_string(),
+            ))
+        }
+        _ => Ok(()),
+    }
+}
+
+static ASSUME_CAPABILITIES: AtomicBool = AtomicBool::new(false);
+
+/// Skip capability checks for the rest of the process (`--assume-capabilities`)
+pub fn set_assume_capabilities(assume: bool) {
+    ASSUME_CAPABILITIES.store(assume, Ordering::Relaxed);
+}
+
+fn assume_capabilities() -> bool {
+    ASSUME_CAPABILITIES.load(Ordering::Relaxed)
+}
+
+/// A node's reported version, fetched on first use and then reused
+#[derive(Debug)]
+pub struct NodeCapabilities {
+    status_url: String,
+    version: OnceCell<Option<NodeVersion>>,
+}
+
+impl NodeCapabilities {
+    pub fn new(host: &str, http_port: u16) -> Self {
+        Self {
+            status_url: format!("http://{}:{}/api/status", host, http_port),
+            version: OnceCell::new(),
+        }
+    }
+
+    /// The node's version, or `None` if it could not be fetched or parsed
+    pub async fn version(&self) -> Option<NodeVersion> {
+        *self
+            .version
+            .get_or_init(|| async {
+                let version = self.fetch_version().await;
+                if version.is_none() {
+                    tracing::debug!(
+                        url = %self.status_url,
+                        "Node version unknown, not gating features"
+                    );
+                }
+                version
+            })
+            .await
+    }
+
+    async fn fetch_version(&self) -> Option<NodeVersion> {
+        let response = reqwest::get(&self.status_url).await.ok()?;
+        if !response.status().is_success() {
+            return None;
+        }
+        let status: serde_json::Value = response.json().await.ok()?;
+        NodeVersion::from_status(status.get("version")?)
+    }
+
+    /// Fail fast if the node is known not to support `capability`
+    pub async fn require(&self, capability: Capability) -> Result<()> {
+        if assume_capabilities() {
+            return Ok(());
+        }
+        check(self.version().await.as_ref(), capability)
+    }
+}
+
+/// Shared per-node capability cache for this process
+pub fn for_node(host: &str, http_port: u16) -> Arc<NodeCapabilities> {
+    static NODES: OnceLock<Mutex<HashMap<(String, u16), Arc<NodeCapabilities>>>> = OnceLock::new();
+    NODES
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap()
+        .entry((host.to_string(), http_port))
+        .or_insert_with(|| Arc::new(NodeCapabilities::new(host, http_port)))
+        .clone()
+}
+
+/// Fail fast if the node at `host:http_port` is known not to support `capability`
+pub async fn require(host: &str, http_port: u16, capability: Capability) -> Result<()> {
+    for_node(host, http_port).require(capability).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::spawn_http_node;
+    use std::sync::atomic::AtomicUsize;
+
+    #[test]
+    fn test_parse_version_strings() {
+        let cases = [
+            ("RChain Node 0.12.8 (DEV)", Some(NodeVersion::new(0, 12, 8))),
+            (
+                "RChain Node 0.13.0-alpha3 (3d6b38a2a9b1a6c7c5b8b8b1a4c0f4c8a3b2e1d0)",
+                Some(NodeVersion::new(0, 13, 0)),
+            ),
+            (
+                "F1r3fly Node 0.4.2 (git: 9f8e7d6-dirty)",
+                Some(NodeVersion::new(0, 4, 2)),
+            ),
+            ("v0.5.0-3-g1a2b3c4", Some(NodeVersion::new(0, 5, 0))),
+            ("0.13.0+build.42", Some(NodeVersion::new(0, 13, 0))),
+            ("1.2", Some(NodeVersion::new(1, 2, 0))),
+            ("node v2.10.1", Some(NodeVersion::new(2, 10, 1))),
+            ("F1r3fly Node (1a2b3c4)", None),
+            ("DEV", None),
+            ("", None),
+        ];
+        for (raw, expected) in cases {
+            assert_eq!(NodeVersion::parse(raw), expected, "{}", raw);
+        }
+    }
+
+    #[test]
+    fn test_version_from_status_field() {
+        let object = serde_json::json!({"api": "1", "node": "RChain Node 0.12.8 (DEV)"});
+        assert_eq!(
+            NodeVersion::from_status(&object),
+            Some(NodeVersion::new(0, 12, 8))
+        );
+        let string = serde_json::json!("F1r3fly Node 0.13.1");
+        assert_eq!(
+            NodeVersion::from_status(&string),
+            Some(NodeVersion::new(0, 13, 1))
+        );
+        assert_eq!(NodeVersion::from_status(&serde_json::json!(13)), None);
+    }
+
+    #[test]
+    fn test_capability_lookup() {
+        let old = NodeVersion::new(0, 9, 4);
+        assert!(Capability::FindDeploy.supported_by(&old));
+        assert!(!Capability::BlocksByHeight.supported_by(&old));
+        assert!(!Capability::WebsocketEvents.supported_by(&old));
+        assert!(Capability::WebsocketEvents.supported_by(&NodeVersion::new(0, 12, 8)));
+        assert!(!Capability::DeployPool.supported_by(&NodeVersion::new(0, 12, 8)));
+        assert!(Capability::DeployPool.supported_by(&NodeVersion::new(1, 0, 0)));
+        assert!(!Capability::SystemDeploys.supported_by(&NodeVersion::new(0, 13, 1)));
+
+        let err = check(Some(&old), Capability::WebsocketEvents).unwrap_err();
+        assert!(err.to_string().contains(
+            "node reports version 0.9.4 which does not support websocket events; requires >= 0.10"
+        ));
+        // An unknown version is never gated
+        assert!(check(None, Capability::DeployPool).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_version_is_fetched_once() {
+        let status_calls = Arc::new(AtomicUsize::new(0));
+        let calls = status_calls.clone();
+        let port = spawn_http_node(move |line, _body| {
+            if line.contains("/api/status") {
+                calls.fetch_add(1, Ordering::SeqCst);
+                let status = serde_json::json!({
+                    "version": {"api": "1", "node": "RChain Node 0.9.4 (DEV)"}
+                });
+                ("200 OK", status.to_string())
+            } else {
+                ("404 Not Found", "{}".to_string())
+            }
+        })
+        .await;
+
+        let node = NodeCapabilities::new("127.0.0.1", port);
+        assert!(node.require(Capability::FindDeploy).await.is_ok());
+        assert!(node.require(Capability::BlocksByHeight).await.is_err());
+        assert_eq!(node.version().await, Some(NodeVersion::new(0, 9, 4)));
+        assert_eq!(status_calls.load(Ordering::SeqCst), 1);
+    }
+}
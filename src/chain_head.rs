@@ -0,0 +1,496 @@
+//! One shared subscription to a node's finalized blocks
+//!
+//! [`F1r3flyConnectionManager::subscribe_finalized`] hands out receivers of
+//! a tokio broadcast channel. The first subscription starts a background task
+//! that owns the node's `/ws/events` connection, looks up each finalized
+//! block over HTTP and sends its [`BlockSummary`] to every receiver. When the
+//! last receiver is dropped the task closes the connection and stops; the
+//! next subscription starts it again.
+//!
+//! Blocks reach subscribers in height order, each once per task. A block
+//! finalized more than one height above the last one sent (after a
+//! reconnect, or when an event was missed) is preceded by the blocks between,
+//! found by walking main parents back, at most [`MAX_BACKFILL`] of them. On
+//! reconnecting, the task catches up to the node's last finalized block the
+//! same way.
+//!
+//! A slow subscriber does not hold up the others: once it is
+//! [`CHANNEL_CAPACITY`] blocks behind, its oldest unread block is dropped
+//! and its next `recv` reports `Lagged`. [`ChainHeadStats::lagged`] counts
+//! those drops.
+//!
+//! [`F1r3flyConnectionManager::subscribe_finalized`]: crate::F1r3flyConnectionManager::subscribe_finalized
+
+use crate::events::{BlockEvent, BlockEventKind};
+use crate::export::ChainSource;
+use crate::f1r3fly_api::{BlockHeader, BlockSummary};
+use crate::maintenance::MaintenanceWait;
+use futures_util::StreamExt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::Message;
+
+/// Blocks a subscriber may fall behind before it loses the oldest
+pub const CHANNEL_CAPACITY: usize = 256;
+/// Most missed blocks recovered before a newly finalized one
+pub const MAX_BACKFILL: usize = 100;
+/// Wait before reconnecting after the connection fails or drops
+pub const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+/// How often a quiet task checks whether anyone is still subscribed
+const IDLE_CHECK: Duration = Duration::from_secs(1);
+
+/// Counters of a [`ChainHead`], over every run of its task
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChainHeadStats {
+    /// Whether the background task is running
+    pub running: bool,
+    pub subscribers: usize,
+    /// Blocks sent, including backfilled ones
+    pub delivered: u64,
+    /// Blocks sent to fill a gap rather than in reply to their own event
+    pub backfilled: u64,
+    /// Blocks dropped from a slow subscriber's queue before it read them
+    pub lagged: u64,
+    /// WebSocket connections made; more than the task starts means reconnects
+    pub connects: u64,
+    /// Times the task was started
+    pub starts: u64,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    delivered: AtomicU64,
+    backfilled: AtomicU64,
+    lagged: AtomicU64,
+    connects: AtomicU64,
+    starts: AtomicU64,
+}
+
+/// Timing and sizes of a [`ChainHead`]; tests shorten them
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ChainHeadOptions {
+    pub capacity: usize,
+    pub max_backfill: usize,
+    pub reconnect_delay: Duration,
+    pub idle_check: Duration,
+}
+
+impl Default for ChainHeadOptions {
+    fn default() -> Self {
+        Self {
+            capacity: CHANNEL_CAPACITY,
+            max_backfill: MAX_BACKFILL,
+            reconnect_delay: RECONNECT_DELAY,
+            idle_check: IDLE_CHECK,
+        }
+    }
+}
+
+/// The running task's channel, shared by subscribers
+struct Running {
+    sender: broadcast::Sender<BlockSummary>,
+    task: JoinHandle<()>,
+}
+
+/// Finalized blocks of one node, fanned out to any number of subscribers
+pub struct ChainHead {
+    ws_url: String,
+    source: ChainSource,
+    options: ChainHeadOptions,
+    running: Mutex<Option<Running>>,
+    counters: Counters,
+}
+
+impl ChainHead {
+    /// Follow the node whose HTTP API, and `/ws/events`, is on `http_port`
+    pub fn new(host: &str, http_port: u16) -> Arc<Self> {
+        Self::with_options(host, http_port, ChainHeadOptions::default())
+    }
+
+    pub(crate) fn with_options(host: &str, http_port: u16, options: ChainHeadOptions) -> Arc<Self> {
+        Arc::new(Self {
+            ws_url: format!("ws://{}:{}/ws/events", host, http_port),
+            source: ChainSource::new(host, http_port),
+            options,
+            running: Mutex::new(None),
+            counters: Counters::default(),
+        })
+    }
+
+    /// A receiver of blocks finalized from now on, starting the task if needed
+    ///
+    /// Must be called within a tokio runtime.
+    pub fn subscribe(self: &Arc<Self>) -> broadcast::Receiver<BlockSummary> {
+        let mut running = self.running.lock().unwrap();
+        if let Some(current) = running.as_ref().filter(|r| !r.task.is_finished()) {
+            return current.sender.subscribe();
+        }
+        let (sender, receiver) = broadcast::channel(self.options.capacity);
+        self.counters.starts.fetch_add(1, Ordering::Relaxed);
+        let task = tokio::spawn(self.clone().run(sender.clone()));
+        *running = Some(Running { sender, task });
+        receiver
+    }
+
+    pub fn stats(&self) -> ChainHeadStats {
+        let running = self.running.lock().unwrap();
+        let counters = &self.counters;
+        ChainHeadStats {
+            running: running.as_ref().is_some_and(|r| !r.task.is_finished()),
+            subscribers: running.as_ref().map_or(0, |r| r.sender.receiver_count()),
+            delivered: counters.delivered.load(Ordering::Relaxed),
+            backfilled: counters.backfilled.load(Ordering::Relaxed),
+            lagged: counters.lagged.load(Ordering::Relaxed),
+            connects: counters.connects.load(Ordering::Relaxed),
+            starts: counters.starts.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Stop if nobody is subscribed; true if the task should return
+    ///
+    /// Checked under the same lock `subscribe` takes, so a subscription
+    /// either joins this run or starts the next one.
+    fn release(&self, sender: &broadcast::Sender<BlockSummary>) -> bool {
+        let mut running = self.running.lock().unwrap();
+        if sender.receiver_count() > 0 {
+            return false;
+        }
+        *running = None;
+        true
+    }
+
+    /// Send `blocks` in order; false once nobody is subscribed
+    fn send(&self, sender: &broadcast::Sender<BlockSummary>, blocks: Vec<BlockSummary>) -> bool {
+        for block in blocks {
+            // A full queue drops its oldest block for whoever has not read it
+            if sender.len() >= self.options.capacity {
+                self.counters.lagged.fetch_add(1, Ordering::Relaxed);
+            }
+            if sender.send(block).is_err() {
+                return false;
+            }
+            self.counters.delivered.fetch_add(1, Ordering::Relaxed);
+        }
+        true
+    }
+
+    async fn run(self: Arc<Self>, sender: broadcast::Sender<BlockSummary>) {
+        let mut gaps = GapFiller::new(self.source.clone(), self.options.max_backfill);
+        let mut connected_before = false;
+        let mut maintenance = MaintenanceWait::new();
+        loop {
+            let mut delay = self.options.reconnect_delay;
+            match tokio_tungstenite::connect_async(&self.ws_url).await {
+                Ok((mut stream, _)) => {
+                    maintenance.on_success();
+                    tracing::info!("WebSocket connected to {}", self.ws_url);
+                    self.counters.connects.fetch_add(1, Ordering::Relaxed);
+                    if connected_before {
+                        match self.source.last_finalized().await {
+                            Ok(head) => {
+                                let blocks =
+                                    self.blocks_up_to(&mut gaps, &head.block_hash, true).await;
+                                if !self.send(&sender, blocks) && self.release(&sender) {
+                                    return;
+                                }
+                            }
+                            Err(e) => tracing::warn!("Could not catch up after reconnect: {}", e),
+                        }
+                    }
+                    connected_before = true;
+                    loop {
+                        tokio::select! {
+                            msg = stream.next() => {
+                                let text = match msg {
+                                    Some(Ok(Message::Text(t))) => t,
+                                    Some(Ok(_)) => continue,
+                                    Some(Err(e)) => {
+                                        tracing::debug!("WebSocket error: {}", e);
+                                        break;
+                                    }
+                                    None => break,
+                                };
+                                let Some(event) = BlockEvent::from_message(text.as_str(), 0)
+                                    .filter(|e| e.kind == BlockEventKind::Finalised)
+                                else {
+                                    continue;
+                                };
+                                let blocks =
+                                    self.blocks_up_to(&mut gaps, &event.block_hash, false).await;
+                                if !self.send(&sender, blocks) && self.release(&sender) {
+                                    let _ = stream.close(None).await;
+                                    return;
+                                }
+                            }
+                            _ = tokio::time::sleep(self.options.idle_check) => {
+                                if self.release(&sender) {
+                                    let _ = stream.close(None).await;
+                                    return;
+                                }
+                            }
+                        }
+                    }
+                    tracing::info!(
+                        "WebSocket disconnected, reconnecting in {}s...",
+                        self.options.reconnect_delay.as_secs()
+                    );
+                }
+                Err(e) => {
+                    delay = crate::events::connect_retry_delay(
+                        &mut maintenance,
+                        &e,
+                        self.options.reconnect_delay,
+                    );
+                }
+            }
+            let deadline = tokio::time::Instant::now() + delay;
+            while tokio::time::Instant::now() < deadline {
+                if self.release(&sender) {
+                    return;
+                }
+                tokio::time::sleep_until(
+                    deadline.min(tokio::time::Instant::now() + self.options.idle_check),
+                )
+                .await;
+            }
+        }
+    }
+
+    /// Blocks to send for a finalized `block_hash`, counting backfill
+    ///
+    /// When `catching_up` no event named the block, so all of them are backfill.
+    async fn blocks_up_to(
+        &self,
+        gaps: &mut GapFiller,
+        block_hash: &str,
+        catching_up: bool,
+    ) -> Vec<BlockSummary> {
+        let blocks = gaps.blocks_up_to(block_hash).await;
+        let backfilled = if catching_up {
+            blocks.len()
+        } else {
+            blocks.len().saturating_sub(1)
+        } as u64;
+        self.counters
+            .backfilled
+            .fetch_add(backfilled, Ordering::Relaxed);
+        blocks
+    }
+}
+
+/// Turns finalized hashes into the blocks to send, in height order
+#[derive(Debug)]
+struct GapFiller {
+    source: ChainSource,
+    max_backfill: usize,
+    /// Height and hash of the last block sent
+    last: Option<(i64, String)>,
+}
+
+impl GapFiller {
+    fn new(source: ChainSource, max_backfill: usize) -> Self {
+        Self {
+            source,
+            max_backfill,
+            last: None,
+        }
+    }
+
+    /// `block_hash` and the main-chain blocks between it and the last one
+    /// sent, oldest first; nothing if it is not above the last one sent
+    async fn blocks_up_to(&mut self, block_hash: &str) -> Vec<BlockSummary> {
+        if self
+            .last
+            .as_ref()
+            .is_some_and(|(_, hash)| hash == block_hash)
+        {
+            return Vec::new();
+        }
+        let head = match self.source.block(block_hash).await {
+            Ok(detail) => detail.header,
+            Err(e) => {
+                tracing::warn!("Could not fetch finalized block {}: {}", block_hash, e);
+                return Vec::new();
+            }
+        };
+        let floor = match &self.last {
+            Some((height, _)) if head.block_number <= *height => return Vec::new(),
+            Some((height, _)) => Some(*height),
+            None => None,
+        };
+        self.last = Some((head.block_number, head.block_hash.clone()));
+        let mut chain: Vec<BlockHeader> = vec![head];
+        if let Some(floor) = floor {
+            while chain.len() <= self.max_backfill {
+                let oldest = chain.last().expect("chain starts with the head");
+                let Some(parent) = oldest
+                    .parents
+                    .first()
+                    .filter(|_| oldest.block_number > floor + 1)
+                    .cloned()
+                else {
+                    break;
+                };
+                match self.source.block(&parent).await {
+                    Ok(detail) => chain.push(detail.header),
+                    Err(e) => {
+                        tracing::warn!("Could not backfill block {}: {}", parent, e);
+                        break;
+                    }
+                }
+            }
+        }
+        chain.iter().rev().map(BlockSummary::from).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{spawn_event_node, BlockFixture, WsSession};
+    use tokio::sync::broadcast::error::RecvError;
+
+    fn fast() -> ChainHeadOptions {
+        ChainHeadOptions {
+            reconnect_delay: Duration::from_millis(20),
+            idle_check: Duration::from_millis(10),
+            ..ChainHeadOptions::default()
+        }
+    }
+
+    /// Serves `/api/block/b{n}` for every height and `last_finalized` as the head
+    fn blocks(last_finalized: i64) -> impl Fn(&str, &str) -> (&'static str, String) {
+        move |line, _| {
+            let path = line.split_whitespace().nth(1).unwrap_or("");
+            if path == "/api/last-finalized-block" {
+                return (
+                    "200 OK",
+                    BlockFixture::new(last_finalized).to_json().to_string(),
+                );
+            }
+            match path
+                .strip_prefix("/api/block/b")
+                .and_then(|n| n.parse().ok())
+            {
+                Some(n) => ("200 OK", BlockFixture::new(n).to_json().to_string()),
+                None => ("404 Not Found", "{}".to_string()),
+            }
+        }
+    }
+
+    fn finalized(heights: &[i64]) -> Vec<String> {
+        heights
+            .iter()
+            .map(|n| BlockFixture::new(*n).finalized_event())
+            .collect()
+    }
+
+    async fn heights(receiver: &mut broadcast::Receiver<BlockSummary>, count: usize) -> Vec<i64> {
+        let mut heights = Vec::new();
+        for _ in 0..count {
+            let block = tokio::time::timeout(Duration::from_secs(5), receiver.recv())
+                .await
+                .expect("block arrives")
+                .unwrap();
+            heights.push(block.block_number);
+        }
+        heights
+    }
+
+    async fn wait_until(mut done: impl FnMut() -> bool) {
+        for _ in 0..500 {
+            if done() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("condition not reached");
+    }
+
+    #[tokio::test]
+    async fn test_fans_out_to_every_subscriber() {
+        let node = spawn_event_node(vec![WsSession::stay(finalized(&[1, 2, 3]))], blocks(3)).await;
+        let head = ChainHead::with_options("127.0.0.1", node.port, fast());
+        let mut first = head.subscribe();
+        let mut second = head.subscribe();
+        assert_eq!(head.stats().subscribers, 2);
+
+        assert_eq!(heights(&mut first, 3).await, vec![1, 2, 3]);
+        assert_eq!(heights(&mut second, 3).await, vec![1, 2, 3]);
+        let stats = head.stats();
+        assert_eq!((stats.delivered, stats.starts, stats.connects), (3, 1, 1));
+        assert_eq!(node.accepted(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_backfills_missed_blocks() {
+        // The first connection drops after height 2; heights 3 and 4 are
+        // finalized while disconnected, 5 arrives on the second connection
+        let node = spawn_event_node(
+            vec![
+                WsSession::disconnect(finalized(&[1, 2])),
+                WsSession::stay(finalized(&[5])),
+            ],
+            blocks(4),
+        )
+        .await;
+        let head = ChainHead::with_options("127.0.0.1", node.port, fast());
+        let mut receiver = head.subscribe();
+
+        assert_eq!(heights(&mut receiver, 5).await, vec![1, 2, 3, 4, 5]);
+        let stats = head.stats();
+        assert_eq!(
+            (stats.connects, stats.backfilled, stats.delivered),
+            (2, 2, 5)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_slow_subscriber_lags_without_blocking_others() {
+        let node = spawn_event_node(
+            vec![WsSession::stay(finalized(&[1, 2, 3, 4, 5]))],
+            blocks(5),
+        )
+        .await;
+        let options = ChainHeadOptions {
+            capacity: 2,
+            ..fast()
+        };
+        let head = ChainHead::with_options("127.0.0.1", node.port, options);
+        let mut fast_reader = head.subscribe();
+        let mut slow_reader = head.subscribe();
+
+        assert_eq!(heights(&mut fast_reader, 5).await, vec![1, 2, 3, 4, 5]);
+        assert_eq!(slow_reader.recv().await, Err(RecvError::Lagged(3)));
+        assert_eq!(heights(&mut slow_reader, 2).await, vec![4, 5]);
+        assert_eq!(head.stats().lagged, 3);
+    }
+
+    #[tokio::test]
+    async fn test_stops_when_receivers_drop_and_restarts() {
+        let node = spawn_event_node(
+            vec![
+                WsSession::stay(finalized(&[1])),
+                WsSession::stay(finalized(&[7])),
+            ],
+            blocks(7),
+        )
+        .await;
+        let head = ChainHead::with_options("127.0.0.1", node.port, fast());
+        let mut receiver = head.subscribe();
+        assert_eq!(heights(&mut receiver, 1).await, vec![1]);
+
+        drop(receiver);
+        wait_until(|| !head.stats().running && node.open() == 0).await;
+        assert_eq!(head.stats().subscribers, 0);
+
+        let mut receiver = head.subscribe();
+        assert_eq!(heights(&mut receiver, 1).await, vec![7]);
+        let stats = head.stats();
+        assert_eq!((stats.starts, stats.backfilled), (2, 0));
+        assert_eq!(node.accepted(), 2);
+    }
+}
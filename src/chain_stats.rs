@@ -0,0 +1,510 @@
+//! Block-time and throughput statistics for `chain-stats`
+//!
+//! Headers for a height range come from the chunked ranged-blocks API. When a
+//! height holds more than one block the range spans a fork and inter-block
+//! times become ambiguous, so statistics are computed over the main chain
+//! only: the chain reached by following main parents (the first parent hash)
+//! back from the highest block. Timestamps of zero are treated as missing and
+//! the pairs around them are skipped; negative intervals from clock skew are
+//! clamped to zero. Both are counted in the report.
+//!
+//! `--system-errors-only` replaces the statistics with a scan of every block
+//! in the range, forks included, for failed system deploys such as a
+//! CloseBlock that could not run or a slash.
+
+use crate::error::Result;
+use crate::export::ChainSource;
+use crate::f1r3fly_api::{BlockDetail, BlockHeader, SystemDeploy};
+use crate::utils::Summary;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// Fetch headers for heights `start..=end`, `chunk_size` heights per request
+pub async fn fetch_headers(
+    source: &ChainSource,
+    start: i64,
+    end: i64,
+    chunk_size: i64,
+) -> Result<Vec<BlockHeader>> {
+    let mut headers = Vec::new();
+    let mut chunk_start = start;
+    while chunk_start <= end {
+        let chunk_end = (chunk_start + chunk_size.max(1) - 1).min(end);
+        headers.extend(
+            source
+                .headers_in_range(chunk_start, chunk_end)
+                .await?
+                .into_iter()
+                .filter(|h| (chunk_start..=chunk_end).contains(&h.block_number)),
+        );
+        chunk_start = chunk_end + 1;
+    }
+    Ok(headers)
+}
+
+/// Number of heights holding more than one block
+pub fn forked_heights(headers: &[BlockHeader]) -> usize {
+    let mut per_height: BTreeMap<i64, usize> = BTreeMap::new();
+    for header in headers {
+        *per_height.entry(header.block_number).or_default() += 1;
+    }
+    per_height.values().filter(|&&count| count > 1).count()
+}
+
+/// Main-chain blocks in ascending height order
+///
+/// Without forks this is every block. Otherwise main parents are followed back
+/// from each block at the highest height and the longest chain wins, ties
+/// going to the lowest hash; the walk stops at the first parent outside the
+/// range.
+pub fn main_chain(headers: &[BlockHeader]) -> Vec<&BlockHeader> {
+    let mut sorted: Vec<&BlockHeader> = headers.iter().collect();
+    sorted.sort_by(|a, b| (a.block_number, &a.block_hash).cmp(&(b.block_number, &b.block_hash)));
+    if forked_heights(headers) == 0 {
+        return sorted;
+    }
+
+    let by_hash: HashMap<&str, &BlockHeader> =
+        headers.iter().map(|h| (h.block_hash.as_str(), h)).collect();
+    let top = sorted.last().map_or(0, |h| h.block_number);
+    let mut best: Vec<&BlockHeader> = Vec::new();
+    for tip in sorted.iter().filter(|h| h.block_number == top) {
+        let mut chain = vec![*tip];
+        let mut current = *tip;
+        while let Some(parent) = current
+            .parents
+            .first()
+            .and_then(|hash| by_hash.get(hash.as_str()))
+        {
+            // Guard against malformed data looping or skipping upwards
+            if parent.block_number >= current.block_number {
+                break;
+            }
+            chain.push(parent);
+            current = parent;
+        }
+        if chain.len() > best.len() {
+            best = chain;
+        }
+    }
+    best.reverse();
+    best
+}
+
+/// Intervals between consecutive timestamps, in milliseconds
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Intervals {
+    pub millis: Vec<u64>,
+    /// Pairs skipped because a timestamp was missing (zero)
+    pub skipped: usize,
+    /// Negative intervals clamped to zero
+    pub clamped: usize,
+}
+
+/// Intervals between consecutive blocks of an ascending chain
+pub fn block_intervals(chain: &[&BlockHeader]) -> Intervals {
+    let mut intervals = Intervals::default();
+    for pair in chain.windows(2) {
+        let (previous, next) = (pair[0].timestamp, pair[1].timestamp);
+        if previous <= 0 || next <= 0 {
+            intervals.skipped += 1;
+            continue;
+        }
+        let delta = next - previous;
+        if delta < 0 {
+            intervals.clamped += 1;
+        }
+        intervals.millis.push(delta.max(0) as u64);
+    }
+    intervals
+}
+
+/// Blocks proposed by one validator
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValidatorShare {
+    pub validator: String,
+    pub blocks: usize,
+    /// Fraction of main-chain blocks, `0.0..=1.0`
+    pub share: f64,
+}
+
+/// Validators by block count, most blocks first
+pub fn validator_shares(chain: &[&BlockHeader]) -> Vec<ValidatorShare> {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for header in chain {
+        *counts.entry(header.sender.as_str()).or_default() += 1;
+    }
+    let mut shares: Vec<ValidatorShare> = counts
+        .into_iter()
+        .map(|(validator, blocks)| ValidatorShare {
+            validator: validator.to_string(),
+            blocks,
+            share: blocks as f64 / chain.len() as f64,
+        })
+        .collect();
+    shares.sort_by(|a, b| b.blocks.cmp(&a.blocks));
+    shares
+}
+
+/// Throughput report for a range of heights
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChainStats {
+    pub start: i64,
+    pub end: i64,
+    /// Main-chain blocks the statistics cover
+    pub blocks: usize,
+    /// Heights holding more than one block
+    pub forked_heights: usize,
+    /// Inter-block time in milliseconds
+    pub block_time: Option<Summary>,
+    pub skipped_intervals: usize,
+    pub clamped_intervals: usize,
+    pub deploys: u64,
+    pub deploys_per_block: Option<Summary>,
+    /// Deploys after the first block divided by the time from first to last block
+    pub deploys_per_second: Option<f64>,
+    /// Phlo consumed by main-chain deploys, when costs were fetched
+    pub phlo: Option<u64>,
+    pub validators: Vec<ValidatorShare>,
+}
+
+impl ChainStats {
+    /// Compute statistics over the main chain of `headers`
+    pub fn compute(start: i64, end: i64, headers: &[BlockHeader]) -> Self {
+        let chain = main_chain(headers);
+        let intervals = block_intervals(&chain);
+        let deploy_counts: Vec<u64> = chain.iter().map(|h| h.deploy_count.max(0) as u64).collect();
+
+        let timed: Vec<&&BlockHeader> = chain.iter().filter(|h| h.timestamp > 0).collect();
+        let deploys_per_second = match (timed.first(), timed.last()) {
+            (Some(first), Some(last)) if last.timestamp > first.timestamp => {
+                let after_first: u64 = timed[1..]
+                    .iter()
+                    .map(|h| h.deploy_count.max(0) as u64)
+                    .sum();
+                Some(after_first as f64 * 1000.0 / (last.timestamp - first.timestamp) as f64)
+            }
+            _ => None,
+        };
+
+        Self {
+            start,
+            end,
+            blocks: chain.len(),
+            forked_heights: forked_heights(headers),
+            block_time: Summary::from_samples(intervals.millis),
+            skipped_intervals: intervals.skipped,
+            clamped_intervals: intervals.clamped,
+            deploys: deploy_counts.iter().sum(),
+            deploys_per_block: Summary::from_samples(deploy_counts),
+            deploys_per_second,
+            phlo: None,
+            validators: validator_shares(&chain),
+        }
+    }
+}
+
+/// Total phlo of the main-chain blocks that carry deploys
+///
+/// Fetches each such block's detail; `None` if any fetch fails, so a partial
+/// sum is never reported as the total.
+pub async fn main_chain_phlo(source: &ChainSource, headers: &[BlockHeader]) -> Option<u64> {
+    let mut total = 0;
+    for header in main_chain(headers) {
+        if header.deploy_count <= 0 {
+            continue;
+        }
+        match source.block(&header.block_hash).await {
+            Ok(block) => total += block.deploys.iter().map(|d| d.cost).sum::<u64>(),
+            Err(e) => {
+                tracing::warn!(block = %header.block_hash, error = %e, "could not fetch deploy costs");
+                return None;
+            }
+        }
+    }
+    Some(total)
+}
+
+/// A block with at least one failed system deploy
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FailedSystemBlock {
+    pub block_hash: String,
+    pub block_number: i64,
+    pub sender: String,
+    pub failures: Vec<SystemDeploy>,
+}
+
+/// Failed system deploys over a range of heights (`--system-errors-only`)
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct SystemErrorReport {
+    pub start: i64,
+    pub end: i64,
+    /// Blocks examined, on forks as well as the main chain
+    pub scanned: usize,
+    /// Blocks whose node did not report system deploys
+    pub unreported: usize,
+    /// Failed system deploys by kind
+    pub by_kind: BTreeMap<String, usize>,
+    pub blocks: Vec<FailedSystemBlock>,
+}
+
+impl SystemErrorReport {
+    pub fn new(start: i64, end: i64) -> Self {
+        Self {
+            start,
+            end,
+            ..Self::default()
+        }
+    }
+
+    /// Count one block, keeping it if any of its system deploys failed
+    pub fn add(&mut self, block: &BlockDetail) {
+        self.scanned += 1;
+        if block.system_deploys.is_none() {
+            self.unreported += 1;
+        }
+        let failures: Vec<SystemDeploy> =
+            block.failed_system_deploys().into_iter().cloned().collect();
+        if failures.is_empty() {
+            return;
+        }
+        for failure in &failures {
+            *self.by_kind.entry(failure.kind.to_string()).or_default() += 1;
+        }
+        self.blocks.push(FailedSystemBlock {
+            block_hash: block.header.block_hash.clone(),
+            block_number: block.header.block_number,
+            sender: block.header.sender.clone(),
+            failures,
+        });
+    }
+}
+
+/// Fetch every block in `headers` and collect its failed system deploys
+pub async fn system_errors(
+    source: &ChainSource,
+    start: i64,
+    end: i64,
+    headers: &[BlockHeader],
+) -> Result<SystemErrorReport> {
+    let mut report = SystemErrorReport::new(start, end);
+    for header in headers {
+        report.add(&source.block(&header.block_hash).await?);
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const T0: i64 = 1_700_000_000_000;
+
+    fn header(hash: &str, number: i64, parent: &str, timestamp: i64, deploys: i32) -> BlockHeader {
+        BlockHeader {
+            block_hash: hash.to_string(),
+            block_number: number,
+            sender: format!("v{}", number % 2),
+            seq_num: number,
+            timestamp,
+            shard_id: "root".to_string(),
+            parents: if parent.is_empty() {
+                Vec::new()
+            } else {
+                vec![parent.to_string()]
+            },
+            pre_state_hash: String::new(),
+            post_state_hash: String::new(),
+            deploy_count: deploys,
+            bonds_count: 2,
+        }
+    }
+
+    /// Heights 0..n, one block every second, `n % 3` deploys per block
+    fn linear(n: i64) -> Vec<BlockHeader> {
+        (0..n)
+            .map(|i| {
+                let parent = if i == 0 {
+                    String::new()
+                } else {
+                    format!("b{}", i - 1)
+                };
+                header(
+                    &format!("b{}", i),
+                    i,
+                    &parent,
+                    T0 + i * 1000,
+                    (i % 3) as i32,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_linear_chain_stats() {
+        let stats = ChainStats::compute(0, 9, &linear(10));
+        assert_eq!(stats.blocks, 10);
+        assert_eq!(stats.forked_heights, 0);
+        let block_time = stats.block_time.unwrap();
+        assert_eq!(
+            (block_time.count, block_time.p50, block_time.p95),
+            (9, 1000, 1000)
+        );
+        assert_eq!(block_time.mean, 1000.0);
+        assert_eq!(stats.deploys, 9);
+        assert_eq!(stats.deploys_per_block.unwrap().max, 2);
+        // 9 deploys after block 0 over 9 seconds
+        assert_eq!(stats.deploys_per_second, Some(1.0));
+        assert_eq!(stats.validators.len(), 2);
+        assert_eq!(stats.validators[0].blocks, 5);
+        assert_eq!(stats.validators[0].share, 0.5);
+    }
+
+    #[test]
+    fn test_missing_timestamps_skip_pairs() {
+        let mut headers = linear(5);
+        headers[2].timestamp = 0;
+        let intervals = block_intervals(&main_chain(&headers));
+        assert_eq!(intervals.skipped, 2);
+        assert_eq!(intervals.millis, vec![1000, 1000]);
+    }
+
+    #[test]
+    fn test_clock_skew_clamped_and_counted() {
+        let mut headers = linear(4);
+        // Block 2 claims to be 500ms older than block 1
+        headers[2].timestamp = T0 + 500;
+        let intervals = block_intervals(&main_chain(&headers));
+        assert_eq!(intervals.clamped, 1);
+        assert_eq!(intervals.millis, vec![1000, 0, 2500]);
+        let stats = ChainStats::compute(0, 3, &headers);
+        assert_eq!(stats.clamped_intervals, 1);
+        assert_eq!(stats.block_time.unwrap().min, 0);
+    }
+
+    #[test]
+    fn test_fork_uses_main_chain_only() {
+        let mut headers = linear(5);
+        // An orphan at height 2 with a wildly different timestamp
+        headers.push(header("orphan", 2, "b1", T0 + 60_000, 7));
+        assert_eq!(forked_heights(&headers), 1);
+
+        let chain = main_chain(&headers);
+        let hashes: Vec<&str> = chain.iter().map(|h| h.block_hash.as_str()).collect();
+        assert_eq!(hashes, vec!["b0", "b1", "b2", "b3", "b4"]);
+
+        let stats = ChainStats::compute(0, 4, &headers);
+        assert_eq!(stats.blocks, 5);
+        assert_eq!(stats.forked_heights, 1);
+        assert_eq!(stats.block_time.unwrap().max, 1000);
+        assert_eq!(stats.deploys, 1 + 2 + 0 + 1);
+    }
+
+    #[test]
+    fn test_fork_at_tip_prefers_longest_chain() {
+        let mut headers = linear(3);
+        // Competing tips at height 3; only b3 links back into the range
+        headers.push(header("b3", 3, "b2", T0 + 3000, 0));
+        headers.push(header("a3", 3, "elsewhere", T0 + 3000, 0));
+        let chain = main_chain(&headers);
+        assert_eq!(chain.len(), 4);
+        assert_eq!(chain.last().unwrap().block_hash, "b3");
+    }
+
+    #[test]
+    fn test_empty_range() {
+        let stats = ChainStats::compute(5, 9, &[]);
+        assert_eq!(stats.blocks, 0);
+        assert!(stats.block_time.is_none());
+        assert!(stats.deploys_per_block.is_none());
+        assert!(stats.deploys_per_second.is_none());
+        assert!(stats.validators.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_headers_in_chunks() {
+        use crate::test_support::spawn_http_node;
+        use serde_json::json;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        let port = spawn_http_node(move |line, _body| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            let path = line.split_whitespace().nth(1).unwrap_or("");
+            let range = path.strip_prefix("/api/blocks/").unwrap();
+            let (s, e) = range.split_once('/').unwrap();
+            let (s, e): (i64, i64) = (s.parse().unwrap(), e.parse().unwrap());
+            // Nodes may return a neighbouring height; it must not be counted twice
+            let blocks: Vec<_> = (s..=e + 1)
+                .map(|n| {
+                    json!({
+                        "blockHash": format!("b{}", n),
+                        "blockNumber": n,
+                        "timestamp": T0 + n * 1000,
+                        "parentsHashList": [format!("b{}", n - 1)],
+                        "deployCount": 1
+                    })
+                })
+                .collect();
+            ("200 OK", json!(blocks).to_string())
+        })
+        .await;
+
+        let source = ChainSource::new("127.0.0.1", port);
+        let headers = fetch_headers(&source, 3, 9, 3).await.unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+        let numbers: Vec<i64> = headers.iter().map(|h| h.block_number).collect();
+        assert_eq!(numbers, (3..=9).collect::<Vec<_>>());
+        assert_eq!(headers[1].parents, vec!["b3".to_string()]);
+        assert_eq!(forked_heights(&headers), 0);
+    }
+
+    #[tokio::test]
+    async fn test_system_errors_finds_the_failing_block() {
+        use crate::test_support::spawn_http_node;
+        use serde_json::json;
+
+        // Height 3 failed a slash; height 5 comes from a node without system deploys
+        let port = spawn_http_node(|line, _body| {
+            let path = line.split_whitespace().nth(1).unwrap_or("");
+            if let Some(range) = path.strip_prefix("/api/blocks/") {
+                let (s, e) = range.split_once('/').unwrap();
+                let (s, e): (i64, i64) = (s.parse().unwrap(), e.parse().unwrap());
+                let blocks: Vec<_> = (s..=e)
+                    .map(|n| json!({"blockHash": format!("b{}", n), "blockNumber": n}))
+                    .collect();
+                return ("200 OK", json!(blocks).to_string());
+            }
+            let n: i64 = path.strip_prefix("/api/block/b").unwrap().parse().unwrap();
+            let info = json!({"blockHash": format!("b{}", n), "blockNumber": n, "sender": "04aa"});
+            let body = match n {
+                5 => json!({"blockInfo": info, "deploys": []}),
+                3 => json!({"blockInfo": info, "deploys": [], "systemDeploys": [
+                    {"systemDeployType": "CloseBlockSystemDeploy", "failed": false},
+                    {"systemDeployType": "SlashSystemDeploy", "failed": true,
+                     "errorMsg": "Slashing failed: validator not bonded"}
+                ]}),
+                _ => json!({"blockInfo": info, "deploys": [], "systemDeploys": [
+                    {"systemDeployType": "CloseBlockSystemDeploy", "failed": false}
+                ]}),
+            };
+            ("200 OK", body.to_string())
+        })
+        .await;
+
+        let source = ChainSource::new("127.0.0.1", port);
+        let headers = fetch_headers(&source, 1, 5, 2).await.unwrap();
+        let report = system_errors(&source, 1, 5, &headers).await.unwrap();
+        assert_eq!((report.scanned, report.unreported), (5, 1));
+        assert_eq!(report.blocks.len(), 1);
+        assert_eq!(report.blocks[0].block_hash, "b3");
+        assert_eq!(report.blocks[0].failures.len(), 1);
+        assert_eq!(
+            report.blocks[0].failures[0].error.as_deref(),
+            Some("Slashing failed: validator not bonded")
+        );
+        assert_eq!(report.by_kind.get("Slash"), Some(&1));
+    }
+}
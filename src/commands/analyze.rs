@@ -0,0 +1,56 @@
+use crate::args::AnalyzeArgs;
+use crate::error::{NodeCliError, Result};
+use crate::term_analysis::{analyze, Level};
+use crate::utils::Report;
+
+/// Print a Rholang file's size and complexity against the deploy limits,
+/// failing when it is over one
+pub fn analyze_command(args: &AnalyzeArgs) -> Result<()> {
+    let path = args.file.display().to_string();
+    let term = std::fs::read_to_string(&args.file)
+        .map_err(|e| NodeCliError::file_read_failed(&path, &e.to_string()))?;
+    let limits = args.term_limits.resolve();
+    let stats = analyze(&term);
+    let level = stats.level(&limits);
+
+    if args.output.is_json() {
+        let measures: Vec<_> = stats
+            .measures(&limits)
+            .iter()
+            .map(|m| {
+                serde_json::json!({
+                    "name": m.name,
+                    "value": m.value,
+                    "limit": m.limit,
+                    "percent": m.percent(),
+                    "level": format!("{:?}", m.level()).to_lowercase(),
+                })
+            })
+            .collect();
+        let output = serde_json::json!({
+            "file": path,
+            "bytes": stats.bytes,
+            "lines": stats.lines,
+            "nodes": stats.nodes,
+            "top_level_bindings": stats.top_level_bindings,
+            "max_depth": stats.max_depth,
+            "measures": measures,
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+    } else {
+        let mut report = Report::new();
+        report.line(format!("{}:", path));
+        for line in stats.lines(&limits) {
+            report.line(format!("  {}", line));
+        }
+        report.print();
+    }
+
+    if level == Level::Over {
+        return Err(NodeCliError::General(format!(
+            "{} is over a size or complexity limit",
+            path
+        )));
+    }
+    Ok(())
+}
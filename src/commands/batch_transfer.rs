@@ -0,0 +1,355 @@
+use crate::args::{BatchTransferArgs, NodeConnArgs};
+use crate::batch_transfer::{
+    combined_outcomes, dust_sent, parse_payouts, total_dust, Payout, RowOutcome,
+};
+use crate::commands::network::{build_config, failed_deploy_id, preflight_node, sender_balance};
+use crate::connection_manager::F1r3flyConnectionManager;
+use crate::contract_templates::transfer_contract;
+use crate::error::{NodeCliError, Result};
+use crate::f1r3fly_api::par_to_rholang_json;
+use crate::rholang_helpers::convert_rholang_to_json;
+use crate::transfer_guard;
+use crate::truncation::{shorten, Field};
+use crate::utils::{confirm_on_stdin, print_progress, Report};
+use crate::vault::{build_payout_rholang, format_rev};
+
+/// Pay every row of a CSV file from the signing key, in one deploy or one deploy per row
+pub async fn batch_transfer_command(args: &BatchTransferArgs, assume_yes: bool) -> Result<()> {
+    let text = std::fs::read_to_string(&args.file).map_err(|e| {
+        NodeCliError::file_read_failed(&args.file.display().to_string(), &e.to_string())
+    })?;
+    let payouts = parse_payouts(&text)?;
+    let total = total_dust(&payouts)?;
+    let manager_for = |node: &NodeConnArgs| {
+        F1r3flyConnectionManager::new(build_config(
+            node,
+            &args.observer,
+            &args.signing.private_key,
+            args.wait.max_wait,
+            args.wait.max_wait,
+            args.wait.check_interval,
+            &args.wait.poll,
+        ))
+    };
+
+    if args.dry_run {
+        let from = manager_for(&args.node).get_address()?;
+        let mut report = Report::new();
+        if args.sequential {
+            for payout in &payouts {
+                report
+                    .line(format!(
+                        "// line {}: {} tokens to {}",
+                        payout.line, payout.amount, payout.address
+                    ))
+                    .line(transfer_contract(
+                        &from,
+                        &payout.address,
+                        payout.amount.dust(),
+                    )?);
+            }
+        } else {
+            report.line(combined_term(&from, &payouts));
+        }
+        report
+            .note(format!(
+                "Dry run: {} transfer(s), {} tokens in total; nothing deployed",
+                payouts.len(),
+                format_rev(total.into())
+            ))
+            .print();
+        return Ok(());
+    }
+
+    let node = preflight_node(&args.node, args.skip_preflight).await?;
+    let manager = manager_for(&node);
+    let from = manager.get_address()?;
+    let deploys = if args.sequential { payouts.len() } else { 1 };
+    if !args.force {
+        if let Some(balance) = sender_balance(&node.host, node.http_port, &from).await {
+            let fee = transfer_guard::max_phlo_fee(true).saturating_mul(deploys as u64);
+            transfer_guard::check_sender_balance(balance, total, fee)
+                .map_err(NodeCliError::General)?;
+        }
+    }
+    let summary = [
+        ("From", from.clone()),
+        ("Recipients", payouts.len().to_string()),
+        (
+            "Total",
+            format!("{} tokens ({} dust)", format_rev(total.into()), total),
+        ),
+        (
+            "Deploys",
+            if args.sequential {
+                format!("{}, one per row", deploys)
+            } else {
+                "1, all rows together".to_string()
+            },
+        ),
+        ("Node", format!("{}:{}", node.host, node.grpc_port)),
+    ];
+    confirm_on_stdin("batch transfer", &summary, "yes", assume_yes)?;
+
+    let outcomes = if args.sequential {
+        send_sequential(&manager, &from, &payouts).await
+    } else {
+        send_combined(&manager, &from, &payouts).await
+    };
+
+    let failed = outcomes.iter().filter(|o| o.is_failed()).count();
+    batch_report(&payouts, &outcomes).print();
+    if failed > 0 {
+        return Err(NodeCliError::General(format!(
+            "{} of {} transfer(s) failed",
+            failed,
+            payouts.len()
+        )));
+    }
+    Ok(())
+}
+
+/// One deploy paying every row
+fn combined_term(from: &str, payouts: &[Payout]) -> String {
+    let pairs: Vec<(&str, u64)> = payouts
+        .iter()
+        .map(|p| (p.address.as_str(), p.amount.dust()))
+        .collect();
+    build_payout_rholang(from, &pairs)
+}
+
+/// Every row failed together, as when the one deploy carrying them failed
+fn all_failed(count: usize, deploy_id: Option<&str>, error: &str) -> Vec<RowOutcome> {
+    vec![
+        RowOutcome::DeployFailed {
+            deploy_id: deploy_id.map(str::to_string),
+            error: error.to_string(),
+        };
+        count
+    ]
+}
+
+async fn send_combined(
+    manager: &F1r3flyConnectionManager,
+    from: &str,
+    payouts: &[Payout],
+) -> Vec<RowOutcome> {
+    print_progress(&format!(
+        "Sending {} transfer(s) in one deploy and waiting for finalization...",
+        payouts.len()
+    ));
+    match manager
+        .deploy_and_wait(&combined_term(from, payouts), true, 0)
+        .await
+    {
+        Ok(result) if result.errored => all_failed(
+            payouts.len(),
+            Some(&result.deploy_id),
+            &format!(
+                "combined deploy errored: {}",
+                result
+                    .system_deploy_error
+                    .as_deref()
+                    .unwrap_or("no error message given")
+            ),
+        ),
+        Ok(result) => {
+            let results: Vec<serde_json::Value> = result
+                .data
+                .iter()
+                .filter_map(|par| convert_rholang_to_json(&par_to_rholang_json(par)).ok())
+                .collect();
+            combined_outcomes(payouts.len(), &result.deploy_id, &results)
+        }
+        Err(e) => all_failed(payouts.len(), failed_deploy_id(&e), &e.to_string()),
+    }
+}
+
+async fn send_sequential(
+    manager: &F1r3flyConnectionManager,
+    from: &str,
+    payouts: &[Payout],
+) -> Vec<RowOutcome> {
+    let mut outcomes = Vec::with_capacity(payouts.len());
+    for (n, payout) in payouts.iter().enumerate() {
+        print_progress(&format!(
+            "Transfer {}/{}: {} tokens to {}",
+            n + 1,
+            payouts.len(),
+            payout.amount,
+            payout.address
+        ));
+        let outcome = match transfer_contract(from, &payout.address, payout.amount.dust()) {
+            Ok(term) => match manager.deploy_and_wait(&term, true, 0).await {
+                Ok(result) if result.errored => RowOutcome::DeployFailed {
+                    error: result
+                        .system_deploy_error
+                        .unwrap_or_else(|| "deploy errored".to_string()),
+                    deploy_id: Some(result.deploy_id),
+                },
+                Ok(result) => RowOutcome::Sent {
+                    deploy_id: result.deploy_id,
+                },
+                Err(e) => RowOutcome::DeployFailed {
+                    deploy_id: failed_deploy_id(&e).map(str::to_string),
+                    error: e.to_string(),
+                },
+            },
+            Err(e) => RowOutcome::Failed {
+                deploy_id: None,
+                error: e.to_string(),
+            },
+        };
+        outcomes.push(outcome);
+    }
+    outcomes
+}
+
+/// Per-row status, totals, and which rows each failed deploy carried
+fn batch_report(payouts: &[Payout], outcomes: &[RowOutcome]) -> Report {
+    let mut report = Report::new();
+    for (payout, outcome) in payouts.iter().zip(outcomes) {
+        let status = match outcome {
+            RowOutcome::Sent { deploy_id } => {
+                format!("sent in {}", shorten(deploy_id, Field::Hash))
+            }
+            RowOutcome::Failed { error, .. } | RowOutcome::DeployFailed { error, .. } => {
+                format!("FAILED: {}", error)
+            }
+            RowOutcome::Unconfirmed { deploy_id } => format!(
+                "unconfirmed: {} finalized without a result for this row",
+                shorten(deploy_id, Field::Hash)
+            ),
+        };
+        let memo = payout
+            .memo
+            .as_deref()
+            .map(|memo| format!(" ({})", memo))
+            .unwrap_or_default();
+        report.line(format!(
+            "line {}: {} tokens to {}{}: {}",
+            payout.line,
+            payout.amount,
+            shorten(&payout.address, Field::Key),
+            memo,
+            status
+        ));
+    }
+
+    let count = |pick: fn(&RowOutcome) -> bool| outcomes.iter().filter(|o| pick(o)).count();
+    let moved = dust_sent(payouts, outcomes);
+    report
+        .line(format!(
+            "Sent: {}, Failed: {}, Unconfirmed: {}",
+            count(|o| matches!(o, RowOutcome::Sent { .. })),
+            count(RowOutcome::is_failed),
+            count(|o| matches!(o, RowOutcome::Unconfirmed { .. }))
+        ))
+        .line(format!(
+            "Moved: {} dust ({} tokens)",
+            moved,
+            format_rev(moved)
+        ));
+
+    // An errored deploy applies none of its transfers; name the rows it carried.
+    // A transfer refused inside a deploy that ran is only its row's failure.
+    let mut failed_deploys: Vec<(&str, Vec<usize>)> = Vec::new();
+    for (payout, outcome) in payouts.iter().zip(outcomes) {
+        if let RowOutcome::DeployFailed {
+            deploy_id: Some(deploy_id),
+            ..
+        } = outcome
+        {
+            match failed_deploys
+                .iter_mut()
+                .find(|(id, _)| *id == deploy_id.as_str())
+            {
+                Some((_, lines)) => lines.push(payout.line),
+                None => failed_deploys.push((deploy_id.as_str(), vec![payout.line])),
+            }
+        }
+    }
+    for (deploy_id, lines) in failed_deploys {
+        let lines: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+        report.line(format!(
+            "Failed deploy {} carried line(s) {}",
+            shorten(deploy_id, Field::Hash),
+            lines.join(", ")
+        ));
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::capture::Capture;
+
+    const A: &str = "1111AtahZeefej4tvVR6ti9TJtv8yxLebT31SCEVDCKMNikBk5r3g";
+    const B: &str = "111127RX5ZgiAdRaQy4AWy57RdvAAckdELReEBxzvWYVvdnR32PiHA";
+
+    #[test]
+    fn test_batch_report_names_rows_of_failed_deploys() {
+        let payouts = parse_payouts(&format!("{A},1.5,rent\n{B},2\n{A},3\n")).unwrap();
+        let outcomes = [
+            RowOutcome::Sent {
+                deploy_id: "d1".to_string(),
+            },
+            RowOutcome::DeployFailed {
+                deploy_id: Some("d2".to_string()),
+                error: "Insufficient funds".to_string(),
+            },
+            RowOutcome::DeployFailed {
+                deploy_id: Some("d2".to_string()),
+                error: "Insufficient funds".to_string(),
+            },
+        ];
+        let capture = Capture::start(false);
+        batch_report(&payouts, &outcomes).print();
+        let lines = capture.finish();
+        assert_eq!(
+            lines[0],
+            format!(
+                "line 1: 1.5 tokens to {} (rent): sent in d1",
+                shorten(A, Field::Key)
+            )
+        );
+        assert!(
+            lines[1].ends_with("FAILED: Insufficient funds"),
+            "{:?}",
+            lines
+        );
+        assert!(lines.contains(&"Sent: 1, Failed: 2, Unconfirmed: 0".to_string()));
+        assert!(lines.contains(&"Moved: 150000000 dust (1.5 tokens)".to_string()));
+        assert!(lines.contains(&"Failed deploy d2 carried line(s) 2, 3".to_string()));
+    }
+
+    #[test]
+    fn test_batch_report_keeps_row_failures_to_their_rows() {
+        // The combined deploy ran; the vault refused one of its transfers
+        let payouts = parse_payouts(&format!("{A},1\n{B},2\n")).unwrap();
+        let outcomes = [
+            RowOutcome::Sent {
+                deploy_id: "d1".to_string(),
+            },
+            RowOutcome::Failed {
+                deploy_id: Some("d1".to_string()),
+                error: "Insufficient funds".to_string(),
+            },
+        ];
+        let capture = Capture::start(false);
+        batch_report(&payouts, &outcomes).print();
+        let lines = capture.finish();
+        assert!(
+            lines[1].ends_with("FAILED: Insufficient funds"),
+            "{:?}",
+            lines
+        );
+        assert!(lines.contains(&"Sent: 1, Failed: 1, Unconfirmed: 0".to_string()));
+        assert!(
+            !lines.iter().any(|line| line.starts_with("Failed deploy")),
+            "{:?}",
+            lines
+        );
+    }
+}
@@ -0,0 +1,200 @@
+use crate::args::ChainStatsArgs;
+use crate::capabilities::{self, Capability};
+use crate::chain_stats::{
+    fetch_headers, main_chain_phlo, system_errors, ChainStats, SystemErrorReport,
+};
+use crate::error::{NodeCliError, Result};
+use crate::export::ChainSource;
+use crate::f1r3fly_api::F1r3flyApi;
+use crate::truncation::{shorten, Field};
+use crate::utils::Summary;
+
+/// Resolve `--start`/`--end`/`--last` to an inclusive height range, ending at
+/// the node's tip when `end` is not given
+pub(crate) async fn resolve_height_range(
+    host: &str,
+    http_port: u16,
+    private_key: Option<&str>,
+    start: Option<i64>,
+    end: Option<i64>,
+    last: Option<i64>,
+) -> Result<(i64, i64)> {
+    let end = match end {
+        Some(end) => end,
+        None => F1r3flyApi::for_queries(private_key, host, 0)?
+            .get_tip_block_number(http_port)
+            .await?
+            .ok_or_else(|| NodeCliError::network_connection_failed("node reported no tip block"))?,
+    };
+    let start = match (start, last) {
+        (Some(start), _) => start,
+        (None, Some(last)) if last >= 1 => (end - last + 1).max(0),
+        (None, Some(_)) => {
+            return Err(NodeCliError::config_invalid_value(
+                "last",
+                "must be at least 1",
+            ))
+        }
+        (None, None) => return Err(NodeCliError::config_missing_required("--start or --last")),
+    };
+    if start < 0 || start > end {
+        return Err(NodeCliError::config_invalid_value(
+            "start",
+            &format!("{}..={} is not a valid height range", start, end),
+        ));
+    }
+    Ok((start, end))
+}
+
+/// Chain throughput over a height range, as a report or JSON
+pub async fn chain_stats_command(args: &ChainStatsArgs) -> Result<()> {
+    if args.chunk_size < 1 {
+        return Err(NodeCliError::config_invalid_value(
+            "chunk-size",
+            "must be at least 1",
+        ));
+    }
+    capabilities::require(&args.host, args.port, Capability::BlocksByHeight).await?;
+    if args.system_errors_only {
+        capabilities::require(&args.host, args.port, Capability::SystemDeploys).await?;
+    }
+    let (start, end) = resolve_height_range(
+        &args.host,
+        args.port,
+        args.query_key.private_key.as_deref(),
+        args.start,
+        args.end,
+        args.last,
+    )
+    .await?;
+    let source = ChainSource::new(&args.host, args.port);
+
+    eprintln!(
+        "Fetching heights {}..={} from {}:{}",
+        start, end, args.host, args.port
+    );
+    let headers = fetch_headers(&source, start, end, args.chunk_size).await?;
+    if args.system_errors_only {
+        let report = system_errors(&source, start, end, &headers).await?;
+        if args.output.is_json() {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            print_system_errors(&report);
+        }
+        return Ok(());
+    }
+    let mut stats = ChainStats::compute(start, end, &headers);
+    if stats.forked_heights > 0 {
+        eprintln!(
+            "Warning: {} height(s) hold more than one block; statistics cover the main chain only",
+            stats.forked_heights
+        );
+    }
+    if !args.skip_phlo {
+        stats.phlo = main_chain_phlo(&source, &headers).await;
+    }
+
+    if args.output.is_json() {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+        return Ok(());
+    }
+    print_report(&stats);
+    Ok(())
+}
+
+/// Seconds with two decimals; block times are too short for whole-second rounding
+fn secs(millis: f64) -> String {
+    format!("{:.2}s", millis / 1000.0)
+}
+
+fn print_report(stats: &ChainStats) {
+    println!(
+        "Heights {}..={}: {} main-chain block(s)",
+        stats.start, stats.end, stats.blocks
+    );
+
+    match &stats.block_time {
+        Some(time) => println!(
+            "Block time       avg {}  p50 {}  p95 {}  max {}  ({} intervals)",
+            secs(time.mean),
+            secs(time.p50 as f64),
+            secs(time.p95 as f64),
+            secs(time.max as f64),
+            time.count
+        ),
+        None => println!("Block time       not enough timestamps"),
+    }
+    if stats.skipped_intervals > 0 || stats.clamped_intervals > 0 {
+        println!(
+            "                 {} interval(s) skipped for missing timestamps, {} negative clamped to 0",
+            stats.skipped_intervals, stats.clamped_intervals
+        );
+    }
+
+    if let Some(per_block) = &stats.deploys_per_block {
+        print_deploys(stats.deploys, per_block);
+    }
+    match stats.deploys_per_second {
+        Some(rate) => println!("Deploys/second   {:.3}", rate),
+        None => println!("Deploys/second   n/a"),
+    }
+    match stats.phlo {
+        Some(phlo) => println!("Phlo consumed    {}", phlo),
+        None => println!("Phlo consumed    n/a"),
+    }
+
+    if !stats.validators.is_empty() {
+        println!("\nBlocks by validator");
+        for share in &stats.validators {
+            println!(
+                "  {:>6} {:>6.1}%  {}",
+                share.blocks,
+                share.share * 100.0,
+                share.validator
+            );
+        }
+    }
+}
+
+fn print_deploys(total: u64, per_block: &Summary) {
+    println!(
+        "Deploys/block    avg {:.2}  p50 {}  p95 {}  max {}  ({} total)",
+        per_block.mean, per_block.p50, per_block.p95, per_block.max, total
+    );
+}
+
+fn print_system_errors(report: &SystemErrorReport) {
+    println!(
+        "Heights {}..={}: {} block(s) scanned, {} with failed system deploys",
+        report.start,
+        report.end,
+        report.scanned,
+        report.blocks.len()
+    );
+    if report.unreported > 0 {
+        println!(
+            "                 {} block(s) without system deploys (not provided by this node version)",
+            report.unreported
+        );
+    }
+    if !report.by_kind.is_empty() {
+        println!("\nFailures by kind");
+        for (kind, count) in &report.by_kind {
+            println!("  {:>6}  {}", count, kind);
+        }
+    }
+    if !report.blocks.is_empty() {
+        println!("\nBlocks");
+        for block in &report.blocks {
+            for failure in &block.failures {
+                println!(
+                    "  #{:<8} {}  {:<10} {}",
+                    block.block_number,
+                    shorten(&block.block_hash, Field::Column),
+                    failure.kind,
+                    failure.error.as_deref().unwrap_or("(no error message)")
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,371 @@
+use crate::args::CompareStateArgs;
+use crate::error::{NodeCliError, Result};
+use crate::f1r3fly_api::{BlockSummary, F1r3flyApi};
+use crate::ports;
+use crate::topology::{NodeEndpoint, Topology};
+use futures_util::future::join_all;
+
+const HASH_DISPLAY_LEN: usize = 16;
+
+/// What a single node reported for the requested block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeBlockState {
+    Found(BlockSummary),
+    /// The node answered but does not have the block
+    Missing,
+    Unreachable(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct NodeStateReport {
+    pub node: NodeEndpoint,
+    pub state: NodeBlockState,
+}
+
+/// State hashes of one block across several nodes
+///
+/// The reference pair is the (pre, post) state hash reported by the most
+/// nodes; ties go to the node listed first.
+#[derive(Debug, Clone)]
+pub struct StateComparison {
+    pub block_hash: String,
+    pub reports: Vec<NodeStateReport>,
+    pub reference: Option<(String, String)>,
+}
+
+impl StateComparison {
+    pub fn new(block_hash: &str, reports: Vec<NodeStateReport>) -> Self {
+        let mut counts: Vec<((String, String), usize)> = Vec::new();
+        for report in &reports {
+            if let NodeBlockState::Found(block) = &report.state {
+                let pair = (block.pre_state_hash.clone(), block.post_state_hash.clone());
+                match counts.iter_mut().find(|(p, _)| *p == pair) {
+                    Some((_, count)) => *count += 1,
+                    None => counts.push((pair, 1)),
+                }
+            }
+        }
+
+        // max_by_key returns the last maximum, so scan in reverse to prefer the first
+        let reference = counts
+            .into_iter()
+            .rev()
+            .max_by_key(|(_, count)| *count)
+            .map(|(pair, _)| pair);
+
+        Self {
+            block_hash: block_hash.to_string(),
+            reports,
+            reference,
+        }
+    }
+
+    /// True if the node has the block but its state hashes differ from the reference
+    pub fn is_divergent(&self, report: &NodeStateReport) -> bool {
+        match (&report.state, &self.reference) {
+            (NodeBlockState::Found(block), Some((pre, post))) => {
+                block.pre_state_hash != *pre || block.post_state_hash != *post
+            }
+            _ => false,
+        }
+    }
+
+    pub fn agreeing_count(&self) -> usize {
+        self.reports
+            .iter()
+            .filter(|r| matches!(r.state, NodeBlockState::Found(_)) && !self.is_divergent(r))
+            .count()
+    }
+
+    pub fn divergent_count(&self) -> usize {
+        self.reports.iter().filter(|r| self.is_divergent(r)).count()
+    }
+
+    pub fn missing_count(&self) -> usize {
+        self.reports
+            .iter()
+            .filter(|r| r.state == NodeBlockState::Missing)
+            .count()
+    }
+
+    pub fn unreachable_count(&self) -> usize {
+        self.reports
+            .iter()
+            .filter(|r| matches!(r.state, NodeBlockState::Unreachable(_)))
+            .count()
+    }
+
+    /// Render the per-node table, the full hashes of any divergent node and a summary
+    pub fn render(&self) -> String {
+        let name_width = self
+            .reports
+            .iter()
+            .map(|r| r.node.name.len())
+            .max()
+            .unwrap_or(4)
+            .max(4);
+        let hash_width = HASH_DISPLAY_LEN + 1;
+
+        let mut out = format!("State comparison for block {}\n\n", self.block_hash);
+        out.push_str(&format!(
+            "{:<name_width$}  {:>8}  {:<hash_width$}  {:<hash_width$}  STATUS\n",
+            "NODE", "BLOCK #", "PRE-STATE", "POST-STATE",
+        ));
+
+        for report in &self.reports {
+            let line = match &report.state {
+                NodeBlockState::Found(block) => {
+                    let (ref_pre, ref_post) = self.reference.clone().unwrap_or_default();
+                    format!(
+                        "{:<name_width$}  {:>8}  {:<hash_width$}  {:<hash_width$}  {}",
+                        report.node.name,
+                        block.block_number,
+                        mark_hash(&block.pre_state_hash, &ref_pre),
+                        mark_hash(&block.post_state_hash, &ref_post),
+                        if self.is_divergent(report) {
+                            "DIVERGENT"
+                        } else {
+                            "agree"
+                        }
+                    )
+                }
+                NodeBlockState::Missing => format!(
+                    "{:<name_width$}  {:>8}  {:<hash_width$}  {:<hash_width$}  missing",
+                    report.node.name, "-", "-", "-"
+                ),
+                NodeBlockState::Unreachable(err) => format!(
+                    "{:<name_width$}  {:>8}  {:<hash_width$}  {:<hash_width$}  unreachable ({})",
+                    report.node.name, "-", "-", "-", err
+                ),
+            };
+            out.push_str(line.trim_end());
+            out.push('\n');
+        }
+
+        if let Some((ref_pre, ref_post)) = &self.reference {
+            let divergent: Vec<&NodeStateReport> = self
+                .reports
+                .iter()
+                .filter(|r| self.is_divergent(r))
+                .collect();
+            if !divergent.is_empty() {
+                out.push_str("\nDivergent nodes:\n");
+                for report in divergent {
+                    if let NodeBlockState::Found(block) = &report.state {
+                        out.push_str(&format!(
+                            "  {} ({})\n",
+                            report.node.name,
+                            report.node.address()
+                        ));
+                        if block.pre_state_hash != *ref_pre {
+                            out.push_str(&format!("    pre-state   expected {}\n", ref_pre));
+                            out.push_str(&format!(
+                                "                found    {}\n",
+                                block.pre_state_hash
+                            ));
+                        }
+                        if block.post_state_hash != *ref_post {
+                            out.push_str(&format!("    post-state  expected {}\n", ref_post));
+                            out.push_str(&format!(
+                                "                found    {}\n",
+                                block.post_state_hash
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+
+        out.push_str(&format!(
+            "\nSummary: {} agree, {} divergent, {} missing, {} unreachable\n",
+            self.agreeing_count(),
+            self.divergent_count(),
+            self.missing_count(),
+            self.unreachable_count()
+        ));
+        out
+    }
+}
+
+/// Shorten a hash for the table, prefixing `!` when it differs from the reference
+fn mark_hash(hash: &str, reference: &str) -> String {
+    let short: String = if hash.is_empty() {
+        "(empty)".to_string()
+    } else {
+        hash.chars().take(HASH_DISPLAY_LEN).collect()
+    };
+    if hash == reference {
+        format!(" {}", short)
+    } else {
+        format!("!{}", short)
+    }
+}
+
+/// Fetch the block from every node concurrently
+pub async fn fetch_node_states(
+    nodes: &[NodeEndpoint],
+    block_hash: &str,
+    private_key: Option<&str>,
+) -> Vec<NodeStateReport> {
+    let fetches = nodes.iter().map(|node| async move {
+        let state = match F1r3flyApi::for_queries(
+            private_key,
+            &node.host,
+            node.grpc_port.unwrap_or(ports::VALIDATOR1.grpc_internal),
+        ) {
+            Ok(api) => match api.get_block_summary(block_hash, node.http_port).await {
+                Ok(Some(block)) => NodeBlockState::Found(block),
+                Ok(None) => NodeBlockState::Missing,
+                Err(e) => NodeBlockState::Unreachable(e.to_string()),
+            },
+            Err(e) => NodeBlockState::Unreachable(e.to_string()),
+        };
+        NodeStateReport {
+            node: node.clone(),
+            state,
+        }
+    });
+    join_all(fetches).await
+}
+
+/// Compare a block's pre/post state hashes across nodes
+pub async fn compare_state_command(args: &CompareStateArgs) -> Result<()> {
+    let topology = Topology::resolve(args.topology.as_deref(), &args.nodes)?;
+    if topology.nodes.len() < 2 {
+        return Err(NodeCliError::config_invalid_value(
+            "node",
+            "at least two nodes are required (use --node or --topology)",
+        ));
+    }
+
+    let reports = fetch_node_states(
+        &topology.nodes,
+        &args.block_hash,
+        args.query_key.private_key.as_deref(),
+    )
+    .await;
+    let comparison = StateComparison::new(&args.block_hash, reports);
+    print!("{}", comparison.render());
+
+    if comparison.reference.is_none() {
+        return Err(NodeCliError::General(format!(
+            "No node returned block {}",
+            args.block_hash
+        )));
+    }
+    if comparison.divergent_count() > 0 {
+        return Err(NodeCliError::General(format!(
+            "State hashes diverge on {} of {} node(s)",
+            comparison.divergent_count(),
+            topology.nodes.len()
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::QueryKeyArgs;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    const BLOCK: &str = "b10c";
+
+    fn block_json(pre: &str, post: &str) -> serde_json::Value {
+        serde_json::json!({
+            "blockInfo": {
+                "blockHash": BLOCK,
+                "blockNumber": 12,
+                "sender": "04aa",
+                "seqNum": 3,
+                "preStateHash": pre,
+                "postStateHash": post
+            },
+            "deploys": []
+        })
+    }
+
+    /// Minimal HTTP node answering `/api/block/{BLOCK}` with `body`, or 404 when `None`
+    async fn spawn_mock_node(body: Option<serde_json::Value>) -> u16 {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = vec![0u8; 4096];
+                let n = socket.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]).to_string();
+                let path = request.split_whitespace().nth(1).unwrap_or("");
+                let (status, body) = match &body {
+                    Some(b) if path == format!("/api/block/{}", BLOCK) => ("200 OK", b.to_string()),
+                    _ => ("404 Not Found", "{}".to_string()),
+                };
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn test_compare_state_detects_divergent_node() {
+        let good = block_json(&"a".repeat(64), &"b".repeat(64));
+        let bad = block_json(&"a".repeat(64), &"c".repeat(64));
+        let nodes = vec![
+            format!("v1=127.0.0.1:{}", spawn_mock_node(Some(good.clone())).await),
+            format!("v2=127.0.0.1:{}", spawn_mock_node(Some(good)).await),
+            format!("v3=127.0.0.1:{}", spawn_mock_node(Some(bad)).await),
+            format!("obs=127.0.0.1:{}", spawn_mock_node(None).await),
+        ];
+
+        let topology = Topology::resolve(None, &nodes).unwrap();
+        let comparison =
+            StateComparison::new(BLOCK, fetch_node_states(&topology.nodes, BLOCK, None).await);
+        assert_eq!(comparison.agreeing_count(), 2);
+        assert_eq!(comparison.divergent_count(), 1);
+        assert_eq!(comparison.missing_count(), 1);
+
+        let rendered = comparison.render();
+        let v3_row = rendered.lines().find(|l| l.starts_with("v3")).unwrap();
+        assert!(v3_row.ends_with("DIVERGENT"));
+        assert!(v3_row.contains(&format!("!{}", "c".repeat(HASH_DISPLAY_LEN))));
+        assert!(v3_row.contains(&format!(" {}", "a".repeat(HASH_DISPLAY_LEN))));
+        assert!(rendered
+            .lines()
+            .any(|l| l.starts_with("obs") && l.ends_with("missing")));
+        assert!(rendered.contains(&format!("expected {}", "b".repeat(64))));
+        assert!(rendered.contains(&format!("found    {}", "c".repeat(64))));
+        assert!(!rendered.contains("pre-state   expected"));
+
+        let args = CompareStateArgs {
+            block_hash: BLOCK.to_string(),
+            nodes,
+            topology: None,
+            query_key: QueryKeyArgs::default(),
+        };
+        let err = compare_state_command(&args).await.unwrap_err();
+        assert!(err.to_string().contains("diverge on 1 of 4"));
+    }
+
+    #[tokio::test]
+    async fn test_compare_state_missing_is_not_divergent() {
+        let good = block_json("aa", "bb");
+        let nodes = vec![
+            format!("127.0.0.1:{}", spawn_mock_node(Some(good.clone())).await),
+            format!("127.0.0.1:{}", spawn_mock_node(Some(good)).await),
+            format!("127.0.0.1:{}", spawn_mock_node(None).await),
+        ];
+        let args = CompareStateArgs {
+            block_hash: BLOCK.to_string(),
+            nodes,
+            topology: None,
+            query_key: QueryKeyArgs::default(),
+        };
+        assert!(compare_state_command(&args).await.is_ok());
+    }
+}
@@ -0,0 +1,21 @@
+use crate::args::{Cli, ConfigAction, ConfigArgs};
+use crate::effective_config::EffectiveConfig;
+use crate::error::{NodeCliError, Result};
+use clap::CommandFactory;
+
+/// Run a `config` subcommand
+pub fn config_command(args: &ConfigArgs) -> Result<()> {
+    match &args.action {
+        ConfigAction::Effective(effective) => {
+            let argv =
+                std::iter::once("node_cli").chain(effective.command.iter().map(String::as_str));
+            let matches = Cli::command()
+                .try_get_matches_from(argv)
+                .map_err(|e| NodeCliError::config_invalid_value("command", &e.to_string()))?;
+            for line in EffectiveConfig::from_matches(&matches).lines() {
+                println!("{}", line);
+            }
+            Ok(())
+        }
+    }
+}
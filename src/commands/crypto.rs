@@ -1,6 +1,6 @@
 use crate::args::*;
 use crate::error::{NodeCliError, Result};
-use crate::utils::{print_key, print_success, CryptoUtils};
+use crate::utils::{display_address, print_key, print_qr, print_success, CryptoUtils};
 use std::fs;
 use std::path::Path;
 
@@ -77,6 +77,16 @@ pub fn generate_key_pair_command(args: &GenerateKeyPairArgs) -> Result<()> {
         print_key(&format!("Public key ({})", key_type), &public_key_hex);
     }
 
+    // The address is derived from the uncompressed key whichever form was printed
+    let vault_address = CryptoUtils::generate_vault_address(&CryptoUtils::serialize_public_key(
+        &public_key,
+        false,
+    ))?;
+    print_key("Vault address", &display_address(&vault_address));
+    if args.qr {
+        print_qr(&vault_address);
+    }
+
     Ok(())
 }
 
@@ -109,7 +119,10 @@ pub fn generate_vault_address_command(args: &GenerateVaultAddressArgs) -> Result
 
     // Print the result using output utils
     print_key("Public key", &public_key_hex);
-    print_key("Vault address", &vault_address);
+    print_key("Vault address", &display_address(&vault_address));
+    if args.qr {
+        print_qr(&vault_address);
+    }
 
     Ok(())
 }
@@ -1,126 +1,293 @@
+use std::collections::BTreeMap;
+
 use chrono::{TimeZone, Utc};
 use futures_util::StreamExt;
 use tokio::sync::mpsc;
 use tokio_tungstenite::connect_async;
 
 use crate::args::DagArgs;
-use crate::dag::{BlockStatus, DagApp, DagBlock, DagEvent};
-use crate::error::NodeCliError;
+use crate::capabilities::{self, Capability};
+use crate::dag::{
+    prefs, BlockStatus, DagApp, DagBlock, DagDeploy, DagEvent, PartialPrefs, PrefsFile,
+};
+use crate::error::{NodeCall, NodeCliError};
+use crate::events::{read_dump, replay, EventDump, RecordedFrame};
+use crate::f1r3fly_api::{BlockDetail, SystemDeploy};
+use crate::wallet_watch::WalletWatch;
+
+/// Most blocks whose deploys are fetched at once for highlighting
+const MAX_DEPLOY_FETCHES: usize = 4;
 
 /// Run the DAG visualization command
 pub async fn run_dag(args: &DagArgs) -> Result<(), NodeCliError> {
-    println!("Loading blocks from {}:{}...", args.host, args.http_port);
+    if let Some(path) = &args.dump.from_dump {
+        println!("Replaying events from {}...", path.display());
+    } else {
+        println!("Loading blocks from {}:{}...", args.host, args.http_port);
+    }
 
     // Create channel for WebSocket events
     let (tx, rx) = mpsc::channel::<DagEvent>(100);
 
-    // Create the app
+    // Create the app; flags override the settings saved by the last session
     let mut app = DagApp::new();
-    app.renderer.show_deploys = args.show_deploys;
+    let prefs_file = PrefsFile::open_default();
+    let saved = match &prefs_file {
+        Some(file) if args.reset_ui => {
+            if let Err(e) = file.reset() {
+                eprintln!("Warning: could not reset {}: {}", file.path().display(), e);
+            }
+            PartialPrefs::default()
+        }
+        Some(file) => file.load().unwrap_or_else(|reason| {
+            eprintln!(
+                "Warning: ignoring saved DAG settings in {}: {}",
+                file.path().display(),
+                reason
+            );
+            PartialPrefs::default()
+        }),
+        None => PartialPrefs::default(),
+    };
+    let flags = PartialPrefs {
+        show_deploys: args.show_deploys,
+        use_color: args.color,
+        relayout_interval_ms: args.relayout_interval.map(|d| d.as_millis() as u64),
+    };
+    app.apply_prefs(prefs::resolve(&flags, &saved));
+    app.renderer.identities = args.identity.load()?;
+
+    // Highlighting needs each block's deployers, and the detail view a
+    // block's system deploys, fetched in the background
+    let targets: Vec<_> = args
+        .highlight_deployer
+        .iter()
+        .chain(&args.highlight_address)
+        .cloned()
+        .collect();
+    if !targets.is_empty() || args.dump.from_dump.is_none() {
+        let (requests_tx, requests_rx) = mpsc::unbounded_channel();
+        app = if targets.is_empty() {
+            app.with_deploy_loader(requests_tx)
+        } else {
+            app.with_highlight(WalletWatch::new(&targets)?, requests_tx)
+        };
+        let api_base = format!("http://{}:{}", args.host, args.http_port);
+        tokio::spawn(run_deploy_loader(api_base, requests_rx, tx.clone()));
+    }
 
-    // Load initial blocks
-    let blocks = fetch_initial_blocks(&args.host, args.http_port, args.depth).await?;
-    app.load_blocks(blocks);
+    if let Some(path) = &args.dump.from_dump {
+        // A replay builds the DAG from the recorded frames alone
+        let contents = read_dump(path).map_err(|e| {
+            NodeCliError::file_read_failed(&path.display().to_string(), &e.to_string())
+        })?;
+        if contents.skipped > 0 {
+            eprintln!(
+                "Warning: skipped {} lines of {} that are not recorded frames",
+                contents.skipped,
+                path.display()
+            );
+        }
+        app = app.with_event_receiver(rx);
+        tokio::spawn(run_replay(
+            path.display().to_string(),
+            contents.frames,
+            args.dump.replay_speed,
+            tx.clone(),
+        ));
+    } else {
+        // Load initial blocks
+        let (blocks, drift) = fetch_initial_blocks(&args.host, args.http_port, args.depth).await?;
+        app.load_blocks(blocks);
+        if let Some(summary) = drift.summary() {
+            eprintln!("Warning: {}", summary);
+            app.status_message = summary;
+        }
 
-    // Set up event receiver if live mode
-    if !args.no_live {
+        // Events carry live blocks and highlighting's deploy fetches
         app = app.with_event_receiver(rx);
+    }
+
+    // Set up the WebSocket listener if live mode
+    if !args.no_live && args.dump.from_dump.is_none() {
+        capabilities::require(&args.host, args.http_port, Capability::WebsocketEvents).await?;
+        let dump = match &args.dump.dump_events {
+            Some(path) => Some(EventDump::open(path).map_err(|e| {
+                NodeCliError::file_write_failed(&path.display().to_string(), &e.to_string())
+            })?),
+            None => None,
+        };
 
         // Spawn WebSocket listener (same port as HTTP API)
         let ws_url = format!("ws://{}:{}/ws/events", args.host, args.http_port);
         let api_base = format!("http://{}:{}", args.host, args.http_port);
         let tx_clone = tx.clone();
         tokio::spawn(async move {
-            if let Err(e) = run_websocket_listener(ws_url, api_base, tx_clone).await {
+            if let Err(e) = run_websocket_listener(ws_url, api_base, tx_clone, dump).await {
                 eprintln!("WebSocket error: {}", e);
             }
         });
     }
 
     // Run the TUI
-    app.run()
-        .await
-        .map_err(|e| NodeCliError::io_error(&e.to_string()))?;
+    let result = app.run().await;
+    if let Some(file) = &prefs_file {
+        if let Err(e) = file.save(&app.prefs()) {
+            eprintln!("Warning: could not save DAG settings: {}", e);
+        }
+    }
+    result.map_err(|e| NodeCliError::io_error(&e.to_string()))?;
 
     Ok(())
 }
 
 /// Fetch initial blocks from the API
+///
+/// Blocks missing fields other than the hash are kept with defaults; what
+/// was missing is tallied in the returned [`SchemaDrift`].
 async fn fetch_initial_blocks(
     host: &str,
     port: u16,
     depth: usize,
-) -> Result<Vec<DagBlock>, NodeCliError> {
+) -> Result<(Vec<DagBlock>, SchemaDrift), NodeCliError> {
     let url = format!("http://{}:{}/api/blocks/{}", host, port, depth);
 
-    let response = reqwest::get(&url)
-        .await
-        .map_err(|e| NodeCliError::http_error(&e.to_string()))?;
+    let call = NodeCall::http(&url);
+    let response = reqwest::get(&url).await.map_err(|e| call.fail(e))?;
 
     if !response.status().is_success() {
-        return Err(NodeCliError::http_error(&format!(
+        return Err(call.fail(NodeCliError::http_error(&format!(
             "Failed to fetch blocks: {}",
             response.status()
-        )));
+        ))));
     }
 
-    let body: serde_json::Value = response
-        .json()
-        .await
-        .map_err(|e| NodeCliError::http_error(&e.to_string()))?;
+    let body: serde_json::Value = response.json().await.map_err(|e| call.fail(e))?;
 
+    let mut drift = SchemaDrift::default();
     let mut blocks = Vec::new();
 
     // Parse the response - it should be an array of blocks
     if let Some(block_array) = body.as_array() {
         for block_json in block_array {
-            if let Some(block) = parse_block_json(block_json) {
+            if let Some(block) = parse_block_json(block_json, &mut drift) {
                 blocks.push(block);
             }
         }
     }
 
-    Ok(blocks)
+    Ok((blocks, drift))
+}
+
+/// Block fields that were missing or of an unexpected type, counted per field
+///
+/// Only `blockHash` is required; a block without it is dropped and counted
+/// separately. Every other field falls back to a default, so a node whose
+/// block JSON has drifted still shows its blocks, and the counts say how.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SchemaDrift {
+    /// Blocks parsed, defaults included
+    pub parsed: usize,
+    /// Blocks dropped for having no usable `blockHash`
+    pub dropped: usize,
+    /// Blocks per field that was missing or of an unexpected type
+    pub missing: BTreeMap<&'static str, usize>,
+}
+
+impl SchemaDrift {
+    /// True if every block had every field
+    pub fn is_clean(&self) -> bool {
+        self.dropped == 0 && self.missing.is_empty()
+    }
+
+    fn miss(&mut self, field: &'static str) {
+        *self.missing.entry(field).or_default() += 1;
+    }
+
+    /// `field` of `info` if it holds the expected type, else counted as missing
+    fn field<T>(
+        &mut self,
+        info: &serde_json::Value,
+        field: &'static str,
+        read: impl FnOnce(&serde_json::Value) -> Option<T>,
+    ) -> Option<T> {
+        let value = info.get(field).and_then(read);
+        if value.is_none() {
+            self.miss(field);
+        }
+        value
+    }
+
+    /// One-line account of the drift, e.g. "132 blocks parsed, 5 missing
+    /// sender — possible node schema drift"; `None` if there was none
+    pub fn summary(&self) -> Option<String> {
+        if self.is_clean() {
+            return None;
+        }
+        let mut parts = vec![format!("{} blocks parsed", self.parsed)];
+        if self.dropped > 0 {
+            parts.push(format!("{} dropped without blockHash", self.dropped));
+        }
+        let mut fields: Vec<_> = self.missing.iter().collect();
+        fields.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        parts.extend(
+            fields
+                .into_iter()
+                .map(|(field, count)| format!("{} missing {}", count, field)),
+        );
+        Some(format!("{} — possible node schema drift", parts.join(", ")))
+    }
 }
 
 /// Parse a block from JSON. Handles both flat LightBlockInfo (legacy)
 /// and wrapped BlockInfoSerde format ({"blockInfo": {...}}).
-fn parse_block_json(json: &serde_json::Value) -> Option<DagBlock> {
+///
+/// Only the hash is required; other fields default and are tallied in `drift`.
+fn parse_block_json(json: &serde_json::Value, drift: &mut SchemaDrift) -> Option<DagBlock> {
     // Unwrap blockInfo wrapper if present (new BlockInfoSerde format)
     let info = json.get("blockInfo").unwrap_or(json);
-    let hash = info.get("blockHash")?.as_str()?.to_string();
-    let block_number = info.get("blockNumber")?.as_i64()?;
-    let timestamp_ms = info.get("timestamp")?.as_i64().unwrap_or(0);
+    let Some(hash) = info.get("blockHash").and_then(|h| h.as_str()) else {
+        drift.dropped += 1;
+        return None;
+    };
+    drift.parsed += 1;
+
+    let block_number = drift
+        .field(info, "blockNumber", |v| v.as_i64())
+        .unwrap_or(-1);
+    let timestamp_ms = drift.field(info, "timestamp", |v| v.as_i64()).unwrap_or(0);
     let timestamp = Utc
         .timestamp_millis_opt(timestamp_ms)
         .single()
         .unwrap_or_else(Utc::now);
-    let creator = info.get("sender")?.as_str()?.to_string();
-    let seq_num = info.get("seqNum")?.as_i64().unwrap_or(0);
-
-    let parents: Vec<String> = info
-        .get("parentsHashList")
-        .and_then(|p| p.as_array())
-        .map(|arr| {
-            arr.iter()
-                .filter_map(|v| v.as_str().map(String::from))
-                .collect()
+    let creator = drift
+        .field(info, "sender", |v| v.as_str().map(String::from))
+        .unwrap_or_else(|| "unknown".to_string());
+    let seq_num = drift.field(info, "seqNum", |v| v.as_i64()).unwrap_or(0);
+
+    let parents: Vec<String> = drift
+        .field(info, "parentsHashList", |p| {
+            p.as_array().map(|arr| {
+                arr.iter()
+                    .filter_map(|v| v.as_str().map(String::from))
+                    .collect()
+            })
         })
         .unwrap_or_default();
 
-    let deploy_count = info.get("deployCount")?.as_i64().unwrap_or(0) as u32;
+    let deploy_count = drift
+        .field(info, "deployCount", |v| v.as_u64())
+        .unwrap_or(0) as u32;
 
-    // Blocks missing isFinalized are skipped so the gap is visible rather than silently
-    // assumed finalized — aligns with no-swallow principle.
-    let is_finalized = info.get("isFinalized").and_then(|v| v.as_bool())?;
-    let status = if is_finalized {
-        BlockStatus::Finalized
-    } else {
-        BlockStatus::Added
+    // A block missing isFinalized is shown as added, and counted so the gap is
+    // visible rather than silently assumed either way
+    let status = match drift.field(info, "isFinalized", |v| v.as_bool()) {
+        Some(true) => BlockStatus::Finalized,
+        _ => BlockStatus::Added,
     };
 
     let mut block = DagBlock::new(
-        hash,
+        hash.to_string(),
         block_number,
         timestamp,
         creator,
@@ -156,8 +323,9 @@ async fn fetch_block_by_hash(api_base: &str, hash: &str) -> Option<DagBlock> {
         if let Ok(response) = reqwest::get(&url).await {
             if let Ok(body) = response.json::<serde_json::Value>().await {
                 // Response format: {"blockInfo": {...}, "deploys": [...]}
-                if let Some(block_info) = body.get("blockInfo") {
-                    if let Some(block) = parse_block_info_json(block_info) {
+                // A live block missing fields still beats the bare event payload
+                if body.get("blockInfo").is_some() {
+                    if let Some(block) = parse_block_json(&body, &mut SchemaDrift::default()) {
                         return Some(block);
                     }
                 }
@@ -167,61 +335,65 @@ async fn fetch_block_by_hash(api_base: &str, hash: &str) -> Option<DagBlock> {
     None
 }
 
-/// Parse a block from the /api/block/{hash} response format.
-/// Receives the inner blockInfo object (already unwrapped by caller).
-fn parse_block_info_json(info: &serde_json::Value) -> Option<DagBlock> {
-    let hash = info.get("blockHash")?.as_str()?.to_string();
-    let block_number = info.get("blockNumber")?.as_i64()?;
-    let timestamp_ms = info.get("timestamp")?.as_i64().unwrap_or(0);
-    let timestamp = Utc
-        .timestamp_millis_opt(timestamp_ms)
-        .single()
-        .unwrap_or_else(Utc::now);
-    let creator = info.get("sender")?.as_str()?.to_string();
-    let seq_num = info.get("seqNum")?.as_i64().unwrap_or(0);
-
-    let parents: Vec<String> = info
-        .get("parentsHashList")
-        .and_then(|p| p.as_array())
-        .map(|arr| {
-            arr.iter()
-                .filter_map(|v| v.as_str().map(String::from))
-                .collect()
+/// Fetch the deploys of a block, and its system deploys if the node reports them
+async fn fetch_block_deploys(
+    api_base: &str,
+    hash: &str,
+) -> Option<(Vec<DagDeploy>, Option<Vec<SystemDeploy>>)> {
+    let url = format!("{}/api/block/{}", api_base, hash);
+    let response = reqwest::get(&url).await.ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    let body: serde_json::Value = response.json().await.ok()?;
+    let detail = BlockDetail::from_json(&body).ok()?;
+    let deploys = detail
+        .deploys
+        .into_iter()
+        .map(|deploy| DagDeploy {
+            id: deploy.sig,
+            cost: deploy.cost,
+            deployer: deploy.deployer,
+            errored: deploy.errored,
         })
-        .unwrap_or_default();
-
-    let deploy_count = info.get("deployCount")?.as_i64().unwrap_or(0) as u32;
-
-    let is_finalized = info.get("isFinalized").and_then(|v| v.as_bool())?;
-    let status = if is_finalized {
-        BlockStatus::Finalized
-    } else {
-        BlockStatus::Added
-    };
+        .collect();
+    Some((deploys, detail.system_deploys))
+}
 
-    let mut block = DagBlock::new(
-        hash,
-        block_number,
-        timestamp,
-        creator,
-        seq_num,
-        parents,
-        deploy_count,
-        status,
-    );
+/// Fetch deploys for requested block hashes, at most `MAX_DEPLOY_FETCHES` at a time
+async fn run_deploy_loader(
+    api_base: String,
+    requests: mpsc::UnboundedReceiver<String>,
+    tx: mpsc::Sender<DagEvent>,
+) {
+    let requests = futures_util::stream::unfold(requests, |mut requests| async move {
+        requests.recv().await.map(|hash| (hash, requests))
+    });
+    let mut loaded = requests
+        .map(|hash| {
+            let api_base = api_base.clone();
+            async move {
+                let deploys = fetch_block_deploys(&api_base, &hash).await;
+                (hash, deploys)
+            }
+        })
+        .buffer_unordered(MAX_DEPLOY_FETCHES);
 
-    // Optional fields
-    if let Some(shard) = info.get("shardId").and_then(|s| s.as_str()) {
-        block.shard_id = shard.to_string();
-    }
-    if let Some(pre) = info.get("preStateHash").and_then(|s| s.as_str()) {
-        block.pre_state_hash = pre.to_string();
-    }
-    if let Some(post) = info.get("postStateHash").and_then(|s| s.as_str()) {
-        block.post_state_hash = post.to_string();
+    while let Some((hash, deploys)) = loaded.next().await {
+        let event = match deploys {
+            Some((deploys, system_deploys)) => {
+                DagEvent::DeploysLoaded(hash, deploys, system_deploys)
+            }
+            None => DagEvent::Error(format!(
+                "Could not load deploys for {}...",
+                &hash[..8.min(hash.len())]
+            )),
+        };
+        if tx.send(event).await.is_err() {
+            // Receiver dropped, exit
+            break;
+        }
     }
-
-    Some(block)
 }
 
 /// Run the WebSocket listener for real-time events
@@ -229,6 +401,7 @@ async fn run_websocket_listener(
     ws_url: String,
     api_base: String,
     tx: mpsc::Sender<DagEvent>,
+    mut dump: Option<EventDump>,
 ) -> Result<(), NodeCliError> {
     let (ws_stream, _) = connect_async(&ws_url)
         .await
@@ -239,48 +412,26 @@ async fn run_websocket_listener(
     while let Some(msg) = read.next().await {
         match msg {
             Ok(tokio_tungstenite::tungstenite::Message::Text(text)) => {
-                if let Ok(event) = parse_websocket_event(&text) {
-                    // For all block events, fetch full block info via HTTP
-                    // to get the correct block number
-                    let enriched_event = match &event {
-                        DagEvent::BlockCreated(block) => {
-                            if let Some(mut full_block) =
-                                fetch_block_by_hash(&api_base, &block.hash).await
-                            {
-                                full_block.status = BlockStatus::Created;
-                                DagEvent::BlockCreated(full_block)
-                            } else {
-                                event
-                            }
+                // Record before parsing, so frames that fail to parse are kept
+                let location = match dump.as_mut() {
+                    Some(recorder) => match recorder.record(&RecordedFrame::now(&text)) {
+                        Ok(offset) => Some(format!("frame at {}", recorder.locate(offset))),
+                        Err(e) => {
+                            let message = format!(
+                                "Stopped recording to {}: {}",
+                                recorder.path().display(),
+                                e
+                            );
+                            let _ = tx.send(DagEvent::Error(message)).await;
+                            dump = None;
+                            None
                         }
-                        DagEvent::BlockAdded(hash) => {
-                            // Fetch full block and return as BlockCreated with Added status
-                            // This ensures we have block_number even if we missed BlockCreated
-                            if let Some(mut full_block) = fetch_block_by_hash(&api_base, hash).await
-                            {
-                                full_block.status = BlockStatus::Added;
-                                DagEvent::BlockCreated(full_block)
-                            } else {
-                                event
-                            }
-                        }
-                        DagEvent::BlockFinalized(hash) => {
-                            // Fetch full block and return as BlockCreated with Finalized status
-                            if let Some(mut full_block) = fetch_block_by_hash(&api_base, hash).await
-                            {
-                                full_block.status = BlockStatus::Finalized;
-                                DagEvent::BlockCreated(full_block)
-                            } else {
-                                event
-                            }
-                        }
-                        _ => event,
-                    };
-
-                    if tx.send(enriched_event).await.is_err() {
-                        // Receiver dropped, exit
-                        break;
-                    }
+                    },
+                    None => None,
+                };
+                if !forward_frame(&text, Some(&api_base), &tx, location).await {
+                    // Receiver dropped, exit
+                    break;
                 }
             }
             Ok(tokio_tungstenite::tungstenite::Message::Close(_)) => {
@@ -297,10 +448,78 @@ async fn run_websocket_listener(
     Ok(())
 }
 
+/// Feed recorded frames to the TUI as if they had just arrived
+async fn run_replay(
+    source: String,
+    frames: Vec<RecordedFrame>,
+    speed: f64,
+    tx: mpsc::Sender<DagEvent>,
+) {
+    let mut frames = replay(frames, speed);
+    let mut index = 0;
+    while let Some(frame) = frames.recv().await {
+        index += 1;
+        let location = format!("frame {} of {}", index, source);
+        if !forward_frame(&frame.text, None, &tx, Some(location)).await {
+            break;
+        }
+    }
+}
+
+/// Parse one frame and send the resulting event, returning false once the TUI is gone
+///
+/// With `api_base`, block events are completed with the block's details from
+/// the node. A frame that fails to parse is reported only when `location`
+/// says where it can be found again.
+async fn forward_frame(
+    text: &str,
+    api_base: Option<&str>,
+    tx: &mpsc::Sender<DagEvent>,
+    location: Option<String>,
+) -> bool {
+    let event = match parse_websocket_event(text) {
+        Ok(Some(event)) => event,
+        Ok(None) => return true,
+        Err(e) => {
+            return match location {
+                Some(at) => {
+                    let message = format!("{} ({})", e, at);
+                    tx.send(DagEvent::Error(message)).await.is_ok()
+                }
+                None => true,
+            };
+        }
+    };
+    let event = match api_base {
+        Some(api_base) => enrich_event(event, api_base).await,
+        None => event,
+    };
+    tx.send(event).await.is_ok()
+}
+
+/// Replace a block event's payload with the block as the node reports it,
+/// so the block number is right even if `block-created` was missed
+async fn enrich_event(event: DagEvent, api_base: &str) -> DagEvent {
+    let (hash, status) = match &event {
+        DagEvent::BlockCreated(block) => (block.hash.as_str(), BlockStatus::Created),
+        DagEvent::BlockAdded(hash) => (hash.as_str(), BlockStatus::Added),
+        DagEvent::BlockFinalized(hash) => (hash.as_str(), BlockStatus::Finalized),
+        _ => return event,
+    };
+    match fetch_block_by_hash(api_base, hash).await {
+        Some(mut full_block) => {
+            full_block.status = status;
+            DagEvent::BlockCreated(full_block)
+        }
+        None => event,
+    }
+}
+
 /// Parse a WebSocket event into a DagEvent
 /// The node sends events in this format:
 /// {"event": "block-created", "schema-version": 1, "payload": {...}}
-fn parse_websocket_event(text: &str) -> Result<DagEvent, NodeCliError> {
+/// Events the DAG does not show are `Ok(None)`.
+fn parse_websocket_event(text: &str) -> Result<Option<DagEvent>, NodeCliError> {
     let json: serde_json::Value =
         serde_json::from_str(text).map_err(|e| NodeCliError::parse_error(&e.to_string()))?;
 
@@ -313,33 +532,34 @@ fn parse_websocket_event(text: &str) -> Result<DagEvent, NodeCliError> {
         "block-created" => {
             if let Some(p) = payload {
                 let block = parse_event_block(p, BlockStatus::Created)?;
-                return Ok(DagEvent::BlockCreated(block));
+                return Ok(Some(DagEvent::BlockCreated(block)));
             }
         }
         "block-added" => {
             if let Some(p) = payload {
                 if let Some(hash) = p.get("block-hash").and_then(|h| h.as_str()) {
-                    return Ok(DagEvent::BlockAdded(hash.to_string()));
+                    return Ok(Some(DagEvent::BlockAdded(hash.to_string())));
                 }
             }
         }
         "block-finalised" => {
             if let Some(p) = payload {
                 if let Some(hash) = p.get("block-hash").and_then(|h| h.as_str()) {
-                    return Ok(DagEvent::BlockFinalized(hash.to_string()));
+                    return Ok(Some(DagEvent::BlockFinalized(hash.to_string())));
                 }
             }
         }
-        // Non-block events: handshake, genesis ceremony, node lifecycle.
-        // Not relevant for DAG visualization.
+        // Non-block events: handshake, transfers, genesis ceremony, node
+        // lifecycle. Not relevant for DAG visualization.
         "started"
+        | "transfers-available"
         | "sent-unapproved-block"
         | "sent-approved-block"
         | "block-approval-received"
         | "approved-block-received"
         | "entered-running-state"
         | "node-started" => {
-            return Err(NodeCliError::parse_error("Non-block event, skipping"));
+            return Ok(None);
         }
         _ => {}
     }
@@ -402,3 +622,83 @@ fn parse_event_block(
 
     Ok(block)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn full_block(hash: &str) -> serde_json::Value {
+        json!({
+            "blockHash": hash,
+            "blockNumber": 7,
+            "timestamp": 1_700_000_000_000i64,
+            "sender": "04abcdef0123",
+            "seqNum": 3,
+            "parentsHashList": ["p1", "p2"],
+            "deployCount": 2,
+            "isFinalized": true,
+            "shardId": "root"
+        })
+    }
+
+    #[test]
+    fn test_complete_block_has_no_drift() {
+        let mut drift = SchemaDrift::default();
+        let wrapped = json!({ "blockInfo": full_block("aa") });
+        let block = parse_block_json(&wrapped, &mut drift).unwrap();
+        assert_eq!(block.block_number, 7);
+        assert_eq!(block.parents, ["p1", "p2"]);
+        assert_eq!(block.status, BlockStatus::Finalized);
+        assert_eq!(block.shard_id, "root");
+        assert!(drift.is_clean());
+        assert_eq!(drift.summary(), None);
+    }
+
+    #[test]
+    fn test_mutated_blocks_are_kept_and_counted() {
+        let mut no_sender = full_block("b1");
+        no_sender.as_object_mut().unwrap().remove("sender");
+        let mut bad_timestamp = full_block("b2");
+        bad_timestamp["timestamp"] = json!("yesterday");
+        let mut bare = json!({ "blockHash": "b3" });
+        bare["sender"] = json!(42);
+        let no_hash = json!({ "blockNumber": 9, "sender": "04ff" });
+
+        let mut drift = SchemaDrift::default();
+        let fixtures = [full_block("b0"), no_sender, bad_timestamp, bare, no_hash];
+        let blocks: Vec<_> = fixtures
+            .iter()
+            .filter_map(|json| parse_block_json(json, &mut drift))
+            .collect();
+
+        let hashes: Vec<_> = blocks.iter().map(|b| b.hash.as_str()).collect();
+        assert_eq!(hashes, ["b0", "b1", "b2", "b3"]);
+        assert_eq!(blocks[1].creator, "unknown");
+        assert_eq!(blocks[2].timestamp.timestamp_millis(), 0);
+        assert_eq!(blocks[3].block_number, -1);
+        assert_eq!(blocks[3].status, BlockStatus::Added);
+
+        assert_eq!(drift.parsed, 4);
+        assert_eq!(drift.dropped, 1);
+        assert_eq!(drift.missing["sender"], 2);
+        assert_eq!(drift.missing["timestamp"], 2);
+        for field in [
+            "blockNumber",
+            "seqNum",
+            "parentsHashList",
+            "deployCount",
+            "isFinalized",
+        ] {
+            assert_eq!(drift.missing[field], 1, "{}", field);
+        }
+        assert_eq!(drift.missing.len(), 7);
+        assert_eq!(
+            drift.summary().unwrap(),
+            "4 blocks parsed, 1 dropped without blockHash, 2 missing sender, \
+             2 missing timestamp, 1 missing blockNumber, 1 missing deployCount, \
+             1 missing isFinalized, 1 missing parentsHashList, 1 missing seqNum \
+             — possible node schema drift"
+        );
+    }
+}
@@ -0,0 +1,317 @@
+use crate::args::DashboardArgs;
+use crate::capabilities::{self, Capability};
+use crate::chain_stats::{fetch_headers, main_chain};
+use crate::commands::equivocation::block_meta;
+use crate::dashboard::model::{short_hash, REORG_WINDOW};
+use crate::dashboard::{
+    BlockRow, Dashboard, DashboardApp, DashboardUpdate, FinalitySample, NodeState,
+};
+use crate::equivocation::EquivocationTracker;
+use crate::error::{NodeCliError, Result};
+use crate::events::subscribe_blocks_added;
+use crate::export::ChainSource;
+use crate::f1r3fly_api::{fault_tolerance, BlockSummary, F1r3flyApi};
+use crate::ports;
+use crate::topology::{NodeEndpoint, Topology};
+use futures_util::future::join_all;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Updates queued for the dashboard before the background tasks wait for it
+const UPDATE_QUEUE: usize = 256;
+
+/// Show node health, finality, recent blocks and alerts in one terminal view
+///
+/// Three background tasks feed the view: one checks every node's status, one
+/// samples the followed node's finality and main chain, and one follows its
+/// added blocks and matches them for equivocations.
+pub async fn dashboard_command(args: &DashboardArgs) -> Result<()> {
+    let nodes = health_nodes(args)?;
+    capabilities::require(&args.host, args.ws_port, Capability::WebsocketEvents).await?;
+    // Reorgs are found by comparing main chains, which needs the ranged blocks API
+    let reorgs = capabilities::require(&args.host, args.http_port, Capability::BlocksByHeight)
+        .await
+        .is_ok();
+
+    let (tx, rx) = mpsc::channel(UPDATE_QUEUE);
+    let tasks = [
+        tokio::spawn(poll_health(
+            nodes.clone(),
+            args.query_key.private_key.clone(),
+            args.refresh_interval,
+            tx.clone(),
+        )),
+        tokio::spawn(poll_finality(
+            args.host.clone(),
+            args.http_port,
+            args.query_key.private_key.clone(),
+            reorgs,
+            args.refresh_interval,
+            tx.clone(),
+        )),
+        tokio::spawn(follow_blocks(
+            ChainSource::new(&args.host, args.http_port),
+            format!("ws://{}:{}", args.host, args.ws_port),
+            args.window,
+            tx,
+        )),
+    ];
+
+    let mut dashboard = Dashboard::new(&nodes, args.stall_timeout);
+    if !reorgs {
+        dashboard.status_message =
+            "Node lacks the ranged blocks API; main-chain reorgs are not detected".to_string();
+    }
+    let mut app = DashboardApp::new(dashboard, rx);
+    let result = app.run().await;
+    for task in tasks {
+        task.abort();
+    }
+    result.map_err(|e| NodeCliError::io_error(&e.to_string()))
+}
+
+/// Nodes whose health is shown: `--node` and `--topology`, else the default
+/// topology file, else the standard shard on `--host`
+fn health_nodes(args: &DashboardArgs) -> Result<Vec<NodeEndpoint>> {
+    let topology = if args.nodes.is_empty() && args.topology.is_none() {
+        Topology::active(None)?
+    } else {
+        Topology::resolve(args.topology.as_deref(), &args.nodes)?
+    };
+    if topology.nodes.is_empty() {
+        return Ok(standard_shard(&args.host));
+    }
+    Ok(topology.nodes)
+}
+
+fn standard_shard(host: &str) -> Vec<NodeEndpoint> {
+    ports::STANDARD_SHARD
+        .iter()
+        .map(|node| NodeEndpoint {
+            name: node.role.name().to_string(),
+            host: host.to_string(),
+            grpc_port: Some(node.grpc_internal),
+            http_port: node.http,
+        })
+        .collect()
+}
+
+/// What a node's `/api/status` says about it
+pub async fn node_state(node: &NodeEndpoint, private_key: Option<&str>) -> NodeState {
+    let api = match F1r3flyApi::for_queries(
+        private_key,
+        &node.host,
+        node.grpc_port.unwrap_or(ports::VALIDATOR1.grpc_internal),
+    ) {
+        Ok(api) => api,
+        Err(e) => return NodeState::Down(e.to_string()),
+    };
+    match api.get_node_status(node.http_port).await {
+        Ok(Some(status)) => NodeState::Up {
+            peers: status.peers,
+            lfb_height: status.last_finalized_block_number,
+            ready: status.is_ready,
+        },
+        Ok(None) => NodeState::Down("no status".to_string()),
+        Err(e) => NodeState::Down(e.to_string()),
+    }
+}
+
+/// The last finalized block, tip height and fault tolerance of a node
+pub async fn finality_sample(
+    api: &F1r3flyApi<'_>,
+    http_port: u16,
+) -> Result<Option<FinalitySample>> {
+    let Some(json) = api.get_last_finalized_block_json(http_port).await? else {
+        return Ok(None);
+    };
+    let Some(lfb) = BlockSummary::from_json(&json) else {
+        return Ok(None);
+    };
+    let tip_height = api.get_tip_block_number(http_port).await?;
+    Ok(Some(FinalitySample {
+        lfb_height: lfb.block_number,
+        lfb_hash: lfb.block_hash,
+        tip_height,
+        fault_tolerance: fault_tolerance(&json),
+    }))
+}
+
+/// Main chain over the last [`REORG_WINDOW`] heights up to `tip`, as (height, hash)
+async fn recent_main_chain(source: &ChainSource, tip: i64) -> Result<Vec<(i64, String)>> {
+    let start = (tip - REORG_WINDOW + 1).max(0);
+    let headers = fetch_headers(source, start, tip, REORG_WINDOW).await?;
+    Ok(main_chain(&headers)
+        .into_iter()
+        .map(|header| (header.block_number, header.block_hash.clone()))
+        .collect())
+}
+
+async fn poll_health(
+    nodes: Vec<NodeEndpoint>,
+    private_key: Option<String>,
+    interval: Duration,
+    tx: mpsc::Sender<DashboardUpdate>,
+) {
+    loop {
+        let states = join_all(
+            nodes
+                .iter()
+                .map(|node| node_state(node, private_key.as_deref())),
+        )
+        .await;
+        for (node, state) in nodes.iter().zip(states) {
+            let name = node.name.clone();
+            if tx
+                .send(DashboardUpdate::Health { name, state })
+                .await
+                .is_err()
+            {
+                return;
+            }
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+async fn poll_finality(
+    host: String,
+    http_port: u16,
+    private_key: Option<String>,
+    reorgs: bool,
+    interval: Duration,
+    tx: mpsc::Sender<DashboardUpdate>,
+) {
+    let api = match F1r3flyApi::for_queries(private_key.as_deref(), &host, http_port) {
+        Ok(api) => api,
+        Err(e) => {
+            let _ = tx.send(DashboardUpdate::Error(e.to_string())).await;
+            return;
+        }
+    };
+    let source = ChainSource::new(&host, http_port);
+    loop {
+        let mut updates = Vec::new();
+        match finality_sample(&api, http_port).await {
+            Ok(Some(sample)) => {
+                let tip = sample.tip_height;
+                updates.push(DashboardUpdate::Finality(sample));
+                if let (true, Some(tip)) = (reorgs, tip) {
+                    updates.push(match recent_main_chain(&source, tip).await {
+                        Ok(chain) => DashboardUpdate::MainChain(chain),
+                        Err(e) => DashboardUpdate::Error(e.to_string()),
+                    });
+                }
+            }
+            Ok(None) => updates.push(DashboardUpdate::Error(
+                "no last finalized block yet".to_string(),
+            )),
+            Err(e) => updates.push(DashboardUpdate::Error(e.to_string())),
+        }
+        for update in updates {
+            if tx.send(update).await.is_err() {
+                return;
+            }
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Forward added blocks and the equivocations among them until the dashboard closes
+async fn follow_blocks(
+    source: ChainSource,
+    ws_url: String,
+    window: i64,
+    tx: mpsc::Sender<DashboardUpdate>,
+) {
+    let mut blocks = subscribe_blocks_added(&ws_url);
+    let mut tracker = EquivocationTracker::new(window);
+    while let Some(added) = blocks.recv().await {
+        let received_at = chrono::Utc::now().timestamp_millis();
+        let (height, equivocation) = match block_meta(&source, &added).await {
+            Ok(block) => (
+                Some(block.block_number),
+                tracker.observe(&block).map(DashboardUpdate::Equivocation),
+            ),
+            Err(e) => (
+                added.block_number,
+                Some(DashboardUpdate::Error(format!(
+                    "Failed to fetch block {}: {}",
+                    short_hash(&added.block_hash),
+                    e
+                ))),
+            ),
+        };
+        let row = BlockRow {
+            height,
+            hash: added.block_hash,
+            creator: added.creator,
+            received_at,
+        };
+        let updates = std::iter::once(DashboardUpdate::Block(row)).chain(equivocation);
+        for update in updates {
+            if tx.send(update).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::DEV_PRIVATE_KEY;
+    use crate::test_support::spawn_http_node;
+
+    #[test]
+    fn test_standard_shard_fallback() {
+        let nodes = standard_shard("node.example");
+        assert_eq!(nodes.len(), ports::STANDARD_SHARD.len());
+        assert!(nodes.iter().all(|node| node.host == "node.example"));
+        assert_eq!(nodes[0].http_port, ports::BOOTSTRAP.http);
+    }
+
+    #[tokio::test]
+    async fn test_finality_sample_and_health() {
+        let port = spawn_http_node(|line, _| match line.split(' ').nth(1) {
+            Some("/api/last-finalized-block") => (
+                "200 OK",
+                r#"{"blockInfo":{"blockHash":"ab12","blockNumber":40,"faultTolerance":0.25}}"#
+                    .to_string(),
+            ),
+            Some("/api/blocks/1") => ("200 OK", r#"[{"blockNumber":43}]"#.to_string()),
+            Some("/api/status") => (
+                "200 OK",
+                r#"{"version":{},"address":"a","networkId":"n","shardId":"root","peers":3,
+                    "nodes":4,"minPhloPrice":1,"lastFinalizedBlockNumber":40,"isReady":true}"#
+                    .to_string(),
+            ),
+            _ => ("404 Not Found", String::new()),
+        })
+        .await;
+
+        let api = F1r3flyApi::new(DEV_PRIVATE_KEY, "127.0.0.1", 1).unwrap();
+        let sample = finality_sample(&api, port).await.unwrap().unwrap();
+        assert_eq!(
+            sample,
+            FinalitySample {
+                lfb_height: 40,
+                lfb_hash: "ab12".to_string(),
+                tip_height: Some(43),
+                fault_tolerance: Some(0.25),
+            }
+        );
+
+        let node = NodeEndpoint::parse(&format!("127.0.0.1:{}", port)).unwrap();
+        assert_eq!(
+            node_state(&node, None).await,
+            NodeState::Up {
+                peers: 3,
+                lfb_height: Some(40),
+                ready: Some(true),
+            }
+        );
+        let down = NodeEndpoint::parse("127.0.0.1:1").unwrap();
+        assert!(matches!(node_state(&down, None).await, NodeState::Down(_)));
+    }
+}
@@ -0,0 +1,147 @@
+use crate::args::EquivocationCheckArgs;
+use crate::capabilities::{self, Capability};
+use crate::chain_stats::{fetch_headers, main_chain};
+use crate::commands::chain_stats::resolve_height_range;
+use crate::equivocation::{detect, BlockMeta, Equivocation, EquivocationTracker};
+use crate::error::{NodeCliError, Result};
+use crate::events::{subscribe_blocks_added, BlockAdded};
+use crate::export::ChainSource;
+use std::collections::HashSet;
+
+/// Report validators that created two blocks with the same sequence number,
+/// over a height range or, with `--live`, as blocks are added
+pub async fn equivocation_check_command(args: &EquivocationCheckArgs) -> Result<()> {
+    if args.live {
+        return follow_blocks(args).await;
+    }
+    if args.chunk_size < 1 {
+        return Err(NodeCliError::config_invalid_value(
+            "chunk-size",
+            "must be at least 1",
+        ));
+    }
+    capabilities::require(&args.host, args.port, Capability::BlocksByHeight).await?;
+    let (start, end) = resolve_height_range(
+        &args.host,
+        args.port,
+        args.query_key.private_key.as_deref(),
+        args.start,
+        args.end,
+        args.last,
+    )
+    .await?;
+    let source = ChainSource::new(&args.host, args.port);
+
+    eprintln!(
+        "Scanning heights {}..={} from {}:{}",
+        start, end, args.host, args.port
+    );
+    let headers = fetch_headers(&source, start, end, args.chunk_size).await?;
+    let blocks: Vec<BlockMeta> = headers.iter().map(BlockMeta::from).collect();
+    let found = detect(&blocks);
+    if found.is_empty() {
+        println!(
+            "No equivocations in {} block(s) at heights {}..={}",
+            blocks.len(),
+            start,
+            end
+        );
+        return Ok(());
+    }
+
+    let main: HashSet<&str> = main_chain(&headers)
+        .into_iter()
+        .map(|h| h.block_hash.as_str())
+        .collect();
+    for equivocation in &found {
+        print_equivocation(equivocation, Some(&main));
+    }
+    Err(NodeCliError::General(format!(
+        "{} equivocation(s) at heights {}..={}",
+        found.len(),
+        start,
+        end
+    )))
+}
+
+/// Match every added block against the last `--window` heights until Ctrl+C
+async fn follow_blocks(args: &EquivocationCheckArgs) -> Result<()> {
+    if args.window < 0 {
+        return Err(NodeCliError::config_invalid_value(
+            "window",
+            "must not be negative",
+        ));
+    }
+    capabilities::require(&args.host, args.port, Capability::WebsocketEvents).await?;
+    let source = ChainSource::new(&args.host, args.port);
+    let ws_url = format!("ws://{}:{}", args.host, args.ws_port);
+    let mut blocks = subscribe_blocks_added(&ws_url);
+    let mut tracker = EquivocationTracker::new(args.window);
+
+    println!(
+        " Following new blocks on {}, matching the last {} heights... (Press Ctrl+C to stop)\n",
+        ws_url, args.window
+    );
+
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+
+    loop {
+        tokio::select! {
+            _ = &mut ctrl_c => {
+                println!("\n Shutting down gracefully...");
+                return Ok(());
+            }
+            added = blocks.recv() => {
+                let Some(added) = added else {
+                    return Err(NodeCliError::network_connection_failed("event stream ended"));
+                };
+                match block_meta(&source, &added).await {
+                    Ok(block) => {
+                        if let Some(equivocation) = tracker.observe(&block) {
+                            print_equivocation(&equivocation, None);
+                        }
+                    }
+                    Err(e) => eprintln!(" Failed to fetch block {}: {}", added.block_hash, e),
+                }
+            }
+        }
+    }
+}
+
+/// Creator and sequence number of an added block
+///
+/// Nodes that leave them out of `block-added` events have the block header
+/// fetched instead.
+pub(crate) async fn block_meta(source: &ChainSource, added: &BlockAdded) -> Result<BlockMeta> {
+    if let (Some(creator), Some(seq_num), Some(block_number)) =
+        (&added.creator, added.seq_num, added.block_number)
+    {
+        return Ok(BlockMeta {
+            block_hash: added.block_hash.clone(),
+            block_number,
+            creator: creator.clone(),
+            seq_num,
+        });
+    }
+    let block = source.block(&added.block_hash).await?;
+    Ok(BlockMeta::from(&block.header))
+}
+
+/// Print the conflicting blocks, marking main-chain membership when it is known
+fn print_equivocation(equivocation: &Equivocation, main: Option<&HashSet<&str>>) {
+    println!(
+        "Equivocation: validator {} created {} blocks with seq_num {}",
+        equivocation.creator,
+        equivocation.blocks.len(),
+        equivocation.seq_num
+    );
+    for block in &equivocation.blocks {
+        let chain = match main {
+            Some(main) if main.contains(block.block_hash.as_str()) => "  main chain",
+            Some(_) => "  off main chain",
+            None => "",
+        };
+        println!("  #{} {}{}", block.block_number, block.block_hash, chain);
+    }
+}
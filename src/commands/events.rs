@@ -1,7 +1,16 @@
 use crate::args::WatchEventsArgs;
+use crate::capabilities::{self, Capability};
+use crate::cross_node::CrossNodeTracker;
 use crate::error::{NodeCliError, Result};
+use crate::events::{read_dump, replay, subscribe_frames, BlockEvent, EventDump, RecordedFrame};
+use crate::maintenance::MaintenanceWait;
+use crate::topology::{NodeEndpoint, Topology};
+use crate::utils::{format_duration, print_warning};
 use futures_util::StreamExt;
 use serde::Deserialize;
+use std::path::Path;
+use std::time::Duration;
+use tokio::sync::mpsc;
 use tokio_tungstenite::{connect_async, tungstenite::Message};
 
 /// F1R3FLY node event from WebSocket /ws/events endpoint.
@@ -219,6 +228,23 @@ pub async fn watch_events_command(args: &WatchEventsArgs) -> Result<()> {
         }
     }
 
+    if let Some(path) = &args.dump.from_dump {
+        return replay_dump(path, args).await;
+    }
+
+    if !args.nodes.is_empty() || args.topology.is_some() {
+        let topology = Topology::resolve(args.topology.as_deref(), &args.nodes)?;
+        if topology.nodes.is_empty() {
+            return Err(NodeCliError::config_invalid_value(
+                "topology",
+                "lists no nodes to watch",
+            ));
+        }
+        return watch_nodes(topology.nodes, args).await;
+    }
+
+    capabilities::require(&args.host, args.http_port, Capability::WebsocketEvents).await?;
+    let mut dump = open_dump(args.dump.dump_events.as_deref())?;
     let ws_url = format!("ws://{}:{}/ws/events", args.host, args.http_port);
 
     println!(" Connecting to F1r3fly node WebSocket...");
@@ -227,20 +253,30 @@ pub async fn watch_events_command(args: &WatchEventsArgs) -> Result<()> {
     if let Some(filter) = &args.filter {
         println!(" Filter: {}", filter);
     }
+    if let Some(dump) = &dump {
+        println!(" Recording frames to: {}", dump.path().display());
+    }
     println!();
 
     let mut stats = EventStats::new();
     let start_time = std::time::Instant::now();
     let mut retry_count = 0;
+    let mut maintenance = MaintenanceWait::new();
     const MAX_RETRIES: u32 = 10;
     const RETRY_DELAY_SECS: u64 = 10;
 
     loop {
-        match connect_and_watch(&ws_url, args, &mut stats).await {
+        match connect_and_watch(&ws_url, args, &mut stats, &mut dump, &mut maintenance).await {
             Ok(_) => {
                 break;
             }
             Err(e) => {
+                // Waiting out maintenance does not use up reconnection attempts
+                let retry_delay = Duration::from_secs(RETRY_DELAY_SECS);
+                if let Some(delay) = maintenance.on_error(&e, retry_delay) {
+                    tokio::time::sleep(delay).await;
+                    continue;
+                }
                 retry_count += 1;
 
                 if !args.retry_forever && retry_count > MAX_RETRIES {
@@ -274,14 +310,96 @@ pub async fn watch_events_command(args: &WatchEventsArgs) -> Result<()> {
     Ok(())
 }
 
+fn open_dump(path: Option<&Path>) -> Result<Option<EventDump>> {
+    path.map(|path| {
+        EventDump::open(path).map_err(|e| {
+            NodeCliError::file_write_failed(&path.display().to_string(), &e.to_string())
+        })
+    })
+    .transpose()
+}
+
+/// Record `text` to the dump, if any, returning where it was written
+///
+/// A dump that can no longer be written is dropped with a warning rather
+/// than ending the watch.
+fn record_frame(dump: &mut Option<EventDump>, text: &str) -> Option<String> {
+    let recorder = dump.as_mut()?;
+    match recorder.record(&RecordedFrame::now(text)) {
+        Ok(offset) => Some(recorder.locate(offset)),
+        Err(e) => {
+            print_warning(&format!(
+                "Stopped recording to {}: {}",
+                recorder.path().display(),
+                e
+            ));
+            *dump = None;
+            None
+        }
+    }
+}
+
+/// Feed a `--dump-events` file through the same handling as live frames
+async fn replay_dump(path: &Path, args: &WatchEventsArgs) -> Result<()> {
+    let contents = read_dump(path)
+        .map_err(|e| NodeCliError::file_read_failed(&path.display().to_string(), &e.to_string()))?;
+    println!(
+        " Replaying {} frames from {} at {}x",
+        contents.frames.len(),
+        path.display(),
+        args.dump.replay_speed
+    );
+    if contents.skipped > 0 {
+        print_warning(&format!(
+            "Skipped {} lines that are not recorded frames (a partial last line is left by a crash)",
+            contents.skipped
+        ));
+    }
+    println!();
+
+    let mut stats = EventStats::new();
+    let start_time = std::time::Instant::now();
+    let mut frames = replay(contents.frames, args.dump.replay_speed);
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+
+    loop {
+        tokio::select! {
+            _ = &mut ctrl_c => {
+                println!("\n Stopping replay...");
+                break;
+            }
+            frame = frames.recv() => {
+                let Some(frame) = frame else { break };
+                if let Err(e) = handle_event(&frame.text, args, &mut stats, None) {
+                    eprintln!(" Error processing event: {}", e);
+                }
+            }
+        }
+    }
+
+    stats.print_summary(start_time.elapsed());
+    Ok(())
+}
+
 async fn connect_and_watch(
     ws_url: &str,
     args: &WatchEventsArgs,
     stats: &mut EventStats,
+    dump: &mut Option<EventDump>,
+    maintenance: &mut MaintenanceWait,
 ) -> Result<()> {
-    let (ws_stream, _) = connect_async(ws_url).await.map_err(|e| {
-        NodeCliError::network_connection_failed(&format!("WebSocket connection failed: {}", e))
-    })?;
+    let (ws_stream, _) =
+        connect_async(ws_url)
+            .await
+            .map_err(|e| match crate::maintenance::from_ws(&e) {
+                Some(detail) => NodeCliError::node_in_maintenance(&detail),
+                None => NodeCliError::network_connection_failed(&format!(
+                    "WebSocket connection failed: {}",
+                    e
+                )),
+            })?;
+    maintenance.on_success();
 
     println!(" Connected to node WebSocket");
     println!(" Watching for block events... (Press Ctrl+C to stop)\n");
@@ -300,8 +418,12 @@ async fn connect_and_watch(
         msg = read.next() => {
         match msg {
         Some(Ok(Message::Text(text))) => {
-        if let Err(e) = handle_event(&text, args, stats) {
-        eprintln!(" Error processing event: {}", e);
+        let recorded_at = record_frame(dump, &text);
+        if let Err(e) = handle_event(&text, args, stats, None) {
+        match recorded_at {
+        Some(at) => eprintln!(" Error processing event: {} (frame at {})", e, at),
+        None => eprintln!(" Error processing event: {}", e),
+        }
         continue;
         }
         }
@@ -321,7 +443,139 @@ async fn connect_and_watch(
     }
 }
 
-fn handle_event(text: &str, args: &WatchEventsArgs, stats: &mut EventStats) -> Result<()> {
+/// Watch several nodes at once, tagging each event with the node it came
+/// from and reporting blocks one node has that another has not
+///
+/// Each node's stream reconnects on its own every 5s after a disconnect, so
+/// a node that goes away shows up as missing blocks rather than ending the
+/// watch.
+async fn watch_nodes(nodes: Vec<NodeEndpoint>, args: &WatchEventsArgs) -> Result<()> {
+    for node in &nodes {
+        if let Err(e) =
+            capabilities::require(&node.host, node.http_port, Capability::WebsocketEvents).await
+        {
+            print_warning(&format!("{}: {}", node.name, e));
+        }
+    }
+
+    println!(" Watching {} nodes:", nodes.len());
+    for node in &nodes {
+        println!(" - {}  ws://{}/ws/events", node.name, node.address());
+    }
+    if let Some(filter) = &args.filter {
+        println!(" Filter: {}", filter);
+    }
+    println!(" Lag threshold: {}", format_duration(args.lag_threshold));
+    println!(" Press Ctrl+C to stop\n");
+
+    let names: Vec<String> = nodes.iter().map(|node| node.name.clone()).collect();
+    let (tx, mut frames) = mpsc::unbounded_channel();
+    for (index, node) in nodes.iter().enumerate() {
+        let mut node_frames = subscribe_frames(&format!("ws://{}", node.address()));
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            while let Some(text) = node_frames.recv().await {
+                if tx.send((index, text)).is_err() {
+                    return;
+                }
+            }
+        });
+    }
+    drop(tx);
+
+    let mut tracker = CrossNodeTracker::new(names.clone(), args.lag_threshold);
+    let mut stats: Vec<EventStats> = names.iter().map(|_| EventStats::new()).collect();
+    let start_time = std::time::Instant::now();
+    let mut tick = tokio::time::interval(Duration::from_secs(1));
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+
+    loop {
+        tokio::select! {
+            _ = &mut ctrl_c => {
+                println!("\n Shutting down gracefully...");
+                break;
+            }
+            frame = frames.recv() => {
+                let Some((node, text)) = frame else { break };
+                let now = chrono::Utc::now().timestamp_millis();
+                if let Some(event) = BlockEvent::from_message(&text, now) {
+                    tracker.observe(node, &event.block_hash, event.received_at);
+                }
+                if let Err(e) = handle_event(&text, args, &mut stats[node], Some(&names[node])) {
+                    eprintln!(" [{}] Error processing event: {}", names[node], e);
+                }
+            }
+            _ = tick.tick() => {
+                for alert in tracker.check(chrono::Utc::now().timestamp_millis()) {
+                    print_warning(&alert.to_string());
+                }
+            }
+        }
+    }
+
+    print_nodes_summary(&names, &stats, &tracker, start_time.elapsed());
+    Ok(())
+}
+
+fn print_nodes_summary(
+    names: &[String],
+    stats: &[EventStats],
+    tracker: &CrossNodeTracker,
+    duration: Duration,
+) {
+    let width = names
+        .iter()
+        .map(|name| name.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(4);
+    println!("\n Event Statistics ({:.1}s):", duration.as_secs_f64());
+    println!(
+        " {:<width$} {:>7} {:>7} {:>7} {:>9}",
+        "node", "total", "created", "added", "finalized"
+    );
+    for (name, stats) in names.iter().zip(stats) {
+        println!(
+            " {:<width$} {:>7} {:>7} {:>7} {:>9}",
+            name, stats.total, stats.created, stats.added, stats.finalized
+        );
+    }
+
+    println!("\n Cross-node lag (after the first node to report each block):");
+    println!(
+        " {:<width$} {:>6} {:>7} {:>9} {:>9} {:>9} {:>5} {:>6}",
+        "node", "first", "behind", "p50", "p95", "max", "late", "missed"
+    );
+    for node in tracker.summary() {
+        let (behind, p50, p95, max) = match node.lag {
+            Some(lag) => (
+                lag.count.to_string(),
+                format_duration(Duration::from_millis(lag.p50)),
+                format_duration(Duration::from_millis(lag.p95)),
+                format_duration(Duration::from_millis(lag.max)),
+            ),
+            None => ("0".to_string(), "-".into(), "-".into(), "-".into()),
+        };
+        println!(
+            " {:<width$} {:>6} {:>7} {:>9} {:>9} {:>9} {:>5} {:>6}",
+            node.name, node.first, behind, p50, p95, max, node.late, node.missed
+        );
+    }
+    if tracker.open_blocks() > 0 {
+        println!(
+            " {} blocks were still missing on some node when the watch stopped",
+            tracker.open_blocks()
+        );
+    }
+}
+
+fn handle_event(
+    text: &str,
+    args: &WatchEventsArgs,
+    stats: &mut EventStats,
+    source: Option<&str>,
+) -> Result<()> {
     let event: NodeEvent = serde_json::from_str(text)
         .map_err(|e| NodeCliError::from(format!("Failed to parse event: {}", e)))?;
 
@@ -345,6 +599,9 @@ fn handle_event(text: &str, args: &WatchEventsArgs, stats: &mut EventStats) -> R
     }
 
     stats.increment(&event);
+    if let Some(node) = source {
+        print!("[{}]", node);
+    }
     display_pretty(&event);
     Ok(())
 }
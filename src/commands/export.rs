@@ -0,0 +1,117 @@
+use crate::args::ExportChainArgs;
+use crate::capabilities::{self, Capability};
+use crate::error::{NodeCliError, Result};
+use crate::export::{export_range, ChainSource, DeployLayout, NdjsonWriter};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::Instant;
+
+/// Open an output file, appending when resuming an interrupted export
+fn open_output(path: &Path, append: bool) -> Result<Box<dyn Write>> {
+    let file = if append {
+        OpenOptions::new().create(true).append(true).open(path)
+    } else {
+        File::create(path)
+    }
+    .map_err(|e| NodeCliError::file_write_failed(&path.display().to_string(), &e.to_string()))?;
+    Ok(Box::new(BufWriter::new(file)))
+}
+
+fn build_writer(args: &ExportChainArgs) -> Result<NdjsonWriter<Box<dyn Write>>> {
+    let append = args.resume_from.is_some();
+    match (args.deploys, &args.output) {
+        (DeployLayout::Inline, Some(path)) => Ok(NdjsonWriter::inline(open_output(path, append)?)),
+        (DeployLayout::Inline, None) => Ok(NdjsonWriter::inline(Box::new(BufWriter::new(
+            std::io::stdout(),
+        )))),
+        (DeployLayout::Separate, Some(dir)) => {
+            fs::create_dir_all(dir).map_err(|e| {
+                NodeCliError::file_write_failed(&dir.display().to_string(), &e.to_string())
+            })?;
+            Ok(NdjsonWriter::separate(
+                open_output(&dir.join("blocks.ndjson"), append)?,
+                open_output(&dir.join("deploys.ndjson"), append)?,
+            ))
+        }
+        (DeployLayout::Separate, None) => Err(NodeCliError::config_missing_required(
+            "--output (directory for blocks.ndjson and deploys.ndjson)",
+        )),
+    }
+}
+
+/// Stream a height range to NDJSON, reporting progress on stderr
+pub async fn export_chain_command(args: &ExportChainArgs) -> Result<()> {
+    let start = args.resume_from.unwrap_or(args.start);
+    if start < 0 || args.end < 0 {
+        return Err(NodeCliError::config_invalid_value(
+            "start",
+            "block heights must be non-negative",
+        ));
+    }
+    if start > args.end {
+        return Err(NodeCliError::config_invalid_value(
+            "start",
+            &format!("{} is after --end {}", start, args.end),
+        ));
+    }
+    if args.chunk_size < 1 {
+        return Err(NodeCliError::config_invalid_value(
+            "chunk-size",
+            "must be at least 1",
+        ));
+    }
+
+    capabilities::require(&args.host, args.port, Capability::BlocksByHeight).await?;
+    if args.system_errors_only {
+        capabilities::require(&args.host, args.port, Capability::SystemDeploys).await?;
+    }
+    let source = ChainSource::new(&args.host, args.port);
+    let mut writer = build_writer(args)?;
+    let started = Instant::now();
+
+    eprintln!(
+        "Exporting heights {}..={} from {}:{}",
+        start, args.end, args.host, args.port
+    );
+    let total = args.end - start + 1;
+    let done = export_range(
+        &source,
+        start,
+        args.end,
+        args.chunk_size,
+        args.system_errors_only,
+        &mut writer,
+        |progress| {
+            eprintln!(
+                "Exported {}/{} heights ({} blocks, {} deploys)",
+                progress.next_height - start,
+                total,
+                progress.blocks,
+                progress.deploys
+            );
+        },
+    )
+    .await?;
+    writer.flush()?;
+
+    eprintln!(
+        "Export complete: {} blocks, {} deploys in {:.2?}",
+        done.blocks,
+        done.deploys,
+        started.elapsed()
+    );
+    if args.system_errors_only {
+        eprintln!(
+            "{} block(s) without a failed system deploy left out",
+            done.skipped
+        );
+    }
+    if done.unreported > 0 {
+        eprintln!(
+            "Warning: {} block(s) without system deploys (not provided by this node version)",
+            done.unreported
+        );
+    }
+    Ok(())
+}
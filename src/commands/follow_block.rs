@@ -0,0 +1,69 @@
+use crate::args::FollowBlockArgs;
+use crate::block_follow::{follow, probe_block, BlockFollower, FollowOutcome, FollowWait};
+use crate::capabilities::{self, Capability};
+use crate::error::Result;
+use crate::events::subscribe_block_events;
+use crate::f1r3fly_api::F1r3flyApi;
+use crate::utils::{print_info, print_progress, print_success, MIN_POLL_INTERVAL};
+use std::time::Instant;
+
+/// Follow a block through created, added and finalized, printing each stage
+/// with the time since the one before
+pub async fn follow_block_command(args: &FollowBlockArgs) -> Result<()> {
+    let node = &args.node;
+    capabilities::require(&node.host, args.ws_port, Capability::WebsocketEvents).await?;
+    let api = F1r3flyApi::for_queries(
+        args.query_key.private_key.as_deref(),
+        &node.host,
+        node.grpc_port,
+    )?;
+    let timeout = args.timeout;
+    let deadline = Instant::now() + timeout;
+
+    // Subscribe before the first poll so no event in between is missed
+    let ws_url = format!("ws://{}:{}", node.host, args.ws_port);
+    let mut events = subscribe_block_events(&ws_url);
+
+    print_info(&format!(
+        "Following block {} on {}:{} until {} (events from {})",
+        args.block_hash, node.host, node.http_port, args.until, ws_url
+    ));
+    let mut follower = BlockFollower::new(&args.block_hash);
+    let first = probe_block(&api, &args.block_hash, node.http_port).await?;
+    follower.observe_probe(&first, chrono::Utc::now().timestamp_millis());
+    if first.block.is_none() {
+        print_progress("Block not known to the node yet, waiting for it");
+    } else {
+        for line in follower.render() {
+            println!("  {}", line);
+        }
+    }
+
+    let outcome = match (follower.height(), follower.orphaned_by(&first)) {
+        (Some(height), Some(last_finalized)) => FollowOutcome::Orphaned {
+            height,
+            last_finalized,
+        },
+        _ => {
+            let (api, block_hash, http_port) = (&api, args.block_hash.as_str(), node.http_port);
+            follow(
+                &mut follower,
+                &mut events,
+                move || probe_block(api, block_hash, http_port),
+                FollowWait {
+                    until: args.until,
+                    poll_interval: args.check_interval.max(MIN_POLL_INTERVAL),
+                    deadline,
+                },
+                |follower, stage| println!("  {}", follower.describe(stage)),
+            )
+            .await?
+        }
+    };
+
+    if outcome == FollowOutcome::Reached {
+        println!("  {}", follower.summary());
+        print_success(&format!("Block {} {}", args.block_hash, args.until));
+    }
+    outcome.into_result(&args.block_hash, args.until, timeout)
+}
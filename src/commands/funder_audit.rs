@@ -0,0 +1,225 @@
+use crate::args::FunderAuditArgs;
+use crate::capabilities::{self, Capability};
+use crate::chain_stats::{fetch_headers, main_chain};
+use crate::error::{NodeCliError, Result};
+use crate::events::subscribe_blocks_added;
+use crate::export::{ChainSource, DEFAULT_CHUNK_SIZE};
+use crate::f1r3fly_api::F1r3flyApi;
+use crate::funder_audit::{attribute, Attribution, AuditEvent, Check, Flow, Ledger};
+use crate::identities::truncate_key;
+use crate::staking::StakingQueries;
+use crate::vault::DUST_FACTOR;
+use crate::wallet_watch::{scan_blocks, WalletWatch};
+use std::collections::HashSet;
+
+/// Attributions shown with a drift alarm
+const ALARM_RECENT: usize = 10;
+
+/// Reconcile a funder account's balance: backfill from `--from-height`, then
+/// attribute every added block and compare with the node's balance each interval
+pub async fn funder_audit_command(args: &FunderAuditArgs) -> Result<()> {
+    let watch = WalletWatch::new(std::slice::from_ref(&args.funder))?;
+    let address = watch
+        .addresses()
+        .next()
+        .ok_or_else(|| NodeCliError::config_invalid_value("address", "no vault address"))?
+        .to_string();
+    let identities = args.identity.load()?;
+    capabilities::require(&args.host, args.ws_port, Capability::WebsocketEvents).await?;
+    if args.from_height.is_some() {
+        capabilities::require(&args.host, args.http_port, Capability::BlocksByHeight).await?;
+    }
+    let source = ChainSource::new(&args.host, args.http_port);
+    let queries = StakingQueries::new(&args.host, args.http_port);
+
+    // Subscribe before backfilling so blocks added during the scan are not lost
+    let ws_url = format!("ws://{}:{}", args.host, args.ws_port);
+    let mut blocks = subscribe_blocks_added(&ws_url);
+
+    let mut seen = HashSet::new();
+    let mut backfilled = Vec::new();
+    let starting_balance = match args.from_height {
+        Some(from_height) => {
+            let from_height = from_height.max(0);
+            let starting_balance = balance_before(&source, &queries, &address, from_height).await?;
+            let api =
+                F1r3flyApi::for_queries(args.query_key.private_key.as_deref(), &args.host, 0)?;
+            let tip = api
+                .get_tip_block_number(args.http_port)
+                .await?
+                .ok_or_else(|| {
+                    NodeCliError::network_connection_failed("node reported no tip block")
+                })?;
+            if !args.output.is_json() {
+                println!(" Backfilling heights {}..={}", from_height, tip);
+            }
+            seen = scan_blocks(&source, from_height, tip, DEFAULT_CHUNK_SIZE, |block| {
+                backfilled.extend(attribute(block, &watch, &identities))
+            })
+            .await?;
+            starting_balance
+        }
+        None => queries.vault_balance(&address).await?,
+    };
+
+    let mut ledger = Ledger::new(starting_balance.max(0) as u64, args.tolerance, args.window);
+    if !args.output.is_json() {
+        println!(" Auditing funder {}", address);
+        println!(
+            " Starting balance {} tokens, tolerance {} dust",
+            tokens(starting_balance),
+            args.tolerance
+        );
+    }
+    for attribution in backfilled {
+        report(&attribution, args.output.is_json());
+        ledger.record(attribution);
+    }
+    if !args.output.is_json() {
+        println!(
+            " Following new blocks on {}... (Press Ctrl+C to stop)\n",
+            ws_url
+        );
+    }
+
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+    let mut interval = tokio::time::interval(args.interval);
+
+    loop {
+        tokio::select! {
+            _ = &mut ctrl_c => {
+                print_spend_table(&ledger, args.output.is_json());
+                return Ok(());
+            }
+            added = blocks.recv() => {
+                let Some(added) = added else {
+                    print_spend_table(&ledger, args.output.is_json());
+                    return Err(NodeCliError::network_connection_failed("event stream ended"));
+                };
+                // A block the backfill already scanned arrives at most once more
+                if seen.remove(&added.block_hash) {
+                    continue;
+                }
+                match source.block(&added.block_hash).await {
+                    Ok(block) => {
+                        for attribution in attribute(&block, &watch, &identities) {
+                            report(&attribution, args.output.is_json());
+                            ledger.record(attribution);
+                        }
+                    }
+                    Err(e) => eprintln!(" Failed to fetch block {}: {}", added.block_hash, e),
+                }
+            }
+            _ = interval.tick() => {
+                let balance = match queries.vault_balance(&address).await {
+                    Ok(balance) => balance.max(0) as u64,
+                    Err(e) => {
+                        eprintln!(" Failed to query the funder's balance: {}", e);
+                        continue;
+                    }
+                };
+                let check = ledger.check(balance);
+                let summary = ledger.summary();
+                match (check, args.output.is_json()) {
+                    (Check::Drift, true) => print_json(&AuditEvent::Drift(&summary)),
+                    (_, true) => print_json(&AuditEvent::Summary(&summary)),
+                    (Check::Drift, false) => {
+                        println!(" DRIFT  {}", summary.line());
+                        println!(
+                            "   The balance moved by more than {} dust beyond what the observed deploys explain",
+                            ledger.tolerance
+                        );
+                        let skip = ledger.recent.len().saturating_sub(ALARM_RECENT);
+                        for attribution in ledger.recent.iter().skip(skip) {
+                            report(attribution, false);
+                        }
+                    }
+                    (_, false) => println!(" {}", summary.line()),
+                }
+            }
+        }
+    }
+}
+
+/// The funder's balance on the main chain just before `height`
+///
+/// At height 0 there is no earlier block, so the balance is taken at genesis.
+async fn balance_before(
+    source: &ChainSource,
+    queries: &StakingQueries,
+    address: &str,
+    height: i64,
+) -> Result<i64> {
+    let pin = (height - 1).max(0);
+    let headers = fetch_headers(source, pin, pin, 1).await?;
+    let block = main_chain(&headers).pop().ok_or_else(|| {
+        NodeCliError::network_connection_failed(&format!("no block at height {}", pin))
+    })?;
+    queries
+        .vault_balance_at(address, Some(block.block_hash.as_str()))
+        .await
+}
+
+fn tokens(dust: i64) -> f64 {
+    dust as f64 / DUST_FACTOR as f64
+}
+
+fn print_json(event: &AuditEvent) {
+    match serde_json::to_string(event) {
+        Ok(line) => println!("{}", line),
+        Err(e) => eprintln!(" Failed to encode event: {}", e),
+    }
+}
+
+fn report(attribution: &Attribution, json: bool) {
+    if json {
+        print_json(&AuditEvent::Attribution(attribution));
+        return;
+    }
+    let sign = match attribution.flow {
+        Flow::Debit => '-',
+        Flow::Credit => '+',
+    };
+    println!(
+        " #{} {}  {}{} tokens  phlo {}  {}{}",
+        attribution.block_number,
+        attribution.deploy_id,
+        sign,
+        tokens(attribution.amount as i64),
+        attribution.phlo,
+        attribution.who(),
+        if attribution.errored {
+            "  (errored)"
+        } else {
+            ""
+        }
+    );
+}
+
+/// Per-deployer totals, largest spender first
+fn print_spend_table(ledger: &Ledger, json: bool) {
+    if json {
+        print_json(&AuditEvent::Summary(&ledger.summary()));
+        for (deployer, spend) in ledger.spend_table() {
+            print_json(&AuditEvent::Deployer { deployer, spend });
+        }
+        return;
+    }
+    println!("\n Final reconciliation: {}", ledger.summary().line());
+    println!(
+        "\n {:<24} {:>7} {:>16} {:>16} {:>12}",
+        "DEPLOYER", "DEPLOYS", "SENT", "RECEIVED", "PHLO"
+    );
+    for (key, spend) in ledger.spend_table() {
+        let who = spend.name.clone().unwrap_or_else(|| truncate_key(key));
+        println!(
+            " {:<24} {:>7} {:>16} {:>16} {:>12}",
+            who,
+            spend.deploys,
+            tokens(spend.debited as i64),
+            tokens(spend.credited as i64),
+            spend.phlo
+        );
+    }
+}
@@ -0,0 +1,135 @@
+use crate::args::{HistoryAction, HistoryArgs, QueryKeyArgs};
+use crate::error::{NodeCliError, Result};
+use crate::f1r3fly_api::F1r3flyApi;
+use crate::history::{self, HistoryRecord};
+use crate::ports;
+use chrono::{TimeZone, Utc};
+use std::time::Duration;
+
+/// Search and inspect the local deploy history
+pub async fn history_command(args: &HistoryArgs) -> Result<()> {
+    let path = history::history_path()
+        .ok_or_else(|| NodeCliError::config_missing_required(history::HISTORY_FILE_ENV))?;
+    let records = history::read_records(&path)
+        .map_err(|e| NodeCliError::file_read_failed(&path.display().to_string(), &e.to_string()))?;
+
+    match &args.action {
+        HistoryAction::List { since } => {
+            let records = match since {
+                Some(window) => {
+                    let since = history::parse_since(window)
+                        .map_err(|e| NodeCliError::config_invalid_value("since", &e))?;
+                    history::filter_since(records, since)
+                }
+                None => records,
+            };
+            print_records(&records, &path.display().to_string());
+        }
+        HistoryAction::Search { prefix } => {
+            let matches: Vec<HistoryRecord> = records
+                .into_iter()
+                .filter(|r| r.matches_prefix(prefix))
+                .collect();
+            print_records(&matches, &path.display().to_string());
+        }
+        HistoryAction::Show {
+            reference,
+            check,
+            query_key,
+        } => {
+            let record = records
+                .into_iter()
+                .rev()
+                .find(|r| r.matches_prefix(reference))
+                .ok_or_else(|| {
+                    NodeCliError::General(format!("No history record matches '{}'", reference))
+                })?;
+
+            println!("{}", serde_json::to_string_pretty(&record)?);
+            print_hints(&record);
+
+            if *check {
+                check_record(&record, query_key).await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn print_records(records: &[HistoryRecord], path: &str) {
+    if records.is_empty() {
+        println!("No deploy history records found in {}", path);
+        return;
+    }
+
+    for record in records {
+        let time = Utc
+            .timestamp_millis_opt(record.timestamp)
+            .single()
+            .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|| record.timestamp.to_string());
+        println!(
+            "{}  {:<16} {}:{}  deploy={}  block={}  {}",
+            time,
+            record.command,
+            record.host,
+            record.port,
+            record.deploy_id.as_deref().unwrap_or("-"),
+            record.block_hash.as_deref().unwrap_or("-"),
+            record.outcome
+        );
+        if let Some(replacement) = &record.superseded_by {
+            println!("    superseded by {}", replacement);
+        }
+    }
+    println!("{} record(s)", records.len());
+}
+
+fn print_hints(record: &HistoryRecord) {
+    if let Some(deploy_id) = &record.deploy_id {
+        println!(
+            "\nCheck deploy:   node_cli get-deploy -d {} -H {} --http-port {}",
+            deploy_id,
+            record.host,
+            record.http_port.unwrap_or(ports::VALIDATOR1.http)
+        );
+    }
+    if let Some(block_hash) = &record.block_hash {
+        println!(
+            "Check finality: node_cli is-finalized -b {} -H {} -p {}",
+            block_hash, record.host, record.port
+        );
+    }
+}
+
+/// Re-query the recorded node for the deploy's current status
+async fn check_record(record: &HistoryRecord, query_key: &QueryKeyArgs) -> Result<()> {
+    let f1r3fly_api =
+        F1r3flyApi::for_queries(query_key.private_key.as_deref(), &record.host, record.port)?;
+
+    if let Some(deploy_id) = &record.deploy_id {
+        let http_port = record.http_port.unwrap_or(ports::VALIDATOR1.http);
+        match f1r3fly_api.get_deploy_detail(deploy_id, http_port).await? {
+            Some(detail) => {
+                println!("\nCurrent status on {}:{}", record.host, http_port);
+                println!("  Block hash:  {}", detail.block_hash);
+                println!("  Finalized:   {}", detail.is_finalized);
+                println!("  Errored:     {}", detail.errored);
+                if let Some(err) = &detail.system_deploy_error {
+                    println!("  Error:       {}", err);
+                }
+            }
+            None => println!("\nDeploy {} not found in any block yet", deploy_id),
+        }
+    } else if let Some(block_hash) = &record.block_hash {
+        let finalized = f1r3fly_api
+            .is_finalized(block_hash, 1, Duration::ZERO)
+            .await?;
+        println!("\nBlock {} finalized: {}", block_hash, finalized);
+    } else {
+        println!("\nRecord has no deploy ID or block hash to check");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,257 @@
+use crate::args::LatencyProbeArgs;
+use crate::error::Result;
+use crate::f1r3fly_api::{F1r3flyApi, InclusionOutcome, ProposeResult};
+use crate::latency::{
+    Baseline, StageSummary, StageTimings, PROBE_TERM, STAGE_EXPLORATORY, STAGE_FINALIZATION,
+    STAGE_INCLUSION, STAGE_PROPOSE, STAGE_STATUS, STAGE_SUBMIT,
+};
+use crate::utils::{
+    format_change, format_duration, format_timestamp, polls_within, print_info, print_progress,
+    print_success, print_warning, Backoff, MIN_POLL_INTERVAL,
+};
+use std::time::Instant;
+
+/// Samples per stage needed on both sides before a change counts as a regression
+const MIN_COMPARE_SAMPLES: usize = 3;
+
+/// Time each stage of the deploy pipeline over a few cheap rounds
+pub async fn latency_probe_command(args: &LatencyProbeArgs) -> Result<()> {
+    let api = F1r3flyApi::new(
+        &args.signing.private_key,
+        &args.node.host,
+        args.node.grpc_port,
+    )?;
+    let observer_host = args.observer.host(&args.node);
+    let observer_api = F1r3flyApi::new(
+        &args.signing.private_key,
+        observer_host,
+        args.observer.grpc_port(),
+    )?;
+
+    print_info(&format!(
+        "Probing {}:{} with {} {} round(s)",
+        args.node.host,
+        args.node.grpc_port,
+        args.count,
+        if args.read_only {
+            "read-only"
+        } else {
+            "deploy"
+        }
+    ));
+
+    let mut timings = StageTimings::new();
+    let mut failed = 0;
+    for round in 1..=args.count {
+        let result = if args.read_only {
+            read_only_round(&api, &observer_api, args, &mut timings).await
+        } else {
+            deploy_round(&api, &observer_api, args, &mut timings).await
+        };
+        match result {
+            Ok(()) => print_progress(&format!("Round {}/{} done", round, args.count)),
+            Err(e) => {
+                failed += 1;
+                print_warning(&format!("Round {}/{} failed: {}", round, args.count, e));
+            }
+        }
+        if round < args.count {
+            tokio::time::sleep(args.interval).await;
+        }
+    }
+
+    let summaries = timings.summaries();
+    print_summaries(&summaries, failed, args.count);
+
+    if let Some(path) = &args.baseline {
+        match Baseline::load(path)? {
+            Some(baseline) => {
+                print_comparison(&baseline, &summaries, args.threshold);
+                if args.update_baseline {
+                    new_baseline(args, summaries).save(path)?;
+                    print_success(&format!("Baseline updated: {}", path.display()));
+                }
+            }
+            None => {
+                new_baseline(args, summaries).save(path)?;
+                print_success(&format!("Baseline saved: {}", path.display()));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Deploy the probe term and follow it to finalization
+async fn deploy_round(
+    api: &F1r3flyApi<'_>,
+    observer_api: &F1r3flyApi<'_>,
+    args: &LatencyProbeArgs,
+    timings: &mut StageTimings,
+) -> Result<()> {
+    let started = Instant::now();
+    // The deploy call returns once the node has accepted it into its pool
+    let submitted = api.submit_deploy(PROBE_TERM, false, "rholang", 0).await?;
+    timings.record(STAGE_SUBMIT, elapsed_ms(started));
+
+    if args.propose {
+        let started = Instant::now();
+        if let ProposeResult::Skipped(reason) = api.propose().await? {
+            print_warning(&format!("Propose skipped: {}", reason));
+        }
+        timings.record(STAGE_PROPOSE, elapsed_ms(started));
+    }
+
+    let started = Instant::now();
+    // A fixed interval, so every probe measures latency the same way
+    let check_interval = args.check_interval.max(MIN_POLL_INTERVAL);
+    let block_hash = match api
+        .wait_for_inclusion(
+            &submitted.deploy_id,
+            args.node.http_port,
+            None,
+            &Backoff::fixed(check_interval),
+            polls_within(args.inclusion_timeout, check_interval),
+        )
+        .await?
+    {
+        InclusionOutcome::Included { block_hash } => block_hash,
+        InclusionOutcome::Expired { expired_at, tip } => {
+            return Err(format!(
+                "deploy {} expired at block {} (tip {})",
+                submitted.deploy_id, expired_at, tip
+            )
+            .into())
+        }
+        InclusionOutcome::TimedOut { .. } => {
+            return Err(format!(
+                "deploy {} not included within {}",
+                submitted.deploy_id,
+                format_duration(args.inclusion_timeout)
+            )
+            .into())
+        }
+    };
+    timings.record(STAGE_INCLUSION, elapsed_ms(started));
+
+    let started = Instant::now();
+    let finalized = observer_api
+        .is_finalized(
+            &block_hash,
+            polls_within(args.finalization_timeout, check_interval),
+            check_interval,
+        )
+        .await?;
+    if !finalized {
+        return Err(format!(
+            "block {} not finalized within {}",
+            block_hash,
+            format_duration(args.finalization_timeout)
+        )
+        .into());
+    }
+    timings.record(STAGE_FINALIZATION, elapsed_ms(started));
+    Ok(())
+}
+
+/// Evaluate the probe term without deploying it, then read the node status
+async fn read_only_round(
+    api: &F1r3flyApi<'_>,
+    observer_api: &F1r3flyApi<'_>,
+    args: &LatencyProbeArgs,
+    timings: &mut StageTimings,
+) -> Result<()> {
+    let started = Instant::now();
+    observer_api
+        .exploratory_deploy(PROBE_TERM, None, false)
+        .await?;
+    timings.record(STAGE_EXPLORATORY, elapsed_ms(started));
+
+    let started = Instant::now();
+    if api.get_node_status(args.node.http_port).await?.is_none() {
+        return Err("node returned no status".into());
+    }
+    timings.record(STAGE_STATUS, elapsed_ms(started));
+    Ok(())
+}
+
+fn elapsed_ms(started: Instant) -> u64 {
+    started.elapsed().as_millis() as u64
+}
+
+fn new_baseline(args: &LatencyProbeArgs, stages: Vec<StageSummary>) -> Baseline {
+    Baseline {
+        target: format!("{}:{}", args.node.host, args.node.grpc_port),
+        read_only: args.read_only,
+        created_at: chrono::Utc::now().timestamp_millis(),
+        stages,
+    }
+}
+
+fn print_summaries(summaries: &[StageSummary], failed: u32, count: u32) {
+    println!();
+    println!(
+        "  {:<14} {:>6} {:>9} {:>9} {:>9}",
+        "Stage", "Count", "Min", "p50", "p95"
+    );
+    for StageSummary { stage, summary } in summaries {
+        println!(
+            "  {:<14} {:>6} {:>7}ms {:>7}ms {:>7}ms",
+            stage, summary.count, summary.min, summary.p50, summary.p95
+        );
+    }
+    if failed > 0 {
+        println!("\n{} of {} round(s) failed", failed, count);
+    }
+}
+
+fn print_comparison(baseline: &Baseline, summaries: &[StageSummary], threshold_percent: f64) {
+    println!();
+    println!(
+        "Compared with baseline for {} from {}:",
+        baseline.target,
+        format_timestamp(baseline.created_at)
+    );
+    let changes = baseline.compare(summaries);
+    if changes.is_empty() {
+        print_warning("No stages in common with the baseline");
+        return;
+    }
+    let threshold = threshold_percent / 100.0;
+    let mut regressions = 0;
+    println!(
+        "  {:<14} {:>22} {:>22}",
+        "stage", "p50 before -> after", "p95 before -> after"
+    );
+    for change in &changes {
+        let comparison = &change.comparison;
+        let regressed = comparison.is_regression(threshold, MIN_COMPARE_SAMPLES);
+        if regressed {
+            regressions += 1;
+        }
+        println!(
+            "  {:<14} {:>22} {:>22}{}",
+            change.stage,
+            format!(
+                "{}ms -> {}ms ({})",
+                comparison.before.p50,
+                comparison.after.p50,
+                format_change(comparison.p50_change())
+            ),
+            format!(
+                "{}ms -> {}ms ({})",
+                comparison.before.p95,
+                comparison.after.p95,
+                format_change(comparison.p95_change())
+            ),
+            if regressed { "  REGRESSION" } else { "" }
+        );
+    }
+    if regressions > 0 {
+        println!(
+            "{} stage(s) regressed by more than {}%",
+            regressions, threshold_percent
+        );
+    } else {
+        println!("No regressions over {}%", threshold_percent);
+    }
+}
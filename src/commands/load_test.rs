@@ -1,5 +1,10 @@
 use crate::args::LoadTestArgs;
-use crate::f1r3fly_api::F1r3flyApi;
+use crate::contract_templates::transfer_contract;
+use crate::f1r3fly_api::{F1r3flyApi, ProposeResult};
+use crate::grpc::BIGGER_PHLO_LIMIT;
+use crate::load_batches::{next_timestamp, BatchLedger, BatchRow, LoadMode};
+use crate::truncation::{shorten, Field};
+use crate::utils::{format_duration, now_millis, Backoff, Summary, MIN_POLL_INTERVAL};
 use chrono::Local;
 use std::time::{Duration, Instant};
 
@@ -15,23 +20,33 @@ pub struct TestResult {
 }
 
 pub async fn load_test_command(args: &LoadTestArgs) -> Result<(), Box<dyn std::error::Error>> {
-    use crate::utils::CryptoUtils;
-
     println!("");
     println!(" F1R3FLY Load Test ");
     println!("");
-    println!("Tests: {}", args.num_tests);
-    println!("Amount: {}", args.amount);
-    println!("Interval: {}s", args.interval);
-    println!("Check interval: {}s (fast mode)", args.check_interval);
-    println!("Target: {}:{}", args.host, args.port);
+    match args.mode {
+        LoadMode::Sequential => println!("Tests: {}", args.num_tests),
+        LoadMode::ProposeBatches => println!(
+            "Mode: propose-batches ({} batches of {})",
+            args.num_tests, args.batch_size
+        ),
+    }
+    println!("Amount: {}", args.transfer_amount());
+    println!("Interval: {}", format_duration(args.interval));
+    println!(
+        "Check interval: {} (fast mode)",
+        format_duration(args.check_interval)
+    );
+    println!("Target: {}:{}", args.node.host, args.node.grpc_port);
     println!();
 
-    // Derive sender address from private key
-    let secret_key = CryptoUtils::decode_private_key(&args.private_key)?;
-    let public_key = CryptoUtils::derive_public_key(&secret_key);
-    let public_key_hex = CryptoUtils::serialize_public_key(&public_key, false);
-    let sender_address = CryptoUtils::generate_vault_address(&public_key_hex)?;
+    // Initialize API once: the key is parsed here and the gRPC channel is
+    // dialed on the first deploy, then reused for every test
+    let api = F1r3flyApi::new(
+        &args.signing.private_key,
+        &args.node.host,
+        args.node.grpc_port,
+    )?;
+    let sender_address = api.vault_address()?;
 
     // Check initial balances
     println!(" Checking initial wallet balances...");
@@ -63,8 +78,9 @@ pub async fn load_test_command(args: &LoadTestArgs) -> Result<(), Box<dyn std::e
     println!("");
     println!();
 
-    // Initialize API once (reuse connection)
-    let api = F1r3flyApi::new(&args.private_key, &args.host, args.port)?;
+    if args.mode == LoadMode::ProposeBatches {
+        return run_propose_batches(&api, &sender_address, args).await;
+    }
 
     let mut results = Vec::new();
 
@@ -74,7 +90,7 @@ pub async fn load_test_command(args: &LoadTestArgs) -> Result<(), Box<dyn std::e
         println!("");
 
         // Run single test with detailed logging
-        let result = run_single_test(&api, args, test_num).await?;
+        let result = run_single_test(&api, &sender_address, args, test_num).await?;
 
         results.push(result);
 
@@ -83,8 +99,11 @@ pub async fn load_test_command(args: &LoadTestArgs) -> Result<(), Box<dyn std::e
 
         // Wait before next test (unless last one)
         if test_num < args.num_tests {
-            println!(" Waiting {}s before next test...\n", args.interval);
-            tokio::time::sleep(Duration::from_secs(args.interval)).await;
+            println!(
+                " Waiting {} before next test...\n",
+                format_duration(args.interval)
+            );
+            tokio::time::sleep(args.interval).await;
         }
     }
 
@@ -96,6 +115,7 @@ pub async fn load_test_command(args: &LoadTestArgs) -> Result<(), Box<dyn std::e
 
 async fn run_single_test(
     api: &F1r3flyApi<'_>,
+    sender_address: &str,
     args: &LoadTestArgs,
     test_num: u32,
 ) -> Result<TestResult, Box<dyn std::error::Error>> {
@@ -105,7 +125,11 @@ async fn run_single_test(
     println!(" [{}] Deploying transfer...", now_timestamp());
     let deploy_start = Instant::now();
 
-    let rholang = generate_transfer_contract(args);
+    let rholang = transfer_contract(
+        sender_address,
+        &args.to_address,
+        args.transfer_amount().dust(),
+    )?;
     // Load tests don't use expiration timestamp (0 means no expiration)
     let deploy_id = api.deploy(&rholang, true, "rholang", 0).await?.to_string();
 
@@ -120,11 +144,12 @@ async fn run_single_test(
     println!(" [{}] Waiting for block inclusion...", now_timestamp());
     let block_wait_start = Instant::now();
 
+    let backoff = args.poll.backoff(args.check_interval);
     let block_hash = wait_for_block_fast(
         api,
         &deploy_id,
-        args.http_port,
-        args.check_interval,
+        args.node.http_port,
+        &backoff,
         args.inclusion_timeout,
     )
     .await?;
@@ -141,9 +166,9 @@ async fn run_single_test(
     println!(" [{}] Waiting for block finalization...", now_timestamp());
     let finalization_start = Instant::now();
 
-    let max_finalization_attempts = (args.finalization_timeout / args.check_interval.max(1)) as u32;
+    let max_finalization_attempts = backoff.polls_within(args.finalization_timeout);
     let is_finalized = api
-        .is_finalized(&block_hash, max_finalization_attempts, args.check_interval)
+        .is_finalized_with_backoff(&block_hash, max_finalization_attempts, &backoff)
         .await?;
 
     let finalization_time = finalization_start.elapsed();
@@ -179,7 +204,7 @@ async fn run_single_test(
 
     // Step 5: Get wallet balance
     println!(" [{}] Checking wallet balance...", now_timestamp());
-    match get_wallet_balance(api, args).await {
+    match get_balance_for_address(sender_address, args).await {
         Ok(balance) => {
             println!(" [{}] Wallet balance: {}", now_timestamp(), balance);
         }
@@ -201,79 +226,232 @@ async fn run_single_test(
     })
 }
 
-fn generate_transfer_contract(args: &LoadTestArgs) -> String {
-    use crate::utils::CryptoUtils;
-
-    // Derive sender address from private key
-    let secret_key =
-        CryptoUtils::decode_private_key(&args.private_key).expect("Invalid private key");
-    let public_key = CryptoUtils::derive_public_key(&secret_key);
-    let public_key_hex = CryptoUtils::serialize_public_key(&public_key, false);
-    let from_address =
-        CryptoUtils::generate_vault_address(&public_key_hex).expect("Failed to generate address");
-
-    let amount_dust = args.amount * 100_000_000;
-
-    format!(
-        r#"new 
- deployerId(`rho:system:deployerId`),
- stdout(`rho:io:stdout`),
- rl(`rho:registry:lookup`),
- systemVaultCh,
- vaultCh,
- toVaultCh,
- systemVaultKeyCh,
- resultCh
-in {{
- rl!(`rho:vault:system`, *systemVaultCh) |
- for (@(_, SystemVault) <- systemVaultCh) {{
- @SystemVault!("findOrCreate", "{}", *vaultCh) |
- @SystemVault!("findOrCreate", "{}", *toVaultCh) |
- @SystemVault!("deployerAuthKey", *deployerId, *systemVaultKeyCh) |
- for (@(true, vault) <- vaultCh; key <- systemVaultKeyCh; @(true, toVault) <- toVaultCh) {{
- @vault!("transfer", "{}", {}, *key, *resultCh) |
- for (@result <- resultCh) {{
- match result {{
- (true, Nil) => {{
- stdout!(("Transfer successful:", {}, "tokens"))
- }}
- (false, reason) => {{
- stdout!(("Transfer failed:", reason))
- }}
- }}
- }}
- }} |
- for (@(false, errorMsg) <- vaultCh) {{
- stdout!(("Sender vault error:", errorMsg))
- }} |
- for (@(false, errorMsg) <- toVaultCh) {{
- stdout!(("Destination vault error:", errorMsg))
- }}
- }}
-}}"#,
-        from_address, args.to_address, args.to_address, amount_dust, amount_dust
-    )
+/// A block proposed by a batch, watched until it is finalized
+struct ProposedBlock {
+    hash: String,
+    proposed_at: Instant,
+    finalized_after: Option<Duration>,
+}
+
+/// Submit batches of transfers, proposing on the same node after each one
+async fn run_propose_batches(
+    api: &F1r3flyApi<'_>,
+    sender_address: &str,
+    args: &LoadTestArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let rholang = transfer_contract(
+        sender_address,
+        &args.to_address,
+        args.transfer_amount().dust(),
+    )?;
+    let mut ledger = BatchLedger::new();
+    let mut blocks: Vec<ProposedBlock> = Vec::new();
+    let mut last_timestamp = None;
+
+    for _ in 0..args.num_tests {
+        let batch = ledger.start_batch();
+        println!("");
+        println!(" Batch {}/{}", batch, args.num_tests);
+        println!("");
+
+        let submit_start = Instant::now();
+        for _ in 0..args.batch_size {
+            let timestamp = next_timestamp(last_timestamp, now_millis());
+            last_timestamp = Some(timestamp);
+            match api
+                .deploy_with_timestamp_and_phlo_limit(
+                    &rholang,
+                    "rholang",
+                    Some(timestamp),
+                    BIGGER_PHLO_LIMIT,
+                )
+                .await
+            {
+                Ok(deploy_id) => ledger.submitted(deploy_id),
+                Err(e) => {
+                    println!(" [{}] Deploy rejected: {}", now_timestamp(), e);
+                    ledger.rejected();
+                }
+            }
+        }
+        println!(
+            " [{}] Submitted {} deploy(s) ({}ms)",
+            now_timestamp(),
+            args.batch_size,
+            submit_start.elapsed().as_millis()
+        );
+
+        println!(" [{}] Proposing...", now_timestamp());
+        let propose_start = Instant::now();
+        let proposed = api.propose().await;
+        let latency = propose_start.elapsed();
+        let row = match proposed {
+            Ok(ProposeResult::Proposed { block_hash, .. }) => {
+                let deploy_ids = match api.get_block_detail(&block_hash, args.node.http_port).await
+                {
+                    Ok(Some(block)) => block.deploys.into_iter().map(|d| d.sig).collect(),
+                    Ok(None) => {
+                        println!(" Block {} not found on the node", block_hash);
+                        Vec::new()
+                    }
+                    Err(e) => {
+                        println!(" Failed to read block {}: {}", block_hash, e);
+                        Vec::new()
+                    }
+                };
+                blocks.push(ProposedBlock {
+                    hash: block_hash.clone(),
+                    proposed_at: propose_start,
+                    finalized_after: None,
+                });
+                ledger.proposed(&block_hash, &deploy_ids, latency).clone()
+            }
+            Ok(ProposeResult::Skipped(reason)) => ledger.skipped(reason, latency).clone(),
+            Err(e) => ledger.skipped(e.to_string(), latency).clone(),
+        };
+        print_batch_row(&row);
+
+        // One look per block, so finalization never holds up the next batch
+        check_finalized(api, &mut blocks).await;
+
+        if batch < args.num_tests && !args.interval.is_zero() {
+            tokio::time::sleep(args.interval).await;
+        }
+    }
+
+    let deadline = Instant::now() + args.finalization_timeout;
+    let check_interval = args.check_interval.max(MIN_POLL_INTERVAL);
+    while blocks.iter().any(|b| b.finalized_after.is_none()) && Instant::now() < deadline {
+        tokio::time::sleep(check_interval).await;
+        check_finalized(api, &mut blocks).await;
+    }
+
+    print_batch_summary(&ledger, &blocks);
+    Ok(())
+}
+
+/// Check each proposed block not yet known to be finalized, once
+async fn check_finalized(api: &F1r3flyApi<'_>, blocks: &mut [ProposedBlock]) {
+    for block in blocks.iter_mut().filter(|b| b.finalized_after.is_none()) {
+        match api.is_finalized(&block.hash, 1, MIN_POLL_INTERVAL).await {
+            Ok(true) => block.finalized_after = Some(block.proposed_at.elapsed()),
+            Ok(false) => {}
+            Err(e) => tracing::debug!("Finalization check for {} failed: {}", block.hash, e),
+        }
+    }
+}
+
+fn print_batch_row(row: &BatchRow) {
+    match (&row.block_hash, &row.skipped) {
+        (Some(hash), _) => println!(
+            " [{}] Proposed {} in {}ms: {}/{} included, {} carried over, {} pending",
+            now_timestamp(),
+            shorten(hash, Field::Hash),
+            row.propose_latency.as_millis(),
+            row.included,
+            row.submitted,
+            row.carried_included,
+            row.pending_after
+        ),
+        (None, reason) => println!(
+            " [{}] No block after {}ms ({}); {} pending",
+            now_timestamp(),
+            row.propose_latency.as_millis(),
+            reason.as_deref().unwrap_or("propose skipped"),
+            row.pending_after
+        ),
+    }
 }
 
-// Fast block polling (configurable interval and timeout)
+fn print_batch_summary(ledger: &BatchLedger, blocks: &[ProposedBlock]) {
+    println!();
+    println!("");
+    println!(" BATCH RESULTS ");
+    println!("");
+    println!(
+        " {:>5}  {:>9}  {:>8}  {:>7}  {:>7}  {:>8}  Block",
+        "Batch", "Submitted", "Included", "Carried", "Pending", "Propose"
+    );
+    for row in ledger.rows() {
+        println!(
+            " {:>5}  {:>9}  {:>8}  {:>7}  {:>7}  {:>6}ms  {}",
+            row.batch,
+            row.submitted,
+            row.included,
+            row.carried_included,
+            row.pending_after,
+            row.propose_latency.as_millis(),
+            row.block_hash
+                .as_deref()
+                .map_or("-".to_string(), |hash| shorten(hash, Field::Hash))
+        );
+    }
+
+    let totals = ledger.totals();
+    println!();
+    println!(
+        "Batches: {}, blocks proposed: {}",
+        totals.batches, totals.blocks
+    );
+    println!(
+        "Submitted: {} ({} rejected)",
+        totals.submitted, totals.rejected
+    );
+    if totals.submitted > 0 {
+        println!(
+            " Included: {} ({}%), {} in a later batch's block",
+            totals.included,
+            totals.included * 100 / totals.submitted,
+            totals.carried
+        );
+    }
+    println!(" Never included in a proposed block: {}", totals.pending);
+
+    let propose = Summary::from_samples(
+        ledger
+            .rows()
+            .iter()
+            .map(|r| r.propose_latency.as_millis() as u64),
+    );
+    if let Some(propose) = propose {
+        println!(
+            "Propose latency: p50 {}ms, p95 {}ms, max {}ms",
+            propose.p50, propose.p95, propose.max
+        );
+    }
+    let finalized: Vec<u64> = blocks
+        .iter()
+        .filter_map(|b| b.finalized_after)
+        .map(|d| d.as_millis() as u64)
+        .collect();
+    println!("Finalized: {}/{} blocks", finalized.len(), blocks.len());
+    if let Some(finalization) = Summary::from_samples(finalized) {
+        print_timing("Propose to finalization", &finalization);
+    }
+}
+
+// Fast block polling (configurable backoff and timeout)
 async fn wait_for_block_fast(
     api: &F1r3flyApi<'_>,
     deploy_id: &str,
     http_port: u16,
-    check_interval: u64,
-    timeout_seconds: u64,
+    backoff: &Backoff,
+    timeout: Duration,
 ) -> Result<String, Box<dyn std::error::Error>> {
-    let max_attempts = timeout_seconds / check_interval.max(1);
+    let start = Instant::now();
 
-    for attempt in 1..=max_attempts {
+    for attempt in 1..=backoff.polls_within(timeout) {
         if attempt % 10 == 0 {
-            println!(" Still waiting... ({}s elapsed)", attempt * check_interval);
+            println!(
+                " Still waiting... ({} elapsed)",
+                format_duration(start.elapsed())
+            );
         }
 
         match api.get_deploy_block_hash(deploy_id, http_port).await? {
             Some(hash) => return Ok(hash),
             None => {
-                tokio::time::sleep(Duration::from_secs(check_interval)).await;
+                backoff.wait(attempt - 1).await;
             }
         }
     }
@@ -322,7 +500,11 @@ async fn get_balance_for_address(
     );
 
     // Create a separate API instance for read-only port
-    let readonly_api = F1r3flyApi::new(&args.private_key, &args.host, args.readonly_port)?;
+    let readonly_api = F1r3flyApi::new(
+        &args.signing.private_key,
+        args.observer.host(&args.node),
+        args.observer.grpc_port(),
+    )?;
 
     // Execute exploratory deploy to get balance on read-only node
     let (result, _block_info, _cost) = readonly_api
@@ -332,22 +514,6 @@ async fn get_balance_for_address(
     Ok(result.trim().to_string())
 }
 
-// Get wallet balance for the sender address (convenience wrapper)
-async fn get_wallet_balance(
-    _api: &F1r3flyApi<'_>,
-    args: &LoadTestArgs,
-) -> Result<String, Box<dyn std::error::Error>> {
-    use crate::utils::CryptoUtils;
-
-    // Derive sender address from private key
-    let secret_key = CryptoUtils::decode_private_key(&args.private_key)?;
-    let public_key = CryptoUtils::derive_public_key(&secret_key);
-    let public_key_hex = CryptoUtils::serialize_public_key(&public_key, false);
-    let sender_address = CryptoUtils::generate_vault_address(&public_key_hex)?;
-
-    get_balance_for_address(&sender_address, args).await
-}
-
 fn print_progress_stats(results: &[TestResult]) {
     let total = results.len();
     let finalized = results.iter().filter(|r| r.on_main_chain).count();
@@ -388,23 +554,14 @@ fn print_final_summary(results: &[TestResult]) {
     print_bar_chart(failed as f32 / total as f32);
 
     // Timing stats
-    if !results.is_empty() {
-        let avg_inclusion = results
-            .iter()
-            .map(|r| r.inclusion_time.as_secs_f32())
-            .sum::<f32>()
-            / total as f32;
-
-        let avg_total = results
-            .iter()
-            .map(|r| r.total_time.as_secs_f32())
-            .sum::<f32>()
-            / total as f32;
-
+    let inclusion =
+        Summary::from_samples(results.iter().map(|r| r.inclusion_time.as_millis() as u64));
+    let total_time = Summary::from_samples(results.iter().map(|r| r.total_time.as_millis() as u64));
+    if let (Some(inclusion), Some(total_time)) = (inclusion, total_time) {
         println!();
         println!(" Timing Statistics:");
-        println!(" Average inclusion time: {:.1}s", avg_inclusion);
-        println!(" Average total time: {:.1}s", avg_total);
+        print_timing("Inclusion time", &inclusion);
+        print_timing("Total time", &total_time);
     }
 
     println!();
@@ -417,6 +574,18 @@ fn print_final_summary(results: &[TestResult]) {
     }
 }
 
+fn print_timing(label: &str, summary: &Summary) {
+    let secs = |ms: u64| ms as f64 / 1000.0;
+    println!(
+        " {}: p50 {:.1}s, p95 {:.1}s, max {:.1}s (avg {:.1}s)",
+        label,
+        secs(summary.p50),
+        secs(summary.p95),
+        secs(summary.max),
+        summary.mean / 1000.0
+    );
+}
+
 fn print_bar_chart(percentage: f32) {
     let bar_length = 40;
     let filled = (percentage * bar_length as f32) as usize;
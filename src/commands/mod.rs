@@ -1,14 +1,52 @@
+pub mod analyze;
+pub mod batch_transfer;
+pub mod chain_stats;
+pub mod compare_state;
+pub mod config;
 pub mod crypto;
 pub mod dag;
+pub mod dashboard;
+pub mod equivocation;
 pub mod events;
+pub mod export;
+pub mod follow_block;
+pub mod funder_audit;
+pub mod history;
+pub mod latency_probe;
 pub mod load_test;
 pub mod network;
+pub mod outcome;
 pub mod query;
+pub mod registry;
+pub mod stats;
+pub mod test_wallets;
+pub mod validator_chain;
+pub mod watch_wallet;
+pub mod webhook;
 
 // Re-export all command functions for convenience
+pub use analyze::*;
+pub use batch_transfer::*;
+pub use chain_stats::*;
+pub use compare_state::*;
+pub use config::*;
 pub use crypto::*;
 pub use dag::*;
+pub use dashboard::*;
+pub use equivocation::*;
 pub use events::*;
+pub use export::*;
+pub use follow_block::*;
+pub use funder_audit::*;
+pub use history::*;
+pub use latency_probe::*;
 pub use load_test::*;
 pub use network::*;
+pub use outcome::*;
 pub use query::*;
+pub use registry::*;
+pub use stats::*;
+pub use test_wallets::*;
+pub use validator_chain::*;
+pub use watch_wallet::*;
+pub use webhook::*;
@@ -1,90 +1,335 @@
 use crate::args::*;
-use crate::connection_manager::{ConnectionConfig, F1r3flyConnectionManager};
-use crate::f1r3fly_api::{F1r3flyApi, ProposeResult};
-use std::fs;
-use std::time::{Instant, SystemTime, UNIX_EPOCH};
-
-use crate::args::DEV_PRIVATE_KEY;
-
-fn build_config(
-    host: &str,
-    port: u16,
-    http_port: u16,
+use crate::commands::outcome::{CommandOutcome, DeployReceipt, RevTransferResult};
+use crate::confirmations::{self, progress_message, ConfirmationWait};
+use crate::connection_manager::{ConnectionConfig, ConnectionError, F1r3flyConnectionManager};
+use crate::contract_templates::{
+    add_stake_contract, bond_contract, transfer_contract, transfer_contract_from_file,
+    withdraw_contract,
+};
+use crate::error::{DeployRejection, NodeCliError, ProposeRejection};
+use crate::export::ChainSource;
+use crate::f1r3fly_api::{F1r3flyApi, IncludedDeploy, ProposeResult};
+use crate::grpc::{BIGGER_PHLO_LIMIT, DEFAULT_PHLO_LIMIT};
+use crate::history::{self, HistoryRecord};
+use crate::notify::{notify_completion, Completion};
+use crate::payload::{read_payload, Payload, DEFAULT_LANGUAGE};
+use crate::prefix::{self, IdKind};
+use crate::preflight::{self, HttpPortCheck};
+use crate::staking::{PosCheck, PosVerifyMode, StakingQueries, UnbondCheck};
+use crate::term_analysis::{self, TermStats};
+use crate::timeline::{DeployTimeline, Finalization};
+use crate::topology::Topology;
+use crate::transfer_guard::{self, Decision, Guardrails, RECENT_TRANSFERS};
+use crate::truncation::{shorten, Field};
+use crate::utils::{
+    confirm_on_stdin, format_duration, print_connection, print_file_info, print_info,
+    print_progress, print_warning, reserve_stdout, Report, MIN_POLL_INTERVAL,
+};
+use crate::validator_keys::{self, LocalKey};
+use crate::verification::{
+    collect_reports, evaluate, NodeReport, Verification, VerificationNode, VerifyTarget,
+    VERIFY_ATTEMPTS, VERIFY_RETRY_DELAY,
+};
+use crate::webhook::{Webhook, WebhookPayload};
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Connection settings shared by the commands that deploy and wait
+pub(crate) fn build_config(
+    node: &NodeConnArgs,
+    observer: &ObserverConnArgs,
     private_key: &str,
-    max_wait: u64,
-    finalization_timeout: u64,
-    check_interval: u64,
-    observer_host: Option<&str>,
-    observer_port: Option<u16>,
+    max_wait: Duration,
+    finalization_timeout: Duration,
+    check_interval: Duration,
+    poll: &PollArgs,
 ) -> ConnectionConfig {
-    let mut config =
-        ConnectionConfig::new(host.to_string(), port, http_port, private_key.to_string());
-    config.deploy_timeout_secs = max_wait as u32;
-    config.finalization_timeout_secs = finalization_timeout as u32;
-    config.poll_interval_secs = check_interval;
-    if let Some(obs_host) = observer_host {
-        config.observer_host = Some(obs_host.to_string());
-    }
-    if let Some(obs_port) = observer_port {
+    let mut config = ConnectionConfig::new(
+        node.host.clone(),
+        node.grpc_port,
+        node.http_port,
+        private_key.to_string(),
+    );
+    config.deploy_timeout = max_wait;
+    config.finalization_timeout = finalization_timeout;
+    config.poll_interval = poll
+        .poll_initial
+        .unwrap_or(check_interval)
+        .max(MIN_POLL_INTERVAL);
+    config.poll_max = poll.poll_max;
+    if let Some(obs_host) = &observer.observer_host {
+        config.observer_host = Some(obs_host.clone());
+    }
+    if let Some(obs_port) = observer.observer_port {
         config.observer_grpc_port = obs_port;
     }
     config
 }
 
-fn config_from_deploy_args(args: &DeployAndWaitArgs) -> ConnectionConfig {
-    let private_key = args.private_key.as_deref().unwrap_or(DEV_PRIVATE_KEY);
-    build_config(
-        &args.host,
-        args.port,
-        args.http_port,
-        private_key,
+fn config_from_deploy_args(args: &DeployAndWaitArgs, node: &NodeConnArgs) -> ConnectionConfig {
+    let mut config = build_config(
+        node,
+        &args.observer,
+        &args.signing.private_key,
         args.max_wait,
         args.finalization_timeout,
         args.check_interval,
-        args.observer_host.as_deref(),
-        args.observer_port,
-    )
+        &args.poll,
+    );
+    config.deploy_lifespan = args.deploy_lifespan;
+    config
+}
+
+fn config_from_transfer_args(args: &TransferArgs, node: &NodeConnArgs) -> ConnectionConfig {
+    let mut config = build_config(
+        node,
+        &args.observer,
+        &args.signing.private_key,
+        args.wait.max_wait,
+        args.wait.max_wait, // Use max_wait for finalization too (no separate arg)
+        args.wait.check_interval,
+        &args.wait.poll,
+    );
+    config.deploy_lifespan = args.deploy_lifespan;
+    config
 }
 
-fn config_from_transfer_args(args: &TransferArgs) -> ConnectionConfig {
+fn config_from_bond_args(args: &BondValidatorArgs, node: &NodeConnArgs) -> ConnectionConfig {
     build_config(
-        &args.host,
-        args.port,
-        args.http_port,
+        node,
+        &args.observer,
         &args.private_key,
-        args.max_wait,
-        args.max_wait, // Use max_wait for finalization too (no separate arg)
-        args.check_interval,
-        args.observer_host.as_deref(),
-        args.observer_port,
+        args.wait.max_wait,
+        args.finalization_timeout.unwrap_or(args.wait.max_wait),
+        args.wait.check_interval,
+        &args.wait.poll,
     )
 }
 
-fn config_from_bond_args(args: &BondValidatorArgs) -> ConnectionConfig {
-    build_config(
-        &args.host,
-        args.port,
-        args.http_port,
-        &args.private_key,
-        args.max_wait,
-        args.max_wait, // Use max_wait for finalization too (no separate arg)
-        args.check_interval,
-        args.observer_host.as_deref(),
-        args.observer_port,
+/// Checks the node's gRPC and HTTP endpoints before a compound command deploys,
+/// returning the connection to use (with a detected HTTP port if the configured one is wrong)
+pub(crate) async fn preflight_node(
+    node: &NodeConnArgs,
+    skip: bool,
+) -> crate::error::Result<NodeConnArgs> {
+    let mut checked = node.clone();
+    if skip {
+        return Ok(checked);
+    }
+    match preflight::check_node(&node.host, node.grpc_port, node.http_port).await? {
+        HttpPortCheck::Configured(_) => {}
+        HttpPortCheck::Detected {
+            configured,
+            detected,
+        } => {
+            print_progress(&format!(
+                "Notice: no HTTP status endpoint on port {}, using {} (next to gRPC port {}). Pass --http-port {} to skip detection.",
+                configured, detected, node.grpc_port, detected
+            ));
+            checked.http_port = detected;
+        }
+    }
+    Ok(checked)
+}
+
+/// Cross-checks a finalized deploy on the `--verify-on` nodes against the
+/// post-state hash reported by the node it was deployed to
+///
+/// The outcome is reported by [`report_verification`] with the deploy's result.
+async fn verify_finalization(
+    verify: &VerifyArgs,
+    node: &NodeConnArgs,
+    result: &crate::f1r3fly_api::DeployResult,
+) -> Verification {
+    if verify.verify_on.is_empty() {
+        return Verification::NotRequested;
+    }
+    let primary = VerificationNode::new(VerifyTarget {
+        host: node.host.clone(),
+        http_port: node.http_port,
+    });
+    let expected = match primary.report(&result.deploy_id, &result.block_hash).await {
+        Ok(NodeReport::Block {
+            post_state_hash, ..
+        }) => Some(post_state_hash),
+        _ => None,
+    };
+    let reports = collect_reports(
+        &verify.verify_on,
+        &result.deploy_id,
+        &result.block_hash,
+        VERIFY_ATTEMPTS,
+        VERIFY_RETRY_DELAY,
     )
+    .await;
+    evaluate(expected.as_deref(), &reports)
+}
+
+/// Adds the `--verify-on` outcome to a result, when verification was requested
+fn report_verification(report: &mut Report, verification: &Verification) {
+    if !matches!(verification, Verification::NotRequested) {
+        report.line(format!("Verification: {}", verification.describe()));
+    }
+}
+
+/// Completes the history record, downgrading it when verification nodes disagreed
+fn verified_record(
+    record: HistoryRecord,
+    result: &crate::f1r3fly_api::DeployResult,
+    verification: &Verification,
+) -> HistoryRecord {
+    let record = record.complete(result);
+    match verification {
+        Verification::Mismatch { .. } => record.verification_mismatch(verification.describe()),
+        _ => record,
+    }
+}
+
+/// Appends the record to the local deploy history when enabled.
+/// Failures are logged and never fail the command.
+fn save_history(flags: &HistoryFlags, record: HistoryRecord) {
+    if flags.enabled() {
+        crate::history::record(&record);
+    }
+}
+
+/// Announces the end of a wait: the bell and notifier with `--notify`, and the
+/// receipt to the webhook when one is set
+struct WaitEnd<'a> {
+    command: &'a str,
+    notify: bool,
+    webhook: Option<Webhook>,
+    start: Instant,
+}
+
+impl<'a> WaitEnd<'a> {
+    fn new(command: &'a str, notify: bool, webhook: &WebhookArgs, start: Instant) -> Self {
+        Self {
+            command,
+            notify,
+            webhook: webhook.resolve(),
+            start,
+        }
+    }
+
+    /// The wait failed; `receipt` is known once the deploy is in a block
+    async fn failed(&self, deploy_id: Option<&str>, receipt: Option<&DeployReceipt>, error: &str) {
+        self.announce(deploy_id, false, receipt, Some(error.to_string()))
+            .await;
+    }
+
+    /// The deploy is in a block and finalization was not awaited
+    async fn included(&self, included: &IncludedDeploy) {
+        self.announce(Some(&included.deploy_id), true, None, None)
+            .await;
+    }
+
+    /// The deploy is finalized, and succeeded unless it errored
+    async fn finished(&self, receipt: &DeployReceipt) {
+        let error = receipt.errored.then(|| {
+            receipt
+                .system_deploy_error
+                .clone()
+                .unwrap_or_else(|| "deploy errored".to_string())
+        });
+        self.announce(
+            Some(&receipt.deploy_id),
+            !receipt.errored,
+            Some(receipt),
+            error,
+        )
+        .await;
+    }
+
+    async fn announce(
+        &self,
+        deploy_id: Option<&str>,
+        succeeded: bool,
+        receipt: Option<&DeployReceipt>,
+        error: Option<String>,
+    ) {
+        let completion = Completion {
+            command: self.command,
+            deploy_id,
+            succeeded,
+            elapsed: self.start.elapsed(),
+        };
+        notify_completion(self.notify, &completion).await;
+
+        // A failed delivery is reported, never fatal
+        let Some(webhook) = &self.webhook else {
+            return;
+        };
+        let delivery = webhook
+            .send(&WebhookPayload::new(&completion, receipt, error))
+            .await;
+        if delivery.delivered() {
+            tracing::debug!(
+                "Webhook delivered to {} after {} attempt(s)",
+                webhook.url,
+                delivery.attempts
+            );
+        } else {
+            print_warning(&format!(
+                "Webhook delivery to {} failed after {} attempt(s): {}",
+                webhook.url, delivery.attempts, delivery.last
+            ));
+        }
+    }
+}
+
+/// Deploy ID still known after a failed wait
+pub(crate) fn failed_deploy_id(error: &ConnectionError) -> Option<&str> {
+    match error {
+        ConnectionError::DeployExpired { deploy_id, .. }
+        | ConnectionError::TimedOut { deploy_id, .. } => Some(deploy_id.as_str()),
+        _ => None,
+    }
+}
+
+/// Prints the payload size, in decoded bytes for binary payloads
+fn print_payload_size(payload: &Payload) {
+    if payload.binary {
+        print_progress(&format!(
+            "Payload size: {} bytes ({} bytes base64-encoded)",
+            payload.size,
+            payload.term.len()
+        ));
+    } else {
+        print_progress(&format!("Payload size: {} bytes", payload.size));
+    }
+}
+
+/// Check a payload's size and complexity before it is sent
+fn check_term(
+    payload: &Payload,
+    limits: &TermLimitArgs,
+    verbose: bool,
+    force: bool,
+) -> Result<(), NodeCliError> {
+    let stats = if payload.binary {
+        // Only the size applies to binary payloads
+        TermStats {
+            bytes: payload.term.len(),
+            ..TermStats::default()
+        }
+    } else {
+        term_analysis::analyze(&payload.term)
+    };
+    term_analysis::check_before_deploy(&stats, &limits.resolve(), verbose, force)
 }
 
 /// Calculates the expiration timestamp from CLI arguments.
 /// Returns 0 if no expiration is specified.
-fn calculate_expiration_timestamp(expiration: Option<i64>, expires_in: Option<u64>) -> i64 {
+fn calculate_expiration_timestamp(expiration: Option<i64>, expires_in: Option<Duration>) -> i64 {
     if let Some(exp_ts) = expiration {
         exp_ts
-    } else if let Some(duration_secs) = expires_in {
+    } else if let Some(duration) = expires_in {
         let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .expect("Failed to get system time")
             .as_millis() as i64;
-        now + (duration_secs as i64 * 1000)
+        now + duration.as_millis() as i64
     } else {
         0 // No expiration
     }
@@ -93,56 +338,53 @@ fn calculate_expiration_timestamp(expiration: Option<i64>, expires_in: Option<u6
 pub async fn exploratory_deploy_command(
     args: &ExploratoryDeployArgs,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    // Read the Rholang code from file
-    println!(" Reading Rholang from: {}", args.file.display());
-    let rholang_code =
-        fs::read_to_string(&args.file).map_err(|e| format!("Failed to read file: {}", e))?;
-    println!(" Code size: {} bytes", rholang_code.len());
+    // Read the Rholang code from the file, stdin or --code
+    let source = args.source.source();
+    let rholang_code = source.read(DEFAULT_LANGUAGE)?.term;
+    print_file_info(&source.to_string(), rholang_code.len());
 
     // Initialize the F1r3fly API client
-    println!(" Connecting to F1r3fly node at {}:{}", args.host, args.port);
-    let f1r3fly_api = F1r3flyApi::new(&args.private_key, &args.host, args.port)?;
+    print_connection(&args.node.host, args.node.grpc_port);
+    let f1r3fly_api = F1r3flyApi::new(
+        &args.signing.private_key,
+        &args.node.host,
+        args.node.grpc_port,
+    )?;
 
     // Execute the exploratory deployment
-    println!(" Executing Rholang code (exploratory deploy)...");
+    print_info("Executing Rholang code (exploratory deploy)...");
 
     // Display block hash if provided
     if let Some(block_hash) = &args.block_hash {
-        println!(" Using block hash: {}", block_hash);
+        print_info(&format!("Using block hash: {}", block_hash));
     }
 
     // Display state hash preference
     if args.use_pre_state {
-        println!(" Using pre-state hash");
+        print_info("Using pre-state hash");
     } else {
-        println!(" Using post-state hash");
+        print_info("Using post-state hash");
     }
 
     let start_time = Instant::now();
 
-    match f1r3fly_api
+    let (result, block_info, cost) = f1r3fly_api
         .exploratory_deploy(
             &rholang_code,
             args.block_hash.as_deref(),
             args.use_pre_state,
         )
-        .await
-    {
-        Ok((result, block_info, cost)) => {
-            let duration = start_time.elapsed();
-            println!("Execution successful!");
-            println!("Cost:    {} phlogiston", cost);
-            println!("Time:    {:.2?}", duration);
-            println!("{}", block_info);
-            println!("Result:");
-            println!("{}", result);
-        }
-        Err(e) => {
-            println!("Execution failed!");
-            println!("Error: {}", e);
-            return Err(e);
-        }
-    }
+        .await?;
+
+    let mut report = Report::new();
+    report
+        .note("Execution successful!")
+        .line(format!("Cost:    {} phlogiston", cost))
+        .note(format!("Time:    {:.2?}", start_time.elapsed()))
+        .line(block_info)
+        .line("Result:")
+        .line(result);
+    report.print();
 
     Ok(())
 }
@@ -150,10 +392,13 @@ pub async fn exploratory_deploy_command(
 pub async fn estimate_cost_command(
     args: &ExploratoryDeployArgs,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let rholang_code =
-        fs::read_to_string(&args.file).map_err(|e| format!("Failed to read file: {}", e))?;
+    let rholang_code = args.source.source().read(DEFAULT_LANGUAGE)?.term;
 
-    let f1r3fly_api = F1r3flyApi::new(&args.private_key, &args.host, args.port)?;
+    let f1r3fly_api = F1r3flyApi::new(
+        &args.signing.private_key,
+        &args.node.host,
+        args.node.grpc_port,
+    )?;
 
     let (_result, _block_info, cost) = f1r3fly_api
         .exploratory_deploy(
@@ -163,461 +408,1809 @@ pub async fn estimate_cost_command(
         )
         .await?;
 
-    println!("{}", cost);
+    Report::new().line(cost.to_string()).print();
 
     Ok(())
 }
 
-pub async fn deploy_command(args: &DeployArgs) -> Result<(), Box<dyn std::error::Error>> {
-    // Read the Rholang code from file
-    println!("Reading Rholang from: {}", args.file.display());
-    let rholang_code =
-        fs::read_to_string(&args.file).map_err(|e| format!("Failed to read file: {}", e))?;
-    println!("Code size: {} bytes", rholang_code.len());
-
-    // Initialize the F1r3fly API client
-    println!("Connecting to F1r3fly node at {}:{}", args.host, args.port);
-    let f1r3fly_api = F1r3flyApi::new(&args.private_key, &args.host, args.port)?;
-
+/// Progress lines shown before a deploy is sent
+fn announce_deploy(args: &DeployArgs, payload: &Payload, expiration_timestamp: i64) {
+    print_payload_size(payload);
+    print_progress(&format!(
+        "Connecting to F1r3fly node at {}:{}",
+        args.node.host, args.node.grpc_port
+    ));
     let phlo_limit = if args.bigger_phlo {
         "5,000,000,000"
     } else {
         "50,000"
     };
-    println!("Using phlo limit: {}", phlo_limit);
-
-    // Calculate expiration timestamp
-    let expiration_timestamp = calculate_expiration_timestamp(args.expiration, args.expires_in);
+    print_progress(&format!("Using phlo limit: {}", phlo_limit));
     if expiration_timestamp > 0 {
-        println!("Deploy expiration: {} ms", expiration_timestamp);
+        print_progress(&format!("Deploy expiration: {} ms", expiration_timestamp));
     }
+}
 
-    // Deploy the Rholang code
-    println!("Deploying Rholang code...");
+/// Result of a deploy submitted without waiting for a block
+fn deploy_report(deploy_id: &str, duration: Duration) -> Report {
+    let mut report = Report::new();
+    report
+        .note("Deployment successful!")
+        .note(format!("Time taken: {:.2?}", duration))
+        .line(format!("Deploy ID: {}", deploy_id));
+    report
+}
+
+pub async fn deploy_command(
+    args: &DeployArgs,
+) -> Result<CommandOutcome, Box<dyn std::error::Error>> {
+    // Read the payload from the file, stdin or --code
+    let language = args.language.resolve()?;
+    let source = args.source.source();
+    print_progress(&format!("Reading {} payload from: {}", language, source));
+    let payload = source.read(&language)?;
+    let expiration_timestamp = calculate_expiration_timestamp(args.expiration, args.expires_in);
+    announce_deploy(args, &payload, expiration_timestamp);
+    check_term(&payload, &args.term_limits, args.verbose, args.force)?;
+
+    let f1r3fly_api = F1r3flyApi::new(
+        &args.signing.private_key,
+        &args.node.host,
+        args.node.grpc_port,
+    )?;
+
+    let mut history_record = HistoryRecord::new(
+        "deploy",
+        &args.node.host,
+        args.node.grpc_port,
+        &payload.term,
+    );
+    // Only a file can be re-read by bump-deploy
+    history_record.source = source.path().map(|path| path.display().to_string());
+
+    // Deploy the payload
+    print_progress(&format!("Deploying {} payload...", language));
     let start_time = Instant::now();
 
     match f1r3fly_api
         .deploy(
-            &rholang_code,
+            &payload.term,
             args.bigger_phlo,
-            "rholang",
+            &language,
             expiration_timestamp,
         )
         .await
     {
         Ok(deploy_id) => {
-            let duration = start_time.elapsed();
-            println!("Deployment successful!");
-            println!("Time taken: {:.2?}", duration);
-            println!("Deploy ID: {}", deploy_id);
+            deploy_report(&deploy_id, start_time.elapsed()).print();
+
+            history_record.deploy_id = Some(deploy_id.clone());
+            save_history(&args.history, history_record);
+            Ok(CommandOutcome::Deploy { deploy_id })
         }
         Err(e) => {
-            println!("Deployment failed!");
-            println!("Error: {}", e);
-            return Err(e);
+            print_deploy_rejection(
+                e.downcast_ref::<NodeCliError>()
+                    .and_then(NodeCliError::deploy_rejection),
+                args.verbose,
+            );
+            save_history(&args.history, history_record.fail(&e));
+            Err(e)
         }
     }
-
-    Ok(())
 }
 
 pub async fn propose_command(args: &ProposeArgs) -> Result<(), Box<dyn std::error::Error>> {
     // Initialize the F1r3fly API client
-    println!(" Connecting to F1r3fly node at {}:{}", args.host, args.port);
-    let f1r3fly_api = F1r3flyApi::new(&args.private_key, &args.host, args.port)?;
+    print_connection(&args.node.host, args.node.grpc_port);
+    let f1r3fly_api = F1r3flyApi::new(
+        &args.signing.private_key,
+        &args.node.host,
+        args.node.grpc_port,
+    )?;
 
     // Propose a block
-    println!(" Proposing a new block...");
+    print_info("Proposing a new block...");
     let start_time = Instant::now();
 
-    match f1r3fly_api.propose().await {
-        Ok(ProposeResult::Proposed(block_hash)) => {
-            let duration = start_time.elapsed();
-            println!(" Block proposed successfully!");
-            println!(" Block hash: {}", block_hash);
-            println!(" Time taken: {:.2?}", duration);
+    let proposed = match f1r3fly_api.propose().await {
+        Ok(proposed) => proposed,
+        Err(e) => {
+            let e = NodeCliError::from(e);
+            if let Some((ProposeRejection::NotActiveValidator, raw)) = e.propose_rejection() {
+                explain_inactive_validator(args, raw).await;
+            }
+            return Err(e.into());
         }
-        Ok(ProposeResult::Skipped(reason)) => {
-            let duration = start_time.elapsed();
-            println!(" Proposal was skipped: {}", reason);
-            println!(" Time taken: {:.2?}", duration);
+    };
+
+    let mut report = Report::new();
+    match proposed {
+        ProposeResult::Proposed { block_hash, .. } => {
+            report
+                .note(" Block proposed successfully!")
+                .line(format!(" Block hash: {}", block_hash));
         }
-        Err(e) => {
-            println!(" Block proposal failed!");
-            println!("Error: {}", e);
-            return Err(e);
+        ProposeResult::Skipped(reason) => {
+            report.line(format!(" Proposal was skipped: {}", reason));
         }
     }
+    report.time("Time taken", start_time.elapsed()).print();
 
     Ok(())
 }
 
-pub async fn full_deploy_command(args: &DeployArgs) -> Result<(), Box<dyn std::error::Error>> {
-    // Read the Rholang code from file
-    println!("Reading Rholang from: {}", args.file.display());
-    let rholang_code =
-        fs::read_to_string(&args.file).map_err(|e| format!("Failed to read file: {}", e))?;
-    println!("Code size: {} bytes", rholang_code.len());
-
-    // Initialize the F1r3fly API client
-    println!("Connecting to F1r3fly node at {}:{}", args.host, args.port);
-    let f1r3fly_api = F1r3flyApi::new(&args.private_key, &args.host, args.port)?;
+/// Name the key the node refused to propose with and, with `--suggest`, any
+/// local key that is in the active validator set
+///
+/// Only public keys and keystore entry names are printed.
+async fn explain_inactive_validator(args: &ProposeArgs, raw: &str) {
+    let host = &args.node.host;
+    match validator_keys::public_key_of(&args.signing.private_key) {
+        Ok(public_key) => print_warning(&validator_keys::not_active_message(&public_key, host)),
+        Err(_) => print_warning(&format!(
+            "The node's validator is not in the active validator set on {}",
+            host
+        )),
+    }
+    print_info(&format!("Node message: {}", raw));
+    if !args.suggest {
+        print_info("Pass --suggest to look for a local key that is an active validator");
+        return;
+    }
 
-    let phlo_limit = if args.bigger_phlo {
-        "5,000,000,000"
-    } else {
-        "50,000"
+    let active = match StakingQueries::new(host, args.node.http_port)
+        .active_validators(None)
+        .await
+    {
+        Ok(active) => active,
+        Err(e) => {
+            print_warning(&format!("Could not read the active validator set: {}", e));
+            return;
+        }
     };
-    println!("Using phlo limit: {}", phlo_limit);
 
-    // Calculate expiration timestamp
-    let expiration_timestamp = calculate_expiration_timestamp(args.expiration, args.expires_in);
-    if expiration_timestamp > 0 {
-        println!("Deploy expiration: {} ms", expiration_timestamp);
+    let keystore = validator_keys::keystore_dir(args.keystore.as_deref());
+    let mut local: Vec<LocalKey> = keystore
+        .as_deref()
+        .map(validator_keys::read_keystore)
+        .unwrap_or_default();
+    if local.is_empty() {
+        match &keystore {
+            Some(dir) => print_info(&format!("No private key files in {}", dir.display())),
+            None => print_info("No keystore directory (set --keystore or --config-dir)"),
+        }
+    }
+    local.extend(validator_keys::dev_keys());
+
+    let matches = validator_keys::active_matches(&active, &local);
+    if matches.is_empty() {
+        print_info(&format!(
+            "None of the {} local keys is among the {} active validators on {}",
+            local.len(),
+            active.len(),
+            host
+        ));
+        return;
     }
+    let mut report = Report::new();
+    report.line(format!(
+        "Local keys that are active validators on {}:",
+        host
+    ));
+    for key in matches {
+        report.line(format!("  {}  {}", key.name, key.public_key));
+    }
+    report.print();
+}
+
+pub async fn full_deploy_command(args: &DeployArgs) -> Result<(), Box<dyn std::error::Error>> {
+    // Read the payload from the file, stdin or --code
+    let language = args.language.resolve()?;
+    let source = args.source.source();
+    print_progress(&format!("Reading {} payload from: {}", language, source));
+    let payload = source.read(&language)?;
+    let expiration_timestamp = calculate_expiration_timestamp(args.expiration, args.expires_in);
+    announce_deploy(args, &payload, expiration_timestamp);
+    check_term(&payload, &args.term_limits, args.verbose, args.force)?;
+
+    let f1r3fly_api = F1r3flyApi::new(
+        &args.signing.private_key,
+        &args.node.host,
+        args.node.grpc_port,
+    )?;
 
     // Deploy and propose
-    println!("Deploying Rholang code and proposing a block...");
+    print_progress(&format!(
+        "Deploying {} payload and proposing a block...",
+        language
+    ));
     let start_time = Instant::now();
 
-    match f1r3fly_api
+    let proposed = match f1r3fly_api
         .full_deploy(
-            &rholang_code,
+            &payload.term,
             args.bigger_phlo,
-            "rholang",
+            &language,
             expiration_timestamp,
         )
         .await
     {
-        Ok(ProposeResult::Proposed(block_hash)) => {
-            let duration = start_time.elapsed();
-            println!("Deployment and block proposal successful!");
-            println!("Time taken: {:.2?}", duration);
-            println!("Block hash: {}", block_hash);
-        }
-        Ok(ProposeResult::Skipped(reason)) => {
-            let duration = start_time.elapsed();
-            println!("Deployment successful, but proposal was skipped.");
-            println!("Time taken: {:.2?}", duration);
-            println!("Skip reason: {}", reason);
-        }
+        Ok(proposed) => proposed,
         Err(e) => {
-            println!("Operation failed!");
-            println!("Error: {}", e);
+            print_deploy_rejection(
+                e.downcast_ref::<NodeCliError>()
+                    .and_then(NodeCliError::deploy_rejection),
+                args.verbose,
+            );
             return Err(e);
         }
-    }
+    };
+
+    let mut report = Report::new();
+    match proposed {
+        ProposeResult::Proposed { block_hash, .. } => report
+            .note("Deployment and block proposal successful!")
+            .note(format!("Time taken: {:.2?}", start_time.elapsed()))
+            .line(format!("Block hash: {}", block_hash)),
+        ProposeResult::Skipped(reason) => report
+            .line("Deployment successful, but proposal was skipped.")
+            .note(format!("Time taken: {:.2?}", start_time.elapsed()))
+            .line(format!("Skip reason: {}", reason)),
+    };
+    report.print();
 
     Ok(())
 }
 
 pub async fn is_finalized_command(
     args: &IsFinalizedArgs,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<CommandOutcome, Box<dyn std::error::Error>> {
     // Initialize the F1r3fly API client
-    println!(" Connecting to F1r3fly node at {}:{}", args.host, args.port);
-    let f1r3fly_api = F1r3flyApi::new(&args.private_key, &args.host, args.port)?;
+    print_connection(&args.node.host, args.node.grpc_port);
+    let f1r3fly_api = F1r3flyApi::new(
+        &args.signing.private_key,
+        &args.node.host,
+        args.node.grpc_port,
+    )?;
+
+    let block_hash = match expand_prefix(
+        &f1r3fly_api,
+        &args.node.conn(),
+        IdKind::BlockHash,
+        &args.block_hash,
+        args.search_depth,
+    )
+    .await
+    {
+        Ok(block_hash) => block_hash,
+        Err(e) => {
+            print_lookup_hints(
+                &f1r3fly_api,
+                args.node.http_port,
+                IdKind::BlockHash,
+                &args.block_hash,
+                args.no_hints,
+            )
+            .await;
+            return Err(e);
+        }
+    };
 
     // Check if the block is finalized
-    println!(" Checking if block is finalized: {}", args.block_hash);
-    println!(
-        " Will retry every {} seconds, up to {} times",
-        args.retry_delay, args.max_attempts
-    );
+    print_info(&format!("Checking if block is finalized: {}", block_hash));
+    print_info(&format!(
+        "Will retry every {}, up to {} times",
+        format_duration(args.retry_delay),
+        args.max_attempts
+    ));
     let start_time = Instant::now();
 
-    match f1r3fly_api
-        .is_finalized(&args.block_hash, args.max_attempts, args.retry_delay)
+    let is_finalized = match f1r3fly_api
+        .is_finalized(&block_hash, args.max_attempts, args.retry_delay)
         .await
     {
-        Ok(is_finalized) => {
-            let duration = start_time.elapsed();
-            if is_finalized {
-                println!(" Block is finalized!");
-            } else {
-                println!(
-                    " Block is not finalized after {} attempts",
-                    args.max_attempts
-                );
-            }
-            println!(" Time taken: {:.2?}", duration);
-        }
+        Ok(is_finalized) => is_finalized,
         Err(e) => {
-            println!(" Error checking block finalization!");
-            println!("Error: {}", e);
+            print_lookup_hints(
+                &f1r3fly_api,
+                args.node.http_port,
+                IdKind::BlockHash,
+                &block_hash,
+                args.no_hints,
+            )
+            .await;
             return Err(e);
         }
+    };
+
+    let confirmations = match args.confirm_depth {
+        Some(depth) if is_finalized => {
+            let wait = ConfirmationWait {
+                depth,
+                poll_interval: args.retry_delay.max(MIN_POLL_INTERVAL),
+                deadline: start_time + args.retry_delay * args.max_attempts,
+            };
+            Some(
+                wait_for_confirm_depth(&f1r3fly_api, args.node.http_port, &block_hash, None, wait)
+                    .await?,
+            )
+        }
+        _ => None,
+    };
+
+    let mut report = Report::new();
+    if is_finalized {
+        report.line(" Block is finalized!");
+    } else {
+        report.line(format!(
+            " Block is not finalized after {} attempts",
+            args.max_attempts
+        ));
+    }
+    if let Some(confirmations) = confirmations {
+        report.line(format!("Confirmations: {}", confirmations));
+    }
+    report.time("Time taken", start_time.elapsed()).print();
+    if !is_finalized {
+        print_lookup_hints(
+            &f1r3fly_api,
+            args.node.http_port,
+            IdKind::BlockHash,
+            &block_hash,
+            args.no_hints,
+        )
+        .await;
+    }
+
+    Ok(CommandOutcome::Finalized {
+        block_hash,
+        finalized: is_finalized,
+        confirmations,
+    })
+}
+
+/// Wait for `--confirm-depth` finalized blocks on top of `block_hash`,
+/// printing progress as they come in
+async fn wait_for_confirm_depth(
+    api: &F1r3flyApi<'_>,
+    http_port: u16,
+    block_hash: &str,
+    known_height: Option<i64>,
+    wait: ConfirmationWait,
+) -> crate::error::Result<i64> {
+    confirmations::wait_for_confirmations(api, http_port, block_hash, known_height, wait, |n| {
+        print_progress(&progress_message(n, wait.depth))
+    })
+    .await
+}
+
+/// `id` as given, or the full identifier it is a prefix of within the last
+/// `search_depth` heights
+async fn expand_prefix(
+    f1r3fly_api: &F1r3flyApi<'_>,
+    node: &NodeConnArgs,
+    kind: IdKind,
+    id: &str,
+    search_depth: i64,
+) -> Result<String, Box<dyn std::error::Error>> {
+    if !kind.is_prefix(id) {
+        return Ok(id.to_string());
+    }
+    let tip = f1r3fly_api
+        .get_tip_block_number(node.http_port)
+        .await?
+        .ok_or_else(|| NodeCliError::network_connection_failed("node reported no tip block"))?;
+    let source = ChainSource::new(&node.host, node.http_port);
+    let full = prefix::resolve(&source, kind, id, tip, search_depth).await?;
+    print_info(&format!("Resolved {} to {}", id, full));
+    Ok(full)
+}
+
+/// Print what a failed identifier looks like, probing the node once for the
+/// other kind, unless `--no-hints` was given
+async fn print_lookup_hints(
+    api: &F1r3flyApi<'_>,
+    http_port: u16,
+    kind: IdKind,
+    id: &str,
+    no_hints: bool,
+) {
+    if no_hints {
+        return;
+    }
+    let mut report = Report::new();
+    for line in prefix::failed_lookup_hints(api, http_port, kind, id).await {
+        report.line(line);
+    }
+    report.print();
+}
+
+/// Print a remediation hint for a deploy the node rejected, plus its raw message with -v
+///
+/// The error itself is left to the dispatcher, which prints it once.
+fn print_deploy_rejection(rejection: Option<(&DeployRejection, &str)>, verbose: bool) {
+    if let Some((reason, raw)) = rejection {
+        let mut report = Report::new();
+        if let Some(hint) = reason.hint() {
+            report.line(format!("Hint: {}", hint));
+        }
+        if verbose {
+            report.line(format!("Node message: {}", raw));
+        }
+        report.print();
+    }
+}
+
+/// Public key (uncompressed hex) and vault address for a deployer's private key
+fn deployer_identity(private_key: &str) -> crate::error::Result<(String, String)> {
+    use crate::utils::CryptoUtils;
+
+    let secret_key = CryptoUtils::decode_private_key(private_key)?;
+    let public_key = CryptoUtils::derive_public_key(&secret_key);
+    let public_key_hex = CryptoUtils::serialize_public_key(&public_key, false);
+    let address = CryptoUtils::generate_vault_address(&public_key_hex)?;
+    Ok((public_key_hex, address))
+}
+
+/// Phlo line for a confirmation summary, with the node's estimate when it can give one
+async fn phlo_summary(manager: &F1r3flyConnectionManager, code: &str, bigger_phlo: bool) -> String {
+    let limit = if bigger_phlo {
+        BIGGER_PHLO_LIMIT
+    } else {
+        DEFAULT_PHLO_LIMIT
+    };
+    match manager.estimate_cost(code).await {
+        Ok(cost) => format!("~{} estimated (limit {}, price 1)", cost, limit),
+        Err(_) => format!("limit {} at price 1 (estimate unavailable)", limit),
+    }
+}
+
+/// Adds the outcome of a propose that follows a deploy; failures are reported
+/// but do not fail the command
+fn report_proposal<E: std::fmt::Display>(report: &mut Report, proposed: Result<ProposeResult, E>) {
+    match proposed {
+        Ok(ProposeResult::Proposed { block_hash, .. }) => {
+            report.line(format!("Block proposed: {}", block_hash))
+        }
+        Ok(ProposeResult::Skipped(reason)) => report.line(format!("Propose skipped: {}", reason)),
+        Err(e) => report.line(format!("Propose failed: {}", e)),
+    };
+}
+
+/// Proposes a block after a staking deploy, reporting but not failing on errors
+async fn propose_after_deploy(report: &mut Report, private_key: &str, host: &str, port: u16) {
+    match F1r3flyApi::new(private_key, host, port) {
+        Ok(api) => report_proposal(report, api.propose().await),
+        Err(e) => report_proposal::<NodeCliError>(report, Err(e)),
     }
+}
+
+/// Deploy ID and block of a deploy that made it into a block
+fn report_included(report: &mut Report, result: &crate::f1r3fly_api::DeployResult) {
+    report
+        .line(format!("Deploy ID: {}", result.deploy_id))
+        .line(format!("Block hash: {}", result.block_hash));
+}
+
+/// A deploy left in a block with `--no-wait-finalization`, and how to follow it up
+fn included_report(included: &IncludedDeploy, elapsed: Duration) -> Report {
+    let mut report = Report::new();
+    report
+        .line(format!("Deploy ID: {}", included.deploy_id))
+        .line(format!("Block hash: {}", included.block_hash))
+        .note(format!("Total time: {:.2?}", elapsed))
+        .note(format!(
+            "Not waiting for finalization. Check with: node_cli is-finalized -b {}",
+            included.block_hash
+        ));
+    report
+}
 
+/// Compare the observer's `rho:system:pos` with the shard's expected contract
+/// before its bond and balance answers are relied on
+async fn verify_pos_contract(
+    queries: &StakingQueries,
+    args: &PosVerifyArgs,
+) -> crate::error::Result<()> {
+    if args.verify_pos == PosVerifyMode::Off {
+        return Ok(());
+    }
+    let expectations = Topology::pos_expectations(args.topology.as_deref())?;
+    let (shard, check) = queries.check_pos(&expectations).await?;
+    let message = check.describe(&shard);
+    match check {
+        PosCheck::Verified(_) => print_progress(&message),
+        PosCheck::Unconfigured(_) => print_info(&message),
+        PosCheck::Mismatch { .. } if args.verify_pos == PosVerifyMode::Warn => {
+            print_warning(&message)
+        }
+        PosCheck::Mismatch { .. } => {
+            return Err(NodeCliError::General(format!(
+                "{}. Refusing to continue; pass --verify-pos warn to proceed anyway.",
+                message
+            )))
+        }
+    }
     Ok(())
 }
 
 pub async fn bond_validator_command(
     args: &BondValidatorArgs,
+    assume_yes: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    println!("Bonding validator with stake: {}", args.stake);
-
-    let bonding_code = format!(
-        r#"new rl(`rho:registry:lookup`), poSCh, retCh, stdout(`rho:io:stdout`) in {{
- stdout!("About to lookup PoS contract...") |
- rl!(`rho:system:pos`, *poSCh) |
- for(@(_, PoS) <- poSCh) {{
- stdout!("About to bond...") |
- new deployerId(`rho:system:deployerId`) in {{
- @PoS!("bond", *deployerId, {}, *retCh) |
- for (@(result, message) <- retCh) {{
- stdout!(("Bond result:", result, "Message:", message))
- }}
- }}
- }}
-}}"#,
-        args.stake
-    );
-
+    print_progress(&format!("Bonding validator with stake: {}", args.stake));
+
+    let (public_key, address) = deployer_identity(&args.private_key)?;
+    let observer_host = args.observer.host(&args.node);
+    let queries = StakingQueries::new(observer_host, args.observer.observer_http_port);
+    verify_pos_contract(&queries, &args.pos).await?;
+    queries.require_not_bonded(&public_key).await?;
+    let balance = queries.require_balance(&address, args.stake).await?;
+    print_progress(&format!(
+        "Deployer balance: {} (covers stake + phlo)",
+        balance
+    ));
+
+    let bonding_code = bond_contract(args.stake)?;
+
+    let node = preflight_node(&args.node, args.skip_preflight).await?;
     let expiration = calculate_expiration_timestamp(args.expiration, args.expires_in);
-    let manager = F1r3flyConnectionManager::new(config_from_bond_args(args));
+    let manager = F1r3flyConnectionManager::new(config_from_bond_args(args, &node));
+    if !assume_yes {
+        let summary = [
+            ("Validator", public_key.clone()),
+            ("From", address.clone()),
+            ("Stake", args.stake.to_string()),
+            (
+                "Node",
+                format!("{}:{}", args.node.host, args.node.grpc_port),
+            ),
+            ("Phlo", phlo_summary(&manager, &bonding_code, true).await),
+        ];
+        confirm_on_stdin("bond validator", &summary, "yes", false)?;
+    }
     let start = Instant::now();
 
-    let result = manager
+    let mut history_record = HistoryRecord::new(
+        "bond-validator",
+        &args.node.host,
+        args.node.grpc_port,
+        &bonding_code,
+    );
+    history_record.http_port = Some(node.http_port);
+
+    if args.no_wait_finalization {
+        let included = match manager
+            .deploy_and_wait_for_block(&bonding_code, DEFAULT_LANGUAGE, true, expiration)
+            .await
+        {
+            Ok(included) => included,
+            Err(e) => {
+                print_deploy_rejection(e.deploy_rejection(), args.verbose);
+                save_history(&args.history, history_record.fail(&e));
+                return Err(NodeCliError::from(e).into());
+            }
+        };
+        save_history(&args.history, history_record.included(&included));
+        let mut report = included_report(&included, start.elapsed());
+        if args.propose {
+            propose_after_deploy(
+                &mut report,
+                &args.private_key,
+                &args.node.host,
+                args.node.grpc_port,
+            )
+            .await;
+        }
+        report
+            .note("Once the block is finalized, verify the bond with: node_cli bonds")
+            .print();
+        return Ok(());
+    }
+
+    let result = match manager
         .deploy_and_wait(&bonding_code, true, expiration)
         .await
-        .map_err(|e| -> Box<dyn std::error::Error> { e.to_string().into() })?;
+    {
+        Ok(result) => result,
+        Err(e) => {
+            print_deploy_rejection(e.deploy_rejection(), args.verbose);
+            save_history(&args.history, history_record.fail(&e));
+            return Err(NodeCliError::from(e).into());
+        }
+    };
+    save_history(&args.history, history_record.complete(&result));
 
-    println!("Deploy ID: {}", result.deploy_id);
-    println!("Block hash: {}", result.block_hash);
-    println!("Total time: {:.2?}", start.elapsed());
+    let mut report = Report::new();
+    report_included(&mut report, &result);
+    report.note(format!("Total time: {:.2?}", start.elapsed()));
 
     if args.propose {
-        let api = F1r3flyApi::new(&args.private_key, &args.host, args.port)?;
-        match api.propose().await {
-            Ok(ProposeResult::Proposed(hash)) => println!("Block proposed: {}", hash),
-            Ok(ProposeResult::Skipped(reason)) => println!("Propose skipped: {}", reason),
-            Err(e) => println!("Propose failed: {}", e),
+        propose_after_deploy(
+            &mut report,
+            &args.private_key,
+            &args.node.host,
+            args.node.grpc_port,
+        )
+        .await;
+    }
+
+    report
+        .note("Bonding complete. Verify with: node_cli bonds")
+        .print();
+    Ok(())
+}
+
+/// Add stake to the deployer's existing bond and verify the new stake once finalized
+pub async fn add_stake_command(args: &AddStakeArgs, assume_yes: bool) -> crate::error::Result<()> {
+    let (public_key, address) = deployer_identity(&args.private_key)?;
+    let observer_host = args.observer.host(&args.node);
+    let queries = StakingQueries::new(observer_host, args.observer.observer_http_port);
+    verify_pos_contract(&queries, &args.pos).await?;
+
+    // Refuse before deploying anything if there is no bond to add to
+    let old_stake = queries.require_bonded(&public_key).await?;
+    let balance = queries.require_balance(&address, args.amount).await?;
+    print_progress(&format!("Adding {} to validator stake", args.amount));
+    print_progress(&format!("Current stake: {}", old_stake));
+    print_progress(&format!(
+        "Deployer balance: {} (covers amount + phlo)",
+        balance
+    ));
+
+    let code = add_stake_contract(args.amount)?;
+    let node = preflight_node(&args.node, args.skip_preflight).await?;
+    let expiration = calculate_expiration_timestamp(args.expiration, args.expires_in);
+    let config = build_config(
+        &node,
+        &args.observer,
+        &args.private_key,
+        args.wait.max_wait,
+        args.wait.max_wait, // Use max_wait for finalization too (no separate arg)
+        args.wait.check_interval,
+        &args.wait.poll,
+    );
+    let manager = F1r3flyConnectionManager::new(config);
+    if !assume_yes {
+        let summary = [
+            ("Validator", public_key.clone()),
+            ("From", address.clone()),
+            (
+                "Amount",
+                format!(
+                    "{} (stake {} → {})",
+                    args.amount,
+                    old_stake,
+                    old_stake + args.amount as i64
+                ),
+            ),
+            (
+                "Node",
+                format!("{}:{}", args.node.host, args.node.grpc_port),
+            ),
+            ("Phlo", phlo_summary(&manager, &code, true).await),
+        ];
+        confirm_on_stdin("add stake", &summary, "yes", false)?;
+    }
+    let start = Instant::now();
+
+    let mut history_record =
+        HistoryRecord::new("add-stake", &args.node.host, args.node.grpc_port, &code);
+    history_record.http_port = Some(node.http_port);
+
+    let result = match manager.deploy_and_wait(&code, true, expiration).await {
+        Ok(result) => result,
+        Err(e) => {
+            print_deploy_rejection(e.deploy_rejection(), args.verbose);
+            save_history(&args.history, history_record.fail(&e));
+            return Err(NodeCliError::from(e));
         }
+    };
+    save_history(&args.history, history_record.complete(&result));
+
+    let mut report = Report::new();
+    report_included(&mut report, &result);
+    report.note(format!("Total time: {:.2?}", start.elapsed()));
+
+    if result.errored {
+        report.print();
+        return Err(NodeCliError::General(format!(
+            "Add-stake deploy failed: {}",
+            result
+                .system_deploy_error
+                .as_deref()
+                .unwrap_or("deploy errored")
+        )));
+    }
+
+    // Stake at the finalized block that included the deploy
+    let change = queries
+        .verify_stake_increase(&public_key, &result.block_hash, old_stake, args.amount)
+        .await?;
+    report.line(format!(
+        "Stake: {} → {} (+{})",
+        change.old,
+        change.new,
+        change.delta()
+    ));
+
+    if args.propose {
+        propose_after_deploy(
+            &mut report,
+            &args.private_key,
+            &args.node.host,
+            args.node.grpc_port,
+        )
+        .await;
     }
 
-    println!("Bonding complete. Verify with: node_cli bonds");
+    report.print();
     Ok(())
 }
 
-pub async fn transfer_command(args: &TransferArgs) -> Result<(), Box<dyn std::error::Error>> {
-    use crate::utils::CryptoUtils;
+/// Withdraw the deployer's validator bond and check the bonds map once finalized
+///
+/// PoS keeps a withdrawn bond through quarantine, so the key is usually still
+/// listed at the withdrawal block; the report says when it should leave.
+pub async fn unbond_validator_command(
+    args: &UnbondValidatorArgs,
+    assume_yes: bool,
+) -> crate::error::Result<()> {
+    let (public_key, _) = deployer_identity(&args.private_key)?;
+    let observer_host = args.observer.host(&args.node);
+    let queries = StakingQueries::new(observer_host, args.observer.observer_http_port);
+    verify_pos_contract(&queries, &args.pos).await?;
+
+    // Refuse before deploying anything if there is no bond to withdraw
+    let stake = queries.require_bonded(&public_key).await?;
+    print_progress(&format!("Unbonding validator with stake: {}", stake));
+    let delay = match queries.withdrawal_delay().await {
+        Ok(delay) => {
+            print_progress(&format!("Withdrawal delay: {}", delay.describe()));
+            Some(delay)
+        }
+        Err(e) => {
+            print_warning(&format!(
+                "Could not read the withdrawal delay from the PoS contract: {}",
+                e
+            ));
+            None
+        }
+    };
+
+    let code = withdraw_contract()?;
+    let node = preflight_node(&args.node, args.skip_preflight).await?;
+    let expiration = calculate_expiration_timestamp(args.expiration, args.expires_in);
+    let config = build_config(
+        &node,
+        &args.observer,
+        &args.private_key,
+        args.wait.max_wait,
+        args.finalization_timeout.unwrap_or(args.wait.max_wait),
+        args.wait.check_interval,
+        &args.wait.poll,
+    );
+    let manager = F1r3flyConnectionManager::new(config);
+    if !assume_yes {
+        let summary = [
+            ("Validator", public_key.clone()),
+            ("Stake", stake.to_string()),
+            (
+                "Node",
+                format!("{}:{}", args.node.host, args.node.grpc_port),
+            ),
+            ("Phlo", phlo_summary(&manager, &code, true).await),
+        ];
+        confirm_on_stdin("unbond validator", &summary, "yes", false)?;
+    }
+    let start = Instant::now();
+
+    let mut history_record = HistoryRecord::new(
+        "unbond-validator",
+        &args.node.host,
+        args.node.grpc_port,
+        &code,
+    );
+    history_record.http_port = Some(node.http_port);
 
-    // Derive sender address
-    let from_address = {
-        let secret_key = CryptoUtils::decode_private_key(&args.private_key)?;
-        let public_key = CryptoUtils::derive_public_key(&secret_key);
-        let public_key_hex = CryptoUtils::serialize_public_key(&public_key, false);
-        CryptoUtils::generate_vault_address(&public_key_hex)?
+    let result = match manager.deploy_and_wait(&code, true, expiration).await {
+        Ok(result) => result,
+        Err(e) => {
+            print_deploy_rejection(e.deploy_rejection(), args.verbose);
+            save_history(&args.history, history_record.fail(&e));
+            return Err(NodeCliError::from(e));
+        }
     };
+    save_history(&args.history, history_record.complete(&result));
+
+    let mut report = Report::new();
+    report_included(&mut report, &result);
+    report.note(format!("Total time: {:.2?}", start.elapsed()));
+
+    if result.errored {
+        report.print();
+        return Err(NodeCliError::General(format!(
+            "Unbond deploy failed: {}",
+            result
+                .system_deploy_error
+                .as_deref()
+                .unwrap_or("deploy errored")
+        )));
+    }
+
+    // Bonds at the finalized block that included the deploy
+    match queries
+        .check_unbonded(&public_key, &result.block_hash)
+        .await?
+    {
+        UnbondCheck::Removed => {
+            report.line("Bond: removed");
+        }
+        UnbondCheck::Scheduled { stake } => {
+            report.line(format!(
+                "Bond: {} still bonded, scheduled for removal after quarantine",
+                stake
+            ));
+            let expected = match delay {
+                Some(delay) => match result
+                    .block_number
+                    .and_then(|block| Some((block, delay.removal_block(block)?)))
+                {
+                    Some((block, at)) => format!(
+                        "block {} (withdrawn in block {}, {})",
+                        at,
+                        block,
+                        delay.describe()
+                    ),
+                    None => format!("after {}", delay.describe()),
+                },
+                None => "unknown".to_string(),
+            };
+            report.line(format!("Expected removal: {}", expected));
+        }
+    }
+
+    if args.propose {
+        propose_after_deploy(
+            &mut report,
+            &args.private_key,
+            &args.node.host,
+            args.node.grpc_port,
+        )
+        .await;
+    }
+
+    report
+        .note(format!(
+            "Check the bond with: node_cli bond-status --public-key {}",
+            public_key
+        ))
+        .print();
+    Ok(())
+}
+
+/// Sender's balance in dust, or `None` with a warning if it cannot be read
+pub(crate) async fn sender_balance(host: &str, http_port: u16, from_address: &str) -> Option<i64> {
+    match StakingQueries::new(host, http_port)
+        .vault_balance(from_address)
+        .await
+    {
+        Ok(balance) => Some(balance),
+        Err(e) => {
+            print_warning(&format!("Could not check the sender's balance: {}", e));
+            None
+        }
+    }
+}
+
+/// Balance and recent-transfer notes for the transfer prompt
+///
+/// `balance` is the sender's balance in dust, when it could be read.
+fn transfer_amount_notes(
+    args: &TransferArgs,
+    balance: Option<i64>,
+    guardrails: &Guardrails,
+) -> Vec<String> {
+    let balance = balance
+        .filter(|_| guardrails.balance_percent > 0)
+        .map(|balance| balance as u64);
+    let recent = if args.history.enabled() {
+        history::history_path()
+            .and_then(|path| history::read_records(&path).ok())
+            .map(|records| history::recent_transfer_amounts(&records, RECENT_TRANSFERS))
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+    transfer_guard::amount_notes(args.transfer_amount(), balance, &recent, guardrails)
+}
+
+pub async fn transfer_command(
+    args: &TransferArgs,
+    assume_yes: bool,
+) -> Result<CommandOutcome, Box<dyn std::error::Error>> {
+    if args.output.is_json() {
+        reserve_stdout(true);
+    }
+    // Parse the key once; the API is reused to propose after the transfer
+    let api = F1r3flyApi::new(
+        &args.signing.private_key,
+        &args.node.host,
+        args.node.grpc_port,
+    )?;
+    let from_address = api.vault_address()?;
 
     validate_vault_address(&from_address)?;
     validate_vault_address(&args.to_address)?;
 
-    let amount_dust = args.amount * 100_000_000;
-    println!(
+    let amount = args.transfer_amount();
+    let amount_dust = amount.dust();
+    print_progress(&format!(
         "Transfer: {} -> {} ({} dust)",
         from_address, args.to_address, amount_dust
-    );
+    ));
+
+    let node = preflight_node(&args.node, args.skip_preflight).await?;
+    let guardrails = Guardrails::resolve(args.max_transfer, args.balance_warn_percent);
+    // The prompt's notes are skipped with --yes; the balance is still checked
+    let skip_notes = assume_yes || args.no_amount_checks;
+    let balance = if args.force && skip_notes {
+        None
+    } else {
+        sender_balance(&args.node.host, node.http_port, &from_address).await
+    };
+    if let (Some(balance), false) = (balance, args.force) {
+        let fee = transfer_guard::max_phlo_fee(args.bigger_phlo);
+        transfer_guard::check_sender_balance(balance, amount_dust, fee)
+            .map_err(NodeCliError::General)?;
+    }
 
-    let rholang_code = generate_transfer_contract(&from_address, &args.to_address, amount_dust);
+    let rholang_code = match &args.template_file {
+        Some(path) => {
+            transfer_contract_from_file(path, &from_address, &args.to_address, amount_dust)?
+        }
+        None => transfer_contract(&from_address, &args.to_address, amount_dust)?,
+    };
     let expiration = calculate_expiration_timestamp(args.expiration, args.expires_in);
-
-    let manager = F1r3flyConnectionManager::new(config_from_transfer_args(args));
+    let manager = F1r3flyConnectionManager::new(config_from_transfer_args(args, &node));
+    let notes = if skip_notes {
+        Vec::new()
+    } else {
+        transfer_amount_notes(args, balance, &guardrails)
+    };
+    match transfer_guard::decide(
+        amount,
+        args.confirm_above,
+        &guardrails,
+        notes,
+        assume_yes,
+        args.allow_large_transfer,
+    ) {
+        Decision::Proceed => {}
+        Decision::Refuse(message) => return Err(NodeCliError::General(message).into()),
+        Decision::Confirm { phrase, notes } => {
+            let mut summary = vec![
+                ("From", from_address.clone()),
+                ("To", args.to_address.clone()),
+                (
+                    "Amount",
+                    format!("{} tokens ({} dust)", amount, amount_dust),
+                ),
+                (
+                    "Node",
+                    format!("{}:{}", args.node.host, args.node.grpc_port),
+                ),
+                (
+                    "Phlo",
+                    phlo_summary(&manager, &rholang_code, args.bigger_phlo).await,
+                ),
+            ];
+            summary.extend(notes.into_iter().map(|note| ("Warning", note)));
+            confirm_on_stdin("transfer", &summary, &phrase, false)?;
+        }
+    }
     let start = Instant::now();
+    let wait_end = WaitEnd::new("transfer", args.notify, &args.webhook, start);
 
-    let result = manager
+    let mut history_record = HistoryRecord::new(
+        "transfer",
+        &args.node.host,
+        args.node.grpc_port,
+        &rholang_code,
+    );
+    history_record.http_port = Some(node.http_port);
+    history_record.amount_dust = Some(amount_dust);
+
+    let result = match manager
         .deploy_and_wait(&rholang_code, args.bigger_phlo, expiration)
         .await
-        .map_err(|e| -> Box<dyn std::error::Error> { e.to_string().into() })?;
+    {
+        Ok(result) => result,
+        Err(e) => {
+            print_deploy_rejection(e.deploy_rejection(), args.verbose);
+            save_history(&args.history, history_record.fail(&e));
+            wait_end
+                .failed(failed_deploy_id(&e), None, &e.to_string())
+                .await;
+            return Err(NodeCliError::from(e).into());
+        }
+    };
+    let verification = verify_finalization(&args.verify, &node, &result).await;
+    save_history(
+        &args.history,
+        verified_record(history_record, &result, &verification),
+    );
 
+    transfer_report(&result, &verification, start.elapsed()).print();
+    let deploy_id = Some(result.deploy_id.as_str());
+    let mut receipt = DeployReceipt::from(&result);
+    let transfer_result = |receipt: &DeployReceipt| {
+        RevTransferResult::new(
+            receipt,
+            &from_address,
+            &args.to_address,
+            amount_dust,
+            start.elapsed(),
+        )
+    };
     if result.errored {
+        wait_end.finished(&receipt).await;
+        if args.output.is_json() {
+            print_transfer_json(&transfer_result(&receipt))?;
+        }
         let err = result
             .system_deploy_error
             .as_deref()
             .unwrap_or("unknown error");
-        println!("Transfer failed: {}", err);
         return Err(format!("Transfer failed: {}", err).into());
     }
+    if let Err(e) = verification.check(args.verify.require_verification) {
+        wait_end
+            .failed(deploy_id, Some(&receipt), &e.to_string())
+            .await;
+        return Err(e.into());
+    }
 
-    println!("Deploy ID: {}", result.deploy_id);
-    println!("Block hash: {}", result.block_hash);
-    if let Some(cost) = result.cost {
-        println!("Cost: {}", cost);
+    if let Some(depth) = args.confirm_depth {
+        let wait = ConfirmationWait {
+            depth,
+            poll_interval: args.wait.check_interval.max(MIN_POLL_INTERVAL),
+            // Inclusion and finalization each get --max-wait
+            deadline: start + args.wait.max_wait * 2,
+        };
+        match wait_for_confirm_depth(
+            &api,
+            node.http_port,
+            &result.block_hash,
+            result.block_number,
+            wait,
+        )
+        .await
+        {
+            Ok(confirmations) => {
+                Report::new()
+                    .line(format!("Confirmations: {}", confirmations))
+                    .print();
+                receipt.confirmations = Some(confirmations);
+            }
+            Err(e) => {
+                wait_end
+                    .failed(deploy_id, Some(&receipt), &e.to_string())
+                    .await;
+                return Err(e.into());
+            }
+        }
     }
-    println!("Total time: {:.2?}", start.elapsed());
 
+    let mut report = Report::new();
     if args.propose {
-        let api = F1r3flyApi::new(&args.private_key, &args.host, args.port)?;
-        match api.propose().await {
-            Ok(ProposeResult::Proposed(hash)) => println!("Block proposed: {}", hash),
-            Ok(ProposeResult::Skipped(reason)) => println!("Propose skipped: {}", reason),
-            Err(e) => println!("Propose failed: {}", e),
-        }
+        report_proposal(&mut report, api.propose().await);
     }
+    report.note("Transfer complete.").print();
+    wait_end.finished(&receipt).await;
+    let transfer = transfer_result(&receipt);
+    if args.output.is_json() {
+        print_transfer_json(&transfer)?;
+    }
+    Ok(CommandOutcome::Transfer(transfer))
+}
 
-    println!("Transfer complete.");
+/// The one line `transfer --json` writes to stdout
+fn print_transfer_json(transfer: &RevTransferResult) -> Result<(), serde_json::Error> {
+    println!("{}", serde_json::to_string(transfer)?);
     Ok(())
 }
 
+/// Result of a finalized transfer; an errored transfer only reports verification,
+/// its error is returned to the dispatcher
+fn transfer_report(
+    result: &crate::f1r3fly_api::DeployResult,
+    verification: &Verification,
+    elapsed: Duration,
+) -> Report {
+    let mut report = Report::new();
+    report_verification(&mut report, verification);
+    if result.errored {
+        return report;
+    }
+    report_included(&mut report, result);
+    if let Some(cost) = result.cost {
+        report.line(format!("Cost: {}", cost));
+    }
+    report.note(format!("Total time: {:.2?}", elapsed));
+    report
+}
+
 pub async fn deploy_and_wait_command(
     args: &DeployAndWaitArgs,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let rholang_code =
-        fs::read_to_string(&args.file).map_err(|e| format!("Failed to read file: {}", e))?;
-
-    let manager = F1r3flyConnectionManager::new(config_from_deploy_args(args));
+) -> Result<CommandOutcome, Box<dyn std::error::Error>> {
+    let language = args.language.resolve()?;
+    let source = args.source.source();
+    let payload = source.read(&language)?;
+    check_term(&payload, &args.term_limits, args.verbose, args.force)?;
+
+    let node = preflight_node(&args.node, args.skip_preflight).await?;
+    let manager = F1r3flyConnectionManager::new(config_from_deploy_args(args, &node));
     let expiration = calculate_expiration_timestamp(args.expiration, args.expires_in);
 
-    println!("Deploying and waiting for finalization...");
+    print_progress(if args.no_wait_finalization {
+        "Deploying and waiting for block inclusion..."
+    } else {
+        "Deploying and waiting for finalization..."
+    });
     let start = Instant::now();
+    let wait_end = WaitEnd::new("deploy-and-wait", args.notify, &args.webhook, start);
+
+    let mut history_record = HistoryRecord::new(
+        "deploy-and-wait",
+        &args.node.host,
+        args.node.grpc_port,
+        &payload.term,
+    );
+    history_record.http_port = Some(node.http_port);
+    // Only a file can be re-read by bump-deploy
+    history_record.source = source.path().map(|path| path.display().to_string());
+
+    if args.no_wait_finalization {
+        let included = match manager
+            .deploy_and_wait_for_block(&payload.term, &language, args.bigger_phlo, expiration)
+            .await
+        {
+            Ok(included) => included,
+            Err(e) => {
+                print_deploy_rejection(e.deploy_rejection(), args.verbose);
+                save_history(&args.history, history_record.fail(&e));
+                wait_end
+                    .failed(failed_deploy_id(&e), None, &e.to_string())
+                    .await;
+                return Err(NodeCliError::from(e).into());
+            }
+        };
+        save_history(&args.history, history_record.included(&included));
+        included_report(&included, start.elapsed()).print();
+        if args.propose {
+            let api = F1r3flyApi::new(
+                &args.signing.private_key,
+                &args.node.host,
+                args.node.grpc_port,
+            )?;
+            let mut report = Report::new();
+            report_proposal(&mut report, api.propose().await);
+            report.print();
+        }
+        wait_end.included(&included).await;
+        return Ok(CommandOutcome::Included {
+            deploy_id: included.deploy_id,
+            block_hash: included.block_hash,
+        });
+    }
+
+    let result = match manager
+        .deploy_and_wait_with_language(&payload.term, &language, args.bigger_phlo, expiration)
+        .await
+    {
+        Ok(result) => result,
+        Err(e) => {
+            print_deploy_rejection(e.deploy_rejection(), args.verbose);
+            save_history(&args.history, history_record.fail(&e));
+            wait_end
+                .failed(failed_deploy_id(&e), None, &e.to_string())
+                .await;
+            return Err(NodeCliError::from(e).into());
+        }
+    };
+    let verification = verify_finalization(&args.verify, &node, &result).await;
+    save_history(
+        &args.history,
+        verified_record(history_record, &result, &verification),
+    );
+
+    deploy_and_wait_report(&result, &verification, start.elapsed()).print();
+    let deploy_id = Some(result.deploy_id.as_str());
+    let mut receipt = DeployReceipt::from(&result);
+    if result.errored {
+        wait_end.finished(&receipt).await;
+        return Err(NodeCliError::deploy_errored(
+            &result.deploy_id,
+            result.system_deploy_error.as_deref(),
+        )
+        .into());
+    }
+    if let Err(e) = verification.check(args.verify.require_verification) {
+        wait_end
+            .failed(deploy_id, Some(&receipt), &e.to_string())
+            .await;
+        return Err(e.into());
+    }
+
+    let api = F1r3flyApi::new(
+        &args.signing.private_key,
+        &args.node.host,
+        args.node.grpc_port,
+    )?;
+    if let Some(depth) = args.confirm_depth {
+        let wait = ConfirmationWait {
+            depth,
+            poll_interval: args.check_interval.max(MIN_POLL_INTERVAL),
+            deadline: start + args.max_wait + args.finalization_timeout,
+        };
+        match wait_for_confirm_depth(
+            &api,
+            node.http_port,
+            &result.block_hash,
+            result.block_number,
+            wait,
+        )
+        .await
+        {
+            Ok(confirmations) => {
+                Report::new()
+                    .line(format!("Confirmations: {}", confirmations))
+                    .print();
+                receipt.confirmations = Some(confirmations);
+            }
+            Err(e) => {
+                wait_end
+                    .failed(deploy_id, Some(&receipt), &e.to_string())
+                    .await;
+                return Err(e.into());
+            }
+        }
+    }
+
+    if args.propose {
+        let mut report = Report::new();
+        report_proposal(&mut report, api.propose().await);
+        report.print();
+    }
+    wait_end.finished(&receipt).await;
+
+    Ok(CommandOutcome::DeployReceipt(receipt))
+}
+
+/// Resume the wait of `deploy-and-wait` or `transfer` for a deploy already sent
+///
+/// The deploy's expiry is not known here, so an expired deploy is reported as
+/// not included once the attempts run out.
+pub async fn wait_for_deploy_command(
+    args: &WaitForDeployArgs,
+) -> Result<CommandOutcome, Box<dyn std::error::Error>> {
+    let config = build_config(
+        &args.node,
+        &args.observer,
+        &args.signing.private_key,
+        args.max_wait(),
+        args.max_wait(),
+        args.check_interval,
+        &args.poll,
+    );
+    let manager = F1r3flyConnectionManager::new(config);
 
+    print_progress(&format!(
+        "Waiting for deploy {} (up to {} for inclusion, then for finalization)...",
+        shorten(&args.deploy_id, Field::Hash),
+        format_duration(args.max_wait())
+    ));
+    let start = Instant::now();
     let result = manager
-        .deploy_and_wait(&rholang_code, args.bigger_phlo, expiration)
+        .wait_for_deploy_result(&args.deploy_id, None)
         .await
-        .map_err(|e| -> Box<dyn std::error::Error> { e.to_string().into() })?;
+        .map_err(NodeCliError::from)?;
+
+    deploy_and_wait_report(&result, &Verification::NotRequested, start.elapsed()).print();
+    if result.errored {
+        return Err(NodeCliError::deploy_errored(
+            &result.deploy_id,
+            result.system_deploy_error.as_deref(),
+        )
+        .into());
+    }
+    Ok(CommandOutcome::DeployReceipt(DeployReceipt::from(&result)))
+}
 
-    println!("Deploy ID: {}", result.deploy_id);
-    println!("Block hash: {}", result.block_hash);
+/// Result of a finalized deploy, with the data it sent to its deploy ID
+fn deploy_and_wait_report(
+    result: &crate::f1r3fly_api::DeployResult,
+    verification: &Verification,
+    elapsed: Duration,
+) -> Report {
+    let mut report = Report::new();
+    report_verification(&mut report, verification);
+    report_included(&mut report, result);
     if let Some(block_num) = result.block_number {
-        println!("Block number: {}", block_num);
+        report.line(format!("Block number: {}", block_num));
     }
     if let Some(cost) = result.cost {
-        println!("Cost: {}", cost);
+        report.line(format!("Cost: {}", cost));
+        report.line(format!("Errored: {}", result.errored));
+    } else if result.errored {
+        report.line("Errored: true");
     }
-    if result.errored {
-        println!("Errored: true");
-        if let Some(ref err) = result.system_deploy_error {
-            println!("Deploy error: {}", err);
-        }
+    if let Some(ref err) = result.system_deploy_error {
+        report.line(format!("Deploy error: {}", err));
     }
     if result.data.is_empty() {
-        println!("Data: (none)");
+        report.line("Data: (none)");
     } else {
         for (i, par) in result.data.iter().enumerate() {
             let simplified =
                 crate::f1r3fly_api::extract_par_data(par).unwrap_or_else(|| format!("{:?}", par));
-            println!("Data[{}]: {}", i, simplified);
+            report.line(format!("Data[{}]: {}", i, simplified));
         }
     }
-    println!("Total time: {:.2?}", start.elapsed());
+    report.note(format!("Total time: {:.2?}", elapsed));
+    report
+}
 
-    if args.propose {
-        let private_key = args.private_key.as_deref().unwrap_or(DEV_PRIVATE_KEY);
-        let api = F1r3flyApi::new(private_key, &args.host, args.port)?;
-        match api.propose().await {
-            Ok(ProposeResult::Proposed(hash)) => println!("Block proposed: {}", hash),
-            Ok(ProposeResult::Skipped(reason)) => println!("Propose skipped: {}", reason),
-            Err(e) => println!("Propose failed: {}", e),
+/// Lifecycle timeline for a deploy in `block_hash`
+///
+/// Stage times come from the block's own deploy list; `block_timestamp` is the
+/// fallback when the block cannot be fetched. Finalization time comes from
+/// local history first, then the LFB timestamp as an upper bound.
+async fn deploy_timeline(
+    api: &F1r3flyApi<'_>,
+    deploy_id: &str,
+    block_hash: Option<&str>,
+    block_timestamp: Option<i64>,
+    is_finalized: bool,
+    http_port: u16,
+) -> DeployTimeline {
+    let block = match block_hash {
+        Some(hash) => api.get_block_detail(hash, http_port).await.ok().flatten(),
+        None => None,
+    };
+    let deployed = block.as_ref().and_then(|b| {
+        b.deploys
+            .iter()
+            .find(|d| d.sig.eq_ignore_ascii_case(deploy_id))
+            .map(|d| d.timestamp)
+    });
+    let included = block.map(|b| b.header.timestamp).or(block_timestamp);
+
+    let finalization = if !is_finalized {
+        Finalization::Pending
+    } else if let Some(at) = history::recorded_finalization(deploy_id) {
+        Finalization::Recorded(at)
+    } else {
+        match api.get_last_finalized_block(http_port).await {
+            Ok(Some(lfb)) if lfb.timestamp > 0 => Finalization::NoLaterThan(lfb.timestamp),
+            _ => Finalization::Unknown,
         }
+    };
+
+    DeployTimeline {
+        deployed,
+        in_block: block_hash.is_some(),
+        included,
+        finalization,
     }
+}
 
-    Ok(())
+fn report_timeline(report: &mut Report, timeline: &DeployTimeline) {
+    report.line("").line("Timeline");
+    for line in timeline.render() {
+        report.line(format!("  {}", line));
+    }
+    report.line(format!("  {}", timeline.summary()));
 }
 
 pub async fn get_deploy_command(args: &GetDeployArgs) -> Result<(), Box<dyn std::error::Error>> {
-    let f1r3fly_api = F1r3flyApi::new(DEV_PRIVATE_KEY, &args.host, 40412)?;
+    let f1r3fly_api = F1r3flyApi::for_queries(
+        args.query_key.private_key.as_deref(),
+        &args.node.host,
+        args.node.grpc_port,
+    )?;
+    let deploy_id = &match expand_prefix(
+        &f1r3fly_api,
+        &args.node.conn(),
+        IdKind::DeployId,
+        &args.deploy_id,
+        args.search_depth,
+    )
+    .await
+    {
+        Ok(deploy_id) => deploy_id,
+        Err(e) => {
+            print_lookup_hints(
+                &f1r3fly_api,
+                args.node.http_port,
+                IdKind::DeployId,
+                &args.deploy_id,
+                args.no_hints,
+            )
+            .await;
+            return Err(e);
+        }
+    };
     let start_time = Instant::now();
+    let mut report = Report::new();
+
+    // gRPC findDeploy works on nodes that expose no HTTP port; HTTP is the fallback
+    let found = match f1r3fly_api.find_deploy(deploy_id).await {
+        Ok(found) => Some(found),
+        Err(e) => {
+            tracing::debug!("gRPC deploy lookup failed, trying HTTP: {}", e);
+            None
+        }
+    };
+    if let Some(None) = found {
+        return deploy_not_found(&f1r3fly_api, args, deploy_id).await;
+    }
 
     // Try detail view first (Rust node with PR #472+)
     if let Ok(Some(detail)) = f1r3fly_api
-        .get_deploy_detail(&args.deploy_id, args.http_port)
+        .get_deploy_detail(deploy_id, args.node.http_port)
         .await
     {
         let duration = start_time.elapsed();
+        let timeline = deploy_timeline(
+            &f1r3fly_api,
+            deploy_id,
+            Some(&detail.block_hash),
+            Some(detail.timestamp),
+            detail.is_finalized,
+            args.node.http_port,
+        )
+        .await;
         match args.format.as_str() {
             "json" => {
-                println!("{}", serde_json::to_string_pretty(&detail)?);
+                let mut json = serde_json::to_value(&detail)?;
+                json["timeline"] = timeline.to_json();
+                report.line(serde_json::to_string_pretty(&json)?);
             }
             "summary" => {
-                println!(
+                report.line(format!(
                     "Deploy {} in block {} (#{}) cost={} errored={}",
-                    args.deploy_id,
-                    detail.block_hash,
-                    detail.block_number,
-                    detail.cost,
-                    detail.errored
-                );
+                    deploy_id, detail.block_hash, detail.block_number, detail.cost, detail.errored
+                ));
             }
             _ => {
-                println!("Deploy Information");
-                println!("----------------------------------------");
-                println!("Deploy ID:    {}", detail.deploy_id);
-                println!("Block Hash:   {}", detail.block_hash);
-                println!("Block Number: {}", detail.block_number);
-                println!("Finalized:    {}", detail.is_finalized);
+                report
+                    .line("Deploy Information")
+                    .line("----------------------------------------")
+                    .line(format!("Deploy ID:    {}", detail.deploy_id))
+                    .line(format!("Block Hash:   {}", detail.block_hash))
+                    .line(format!("Block Number: {}", detail.block_number))
+                    .line(format!("Finalized:    {}", detail.is_finalized));
                 if let Some(ref deployer) = detail.deployer {
-                    println!("Deployer:     {}", deployer);
+                    report.line(format!("Deployer:     {}", shorten(deployer, Field::Key)));
                 }
-                println!("Cost:         {}", detail.cost);
-                println!("Errored:      {}", detail.errored);
+                report
+                    .line(format!("Cost:         {}", detail.cost))
+                    .line(format!("Errored:      {}", detail.errored));
                 if let Some(ref err) = detail.system_deploy_error {
                     if !err.is_empty() {
-                        println!("Error:        {}", err);
+                        report.line(format!("Error:        {}", err));
                     }
                 }
                 if let Some(price) = detail.phlo_price {
-                    println!("Phlo Price:   {}", price);
+                    report.line(format!("Phlo Price:   {}", price));
                 }
                 if let Some(limit) = detail.phlo_limit {
-                    println!("Phlo Limit:   {}", limit);
+                    report.line(format!("Phlo Limit:   {}", limit));
                 }
-                println!("Timestamp:    {}", detail.timestamp);
+                report.line(format!("Timestamp:    {}", detail.timestamp));
                 if let Some(ref algo) = detail.sig_algorithm {
-                    println!("Sig Algo:     {}", algo);
+                    report.line(format!("Sig Algo:     {}", algo));
                 }
                 if args.verbose {
                     if let Some(vabn) = detail.valid_after_block_number {
-                        println!("VABN:         {}", vabn);
+                        report.line(format!("VABN:         {}", vabn));
                     }
                 }
-                println!("Query time:   {:.2?}", duration);
+                report.note(format!("Query time:   {:.2?}", duration));
+                report_timeline(&mut report, &timeline);
+            }
+        }
+        report.print();
+        return Ok(());
+    }
+
+    // Found over gRPC but no HTTP detail: show what gRPC reported
+    if let Some(Some(info)) = found {
+        let duration = start_time.elapsed();
+        let is_finalized = f1r3fly_api
+            .is_finalized(&info.block_hash, 1, Duration::ZERO)
+            .await
+            .unwrap_or(false);
+        let timeline = deploy_timeline(
+            &f1r3fly_api,
+            deploy_id,
+            Some(&info.block_hash),
+            None,
+            is_finalized,
+            args.node.http_port,
+        )
+        .await;
+        let unknown = |value: Option<String>| value.unwrap_or_else(|| "unknown".to_string());
+        let cost = unknown(info.cost.map(|c| c.to_string()));
+        let errored = unknown(info.errored.map(|e| e.to_string()));
+        match args.format.as_str() {
+            "json" => {
+                let mut json = serde_json::to_value(&info)?;
+                json["is_finalized"] = is_finalized.into();
+                json["timeline"] = timeline.to_json();
+                report.line(serde_json::to_string_pretty(&json)?);
+            }
+            "summary" => {
+                report.line(format!(
+                    "Deploy {} in block {} (#{}) cost={} errored={}",
+                    deploy_id, info.block_hash, info.block_number, cost, errored
+                ));
+            }
+            _ => {
+                report
+                    .line("Deploy Information (gRPC view)")
+                    .line("----------------------------------------")
+                    .line(format!("Deploy ID:    {}", info.deploy_id))
+                    .line(format!("Block Hash:   {}", info.block_hash))
+                    .line(format!("Block Number: {}", info.block_number))
+                    .line(format!("Finalized:    {}", is_finalized))
+                    .line(format!("Cost:         {}", cost))
+                    .line(format!("Errored:      {}", errored))
+                    .note(format!("Query time:   {:.2?}", duration));
+                report_timeline(&mut report, &timeline);
             }
         }
+        report.print();
         return Ok(());
     }
 
     // Fall back to default view (works on all nodes)
     match f1r3fly_api
-        .get_deploy_default(&args.deploy_id, args.http_port)
-        .await
+        .get_deploy_default(deploy_id, args.node.http_port)
+        .await?
     {
-        Ok(Some(json)) => {
+        Some(mut json) => {
             let duration = start_time.elapsed();
+            let block_hash = json.get("blockHash").and_then(|v| v.as_str());
+            let is_finalized = match block_hash {
+                Some(hash) => f1r3fly_api
+                    .is_finalized(hash, 1, Duration::ZERO)
+                    .await
+                    .unwrap_or(false),
+                None => false,
+            };
+            let timeline = deploy_timeline(
+                &f1r3fly_api,
+                deploy_id,
+                block_hash,
+                json.get("timestamp").and_then(|v| v.as_i64()),
+                is_finalized,
+                args.node.http_port,
+            )
+            .await;
             match args.format.as_str() {
                 "json" => {
-                    println!("{}", serde_json::to_string_pretty(&json)?);
+                    if let Some(object) = json.as_object_mut() {
+                        object.insert("timeline".to_string(), timeline.to_json());
+                    }
+                    report.line(serde_json::to_string_pretty(&json)?);
                 }
                 _ => {
-                    println!("Deploy Information (basic view)");
-                    println!("----------------------------------------");
-                    println!("Deploy ID:    {}", args.deploy_id);
+                    report
+                        .line("Deploy Information (basic view)")
+                        .line("----------------------------------------")
+                        .line(format!("Deploy ID:    {}", deploy_id));
                     if let Some(hash) = json.get("blockHash").and_then(|v| v.as_str()) {
-                        println!("Block Hash:   {}", hash);
+                        report.line(format!("Block Hash:   {}", hash));
                     }
                     if let Some(num) = json.get("blockNumber").and_then(|v| v.as_i64()) {
-                        println!("Block Number: {}", num);
+                        report.line(format!("Block Number: {}", num));
                     }
                     if let Some(sender) = json.get("sender").and_then(|v| v.as_str()) {
-                        println!("Sender:       {}", sender);
+                        report.line(format!("Sender:       {}", shorten(sender, Field::Key)));
                     }
                     if let Some(ts) = json.get("timestamp").and_then(|v| v.as_i64()) {
-                        println!("Timestamp:    {}", ts);
+                        report.line(format!("Timestamp:    {}", ts));
                     }
-                    println!("Query time:   {:.2?}", duration);
-                    println!();
-                    println!(
-                        "Note: deploy execution details (cost, errored) require Rust node v0.4.11+"
+                    report.note(format!("Query time:   {:.2?}", duration));
+                    report_timeline(&mut report, &timeline);
+                    report.note("").note(
+                        "Note: deploy execution details (cost, errored) require Rust node v0.4.11+",
                     );
                 }
             }
         }
-        Ok(None) => {
-            println!("Deploy {} not found", args.deploy_id);
+        None => return deploy_not_found(&f1r3fly_api, args, deploy_id).await,
+    }
+    report.print();
+
+    Ok(())
+}
+
+/// Say `deploy_id` was not found, with hints about what the ID may be instead
+async fn deploy_not_found(
+    api: &F1r3flyApi<'_>,
+    args: &GetDeployArgs,
+    deploy_id: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    Report::new()
+        .line(format!("Deploy {} not found", deploy_id))
+        .print();
+    print_lookup_hints(
+        api,
+        args.node.http_port,
+        IdKind::DeployId,
+        deploy_id,
+        args.no_hints,
+    )
+    .await;
+    Ok(())
+}
+
+/// Most recent history record of `deploy_id`, if history has one
+///
+/// Best-effort: a missing or unreadable history file yields `None`.
+fn recorded_deploy(deploy_id: &str) -> Option<HistoryRecord> {
+    let records = history::read_records(&history::history_path()?).ok()?;
+    records.into_iter().rev().find(|r| r.is_deploy(deploy_id))
+}
+
+/// The term a stuck deploy was signed with
+///
+/// `--file` wins; otherwise the source file named in the deploy's history
+/// record is reread. Either way the term must hash to the one recorded, so a
+/// file edited since the deploy is never resubmitted as its replacement.
+fn recover_term(
+    deploy_id: &str,
+    file: Option<&Path>,
+    language: &str,
+    recorded: Option<&HistoryRecord>,
+) -> crate::error::Result<(Payload, String)> {
+    let source = match (file, recorded.and_then(|r| r.source.as_deref())) {
+        (Some(file), _) => file.display().to_string(),
+        (None, Some(source)) => source.to_string(),
+        (None, None) => {
+            return Err(NodeCliError::config_invalid_value(
+                "deploy-id",
+                &format!(
+                    "cannot recover the term of deploy {}: {}. \
+                     Pass the original term with --file <PATH>",
+                    deploy_id,
+                    if recorded.is_some() {
+                        "its history record has no source file"
+                    } else {
+                        "it is not in the local deploy history"
+                    }
+                ),
+            ));
+        }
+    };
+
+    let payload = read_payload(Path::new(&source), language)?;
+    if let Some(record) = recorded {
+        if history::term_hash(&payload.term) != record.term_hash {
+            return Err(NodeCliError::config_invalid_value(
+                "file",
+                &format!(
+                    "{} no longer matches the term recorded for deploy {} (hash {}). \
+                     Pass the original term with --file <PATH>",
+                    source, deploy_id, record.term_hash
+                ),
+            ));
         }
+    }
+    Ok((payload, source))
+}
+
+/// Fail if `deploy_id` is already in a block, where bumping it would run the term twice
+async fn ensure_pending(
+    api: &F1r3flyApi<'_>,
+    deploy_id: &str,
+    http_port: u16,
+) -> crate::error::Result<()> {
+    match api.get_deploy_block_hash(deploy_id, http_port).await? {
+        Some(block_hash) => Err(NodeCliError::config_invalid_value(
+            "deploy-id",
+            &format!(
+                "deploy {} is already included in block {}; there is nothing to bump",
+                deploy_id, block_hash
+            ),
+        )),
+        None => Ok(()),
+    }
+}
+
+pub async fn bump_deploy_command(args: &BumpDeployArgs) -> crate::error::Result<()> {
+    let language = args.language.resolve()?;
+    let recorded = recorded_deploy(&args.deploy_id);
+    let (payload, source) = recover_term(
+        &args.deploy_id,
+        args.file.as_deref(),
+        &language,
+        recorded.as_ref(),
+    )?;
+    print_progress(&format!("Recovered {} term from: {}", language, source));
+    print_payload_size(&payload);
+
+    let f1r3fly_api = F1r3flyApi::new(
+        &args.signing.private_key,
+        &args.node.host,
+        args.node.grpc_port,
+    )?;
+    ensure_pending(&f1r3fly_api, &args.deploy_id, args.node.http_port).await?;
+    print_progress(&format!(
+        "Deploy {} is not in a block yet; resubmitting at phlo price {}",
+        args.deploy_id, args.phlo_price
+    ));
+
+    let mut history_record = HistoryRecord::new(
+        "bump-deploy",
+        &args.node.host,
+        args.node.grpc_port,
+        &payload.term,
+    );
+    history_record.http_port = Some(args.node.http_port);
+    history_record.source = Some(source);
+
+    let submitted = match f1r3fly_api
+        .submit_deploy_with_phlo_price(
+            &payload.term,
+            args.bigger_phlo,
+            &language,
+            0,
+            args.phlo_price,
+        )
+        .await
+    {
+        Ok(submitted) => submitted,
         Err(e) => {
-            println!("Error retrieving deploy information: {}", e);
-            return Err(e);
+            print_deploy_rejection(
+                e.downcast_ref::<NodeCliError>()
+                    .and_then(NodeCliError::deploy_rejection),
+                args.verbose,
+            );
+            save_history(&args.history, history_record.fail(&e));
+            return Err(e.into());
+        }
+    };
+
+    let mut report = Report::new();
+    report
+        .note("Replacement deploy submitted!")
+        .line(format!("Original deploy ID:    {}", args.deploy_id))
+        .line(format!("Replacement deploy ID: {}", submitted.deploy_id))
+        .line(
+            "Warning: the original deploy stays in the pool. If both are included, \
+             the term executes twice; prefer idempotent terms (e.g. guard side \
+             effects on a one-shot channel) for deploys you may bump.",
+        )
+        .print();
+
+    history_record.deploy_id = Some(submitted.deploy_id);
+    // The original's record is marked even without --history, so the ledger
+    // never shows two live deploys for one term
+    if !args.history.no_history && (args.history.enabled() || recorded.is_some()) {
+        if let Some(path) = history::history_path() {
+            if let Err(e) = history::supersede(&path, &args.deploy_id, &history_record) {
+                tracing::warn!(
+                    path = %path.display(),
+                    "Failed to record the superseded deploy: {}",
+                    e
+                );
+            }
         }
     }
 
@@ -636,76 +2229,496 @@ fn validate_vault_address(address: &str) -> Result<(), Box<dyn std::error::Error
     Ok(())
 }
 
-fn generate_transfer_contract(from_address: &str, to_address: &str, amount_dust: u64) -> String {
-    format!(
-        r#"new 
- deployerId(`rho:system:deployerId`),
- stdout(`rho:io:stdout`),
- rl(`rho:registry:lookup`),
- systemVaultCh,
- vaultCh,
- toVaultCh,
- systemVaultKeyCh,
- resultCh
-in {{
- rl!(`rho:vault:system`, *systemVaultCh) |
- for (@(_, SystemVault) <- systemVaultCh) {{
- @SystemVault!("findOrCreate", "{}", *vaultCh) |
- @SystemVault!("findOrCreate", "{}", *toVaultCh) |
- @SystemVault!("deployerAuthKey", *deployerId, *systemVaultKeyCh) |
- for (@(true, vault) <- vaultCh; key <- systemVaultKeyCh; @(true, toVault) <- toVaultCh) {{
- @vault!("transfer", "{}", {}, *key, *resultCh) |
- for (@result <- resultCh) {{
- match result {{
- (true, Nil) => {{
- stdout!(("Transfer successful:", {}, "tokens"))
- }}
- (false, reason) => {{
- stdout!(("Transfer failed:", reason))
- }}
- }}
- }}
- }} |
- for (@(false, errorMsg) <- vaultCh) {{
- stdout!(("Sender vault error:", errorMsg))
- }} |
- for (@(false, errorMsg) <- toVaultCh) {{
- stdout!(("Destination vault error:", errorMsg))
- }}
- }}
-}}"#,
-        from_address, // findOrCreate sender
-        to_address,   // findOrCreate recipient
-        to_address,   // transfer target
-        amount_dust,  // transfer amount
-        amount_dust   // success message amount
-    )
-}
-
 /// Read data at a deploy ID from a specific block
 pub async fn get_data_command(args: &GetDataArgs) -> crate::error::Result<()> {
-    let f1r3fly_api = F1r3flyApi::new(&args.private_key, &args.host, args.port)?;
+    let f1r3fly_api = F1r3flyApi::new(
+        &args.signing.private_key,
+        &args.node.host,
+        args.node.grpc_port,
+    )?;
 
     let pars = f1r3fly_api
         .get_data_at_deploy_id(&args.deploy_id, &args.block_hash)
         .await
         .map_err(|e| crate::error::NodeCliError::General(e.to_string()))?;
 
+    let mut report = Report::new();
     if pars.is_empty() {
-        println!(
+        report.line(format!(
             "No data found for deploy {} at block {}",
             args.deploy_id, args.block_hash
-        );
+        ));
     } else {
         for (i, par) in pars.iter().enumerate() {
             let simplified =
                 crate::f1r3fly_api::extract_par_data(par).unwrap_or_else(|| format!("{:?}", par));
-            println!("{}", simplified);
+            report.line(simplified);
             if i < pars.len() - 1 {
-                println!("---");
+                report.line("---");
             }
         }
     }
+    report.print();
 
     Ok(())
 }
+
+pub async fn data_at_name_command(args: &DataAtNameArgs) -> crate::error::Result<()> {
+    let name = if args.unforgeable {
+        crate::f1r3fly_api::unforgeable_name(&args.name)?
+    } else {
+        crate::f1r3fly_api::public_name(&args.name)
+    };
+    let shown = if args.unforgeable {
+        format!("unforgeable {}", args.name.trim())
+    } else {
+        format!("@{:?}", args.name)
+    };
+    let f1r3fly_api = F1r3flyApi::for_queries(
+        args.query_key.private_key.as_deref(),
+        &args.node.host,
+        args.node.grpc_port,
+    )?;
+
+    let found = match &args.block_hash {
+        Some(block_hash) => {
+            let pars = f1r3fly_api
+                .get_data_at_name_in_block(name, block_hash)
+                .await
+                .map_err(NodeCliError::from)?;
+            (!pars.is_empty()).then(|| (block_hash.clone(), pars))
+        }
+        None => f1r3fly_api
+            .get_data_at_name(&name, args.depth)
+            .await
+            .map_err(NodeCliError::from)?
+            .map(|(block, pars)| {
+                (
+                    format!("{} (block number {})", block.block_hash, block.block_number),
+                    pars,
+                )
+            }),
+    };
+
+    let mut report = Report::new();
+    match found {
+        None => {
+            let place = match &args.block_hash {
+                Some(block_hash) => format!("at block {}", block_hash),
+                None => format!("in the last {} main-chain block(s)", args.depth),
+            };
+            report.line(format!("No data at {} {}", shown, place));
+        }
+        Some((block, pars)) => {
+            report.note(format!("{} datum(s) at {}", pars.len(), shown));
+            report.line(format!("Block: {}", block));
+            for (i, par) in pars.iter().enumerate() {
+                let value = crate::rholang_helpers::convert_rholang_to_json(
+                    &crate::f1r3fly_api::par_to_rholang_json(par),
+                )
+                .map_err(|e| NodeCliError::parse_error(&e.to_string()))?;
+                if i > 0 {
+                    report.line("---");
+                }
+                report.line(
+                    serde_json::to_string_pretty(&value)
+                        .map_err(|e| NodeCliError::parse_error(&e.to_string()))?,
+                );
+            }
+        }
+    }
+    report.print();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::spawn_http_node;
+    use crate::utils::capture::Capture;
+    use clap::Parser;
+
+    fn add_stake_args(observer_http_port: u16) -> AddStakeArgs {
+        AddStakeArgs {
+            node: NodeConnArgs {
+                host: "127.0.0.1".to_string(),
+                grpc_port: 1,
+                http_port: 1,
+                node_role: None,
+            },
+            amount: 500,
+            private_key: DEV_PRIVATE_KEY.to_string(),
+            key_guard: KeyGuardArgs::default(),
+            propose: false,
+            wait: WaitBehaviorArgs {
+                max_wait: Duration::from_secs(1),
+                check_interval: Duration::from_secs(1),
+                poll: PollArgs::default(),
+            },
+            observer: ObserverConnArgs {
+                observer_http_port,
+                ..Default::default()
+            },
+            expiration: None,
+            expires_in: None,
+            verbose: false,
+            skip_preflight: true,
+            history: HistoryFlags::default(),
+            pos: PosVerifyArgs {
+                verify_pos: PosVerifyMode::Off,
+                topology: None,
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_add_stake_refuses_unbonded_key() {
+        let explore_calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let calls = explore_calls.clone();
+        let port = spawn_http_node(move |line, _body| {
+            if line.contains("/api/explore-deploy") {
+                calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                let bonds = serde_json::json!({
+                    "expr": [{"ExprMap": {"data": [
+                        [{"ExprBytes": {"data": "04aa"}}, {"ExprInt": {"data": 1000}}]
+                    ]}}]
+                });
+                ("200 OK", bonds.to_string())
+            } else {
+                ("404 Not Found", "{}".to_string())
+            }
+        })
+        .await;
+
+        let err = add_stake_command(&add_stake_args(port), true)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("is not bonded"));
+        assert!(err.contains("bond-validator"));
+        // Only the bonds lookup ran: no balance check and no deploy
+        assert_eq!(explore_calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_unbond_refuses_unbonded_key() {
+        let explore_calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let calls = explore_calls.clone();
+        let port = spawn_http_node(move |line, _body| {
+            if line.contains("/api/explore-deploy") {
+                calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                let bonds = serde_json::json!({
+                    "expr": [{"ExprMap": {"data": [
+                        [{"ExprBytes": {"data": "04aa"}}, {"ExprInt": {"data": 1000}}]
+                    ]}}]
+                });
+                ("200 OK", bonds.to_string())
+            } else {
+                ("404 Not Found", "{}".to_string())
+            }
+        })
+        .await;
+
+        let stake_args = add_stake_args(port);
+        let args = UnbondValidatorArgs {
+            node: stake_args.node,
+            private_key: stake_args.private_key,
+            key_guard: stake_args.key_guard,
+            propose: false,
+            wait: stake_args.wait,
+            finalization_timeout: None,
+            observer: stake_args.observer,
+            pos: stake_args.pos,
+            expiration: None,
+            expires_in: None,
+            verbose: false,
+            skip_preflight: true,
+            history: HistoryFlags::default(),
+        };
+        let err = unbond_validator_command(&args, true)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("is not bonded"));
+        // Only the bonds lookup ran: no delay query and no deploy
+        assert_eq!(explore_calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_add_stake_refuses_unexpected_pos_contract() {
+        let explore_calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let calls = explore_calls.clone();
+        let port = spawn_http_node(move |line, _body| {
+            if line.contains("/api/status") {
+                ("200 OK", r#"{"shardId": "root"}"#.to_string())
+            } else if line.contains("/api/explore-deploy") {
+                calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                let entry = serde_json::json!({
+                    "expr": [{"ExprTuple": {"data": [
+                        {"ExprUri": {"data": "rho:id:impostor"}},
+                        {"ExprUnforg": {"data": {"UnforgPrivate": {"data": "beef"}}}}
+                    ]}}]
+                });
+                ("200 OK", entry.to_string())
+            } else {
+                ("404 Not Found", "{}".to_string())
+            }
+        })
+        .await;
+        let topology =
+            std::env::temp_dir().join(format!("node_cli_pos_topology_{}.json", std::process::id()));
+        std::fs::write(
+            &topology,
+            r#"{"pos": {"root": {"uris": ["rho:id:genuine"]}}}"#,
+        )
+        .unwrap();
+
+        let mut args = add_stake_args(port);
+        args.pos = PosVerifyArgs {
+            verify_pos: PosVerifyMode::Refuse,
+            topology: Some(topology.clone()),
+        };
+        let err = add_stake_command(&args, true)
+            .await
+            .unwrap_err()
+            .to_string();
+        let _ = std::fs::remove_file(&topology);
+        assert!(err.contains("found rho:id:impostor"), "{}", err);
+        assert!(err.contains("expected rho:id:genuine"), "{}", err);
+        // Only the PoS lookup ran: the bonds query was never trusted
+        assert_eq!(explore_calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_bump_requires_deploy_still_pending() {
+        let port = spawn_http_node(|line, _body| {
+            if line.contains("/api/deploy/included") {
+                ("200 OK", r#"{"blockHash": "b10c"}"#.to_string())
+            } else {
+                ("404 Not Found", "{}".to_string())
+            }
+        })
+        .await;
+        let api = F1r3flyApi::new(DEV_PRIVATE_KEY, "127.0.0.1", 1).unwrap();
+
+        assert!(ensure_pending(&api, "pending", port).await.is_ok());
+        let err = ensure_pending(&api, "included", port)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("already included in block b10c"), "{}", err);
+    }
+
+    #[test]
+    fn test_bump_recovers_term_or_asks_for_file() {
+        let dir = std::env::temp_dir().join(format!("node_cli_bump_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("contract.rho");
+        std::fs::write(&source, "new x in { x!(1) }").unwrap();
+
+        let err = recover_term("3045", None, "rholang", None)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("--file <PATH>"), "{}", err);
+
+        let mut record = HistoryRecord::new("deploy", "localhost", 40412, "new x in { x!(1) }");
+        record.source = Some(source.display().to_string());
+        let (payload, _) = recover_term("3045", None, "rholang", Some(&record)).unwrap();
+        assert_eq!(payload.term, "new x in { x!(1) }");
+
+        // An edited source file is not the term that was deployed
+        std::fs::write(&source, "new x in { x!(2) }").unwrap();
+        let err = recover_term("3045", None, "rholang", Some(&record))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("no longer matches"), "{}", err);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    fn finalized(errored: bool) -> crate::f1r3fly_api::DeployResult {
+        crate::f1r3fly_api::DeployResult {
+            deploy_id: "3045ab".to_string(),
+            block_hash: "b10c".to_string(),
+            block_number: Some(42),
+            cost: Some(1234),
+            errored,
+            system_deploy_error: errored.then(|| "Insufficient funds".to_string()),
+            data: Vec::new(),
+        }
+    }
+
+    /// Captured lines without the timing lines, which differ from run to run
+    fn untimed(lines: Vec<String>) -> Vec<String> {
+        lines
+            .into_iter()
+            .filter(|l| !l.contains("time:") && !l.contains("Time taken:"))
+            .collect()
+    }
+
+    fn deploy_flow(quiet: bool) -> Vec<String> {
+        let args = DeployArgs::try_parse_from([
+            "deploy",
+            "-f",
+            "contract.rho",
+            "--host",
+            "node0",
+            "--grpc-port",
+            "40412",
+            "--expiration",
+            "1700000000000",
+        ])
+        .unwrap();
+        let payload = Payload {
+            term: "new x in { x!(1) }".to_string(),
+            size: 18,
+            binary: false,
+        };
+        let capture = Capture::start(quiet);
+        announce_deploy(&args, &payload, 1700000000000);
+        deploy_report("3045ab", Duration::from_millis(1500)).print();
+        capture.finish()
+    }
+
+    #[test]
+    fn test_deploy_output_matches_golden() {
+        assert_eq!(
+            untimed(deploy_flow(false)),
+            [
+                "Payload size: 18 bytes",
+                "Connecting to F1r3fly node at node0:40412",
+                "Using phlo limit: 50,000",
+                "Deploy expiration: 1700000000000 ms",
+                "Deployment successful!",
+                "Deploy ID: 3045ab",
+            ]
+        );
+        assert_eq!(deploy_flow(true), ["Deploy ID: 3045ab"]);
+    }
+
+    #[test]
+    fn test_transfer_output_matches_golden() {
+        let verified = Verification::Verified { nodes: 2 };
+        let capture = Capture::start(false);
+        transfer_report(&finalized(false), &verified, Duration::from_millis(1500)).print();
+        assert_eq!(
+            untimed(capture.finish()),
+            [
+                format!("Verification: {}", verified.describe()),
+                "Deploy ID: 3045ab".to_string(),
+                "Block hash: b10c".to_string(),
+                "Cost: 1234".to_string(),
+            ]
+        );
+
+        // The error of an errored transfer is left to the dispatcher
+        let capture = Capture::start(false);
+        transfer_report(
+            &finalized(true),
+            &Verification::NotRequested,
+            Duration::from_millis(1500),
+        )
+        .print();
+        assert!(capture.finish().is_empty());
+
+        let capture = Capture::start(true);
+        transfer_report(
+            &finalized(false),
+            &Verification::NotRequested,
+            Duration::from_millis(1500),
+        )
+        .print();
+        assert_eq!(
+            capture.finish(),
+            ["Deploy ID: 3045ab", "Block hash: b10c", "Cost: 1234"]
+        );
+    }
+
+    #[test]
+    fn test_transfer_json_result() {
+        let mut receipt = DeployReceipt::from(&finalized(false));
+        receipt.confirmations = Some(3);
+        let transfer = RevTransferResult::new(
+            &receipt,
+            "1111from",
+            "1111to",
+            25_000_000,
+            Duration::from_millis(1500),
+        );
+        assert_eq!(
+            serde_json::to_value(&transfer).unwrap(),
+            serde_json::json!({
+                "deploy_id": "3045ab",
+                "block_hash": "b10c",
+                "block_number": 42,
+                "from_address": "1111from",
+                "to_address": "1111to",
+                "amount_dust": 25_000_000,
+                "cost": 1234,
+                "finalized": true,
+                "errored": false,
+                "system_deploy_error": null,
+                "confirmations": 3,
+                "elapsed_ms": 1500,
+            })
+        );
+    }
+
+    #[test]
+    fn test_deploy_and_wait_output_matches_golden() {
+        let capture = Capture::start(false);
+        deploy_and_wait_report(
+            &finalized(true),
+            &Verification::NotRequested,
+            Duration::from_millis(1500),
+        )
+        .print();
+        assert_eq!(
+            untimed(capture.finish()),
+            [
+                "Deploy ID: 3045ab",
+                "Block hash: b10c",
+                "Block number: 42",
+                "Cost: 1234",
+                "Errored: true",
+                "Deploy error: Insufficient funds",
+                "Data: (none)",
+            ]
+        );
+
+        let capture = Capture::start(false);
+        let mut unknown_outcome = finalized(false);
+        unknown_outcome.cost = None;
+        for result in [finalized(false), unknown_outcome] {
+            deploy_and_wait_report(
+                &result,
+                &Verification::NotRequested,
+                Duration::from_millis(1500),
+            )
+            .print();
+        }
+        assert_eq!(
+            untimed(capture.finish()),
+            [
+                "Deploy ID: 3045ab",
+                "Block hash: b10c",
+                "Block number: 42",
+                "Cost: 1234",
+                "Errored: false",
+                "Data: (none)",
+                "Deploy ID: 3045ab",
+                "Block hash: b10c",
+                "Block number: 42",
+                "Data: (none)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_output_goes_through_facade() {
+        // Direct prints ignore --quiet; use utils::output or a Report instead
+        let source = include_str!("network.rs");
+        assert!(!source.contains(concat!("print", "ln!(")));
+        assert!(!source.contains(concat!("eprint", "ln!(")));
+    }
+}
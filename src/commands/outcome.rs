@@ -0,0 +1,172 @@
+//! Structured command results for embedding
+//!
+//! [`crate::dispatcher::Dispatcher::dispatch`] returns what the command
+//! produced as a [`CommandOutcome`], so an application driving the crate can
+//! use the data without parsing printed output. Commands still print their
+//! usual output; commands that have no structured result yet report
+//! [`CommandOutcome::Done`].
+
+use crate::f1r3fly_api::{DeployResult, NodeStatus};
+use serde::Serialize;
+
+/// What a successful command produced, serialized with an `outcome` tag
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum CommandOutcome {
+    /// Completed, with results only printed
+    Done,
+    /// Ran until the user stopped it (`dag`, `watch-events`, `watch-wallet`)
+    Interactive,
+    /// `status`
+    NodeStatus(NodeStatus),
+    /// `blocks`: the node's block list, or the one block asked for
+    Blocks { blocks: serde_json::Value },
+    /// `bonds`, in the node's order
+    Bonds { bonds: Vec<Bond> },
+    /// `wallet-balance`
+    Balance {
+        address: String,
+        balance: String,
+        /// Height the balance was read at, when known
+        block_number: Option<i64>,
+        cached: bool,
+    },
+    /// `wallet-balance` with several addresses, in the order given
+    Balances { balances: Vec<AddressBalance> },
+    /// `deploy`: accepted by the node, not yet in a block
+    Deploy { deploy_id: String },
+    /// `deploy-and-wait --no-wait-finalization`: in a block, not yet finalized
+    Included {
+        deploy_id: String,
+        block_hash: String,
+    },
+    /// `deploy-and-wait`: finalized in a block
+    DeployReceipt(DeployReceipt),
+    /// `transfer`: finalized in a block
+    Transfer(RevTransferResult),
+    /// `is-finalized`
+    Finalized {
+        block_hash: String,
+        finalized: bool,
+        /// Finalized blocks on top, with `--confirm-depth`
+        confirmations: Option<i64>,
+    },
+}
+
+/// A validator's stake
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Bond {
+    pub validator: String,
+    pub stake: i64,
+    /// Vault address of the validator's key; `None` for a malformed key
+    pub address: Option<String>,
+    /// Vault balance in dust, with `--with-balances`; `None` when not read
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub balance: Option<i64>,
+}
+
+/// One address of a multi-address `wallet-balance`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AddressBalance {
+    pub address: String,
+    /// Balance in dust; `None` when the query for this address failed
+    pub dust: Option<i64>,
+    /// The balance in tokens, exact to eight decimals without trailing zeros
+    pub rev: Option<String>,
+    /// Why the query failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl AddressBalance {
+    pub fn new(address: &str, dust: Result<i64, String>) -> Self {
+        match dust {
+            Ok(dust) => Self {
+                address: address.to_string(),
+                dust: Some(dust),
+                rev: u128::try_from(dust).ok().map(crate::vault::format_rev),
+                error: None,
+            },
+            Err(error) => Self {
+                address: address.to_string(),
+                dust: None,
+                rev: None,
+                error: Some(error),
+            },
+        }
+    }
+}
+
+/// A finalized deploy
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DeployReceipt {
+    pub deploy_id: String,
+    pub block_hash: String,
+    pub block_number: Option<i64>,
+    pub cost: Option<u64>,
+    pub errored: bool,
+    pub system_deploy_error: Option<String>,
+    /// Finalized blocks on top of `block_hash`, with `--confirm-depth`
+    pub confirmations: Option<i64>,
+}
+
+impl From<&DeployResult> for DeployReceipt {
+    fn from(result: &DeployResult) -> Self {
+        Self {
+            deploy_id: result.deploy_id.clone(),
+            block_hash: result.block_hash.clone(),
+            block_number: result.block_number,
+            cost: result.cost,
+            errored: result.errored,
+            system_deploy_error: result.system_deploy_error.clone(),
+            confirmations: None,
+        }
+    }
+}
+
+/// A finalized transfer, printed on its own by `transfer --json`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RevTransferResult {
+    pub deploy_id: String,
+    pub block_hash: String,
+    pub block_number: Option<i64>,
+    pub from_address: String,
+    pub to_address: String,
+    pub amount_dust: u64,
+    pub cost: Option<u64>,
+    /// Whether the block holding the deploy was seen finalized
+    pub finalized: bool,
+    pub errored: bool,
+    pub system_deploy_error: Option<String>,
+    /// Finalized blocks on top of `block_hash`, with `--confirm-depth`
+    pub confirmations: Option<i64>,
+    /// From sending the deploy to the end of the wait, in milliseconds
+    pub elapsed_ms: u64,
+}
+
+impl RevTransferResult {
+    /// The transfer in `receipt`, sent from `from_address` after `elapsed`
+    pub fn new(
+        receipt: &DeployReceipt,
+        from_address: &str,
+        to_address: &str,
+        amount_dust: u64,
+        elapsed: std::time::Duration,
+    ) -> Self {
+        Self {
+            deploy_id: receipt.deploy_id.clone(),
+            block_hash: receipt.block_hash.clone(),
+            block_number: receipt.block_number,
+            from_address: from_address.to_string(),
+            to_address: to_address.to_string(),
+            amount_dust,
+            cost: receipt.cost,
+            // deploy_and_wait only returns once the block is finalized
+            finalized: true,
+            errored: receipt.errored,
+            system_deploy_error: receipt.system_deploy_error.clone(),
+            confirmations: receipt.confirmations,
+            elapsed_ms: elapsed.as_millis() as u64,
+        }
+    }
+}
@@ -1,24 +1,102 @@
 use crate::args::*;
-use crate::f1r3fly_api::F1r3flyApi;
+use crate::balance_cache::{BalanceCache, CachedBalance};
+use crate::block_groups::{self, BlockTime, Granularity, Row};
+use crate::capabilities::{self, Capability, NodeVersion};
+use crate::chain_stats::main_chain;
+use crate::commands::outcome::{AddressBalance, Bond, CommandOutcome};
+use crate::error::{ApiError, NodeCall, NodeCliError};
+use crate::export::ChainSource;
+use crate::f1r3fly_api::{BlockDetail, BlockHeader, F1r3flyApi, SystemDeploy};
+use crate::pinned::PinnedQueryClient;
+use crate::ports;
+use crate::prefix::{self, IdKind};
+use crate::rholang_helpers::convert_rholang_to_json;
+use crate::staking::{
+    build_pos_method_query, parse_active_validators, parse_bonds, validator_address, BondStatus,
+    StakingQueries, INVALID_KEY,
+};
+use crate::truncation::{shorten, Field};
+use crate::utils::{display_address, format_timestamp, now_millis, print_qr};
+use crate::validator_status::{bonded_at, explain, StatusEvidence};
+use futures_util::stream::{self, StreamExt};
 use reqwest;
+use serde::Serialize;
 use serde_json;
-use std::collections::{HashSet, VecDeque};
+use std::collections::HashSet;
+use std::io::IsTerminal;
 use std::time::Instant;
 
-pub async fn status_command(args: &HttpArgs) -> Result<(), Box<dyn std::error::Error>> {
-    println!(" Getting node status from {}:{}", args.host, args.port);
+/// Print `value` as the command's only output, for `--output json`
+fn print_json<T: Serialize>(value: &T) -> Result<(), Box<dyn std::error::Error>> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// An unsuccessful HTTP answer to a query: printed in text mode, an error
+/// with `--output json` so it reaches stderr instead of stdout
+async fn query_failed(
+    output: &OutputArgs,
+    call: &NodeCall,
+    what: &str,
+    response: reqwest::Response,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let status = response.status();
+    let body = response.text().await?;
+    if output.is_json() {
+        return Err(call
+            .fail(NodeCliError::http_status(status.as_u16(), &body))
+            .into());
+    }
+    println!(" Failed to get {}: HTTP {}", what, status);
+    println!("Error: {}", body);
+    Ok(())
+}
+
+/// A query that could not reach the node, printed unless `--output json`
+fn connection_failed(
+    output: &OutputArgs,
+    call: &NodeCall,
+    err: reqwest::Error,
+) -> Box<dyn std::error::Error> {
+    let err = call.fail(err);
+    if !output.is_json() {
+        println!(" Connection failed!");
+        println!("Error: {}", err);
+    }
+    err.into()
+}
+
+/// A reply without the expected data: printed in text mode, an error with `--output json`
+fn missing_data(output: &OutputArgs, message: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if output.is_json() {
+        return Err(NodeCliError::parse_error(message).into());
+    }
+    println!(" {}", message);
+    Ok(())
+}
+
+pub async fn status_command(args: &HttpArgs) -> Result<CommandOutcome, Box<dyn std::error::Error>> {
+    let json = args.output.is_json();
+    if !json {
+        println!(" Getting node status from {}:{}", args.host, args.port);
+    }
 
     let url = format!("http://{}:{}/api/status", args.host, args.port);
     let client = reqwest::Client::new();
 
     let start_time = Instant::now();
 
+    let call = NodeCall::http(&url);
     match client.get(&url).send().await {
         Ok(response) => {
             let duration = start_time.elapsed();
             if response.status().is_success() {
                 let status_text = response.text().await?;
                 let status: crate::f1r3fly_api::NodeStatus = serde_json::from_str(&status_text)?;
+                if json {
+                    print_json(&status)?;
+                    return Ok(CommandOutcome::NodeStatus(status));
+                }
 
                 println!(" Node status retrieved successfully!");
                 println!(" Time taken: {:.2?}", duration);
@@ -53,22 +131,20 @@ pub async fn status_command(args: &HttpArgs) -> Result<(), Box<dyn std::error::E
                     fmt(status.epoch_length)
                 );
                 println!("  Version:       {}", status.version);
+                return Ok(CommandOutcome::NodeStatus(status));
             } else {
-                println!(" Failed to get node status: HTTP {}", response.status());
-                println!("Error: {}", response.text().await?);
+                query_failed(&args.output, &call, "node status", response).await?;
             }
         }
-        Err(e) => {
-            println!(" Connection failed!");
-            println!("Error: {}", e);
-            return Err(e.into());
-        }
+        Err(e) => return Err(connection_failed(&args.output, &call, e)),
     }
 
-    Ok(())
+    Ok(CommandOutcome::Done)
 }
 
-pub async fn blocks_command(args: &BlocksArgs) -> Result<(), Box<dyn std::error::Error>> {
+pub async fn blocks_command(
+    args: &BlocksArgs,
+) -> Result<CommandOutcome, Box<dyn std::error::Error>> {
     let start_time = Instant::now();
     let client = reqwest::Client::new();
 
@@ -79,6 +155,7 @@ pub async fn blocks_command(args: &BlocksArgs) -> Result<(), Box<dyn std::error:
             args.host, args.port, block_hash
         );
 
+        let call = NodeCall::http(&url);
         match client.get(&url).send().await {
             Ok(response) => {
                 let duration = start_time.elapsed();
@@ -90,12 +167,37 @@ pub async fn blocks_command(args: &BlocksArgs) -> Result<(), Box<dyn std::error:
                     println!(" Time taken: {:.2?}", duration);
                     println!(" Block Details:");
                     println!("{}", serde_json::to_string_pretty(&block_json)?);
+                    if let Ok(detail) = BlockDetail::from_json(&block_json) {
+                        let version = capabilities::for_node(&args.host, args.port)
+                            .version()
+                            .await;
+                        print_system_deploys(detail.system_deploys.as_deref(), version);
+                    }
+                    return Ok(CommandOutcome::Blocks { blocks: block_json });
                 } else {
                     println!(" Failed to get block: HTTP {}", response.status());
                     println!("Error: {}", response.text().await?);
+                    if !args.no_hints {
+                        let api = F1r3flyApi::for_queries(
+                            args.query_key.private_key.as_deref(),
+                            &args.host,
+                            args.port,
+                        )?;
+                        for line in prefix::failed_lookup_hints(
+                            &api,
+                            args.port,
+                            IdKind::BlockHash,
+                            block_hash,
+                        )
+                        .await
+                        {
+                            println!("{}", line);
+                        }
+                    }
                 }
             }
             Err(e) => {
+                let e = call.fail(e);
                 println!(" Connection failed!");
                 println!("Error: {}", e);
                 return Err(e.into());
@@ -111,6 +213,7 @@ pub async fn blocks_command(args: &BlocksArgs) -> Result<(), Box<dyn std::error:
             args.host, args.port, args.number
         );
 
+        let call = NodeCall::http(&url);
         match client.get(&url).send().await {
             Ok(response) => {
                 let duration = start_time.elapsed();
@@ -121,13 +224,21 @@ pub async fn blocks_command(args: &BlocksArgs) -> Result<(), Box<dyn std::error:
                     println!(" Blocks retrieved successfully!");
                     println!(" Time taken: {:.2?}", duration);
                     println!(" Recent Blocks:");
-                    println!("{}", serde_json::to_string_pretty(&blocks_json)?);
+                    if args.format == "table" {
+                        print_blocks_table(&blocks_json, args.granularity);
+                    } else {
+                        println!("{}", serde_json::to_string_pretty(&blocks_json)?);
+                    }
+                    return Ok(CommandOutcome::Blocks {
+                        blocks: blocks_json,
+                    });
                 } else {
                     println!(" Failed to get blocks: HTTP {}", response.status());
                     println!("Error: {}", response.text().await?);
                 }
             }
             Err(e) => {
+                let e = call.fail(e);
                 println!(" Connection failed!");
                 println!("Error: {}", e);
                 return Err(e.into());
@@ -135,13 +246,224 @@ pub async fn blocks_command(args: &BlocksArgs) -> Result<(), Box<dyn std::error:
         }
     }
 
-    Ok(())
+    Ok(CommandOutcome::Done)
 }
 
-pub async fn bonds_command(args: &HttpArgs) -> Result<(), Box<dyn std::error::Error>> {
-    println!(" Getting validator bonds from {}:{}", args.host, args.port);
+/// Recent blocks as one row each, newest first, with date separators
+///
+/// Blocks at a height off the main chain are marked `fork` and grouped with
+/// the main-chain block at their height.
+/// The system deploys section of `blocks --block-hash`
+fn print_system_deploys(system_deploys: Option<&[SystemDeploy]>, version: Option<NodeVersion>) {
+    println!(" System Deploys:");
+    let Some(system_deploys) = system_deploys else {
+        match version {
+            Some(version) if !Capability::SystemDeploys.supported_by(&version) => println!(
+                "   (not provided by this node version: {} reports them from {})",
+                version,
+                Capability::SystemDeploys.min_version()
+            ),
+            _ => println!("   (not provided by this node version)"),
+        }
+        return;
+    };
+    if system_deploys.is_empty() {
+        println!("   (none)");
+    }
+    for deploy in system_deploys {
+        if deploy.failed {
+            println!(
+                "   FAILED {:<10} {}",
+                deploy.kind,
+                deploy.error.as_deref().unwrap_or("")
+            );
+        } else {
+            println!("   ok     {}", deploy.kind);
+        }
+    }
+}
 
-    let url = format!("http://{}:{}/api/explore-deploy", args.host, args.port);
+fn print_blocks_table(blocks_json: &serde_json::Value, granularity: Granularity) {
+    let mut headers: Vec<BlockHeader> = blocks_json
+        .as_array()
+        .map(|list| {
+            list.iter()
+                .filter_map(|json| {
+                    let info = json.get("blockInfo").unwrap_or(json);
+                    serde_json::from_value(info.clone()).ok()
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    headers.sort_by(|a, b| (b.block_number, &a.block_hash).cmp(&(a.block_number, &b.block_hash)));
+    let main: HashSet<&str> = main_chain(&headers)
+        .into_iter()
+        .map(|h| h.block_hash.as_str())
+        .collect();
+
+    let partition = block_groups::partition(&headers, granularity, |h| BlockTime {
+        block_number: h.block_number,
+        timestamp: h.timestamp,
+        main_chain: main.contains(h.block_hash.as_str()),
+    });
+    let styled = std::io::stdout().is_terminal();
+    let sender_width = headers
+        .iter()
+        .map(|h| shorten(&h.sender, Field::Key).chars().count())
+        .max()
+        .unwrap_or(0);
+    for row in &partition.rows {
+        match row {
+            Row::Separator(i) => println!(
+                "{}",
+                block_groups::separator(&partition.groups[*i].label, styled)
+            ),
+            Row::Block(h) => println!(
+                "  #{:<8} {}  {:<sender_width$}  {}  {:>3} deploys{}",
+                h.block_number,
+                shorten(&h.block_hash, Field::Hash),
+                shorten(&h.sender, Field::Key),
+                format_timestamp(h.timestamp),
+                h.deploy_count,
+                if main.contains(h.block_hash.as_str()) {
+                    ""
+                } else {
+                    "  fork"
+                }
+            ),
+        }
+    }
+    if !partition.groups.is_empty() {
+        println!();
+        for line in block_groups::summary(&partition.groups, granularity) {
+            println!("{}", line);
+        }
+    }
+}
+
+/// Bonds from a `block.bonds` list, in the node's order, with each key's vault address
+fn bonds_with_addresses(bonds: &[serde_json::Value]) -> Vec<Bond> {
+    bonds
+        .iter()
+        .filter_map(|bond| {
+            let validator = bond.get("validator")?.as_str()?;
+            Some(Bond {
+                validator: validator.to_string(),
+                stake: bond.get("stake")?.as_i64()?,
+                address: validator_address(validator),
+                balance: None,
+            })
+        })
+        .collect()
+}
+
+/// Bonded validators of a `getActiveValidators` explore-deploy response,
+/// split by whether the active set in `expr` contains them
+///
+/// The set holds keys only, so stakes come from the `block.bonds` list of the
+/// same response. Bonded validators outside the set (quarantined, or waiting
+/// to join or leave) end up in `inactive`.
+#[derive(Debug)]
+struct ActiveBonds {
+    active: Vec<Bond>,
+    inactive: Vec<Bond>,
+}
+
+impl ActiveBonds {
+    fn from_response(response: &serde_json::Value) -> crate::error::Result<Self> {
+        let active_keys: HashSet<String> = parse_active_validators(response)?.into_iter().collect();
+        let bonds = response
+            .get("block")
+            .and_then(|b| b.get("bonds"))
+            .and_then(|b| b.as_array())
+            .ok_or_else(|| NodeCliError::parse_error("No bonds data found in response"))?;
+        let (active, inactive): (Vec<Bond>, Vec<Bond>) = bonds_with_addresses(bonds)
+            .into_iter()
+            .partition(|bond| active_keys.contains(&bond.validator.to_ascii_lowercase()));
+        if active.len() < active_keys.len() {
+            tracing::warn!(
+                "{} active validator(s) have no bond in the queried block and are not listed",
+                active_keys.len() - active.len()
+            );
+        }
+        Ok(Self { active, inactive })
+    }
+}
+
+/// Read the vault balance of each bond's address with `--with-balances`
+async fn add_balances(args: &ValidatorListArgs, bonds: &mut [Bond]) {
+    if !args.with_balances {
+        return;
+    }
+    let addresses: Vec<String> = bonds.iter().filter_map(|b| b.address.clone()).collect();
+    let queries = StakingQueries::new(&args.http.host, args.http.port);
+    let balances = queries.vault_balances(&addresses, args.concurrency).await;
+    for bond in bonds {
+        bond.balance = bond
+            .address
+            .as_ref()
+            .and_then(|address| balances.get(address).copied());
+    }
+}
+
+/// One line per validator, with its vault address and balance when asked for
+fn bond_line(
+    position: usize,
+    name: impl std::fmt::Display,
+    bond: &Bond,
+    args: &ValidatorListArgs,
+) -> String {
+    let mut details = format!("stake: {}", bond.stake);
+    if args.show_addresses || args.with_balances {
+        let address = bond
+            .address
+            .as_deref()
+            .map_or(INVALID_KEY.to_string(), display_address);
+        details.push_str(&format!(", address: {}", address));
+    }
+    if args.with_balances && bond.address.is_some() {
+        let balance = bond
+            .balance
+            .map_or("unavailable".to_string(), |b| b.to_string());
+        details.push_str(&format!(", balance: {}", balance));
+    }
+    format!(" {}. {} ({})", position, name, details)
+}
+
+/// `bonds` and `active-validators` with `--output json`
+#[derive(Serialize)]
+struct ValidatorList<'a> {
+    validator_count: usize,
+    total_stake: i64,
+    bonds: &'a [Bond],
+}
+
+impl<'a> ValidatorList<'a> {
+    fn new(bonds: &'a [Bond]) -> Self {
+        Self {
+            validator_count: bonds.len(),
+            total_stake: bonds.iter().map(|b| b.stake).sum(),
+            bonds,
+        }
+    }
+}
+
+pub async fn bonds_command(
+    args: &ValidatorListArgs,
+) -> Result<CommandOutcome, Box<dyn std::error::Error>> {
+    let identities = args.http.identity.load()?;
+    let output = &args.http.output;
+    if !output.is_json() {
+        println!(
+            " Getting validator bonds from {}:{}",
+            args.http.host, args.http.port
+        );
+    }
+
+    let url = format!(
+        "http://{}:{}/api/explore-deploy",
+        args.http.host, args.http.port
+    );
     let client = reqwest::Client::new();
 
     let rholang_query = r#"new return, rl(`rho:registry:lookup`), poSCh in { rl!(`rho:system:pos`, *poSCh) | for(@(_, PoS) <- poSCh) { @PoS!("getBonds", *return) } }"#;
@@ -152,6 +474,7 @@ pub async fn bonds_command(args: &HttpArgs) -> Result<(), Box<dyn std::error::Er
 
     let start_time = Instant::now();
 
+    let call = NodeCall::http(&url);
     match client
         .post(&url)
         .header("Content-Type", "application/json")
@@ -165,76 +488,68 @@ pub async fn bonds_command(args: &HttpArgs) -> Result<(), Box<dyn std::error::Er
                 let bonds_text = response.text().await?;
                 let bonds_json: serde_json::Value = serde_json::from_str(&bonds_text)?;
 
-                println!(" Validator bonds retrieved successfully!");
-                println!(" Time taken: {:.2?}", duration);
-                println!();
-
                 // Parse and display bonds data in a clean format
                 if let Some(block) = bonds_json.get("block") {
                     if let Some(bonds) = block.get("bonds") {
                         if let Some(bonds_array) = bonds.as_array() {
-                            let validator_count = bonds_array.len();
-                            let total_stake: i64 = bonds_array
-                                .iter()
-                                .filter_map(|bond| bond.get("stake")?.as_i64())
-                                .sum();
+                            let mut bonds = bonds_with_addresses(bonds_array);
+                            add_balances(args, &mut bonds).await;
+                            if output.is_json() {
+                                print_json(&ValidatorList::new(&bonds))?;
+                                return Ok(CommandOutcome::Bonds { bonds });
+                            }
 
+                            println!(" Validator bonds retrieved successfully!");
+                            println!(" Time taken: {:.2?}", duration);
+                            println!();
+                            let list = ValidatorList::new(&bonds);
                             println!(
                                 " Bonded Validators ({} total, {} total stake):",
-                                validator_count, total_stake
+                                list.validator_count, list.total_stake
                             );
                             println!();
 
-                            for (i, bond) in bonds_array.iter().enumerate() {
-                                if let (Some(validator), Some(stake)) = (
-                                    bond.get("validator").and_then(|v| v.as_str()),
-                                    bond.get("stake").and_then(|s| s.as_i64()),
-                                ) {
-                                    // Truncate long validator keys for readability
-                                    let truncated_key = if validator.len() > 16 {
-                                        format!(
-                                            "{}...{}",
-                                            &validator[..8],
-                                            &validator[validator.len() - 8..]
-                                        )
-                                    } else {
-                                        validator.to_string()
-                                    };
-
-                                    println!(" {}. {} (stake: {})", i + 1, truncated_key, stake);
-                                }
+                            for (i, bond) in bonds.iter().enumerate() {
+                                // Name (if known) with the truncated key for readability
+                                let name = identities.resolve_validator(&bond.validator);
+                                println!("{}", bond_line(i + 1, name, bond, args));
                             }
+                            return Ok(CommandOutcome::Bonds { bonds });
                         } else {
-                            println!(" Invalid bonds format in response");
+                            missing_data(output, "Invalid bonds format in response")?;
                         }
                     } else {
-                        println!(" No bonds data found in response");
+                        missing_data(output, "No bonds data found in response")?;
                     }
                 } else {
-                    println!(" No block data found in response");
+                    missing_data(output, "No block data found in response")?;
                 }
             } else {
-                println!(" Failed to get bonds: HTTP {}", response.status());
-                println!("Error: {}", response.text().await?);
+                query_failed(output, &call, "bonds", response).await?;
             }
         }
-        Err(e) => {
-            println!(" Connection failed!");
-            println!("Error: {}", e);
-            return Err(e.into());
-        }
+        Err(e) => return Err(connection_failed(output, &call, e)),
     }
 
-    Ok(())
+    Ok(CommandOutcome::Done)
 }
 
-pub async fn active_validators_command(args: &HttpArgs) -> Result<(), Box<dyn std::error::Error>> {
-    println!(
-        " Getting active validators from {}:{}",
-        args.host, args.port
-    );
+pub async fn active_validators_command(
+    args: &ValidatorListArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let identities = args.http.identity.load()?;
+    let output = &args.http.output;
+    if !output.is_json() {
+        println!(
+            " Getting active validators from {}:{}",
+            args.http.host, args.http.port
+        );
+    }
 
-    let url = format!("http://{}:{}/api/explore-deploy", args.host, args.port);
+    let url = format!(
+        "http://{}:{}/api/explore-deploy",
+        args.http.host, args.http.port
+    );
     let client = reqwest::Client::new();
 
     let rholang_query = r#"new return, rl(`rho:registry:lookup`), poSCh in { rl!(`rho:system:pos`, *poSCh) | for(@(_, PoS) <- poSCh) { @PoS!("getActiveValidators", *return) } }"#;
@@ -245,6 +560,7 @@ pub async fn active_validators_command(args: &HttpArgs) -> Result<(), Box<dyn st
 
     let start_time = Instant::now();
 
+    let call = NodeCall::http(&url);
     match client
         .post(&url)
         .header("Content-Type", "application/json")
@@ -258,85 +574,143 @@ pub async fn active_validators_command(args: &HttpArgs) -> Result<(), Box<dyn st
                 let validators_text = response.text().await?;
                 let validators_json: serde_json::Value = serde_json::from_str(&validators_text)?;
 
+                let ActiveBonds {
+                    active: mut bonds,
+                    inactive,
+                } = match ActiveBonds::from_response(&validators_json) {
+                    Ok(split) => split,
+                    Err(e) if output.is_json() => return Err(e.into()),
+                    Err(e) => {
+                        println!(" {}", e);
+                        return Ok(());
+                    }
+                };
+                add_balances(args, &mut bonds).await;
+                if output.is_json() {
+                    return print_json(&ValidatorList::new(&bonds));
+                }
+
                 println!(" Active validators retrieved successfully!");
                 println!(" Time taken: {:.2?}", duration);
                 println!();
+                let list = ValidatorList::new(&bonds);
+                println!(
+                    " Active Validators ({} total, {} total stake):",
+                    list.validator_count, list.total_stake
+                );
+                println!();
 
-                // Parse and display validator data in a clean format
-                if let Some(block) = validators_json.get("block") {
-                    if let Some(bonds) = block.get("bonds") {
-                        if let Some(bonds_array) = bonds.as_array() {
-                            let validator_count = bonds_array.len();
-                            let total_stake: i64 = bonds_array
-                                .iter()
-                                .filter_map(|bond| bond.get("stake")?.as_i64())
-                                .sum();
-
-                            println!(
-                                " Active Validators ({} total, {} total stake):",
-                                validator_count, total_stake
-                            );
-                            println!();
-
-                            for (i, bond) in bonds_array.iter().enumerate() {
-                                if let (Some(validator), Some(stake)) = (
-                                    bond.get("validator").and_then(|v| v.as_str()),
-                                    bond.get("stake").and_then(|s| s.as_i64()),
-                                ) {
-                                    // Truncate long validator keys for readability
-                                    let truncated_key = if validator.len() > 16 {
-                                        format!(
-                                            "{}...{}",
-                                            &validator[..8],
-                                            &validator[validator.len() - 8..]
-                                        )
-                                    } else {
-                                        validator.to_string()
-                                    };
-
-                                    println!(" {}. {} (stake: {})", i + 1, truncated_key, stake);
-                                }
-                            }
-                        } else {
-                            println!(" Invalid bonds format in response");
-                        }
-                    } else {
-                        println!(" No bonds data found in response");
+                for (i, bond) in bonds.iter().enumerate() {
+                    // Name (if known) with the truncated key for readability
+                    let name = identities.resolve_validator(&bond.validator);
+                    println!("{}", bond_line(i + 1, name, bond, args));
+                }
+                if !inactive.is_empty() {
+                    println!();
+                    println!(
+                        " {} bonded validator(s) not in the active set:",
+                        inactive.len()
+                    );
+                    for bond in &inactive {
+                        let name = identities.resolve_validator(&bond.validator);
+                        println!("  - {} (stake: {})", name, bond.stake);
                     }
-                } else {
-                    println!(" No block data found in response");
                 }
             } else {
-                println!(
-                    " Failed to get active validators: HTTP {}",
-                    response.status()
-                );
-                println!("Error: {}", response.text().await?);
+                query_failed(output, &call, "active validators", response).await?;
             }
         }
-        Err(e) => {
-            println!(" Connection failed!");
-            println!("Error: {}", e);
-            return Err(e.into());
-        }
+        Err(e) => return Err(connection_failed(output, &call, e)),
     }
 
     Ok(())
 }
 
+/// `wallet-balance` with `--output json`
+#[derive(Serialize)]
+struct BalanceJson<'a> {
+    address: &'a str,
+    /// In dust
+    balance: i64,
+    block_number: Option<i64>,
+    cached: bool,
+}
+
+impl<'a> BalanceJson<'a> {
+    /// Anything but a number as the balance (such as the vault's error
+    /// message for a bad address) is an error
+    fn new(
+        address: &'a str,
+        balance: &str,
+        block_number: Option<i64>,
+        cached: bool,
+    ) -> crate::error::Result<Self> {
+        let balance = parse_balance(balance)?;
+        Ok(Self {
+            address,
+            balance,
+            block_number,
+            cached,
+        })
+    }
+}
+
+/// The balance in a vault's answer to [`balance_query`]; anything but a
+/// number (such as the vault's error message for a bad address) is an error
+fn parse_balance(result: &str) -> crate::error::Result<i64> {
+    result.trim().parse::<i64>().map_err(|_| {
+        NodeCliError::parse_error(&format!("vault did not return a balance: {}", result))
+    })
+}
+
+/// Addresses given with `--address` followed by those in `--addresses-file`
+fn requested_addresses(args: &WalletBalanceArgs) -> crate::error::Result<Vec<String>> {
+    let mut addresses = args.addresses.clone();
+    if let Some(path) = &args.addresses_file {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            NodeCliError::file_read_failed(&path.display().to_string(), &e.to_string())
+        })?;
+        addresses.extend(parse_address_list(&content));
+    }
+    if addresses.is_empty() {
+        return Err(NodeCliError::General(
+            "No wallet addresses given; pass --address or --addresses-file".to_string(),
+        ));
+    }
+    Ok(addresses)
+}
+
+/// Addresses in an `--addresses-file`, one per line, skipping blank lines and `#` comments
+fn parse_address_list(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
 pub async fn wallet_balance_command(
     args: &WalletBalanceArgs,
-) -> Result<(), Box<dyn std::error::Error>> {
-    println!(" Checking wallet balance for address: {}", args.address);
+) -> Result<CommandOutcome, Box<dyn std::error::Error>> {
+    let addresses = requested_addresses(args)?;
+    if lists_addresses(args) {
+        wallet_balances(args, &addresses).await
+    } else {
+        single_wallet_balance(args, &addresses[0]).await
+    }
+}
 
-    // Use F1r3fly API with gRPC (like exploratory-deploy)
-    let f1r3fly_api = F1r3flyApi::new(
-        "5f668a7ee96d944a4494cc947e4005e172d7ab3461ee5538f1f2a45a835e9657", // Bootstrap private key
-        &args.host,
-        args.port,
-    )?;
+/// Whether the addresses came as a list, which always gets the table (and the
+/// JSON array), even when the list holds a single address
+fn lists_addresses(args: &WalletBalanceArgs) -> bool {
+    args.addresses.len() > 1 || args.addresses_file.is_some()
+}
 
-    let rholang_query = format!(
+/// Exploratory deploy returning the balance of the vault at `address`, or the
+/// vault's error message
+fn balance_query(address: &str) -> String {
+    format!(
         r#"new return, rl(`rho:registry:lookup`), systemVaultCh, vaultCh, balanceCh in {{
  rl!(`rho:vault:system`, *systemVaultCh) |
  for (@(_, SystemVault) <- systemVaultCh) {{
@@ -356,8 +730,84 @@ pub async fn wallet_balance_command(
  }}
  }}
  }}"#,
-        args.address
-    );
+        address
+    )
+}
+
+async fn single_wallet_balance(
+    args: &WalletBalanceArgs,
+    address: &str,
+) -> Result<CommandOutcome, Box<dyn std::error::Error>> {
+    let json = args.output.is_json();
+    if !json {
+        println!(
+            " Checking wallet balance for address: {}",
+            display_address(address)
+        );
+        if args.qr {
+            print_qr(address);
+        }
+    }
+
+    // Use F1r3fly API with gRPC (like exploratory-deploy)
+    let f1r3fly_api =
+        F1r3flyApi::for_queries(args.query_key.private_key.as_deref(), &args.host, args.port)?;
+    let rholang_query = balance_query(address);
+
+    let cache = if args.cache {
+        let cache = BalanceCache::open_default();
+        if cache.is_none() && !json {
+            println!(" No config directory found, balance cache disabled");
+        }
+        cache
+    } else {
+        None
+    };
+    let cache_key = BalanceCache::key(&args.host, args.port, address);
+    let mut tip = None;
+
+    if let Some(cache) = cache.as_ref().filter(|_| !args.refresh) {
+        match cache.get(&cache_key) {
+            Ok(Some(cached)) => {
+                let now = now_millis();
+                // Only ask for the tip once the entry is older than --max-age
+                if !cached.within_max_age(now, args.max_age) {
+                    tip = f1r3fly_api.get_current_block_number().await.ok();
+                }
+                if cached.is_usable(now, args.max_age, tip) {
+                    if json {
+                        print_json(&BalanceJson::new(
+                            address,
+                            &cached.balance,
+                            Some(cached.block_number),
+                            true,
+                        )?)?;
+                    } else {
+                        println!(
+                            "Balance for {}: {} (cached @ block {})",
+                            display_address(address),
+                            cached.balance,
+                            cached.block_number
+                        );
+                    }
+                    return Ok(CommandOutcome::Balance {
+                        address: address.to_string(),
+                        balance: cached.balance,
+                        block_number: Some(cached.block_number),
+                        cached: true,
+                    });
+                }
+            }
+            Ok(None) => {}
+            Err(e) if json => tracing::warn!("Balance cache unavailable: {}", e),
+            Err(e) => println!(" Balance cache unavailable: {}", e),
+        }
+    }
+
+    // Record the height before querying so a cached entry never claims a newer block
+    if cache.is_some() && tip.is_none() {
+        tip = f1r3fly_api.get_current_block_number().await.ok();
+    }
 
     let start_time = Instant::now();
 
@@ -367,115 +817,213 @@ pub async fn wallet_balance_command(
     {
         Ok((result, block_info, _cost)) => {
             let duration = start_time.elapsed();
-            println!("Wallet balance retrieved successfully!");
-            println!("Time taken: {:.2?}", duration);
-            println!("Balance for {}: {}", args.address, result);
-            println!("{}", block_info);
+            if !json {
+                println!("Wallet balance retrieved successfully!");
+                println!("Time taken: {:.2?}", duration);
+                println!("Balance for {}: {}", display_address(address), result);
+                println!("{}", block_info);
+            }
+
+            if let (Some(cache), Some(block_number)) = (&cache, tip) {
+                let entry = CachedBalance::new(result.clone(), block_number, now_millis());
+                if let Err(e) = cache.put(&cache_key, entry) {
+                    if json {
+                        tracing::warn!("Failed to update balance cache: {}", e);
+                    } else {
+                        println!(" Failed to update balance cache: {}", e);
+                    }
+                }
+            }
+            if json {
+                print_json(&BalanceJson::new(address, &result, tip, false)?)?;
+            }
+            Ok(CommandOutcome::Balance {
+                address: address.to_string(),
+                balance: result,
+                block_number: tip,
+                cached: false,
+            })
         }
         Err(e) => {
-            println!(" Failed to get wallet balance!");
-            println!("Error: {}", e);
-            return Err(e.into());
+            if !json {
+                println!(" Failed to get wallet balance!");
+                println!("Error: {}", e);
+            }
+            Err(e.into())
         }
     }
-
-    Ok(())
 }
 
-pub async fn bond_status_command(args: &BondStatusArgs) -> Result<(), Box<dyn std::error::Error>> {
-    println!(" Checking bond status for public key: {}", args.public_key);
-
-    let url = format!("http://{}:{}/api/explore-deploy", args.host, args.port);
-    let client = reqwest::Client::new();
-
-    // Get all bonds first, then check if our public key is in there
-    let rholang_query = r#"new return, rl(`rho:registry:lookup`), poSCh in { rl!(`rho:system:pos`, *poSCh) | for(@(_, PoS) <- poSCh) { @PoS!("getBonds", *return) } }"#;
-
-    let body = serde_json::json!({
-    "term": rholang_query
-    });
+/// `wallet-balance` with several addresses: queried `--concurrency` at a
+/// time, each failure reported in its own row rather than ending the command
+async fn wallet_balances(
+    args: &WalletBalanceArgs,
+    addresses: &[String],
+) -> Result<CommandOutcome, Box<dyn std::error::Error>> {
+    let json = args.output.is_json();
+    if args.cache || args.qr {
+        tracing::warn!("--cache and --qr apply to a single address and are ignored");
+    }
+    if !json {
+        println!(
+            " Checking wallet balances for {} addresses",
+            addresses.len()
+        );
+    }
 
+    let f1r3fly_api =
+        F1r3flyApi::for_queries(args.query_key.private_key.as_deref(), &args.host, args.port)?;
     let start_time = Instant::now();
 
-    match client
-        .post(&url)
-        .header("Content-Type", "application/json")
-        .json(&body)
-        .send()
-        .await
-    {
-        Ok(response) => {
-            let duration = start_time.elapsed();
-            if response.status().is_success() {
-                let bonds_text = response.text().await?;
-                let bonds_json: serde_json::Value = serde_json::from_str(&bonds_text)?;
-
-                println!(" Bond information retrieved successfully!");
-                println!(" Time taken: {:.2?}", duration);
+    let api = &f1r3fly_api;
+    let mut results: Vec<(usize, AddressBalance)> = stream::iter(addresses.iter().enumerate())
+        .map(|(position, address)| async move {
+            let dust = match api
+                .exploratory_deploy(&balance_query(address), None, false)
+                .await
+            {
+                Ok((result, _, _)) => parse_balance(&result)
+                    .map_err(|_| format!("vault did not return a balance: {}", result.trim())),
+                Err(e) => Err(e.to_string()),
+            };
+            (position, AddressBalance::new(address, dust))
+        })
+        .buffer_unordered(args.concurrency.max(1))
+        .collect()
+        .await;
+    results.sort_by_key(|(position, _)| *position);
+    let balances: Vec<AddressBalance> = results.into_iter().map(|(_, b)| b).collect();
+
+    if json {
+        print_json(&balances)?;
+    } else {
+        println!(" Time taken: {:.2?}", start_time.elapsed());
+        println!();
+        print!("{}", render_balances(&balances));
+    }
+    Ok(CommandOutcome::Balances { balances })
+}
 
-                // Check if the public key exists in the bonds
-                let is_bonded = check_if_key_is_bonded(&bonds_json, &args.public_key);
+/// Address, dust and token columns with a total over the addresses that answered
+fn render_balances(balances: &[AddressBalance]) -> String {
+    let addresses: Vec<String> = balances
+        .iter()
+        .map(|b| display_address(&b.address))
+        .collect();
+    let found: Vec<i64> = balances.iter().filter_map(|b| b.dust).collect();
+    // Summed as u128 so many large balances cannot overflow
+    let total: u128 = found
+        .iter()
+        .map(|&dust| u128::try_from(dust).unwrap_or_default())
+        .sum();
+    let total_label = if found.len() == balances.len() {
+        "TOTAL".to_string()
+    } else {
+        format!("TOTAL ({} of {})", found.len(), balances.len())
+    };
+    let address_width = addresses
+        .iter()
+        .map(|a| a.chars().count())
+        .chain([total_label.len(), "ADDRESS".len()])
+        .max()
+        .unwrap_or_default();
+    let dust_width = found
+        .iter()
+        .map(|d| d.to_string().len())
+        .chain([total.to_string().len()])
+        .max()
+        .unwrap_or_default()
+        .max("DUST".len());
+
+    let mut out = format!(
+        "{:<address_width$}  {:>dust_width$}  REV\n",
+        "ADDRESS", "DUST"
+    );
+    for (address, balance) in addresses.iter().zip(balances) {
+        let line = match (&balance.dust, &balance.rev, &balance.error) {
+            (Some(dust), Some(rev), _) => {
+                format!(
+                    "{:<address_width$}  {:>dust_width$}  {}",
+                    address, dust, rev
+                )
+            }
+            (_, _, error) => format!(
+                "{:<address_width$}  {:>dust_width$}  error: {}",
+                address,
+                "-",
+                error.as_deref().unwrap_or("no balance")
+            ),
+        };
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out.push_str(&format!(
+        "{:<address_width$}  {:>dust_width$}  {}\n",
+        total_label,
+        total,
+        crate::vault::format_rev(total)
+    ));
+    out
+}
 
-                if is_bonded {
-                    println!(" Validator is BONDED");
-                    println!(" Public key: {}", args.public_key);
-                } else {
-                    println!(" Validator is NOT BONDED");
-                    println!(" Public key: {}", args.public_key);
-                }
+pub async fn bond_status_command(args: &BondStatusArgs) -> Result<(), Box<dyn std::error::Error>> {
+    if !args.output.is_json() {
+        println!(" Checking bond status for public key: {}", args.public_key);
+    }
 
-                println!("\n Full bonds data:");
-                println!("{}", serde_json::to_string_pretty(&bonds_json)?);
-            } else {
-                println!(" Failed to get bond status: HTTP {}", response.status());
-                println!("Error: {}", response.text().await?);
-            }
-        }
-        Err(e) => {
-            println!(" Connection failed!");
-            println!("Error: {}", e);
-            return Err(e.into());
-        }
+    let queries = StakingQueries::new(&args.host, args.port);
+    let start_time = Instant::now();
+    let (bonds_response, active) = tokio::join!(
+        queries.bonds_response(None),
+        queries.active_validators(None)
+    );
+    let bonds_response = bonds_response?;
+    let bonds = parse_bonds(&bonds_response)?;
+    let active = active
+        .inspect_err(|e| tracing::warn!("Active validator query failed: {}", e))
+        .ok();
+    let status = BondStatus::new(&args.public_key, &bonds, active.as_deref());
+
+    if args.output.is_json() {
+        println!("{}", serde_json::to_string_pretty(&status)?);
+        return Ok(());
     }
 
-    Ok(())
-}
+    println!(" Bond information retrieved successfully!");
+    println!(" Time taken: {:.2?}", start_time.elapsed());
+    println!();
+    println!("{}", status.render());
 
-fn check_if_key_is_bonded(bonds_json: &serde_json::Value, target_public_key: &str) -> bool {
-    // Navigate through the JSON structure to find bonds
-    // The structure is: block.bonds[].validator
-    if let Some(block) = bonds_json.get("block") {
-        if let Some(bonds_array) = block.get("bonds") {
-            if let Some(bonds) = bonds_array.as_array() {
-                // Check each bond entry
-                for bond in bonds {
-                    if let Some(validator) = bond.get("validator") {
-                        if let Some(validator_key) = validator.as_str() {
-                            if validator_key == target_public_key {
-                                return true;
-                            }
-                        }
-                    }
-                }
-            }
-        }
+    if args.raw {
+        println!("\n Full bonds data:");
+        println!("{}", serde_json::to_string_pretty(&bonds_response)?);
     }
-    false
+
+    Ok(())
 }
 
 pub async fn metrics_command(args: &HttpArgs) -> Result<(), Box<dyn std::error::Error>> {
-    println!(" Getting node metrics from {}:{}", args.host, args.port);
+    let json = args.output.is_json();
+    if !json {
+        println!(" Getting node metrics from {}:{}", args.host, args.port);
+    }
 
     let url = format!("http://{}:{}/metrics", args.host, args.port);
     let client = reqwest::Client::new();
 
     let start_time = Instant::now();
 
+    let call = NodeCall::http(&url);
     match client.get(&url).send().await {
         Ok(response) => {
             let duration = start_time.elapsed();
             if response.status().is_success() {
                 let metrics_text = response.text().await?;
+                if json {
+                    return print_json(&serde_json::json!({
+                        "metrics": parse_metrics(&metrics_text)
+                    }));
+                }
 
                 println!(" Node metrics retrieved successfully!");
                 println!(" Time taken: {:.2?}", duration);
@@ -504,20 +1052,30 @@ pub async fn metrics_command(args: &HttpArgs) -> Result<(), Box<dyn std::error::
                     println!("\n Use --verbose flag (if implemented) to see all metrics");
                 }
             } else {
-                println!(" Failed to get metrics: HTTP {}", response.status());
-                println!("Error: {}", response.text().await?);
+                query_failed(&args.output, &call, "metrics", response).await?;
             }
         }
-        Err(e) => {
-            println!(" Connection failed!");
-            println!("Error: {}", e);
-            return Err(e.into());
-        }
+        Err(e) => return Err(connection_failed(&args.output, &call, e)),
     }
 
     Ok(())
 }
 
+/// Samples of a Prometheus text exposition, keyed by name and labels as written
+///
+/// Comments and lines whose value is not a number are skipped.
+fn parse_metrics(text: &str) -> serde_json::Map<String, serde_json::Value> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let (sample, value) = line.rsplit_once(' ')?;
+            let value = serde_json::Number::from_f64(value.parse().ok()?)?;
+            Some((sample.trim().to_string(), serde_json::Value::Number(value)))
+        })
+        .collect()
+}
+
 // Helper struct for discovered peers
 #[derive(Debug, Clone)]
 struct DiscoveredPeer {
@@ -565,6 +1123,7 @@ async fn query_node_status(
         println!(" URL: {}", url);
     }
 
+    let call = NodeCall::http(&url);
     match client.get(&url).send().await {
         Ok(response) => {
             let status_code = response.status();
@@ -604,7 +1163,7 @@ async fn query_node_status(
             if debug {
                 println!(" [DEBUG] Error: {}", e);
             }
-            Err("Connection failed".to_string())
+            Err(call.fail(e).to_string())
         }
     }
 }
@@ -626,6 +1185,31 @@ fn extract_peers(status_json: &serde_json::Value) -> Vec<DiscoveredPeer> {
     peers
 }
 
+/// `/status` of each of `nodes`, queried `concurrency` at a time, in the order of `nodes`
+///
+/// A node that does not answer within the client's timeout counts as down
+/// without holding up the others.
+async fn query_statuses(
+    client: &reqwest::Client,
+    nodes: &[(String, u16)],
+    concurrency: usize,
+    debug: bool,
+) -> Vec<Result<serde_json::Value, String>> {
+    let mut statuses: Vec<(usize, Result<serde_json::Value, String>)> =
+        stream::iter(nodes.iter().enumerate())
+            .map(|(position, (host, port))| async move {
+                let status = query_node_status(client, host, *port, debug)
+                    .await
+                    .map(|(json, _raw)| json);
+                (position, status)
+            })
+            .buffer_unordered(concurrency.max(1))
+            .collect()
+            .await;
+    statuses.sort_by_key(|(position, _)| *position);
+    statuses.into_iter().map(|(_, status)| status).collect()
+}
+
 // Display peer details in a formatted way
 fn display_peer_info(peer: &DiscoveredPeer, indent: &str) {
     println!("{} Address: {}", indent, peer.address);
@@ -636,28 +1220,145 @@ fn display_peer_info(peer: &DiscoveredPeer, indent: &str) {
     println!("{} Status: {}", indent, peer.connection_status);
 }
 
+/// One node of a `network-health` report
+#[derive(Debug, Serialize)]
+struct NodeHealth {
+    host: String,
+    port: u16,
+    healthy: bool,
+    /// Peers the node reports, when it answered
+    peer_count: Option<u64>,
+    /// The node's version string, when it answered with one
+    version: Option<String>,
+    /// Why the node counts as unhealthy
+    error: Option<String>,
+}
+
+impl NodeHealth {
+    fn new(host: &str, port: u16, status: &Result<serde_json::Value, String>) -> Self {
+        match status {
+            Ok(status_json) => Self {
+                host: host.to_string(),
+                port,
+                healthy: true,
+                peer_count: status_json
+                    .get("peers")
+                    .and_then(|p| p.as_u64())
+                    .or_else(|| {
+                        status_json
+                            .get("peerList")
+                            .and_then(|p| p.as_array())
+                            .map(|peers| peers.len() as u64)
+                    }),
+                version: match status_json.get("version") {
+                    Some(serde_json::Value::String(version)) => Some(version.clone()),
+                    Some(version) => version
+                        .get("node")
+                        .and_then(|v| v.as_str())
+                        .map(str::to_string),
+                    None => None,
+                },
+                error: None,
+            },
+            Err(e) => Self {
+                host: host.to_string(),
+                port,
+                healthy: false,
+                peer_count: None,
+                version: None,
+                error: Some(e.clone()),
+            },
+        }
+    }
+}
+
+/// A node and a peer it reported, as `host:port`
+#[derive(Debug, Serialize)]
+struct PeerEdge {
+    from: String,
+    to: String,
+}
+
+/// `network-health --output json`
+#[derive(Debug, Serialize)]
+struct HealthReport {
+    nodes: Vec<NodeHealth>,
+    summary: HealthSummary,
+    /// With `--recursive`, every peer each healthy node reported
+    #[serde(skip_serializing_if = "Option::is_none")]
+    peer_graph: Option<Vec<PeerEdge>>,
+}
+
+#[derive(Debug, Serialize)]
+struct HealthSummary {
+    total: usize,
+    healthy: usize,
+    unhealthy: usize,
+    healthy_pct: f64,
+    /// With `--recursive`, peers added to the search
+    #[serde(skip_serializing_if = "Option::is_none")]
+    discovered_peers: Option<usize>,
+}
+
+impl HealthSummary {
+    fn new(nodes: &[NodeHealth], discovered_peers: Option<usize>) -> Self {
+        let healthy = nodes.iter().filter(|n| n.healthy).count();
+        Self {
+            total: nodes.len(),
+            healthy,
+            unhealthy: nodes.len() - healthy,
+            healthy_pct: if nodes.is_empty() {
+                0.0
+            } else {
+                healthy as f64 * 100.0 / nodes.len() as f64
+            },
+            discovered_peers,
+        }
+    }
+
+    /// Why the shard falls short of `--min-healthy` or `--min-healthy-pct`, if it does
+    fn shortfall(&self, min_healthy: Option<usize>, min_healthy_pct: Option<u8>) -> Option<String> {
+        if let Some(min) = min_healthy.filter(|min| self.healthy < *min) {
+            return Some(format!(
+                "Only {} of {} node(s) are healthy, fewer than --min-healthy {}",
+                self.healthy, self.total, min
+            ));
+        }
+        if let Some(pct) = min_healthy_pct.filter(|pct| self.healthy_pct < f64::from(*pct)) {
+            return Some(format!(
+                "Only {:.1}% of nodes are healthy ({} of {}), below --min-healthy-pct {}",
+                self.healthy_pct, self.healthy, self.total, pct
+            ));
+        }
+        None
+    }
+}
+
 pub async fn network_health_command(
     args: &NetworkHealthArgs,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    let text = !args.output.is_json();
+
     // Validate host and ports combination early
     if let Err(e) = validate_host_and_ports(&args.host, &args.custom_ports) {
-        println!(" {}", e);
+        if text {
+            println!(" {}", e);
+        }
         return Err(e.into());
     }
 
-    println!(" Checking F1r3fly network health");
+    if text {
+        println!(" Checking F1r3fly network health");
+    }
 
     let mut ports_to_check = Vec::new();
 
     if args.standard_ports {
-        // Standard F1r3fly shard ports from the docker configuration
-        ports_to_check.extend_from_slice(&[
-            (40403, "Bootstrap"),
-            (40413, "Validator1"),
-            (40423, "Validator2"),
-            (40433, "Validator3"),
-            (40453, "Observer"),
-        ]);
+        ports_to_check.extend(
+            ports::STANDARD_SHARD
+                .iter()
+                .map(|node| (node.http, node.role.name())),
+        );
     }
 
     // Add custom ports if specified
@@ -670,56 +1371,76 @@ pub async fn network_health_command(
     }
 
     if ports_to_check.is_empty() {
-        println!(" No ports specified to check");
+        if text {
+            println!(" No ports specified to check");
+        } else {
+            print_json(&HealthReport {
+                nodes: Vec::new(),
+                summary: HealthSummary::new(&[], None),
+                peer_graph: None,
+            })?;
+        }
         return Ok(());
     }
 
-    let client = reqwest::Client::new();
+    let client = reqwest::Client::builder().timeout(args.timeout).build()?;
     let mut healthy_nodes = 0;
     let mut total_nodes = 0;
     let mut all_peer_lists: Vec<Vec<DiscoveredPeer>> = Vec::new();
     let mut node_status_map: Vec<(String, bool, serde_json::Value)> = Vec::new();
+    let mut report_nodes = Vec::new();
+    let mut peer_graph = None;
+    let mut discovered_count = None;
 
     if args.recursive {
         // Recursive peer discovery mode
-        println!(
-            " Starting recursive peer discovery (max peers: {})\n",
-            if args.max_peers <= 0 {
-                "unlimited".to_string()
-            } else {
-                args.max_peers.to_string()
-            }
-        );
+        if text {
+            println!(
+                " Starting recursive peer discovery (max peers: {})\n",
+                if args.max_peers <= 0 {
+                    "unlimited".to_string()
+                } else {
+                    args.max_peers.to_string()
+                }
+            );
+        }
 
         let mut visited = HashSet::new();
-        let mut queue: VecDeque<(String, u16)> = VecDeque::new();
         let mut discovered_peers = Vec::new();
-
-        // Initialize queue with specified ports
-        for (port, _) in &ports_to_check {
-            let uri_key = format!("{}:{}", args.host, port);
-            queue.push_back((args.host.clone(), *port));
-            visited.insert(uri_key);
-        }
-
-        // Process discovery queue
-        while !queue.is_empty() {
+        let mut edges = Vec::new();
+
+        // Breadth first: the nodes of one level are queried together, and the
+        // peers they report make up the next level
+        let mut level: Vec<(String, u16)> = ports_to_check
+            .iter()
+            .map(|(port, _)| (args.host.clone(), *port))
+            .filter(|(host, port)| visited.insert(format!("{}:{}", host, port)))
+            .collect();
+        level.sort();
+
+        while !level.is_empty() {
             // Check if we've reached the peer limit
             if args.max_peers > 0 && discovered_peers.len() >= args.max_peers as usize {
-                println!("\n Reached maximum peer limit of {}", args.max_peers);
+                if text {
+                    println!("\n Reached maximum peer limit of {}", args.max_peers);
+                }
                 break;
             }
 
-            if let Some((host, port)) = queue.pop_front() {
+            let statuses = query_statuses(&client, &level, args.concurrency, args.debug).await;
+            let mut next_level = Vec::new();
+            for ((host, port), status) in level.iter().zip(statuses) {
                 total_nodes += 1;
                 let uri_key = format!("{}:{}", host, port);
+                report_nodes.push(NodeHealth::new(host, *port, &status));
 
-                print!(" Querying {}:{}: ", host, port);
+                if text {
+                    print!(" Querying {}:{}: ", host, port);
+                }
 
-                match query_node_status(&client, &host, port, args.debug).await {
-                    Ok((status_json, _raw_response)) => {
+                match status {
+                    Ok(status_json) => {
                         healthy_nodes += 1;
-                        println!(" HEALTHY");
 
                         // Display full response including peer list
                         node_status_map.push((uri_key.clone(), true, status_json.clone()));
@@ -727,12 +1448,18 @@ pub async fn network_health_command(
                         // Extract peers from this node
                         let peers = extract_peers(&status_json);
                         all_peer_lists.push(peers.clone());
-
-                        if args.verbose {
-                            println!(" Peer count: {}", peers.len());
+                        edges.extend(peers.iter().map(|peer| PeerEdge {
+                            from: uri_key.clone(),
+                            to: peer.uri_key(),
+                        }));
+
+                        if text {
+                            println!(" HEALTHY");
+                            if args.verbose {
+                                println!(" Peer count: {}", peers.len());
+                            }
+                            println!(" Peers from this node:");
                         }
-
-                        println!(" Peers from this node:");
                         for peer in &peers {
                             let peer_uri = peer.uri_key();
                             if !visited.contains(&peer_uri)
@@ -740,61 +1467,89 @@ pub async fn network_health_command(
                                     || discovered_peers.len() < args.max_peers as usize)
                             {
                                 visited.insert(peer_uri);
-                                queue.push_back((peer.host.clone(), peer.protocol_port));
+                                next_level.push((peer.host.clone(), peer.protocol_port));
                                 discovered_peers.push(peer.clone());
-                                print!(
-                                    " Added: {} ({}:{})",
-                                    peer.node_id, peer.host, peer.protocol_port
-                                );
-                                if args.verbose {
-                                    print!(" [status: {}]", peer.connection_status);
+                                let limit_reached = args.max_peers > 0
+                                    && discovered_peers.len() >= args.max_peers as usize;
+                                if text {
+                                    print!(
+                                        " Added: {} ({}:{})",
+                                        peer.node_id, peer.host, peer.protocol_port
+                                    );
+                                    if args.verbose {
+                                        print!(" [status: {}]", peer.connection_status);
+                                    }
+                                    if limit_reached {
+                                        println!(" [LIMIT REACHED]");
+                                    } else {
+                                        println!();
+                                    }
                                 }
-                                if args.max_peers > 0
-                                    && discovered_peers.len() >= args.max_peers as usize
-                                {
-                                    println!(" [LIMIT REACHED]");
+                                if limit_reached {
                                     break;
                                 }
-                                println!();
                             }
                         }
                     }
                     Err(e) => {
-                        println!(" {}", e);
+                        if text {
+                            println!(" {}", e);
+                        }
                         node_status_map.push((uri_key, false, serde_json::json!({})));
                     }
                 }
             }
+            next_level.sort();
+            level = next_level;
         }
 
-        println!("\n Recursive Discovery Summary:");
-        println!(" Healthy nodes: {}/{}", healthy_nodes, total_nodes);
-        println!(" Total discovered peers: {}", discovered_peers.len());
+        if text {
+            println!("\n Recursive Discovery Summary:");
+            println!(" Healthy nodes: {}/{}", healthy_nodes, total_nodes);
+            println!(" Total discovered peers: {}", discovered_peers.len());
+        }
+        peer_graph = Some(edges);
+        discovered_count = Some(discovered_peers.len());
     } else {
         // Standard mode: just query specified ports
-        println!(" Checking {} nodes...\n", ports_to_check.len());
+        ports_to_check.sort_by_key(|(port, _)| *port);
+        ports_to_check.dedup_by_key(|(port, _)| *port);
+        if text {
+            println!(" Checking {} nodes...\n", ports_to_check.len());
+        }
+
+        let nodes: Vec<(String, u16)> = ports_to_check
+            .iter()
+            .map(|(port, _)| (args.host.clone(), *port))
+            .collect();
+        let statuses = query_statuses(&client, &nodes, args.concurrency, args.debug).await;
 
-        for (port, node_type) in ports_to_check {
+        for ((port, node_type), status) in ports_to_check.into_iter().zip(statuses) {
             total_nodes += 1;
             let uri_key = format!("{}:{}", args.host, port);
+            report_nodes.push(NodeHealth::new(&args.host, port, &status));
 
-            print!(" {} ({}:{}): ", node_type, args.host, port);
-
-            match query_node_status(&client, &args.host, port, args.debug).await {
-                Ok((status_json, _raw_response)) => {
+            match status {
+                Ok(status_json) => {
                     healthy_nodes += 1;
-                    let peer_count = status_json
-                        .get("peers")
-                        .and_then(|p| p.as_u64())
-                        .unwrap_or(0);
-
-                    println!(" HEALTHY ({} peers)", peer_count);
 
                     // Store the status and peer list
                     node_status_map.push((uri_key, true, status_json.clone()));
                     let peers = extract_peers(&status_json);
                     all_peer_lists.push(peers);
 
+                    if !text {
+                        continue;
+                    }
+                    let peer_count = status_json
+                        .get("peers")
+                        .and_then(|p| p.as_u64())
+                        .unwrap_or(0);
+                    println!(
+                        " {} ({}:{}):  HEALTHY ({} peers)",
+                        node_type, args.host, port, peer_count
+                    );
+
                     if args.verbose {
                         if let Some(peers_from_endpoint) = status_json.get("peers") {
                             println!(" Peers count from endpoint: {}", peers_from_endpoint);
@@ -808,14 +1563,32 @@ pub async fn network_health_command(
                     }
                 }
                 Err(e) => {
-                    println!(" {}", e);
+                    if text {
+                        println!(" {} ({}:{}):  {}", node_type, args.host, port, e);
+                    }
                     node_status_map.push((uri_key, false, serde_json::json!({})));
                 }
             }
         }
 
-        println!("\n Network Health Summary:");
-        println!(" Healthy nodes: {}/{}", healthy_nodes, total_nodes);
+        if text {
+            println!("\n Network Health Summary:");
+            println!(" Healthy nodes: {}/{}", healthy_nodes, total_nodes);
+        }
+    }
+
+    let summary = HealthSummary::new(&report_nodes, discovered_count);
+    let shortfall = summary.shortfall(args.min_healthy, args.min_healthy_pct);
+    if !text {
+        print_json(&HealthReport {
+            nodes: report_nodes,
+            summary,
+            peer_graph,
+        })?;
+        return match shortfall {
+            Some(shortfall) => Err(NodeCliError::General(shortfall).into()),
+            None => Ok(()),
+        };
     }
 
     // Display detailed peer information for each node
@@ -886,16 +1659,59 @@ pub async fn network_health_command(
         println!(" No healthy nodes found - check if network is running");
     }
 
-    Ok(())
+    match shortfall {
+        Some(shortfall) => Err(NodeCliError::General(shortfall).into()),
+        None => Ok(()),
+    }
+}
+
+/// The fields `last-finalized-block` shows, from the node's `blockInfo`
+#[derive(Debug, Serialize)]
+struct LfbSummary {
+    block_number: u64,
+    block_hash: String,
+    timestamp: u64,
+    /// From blockInfo, where the node has already counted them
+    deploy_count: u64,
+    shard_id: String,
+    fault_tolerance: f64,
+}
+
+impl LfbSummary {
+    /// Missing fields read as `Unknown` or zero
+    fn from_json(block_json: &serde_json::Value) -> Self {
+        let info = block_json.get("blockInfo");
+        let field = |name: &str| info.and_then(|info| info.get(name));
+        let text = |name: &str| {
+            field(name)
+                .and_then(|v| v.as_str())
+                .unwrap_or("Unknown")
+                .to_string()
+        };
+        let number = |name: &str| field(name).and_then(|v| v.as_u64()).unwrap_or(0);
+        Self {
+            block_number: number("blockNumber"),
+            block_hash: text("blockHash"),
+            timestamp: number("timestamp"),
+            deploy_count: number("deployCount"),
+            shard_id: text("shardId"),
+            fault_tolerance: field("faultTolerance")
+                .and_then(|v| v.as_f64())
+                .unwrap_or(0.0),
+        }
+    }
 }
 
 pub async fn last_finalized_block_command(
     args: &HttpArgs,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    println!(
-        " Getting last finalized block from {}:{}",
-        args.host, args.port
-    );
+    let json = args.output.is_json();
+    if !json {
+        println!(
+            " Getting last finalized block from {}:{}",
+            args.host, args.port
+        );
+    }
 
     let url = format!(
         "http://{}:{}/api/last-finalized-block",
@@ -905,6 +1721,7 @@ pub async fn last_finalized_block_command(
 
     let start_time = Instant::now();
 
+    let call = NodeCall::http(&url);
     match client.get(&url).send().await {
         Ok(response) => {
             let duration = start_time.elapsed();
@@ -912,63 +1729,25 @@ pub async fn last_finalized_block_command(
                 let block_text = response.text().await?;
                 let block_json: serde_json::Value = serde_json::from_str(&block_text)?;
 
+                let summary = LfbSummary::from_json(&block_json);
+                if json {
+                    return print_json(&summary);
+                }
+
                 println!(" Last finalized block retrieved successfully!");
                 println!(" Time taken: {:.2?}", duration);
-
-                // Extract key information from blockInfo
-                let block_info = block_json.get("blockInfo");
-
-                let block_hash = block_info
-                    .and_then(|info| info.get("blockHash"))
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-
-                let block_number = block_info
-                    .and_then(|info| info.get("blockNumber"))
-                    .and_then(|v| v.as_u64())
-                    .unwrap_or(0);
-
-                let timestamp = block_info
-                    .and_then(|info| info.get("timestamp"))
-                    .and_then(|v| v.as_u64())
-                    .unwrap_or(0);
-
-                // Get deploy count from blockInfo (it's already calculated)
-                let deploy_count = block_info
-                    .and_then(|info| info.get("deployCount"))
-                    .and_then(|v| v.as_u64())
-                    .unwrap_or(0);
-
-                let shard_id = block_info
-                    .and_then(|info| info.get("shardId"))
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown");
-
-                let fault_tolerance = block_info
-                    .and_then(|info| info.get("faultTolerance"))
-                    .and_then(|v| v.as_f64())
-                    .unwrap_or(0.0);
-
                 println!(" Last Finalized Block Summary:");
-                println!(" Block Number: {}", block_number);
-                println!(" Block Hash: {}", block_hash);
-                println!(" Timestamp: {}", timestamp);
-                println!(" Deploy Count: {}", deploy_count);
-                println!(" Shard ID: {}", shard_id);
-                println!(" Fault Tolerance: {:.6}", fault_tolerance);
+                println!(" Block Number: {}", summary.block_number);
+                println!(" Block Hash: {}", summary.block_hash);
+                println!(" Timestamp: {}", summary.timestamp);
+                println!(" Deploy Count: {}", summary.deploy_count);
+                println!(" Shard ID: {}", summary.shard_id);
+                println!(" Fault Tolerance: {:.6}", summary.fault_tolerance);
             } else {
-                println!(
-                    " Failed to get last finalized block: HTTP {}",
-                    response.status()
-                );
-                println!("Error: {}", response.text().await?);
+                query_failed(&args.output, &call, "last finalized block", response).await?;
             }
         }
-        Err(e) => {
-            println!(" Connection failed!");
-            println!("Error: {}", e);
-            return Err(e.into());
-        }
+        Err(e) => return Err(connection_failed(&args.output, &call, e)),
     }
 
     Ok(())
@@ -979,12 +1758,16 @@ pub async fn show_main_chain_command(
 ) -> Result<(), Box<dyn std::error::Error>> {
     println!(
         " Getting main chain blocks from {}:{}",
-        args.host, args.port
+        args.node.host, args.node.grpc_port
     );
     println!(" Depth: {} blocks", args.depth);
 
     // Initialize the F1r3fly API client
-    let f1r3fly_api = F1r3flyApi::new(&args.private_key, &args.host, args.port)?;
+    let f1r3fly_api = F1r3flyApi::new(
+        &args.signing.private_key,
+        &args.node.host,
+        args.node.grpc_port,
+    )?;
 
     let start_time = Instant::now();
 
@@ -1000,24 +1783,43 @@ pub async fn show_main_chain_command(
                 println!(" No blocks found in main chain");
             } else {
                 println!(" Main Chain Blocks:");
-                for (index, block) in blocks.iter().enumerate() {
+                let partition =
+                    block_groups::partition(&blocks, args.granularity, |block| BlockTime {
+                        block_number: block.block_number,
+                        timestamp: block.timestamp,
+                        main_chain: true,
+                    });
+                let styled = std::io::stdout().is_terminal();
+                for row in &partition.rows {
+                    let block = match row {
+                        Row::Separator(i) => {
+                            println!(
+                                "{}",
+                                block_groups::separator(&partition.groups[*i].label, styled)
+                            );
+                            continue;
+                        }
+                        Row::Block(block) => *block,
+                    };
                     println!(" Block #{}:", block.block_number);
                     println!(" Hash: {}", block.block_hash);
-                    let sender_display = if block.sender.len() >= 16 {
-                        format!("{}...", &block.sender[..16])
-                    } else if block.sender.is_empty() {
+                    let sender_display = if block.sender.is_empty() {
                         "(genesis)".to_string()
                     } else {
-                        block.sender.clone()
+                        shorten(&block.sender, Field::Key)
                     };
                     println!(" Sender: {}", sender_display);
                     println!(" Timestamp: {}", block.timestamp);
                     println!(" Deploy Count: {}", block.deploy_count);
                     println!(" Fault Tolerance: {:.6}", block.fault_tolerance);
-                    if index < blocks.len() - 1 {
+                    if !std::ptr::eq(block, &blocks[blocks.len() - 1]) {
                         println!(" ");
                     }
                 }
+                println!();
+                for line in block_groups::summary(&partition.groups, args.granularity) {
+                    println!("{}", line);
+                }
             }
         }
         Err(e) => {
@@ -1030,16 +1832,36 @@ pub async fn show_main_chain_command(
     Ok(())
 }
 
+/// Refuse a `--port`/`--http-port` pair naming two different standard shard
+/// nodes: the block the queries are pinned to is resolved over HTTP, so it would
+/// come from another node than the one running them
+fn check_same_node(grpc_port: u16, http_port: u16) -> crate::error::Result<()> {
+    match ports::split_nodes(grpc_port, http_port) {
+        Some((grpc_node, http_node)) => Err(NodeCliError::config_invalid_value(
+            "http-port",
+            &format!(
+                "{} is {}'s HTTP port but --port {} is {}'s; use --http-port {} or leave it out to query one node",
+                http_port,
+                http_node.role,
+                grpc_port,
+                grpc_node.role,
+                grpc_node.http
+            ),
+        )),
+        None => Ok(()),
+    }
+}
+
 pub async fn validator_status_command(
     args: &ValidatorStatusArgs,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    let identities = args.identity.load()?;
+    let display_name = identities.resolve_validator(&args.public_key);
     println!(" Checking validator status for: {}", args.public_key);
 
-    let f1r3fly_api = F1r3flyApi::new(
-        "5f668a7ee96d944a4494cc947e4005e172d7ab3461ee5538f1f2a45a835e9657", // Bootstrap private key
-        &args.host,
-        args.port,
-    )?;
+    check_same_node(args.port, args.http_port)?;
+    let f1r3fly_api =
+        F1r3flyApi::for_queries(args.query_key.private_key.as_deref(), &args.host, args.port)?;
 
     let start_time = Instant::now();
 
@@ -1067,78 +1889,68 @@ pub async fn validator_status_command(
  }
  }"#;
 
-    // Use HTTP API for PoS contract queries (like bonds/network-consensus commands)
-    let client = reqwest::Client::new();
-    let http_url = format!("http://{}:{}/api/explore-deploy", args.host, args.http_port);
+    // Resolve the reference block first to ensure consistent state reference
+    let pinned = args.pinned.client(&f1r3fly_api, args.http_port);
+    pinned.block().await?;
 
-    // Get main chain tip first to ensure consistent state reference
-    let main_chain = f1r3fly_api.show_main_chain(1).await?;
-    let tip_block = main_chain.first().ok_or("No blocks found in main chain")?;
-    let current_block = tip_block.block_number;
-    let tip_block_hash = &tip_block.block_hash;
-
-    // Execute all queries using explicit tip block hash for consistency
-    let (bonds_result, active_result, quarantine_result) = tokio::try_join!(
-        query_pos_http(&client, &http_url, bonds_query),
-        query_pos_http(&client, &http_url, active_query),
-        f1r3fly_api.exploratory_deploy(quarantine_query, Some(tip_block_hash), false),
-    )?;
+    // One round trip for all three values unless --no-batch, each retrying a
+    // refreshed reference block before any failure is reported
+    let results = pinned
+        .explore_batch(
+            "validator-status",
+            &[bonds_query, active_query, quarantine_query],
+        )
+        .await
+        .map_err(explain_pos_failure)?;
+    let current_block = pinned.block().await?.number;
 
     let duration = start_time.elapsed();
 
-    // Parse results using HTTP response format
-    let bonds_data = bonds_result;
-    let active_data = active_result;
-
-    // Parse quarantine length
-    let quarantine_length = quarantine_result.0.trim().parse::<i64>().map_err(|e| {
-        format!(
-            "Failed to parse quarantine length: '{}'. Error: {}",
-            quarantine_result.0, e
-        )
-    })?;
+    let bonds = parse_bonds(&results[0]).map_err(explain_pos_failure)?;
+    let active_validators = parse_active_validators(&results[1]).map_err(explain_pos_failure)?;
+    let quarantine_length =
+        parse_pos_int(&results[2], "quarantine length").map_err(explain_pos_failure)?;
 
     println!(" Validator status retrieved successfully!");
     println!(" Time taken: {:.2?}", duration);
     println!();
 
-    // Parse bonded validators from HTTP response
-    let bonded_validators = parse_validator_data(&bonds_data);
-    let active_validators = parse_validator_data(&active_data);
-
     // Check bonded status
-    let is_bonded = bonded_validators.contains(&args.public_key);
+    let public_key = args.public_key.to_ascii_lowercase();
+    let stake = bonds.get(&public_key).copied();
+    let is_bonded = stake.is_some();
+    let is_active = active_validators.contains(&public_key);
+
+    if args.explain {
+        let reference_block = results[0]
+            .get("block")
+            .and_then(|block| block.get("blockNumber")?.as_i64())
+            .unwrap_or(current_block);
+        let evidence = gather_status_evidence(
+            args,
+            &f1r3fly_api,
+            &pinned,
+            StatusEvidence {
+                reference_block,
+                stake,
+                active: is_active,
+                quarantine_length,
+                ..Default::default()
+            },
+        )
+        .await;
+        print_explanation(args, &display_name.name, &evidence);
+        return Ok(());
+    }
 
-    if is_bonded {
+    if let Some(stake) = stake {
         println!(" BONDED: Validator is bonded to the network");
-
-        // Try to extract bond amount from JSON
-        if let Ok(json) = serde_json::from_str::<serde_json::Value>(&bonds_data) {
-            if let Some(block) = json.get("block") {
-                if let Some(bonds) = block.get("bonds") {
-                    if let Some(bonds_array) = bonds.as_array() {
-                        for bond in bonds_array {
-                            if let Some(validator) = bond.get("validator").and_then(|v| v.as_str())
-                            {
-                                if validator == args.public_key {
-                                    if let Some(stake) = bond.get("stake").and_then(|s| s.as_i64())
-                                    {
-                                        println!(" Stake Amount: {}", stake);
-                                    }
-                                    break;
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
+        println!(" Stake Amount: {}", stake);
     } else {
         println!(" NOT BONDED: Validator is not bonded to the network");
     }
 
     // Check active status
-    let is_active = active_validators.contains(&args.public_key);
     if is_active {
         println!(" ACTIVE: Validator is actively participating in consensus");
     } else if is_bonded {
@@ -1149,6 +1961,9 @@ pub async fn validator_status_command(
 
     println!();
     println!(" Summary:");
+    if let Some(name) = &display_name.name {
+        println!(" Name: {}", name);
+    }
     println!(" Public Key: {}", args.public_key);
     println!(" Bonded: {}", if is_bonded { " Yes" } else { " No" });
     println!(" Active: {}", if is_active { " Yes" } else { " No" });
@@ -1168,17 +1983,110 @@ pub async fn validator_status_command(
     Ok(())
 }
 
+/// Fill in epoch length, recent proposals, bonding block and node role
+///
+/// Each source is best-effort: a failed lookup leaves its field unknown and the
+/// explanation says so rather than failing the command.
+async fn gather_status_evidence(
+    args: &ValidatorStatusArgs,
+    api: &F1r3flyApi,
+    pinned: &PinnedQueryClient<'_>,
+    mut evidence: StatusEvidence,
+) -> StatusEvidence {
+    let epoch_length_query = r#"new return, rl(`rho:registry:lookup`), poSCh in {
+ rl!(`rho:system:pos`, *poSCh) |
+ for(@(_, PoS) <- poSCh) {
+ @PoS!("getEpochLength", *return)
+ }
+ }"#;
+
+    let end = evidence.reference_block;
+    let start = (end - args.recent_blocks.max(1) + 1).max(0);
+    let source = ChainSource::new(&args.host, args.http_port);
+    let (epoch_result, blocks, status) = tokio::join!(
+        pinned.exploratory_deploy("epoch-length", epoch_length_query),
+        source.ranged_json(start, end),
+        api.get_node_status(args.http_port),
+    );
+
+    evidence.epoch_length = epoch_result
+        .ok()
+        .and_then(|(value, _, _)| value.trim().parse::<i64>().ok());
+    evidence.node_is_validator = status.ok().flatten().and_then(|s| s.is_validator);
+
+    if let Ok(blocks) = blocks {
+        let mut recent: Vec<(i64, String, Vec<String>)> = blocks
+            .iter()
+            .map(|json| {
+                let info = json.get("blockInfo").unwrap_or(json);
+                let number = info
+                    .get("blockNumber")
+                    .and_then(|n| n.as_i64())
+                    .unwrap_or(0);
+                let sender = info.get("sender").and_then(|s| s.as_str()).unwrap_or("");
+                let bonds = info
+                    .get("bonds")
+                    .and_then(|b| b.as_array())
+                    .map(|bonds| {
+                        bonds
+                            .iter()
+                            .filter_map(|b| b.get("validator")?.as_str().map(str::to_string))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                (number, sender.to_string(), bonds)
+            })
+            .collect();
+        recent.sort_by_key(|(number, _, _)| *number);
+
+        evidence.recent_blocks = recent.len();
+        let proposed: Vec<i64> = recent
+            .iter()
+            .filter(|(_, sender, _)| sender == &args.public_key)
+            .map(|(number, _, _)| *number)
+            .collect();
+        evidence.proposed = proposed.len();
+        evidence.last_proposed = proposed.last().copied();
+        evidence.bonded_at = bonded_at(
+            &args.public_key,
+            recent
+                .iter()
+                .map(|(number, _, bonds)| (*number, bonds.as_slice())),
+        );
+    }
+    evidence
+}
+
+fn print_explanation(args: &ValidatorStatusArgs, name: &Option<String>, evidence: &StatusEvidence) {
+    let explanation = explain(evidence);
+    println!(
+        " Why is {} {}?",
+        name.as_deref().unwrap_or(&args.public_key),
+        if evidence.proposed > 0 {
+            "proposing"
+        } else {
+            "not proposing"
+        }
+    );
+    println!();
+    for check in &explanation.checks {
+        println!(" {} {}", check.result.symbol(), check.question);
+        println!("     {}", check.evidence);
+    }
+    println!();
+    println!(" Conclusion: {}", explanation.conclusion);
+    println!(" Next: {}", explanation.next_action);
+}
+
 pub async fn epoch_info_command(args: &PosQueryArgs) -> Result<(), Box<dyn std::error::Error>> {
     println!(
         " Getting current epoch information from {}:{}",
         args.host, args.port
     );
 
-    let f1r3fly_api = F1r3flyApi::new(
-        "5f668a7ee96d944a4494cc947e4005e172d7ab3461ee5538f1f2a45a835e9657", // Bootstrap private key
-        &args.host,
-        args.port,
-    )?;
+    check_same_node(args.port, args.http_port)?;
+    let f1r3fly_api =
+        F1r3flyApi::for_queries(args.query_key.private_key.as_deref(), &args.host, args.port)?;
 
     let start_time = Instant::now();
 
@@ -1197,36 +2105,24 @@ pub async fn epoch_info_command(args: &PosQueryArgs) -> Result<(), Box<dyn std::
  }
  }"#;
 
-    // Get main chain tip first to ensure consistent state reference
-    let main_chain = f1r3fly_api.show_main_chain(1).await?;
-    let tip_block = main_chain.first().ok_or("No blocks found in main chain")?;
-    let current_block = tip_block.block_number;
-    let tip_block_hash = &tip_block.block_hash;
+    // Resolve the reference block first to ensure consistent state reference
+    let pinned = args.pinned.client(&f1r3fly_api, args.http_port);
+    pinned.block().await?;
 
-    // Get epoch and quarantine data using explicit tip block hash for consistency
-    let (epoch_result, quarantine_result, recent_blocks) = tokio::try_join!(
-        f1r3fly_api.exploratory_deploy(epoch_length_query, Some(tip_block_hash), false),
-        f1r3fly_api.exploratory_deploy(quarantine_length_query, Some(tip_block_hash), false),
+    // Get epoch and quarantine data pinned to the reference block in one batched
+    // round trip (unless --no-batch), retrying against a refreshed block before
+    // any failure is reported
+    let (pos_results, recent_blocks) = tokio::join!(
+        pinned.explore_batch("epoch-info", &[epoch_length_query, quarantine_length_query]),
         f1r3fly_api.show_main_chain(5)
-    )?;
+    );
+    let (pos_results, recent_blocks) = (pos_results?, recent_blocks?);
+    let current_block = pinned.block().await?.number;
 
     let duration = start_time.elapsed();
 
-    // Parse epoch length from PoS contract result
-    let epoch_length = epoch_result.0.trim().parse::<i64>().map_err(|e| {
-        format!(
-            "Failed to parse epoch length from PoS contract: '{}'. Error: {}",
-            epoch_result.0, e
-        )
-    })?;
-
-    // Parse quarantine length from PoS contract result
-    let quarantine_length = quarantine_result.0.trim().parse::<i64>().map_err(|e| {
-        format!(
-            "Failed to parse quarantine length from PoS contract: '{}'. Error: {}",
-            quarantine_result.0, e
-        )
-    })?;
+    let epoch_length = parse_pos_int(&pos_results[0], "epoch length")?;
+    let quarantine_length = parse_pos_int(&pos_results[1], "quarantine length")?;
 
     // Calculate epoch information
     let current_epoch = current_block / epoch_length;
@@ -1297,11 +2193,14 @@ pub async fn epoch_info_command(args: &PosQueryArgs) -> Result<(), Box<dyn std::
 }
 
 pub async fn epoch_rewards_command(args: &PosQueryArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let identities = args.identity.load()?;
     println!(
         " Getting current epoch rewards from {}:{}",
         args.host, args.http_port
     );
 
+    check_same_node(args.port, args.http_port)?;
+
     let rewards_query = r#"new return, rl(`rho:registry:lookup`), poSCh in {
  rl!(`rho:system:pos`, *poSCh) |
  for(@(_, PoS) <- poSCh) {
@@ -1317,17 +2216,23 @@ pub async fn epoch_rewards_command(args: &PosQueryArgs) -> Result<(), Box<dyn st
     let start_time = Instant::now();
 
     let body = serde_json::json!({ "term": rewards_query });
-    let response = client.post(&http_url).json(&body).send().await?;
+    let call = NodeCall::http(&http_url);
+    let response = client
+        .post(&http_url)
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| call.fail(e))?;
 
     if !response.status().is_success() {
         let status = response.status();
         let body = response.text().await.unwrap_or_default();
         println!(" Failed to get epoch rewards!");
         println!("Error: HTTP {} {}", status, body);
-        return Err(format!("HTTP error: {}", status).into());
+        return Err(call.fail(format!("HTTP error: {}", status)).into());
     }
 
-    let response_json: serde_json::Value = response.json().await?;
+    let response_json: serde_json::Value = response.json().await.map_err(|e| call.fail(e))?;
     let duration = start_time.elapsed();
 
     println!(" Epoch rewards retrieved successfully!");
@@ -1351,37 +2256,21 @@ pub async fn epoch_rewards_command(args: &PosQueryArgs) -> Result<(), Box<dyn st
 
     // Parse rewards from ExprMap: { validator_pubkey: ExprInt { data: reward } }
     println!();
-    if let Some(expr) = response_json.get("expr").and_then(|e| e.as_array()) {
-        if let Some(expr_map) = expr
-            .first()
-            .and_then(|e| e.get("ExprMap"))
-            .and_then(|m| m.get("data"))
-            .and_then(|d| d.as_object())
-        {
-            println!(" Current Epoch Rewards ({} validators):", expr_map.len());
+    if let Some(expr) = response_json.get("expr").filter(|e| e.is_array()) {
+        let converted = convert_rholang_to_json(expr)?;
+        if let Some(rewards) = converted.get(0).and_then(|r| r.as_object()) {
+            println!(" Current Epoch Rewards ({} validators):", rewards.len());
             println!();
 
-            let mut entries: Vec<(&String, i64)> = expr_map
+            let mut entries: Vec<(&String, i64)> = rewards
                 .iter()
-                .map(|(key, val)| {
-                    let reward = val
-                        .get("ExprInt")
-                        .and_then(|e| e.get("data"))
-                        .and_then(|d| d.as_i64())
-                        .unwrap_or(0);
-                    (key, reward)
-                })
+                .map(|(key, val)| (key, val.as_i64().unwrap_or(0)))
                 .collect();
             let total_rewards: i64 = entries.iter().map(|(_, r)| r).sum();
             entries.sort_by(|a, b| b.1.cmp(&a.1));
 
             for (key, reward) in &entries {
-                let short_key = if key.len() > 16 {
-                    format!("{}...{}", &key[..8], &key[key.len() - 8..])
-                } else {
-                    key.to_string()
-                };
-                println!(" {} : {}", short_key, reward);
+                println!(" {} : {}", identities.resolve_validator(key), reward);
             }
 
             println!();
@@ -1397,61 +2286,119 @@ pub async fn epoch_rewards_command(args: &PosQueryArgs) -> Result<(), Box<dyn st
     Ok(())
 }
 
-// Helper function for HTTP PoS queries
-async fn query_pos_http(
-    client: &reqwest::Client,
-    url: &str,
-    query: &str,
-) -> Result<String, Box<dyn std::error::Error>> {
-    let body = serde_json::json!({
-    "term": query
-    });
+pub async fn pos_query_command(args: &PosMethodArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let params: Vec<String> = args.args.iter().map(|arg| arg.to_string()).collect();
+    println!(
+        " Calling PoS {}({}) on {}:{}",
+        args.method,
+        params.join(", "),
+        args.host,
+        args.port
+    );
+    if let Some(block_hash) = &args.block_hash {
+        println!(" At block: {}", block_hash);
+    }
 
-    let response = client
-        .post(url)
-        .header("Content-Type", "application/json")
-        .json(&body)
-        .send()
+    check_same_node(args.port, args.http_port)?;
+    let term = build_pos_method_query(&args.method, &args.args)?;
+    let f1r3fly_api =
+        F1r3flyApi::for_queries(args.query_key.private_key.as_deref(), &args.host, args.port)?;
+
+    let start_time = Instant::now();
+    let response = f1r3fly_api
+        .exploratory_deploy_json(&term, args.block_hash.as_deref())
         .await?;
+    println!(" Time taken: {:.2?}", start_time.elapsed());
+    if let Some(block) = response.get("block") {
+        println!(
+            " Block hash: {}, Block number: {}",
+            block
+                .get("blockHash")
+                .and_then(|v| v.as_str())
+                .unwrap_or("unknown"),
+            block
+                .get("blockNumber")
+                .and_then(|v| v.as_i64())
+                .unwrap_or(0)
+        );
+    }
 
-    if response.status().is_success() {
-        let response_text = response.text().await?;
-        let response_json: serde_json::Value = serde_json::from_str(&response_text)?;
+    let expr = response.get("expr").cloned().unwrap_or_default();
+    if !expr.as_array().is_some_and(|values| !values.is_empty()) {
+        return Err(NodeCliError::General(format!(
+            "PoS method '{}' returned no data: the contract may not define it, or it expects other arguments",
+            args.method
+        ))
+        .into());
+    }
+    let result = if args.raw {
+        expr
+    } else {
+        pos_method_result(&expr)?
+    };
+    println!();
+    println!("{}", serde_json::to_string_pretty(&result)?);
+    Ok(())
+}
 
-        // Extract the actual result from the response
-        if let Some(block) = response_json.get("block") {
-            if let Some(result) = block.get("postBlockData") {
-                return Ok(result.to_string());
-            }
-        }
+/// Plain JSON of a PoS method's answer: the value it returned, or a list if it sent several
+fn pos_method_result(
+    expr: &serde_json::Value,
+) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    match convert_rholang_to_json(expr)? {
+        serde_json::Value::Array(mut values) if values.len() == 1 => Ok(values.remove(0)),
+        other => Ok(other),
+    }
+}
 
-        // Fallback to full response if structure is different
-        Ok(response_text)
-    } else {
-        Err(format!("HTTP error: {}", response.status()).into())
+/// Tell apart a PoS query that got no data from one whose answer could not be read
+///
+/// Prints what each likely means before the error is returned.
+fn explain_pos_failure(err: impl Into<NodeCliError>) -> NodeCliError {
+    let err = err.into();
+    if err.is_no_data() {
+        println!(" The node returned no data for this query.");
+        println!(
+            " It may run a version without this PoS method, or not be a node that serves PoS queries."
+        );
+    } else if matches!(
+        err.root(),
+        NodeCliError::Api(ApiError::ParseError(_) | ApiError::UnexpectedExploreResponse { .. })
+    ) {
+        println!(" The node answered, but its response could not be parsed.");
+        println!(" Check that --http-port points at the node's HTTP API.");
     }
+    err
+}
+
+/// Integer result of a PoS explore-deploy response, e.g. the quarantine length
+fn parse_pos_int(
+    response: &serde_json::Value,
+    what: &str,
+) -> Result<i64, Box<dyn std::error::Error>> {
+    let expr = response.get("expr").cloned().unwrap_or_default();
+    convert_rholang_to_json(&expr)?
+        .get(0)
+        .and_then(|v| v.as_i64())
+        .ok_or_else(|| NodeCliError::parse_error(&format!("{}: '{}'", what, expr)).into())
 }
 
 pub async fn network_consensus_command(
     args: &PosQueryArgs,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    let identities = args.identity.load()?;
     println!(
         " Getting network-wide consensus overview from {}:{}",
         args.host, args.port
     );
 
-    let f1r3fly_api = F1r3flyApi::new(
-        "5f668a7ee96d944a4494cc947e4005e172d7ab3461ee5538f1f2a45a835e9657",
-        &args.host,
-        args.port,
-    )?;
+    check_same_node(args.port, args.http_port)?;
+    let f1r3fly_api =
+        F1r3flyApi::for_queries(args.query_key.private_key.as_deref(), &args.host, args.port)?;
 
     let start_time = Instant::now();
 
-    // Get all validator info in parallel using HTTP API for PoS queries
-    let client = reqwest::Client::new();
-    let http_url = format!("http://{}:{}/api/explore-deploy", args.host, args.http_port);
-
+    // Get all validator info in one batched round trip (unless --no-batch)
     let bonds_query = r#"new return, rl(`rho:registry:lookup`), poSCh in {
  rl!(`rho:system:pos`, *poSCh) |
  for(@(_, PoS) <- poSCh) {
@@ -1473,17 +2420,18 @@ pub async fn network_consensus_command(
  }
  }"#;
 
-    // Get main chain tip first to ensure consistent state reference
-    let main_chain = f1r3fly_api.show_main_chain(1).await?;
-    let tip_block = main_chain.first().ok_or("No blocks found in main chain")?;
-    let current_block = tip_block.block_number;
-    let tip_block_hash = &tip_block.block_hash;
+    // Resolve the reference block first to ensure consistent state reference
+    let pinned = args.pinned.client(&f1r3fly_api, args.http_port);
+    pinned.block().await?;
 
-    let (bonds_result, active_result, quarantine_result) = tokio::try_join!(
-        query_pos_http(&client, &http_url, bonds_query),
-        query_pos_http(&client, &http_url, active_query),
-        f1r3fly_api.exploratory_deploy(quarantine_query, Some(tip_block_hash), false),
-    )?;
+    let results = pinned
+        .explore_batch(
+            "network-consensus",
+            &[bonds_query, active_query, quarantine_query],
+        )
+        .await
+        .map_err(explain_pos_failure)?;
+    let current_block = pinned.block().await?.number;
 
     let duration = start_time.elapsed();
 
@@ -1492,24 +2440,18 @@ pub async fn network_consensus_command(
     println!();
 
     // Parse and display network health
-    let bonds_data = bonds_result;
-    let active_data = active_result;
-
-    // Parse quarantine length
-    let quarantine_length = quarantine_result.0.trim().parse::<i64>().map_err(|e| {
-        format!(
-            "Failed to parse quarantine length: '{}'. Error: {}",
-            quarantine_result.0, e
-        )
-    })?;
-
-    // Parse validator data from HTTP response
-    let bonded_validators = parse_validator_data(&bonds_data);
-    let active_validators = parse_validator_data(&active_data);
+    let quarantine_length =
+        parse_pos_int(&results[2], "quarantine length").map_err(explain_pos_failure)?;
+    let mut bonded_validators: Vec<String> = parse_bonds(&results[0])
+        .map_err(explain_pos_failure)?
+        .into_keys()
+        .collect();
+    bonded_validators.sort();
+    let active_validators = parse_active_validators(&results[1]).map_err(explain_pos_failure)?;
 
     let total_bonded = bonded_validators.len();
     let total_active = active_validators.len();
-    let quarantine_count = total_bonded - total_active;
+    let quarantine_count = total_bonded.saturating_sub(total_active);
 
     println!(" Network Consensus Health:");
     println!(" Current Block: {}", current_block);
@@ -1533,48 +2475,19 @@ pub async fn network_consensus_command(
         println!(" Participation Rate: {:.1}%", participation_rate);
     }
 
-    Ok(())
-}
-
-fn parse_validator_data(json_str: &str) -> Vec<String> {
-    // Parse JSON response from HTTP PoS query
-    let mut validators = Vec::new();
-
-    if let Ok(json) = serde_json::from_str::<serde_json::Value>(json_str) {
-        // Extract from the HTTP response structure: response.block.bonds[] or response.block (for active validators)
-        if let Some(block) = json.get("block") {
-            // For bonds data: extract from bonds array
-            if let Some(bonds) = block.get("bonds") {
-                if let Some(bonds_array) = bonds.as_array() {
-                    for bond in bonds_array {
-                        if let Some(validator) = bond.get("validator") {
-                            if let Some(validator_str) = validator.as_str() {
-                                validators.push(validator_str.to_string());
-                            }
-                        }
-                    }
-                }
-            }
-
-            // For active validators data: might be in a different format
-            // The response structure may vary for getActiveValidators vs getBonds
-            if validators.is_empty() {
-                // Try to extract directly from block object or other possible structures
-                if let Some(obj) = block.as_object() {
-                    for (key, _value) in obj {
-                        // Public keys are typically 64-character hex strings
-                        if key.len() == 64 && key.chars().all(|c| c.is_ascii_hexdigit()) {
-                            validators.push(key.clone());
-                        }
-                    }
-                }
-            }
+    let quarantined: Vec<&String> = bonded_validators
+        .iter()
+        .filter(|v| !active_validators.contains(v))
+        .collect();
+    if !quarantined.is_empty() {
+        println!();
+        println!(" Quarantined Validators:");
+        for validator in quarantined {
+            println!(" - {}", identities.resolve_validator(validator));
         }
     }
 
-    validators.sort();
-    validators.dedup();
-    validators
+    Ok(())
 }
 
 pub async fn get_blocks_by_height_command(
@@ -1582,7 +2495,7 @@ pub async fn get_blocks_by_height_command(
 ) -> Result<(), Box<dyn std::error::Error>> {
     println!(
         " Getting blocks by height range from {}:{}",
-        args.host, args.port
+        args.node.host, args.node.grpc_port
     );
     println!(
         " Block range: {} to {}",
@@ -1599,7 +2512,11 @@ pub async fn get_blocks_by_height_command(
     }
 
     // Initialize the F1r3fly API client
-    let f1r3fly_api = F1r3flyApi::new(&args.private_key, &args.host, args.port)?;
+    let f1r3fly_api = F1r3flyApi::new(
+        &args.signing.private_key,
+        &args.node.host,
+        args.node.grpc_port,
+    )?;
 
     let start_time = Instant::now();
 
@@ -1621,12 +2538,10 @@ pub async fn get_blocks_by_height_command(
                 for (index, block) in blocks.iter().enumerate() {
                     println!(" Block #{}:", block.block_number);
                     println!(" Hash: {}", block.block_hash);
-                    let sender_display = if block.sender.len() >= 16 {
-                        format!("{}...", &block.sender[..16])
-                    } else if block.sender.is_empty() {
+                    let sender_display = if block.sender.is_empty() {
                         "(genesis)".to_string()
                     } else {
-                        block.sender.clone()
+                        shorten(&block.sender, Field::Key)
                     };
                     println!(" Sender: {}", sender_display);
                     println!(" Timestamp: {}", block.timestamp);
@@ -1683,15 +2598,16 @@ pub async fn block_transfers_command(
     let client = reqwest::Client::new();
     let start_time = Instant::now();
 
-    let response = client.get(&url).send().await?;
+    let call = NodeCall::http(&url);
+    let response = client.get(&url).send().await.map_err(|e| call.fail(e))?;
     let duration = start_time.elapsed();
 
     if !response.status().is_success() {
         println!("Failed to get block: HTTP {}", response.status());
-        return Err(format!("HTTP {}", response.status()).into());
+        return Err(call.fail(format!("HTTP {}", response.status())).into());
     }
 
-    let block_json: serde_json::Value = response.json().await?;
+    let block_json: serde_json::Value = response.json().await.map_err(|e| call.fail(e))?;
 
     println!("Block retrieved successfully!");
     println!("Time taken: {:.2?}", duration);
@@ -1704,11 +2620,7 @@ pub async fn block_transfers_command(
         .and_then(|n| n.as_i64())
         .unwrap_or(0);
 
-    let block_hash_display = if args.block_hash.len() > 16 {
-        format!("{}...", &args.block_hash[..16])
-    } else {
-        args.block_hash.clone()
-    };
+    let block_hash_display = shorten(&args.block_hash, Field::Hash);
 
     println!("Block #{} ({})", block_number, block_hash_display);
     println!();
@@ -1810,3 +2722,408 @@ pub async fn block_transfers_command(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bond(validator: &str, stake: i64) -> Bond {
+        Bond {
+            validator: validator.to_string(),
+            stake,
+            address: None,
+            balance: None,
+        }
+    }
+
+    #[test]
+    fn test_validator_list_json_schema() {
+        let bonds = [bond("04aa", 1000), bond("04bb", 500)];
+        let json = serde_json::to_value(ValidatorList::new(&bonds)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "validator_count": 2,
+                "total_stake": 1500,
+                "bonds": [
+                    {"validator": "04aa", "stake": 1000, "address": null},
+                    {"validator": "04bb", "stake": 500, "address": null},
+                ],
+            })
+        );
+    }
+
+    /// A `getActiveValidators` explore-deploy response as the node sends it:
+    /// the active set in `expr`, the bonds of the queried block in `block`
+    fn active_validators_response(active: serde_json::Value) -> serde_json::Value {
+        serde_json::json!({
+            "expr": [active],
+            "block": {
+                "blockHash": "5e1c0d3c2b1f9a7e4d6b8c0a2f4e6d8b0c2a4f6e8d0b2c4a6f8e0d2b4c6a8f0e",
+                "blockNumber": 412,
+                "bonds": [
+                    {"validator": "04AA", "stake": 1000},
+                    {"validator": "04bb", "stake": 500},
+                    {"validator": "04cc", "stake": 250},
+                ],
+            },
+        })
+    }
+
+    #[test]
+    fn test_active_validators_come_from_the_active_set() {
+        let keys = || {
+            vec![
+                serde_json::json!({"ExprBytes": {"data": "04cc"}}),
+                serde_json::json!({"ExprBytes": {"data": "04aa"}}),
+            ]
+        };
+        for active in [
+            serde_json::json!({"ExprSet": {"data": keys()}}),
+            serde_json::json!({"ExprList": {"data": keys()}}),
+        ] {
+            let split = ActiveBonds::from_response(&active_validators_response(active)).unwrap();
+            let active: Vec<_> = split.active.iter().map(|b| b.validator.as_str()).collect();
+            let inactive: Vec<_> = split
+                .inactive
+                .iter()
+                .map(|b| b.validator.as_str())
+                .collect();
+            assert_eq!(active, ["04AA", "04cc"]);
+            assert_eq!(inactive, ["04bb"]);
+            assert_eq!(ValidatorList::new(&split.active).total_stake, 1250);
+        }
+    }
+
+    #[test]
+    fn test_active_validators_need_the_active_set() {
+        // Only the bonds, as the command used to read them
+        let mut response = active_validators_response(serde_json::Value::Null);
+        response["expr"] = serde_json::json!([]);
+        assert!(ActiveBonds::from_response(&response).is_err());
+
+        let mut no_bonds = active_validators_response(serde_json::json!({"ExprSet": {"data": []}}));
+        no_bonds["block"] = serde_json::json!({"blockNumber": 412});
+        assert!(ActiveBonds::from_response(&no_bonds).is_err());
+    }
+
+    #[test]
+    fn test_pos_method_result() {
+        use crate::test_support::rholang::{expr_bytes, expr_int, expr_map_pairs, expr_tuple};
+
+        let single = serde_json::json!([expr_int(1_000)]);
+        assert_eq!(
+            pos_method_result(&single).unwrap(),
+            serde_json::json!(1_000)
+        );
+
+        let rewards =
+            serde_json::json!([expr_map_pairs([(expr_bytes(&[0x04, 0xaa]), expr_int(25))])]);
+        assert_eq!(
+            pos_method_result(&rewards).unwrap(),
+            serde_json::json!({"04aa": 25})
+        );
+
+        let several = serde_json::json!([expr_tuple(vec![expr_int(1), expr_int(2)]), expr_int(3)]);
+        assert_eq!(
+            pos_method_result(&several).unwrap(),
+            serde_json::json!([[1, 2], 3])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_epoch_rewards_refuses_ports_of_two_nodes() {
+        use clap::Parser;
+        let argv = [
+            "node_cli",
+            "epoch-rewards",
+            "-p",
+            "40412",
+            "--http-port",
+            "40453",
+        ];
+        let Commands::EpochRewards(args) = Cli::try_parse_from(argv).unwrap().command else {
+            unreachable!()
+        };
+        let err = epoch_rewards_command(&args).await.unwrap_err().to_string();
+        assert!(err.contains("--http-port 40413"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_pos_query_refuses_ports_of_two_nodes() {
+        use clap::Parser;
+        // Validator1's gRPC port with the observer's HTTP port; refused before connecting
+        let argv = [
+            "node_cli",
+            "pos-query",
+            "getMinimumBond",
+            "-p",
+            "40412",
+            "--http-port",
+            "40453",
+        ];
+        let Commands::PosQuery(args) = Cli::try_parse_from(argv).unwrap().command else {
+            unreachable!()
+        };
+        let err = pos_query_command(&args).await.unwrap_err().to_string();
+        assert!(err.contains("--http-port 40413"), "{}", err);
+
+        // A method name that would break out of the string is refused as well
+        let argv = ["node_cli", "pos-query", "get\"Bonds"];
+        let Commands::PosQuery(args) = Cli::try_parse_from(argv).unwrap().command else {
+            unreachable!()
+        };
+        assert!(pos_query_command(&args).await.is_err());
+    }
+
+    #[test]
+    fn test_address_lists_get_the_array_schema() {
+        use clap::Parser;
+        let lists = |extra: &[&str]| {
+            let mut argv = vec!["node_cli", "wallet-balance"];
+            argv.extend(extra);
+            match Cli::try_parse_from(argv).unwrap().command {
+                Commands::WalletBalance(args) => lists_addresses(&args),
+                _ => unreachable!(),
+            }
+        };
+        assert!(!lists(&["-a", "1111aa"]));
+        assert!(lists(&["-a", "1111aa", "-a", "1111bb"]));
+        assert!(lists(&["-a", "1111aa,1111bb"]));
+        assert!(lists(&["--addresses-file", "vaults.txt"]));
+        assert!(lists(&["-a", "1111aa", "--addresses-file", "vaults.txt"]));
+    }
+
+    #[test]
+    fn test_multi_balance_total_does_not_overflow() {
+        let balances = [
+            AddressBalance::new("1111aa", Ok(i64::MAX)),
+            AddressBalance::new("1111bb", Ok(i64::MAX)),
+        ];
+        let table = render_balances(&balances);
+        let total = (i64::MAX as u128 * 2).to_string();
+        assert!(table.lines().last().unwrap().contains(&total));
+    }
+
+    #[test]
+    fn test_address_list_file() {
+        let content = "# treasury\n1111aa\n\n  1111bb  # ops\n#1111cc\n";
+        assert_eq!(parse_address_list(content), ["1111aa", "1111bb"]);
+    }
+
+    #[test]
+    fn test_multi_balance_json_schema() {
+        let balances = [
+            AddressBalance::new("1111aa", Ok(49_999_999_598_463_260)),
+            AddressBalance::new("1111bb", Err("connection refused".to_string())),
+        ];
+        assert_eq!(
+            serde_json::to_value(&balances).unwrap(),
+            serde_json::json!([
+                {"address": "1111aa", "dust": 49_999_999_598_463_260i64, "rev": "499999995.9846326"},
+                {"address": "1111bb", "dust": null, "rev": null, "error": "connection refused"},
+            ])
+        );
+    }
+
+    #[test]
+    fn test_multi_balance_table_reports_failures_inline() {
+        let balances = [
+            AddressBalance::new("1111aa", Ok(150_000_000)),
+            AddressBalance::new("1111bb", Err("vault did not return a balance".to_string())),
+            AddressBalance::new("1111cc", Ok(5)),
+        ];
+        let table = render_balances(&balances);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[0].starts_with("ADDRESS"));
+        assert!(lines[1].ends_with("150000000  1.5"));
+        assert!(lines[2].ends_with("-  error: vault did not return a balance"));
+        assert!(lines[3].ends_with("5  0.00000005"));
+        assert!(lines[4].starts_with("TOTAL (2 of 3)"));
+        assert!(lines[4].ends_with("150000005  1.50000005"));
+    }
+
+    #[tokio::test]
+    async fn test_node_statuses_are_queried_together() {
+        let live = crate::test_support::spawn_http_node(|_line, _body| {
+            ("200 OK", r#"{"peers": 2, "peerList": []}"#.to_string())
+        })
+        .await;
+        // Accept connections without ever answering, like a node that hangs
+        let mut hung = Vec::new();
+        for _ in 0..3 {
+            hung.push(tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap());
+        }
+        let mut nodes: Vec<(String, u16)> = hung
+            .iter()
+            .map(|l| ("127.0.0.1".to_string(), l.local_addr().unwrap().port()))
+            .collect();
+        nodes.insert(1, ("127.0.0.1".to_string(), live));
+
+        let timeout = std::time::Duration::from_millis(300);
+        let client = reqwest::Client::builder().timeout(timeout).build().unwrap();
+        let start = Instant::now();
+        let statuses = query_statuses(&client, &nodes, 4, false).await;
+        // One timeout's wait for all three hung nodes, not three in a row
+        assert!(start.elapsed() < timeout * 2, "{:?}", start.elapsed());
+
+        assert_eq!(statuses.len(), 4);
+        assert_eq!(statuses[1].as_ref().unwrap()["peers"], 2);
+        for position in [0, 2, 3] {
+            assert!(statuses[position].is_err(), "{}", position);
+        }
+    }
+
+    #[test]
+    fn test_network_health_report_schema() {
+        let answered = Ok(serde_json::json!({
+            "version": {"api": "1", "node": "F1r3fly Node 0.13.2 (abc123)"},
+            "peers": 3,
+        }));
+        let down = Err("error sending request".to_string());
+        let nodes = vec![
+            NodeHealth::new("localhost", 40403, &answered),
+            NodeHealth::new("localhost", 40413, &down),
+        ];
+        let report = HealthReport {
+            summary: HealthSummary::new(&nodes, Some(1)),
+            nodes,
+            peer_graph: Some(vec![PeerEdge {
+                from: "localhost:40403".to_string(),
+                to: "10.0.0.2:40400".to_string(),
+            }]),
+        };
+        assert_eq!(
+            serde_json::to_value(&report).unwrap(),
+            serde_json::json!({
+                "nodes": [
+                    {"host": "localhost", "port": 40403, "healthy": true, "peer_count": 3,
+                     "version": "F1r3fly Node 0.13.2 (abc123)", "error": null},
+                    {"host": "localhost", "port": 40413, "healthy": false, "peer_count": null,
+                     "version": null, "error": "error sending request"},
+                ],
+                "summary": {"total": 2, "healthy": 1, "unhealthy": 1, "healthy_pct": 50.0,
+                            "discovered_peers": 1},
+                "peer_graph": [{"from": "localhost:40403", "to": "10.0.0.2:40400"}],
+            })
+        );
+
+        // Without --recursive there is no graph, and a plain version string is kept
+        let plain = NodeHealth::new(
+            "localhost",
+            40453,
+            &Ok(serde_json::json!({"version": "0.13.2"})),
+        );
+        assert_eq!(plain.version.as_deref(), Some("0.13.2"));
+        let json = serde_json::to_value(HealthReport {
+            summary: HealthSummary::new(std::slice::from_ref(&plain), None),
+            nodes: vec![plain],
+            peer_graph: None,
+        })
+        .unwrap();
+        assert!(json.get("peer_graph").is_none());
+        assert!(json["summary"].get("discovered_peers").is_none());
+    }
+
+    #[test]
+    fn test_network_health_thresholds() {
+        let summary = HealthSummary {
+            total: 5,
+            healthy: 3,
+            unhealthy: 2,
+            healthy_pct: 60.0,
+            discovered_peers: None,
+        };
+        assert_eq!(summary.shortfall(None, None), None);
+        assert_eq!(summary.shortfall(Some(3), Some(60)), None);
+        assert!(summary
+            .shortfall(Some(4), None)
+            .unwrap()
+            .contains("--min-healthy 4"));
+        assert!(summary
+            .shortfall(None, Some(80))
+            .unwrap()
+            .contains("--min-healthy-pct 80"));
+        assert_eq!(HealthSummary::new(&[], None).healthy_pct, 0.0);
+    }
+
+    #[test]
+    fn test_last_finalized_block_json_schema() {
+        let block = serde_json::json!({"blockInfo": {
+            "blockHash": "a47bdb40",
+            "blockNumber": 128,
+            "timestamp": 1718000000000u64,
+            "deployCount": 3,
+            "shardId": "root",
+            "faultTolerance": 0.5,
+        }});
+        assert_eq!(
+            serde_json::to_value(LfbSummary::from_json(&block)).unwrap(),
+            serde_json::json!({
+                "block_number": 128,
+                "block_hash": "a47bdb40",
+                "timestamp": 1718000000000u64,
+                "deploy_count": 3,
+                "shard_id": "root",
+                "fault_tolerance": 0.5,
+            })
+        );
+
+        let empty = LfbSummary::from_json(&serde_json::json!({}));
+        assert_eq!(empty.block_hash, "Unknown");
+        assert_eq!(empty.block_number, 0);
+    }
+
+    #[test]
+    fn test_balance_json_schema() {
+        let json =
+            serde_json::to_value(BalanceJson::new("1111abc", "250\n", Some(42), false).unwrap())
+                .unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "address": "1111abc",
+                "balance": 250,
+                "block_number": 42,
+                "cached": false,
+            })
+        );
+        let err = BalanceJson::new("1111abc", "Invalid address", None, false).unwrap_err();
+        assert!(err.to_string().contains("Invalid address"), "{}", err);
+    }
+
+    #[test]
+    fn test_status_json_uses_parsed_fields() {
+        let status: crate::f1r3fly_api::NodeStatus = serde_json::from_value(serde_json::json!({
+            "version": {"api": "1", "node": "F1r3fly 0.1"},
+            "address": "rnode://abc@localhost",
+            "networkId": "testnet",
+            "shardId": "root",
+            "peers": 2,
+            "nodes": 3,
+            "minPhloPrice": 1,
+            "unexpectedField": true,
+        }))
+        .unwrap();
+        let json = serde_json::to_value(&status).unwrap();
+        assert_eq!(json["networkId"], "testnet");
+        assert_eq!(json["peers"], 2);
+        assert!(json.get("unexpectedField").is_none());
+    }
+
+    #[test]
+    fn test_metrics_json_keeps_numeric_samples() {
+        let text = "# HELP peers Connected peers\n\
+                    # TYPE peers gauge\n\
+                    peers 4\n\
+                    casper_blocks_total{shard=\"root\"} 128\n\
+                    rspace_size_bytes 1.5e6\n\
+                    broken_sample NaN-ish\n";
+        let metrics = parse_metrics(text);
+        assert_eq!(metrics.len(), 3);
+        assert_eq!(metrics["peers"], 4.0);
+        assert_eq!(metrics["casper_blocks_total{shard=\"root\"}"], 128.0);
+        assert_eq!(metrics["rspace_size_bytes"], 1_500_000.0);
+    }
+}
@@ -0,0 +1,239 @@
+use crate::args::{
+    InsertSignedArgs, ObserverConnArgs, RegistryAction, RegistryArgs, RegistryLookupArgs,
+};
+use crate::commands::network::{build_config, preflight_node};
+use crate::connection_manager::F1r3flyConnectionManager;
+use crate::error::{NodeCliError, Result};
+use crate::f1r3fly_api::F1r3flyApi;
+use crate::registry::{
+    generate_insert_signed_signature, insert_signed_term, lookup_term, parse_lookup,
+    public_key_to_uri, validate_contract_name, validate_lookup_uri,
+};
+use crate::utils::{now_millis, print_progress, CryptoUtils, Report};
+use chrono::DateTime;
+use std::time::Instant;
+
+/// Publish contracts in the on-chain registry
+pub async fn registry_command(args: &RegistryArgs) -> Result<()> {
+    match &args.action {
+        RegistryAction::InsertSigned(args) => insert_signed_command(args).await,
+        RegistryAction::Lookup(args) => lookup_command(args).await,
+    }
+}
+
+/// Register a contract at the signing key's `rho:id:` URI
+///
+/// The signature covers the deploy's timestamp, so the deploy is sent with
+/// the same timestamp the signature was made for.
+async fn insert_signed_command(args: &InsertSignedArgs) -> Result<()> {
+    validate_contract_name(&args.name)
+        .map_err(|e| NodeCliError::config_invalid_value("name", &e))?;
+    let contract = std::fs::read_to_string(&args.file).map_err(|e| {
+        NodeCliError::file_read_failed(&args.file.display().to_string(), &e.to_string())
+    })?;
+
+    let secret_key = CryptoUtils::decode_private_key(&args.signing.private_key)?;
+    let public_key = CryptoUtils::derive_public_key(&secret_key);
+    let timestamp = DateTime::from_timestamp_millis(now_millis())
+        .ok_or_else(|| NodeCliError::General("System clock is out of range".to_string()))?;
+    let signature = generate_insert_signed_signature(
+        &secret_key,
+        timestamp,
+        &public_key,
+        args.contract_version,
+    );
+    let term = insert_signed_term(
+        &contract,
+        &args.name,
+        &public_key,
+        args.contract_version,
+        &signature,
+    );
+    let uri = public_key_to_uri(&public_key);
+
+    if args.dry_run {
+        Report::new()
+            .line(term)
+            .line("")
+            .line(format!("URI: {}", uri))
+            .note(format!(
+                "Dry run: nothing deployed. The signature is for timestamp {}, so a real run signs again.",
+                timestamp.timestamp_millis()
+            ))
+            .print();
+        return Ok(());
+    }
+
+    let node = preflight_node(&args.node, false).await?;
+    let config = build_config(
+        &node,
+        &ObserverConnArgs::default(),
+        &args.signing.private_key,
+        args.max_wait,
+        args.max_wait,
+        args.check_interval,
+        &args.poll,
+    );
+    let manager = F1r3flyConnectionManager::new(config);
+
+    print_progress(&format!(
+        "Registering {} (version {}) at {}...",
+        args.file.display(),
+        args.contract_version,
+        uri
+    ));
+    let start = Instant::now();
+    let deploy_id = manager
+        .deploy_with_timestamp(&term, timestamp.timestamp_millis())
+        .await?;
+    print_progress(&format!("Deploy ID: {}", deploy_id));
+    let block_hash = manager.wait_for_block(&deploy_id, None).await?;
+
+    Report::new()
+        .line(format!("Deploy ID: {}", deploy_id))
+        .line(format!("Block hash: {}", block_hash))
+        .line(format!("URI: {}", uri))
+        .note(format!("Total time: {:.2?}", start.elapsed()))
+        .note(format!(
+            "Look it up with rl!(`{}`, *ch) once the block is finalized",
+            uri
+        ))
+        .print();
+    Ok(())
+}
+
+/// Show what `rho:registry:lookup` returns for a URI
+///
+/// Nothing registered and a version other than `--expect-version` are errors
+/// with their own exit codes; the entry is printed before a version mismatch
+/// fails the command.
+async fn lookup_command(args: &RegistryLookupArgs) -> Result<()> {
+    validate_lookup_uri(&args.uri).map_err(|e| NodeCliError::config_invalid_value("uri", &e))?;
+    let api = F1r3flyApi::for_queries(
+        args.query_key.private_key.as_deref(),
+        &args.node.host,
+        args.node.grpc_port,
+    )?;
+
+    let start = Instant::now();
+    let response = api
+        .explore_deploy_http(
+            &lookup_term(&args.uri),
+            args.block_hash.as_deref(),
+            args.node.http_port,
+        )
+        .await?;
+    let entry = parse_lookup(&response.expr)
+        .map_err(|e| NodeCliError::parse_error(&e))?
+        .ok_or_else(|| NodeCliError::not_registered(&args.uri))?;
+
+    let mut report = Report::new();
+    report.line(format!("URI: {}", args.uri));
+    match entry.version {
+        Some(version) => report.line(format!("Version: {}", version)),
+        None => report.line("Version: none (not a (version, value) entry)"),
+    };
+    report
+        .line("Value:")
+        .line(serde_json::to_string_pretty(&entry.value)?);
+    if let Some(block) = &response.block {
+        report.note(format!(
+            "Block: {} (height {})",
+            block.block_hash, block.block_number
+        ));
+    }
+    report.time("Time taken", start.elapsed()).print();
+
+    match args.expect_version {
+        Some(expected) if entry.version != Some(expected) => Err(
+            NodeCliError::registry_version_mismatch(&args.uri, expected, entry.version),
+        ),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::{Cli, Commands};
+    use crate::error::{EXIT_NOT_REGISTERED, EXIT_VERSION_MISMATCH};
+    use crate::test_support::rholang::{
+        explore_response, expr_int, expr_nil, expr_tuple, expr_unforg,
+    };
+    use clap::Parser;
+
+    fn lookup_args(http_port: u16, extra: &[&str]) -> RegistryLookupArgs {
+        let port = http_port.to_string();
+        let mut argv = vec![
+            "node_cli",
+            "registry",
+            "lookup",
+            "-H",
+            "127.0.0.1",
+            "--http-port",
+            &port,
+        ];
+        argv.extend(extra);
+        match Cli::try_parse_from(argv).unwrap().command {
+            Commands::Registry(RegistryArgs {
+                action: RegistryAction::Lookup(args),
+            }) => args,
+            _ => unreachable!(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lookup_outcomes() {
+        let port = crate::test_support::spawn_http_node(|line, body| {
+            // Pinned lookups send JSON, latest-state lookups the bare term
+            let term = match serde_json::from_str::<serde_json::Value>(body) {
+                Ok(request) if line.contains("/api/explore-deploy-by-block-hash") => {
+                    request["term"].as_str().unwrap_or_default().to_string()
+                }
+                _ => body.to_string(),
+            };
+            let entry = if term.contains("rl!(`rho:id:signed`, *ch)") {
+                expr_tuple(vec![expr_int(2), expr_unforg("9c3f")])
+            } else {
+                expr_nil()
+            };
+            ("200 OK", explore_response(vec![entry]).to_string())
+        })
+        .await;
+
+        lookup_command(&lookup_args(
+            port,
+            &["rho:id:signed", "--expect-version", "2"],
+        ))
+        .await
+        .unwrap();
+        lookup_command(&lookup_args(port, &["rho:id:signed", "-b", "b1"]))
+            .await
+            .unwrap();
+
+        let mismatch = lookup_command(&lookup_args(
+            port,
+            &["rho:id:signed", "--expect-version", "3"],
+        ))
+        .await
+        .unwrap_err();
+        assert_eq!(mismatch.exit_code(), EXIT_VERSION_MISMATCH);
+
+        let missing = lookup_command(&lookup_args(port, &["rho:id:missing"]))
+            .await
+            .unwrap_err();
+        assert_eq!(missing.exit_code(), EXIT_NOT_REGISTERED);
+    }
+
+    #[tokio::test]
+    async fn test_lookup_transport_error_is_not_not_registered() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+
+        let err = lookup_command(&lookup_args(port, &["rho:id:signed"]))
+            .await
+            .unwrap_err();
+        assert_eq!(err.exit_code(), 1);
+    }
+}
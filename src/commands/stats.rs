@@ -0,0 +1,145 @@
+use crate::args::StatsArgs;
+use crate::error::{NodeCliError, Result};
+use crate::timings::{self, GroupStats};
+use crate::utils::{format_change, format_duration, now_millis, parse_duration, Comparison};
+use std::time::Duration;
+
+fn ms(value: u64) -> String {
+    format_duration(Duration::from_millis(value))
+}
+
+/// Summarize recorded timing samples, optionally against the previous window
+pub fn stats_command(args: &StatsArgs) -> Result<()> {
+    let window = parse_duration(&args.window, "d")
+        .map_err(|e| NodeCliError::config_invalid_value("window", &e))?;
+    if window.is_zero() {
+        return Err(NodeCliError::config_invalid_value(
+            "window",
+            "must be longer than zero",
+        ));
+    }
+    let path = timings::metrics_path().ok_or_else(|| {
+        NodeCliError::config_missing_required("a config directory (set FIREFLY_CONFIG_DIR)")
+    })?;
+    let mut samples = timings::read_samples(&path)?;
+    if let Some(command) = &args.command {
+        samples.retain(|s| &s.command == command);
+    }
+
+    if !timings::enabled() {
+        println!(
+            "Timing recording is off; set {}=1 to record samples",
+            timings::METRICS_ENV
+        );
+    }
+    if samples.is_empty() {
+        println!("No timing samples in {}", path.display());
+        return Ok(());
+    }
+
+    let (previous, current) = timings::windows(now_millis(), window);
+    let after = timings::summarize(&samples, current.0, current.1);
+    if !args.compare {
+        println!("Timings over the last {}\n", format_duration(window));
+        print_summary(&after);
+        return Ok(());
+    }
+
+    let before = timings::summarize(&samples, previous.0, previous.1);
+    println!(
+        "Last {} compared with the {} before it\n",
+        format_duration(window),
+        format_duration(window)
+    );
+    let threshold = args.threshold / 100.0;
+    let mut regressions = 0;
+    for group in &after {
+        let Some(old) = before
+            .iter()
+            .find(|b| b.command == group.command && b.node == group.node)
+        else {
+            println!(
+                "{}  node {}  (no samples in the previous window)\n",
+                group.command, group.node
+            );
+            continue;
+        };
+        println!(
+            "{}  node {}  ({} -> {} ok runs)",
+            group.command, group.node, old.ok, group.ok
+        );
+        println!(
+            "  {:<14} {:>22} {:>22}",
+            "phase", "p50 before -> after", "p95 before -> after"
+        );
+        for (phase, now) in &group.phases {
+            let Some(then) = old.phases.get(phase) else {
+                continue;
+            };
+            let comparison = Comparison::new(*then, *now);
+            let regressed = comparison.is_regression(threshold, args.min_samples);
+            if regressed {
+                regressions += 1;
+            }
+            println!(
+                "  {:<14} {:>22} {:>22}{}",
+                phase,
+                format!(
+                    "{} -> {} ({})",
+                    ms(then.p50),
+                    ms(now.p50),
+                    format_change(comparison.p50_change())
+                ),
+                format!(
+                    "{} -> {} ({})",
+                    ms(then.p95),
+                    ms(now.p95),
+                    format_change(comparison.p95_change())
+                ),
+                if regressed { "  REGRESSION" } else { "" }
+            );
+        }
+        println!();
+    }
+
+    if regressions > 0 {
+        println!(
+            "{} phase(s) regressed by more than {}%",
+            regressions, args.threshold
+        );
+    } else {
+        println!("No regressions over {}%", args.threshold);
+    }
+    Ok(())
+}
+
+fn print_summary(groups: &[GroupStats]) {
+    if groups.is_empty() {
+        println!("No samples in this window");
+        return;
+    }
+    for group in groups {
+        println!(
+            "{}  node {}  ({} ok, {} failed)",
+            group.command, group.node, group.ok, group.failed
+        );
+        println!(
+            "  {:<14} {:>6} {:>10} {:>10} {:>10}",
+            "phase", "count", "p50", "p95", "max"
+        );
+        for (phase, summary) in &group.phases {
+            println!(
+                "  {:<14} {:>6} {:>10} {:>10} {:>10}",
+                phase,
+                summary.count,
+                ms(summary.p50),
+                ms(summary.p95),
+                ms(summary.max)
+            );
+        }
+        for (counter, total) in &group.counters {
+            println!("  {:<14} {:>6}", counter, total);
+        }
+        println!();
+    }
+}
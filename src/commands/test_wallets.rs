@@ -0,0 +1,145 @@
+use crate::args::CreateTestWalletsArgs;
+use crate::commands::network::build_config;
+use crate::connection_manager::F1r3flyConnectionManager;
+use crate::error::{NodeCliError, Result};
+use crate::key_guard;
+use crate::staking::StakingQueries;
+use crate::test_wallets::{verify_balances, Manifest, Output};
+use crate::utils::{print_info, print_progress, print_warning, Report};
+use crate::vault::{build_multi_transfer_rholang, DUST_FACTOR};
+
+/// Generate wallets, fund them in chunks from the signing key, and verify their balances
+pub async fn create_test_wallets_command(args: &CreateTestWalletsArgs) -> Result<()> {
+    key_guard::require_dev_target(
+        "create-test-wallets",
+        &args.node.host,
+        args.topology.as_deref(),
+        "--allow-non-dev",
+        args.allow_non_dev,
+    )?;
+    let manager = F1r3flyConnectionManager::new(build_config(
+        &args.node,
+        &args.observer,
+        &args.signing.private_key,
+        args.wait.max_wait,
+        args.wait.max_wait,
+        args.wait.check_interval,
+        &args.wait.poll,
+    ));
+    let funder = manager.get_address()?;
+    let output = Output::new(&args.output);
+
+    let mut manifest = if args.resume {
+        let manifest = output.load()?;
+        if manifest.funder != funder {
+            return Err(NodeCliError::config_invalid_value(
+                "private-key",
+                &format!(
+                    "the manifest was funded from {}, not {}",
+                    manifest.funder, funder
+                ),
+            ));
+        }
+        if args
+            .count
+            .is_some_and(|count| count != manifest.wallets.len())
+        {
+            print_warning(&format!(
+                "Ignoring --count: the manifest has {} wallet(s)",
+                manifest.wallets.len()
+            ));
+        }
+        print_info(&format!(
+            "Resuming {} ({} wallet(s))",
+            output.manifest_path().display(),
+            manifest.wallets.len()
+        ));
+        manifest
+    } else {
+        if output.manifest_path().exists() {
+            return Err(NodeCliError::config_invalid_value(
+                "output",
+                &format!(
+                    "{} already exists; pass --resume to continue it",
+                    output.manifest_path().display()
+                ),
+            ));
+        }
+        let count = args
+            .count
+            .ok_or_else(|| NodeCliError::config_invalid_value("count", "required"))?;
+        let manifest = Manifest::generate(&funder, args.amount * DUST_FACTOR, count)?;
+        // Written before anything is sent, so an interrupted run can resume
+        output.write(&manifest)?;
+        print_info(&format!(
+            "Generated {} wallet(s) in {}",
+            count,
+            output.manifest_path().display()
+        ));
+        manifest
+    };
+
+    let chunks = manifest.funding_chunks(args.chunk_size);
+    for (n, chunk) in chunks.iter().enumerate() {
+        print_progress(&format!(
+            "Funding deploy {}/{}: {} wallet(s) with {} tokens each",
+            n + 1,
+            chunks.len(),
+            chunk.len(),
+            manifest.amount_dust / DUST_FACTOR
+        ));
+        let recipients: Vec<&str> = chunk
+            .iter()
+            .map(|&i| manifest.wallets[i].address.as_str())
+            .collect();
+        let term = build_multi_transfer_rholang(&funder, &recipients, manifest.amount_dust);
+        match manager.deploy_and_wait(&term, false, 0).await {
+            Ok(result) if !result.errored => manifest.mark_funded(chunk, &result.deploy_id),
+            Ok(result) => manifest.mark_funding_failed(
+                chunk,
+                result
+                    .system_deploy_error
+                    .as_deref()
+                    .unwrap_or("deploy errored"),
+            ),
+            Err(e) => manifest.mark_funding_failed(chunk, &e.to_string()),
+        }
+        output.write(&manifest)?;
+    }
+
+    let queries = StakingQueries::new(
+        args.observer.host(&args.node),
+        args.observer.observer_http_port,
+    );
+    print_progress(&format!(
+        "Verifying {} balance(s)",
+        manifest.to_verify().len()
+    ));
+    verify_balances(&queries, &mut manifest, args.concurrency).await;
+    output.write(&manifest)?;
+
+    let tally = manifest.tally();
+    let mut report = Report::new();
+    report
+        .line(format!("Wallets: {}", manifest.wallets.len()))
+        .line(format!("Verified: {}", tally.verified))
+        .line(format!("Failed: {}", tally.failed))
+        .line(format!("Manifest: {}", output.manifest_path().display()));
+    for wallet in manifest.wallets.iter().filter(|w| w.error.is_some()) {
+        report.line(format!(
+            "  #{} {}: {}",
+            wallet.index,
+            wallet.address,
+            wallet.error.as_deref().unwrap_or_default()
+        ));
+    }
+    report.print();
+    if tally.verified < manifest.wallets.len() {
+        return Err(NodeCliError::General(format!(
+            "{} of {} wallet(s) not verified; run again with --resume to retry",
+            manifest.wallets.len() - tally.verified,
+            manifest.wallets.len()
+        )));
+    }
+    Ok(())
+}
@@ -0,0 +1,122 @@
+use crate::args::ValidatorChainArgs;
+use crate::capabilities::{self, Capability};
+use crate::commands::chain_stats::resolve_height_range;
+use crate::error::Result;
+use crate::export::ChainSource;
+use crate::truncation::{self, shorten, Field};
+use crate::utils::format_timestamp;
+use crate::validator_chain::{analyze, collect, ValidatorChain};
+use std::collections::HashSet;
+
+/// Hex characters in a block hash
+const BLOCK_HASH_LEN: usize = 64;
+
+/// One validator's blocks over a height range, ordered by sequence number
+pub async fn validator_chain_command(args: &ValidatorChainArgs) -> Result<()> {
+    capabilities::require(&args.host, args.port, Capability::BlocksByHeight).await?;
+    let (start, end) = resolve_height_range(
+        &args.host,
+        args.port,
+        args.query_key.private_key.as_deref(),
+        args.start,
+        args.end,
+        args.last,
+    )
+    .await?;
+    let source = ChainSource::new(&args.host, args.port);
+    let validator = args.validator.to_ascii_lowercase();
+
+    eprintln!(
+        "Scanning heights {}..={} from {}:{}",
+        start, end, args.host, args.port
+    );
+    let blocks = collect(&source, &validator, start, end, args.chunk_size).await?;
+    let chain = analyze(&validator, blocks);
+
+    if args.output.is_json() {
+        println!("{}", serde_json::to_string_pretty(&chain)?);
+        return Ok(());
+    }
+    print_chain(&chain, start, end);
+    Ok(())
+}
+
+fn short(hash: &str) -> String {
+    shorten(hash, Field::Hash)
+}
+
+fn print_chain(chain: &ValidatorChain, start: i64, end: i64) {
+    let Some((first, last)) = chain.seq_range() else {
+        println!(
+            "No blocks by {} at heights {}..={}",
+            chain.validator, start, end
+        );
+        return;
+    };
+    println!(
+        "Validator {}: {} block(s), seq_num {}..={} at heights {}..={}",
+        chain.validator,
+        chain.blocks.len(),
+        first,
+        last,
+        start,
+        end
+    );
+
+    let seen: HashSet<&str> = chain.blocks.iter().map(|b| b.block_hash.as_str()).collect();
+    let hash_width = truncation::current().display_width(BLOCK_HASH_LEN, Field::Hash);
+    let previous_width = hash_width + 2;
+    println!(
+        "\n  {:<8} {:<9} {:<hash_width$}  {:<previous_width$}  Time",
+        "Seq", "Height", "Block", "Previous"
+    );
+    for block in &chain.blocks {
+        // Mark a cited previous block that is not among the listed ones
+        let previous = match &block.previous {
+            Some(hash) if seen.contains(hash.as_str()) => short(hash),
+            Some(hash) => format!("{} ?", short(hash)),
+            None => "-".to_string(),
+        };
+        println!(
+            "  {:<8} #{:<8} {:<hash_width$}  {:<previous_width$}  {}",
+            block.seq_num,
+            block.block_number,
+            short(&block.block_hash),
+            previous,
+            if block.timestamp > 0 {
+                format_timestamp(block.timestamp)
+            } else {
+                "-".to_string()
+            }
+        );
+    }
+
+    println!();
+    if chain.gaps.is_empty() {
+        println!("Gaps: none");
+    }
+    for gap in &chain.gaps {
+        println!(
+            "Gap: seq_num {}..={} ({} missing)",
+            gap.from,
+            gap.to,
+            gap.missing()
+        );
+    }
+    for dup in &chain.duplicates {
+        println!(
+            "Duplicate seq_num {}: {} blocks ({}); run equivocation-check for details",
+            dup.seq_num,
+            dup.block_hashes.len(),
+            dup.block_hashes
+                .iter()
+                .map(|h| short(h))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+    match chain.average_interval_ms {
+        Some(ms) => println!("Average production interval: {:.2}s", ms / 1000.0),
+        None => println!("Average production interval: n/a"),
+    }
+}
@@ -0,0 +1,134 @@
+use crate::args::WatchWalletArgs;
+use crate::capabilities::{self, Capability};
+use crate::error::{NodeCliError, Result};
+use crate::events::subscribe_blocks_added;
+use crate::export::{ChainSource, DEFAULT_CHUNK_SIZE};
+use crate::f1r3fly_api::F1r3flyApi;
+use crate::wallet_watch::{backfill, WalletMatch, WalletWatch};
+use std::collections::HashSet;
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+
+/// Watch wallets: backfill from `--from-height`, then match every added block
+pub async fn watch_wallet_command(args: &WatchWalletArgs) -> Result<()> {
+    let watch = WalletWatch::new(&args.watch)?;
+    capabilities::require(&args.host, args.http_port, Capability::WebsocketEvents).await?;
+    if args.from_height.is_some() {
+        capabilities::require(&args.host, args.http_port, Capability::BlocksByHeight).await?;
+    }
+    let source = ChainSource::new(&args.host, args.http_port);
+
+    println!(" Watching {} wallet(s):", args.watch.len());
+    let mut addresses: Vec<&str> = watch.addresses().collect();
+    addresses.sort_unstable();
+    for address in addresses {
+        println!("   {}", address);
+    }
+    println!();
+
+    // Subscribe before backfilling so blocks added during the scan are not lost
+    let ws_url = format!("ws://{}:{}", args.host, args.ws_port);
+    let mut blocks = subscribe_blocks_added(&ws_url);
+
+    let mut seen = HashSet::new();
+    if let Some(from_height) = args.from_height {
+        let api = F1r3flyApi::for_queries(args.query_key.private_key.as_deref(), &args.host, 0)?;
+        let tip = api
+            .get_tip_block_number(args.http_port)
+            .await?
+            .ok_or_else(|| NodeCliError::network_connection_failed("node reported no tip block"))?;
+        println!(" Backfilling heights {}..={}", from_height, tip);
+        let (matches, scanned) =
+            backfill(&source, &watch, from_height.max(0), tip, DEFAULT_CHUNK_SIZE).await?;
+        for found in &matches {
+            report(found, args.notify_command.as_deref()).await;
+        }
+        println!(
+            " Backfill done: {} block(s), {} match(es)",
+            scanned.len(),
+            matches.len()
+        );
+        seen = scanned;
+    }
+
+    println!(
+        " Following new blocks on {}... (Press Ctrl+C to stop)\n",
+        ws_url
+    );
+
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+
+    loop {
+        tokio::select! {
+            _ = &mut ctrl_c => {
+                println!("\n Shutting down gracefully...");
+                return Ok(());
+            }
+            added = blocks.recv() => {
+                let Some(added) = added else {
+                    return Err(NodeCliError::network_connection_failed("event stream ended"));
+                };
+                if !seen.insert(added.block_hash.clone()) {
+                    continue;
+                }
+                match source.block(&added.block_hash).await {
+                    Ok(block) => {
+                        for found in watch.match_block(&block) {
+                            report(&found, args.notify_command.as_deref()).await;
+                        }
+                    }
+                    Err(e) => eprintln!(" Failed to fetch block {}: {}", added.block_hash, e),
+                }
+            }
+        }
+    }
+}
+
+/// Print a match and pass it to the notify command, if any
+async fn report(found: &WalletMatch, notify_command: Option<&str>) {
+    let amount = found
+        .amount
+        .map(|dust| format!("  {} dust", dust))
+        .unwrap_or_default();
+    println!(
+        " #{} {}  {:<8}  {}  {}{}{}",
+        found.block_number,
+        found.block_hash,
+        found.direction,
+        found.deploy_id,
+        found.address,
+        amount,
+        if found.errored { "  (errored)" } else { "" }
+    );
+
+    if let Some(command) = notify_command {
+        if let Err(e) = notify(command, found).await {
+            eprintln!(" Notify command failed: {}", e);
+        }
+    }
+}
+
+/// Run `command` through the shell with the match as a JSON line on stdin
+async fn notify(command: &str, found: &WalletMatch) -> Result<()> {
+    let mut line = serde_json::to_string(found)?;
+    line.push('\n');
+
+    let mut child = tokio::process::Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| NodeCliError::from(format!("could not start '{}': {}", command, e)))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(line.as_bytes()).await?;
+    }
+    let status = child.wait().await?;
+    if !status.success() {
+        return Err(NodeCliError::from(format!(
+            "'{}' exited with {}",
+            command, status
+        )));
+    }
+    Ok(())
+}
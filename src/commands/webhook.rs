@@ -0,0 +1,59 @@
+use crate::args::WebhookTestArgs;
+use crate::commands::outcome::DeployReceipt;
+use crate::error::{NodeCliError, Result};
+use crate::notify::Completion;
+use crate::utils::print_success;
+use crate::webhook::{WebhookPayload, WEBHOOK_SECRET_ENV, WEBHOOK_URL_KEY};
+use std::time::Duration;
+
+/// Deploy ID of the sample receipt; not a real deploy
+const SAMPLE_DEPLOY_ID: &str =
+    "3045022100000000000000000000000000000000000000000000000000000000000000";
+
+/// A receipt shaped like a finalized deploy's, with placeholder values
+fn sample_payload() -> WebhookPayload<DeployReceipt> {
+    let completion = Completion {
+        command: "webhook-test",
+        deploy_id: Some(SAMPLE_DEPLOY_ID),
+        succeeded: true,
+        elapsed: Duration::from_secs(12),
+    };
+    let receipt = DeployReceipt {
+        deploy_id: SAMPLE_DEPLOY_ID.to_string(),
+        block_hash: "0".repeat(64),
+        block_number: Some(0),
+        cost: Some(0),
+        errored: false,
+        system_deploy_error: None,
+        confirmations: None,
+    };
+    WebhookPayload::new(&completion, Some(receipt), None).test()
+}
+
+/// POST a sample receipt to the configured webhook and report how it went
+pub async fn webhook_test_command(args: &WebhookTestArgs) -> Result<()> {
+    let webhook = args.webhook.resolve().ok_or_else(|| {
+        NodeCliError::config_missing_required(&format!(
+            "--webhook-url, or {} in config.toml",
+            WEBHOOK_URL_KEY
+        ))
+    })?;
+    if webhook.secret.is_none() {
+        println!(
+            "No shared secret set ({} or webhook_secret in config.toml); sending unsigned",
+            WEBHOOK_SECRET_ENV
+        );
+    }
+    let delivery = webhook.send(&sample_payload()).await;
+    if !delivery.delivered() {
+        return Err(NodeCliError::General(format!(
+            "Webhook delivery to {} failed after {} attempt(s): {}",
+            webhook.url, delivery.attempts, delivery.last
+        )));
+    }
+    print_success(&format!(
+        "Delivered a test receipt to {} ({}, {} attempt(s))",
+        webhook.url, delivery.last, delivery.attempts
+    ));
+    Ok(())
+}
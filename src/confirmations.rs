@@ -0,0 +1,195 @@
+//! Confirmations on top of finality, for `--confirm-depth`
+//!
+//! A finalized block at height H has N confirmations once the last finalized
+//! block is at height H + N. Integrations that want a safety margin past
+//! finality wait for a given depth with [`wait_for_confirmations`], which
+//! polls the node's last finalized block until the depth is reached or the
+//! deadline passes.
+
+use crate::error::{NodeCliError, Result};
+use crate::f1r3fly_api::F1r3flyApi;
+use std::time::{Duration, Instant};
+
+/// Finalized blocks on top of `target_height`
+pub fn confirmations(target_height: i64, lfb_height: i64) -> i64 {
+    (lfb_height - target_height).max(0)
+}
+
+/// True once the last finalized block is `depth` or more blocks above the target
+pub fn is_confirmed(target_height: i64, lfb_height: i64, depth: u32) -> bool {
+    confirmations(target_height, lfb_height) >= i64::from(depth)
+}
+
+/// Progress line while waiting, e.g. `finalized, waiting for 3 more confirmations: 1/3`
+pub fn progress_message(achieved: i64, depth: u32) -> String {
+    format!(
+        "finalized, waiting for {} more confirmation{}: {}/{}",
+        depth,
+        if depth == 1 { "" } else { "s" },
+        achieved.min(i64::from(depth)),
+        depth
+    )
+}
+
+/// How many confirmations to wait for, and for how long
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConfirmationWait {
+    pub depth: u32,
+    pub poll_interval: Duration,
+    /// When the command's overall timeout runs out
+    pub deadline: Instant,
+}
+
+/// Wait until the finalized block `block_hash` has `wait.depth` confirmations
+///
+/// The block's height is looked up on the node unless `known_height` is
+/// given. `on_progress` is called with the confirmation count each time it
+/// changes. Returns the confirmations reached, which can exceed the depth, or
+/// an error once the deadline passes first.
+pub async fn wait_for_confirmations(
+    api: &F1r3flyApi<'_>,
+    http_port: u16,
+    block_hash: &str,
+    known_height: Option<i64>,
+    wait: ConfirmationWait,
+    mut on_progress: impl FnMut(i64),
+) -> Result<i64> {
+    let ConfirmationWait {
+        depth,
+        poll_interval,
+        deadline,
+    } = wait;
+    let target_height = match known_height {
+        Some(height) => height,
+        None => {
+            api.get_block_summary(block_hash, http_port)
+                .await?
+                .ok_or_else(|| {
+                    NodeCliError::General(format!("block {} not found on the node", block_hash))
+                })?
+                .block_number
+        }
+    };
+
+    let mut reported = None;
+    loop {
+        let lfb_height = api
+            .get_last_finalized_block(http_port)
+            .await?
+            .map(|lfb| lfb.block_number);
+        let achieved = lfb_height.map_or(0, |height| confirmations(target_height, height));
+        if lfb_height.is_some_and(|height| is_confirmed(target_height, height, depth)) {
+            return Ok(achieved);
+        }
+        if reported != Some(achieved) {
+            on_progress(achieved);
+            reported = Some(achieved);
+        }
+        if Instant::now() + poll_interval > deadline {
+            return Err(NodeCliError::General(format!(
+                "block {} at height {} has {}/{} confirmations; timed out waiting for the rest",
+                block_hash, target_height, achieved, depth
+            )));
+        }
+        tokio::time::sleep(poll_interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key_guard::DEV_PRIVATE_KEY;
+    use crate::test_support::spawn_http_node;
+    use std::sync::atomic::{AtomicI64, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_completion_and_progress() {
+        assert_eq!(confirmations(100, 100), 0);
+        assert_eq!(confirmations(100, 102), 2);
+        // A node lagging behind the one that finalized the block
+        assert_eq!(confirmations(100, 98), 0);
+
+        assert!(is_confirmed(100, 100, 0));
+        assert!(!is_confirmed(100, 102, 3));
+        assert!(is_confirmed(100, 103, 3));
+        assert!(is_confirmed(100, 110, 3));
+
+        assert_eq!(
+            progress_message(1, 3),
+            "finalized, waiting for 3 more confirmations: 1/3"
+        );
+        assert_eq!(
+            progress_message(0, 1),
+            "finalized, waiting for 1 more confirmation: 0/1"
+        );
+        assert_eq!(progress_message(5, 3).rsplit(' ').next(), Some("3/3"));
+    }
+
+    fn wait(depth: u32, timeout: Duration) -> ConfirmationWait {
+        ConfirmationWait {
+            depth,
+            poll_interval: Duration::from_millis(1),
+            deadline: Instant::now() + timeout,
+        }
+    }
+
+    /// Mock node with the target block at height 10 and a last finalized
+    /// block that advances one height per lookup from 10
+    async fn advancing_node() -> u16 {
+        let lfb = Arc::new(AtomicI64::new(10));
+        spawn_http_node(move |line, _| {
+            let block = if line.contains("/api/last-finalized-block") {
+                let height = lfb.fetch_add(1, Ordering::SeqCst);
+                serde_json::json!({"blockInfo": {"blockHash": "lfb", "blockNumber": height}})
+            } else if line.contains("/api/block/target") {
+                serde_json::json!({"blockInfo": {"blockHash": "target", "blockNumber": 10}})
+            } else {
+                return ("404 Not Found", String::new());
+            };
+            ("200 OK", block.to_string())
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_waits_for_depth() {
+        let port = advancing_node().await;
+        let api = F1r3flyApi::new(DEV_PRIVATE_KEY, "127.0.0.1", 1).unwrap();
+        let mut seen = Vec::new();
+        let achieved = wait_for_confirmations(
+            &api,
+            port,
+            "target",
+            None,
+            wait(3, Duration::from_secs(10)),
+            |n| seen.push(n),
+        )
+        .await
+        .unwrap();
+        assert_eq!(achieved, 3);
+        assert_eq!(seen, [0, 1, 2]);
+
+        let err = wait_for_confirmations(
+            &api,
+            port,
+            "target",
+            Some(1_000),
+            wait(3, Duration::ZERO),
+            |_| {},
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("0/3"), "{}", err);
+        assert!(wait_for_confirmations(
+            &api,
+            port,
+            "missing",
+            None,
+            wait(1, Duration::ZERO),
+            |_| {},
+        )
+        .await
+        .is_err());
+    }
+}
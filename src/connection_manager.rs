@@ -2,12 +2,30 @@
 ///
 /// Manages connections to F1r3fly nodes with connection reuse and pooling.
 /// Provides a high-level async API for deploying Rholang code and querying state.
-use crate::f1r3fly_api::F1r3flyApi;
-use crate::utils::CryptoUtils;
-use crate::vault::{build_transfer_rholang, TransferResult};
+use crate::capabilities::{self, Capability, NodeCapabilities};
+#[cfg(feature = "ws")]
+use crate::chain_head::{ChainHead, ChainHeadStats};
+use crate::error::{DeployRejection, NodeCliError};
+#[cfg(feature = "ws")]
+use crate::f1r3fly_api::BlockSummary;
+use crate::f1r3fly_api::{
+    DeployExpiry, F1r3flyApi, IncludedDeploy, InclusionOutcome, SubmittedDeploy,
+};
+use crate::grpc::DEPLOY_VALIDITY_WINDOW_BLOCKS;
+use crate::payload::DEFAULT_LANGUAGE;
+use crate::ports;
+use crate::timings;
+use crate::utils::{format_duration, parse_duration, Backoff, CryptoUtils};
+use crate::vault::TransferResult;
 use log;
 use secp256k1::PublicKey;
 use std::env;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const DEFAULT_DEPLOY_TIMEOUT: Duration = Duration::from_secs(60);
+const DEFAULT_FINALIZATION_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(2);
 
 /// Configuration for F1r3fly node connection
 #[derive(Debug, Clone)]
@@ -20,12 +38,16 @@ pub struct ConnectionConfig {
     pub observer_host: Option<String>,
     /// Observer node gRPC port for finalization checks (defaults to 40452)
     pub observer_grpc_port: u16,
-    /// Maximum seconds to wait for deploy inclusion in a block (default: 60)
-    pub deploy_timeout_secs: u32,
-    /// Maximum seconds to wait for block finalization (default: 30)
-    pub finalization_timeout_secs: u32,
-    /// Interval between polling attempts in seconds (default: 2)
-    pub poll_interval_secs: u64,
+    /// Maximum wait for deploy inclusion in a block (default: 60s)
+    pub deploy_timeout: Duration,
+    /// Maximum wait for block finalization (default: 30s)
+    pub finalization_timeout: Duration,
+    /// First interval between inclusion and finalization polls (default: 2s)
+    pub poll_interval: Duration,
+    /// Longest interval the polls back off to (default: 4 times `poll_interval`)
+    pub poll_max: Option<Duration>,
+    /// Deploy lifespan in blocks (default: the node's reported lifespan, else 50)
+    pub deploy_lifespan: Option<i64>,
 }
 
 impl ConnectionConfig {
@@ -34,10 +56,15 @@ impl ConnectionConfig {
     /// # Environment Variables
     ///
     /// - `FIREFLY_HOST`: Node hostname (default: "localhost")
-    /// - `FIREFLY_GRPC_PORT`: gRPC port (default: 40401)
-    /// - `FIREFLY_HTTP_PORT`: HTTP port (default: 40403)
+    /// - `FIREFLY_GRPC_PORT`: gRPC port (default: the bootstrap node's external port, 40401)
+    /// - `FIREFLY_HTTP_PORT`: HTTP port (default: the bootstrap node's, 40403)
     /// - `FIREFLY_PRIVATE_KEY`: Private key for signing (REQUIRED)
-    /// - `FIREFLY_DEPLOY_TIMEOUT`: Max seconds to wait for deploy inclusion in a block (default: 180)
+    /// - `FIREFLY_DEPLOY_TIMEOUT`: Max wait for deploy inclusion in a block, e.g. `90s` or `2m`;
+    ///   a bare number is seconds (default: 60s)
+    /// - `FIREFLY_FINALIZATION_TIMEOUT`: Max wait for block finalization, like the above (default: 30s)
+    /// - `FIREFLY_POLL_INITIAL`: First interval between polls, like the above (default: 2s)
+    /// - `FIREFLY_POLL_MAX`: Longest interval between polls, like the above (default: 4 times the first)
+    /// - `FIREFLY_DEPLOY_LIFESPAN`: Deploy lifespan in blocks (default: reported by the node)
     pub fn from_env() -> Result<Self, ConnectionError> {
         let signing_key =
             env::var("FIREFLY_PRIVATE_KEY").map_err(|_| ConnectionError::MissingPrivateKey)?;
@@ -47,26 +74,35 @@ impl ConnectionConfig {
             grpc_port: env::var("FIREFLY_GRPC_PORT")
                 .ok()
                 .and_then(|p| p.parse().ok())
-                .unwrap_or(40401),
+                .unwrap_or(ports::BOOTSTRAP.grpc_external),
             http_port: env::var("FIREFLY_HTTP_PORT")
                 .ok()
                 .and_then(|p| p.parse().ok())
-                .unwrap_or(40403),
+                .unwrap_or(ports::BOOTSTRAP.http),
             signing_key,
             observer_host: env::var("FIREFLY_OBSERVER_HOST").ok(),
             observer_grpc_port: env::var("FIREFLY_OBSERVER_GRPC_PORT")
                 .ok()
                 .and_then(|p| p.parse().ok())
-                .unwrap_or(40452),
-            deploy_timeout_secs: env::var("FIREFLY_DEPLOY_TIMEOUT")
+                .unwrap_or(ports::OBSERVER.grpc_internal),
+            deploy_timeout: env::var("FIREFLY_DEPLOY_TIMEOUT")
+                .ok()
+                .and_then(|t| parse_duration(&t, "s").ok())
+                .unwrap_or(DEFAULT_DEPLOY_TIMEOUT),
+            finalization_timeout: env::var("FIREFLY_FINALIZATION_TIMEOUT")
+                .ok()
+                .and_then(|t| parse_duration(&t, "s").ok())
+                .unwrap_or(DEFAULT_FINALIZATION_TIMEOUT),
+            poll_interval: env::var("FIREFLY_POLL_INITIAL")
                 .ok()
-                .and_then(|t| t.parse().ok())
-                .unwrap_or(60),
-            finalization_timeout_secs: env::var("FIREFLY_FINALIZATION_TIMEOUT")
+                .and_then(|t| parse_duration(&t, "s").ok())
+                .unwrap_or(DEFAULT_POLL_INTERVAL),
+            poll_max: env::var("FIREFLY_POLL_MAX")
                 .ok()
-                .and_then(|t| t.parse().ok())
-                .unwrap_or(30),
-            poll_interval_secs: 2,
+                .and_then(|t| parse_duration(&t, "s").ok()),
+            deploy_lifespan: env::var("FIREFLY_DEPLOY_LIFESPAN")
+                .ok()
+                .and_then(|l| l.parse().ok()),
         })
     }
 
@@ -78,13 +114,23 @@ impl ConnectionConfig {
             http_port,
             signing_key,
             observer_host: None,
-            observer_grpc_port: 40452,
-            deploy_timeout_secs: 60,
-            finalization_timeout_secs: 30,
-            poll_interval_secs: 2,
+            observer_grpc_port: ports::OBSERVER.grpc_internal,
+            deploy_timeout: DEFAULT_DEPLOY_TIMEOUT,
+            finalization_timeout: DEFAULT_FINALIZATION_TIMEOUT,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            poll_max: None,
+            deploy_lifespan: None,
         }
     }
 
+    /// Delays between inclusion and finalization polls
+    ///
+    /// Each wait makes as many polls as fit in its timeout, so the timeouts
+    /// keep their wall-clock meaning.
+    pub fn poll_backoff(&self) -> Backoff {
+        Backoff::up_to(self.poll_interval, self.poll_max)
+    }
+
     /// Set observer node for finalization checks
     pub fn with_observer(mut self, host: String, grpc_port: u16) -> Self {
         self.observer_host = Some(host);
@@ -102,6 +148,45 @@ pub enum ConnectionError {
     ConnectionFailed(String),
     /// Failed to execute operation
     OperationFailed(String),
+    /// The node refused the deploy
+    DeployRejected {
+        reason: DeployRejection,
+        raw: String,
+    },
+    /// The node's version predates a feature the operation needs
+    Unsupported(String),
+    /// The chain tip passed the deploy's last valid block before it was included
+    DeployExpired {
+        deploy_id: String,
+        expired_at: i64,
+        tip: i64,
+    },
+    /// A wait for the deploy ran out of time
+    TimedOut {
+        phase: WaitPhase,
+        deploy_id: String,
+        /// Block the deploy is in, once it has been included
+        block_hash: Option<String>,
+        waited: Duration,
+    },
+}
+
+/// Phase of a deploy wait that can time out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaitPhase {
+    /// Waiting for the deploy to be included in a block
+    Inclusion,
+    /// Waiting for the deploy's block to be finalized
+    Finalization,
+}
+
+impl std::fmt::Display for WaitPhase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Inclusion => write!(f, "block inclusion"),
+            Self::Finalization => write!(f, "finalization"),
+        }
+    }
 }
 
 impl std::fmt::Display for ConnectionError {
@@ -112,28 +197,106 @@ impl std::fmt::Display for ConnectionError {
             }
             Self::ConnectionFailed(e) => write!(f, "Connection failed: {}", e),
             Self::OperationFailed(e) => write!(f, "Operation failed: {}", e),
+            Self::DeployRejected { reason, .. } => write!(f, "Deploy rejected: {}", reason),
+            Self::Unsupported(e) => write!(f, "{}", e),
+            Self::DeployExpired {
+                expired_at, tip, ..
+            } => write!(
+                f,
+                "Deploy expired at block {} (tip is {}); it will never be included, resubmit",
+                expired_at, tip
+            ),
+            Self::TimedOut {
+                phase: WaitPhase::Inclusion,
+                deploy_id,
+                waited,
+                ..
+            } => write!(
+                f,
+                "Timed out after {} waiting for block inclusion of deploy {}",
+                format_duration(*waited),
+                deploy_id
+            ),
+            Self::TimedOut {
+                phase: WaitPhase::Finalization,
+                deploy_id,
+                block_hash,
+                waited,
+            } => write!(
+                f,
+                "Timed out after {} waiting for finalization of block {}; deploy {} is included",
+                format_duration(*waited),
+                block_hash.as_deref().unwrap_or("?"),
+                deploy_id
+            ),
         }
     }
 }
 
 impl std::error::Error for ConnectionError {}
 
+impl From<ConnectionError> for NodeCliError {
+    fn from(err: ConnectionError) -> Self {
+        match err {
+            ConnectionError::DeployExpired {
+                expired_at, tip, ..
+            } => NodeCliError::deploy_expired(expired_at, tip),
+            other => NodeCliError::General(other.to_string()),
+        }
+    }
+}
+
+impl ConnectionError {
+    /// Keep a classified deploy rejection, wrapping any other deploy failure
+    fn from_deploy_error(err: Box<dyn std::error::Error>) -> Self {
+        match err
+            .downcast_ref::<NodeCliError>()
+            .and_then(NodeCliError::deploy_rejection)
+        {
+            Some((reason, raw)) => Self::DeployRejected {
+                reason: reason.clone(),
+                raw: raw.to_string(),
+            },
+            None => Self::OperationFailed(format!("Deploy failed: {}", err)),
+        }
+    }
+
+    /// The classified rejection and raw node message, if the node refused the deploy
+    pub fn deploy_rejection(&self) -> Option<(&DeployRejection, &str)> {
+        match self {
+            Self::DeployRejected { reason, raw } => Some((reason, raw)),
+            _ => None,
+        }
+    }
+}
+
 /// Manages F1r3fly node connections with connection reuse
 #[derive(Clone)]
 pub struct F1r3flyConnectionManager {
     config: ConnectionConfig,
+    /// The node's version, fetched once and shared by every clone
+    capabilities: Arc<NodeCapabilities>,
+    /// Finalized block subscriptions, one event stream shared by every clone
+    #[cfg(feature = "ws")]
+    chain_head: Arc<ChainHead>,
 }
 
 impl F1r3flyConnectionManager {
     /// Create a new connection manager from environment variables
     pub fn from_env() -> Result<Self, ConnectionError> {
         let config = ConnectionConfig::from_env()?;
-        Ok(Self { config })
+        Ok(Self::new(config))
     }
 
     /// Create a new connection manager with explicit configuration
     pub fn new(config: ConnectionConfig) -> Self {
-        Self { config }
+        let capabilities = capabilities::for_node(&config.node_host, config.http_port);
+        Self {
+            #[cfg(feature = "ws")]
+            chain_head: ChainHead::new(&config.node_host, config.http_port),
+            config,
+            capabilities,
+        }
     }
 
     /// Get the connection configuration
@@ -141,6 +304,40 @@ impl F1r3flyConnectionManager {
         &self.config
     }
 
+    /// Fail fast if the node is known not to support `capability`
+    ///
+    /// The node's version is read from `/api/status` on first use; see
+    /// [`crate::capabilities`].
+    pub async fn require(&self, capability: Capability) -> Result<(), ConnectionError> {
+        self.capabilities
+            .require(capability)
+            .await
+            .map_err(|e| ConnectionError::Unsupported(e.to_string()))
+    }
+
+    /// Receive every block finalized from now on
+    ///
+    /// All subscriptions, across clones of the manager, share one background
+    /// task that follows the node's `/ws/events` (on the HTTP port), looks up
+    /// each finalized block and reconnects and backfills after a disconnect.
+    /// It starts with the first subscription and stops when the last receiver
+    /// is dropped. A receiver more than
+    /// [`CHANNEL_CAPACITY`](crate::chain_head::CHANNEL_CAPACITY) blocks behind
+    /// gets `RecvError::Lagged` instead of slowing the others down. See
+    /// [`crate::chain_head`].
+    ///
+    /// Must be called within a tokio runtime.
+    #[cfg(feature = "ws")]
+    pub fn subscribe_finalized(&self) -> tokio::sync::broadcast::Receiver<BlockSummary> {
+        self.chain_head.subscribe()
+    }
+
+    /// Subscribers, deliveries, lag and reconnects of [`subscribe_finalized`](Self::subscribe_finalized)
+    #[cfg(feature = "ws")]
+    pub fn finalized_stats(&self) -> ChainHeadStats {
+        self.chain_head.stats()
+    }
+
     fn api(&self) -> Result<F1r3flyApi<'_>, ConnectionError> {
         F1r3flyApi::new(
             &self.config.signing_key,
@@ -186,8 +383,20 @@ impl F1r3flyConnectionManager {
 
     /// Deploy Rholang code to the blockchain
     pub async fn deploy(&self, rholang_code: &str) -> Result<String, ConnectionError> {
+        self.deploy_with_language(rholang_code, DEFAULT_LANGUAGE)
+            .await
+    }
+
+    /// Deploy a term in `language` to the blockchain
+    ///
+    /// Binary payloads must already be base64-encoded; see [`crate::payload`].
+    pub async fn deploy_with_language(
+        &self,
+        term: &str,
+        language: &str,
+    ) -> Result<String, ConnectionError> {
         let api = self.api()?;
-        api.deploy_with_phlo_limit(rholang_code, 500_000, "rholang")
+        api.deploy_with_phlo_limit(term, 500_000, language)
             .await
             .map_err(|e| ConnectionError::OperationFailed(e.to_string()))
     }
@@ -218,8 +427,9 @@ impl F1r3flyConnectionManager {
         deploy_id: &str,
         max_attempts: u32,
     ) -> Result<String, ConnectionError> {
+        self.require(Capability::FindDeploy).await?;
         let api = self.api()?;
-        let check_interval_sec = 2;
+        let backoff = self.config.poll_backoff();
 
         for attempt in 1..=max_attempts {
             let result = api
@@ -240,7 +450,7 @@ impl F1r3flyConnectionManager {
                             max_attempts
                         )));
                     }
-                    tokio::time::sleep(tokio::time::Duration::from_secs(check_interval_sec)).await;
+                    backoff.wait(attempt - 1).await;
                 }
             }
         }
@@ -250,6 +460,37 @@ impl F1r3flyConnectionManager {
         ))
     }
 
+    /// Lifespan in blocks for deploys to this node: the configured value, else
+    /// the one the node reports in `/api/status`, else the 50-block default
+    pub async fn deploy_lifespan(&self) -> Result<i64, ConnectionError> {
+        if let Some(lifespan) = self.config.deploy_lifespan {
+            return Ok(lifespan);
+        }
+        let api = self.api()?;
+        let reported = match api.get_node_status(self.config.http_port).await {
+            Ok(status) => status.and_then(|s| s.deploy_lifespan),
+            Err(e) => {
+                tracing::debug!("Node status not available: {}", e);
+                None
+            }
+        };
+        Ok(reported.unwrap_or(DEPLOY_VALIDITY_WINDOW_BLOCKS))
+    }
+
+    /// Validity window of a submitted deploy, if its valid-after block is known
+    async fn deploy_expiry(
+        &self,
+        submitted: &SubmittedDeploy,
+    ) -> Result<Option<DeployExpiry>, ConnectionError> {
+        let Some(valid_after_block_number) = submitted.valid_after_block_number else {
+            return Ok(None);
+        };
+        Ok(Some(DeployExpiry {
+            valid_after_block_number,
+            lifespan: self.deploy_lifespan().await?,
+        }))
+    }
+
     /// Wait for a block to be finalized (uses observer node if configured)
     pub async fn wait_for_finalization(
         &self,
@@ -257,10 +498,8 @@ impl F1r3flyConnectionManager {
         max_attempts: u32,
     ) -> Result<(), ConnectionError> {
         let api = self.observer_api()?;
-        let retry_delay_sec = 5;
-
         let is_finalized = api
-            .is_finalized(block_hash, max_attempts, retry_delay_sec)
+            .is_finalized_with_backoff(block_hash, max_attempts, &self.config.poll_backoff())
             .await
             .map_err(|e| ConnectionError::OperationFailed(e.to_string()))?;
 
@@ -277,39 +516,115 @@ impl F1r3flyConnectionManager {
     /// Deploy Rholang code, wait for finalization, and read result
     ///
     /// 1. Deploy the code via gRPC
-    /// 2. Poll until the deploy appears in a block
+    /// 2. Poll until the deploy appears in a block, giving up early if the
+    ///    chain tip passes the deploy's validity window
     /// 3. Wait for the block to be finalized (via observer)
     /// 4. Read the deployId channel data from the finalized block
-    /// 5. Get deploy execution details (cost, errored)
+    /// 5. Get deploy execution details (cost, errored), from the HTTP detail
+    ///    view or else the block's deploy list over gRPC
     pub async fn deploy_and_wait(
         &self,
         rholang_code: &str,
         bigger_phlo: bool,
         expiration_timestamp: i64,
     ) -> Result<crate::f1r3fly_api::DeployResult, ConnectionError> {
-        let api = self.api()?;
+        self.deploy_and_wait_with_language(
+            rholang_code,
+            DEFAULT_LANGUAGE,
+            bigger_phlo,
+            expiration_timestamp,
+        )
+        .await
+    }
 
-        // Phase 1: Deploy
-        let deploy_id = api
-            .deploy(rholang_code, bigger_phlo, "rholang", expiration_timestamp)
+    /// [`Self::deploy_and_wait`] for a term in `language`
+    pub async fn deploy_and_wait_with_language(
+        &self,
+        term: &str,
+        language: &str,
+        bigger_phlo: bool,
+        expiration_timestamp: i64,
+    ) -> Result<crate::f1r3fly_api::DeployResult, ConnectionError> {
+        let submitted = self
+            .submit(term, language, bigger_phlo, expiration_timestamp)
+            .await?;
+        let expiry = self.deploy_expiry(&submitted).await?;
+        self.wait_for_deploy_result(&submitted.deploy_id, expiry)
             .await
-            .map_err(|e| ConnectionError::OperationFailed(format!("Deploy failed: {}", e)))?;
-        tracing::info!(deploy_id = %deploy_id, "Deploy submitted");
+    }
 
-        // Phase 2: Wait for block inclusion
-        let max_block_wait =
-            (self.config.deploy_timeout_secs as u64 / self.config.poll_interval_secs) as u32;
-        let block_hash = self.wait_for_deploy(&deploy_id, max_block_wait).await?;
-        tracing::info!(block_hash = %block_hash, "Deploy included in block");
+    /// Phases 1 and 2 of [`Self::deploy_and_wait_with_language`]: deploy and
+    /// wait for block inclusion, without waiting for finalization
+    ///
+    /// Deploy data and execution details are not read, since the block may not
+    /// have been replayed yet on the node asked.
+    pub async fn deploy_and_wait_for_block(
+        &self,
+        term: &str,
+        language: &str,
+        bigger_phlo: bool,
+        expiration_timestamp: i64,
+    ) -> Result<IncludedDeploy, ConnectionError> {
+        let submitted = self
+            .submit(term, language, bigger_phlo, expiration_timestamp)
+            .await?;
+        let expiry = self.deploy_expiry(&submitted).await?;
+        let block_hash = self.wait_for_block(&submitted.deploy_id, expiry).await?;
+        Ok(IncludedDeploy {
+            deploy_id: submitted.deploy_id,
+            block_hash,
+        })
+    }
+
+    /// Phase 1: send the deploy
+    async fn submit(
+        &self,
+        term: &str,
+        language: &str,
+        bigger_phlo: bool,
+        expiration_timestamp: i64,
+    ) -> Result<SubmittedDeploy, ConnectionError> {
+        let api = self.api()?;
+        let phase_start = Instant::now();
+        let submitted = api
+            .submit_deploy(term, bigger_phlo, language, expiration_timestamp)
+            .await
+            .map_err(ConnectionError::from_deploy_error)?;
+        tracing::info!(deploy_id = %submitted.deploy_id, "Deploy submitted");
+        timings::phase("submit", phase_start.elapsed());
+        Ok(submitted)
+    }
+
+    /// Wait for a deploy already sent to the network to be finalized, and read its result
+    ///
+    /// Phases 2 to 5 of [`Self::deploy_and_wait`], also used to resume a wait
+    /// (`wait-for-deploy`). Without `expiry` an expired deploy is not noticed
+    /// and the wait runs until the deploy timeout.
+    pub async fn wait_for_deploy_result(
+        &self,
+        deploy_id: &str,
+        expiry: Option<DeployExpiry>,
+    ) -> Result<crate::f1r3fly_api::DeployResult, ConnectionError> {
+        let api = self.api()?;
+        let block_hash = self.wait_for_block(deploy_id, expiry).await?;
+        let deploy_id = deploy_id.to_string();
 
         // Phase 3: Wait for finalization (via observer)
-        let finalization_poll_secs: u64 = 5;
-        let max_finalization =
-            (self.config.finalization_timeout_secs as u64 / finalization_poll_secs) as u32;
-        let max_finalization = max_finalization.max(1);
-        self.wait_for_finalization(&block_hash, max_finalization)
-            .await?;
+        let phase_start = Instant::now();
+        let max_finalization = self
+            .config
+            .poll_backoff()
+            .polls_within(self.config.finalization_timeout);
+        if !self.finalized_within(&block_hash, max_finalization).await? {
+            return Err(ConnectionError::TimedOut {
+                phase: WaitPhase::Finalization,
+                deploy_id,
+                block_hash: Some(block_hash),
+                waited: self.config.finalization_timeout,
+            });
+        }
         tracing::info!("Block finalized");
+        timings::phase("finalization", phase_start.elapsed());
 
         // Phase 4: Read deploy result AFTER finalization
         // Empty data is normal when the contract doesn't write to deployId
@@ -338,19 +653,98 @@ impl F1r3flyConnectionManager {
                 None
             }
         };
+        if let Some(detail) = detail {
+            return Ok(crate::f1r3fly_api::DeployResult {
+                deploy_id,
+                block_hash,
+                block_number: Some(detail.block_number),
+                cost: Some(detail.cost),
+                errored: detail.errored,
+                system_deploy_error: detail.system_deploy_error.filter(|s| !s.is_empty()),
+                data,
+            });
+        }
 
+        // Without the detail view, the block's own deploy list has the outcome
+        let deploy = match api.get_block_deploy(&block_hash, &deploy_id).await {
+            Ok(deploy) => deploy,
+            Err(e) => {
+                tracing::warn!("Deploy execution outcome not available: {}", e);
+                None
+            }
+        };
         Ok(crate::f1r3fly_api::DeployResult {
             deploy_id,
             block_hash,
-            block_number: detail.as_ref().map(|d| d.block_number),
-            cost: detail.as_ref().map(|d| d.cost),
-            errored: detail.as_ref().map(|d| d.errored).unwrap_or(false),
-            system_deploy_error: detail
-                .and_then(|d| d.system_deploy_error.filter(|s| !s.is_empty())),
+            block_number: None,
+            cost: deploy.as_ref().map(|d| d.cost),
+            errored: deploy.as_ref().is_some_and(|d| d.errored),
+            system_deploy_error: deploy
+                .map(|d| d.system_deploy_error)
+                .filter(|s| !s.is_empty()),
             data,
         })
     }
 
+    /// Phase 2: wait for the deploy to be included in a block, returning the block hash
+    ///
+    /// Without `expiry` an expired deploy is not noticed and the wait runs
+    /// until the deploy timeout.
+    pub async fn wait_for_block(
+        &self,
+        deploy_id: &str,
+        expiry: Option<DeployExpiry>,
+    ) -> Result<String, ConnectionError> {
+        let api = self.api()?;
+        let phase_start = Instant::now();
+        let backoff = self.config.poll_backoff();
+        let outcome = api
+            .wait_for_inclusion(
+                deploy_id,
+                self.config.http_port,
+                expiry,
+                &backoff,
+                backoff.polls_within(self.config.deploy_timeout),
+            )
+            .await
+            .map_err(|e| ConnectionError::OperationFailed(e.to_string()))?;
+        let block_hash = match outcome {
+            InclusionOutcome::Included { block_hash } => block_hash,
+            InclusionOutcome::Expired { expired_at, tip } => {
+                return Err(ConnectionError::DeployExpired {
+                    deploy_id: deploy_id.to_string(),
+                    expired_at,
+                    tip,
+                })
+            }
+            InclusionOutcome::TimedOut { attempts } => {
+                tracing::debug!(deploy_id, attempts, "Deploy not included in time");
+                return Err(ConnectionError::TimedOut {
+                    phase: WaitPhase::Inclusion,
+                    deploy_id: deploy_id.to_string(),
+                    block_hash: None,
+                    waited: self.config.deploy_timeout,
+                });
+            }
+        };
+        tracing::info!(block_hash = %block_hash, "Deploy included in block");
+        timings::phase("inclusion", phase_start.elapsed());
+        Ok(block_hash)
+    }
+
+    /// Phase 3: whether the block is finalized within `max_attempts` polls
+    /// (uses observer node if configured)
+    async fn finalized_within(
+        &self,
+        block_hash: &str,
+        max_attempts: u32,
+    ) -> Result<bool, ConnectionError> {
+        self.observer_api()?
+            .is_finalized_with_backoff(block_hash, max_attempts, &self.config.poll_backoff())
+            .await
+            .map_err(|e| ConnectionError::OperationFailed(e.to_string()))
+    }
+
     /// Get direct access to the underlying F1r3flyApi
     pub fn get_api(&self) -> Result<F1r3flyApi<'_>, ConnectionError> {
         self.api()
@@ -384,7 +778,10 @@ impl F1r3flyConnectionManager {
             to_address
         );
 
-        let rholang = build_transfer_rholang(&from_address, to_address, amount_dust);
+        // The same checked template the CLI deploys; a broken one is refused here too
+        let rholang =
+            crate::contract_templates::transfer_contract(&from_address, to_address, amount_dust)
+                .map_err(|e| ConnectionError::OperationFailed(e.to_string()))?;
 
         let result = self.deploy_and_wait(&rholang, false, 0).await?;
 
@@ -449,5 +846,119 @@ mod tests {
         assert_eq!(config.grpc_port, 9000);
         assert_eq!(config.http_port, 9001);
         assert_eq!(config.signing_key, "my_key");
+        assert_eq!(config.deploy_lifespan, None);
+    }
+
+    fn manager_for(http_port: u16, deploy_lifespan: Option<i64>) -> F1r3flyConnectionManager {
+        let mut config = ConnectionConfig::new(
+            "127.0.0.1".to_string(),
+            1,
+            http_port,
+            crate::key_guard::DEV_PRIVATE_KEY.to_string(),
+        );
+        config.deploy_lifespan = deploy_lifespan;
+        F1r3flyConnectionManager::new(config)
+    }
+
+    #[tokio::test]
+    async fn test_deploy_lifespan_sources() {
+        let reporting = crate::test_support::spawn_http_node(|_, _| {
+            (
+                "200 OK",
+                serde_json::json!({
+                    "version": "test", "address": "a", "networkId": "n", "shardId": "root",
+                    "peers": 0, "nodes": 0, "minPhloPrice": 1, "deployLifespan": 20
+                })
+                .to_string(),
+            )
+        })
+        .await;
+        let silent =
+            crate::test_support::spawn_http_node(|_, _| ("404 Not Found", "{}".to_string())).await;
+
+        // Configured value wins, then the node's status, then the default
+        let lifespan = |port: u16, configured: Option<i64>| async move {
+            manager_for(port, configured)
+                .deploy_lifespan()
+                .await
+                .unwrap()
+        };
+        assert_eq!(lifespan(reporting, Some(7)).await, 7);
+        assert_eq!(lifespan(reporting, None).await, 20);
+        assert_eq!(lifespan(silent, None).await, DEPLOY_VALIDITY_WINDOW_BLOCKS);
+    }
+
+    #[cfg(feature = "ws")]
+    #[tokio::test]
+    async fn test_clones_share_one_finalized_stream() {
+        use crate::test_support::{spawn_event_node, BlockFixture, WsSession};
+
+        let node = spawn_event_node(
+            vec![WsSession::stay(
+                vec![BlockFixture::new(3).finalized_event()],
+            )],
+            |line, _| {
+                if line.contains("/api/block/b3") {
+                    ("200 OK", BlockFixture::new(3).to_json().to_string())
+                } else {
+                    ("404 Not Found", "{}".to_string())
+                }
+            },
+        )
+        .await;
+        let manager = manager_for(node.port, None);
+        let clone = manager.clone();
+        let mut first = manager.subscribe_finalized();
+        let mut second = clone.subscribe_finalized();
+        assert_eq!(manager.finalized_stats().subscribers, 2);
+
+        assert_eq!(first.recv().await.unwrap(), BlockFixture::new(3).summary());
+        assert_eq!(second.recv().await.unwrap().block_hash, "b3");
+        assert_eq!(clone.finalized_stats().starts, 1);
+        assert_eq!(node.accepted(), 1);
+    }
+
+    #[test]
+    fn test_deploy_expired_maps_to_cli_error() {
+        let err = ConnectionError::DeployExpired {
+            deploy_id: "d1".to_string(),
+            expired_at: 150,
+            tip: 151,
+        };
+        assert_eq!(
+            err.to_string(),
+            "Deploy expired at block 150 (tip is 151); it will never be included, resubmit"
+        );
+        let cli = NodeCliError::from(err);
+        assert_eq!(cli.exit_code(), crate::error::EXIT_DEPLOY_EXPIRED);
+
+        let other = NodeCliError::from(ConnectionError::OperationFailed("x".to_string()));
+        assert_eq!(other.exit_code(), 1);
+    }
+
+    #[test]
+    fn test_timed_out_names_the_phase() {
+        let inclusion = ConnectionError::TimedOut {
+            phase: WaitPhase::Inclusion,
+            deploy_id: "d1".to_string(),
+            block_hash: None,
+            waited: Duration::from_secs(60),
+        };
+        assert_eq!(
+            inclusion.to_string(),
+            "Timed out after 1m waiting for block inclusion of deploy d1"
+        );
+
+        let finalization = ConnectionError::TimedOut {
+            phase: WaitPhase::Finalization,
+            deploy_id: "d1".to_string(),
+            block_hash: Some("b1".to_string()),
+            waited: Duration::from_secs(30),
+        };
+        assert_eq!(
+            finalization.to_string(),
+            "Timed out after 30s waiting for finalization of block b1; deploy d1 is included"
+        );
+        assert_eq!(NodeCliError::from(finalization).exit_code(), 1);
     }
 }
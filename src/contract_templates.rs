@@ -0,0 +1,476 @@
+//! Rholang contracts the CLI fills in before deploying
+//!
+//! Templates use named placeholders such as `{{to}}`, filled in by
+//! [`fill_placeholders`], so a value can only land where its name is. Each template also has a check that the
+//! filled-in contract still has the shape its values were meant for, done on
+//! the token stream from [`crate::term_analysis`] so braces and quotes inside
+//! literals are not mistaken for structure. A template edited so that a value
+//! lands in the wrong slot, or a placeholder is left unfilled, fails the
+//! check and nothing is deployed.
+//!
+//! The templates are compiled in, so the binary runs from any directory.
+//! `transfer --template-file` swaps in a template from disk, which gets the
+//! same check.
+
+use crate::error::{NodeCliError, Result};
+use crate::term_analysis::{tokenize, Token};
+use crate::utils::rho_helpers::fill_placeholders;
+use std::path::Path;
+
+/// Transfer from the deployer's vault, printing the outcome to stdout
+///
+/// Placeholders: `{{from}}`, `{{to}}`, `{{amount}}` (in dust).
+const TRANSFER_TEMPLATE: &str = r#"new
+ deployerId(`rho:system:deployerId`),
+ stdout(`rho:io:stdout`),
+ rl(`rho:registry:lookup`),
+ systemVaultCh,
+ vaultCh,
+ toVaultCh,
+ systemVaultKeyCh,
+ resultCh
+in {
+ rl!(`rho:vault:system`, *systemVaultCh) |
+ for (@(_, SystemVault) <- systemVaultCh) {
+ @SystemVault!("findOrCreate", "{{from}}", *vaultCh) |
+ @SystemVault!("findOrCreate", "{{to}}", *toVaultCh) |
+ @SystemVault!("deployerAuthKey", *deployerId, *systemVaultKeyCh) |
+ for (@(true, vault) <- vaultCh; key <- systemVaultKeyCh; @(true, toVault) <- toVaultCh) {
+ @vault!("transfer", "{{to}}", {{amount}}, *key, *resultCh) |
+ for (@result <- resultCh) {
+ match result {
+ (true, Nil) => {
+ stdout!(("Transfer successful:", {{amount}}, "tokens"))
+ }
+ (false, reason) => {
+ stdout!(("Transfer failed:", reason))
+ }
+ }
+ }
+ } |
+ for (@(false, errorMsg) <- vaultCh) {
+ stdout!(("Sender vault error:", errorMsg))
+ } |
+ for (@(false, errorMsg) <- toVaultCh) {
+ stdout!(("Destination vault error:", errorMsg))
+ }
+ }
+}"#;
+
+/// A PoS method called with the deployer's ID, printing the PoS reply to stdout
+///
+/// Placeholders: `{{method}}`, the PoS method; `{{label}}`, which introduces
+/// the reply; `{{args}}`, the arguments after the deployer's ID, each
+/// followed by `, ` (empty when there are none).
+const POS_CALL_TEMPLATE: &str = r#"new rl(`rho:registry:lookup`), poSCh, retCh, stdout(`rho:io:stdout`) in {
+ stdout!("About to lookup PoS contract...") |
+ rl!(`rho:system:pos`, *poSCh) |
+ for(@(_, PoS) <- poSCh) {
+ stdout!("About to {{method}}...") |
+ new deployerId(`rho:system:deployerId`) in {
+ @PoS!("{{method}}", *deployerId, {{args}}*retCh) |
+ for (@(result, message) <- retCh) {
+ stdout!(("{{label}} result:", result, "Message:", message))
+ }
+ }
+ }
+}"#;
+
+/// The transfer contract deployed by `transfer` and `load-test`
+///
+/// Fails, pointing at the template, if the filled-in contract does not pass
+/// [`verify_transfer`].
+pub fn transfer_contract(from_address: &str, to_address: &str, amount_dust: u64) -> Result<String> {
+    render_transfer(TRANSFER_TEMPLATE, from_address, to_address, amount_dust).map_err(|problem| {
+        NodeCliError::General(format!(
+            "Refusing to deploy: the transfer contract template is broken ({}). \
+             Check TRANSFER_TEMPLATE in src/contract_templates.rs.",
+            problem
+        ))
+    })
+}
+
+/// The transfer contract from a template file (`transfer --template-file`)
+///
+/// The file uses the built-in template's placeholders and must pass
+/// [`verify_transfer`], so it can change the contract around the transfer
+/// call but not where the tokens go or how many.
+pub fn transfer_contract_from_file(
+    path: &Path,
+    from_address: &str,
+    to_address: &str,
+    amount_dust: u64,
+) -> Result<String> {
+    let template = std::fs::read_to_string(path)
+        .map_err(|e| NodeCliError::file_read_failed(&path.display().to_string(), &e.to_string()))?;
+    render_transfer(&template, from_address, to_address, amount_dust).map_err(|problem| {
+        NodeCliError::General(format!(
+            "Refusing to deploy: the transfer template {} does not check out ({})",
+            path.display(),
+            problem
+        ))
+    })
+}
+
+fn render_transfer(
+    template: &str,
+    from_address: &str,
+    to_address: &str,
+    amount_dust: u64,
+) -> std::result::Result<String, String> {
+    let amount = amount_dust.to_string();
+    let contract = fill_placeholders(
+        template,
+        &[
+            ("from", from_address),
+            ("to", to_address),
+            ("amount", &amount),
+        ],
+    )?;
+    verify_transfer(&contract, to_address, amount_dust)?;
+    Ok(contract)
+}
+
+/// Check a filled-in transfer contract
+///
+/// There is exactly one `@vault!("transfer", ...)`
+/// call, its target is the recipient's address literal and its amount is
+/// `amount_dust`; and the amount appears exactly twice, in that call and in
+/// the success message.
+pub fn verify_transfer(
+    contract: &str,
+    to_address: &str,
+    amount_dust: u64,
+) -> std::result::Result<(), String> {
+    let tokens = tokenize(contract);
+    let amount = amount_dust.to_string();
+    let calls: Vec<usize> = (3..tokens.len())
+        .filter(|&i| {
+            matches!(
+                tokens[i - 3..=i],
+                [
+                    Token::Word("vault"),
+                    Token::Punct,
+                    Token::Open,
+                    Token::Literal("\"transfer\"")
+                ]
+            )
+        })
+        .collect();
+    let [call] = calls[..] else {
+        return Err(format!(
+            "expected one vault transfer call, found {}",
+            calls.len()
+        ));
+    };
+
+    let recipient = format!("\"{}\"", to_address);
+    match tokens.get(call + 1..call + 5) {
+        Some(&[Token::Punct, Token::Literal(target), Token::Punct, Token::Word(sent)]) => {
+            if target != recipient {
+                return Err(format!(
+                    "the transfer goes to {} instead of the recipient {}",
+                    target, recipient
+                ));
+            }
+            if sent != amount {
+                return Err(format!(
+                    "the transfer sends {} instead of the amount {}",
+                    sent, amount
+                ));
+            }
+        }
+        _ => {
+            return Err(
+                "the transfer call does not start with a recipient and an amount".to_string(),
+            )
+        }
+    }
+
+    let amounts = tokens
+        .iter()
+        .filter(|token| **token == Token::Word(&amount))
+        .count();
+    if amounts != 2 {
+        return Err(format!(
+            "the amount {} appears {} times, expected 2 (the transfer and the success message)",
+            amount, amounts
+        ));
+    }
+    Ok(())
+}
+
+/// The bond contract deployed by `bond-validator`
+pub fn bond_contract(stake: u64) -> Result<String> {
+    pos_contract("bond", "Bond", Some(stake))
+}
+
+/// The contract deployed by `add-stake`, adding `amount` to the deployer's bond
+pub fn add_stake_contract(amount: u64) -> Result<String> {
+    pos_contract("addStake", "Add stake", Some(amount))
+}
+
+/// The contract deployed by `unbond-validator`, withdrawing the deployer's bond
+///
+/// PoS keeps the bond through quarantine and pays it back to the deployer's
+/// vault when it leaves the bonds map; see
+/// [`WithdrawalDelay`](crate::staking::WithdrawalDelay).
+pub fn withdraw_contract() -> Result<String> {
+    pos_contract("withdraw", "Withdraw", None)
+}
+
+/// PoS `method` called from [`POS_CALL_TEMPLATE`]
+///
+/// Fails, pointing at the template, if the filled-in contract does not pass
+/// [`verify_pos_call`].
+fn pos_contract(method: &str, label: &str, amount: Option<u64>) -> Result<String> {
+    render_pos_call(POS_CALL_TEMPLATE, method, label, amount).map_err(|problem| {
+        NodeCliError::General(format!(
+            "Refusing to deploy: the PoS contract template is broken ({}). \
+             Check POS_CALL_TEMPLATE in src/contract_templates.rs.",
+            problem
+        ))
+    })
+}
+
+fn render_pos_call(
+    template: &str,
+    method: &str,
+    label: &str,
+    amount: Option<u64>,
+) -> std::result::Result<String, String> {
+    let args = amount
+        .map(|amount| format!("{}, ", amount))
+        .unwrap_or_default();
+    let contract = fill_placeholders(
+        template,
+        &[("method", method), ("label", label), ("args", &args)],
+    )?;
+    verify_pos_call(&contract, method, amount)?;
+    Ok(contract)
+}
+
+/// Check a filled-in PoS contract
+///
+/// There is exactly one `@PoS!("<method>", ...)` call; it passes the
+/// deployer's ID, then `amount` when there is one, then the return channel.
+pub fn verify_pos_call(
+    contract: &str,
+    method: &str,
+    amount: Option<u64>,
+) -> std::result::Result<(), String> {
+    let tokens = tokenize(contract);
+    let name = format!("\"{}\"", method);
+    let calls: Vec<usize> = (3..tokens.len())
+        .filter(|&i| {
+            matches!(
+                tokens[i - 3..=i],
+                [
+                    Token::Word("PoS"),
+                    Token::Punct,
+                    Token::Open,
+                    Token::Literal(called)
+                ] if called == name
+            )
+        })
+        .collect();
+    let [call] = calls[..] else {
+        return Err(format!(
+            "expected one PoS {} call, found {}",
+            method,
+            calls.len()
+        ));
+    };
+
+    let Some(&[Token::Punct, Token::Punct, Token::Word("deployerId"), Token::Punct]) =
+        tokens.get(call + 1..call + 5)
+    else {
+        return Err(format!(
+            "the {} call does not start with the deployer's ID",
+            method
+        ));
+    };
+    match (amount, tokens.get(call + 5..call + 7)) {
+        (Some(amount), Some(&[Token::Word(sent), Token::Punct])) => {
+            if sent != amount.to_string() {
+                return Err(format!(
+                    "the {} call sends {} instead of the amount {}",
+                    method, sent, amount
+                ));
+            }
+            Ok(())
+        }
+        (None, Some(&[Token::Punct, Token::Word("retCh")])) => Ok(()),
+        (Some(_), _) => Err(format!(
+            "the {} call does not pass an amount after the deployer's ID",
+            method
+        )),
+        (None, _) => Err(format!(
+            "the {} call passes more than the deployer's ID",
+            method
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FROM: &str = "1111AtahZeefej4tvVR6ti9TJtv8yxLebT31SCEVDCKMNikBk5r3g";
+    const TO: &str = "111127RX5ZgiAdRaQy4AWy57RdvAAckdELReEBxzvWYVvdnR32PiHA";
+
+    fn corrupt(from: &str, to: &str) -> String {
+        assert!(TRANSFER_TEMPLATE.contains(from), "{}", from);
+        TRANSFER_TEMPLATE.replacen(from, to, 1)
+    }
+
+    #[test]
+    fn test_transfer_contract() {
+        let contract = transfer_contract(FROM, TO, 250_000_000).unwrap();
+        assert!(contract.contains(&format!(r#"@vault!("transfer", "{}", 250000000, *key"#, TO)));
+        assert!(contract.contains(&format!(r#"("findOrCreate", "{}", *vaultCh)"#, FROM)));
+        assert!(!contract.contains("{{"));
+        // Self-transfers are allowed
+        assert!(transfer_contract(FROM, FROM, 1).is_ok());
+    }
+
+    #[test]
+    fn test_swapped_recipient_is_refused() {
+        let template = corrupt(r#""transfer", "{{to}}""#, r#""transfer", "{{from}}""#);
+        let err = render_transfer(&template, FROM, TO, 5).unwrap_err();
+        assert!(err.contains("instead of the recipient"), "{}", err);
+    }
+
+    #[test]
+    fn test_misplaced_amount_is_refused() {
+        // The amount moved out of the transfer call
+        let template = corrupt(r#""{{to}}", {{amount}}, *key"#, r#""{{to}}", 0, *key"#);
+        let err = render_transfer(&template, FROM, TO, 5).unwrap_err();
+        assert!(err.contains("sends 0 instead of the amount 5"), "{}", err);
+
+        // Dropped from the success message
+        let template = corrupt(
+            r#"("Transfer successful:", {{amount}}"#,
+            r#"("Transfer successful:""#,
+        );
+        let err = render_transfer(&template, FROM, TO, 5).unwrap_err();
+        assert!(err.contains("appears 1 times"), "{}", err);
+
+        // Repeated somewhere else
+        let template = corrupt(
+            r#"("Transfer failed:", reason)"#,
+            r#"("Transfer failed:", reason, {{amount}})"#,
+        );
+        let err = render_transfer(&template, FROM, TO, 5).unwrap_err();
+        assert!(err.contains("appears 3 times"), "{}", err);
+    }
+
+    #[test]
+    fn test_unfilled_placeholder_is_refused() {
+        let template = corrupt("{{amount}}, *key", "{{amuont}}, *key");
+        let err = render_transfer(&template, FROM, TO, 5).unwrap_err();
+        assert_eq!(err, "placeholder {{amuont}} was not filled in");
+
+        let template = POS_CALL_TEMPLATE.replace("{{args}}", "{{stake}}");
+        let err = render_pos_call(&template, "bond", "Bond", Some(5)).unwrap_err();
+        assert_eq!(err, "placeholder {{stake}} was not filled in");
+    }
+
+    #[test]
+    fn test_transfer_call_count() {
+        let template = corrupt(r#"@vault!("transfer""#, r#"@vault!("send""#);
+        let err = render_transfer(&template, FROM, TO, 5).unwrap_err();
+        assert_eq!(err, "expected one vault transfer call, found 0");
+
+        // A quoted transfer call in a message is not a call
+        let template = corrupt(
+            r#"("Transfer failed:", reason)"#,
+            r#"("Transfer failed: @vault!(\"transfer\", ...)", reason)"#,
+        );
+        assert!(render_transfer(&template, FROM, TO, 5).is_ok());
+    }
+
+    #[test]
+    fn test_transfer_contract_from_file() {
+        let dir = std::env::temp_dir().join(format!("node_cli_templates_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("transfer.rho");
+
+        // A template that only changes the messages still renders
+        std::fs::write(
+            &path,
+            TRANSFER_TEMPLATE.replace("Transfer successful:", "Sent:"),
+        )
+        .unwrap();
+        let contract = transfer_contract_from_file(&path, FROM, TO, 7).unwrap();
+        assert!(contract.contains(r#"("Sent:", 7, "tokens")"#));
+        assert!(!contract.contains("{{"));
+
+        // One that moves the amount is refused, naming the file
+        std::fs::write(
+            &path,
+            TRANSFER_TEMPLATE.replace("{{amount}}, *key", "1, *key"),
+        )
+        .unwrap();
+        let err = transfer_contract_from_file(&path, FROM, TO, 7)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("transfer.rho"), "{}", err);
+
+        let missing = dir.join("missing.rho");
+        assert!(transfer_contract_from_file(&missing, FROM, TO, 7).is_err());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_a_quote_in_an_address_cannot_shift_the_call() {
+        let to = r#"1111abc", 999, *key, *resultCh) | @x!(""#;
+        assert!(transfer_contract(FROM, to, 5).is_err());
+    }
+
+    #[test]
+    fn test_pos_contracts() {
+        let contract = bond_contract(250_000_000).unwrap();
+        assert!(contract.contains(r#"@PoS!("bond", *deployerId, 250000000, *retCh)"#));
+        assert!(contract.contains(r#"("Bond result:", result"#));
+        assert!(!contract.contains("{{"));
+
+        let contract = add_stake_contract(250).unwrap();
+        assert!(contract.contains(r#"@PoS!("addStake", *deployerId, 250, *retCh)"#));
+        assert!(!contract.contains("{{"));
+
+        let contract = withdraw_contract().unwrap();
+        assert!(contract.contains(r#"@PoS!("withdraw", *deployerId, *retCh)"#));
+        assert!(!contract.contains("{{"));
+    }
+
+    #[test]
+    fn test_broken_pos_template_is_refused() {
+        let bond = |template: &str| render_pos_call(template, "bond", "Bond", Some(5));
+
+        let template = POS_CALL_TEMPLATE.replace("{{args}}*retCh", "0, *retCh");
+        assert_eq!(
+            bond(&template).unwrap_err(),
+            "the bond call sends 0 instead of the amount 5"
+        );
+
+        let template = POS_CALL_TEMPLATE.replace(r#"@PoS!("{{method}}""#, r#"@PoS!("other""#);
+        assert_eq!(
+            bond(&template).unwrap_err(),
+            "expected one PoS bond call, found 0"
+        );
+
+        let template = POS_CALL_TEMPLATE.replace("*deployerId, {{args}}", "{{args}}*deployerId, ");
+        let err = bond(&template).unwrap_err();
+        assert!(
+            err.contains("does not start with the deployer's ID"),
+            "{}",
+            err
+        );
+
+        // An amount slipped into a call that takes none
+        let template = POS_CALL_TEMPLATE.replace("{{args}}*retCh", "1, *retCh");
+        let err = render_pos_call(&template, "withdraw", "Withdraw", None).unwrap_err();
+        assert_eq!(err, "the withdraw call passes more than the deployer's ID");
+    }
+}
@@ -0,0 +1,362 @@
+//! Comparing block sightings across several nodes' event streams
+//!
+//! `watch-events --node a --node b ...` feeds every block event from every
+//! node into a [`CrossNodeTracker`]. The first node to report a block starts
+//! a clock; each other node's first report of it is a lag sample for that
+//! node. A node that has not reported the block once `--lag-threshold` has
+//! passed is [reported](CrossNodeTracker::check) once, which is how a node
+//! falling behind in gossip shows up while it happens.
+//!
+//! Memory stays bounded: a block is dropped as soon as every node has seen
+//! it, or once it is older than [`RETENTION_FACTOR`] thresholds, when the
+//! nodes still missing it count it as missed. At most [`MAX_TRACKED`] blocks
+//! are open at once, and hashes already dropped are remembered in a bounded
+//! list so later events for them (such as finalization after the block was
+//! added everywhere) do not open them again.
+
+use crate::truncation::{shorten, Field};
+use crate::utils::Summary;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::time::Duration;
+
+/// A block is given up on after this many lag thresholds
+pub const RETENTION_FACTOR: u32 = 5;
+/// Most blocks tracked at once; the oldest is dropped to make room
+pub const MAX_TRACKED: usize = 10_000;
+/// Lag samples kept per node for the summary
+pub const MAX_LAG_SAMPLES: usize = 10_000;
+
+/// A block one node reported and another has not, past the threshold
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LagAlert {
+    pub block_hash: String,
+    /// Node that reported the block first
+    pub seen_on: String,
+    /// Time since it did
+    pub ago: Duration,
+    pub missing_on: String,
+}
+
+impl fmt::Display for LagAlert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "block {} seen on {} {}s ago, not yet on {}",
+            shorten(&self.block_hash, Field::Column),
+            self.seen_on,
+            self.ago.as_secs(),
+            self.missing_on
+        )
+    }
+}
+
+/// One node's share of the session
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeLag {
+    pub name: String,
+    /// Blocks this node reported first
+    pub first: usize,
+    /// Blocks this node reported after another node, with how long after
+    pub lag: Option<Summary>,
+    /// Blocks reported past the threshold or not at all
+    pub late: usize,
+    /// Blocks given up on before this node reported them
+    pub missed: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Sighting {
+    first_node: usize,
+    first_at: i64,
+    /// When each node first reported the block
+    seen: Vec<Option<i64>>,
+    /// Nodes already reported as late for the block
+    alerted: Vec<bool>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct NodeTally {
+    first: usize,
+    lags: VecDeque<u64>,
+    late: usize,
+    missed: usize,
+}
+
+/// Open blocks and per-node lag over a watch session
+#[derive(Debug, Clone)]
+pub struct CrossNodeTracker {
+    nodes: Vec<String>,
+    threshold_ms: i64,
+    open: HashMap<String, Sighting>,
+    /// Open hashes, oldest first
+    order: VecDeque<String>,
+    /// Dropped hashes, oldest first, and the same as a set
+    retired: VecDeque<String>,
+    retired_set: HashSet<String>,
+    tallies: Vec<NodeTally>,
+}
+
+impl CrossNodeTracker {
+    pub fn new(nodes: Vec<String>, threshold: Duration) -> Self {
+        let tallies = vec![NodeTally::default(); nodes.len()];
+        Self {
+            nodes,
+            threshold_ms: threshold.as_millis() as i64,
+            open: HashMap::new(),
+            order: VecDeque::new(),
+            retired: VecDeque::new(),
+            retired_set: HashSet::new(),
+            tallies,
+        }
+    }
+
+    /// Blocks still waiting for some node
+    pub fn open_blocks(&self) -> usize {
+        self.open.len()
+    }
+
+    /// Node `node` reported `block_hash` at `at_ms` (Unix epoch milliseconds)
+    pub fn observe(&mut self, node: usize, block_hash: &str, at_ms: i64) {
+        if node >= self.nodes.len() || self.retired_set.contains(block_hash) {
+            return;
+        }
+        let Some(sighting) = self.open.get_mut(block_hash) else {
+            if self.open.len() >= MAX_TRACKED {
+                if let Some(oldest) = self.order.front().cloned() {
+                    self.retire(&oldest);
+                }
+            }
+            let mut seen = vec![None; self.nodes.len()];
+            seen[node] = Some(at_ms);
+            self.tallies[node].first += 1;
+            self.open.insert(
+                block_hash.to_string(),
+                Sighting {
+                    first_node: node,
+                    first_at: at_ms,
+                    seen,
+                    alerted: vec![false; self.nodes.len()],
+                },
+            );
+            self.order.push_back(block_hash.to_string());
+            self.retire_if_complete(block_hash);
+            return;
+        };
+        if sighting.seen[node].is_some() {
+            return;
+        }
+        sighting.seen[node] = Some(at_ms);
+        let lag = (at_ms - sighting.first_at).max(0);
+        let tally = &mut self.tallies[node];
+        if tally.lags.len() == MAX_LAG_SAMPLES {
+            tally.lags.pop_front();
+        }
+        tally.lags.push_back(lag as u64);
+        if lag >= self.threshold_ms && !sighting.alerted[node] {
+            tally.late += 1;
+        }
+        self.retire_if_complete(block_hash);
+    }
+
+    /// Nodes newly past the threshold for an open block, each reported once;
+    /// blocks past the retention are dropped
+    pub fn check(&mut self, now_ms: i64) -> Vec<LagAlert> {
+        let retention_ms = self.threshold_ms.saturating_mul(RETENTION_FACTOR as i64);
+        let mut alerts = Vec::new();
+        let mut expired = Vec::new();
+        for hash in &self.order {
+            let Some(sighting) = self.open.get_mut(hash) else {
+                continue;
+            };
+            let age = now_ms - sighting.first_at;
+            if age < self.threshold_ms {
+                // Opened in order, so everything after this is younger
+                break;
+            }
+            for node in 0..self.nodes.len() {
+                if sighting.seen[node].is_none() && !sighting.alerted[node] {
+                    sighting.alerted[node] = true;
+                    self.tallies[node].late += 1;
+                    alerts.push(LagAlert {
+                        block_hash: hash.clone(),
+                        seen_on: self.nodes[sighting.first_node].clone(),
+                        ago: Duration::from_millis(age.max(0) as u64),
+                        missing_on: self.nodes[node].clone(),
+                    });
+                }
+            }
+            if age >= retention_ms {
+                expired.push(hash.clone());
+            }
+        }
+        for hash in expired {
+            self.retire(&hash);
+        }
+        alerts
+    }
+
+    /// Per-node counts and lag distribution so far
+    pub fn summary(&self) -> Vec<NodeLag> {
+        self.nodes
+            .iter()
+            .zip(&self.tallies)
+            .map(|(name, tally)| NodeLag {
+                name: name.clone(),
+                first: tally.first,
+                lag: Summary::from_samples(tally.lags.iter().copied()),
+                late: tally.late,
+                missed: tally.missed,
+            })
+            .collect()
+    }
+
+    fn retire_if_complete(&mut self, block_hash: &str) {
+        let complete = self
+            .open
+            .get(block_hash)
+            .is_some_and(|s| s.seen.iter().all(Option::is_some));
+        if complete {
+            self.retire(block_hash);
+        }
+    }
+
+    /// Stop tracking `block_hash`, counting it as missed by nodes without it
+    fn retire(&mut self, block_hash: &str) {
+        let Some(sighting) = self.open.remove(block_hash) else {
+            return;
+        };
+        if let Some(at) = self.order.iter().position(|h| h == block_hash) {
+            self.order.remove(at);
+        }
+        for (node, seen) in sighting.seen.iter().enumerate() {
+            if seen.is_none() {
+                self.tallies[node].missed += 1;
+            }
+        }
+        if self.retired.len() == MAX_TRACKED {
+            if let Some(oldest) = self.retired.pop_front() {
+                self.retired_set.remove(&oldest);
+            }
+        }
+        self.retired.push_back(block_hash.to_string());
+        self.retired_set.insert(block_hash.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: i64 = 1000;
+
+    fn tracker() -> CrossNodeTracker {
+        CrossNodeTracker::new(
+            vec![
+                "validator1".to_string(),
+                "validator2".to_string(),
+                "observer".to_string(),
+            ],
+            Duration::from_secs(10),
+        )
+    }
+
+    #[test]
+    fn test_lags_are_measured_from_the_first_sighting() {
+        let mut t = tracker();
+        t.observe(0, "aa", 0);
+        t.observe(2, "aa", 2 * SECOND);
+        t.observe(1, "aa", 500);
+        assert_eq!(t.open_blocks(), 0);
+        // Later events for the same block do not open it again
+        t.observe(2, "aa", 30 * SECOND);
+        assert_eq!(t.open_blocks(), 0);
+        assert!(t.check(60 * SECOND).is_empty());
+
+        let summary = t.summary();
+        assert_eq!(summary[0].first, 1);
+        assert_eq!(summary[0].lag, None);
+        assert_eq!(summary[1].lag.unwrap().max, 500);
+        assert_eq!(summary[2].lag.unwrap().max, 2000);
+        assert!(summary.iter().all(|n| n.late == 0 && n.missed == 0));
+    }
+
+    #[test]
+    fn test_a_lagging_node_is_reported_once() {
+        let mut t = tracker();
+        t.observe(0, "bb", 0);
+        t.observe(1, "bb", SECOND);
+        assert!(t.check(9 * SECOND).is_empty());
+
+        let alerts = t.check(14 * SECOND);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].seen_on, "validator1");
+        assert_eq!(alerts[0].missing_on, "observer");
+        assert_eq!(
+            alerts[0].to_string(),
+            "block bb seen on validator1 14s ago, not yet on observer"
+        );
+        assert!(t.check(15 * SECOND).is_empty());
+
+        // It turns up late: a lag sample, and the block is done
+        t.observe(2, "bb", 20 * SECOND);
+        assert_eq!(t.open_blocks(), 0);
+        let observer = &t.summary()[2];
+        assert_eq!((observer.late, observer.missed), (1, 0));
+        assert_eq!(observer.lag.unwrap().max, 20_000);
+    }
+
+    #[test]
+    fn test_a_permanently_missing_node() {
+        let mut t = tracker();
+        for n in 0..20 {
+            let hash = format!("{:064x}", n);
+            let at = n * 2 * SECOND;
+            t.observe(n as usize % 2, &hash, at);
+            t.observe(1 - n as usize % 2, &hash, at + 100);
+            t.check(at + 200);
+        }
+        // Everything older than the retention is given up on
+        let alerts = t.check(40 * SECOND + 60 * SECOND);
+        assert!(alerts.iter().all(|a| a.missing_on == "observer"));
+        assert_eq!(t.open_blocks(), 0);
+
+        let summary = t.summary();
+        assert_eq!(summary[2].missed, 20);
+        assert_eq!(summary[2].late, 20);
+        assert_eq!(summary[2].lag, None);
+        assert_eq!(summary[0].first + summary[1].first, 20);
+        assert_eq!(summary[0].missed + summary[1].missed, 0);
+    }
+
+    #[test]
+    fn test_memory_is_bounded() {
+        let mut t = CrossNodeTracker::new(
+            vec!["a".to_string(), "b".to_string()],
+            Duration::from_secs(3600),
+        );
+        for n in 0..MAX_TRACKED + 50 {
+            t.observe(0, &n.to_string(), n as i64);
+        }
+        assert_eq!(t.open_blocks(), MAX_TRACKED);
+        assert_eq!(t.summary()[1].missed, 50);
+        // An evicted block seen later does not reopen
+        t.observe(1, "0", 0);
+        assert_eq!(t.open_blocks(), MAX_TRACKED);
+        assert!(t.retired.len() <= MAX_TRACKED);
+
+        for n in 0..MAX_TRACKED + 50 {
+            t.observe(1, &n.to_string(), n as i64);
+        }
+        assert_eq!(t.open_blocks(), 0);
+        assert!(t.retired.len() <= MAX_TRACKED);
+        assert!(t.summary()[1].lag.unwrap().count <= MAX_LAG_SAMPLES);
+    }
+
+    #[test]
+    fn test_unknown_nodes_are_ignored() {
+        let mut t = tracker();
+        t.observe(7, "cc", 0);
+        assert_eq!(t.open_blocks(), 0);
+    }
+}
@@ -1,5 +1,6 @@
+use std::collections::{HashSet, VecDeque};
 use std::io;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use crossterm::{
     event::{self, Event, KeyCode, KeyEventKind},
@@ -18,15 +19,44 @@ use ratatui::{
     Frame, Terminal,
 };
 use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
 
-use super::model::{BlockStatus, Dag, DagBlock};
-use super::renderer::DagRenderer;
+use super::highlight::{block_matches, next_highlighted};
+use super::model::{BlockStatus, Dag, DagBlock, DagDeploy};
+use super::prefs::UiPrefs;
+use super::renderer::{DagRenderer, HIGHLIGHT_MARKER};
+use crate::f1r3fly_api::SystemDeploy;
+use crate::truncation::{shorten, Field};
+use crate::wallet_watch::WalletWatch;
+
+/// Default minimum time between layout recomputations
+pub const DEFAULT_RELAYOUT_INTERVAL_MS: u64 = 200;
+
+/// Layouts slower than this are computed in a blocking task instead of the render loop
+const LAYOUT_BUDGET: Duration = Duration::from_millis(30);
+
+/// Event processing plus drawing longer than this counts as a dropped frame
+const FRAME_BUDGET: Duration = Duration::from_millis(50);
+
+/// How long to wait for input when nothing is pending
+const INPUT_POLL: Duration = Duration::from_millis(100);
+
+/// Events set aside while the detail view is open before the channel is left to fill
+const MAX_HELD_EVENTS: usize = 1000;
+
+/// Window used for the events/sec indicator
+const EVENT_RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Columns the parents region moves per left/right key press
+const PARENTS_SCROLL_STEP: usize = 8;
 
 /// Events from WebSocket
 pub enum DagEvent {
     BlockCreated(DagBlock),
     BlockAdded(String),     // hash
     BlockFinalized(String), // hash
+    /// Block hash, its deploys, and its system deploys if the node reports them
+    DeploysLoaded(String, Vec<DagDeploy>, Option<Vec<SystemDeploy>>),
     Error(String),
 }
 
@@ -36,12 +66,26 @@ pub struct DagApp {
     pub renderer: DagRenderer,
     pub scroll_offset: usize,
     pub selected_index: usize,
+    pub parents_scroll: usize, // Horizontal scroll of truncated PARENTS cells
     pub show_details: bool,
     pub running: bool,
     pub event_receiver: Option<mpsc::Receiver<DagEvent>>,
     pub status_message: String,
     pub block_count: usize,
     pub follow_head: bool, // If true, auto-scroll to show newest blocks at top
+    pub relayout_interval: Duration, // Minimum time between layout recomputations
+    pub layout_budget: Duration, // Slower layouts move off the render path
+    pub layout_dirty: bool, // Blocks changed since the last layout
+    pub layout_count: usize,
+    pub last_layout: Option<Instant>,
+    pub last_layout_duration: Duration,
+    pub dropped_frames: usize,
+    pub highlight: Option<WalletWatch>, // Deployers whose blocks are marked
+    deploy_requests: Option<mpsc::UnboundedSender<String>>,
+    requested_deploys: HashSet<String>,
+    pending_layout: Option<JoinHandle<(Dag, Duration)>>,
+    event_times: VecDeque<Instant>,
+    held_events: Vec<DagEvent>, // Set aside while the detail view is open
 }
 
 impl DagApp {
@@ -51,12 +95,26 @@ impl DagApp {
             renderer: DagRenderer::new(),
             scroll_offset: 0,
             selected_index: 0,
+            parents_scroll: 0,
             show_details: false,
             running: true,
             event_receiver: None,
             status_message: "Connecting...".to_string(),
             block_count: 0,
             follow_head: true, // Start following the head
+            relayout_interval: Duration::from_millis(DEFAULT_RELAYOUT_INTERVAL_MS),
+            layout_budget: LAYOUT_BUDGET,
+            layout_dirty: false,
+            layout_count: 0,
+            last_layout: None,
+            last_layout_duration: Duration::ZERO,
+            dropped_frames: 0,
+            highlight: None,
+            deploy_requests: None,
+            requested_deploys: HashSet::new(),
+            pending_layout: None,
+            event_times: VecDeque::new(),
+            held_events: Vec::new(),
         }
     }
 
@@ -65,12 +123,49 @@ impl DagApp {
         self
     }
 
+    /// Highlight blocks with deploys from `watch`, sending the hashes of blocks
+    /// whose deploys are needed on `requests`
+    pub fn with_highlight(
+        mut self,
+        watch: WalletWatch,
+        requests: mpsc::UnboundedSender<String>,
+    ) -> Self {
+        self.highlight = Some(watch);
+        self.deploy_requests = Some(requests);
+        self
+    }
+
+    /// Fetch deploys for blocks opened in the detail view, without highlighting
+    pub fn with_deploy_loader(mut self, requests: mpsc::UnboundedSender<String>) -> Self {
+        self.deploy_requests = Some(requests);
+        self
+    }
+
+    /// Settings saved between sessions, as currently set
+    pub fn prefs(&self) -> UiPrefs {
+        UiPrefs {
+            show_deploys: self.renderer.show_deploys,
+            use_color: self.renderer.use_color,
+            relayout_interval_ms: self.relayout_interval.as_millis() as u64,
+        }
+    }
+
+    pub fn apply_prefs(&mut self, prefs: UiPrefs) {
+        self.renderer.show_deploys = prefs.show_deploys;
+        self.renderer.use_color = prefs.use_color;
+        self.relayout_interval = Duration::from_millis(prefs.relayout_interval_ms);
+    }
+
     /// Add initial blocks
     pub fn load_blocks(&mut self, blocks: Vec<DagBlock>) {
         for block in blocks {
+            self.request_deploys(&block);
             self.dag.add_block(block);
         }
+        let start = Instant::now();
         self.dag.compute_layout();
+        self.last_layout_duration = start.elapsed();
+        self.layout_count += 1;
         self.block_count = self.dag.blocks.len();
         self.status_message = format!("Loaded {} blocks", self.block_count);
     }
@@ -101,28 +196,46 @@ impl DagApp {
         terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
     ) -> io::Result<()> {
         loop {
+            let frame_start = Instant::now();
+
             // Check for WebSocket events (non-blocking)
-            // Only process events when NOT in detail view to avoid screen updates while comparing hashes
-            if !self.show_details {
-                let events: Vec<DagEvent> = if let Some(ref mut receiver) = self.event_receiver {
-                    let mut collected = Vec::new();
+            // The detail view only applies fetched deploys, holding other events back to avoid
+            // screen updates while comparing hashes
+            if !self.show_details || self.held_events.len() < MAX_HELD_EVENTS {
+                let mut events = std::mem::take(&mut self.held_events);
+                if let Some(ref mut receiver) = self.event_receiver {
                     while let Ok(event) = receiver.try_recv() {
-                        collected.push(event);
+                        events.push(event);
                     }
-                    collected
-                } else {
-                    Vec::new()
-                };
-                for event in events {
-                    self.handle_dag_event(event);
                 }
+                if self.show_details {
+                    let (loaded, held) = events
+                        .into_iter()
+                        .partition(|event| matches!(event, DagEvent::DeploysLoaded(..)));
+                    self.held_events = held;
+                    events = loaded;
+                }
+                self.process_events(events, frame_start);
             }
 
+            // Relayout at most once per frame, coalescing everything drained above
+            self.finish_background_layout().await;
+            self.maybe_relayout(Instant::now());
+
             // Draw
             terminal.draw(|frame| self.render(frame))?;
+            if frame_start.elapsed() > FRAME_BUDGET {
+                self.dropped_frames += 1;
+            }
 
-            // Handle input with timeout
-            if event::poll(Duration::from_millis(100))? {
+            // Handle input with timeout, waking early when a relayout is due
+            let poll_timeout = if self.layout_dirty || self.pending_layout.is_some() {
+                self.relayout_interval
+                    .clamp(Duration::from_millis(10), INPUT_POLL)
+            } else {
+                INPUT_POLL
+            };
+            if event::poll(poll_timeout)? {
                 if let Event::Key(key) = event::read()? {
                     if key.kind == KeyEventKind::Press {
                         self.handle_key(key.code);
@@ -138,13 +251,96 @@ impl DagApp {
         Ok(())
     }
 
+    /// Apply a batch of events received at `now`; layout is deferred to `maybe_relayout`
+    pub fn process_events(&mut self, events: Vec<DagEvent>, now: Instant) {
+        for event in events {
+            if !matches!(event, DagEvent::DeploysLoaded(..)) {
+                self.event_times.push_back(now);
+            }
+            self.handle_dag_event(event);
+        }
+        self.prune_event_times(now);
+    }
+
+    /// Recompute the layout if blocks changed and the relayout interval has passed.
+    /// Returns true if a layout was started.
+    pub fn maybe_relayout(&mut self, now: Instant) -> bool {
+        if !self.layout_dirty || self.pending_layout.is_some() {
+            return false;
+        }
+        if let Some(last) = self.last_layout {
+            if now.duration_since(last) < self.relayout_interval {
+                return false;
+            }
+        }
+
+        self.layout_dirty = false;
+        self.last_layout = Some(now);
+        self.layout_count += 1;
+
+        if self.last_layout_duration > self.layout_budget {
+            // Too slow for the render loop: lay out a snapshot and swap it in when done
+            let mut snapshot = self.dag.clone();
+            self.pending_layout = Some(tokio::task::spawn_blocking(move || {
+                let start = Instant::now();
+                snapshot.compute_layout();
+                (snapshot, start.elapsed())
+            }));
+        } else {
+            let start = Instant::now();
+            self.dag.compute_layout();
+            self.last_layout_duration = start.elapsed();
+        }
+        true
+    }
+
+    /// Swap in a finished background layout, if any
+    async fn finish_background_layout(&mut self) {
+        if !self
+            .pending_layout
+            .as_ref()
+            .is_some_and(|h| h.is_finished())
+        {
+            return;
+        }
+        if let Some(handle) = self.pending_layout.take() {
+            match handle.await {
+                Ok((laid_out, elapsed)) => {
+                    self.dag.adopt_layout(laid_out);
+                    self.last_layout_duration = elapsed;
+                }
+                Err(e) => {
+                    self.status_message = format!("Layout failed: {}", e);
+                    self.layout_dirty = true;
+                }
+            }
+        }
+    }
+
+    /// Events received within the last second
+    pub fn events_per_sec(&mut self, now: Instant) -> usize {
+        self.prune_event_times(now);
+        self.event_times.len()
+    }
+
+    fn prune_event_times(&mut self, now: Instant) {
+        while let Some(front) = self.event_times.front() {
+            if now.duration_since(*front) > EVENT_RATE_WINDOW {
+                self.event_times.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
     fn handle_dag_event(&mut self, event: DagEvent) {
         match event {
             DagEvent::BlockCreated(block) => {
                 self.status_message =
                     format!("New block: #{} {}", block.block_number, block.short_hash);
+                self.request_deploys(&block);
                 self.dag.add_block(block);
-                self.dag.compute_layout();
+                self.layout_dirty = true;
                 self.block_count = self.dag.blocks.len();
 
                 // If following head, keep selection at top
@@ -158,7 +354,17 @@ impl DagApp {
             }
             DagEvent::BlockFinalized(hash) => {
                 self.dag.update_status(&hash, BlockStatus::Finalized);
-                self.status_message = format!("Finalized: {}...", &hash[..8.min(hash.len())]);
+                self.status_message = format!("Finalized: {}", shorten(&hash, Field::Column));
+            }
+            DagEvent::DeploysLoaded(hash, deploys, system_deploys) => {
+                self.dag.set_deploys(&hash, deploys, system_deploys);
+                let matched = match (&self.highlight, self.dag.blocks.get(&hash)) {
+                    (Some(watch), Some(block)) => block_matches(watch, block),
+                    _ => false,
+                };
+                if matched && self.renderer.highlighted.insert(hash.clone()) {
+                    self.status_message = format!("Highlighted: {}", shorten(&hash, Field::Column));
+                }
             }
             DagEvent::Error(msg) => {
                 self.status_message = format!("Error: {}", msg);
@@ -166,6 +372,61 @@ impl DagApp {
         }
     }
 
+    /// Ask for a block's deploys if highlighting needs them and they were not requested yet.
+    /// Blocks without deploys are only fetched when opened in the detail view.
+    fn request_deploys(&mut self, block: &DagBlock) {
+        if self.highlight.is_none() || block.deploy_count == 0 || block.deploys_loaded {
+            return;
+        }
+        self.send_deploy_request(&block.hash);
+    }
+
+    /// Ask for the selected block's deploys and system deploys for the detail view
+    fn request_selected_detail(&mut self) {
+        let Some(row) = self.dag.graph_rows.get(self.selected_index) else {
+            return;
+        };
+        let hash = row.block_hash.clone();
+        if self
+            .dag
+            .blocks
+            .get(&hash)
+            .is_some_and(|b| !b.deploys_loaded)
+        {
+            self.send_deploy_request(&hash);
+        }
+    }
+
+    fn send_deploy_request(&mut self, hash: &str) {
+        let Some(requests) = &self.deploy_requests else {
+            return;
+        };
+        if !self.requested_deploys.insert(hash.to_string()) {
+            return;
+        }
+        if requests.send(hash.to_string()).is_err() {
+            self.requested_deploys.remove(hash);
+        }
+    }
+
+    /// Move the selection to the next highlighted block below it, wrapping to the top
+    fn select_next_highlight(&mut self) {
+        match next_highlighted(
+            &self.dag.graph_rows,
+            &self.renderer.highlighted,
+            self.selected_index,
+        ) {
+            Some(index) => {
+                self.selected_index = index;
+                self.ensure_visible();
+                self.follow_head = self.scroll_offset == 0 && self.selected_index == 0;
+            }
+            None => {
+                self.status_message = "No highlighted blocks yet".to_string();
+            }
+        }
+    }
+
     fn handle_key(&mut self, code: KeyCode) {
         let num_rows = self.dag.graph_rows.len();
 
@@ -212,8 +473,31 @@ impl DagApp {
                 self.selected_index = num_rows.saturating_sub(1);
                 self.ensure_visible();
             }
+            KeyCode::Left | KeyCode::Char('h') => {
+                self.parents_scroll = self.parents_scroll.saturating_sub(PARENTS_SCROLL_STEP);
+            }
+            KeyCode::Right | KeyCode::Char('l') => {
+                // Clamped to the widest visible overflow on the next render
+                self.parents_scroll += PARENTS_SCROLL_STEP;
+            }
+            KeyCode::Char('H') if self.highlight.is_some() => {
+                self.select_next_highlight();
+            }
             KeyCode::Enter => {
                 self.show_details = !self.show_details;
+                if self.show_details {
+                    self.request_selected_detail();
+                }
+            }
+            KeyCode::Char('d') => {
+                self.renderer.show_deploys = !self.renderer.show_deploys;
+            }
+            KeyCode::Char('c') => {
+                self.renderer.use_color = !self.renderer.use_color;
+            }
+            KeyCode::Char('R') => {
+                self.apply_prefs(UiPrefs::default());
+                self.status_message = "Display settings reset to defaults".to_string();
             }
             _ => {}
         }
@@ -284,6 +568,21 @@ impl DagApp {
             self.scroll_offset = self.selected_index - viewport_height + 1;
         }
 
+        // Keep horizontal scroll within the widest truncated PARENTS cell on screen
+        let parents_overflow = self
+            .dag
+            .graph_rows
+            .iter()
+            .skip(self.scroll_offset)
+            .take(viewport_height)
+            .map(|row| {
+                self.renderer
+                    .parents_overflow(row, &self.dag, content_width)
+            })
+            .max()
+            .unwrap_or(0);
+        self.parents_scroll = self.parents_scroll.min(parents_overflow);
+
         // Render visible rows
         let mut items: Vec<ListItem> = Vec::new();
 
@@ -295,9 +594,13 @@ impl DagApp {
             .take(viewport_height)
         {
             let is_selected = i == self.selected_index;
-            let line = self
-                .renderer
-                .render_row(row, &self.dag, is_selected, content_width);
+            let line = self.renderer.render_row(
+                row,
+                &self.dag,
+                is_selected,
+                content_width,
+                self.parents_scroll,
+            );
 
             let style = if is_selected {
                 Style::default().bg(Color::DarkGray)
@@ -316,13 +619,26 @@ impl DagApp {
             .borders(Borders::ALL)
             .border_style(Style::default().fg(Color::DarkGray));
 
-        let status_text = Line::from(vec![
+        let events_per_sec = self.events_per_sec(Instant::now());
+        let mut status_spans = vec![
             Span::styled(" [/jk] ", Style::default().fg(Color::Yellow)),
             Span::raw("Navigate "),
             Span::styled("[Enter] ", Style::default().fg(Color::Yellow)),
             Span::raw("Details "),
             Span::styled("[g/G] ", Style::default().fg(Color::Yellow)),
             Span::raw("Top/Bottom "),
+            Span::styled("[d/c/R] ", Style::default().fg(Color::Yellow)),
+            Span::raw("Deploys/Color/Reset "),
+        ];
+        if parents_overflow > 0 {
+            status_spans.push(Span::styled("[h/l] ", Style::default().fg(Color::Yellow)));
+            status_spans.push(Span::raw("Scroll parents "));
+        }
+        if self.highlight.is_some() {
+            status_spans.push(Span::styled("[H] ", Style::default().fg(Color::Yellow)));
+            status_spans.push(Span::raw("Next highlight "));
+        }
+        status_spans.extend([
             Span::styled("[q] ", Style::default().fg(Color::Yellow)),
             Span::raw("Quit "),
             Span::raw(" "),
@@ -330,8 +646,36 @@ impl DagApp {
                 format!("Blocks: {} ", self.block_count),
                 Style::default().fg(Color::Cyan),
             ),
-            Span::styled(&self.status_message, Style::default().fg(Color::Green)),
         ]);
+        if self.highlight.is_some() {
+            status_spans.push(Span::styled(
+                format!("{} {} ", HIGHLIGHT_MARKER, self.renderer.highlighted.len()),
+                Style::default().fg(Color::LightMagenta),
+            ));
+        }
+        if events_per_sec > 0 {
+            status_spans.push(Span::styled(
+                format!("{} ev/s ", events_per_sec),
+                Style::default().fg(Color::Magenta),
+            ));
+        }
+        if self.dropped_frames > 0 {
+            status_spans.push(Span::styled(
+                format!("Dropped: {} ", self.dropped_frames),
+                Style::default().fg(Color::Red),
+            ));
+        }
+        if self.pending_layout.is_some() {
+            status_spans.push(Span::styled(
+                "Laying out... ",
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+        status_spans.push(Span::styled(
+            self.status_message.as_str(),
+            Style::default().fg(Color::Green),
+        ));
+        let status_text = Line::from(status_spans);
 
         let status = Paragraph::new(status_text).block(status_block);
         frame.render_widget(status, chunks[1]);
@@ -371,9 +715,16 @@ impl DagApp {
                 Span::styled(" Timestamp: ", Style::default().fg(Color::Yellow)),
                 Span::raw(block.timestamp.format("%Y-%m-%d %H:%M:%S UTC").to_string()),
             ]),
+            Line::from(vec![
+                Span::styled(" Age: ", Style::default().fg(Color::Yellow)),
+                Span::raw(block.age_string()),
+            ]),
             Line::from(vec![
                 Span::styled(" Creator: ", Style::default().fg(Color::Yellow)),
-                Span::raw(&block.creator),
+                Span::raw(match self.renderer.identities.get(&block.creator) {
+                    Some(identity) => format!("{} ({})", identity.name, block.creator),
+                    None => block.creator.clone(),
+                }),
             ]),
             Line::from(vec![
                 Span::styled(" Seq Num: ", Style::default().fg(Color::Yellow)),
@@ -398,10 +749,7 @@ impl DagApp {
             lines.push(Line::from(" (genesis - no parents)"));
         } else {
             for parent in &block.parents {
-                lines.push(Line::from(format!(
-                    " {}...",
-                    &parent[..16.min(parent.len())]
-                )));
+                lines.push(Line::from(format!(" {}", shorten(parent, Field::Hash))));
             }
         }
 
@@ -410,26 +758,20 @@ impl DagApp {
             " State Transition:",
             Style::default().fg(Color::Yellow),
         )]));
-        lines.push(Line::from(format!(
-            " Pre: {}...",
-            if block.pre_state_hash.len() > 16 {
-                &block.pre_state_hash[..16]
-            } else if block.pre_state_hash.is_empty() {
-                "(empty)"
-            } else {
-                &block.pre_state_hash
-            }
-        )));
-        lines.push(Line::from(format!(
-            " Post: {}...",
-            if block.post_state_hash.len() > 16 {
-                &block.post_state_hash[..16]
-            } else if block.post_state_hash.is_empty() {
-                "(empty)"
-            } else {
-                &block.post_state_hash
-            }
-        )));
+        for (label, hash) in [
+            ("Pre", &block.pre_state_hash),
+            ("Post", &block.post_state_hash),
+        ] {
+            lines.push(Line::from(format!(
+                " {}: {}",
+                label,
+                if hash.is_empty() {
+                    "(empty)".to_string()
+                } else {
+                    shorten(hash, Field::Hash)
+                }
+            )));
+        }
 
         lines.push(Line::from(""));
         lines.push(Line::from(vec![Span::styled(
@@ -460,6 +802,41 @@ impl DagApp {
             }
         }
 
+        lines.push(Line::from(""));
+        lines.push(Line::from(vec![Span::styled(
+            " System Deploys:",
+            Style::default().fg(Color::Yellow),
+        )]));
+        match (&block.system_deploys, block.deploys_loaded) {
+            (_, false) if self.deploy_requests.is_some() => lines.push(Line::from(" (loading...)")),
+            (_, false) => lines.push(Line::from(" (not loaded)")),
+            (None, true) => lines.push(Line::from(" (not provided by this node version)")),
+            (Some(system_deploys), true) if system_deploys.is_empty() => {
+                lines.push(Line::from(" (none)"))
+            }
+            (Some(system_deploys), true) => {
+                for deploy in system_deploys {
+                    let (status_icon, status_color) = if deploy.failed {
+                        ("", Color::Red)
+                    } else {
+                        ("", Color::Green)
+                    };
+                    let mut spans = vec![
+                        Span::raw(" ["),
+                        Span::styled(status_icon, Style::default().fg(status_color)),
+                        Span::raw(format!("] {}", deploy.kind)),
+                    ];
+                    if let Some(error) = &deploy.error {
+                        spans.push(Span::styled(
+                            format!(" {}", error),
+                            Style::default().fg(Color::Red),
+                        ));
+                    }
+                    lines.push(Line::from(spans));
+                }
+            }
+        }
+
         lines.push(Line::from(""));
         let (status_str, status_color) = match block.status {
             BlockStatus::Finalized => ("FINALIZED", Color::Green),
@@ -493,3 +870,198 @@ impl Default for DagApp {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn synthetic_block(n: i64) -> DagBlock {
+        let parents = if n > 0 {
+            vec![format!("{:064x}", n - 1)]
+        } else {
+            Vec::new()
+        };
+        DagBlock::new(
+            format!("{:064x}", n),
+            n,
+            Utc::now(),
+            format!("validator{}", n % 3),
+            n,
+            parents,
+            0,
+            BlockStatus::Created,
+        )
+    }
+
+    #[test]
+    fn test_relayouts_bounded_by_interval_not_event_count() {
+        let mut app = DagApp::new();
+        app.relayout_interval = Duration::from_millis(200);
+        app.layout_budget = Duration::MAX; // keep layouts on the calling thread
+
+        // 1,000 BlockCreated events over one simulated second: 10 per 10ms frame
+        let start = Instant::now();
+        for frame in 0..100u64 {
+            let now = start + Duration::from_millis(frame * 10);
+            let events = (0..10)
+                .map(|i| DagEvent::BlockCreated(synthetic_block((frame * 10 + i) as i64)))
+                .collect();
+            app.process_events(events, now);
+            app.maybe_relayout(now);
+        }
+        app.maybe_relayout(start + Duration::from_secs(2));
+
+        // One layout per elapsed interval, plus the first and the trailing flush
+        assert!(
+            app.layout_count <= 1000 / 200 + 2,
+            "{} layouts for 1000 events",
+            app.layout_count
+        );
+        assert!(!app.layout_dirty);
+        assert_eq!(app.dag.graph_rows.len(), 1000);
+        assert_eq!(app.block_count, 1000);
+    }
+
+    #[test]
+    fn test_single_batch_gets_single_layout() {
+        let mut app = DagApp::new();
+        app.layout_budget = Duration::MAX;
+        let now = Instant::now();
+
+        let events = (0..1000)
+            .map(|n| DagEvent::BlockCreated(synthetic_block(n)))
+            .collect();
+        app.process_events(events, now);
+        assert_eq!(app.layout_count, 0);
+        assert!(app.maybe_relayout(now));
+        assert!(!app.maybe_relayout(now));
+        assert_eq!(app.layout_count, 1);
+        assert_eq!(app.events_per_sec(now), 1000);
+        assert_eq!(app.events_per_sec(now + Duration::from_secs(2)), 0);
+    }
+
+    #[test]
+    fn test_highlight_fetches_only_blocks_with_deploys() {
+        let (_, public_key) = crate::utils::CryptoUtils::generate_key_pair().unwrap();
+        let key = crate::utils::CryptoUtils::serialize_public_key(&public_key, false);
+        let watch =
+            WalletWatch::new(&[crate::wallet_watch::WatchTarget::PublicKey(key.clone())]).unwrap();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut app = DagApp::new().with_highlight(watch, tx);
+        app.layout_budget = Duration::MAX;
+
+        // Blocks 2 and 5 carry deploys; only they are requested, and only once
+        let blocks: Vec<DagBlock> = (0..8)
+            .map(|n| {
+                let mut block = synthetic_block(n);
+                block.deploy_count = if n == 2 || n == 5 { 1 } else { 0 };
+                block
+            })
+            .collect();
+        app.load_blocks(blocks.clone());
+        app.load_blocks(blocks);
+        let mut requested = Vec::new();
+        while let Ok(hash) = rx.try_recv() {
+            requested.push(hash);
+        }
+        assert_eq!(
+            requested,
+            vec![format!("{:064x}", 2), format!("{:064x}", 5)]
+        );
+
+        let deploy = |deployer: &str| DagDeploy {
+            id: "sig".to_string(),
+            cost: 1,
+            deployer: deployer.to_string(),
+            errored: false,
+        };
+        app.process_events(
+            vec![
+                DagEvent::DeploysLoaded(format!("{:064x}", 2), vec![deploy(&key)], None),
+                DagEvent::DeploysLoaded(format!("{:064x}", 5), vec![deploy("04ab")], None),
+            ],
+            Instant::now(),
+        );
+        assert_eq!(app.renderer.highlighted.len(), 1);
+        assert_eq!(app.events_per_sec(Instant::now()), 0);
+
+        // H jumps to the highlighted block and stays there when it is the only one
+        let target = app
+            .dag
+            .graph_rows
+            .iter()
+            .position(|r| r.block_hash == format!("{:064x}", 2))
+            .unwrap();
+        app.handle_key(KeyCode::Char('H'));
+        assert_eq!(app.selected_index, target);
+        assert!(!app.follow_head);
+        app.handle_key(KeyCode::Char('H'));
+        assert_eq!(app.selected_index, target);
+
+        // A re-sent block keeps the deploys already fetched for it
+        app.process_events(
+            vec![DagEvent::BlockCreated(synthetic_block(2))],
+            Instant::now(),
+        );
+        assert!(app.dag.blocks[&format!("{:064x}", 2)].deploys_loaded);
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_detail_view_requests_system_deploys() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut app = DagApp::new().with_deploy_loader(tx);
+        app.layout_budget = Duration::MAX;
+
+        // Without highlighting nothing is fetched up front, even with deploys
+        let mut block = synthetic_block(0);
+        block.deploy_count = 3;
+        app.load_blocks(vec![block]);
+        assert!(rx.try_recv().is_err());
+
+        // Opening the detail view asks for the block once
+        let hash = app.dag.graph_rows[app.selected_index].block_hash.clone();
+        app.handle_key(KeyCode::Enter);
+        assert_eq!(rx.try_recv().unwrap(), hash);
+        app.handle_key(KeyCode::Enter);
+        app.handle_key(KeyCode::Enter);
+        assert!(rx.try_recv().is_err());
+
+        let failed = SystemDeploy {
+            kind: crate::f1r3fly_api::SystemDeployKind::CloseBlock,
+            failed: true,
+            error: Some("Unable to close block".to_string()),
+        };
+        app.process_events(
+            vec![DagEvent::DeploysLoaded(
+                hash.clone(),
+                Vec::new(),
+                Some(vec![failed.clone()]),
+            )],
+            Instant::now(),
+        );
+        assert_eq!(app.dag.blocks[&hash].system_deploys, Some(vec![failed]));
+    }
+
+    #[test]
+    fn test_display_keys_change_saved_prefs() {
+        let mut app = DagApp::new();
+        assert_eq!(app.prefs(), UiPrefs::default());
+
+        app.handle_key(KeyCode::Char('d'));
+        app.handle_key(KeyCode::Char('c'));
+        app.relayout_interval = Duration::from_millis(750);
+        assert_eq!(
+            app.prefs(),
+            UiPrefs {
+                show_deploys: false,
+                use_color: false,
+                relayout_interval_ms: 750,
+            }
+        );
+
+        app.handle_key(KeyCode::Char('R'));
+        assert_eq!(app.prefs(), UiPrefs::default());
+    }
+}
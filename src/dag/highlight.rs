@@ -0,0 +1,122 @@
+//! Deployer highlighting for the DAG viewer
+//!
+//! Blocks only list their deploys once they are fetched from the node, so the
+//! viewer requests deploys for blocks that have any and marks a block once one
+//! of its deploys is signed by a highlighted key or vault address.
+
+use std::collections::HashSet;
+
+use super::model::{DagBlock, GraphRow};
+use crate::wallet_watch::WalletWatch;
+
+/// Whether any fetched deploy in `block` comes from a highlighted deployer
+pub fn block_matches(watch: &WalletWatch, block: &DagBlock) -> bool {
+    block
+        .deploys
+        .iter()
+        .any(|deploy| watch.watches_deployer(&deploy.deployer))
+}
+
+/// Row of the first highlighted block after `from`, wrapping around to the top
+pub fn next_highlighted(
+    rows: &[GraphRow],
+    highlighted: &HashSet<String>,
+    from: usize,
+) -> Option<usize> {
+    (1..=rows.len())
+        .map(|step| (from + step) % rows.len())
+        .find(|&i| highlighted.contains(&rows[i].block_hash))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dag::model::{BlockStatus, Dag, DagDeploy};
+    use crate::utils::CryptoUtils;
+    use crate::wallet_watch::WatchTarget;
+    use chrono::Utc;
+
+    fn key_and_address() -> (String, String) {
+        let (_, public_key) = CryptoUtils::generate_key_pair().unwrap();
+        let key = CryptoUtils::serialize_public_key(&public_key, false);
+        let address = CryptoUtils::generate_vault_address(&key).unwrap();
+        (key, address)
+    }
+
+    fn block_with_deployers(n: i64, deployers: &[&str]) -> DagBlock {
+        let parents = if n > 0 {
+            vec![format!("{:064x}", n - 1)]
+        } else {
+            Vec::new()
+        };
+        let mut block = DagBlock::new(
+            format!("{:064x}", n),
+            n,
+            Utc::now(),
+            "validator".to_string(),
+            n,
+            parents,
+            deployers.len() as u32,
+            BlockStatus::Finalized,
+        );
+        block.deploys = deployers
+            .iter()
+            .enumerate()
+            .map(|(i, deployer)| DagDeploy {
+                id: format!("sig{}_{}", n, i),
+                cost: 100,
+                deployer: deployer.to_string(),
+                errored: false,
+            })
+            .collect();
+        block.deploys_loaded = true;
+        block
+    }
+
+    #[test]
+    fn test_block_matches_by_key_or_address() {
+        let (key, address) = key_and_address();
+        let (other, _) = key_and_address();
+        let mine = block_with_deployers(1, &[&other, &key.to_uppercase()]);
+        let theirs = block_with_deployers(2, &[&other]);
+        let unloaded = block_with_deployers(3, &[]);
+
+        for target in [
+            WatchTarget::PublicKey(key.clone()),
+            WatchTarget::Address(address),
+        ] {
+            let watch = WalletWatch::new(&[target]).unwrap();
+            assert!(block_matches(&watch, &mine));
+            assert!(!block_matches(&watch, &theirs));
+            assert!(!block_matches(&watch, &unloaded));
+        }
+    }
+
+    #[test]
+    fn test_next_highlighted_wraps() {
+        let mut dag = Dag::new();
+        for n in 0..6 {
+            dag.add_block(block_with_deployers(n, &[]));
+        }
+        dag.compute_layout();
+        let highlighted: HashSet<String> = [1, 4].iter().map(|n| format!("{:064x}", n)).collect();
+        let row_of = |n: i64| {
+            dag.graph_rows
+                .iter()
+                .position(|r| r.block_hash == format!("{:064x}", n))
+                .unwrap()
+        };
+
+        let first = next_highlighted(&dag.graph_rows, &highlighted, 0).unwrap();
+        assert_eq!(first, row_of(4));
+        let second = next_highlighted(&dag.graph_rows, &highlighted, first).unwrap();
+        assert_eq!(second, row_of(1));
+        // Past the last highlight the search starts again from the top
+        assert_eq!(
+            next_highlighted(&dag.graph_rows, &highlighted, second),
+            Some(first)
+        );
+        assert_eq!(next_highlighted(&dag.graph_rows, &HashSet::new(), 0), None);
+        assert_eq!(next_highlighted(&[], &highlighted, 0), None);
+    }
+}
@@ -1,7 +1,10 @@
 pub mod app;
+pub mod highlight;
 pub mod model;
+pub mod prefs;
 pub mod renderer;
 
 pub use app::{DagApp, DagEvent};
 pub use model::{BlockStatus, Dag, DagBlock, DagDeploy, GraphColumn, GraphEdge, GraphRow};
-pub use renderer::DagRenderer;
+pub use prefs::{PartialPrefs, PrefsFile, UiPrefs};
+pub use renderer::{ColumnLayout, DagRenderer, WidthThresholds};
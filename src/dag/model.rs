@@ -1,5 +1,7 @@
+use crate::f1r3fly_api::SystemDeploy;
+use crate::truncation::{shorten, Field};
 use chrono::{DateTime, Utc};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 /// Status of a block in the DAG
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -35,6 +37,8 @@ pub struct DagBlock {
     pub pre_state_hash: String,
     pub post_state_hash: String,
     pub deploys: Vec<DagDeploy>,
+    pub deploys_loaded: bool, // Deploys fetched from the node, even if there were none
+    pub system_deploys: Option<Vec<SystemDeploy>>, // None until fetched, or if the node does not report them
 }
 
 impl DagBlock {
@@ -48,16 +52,8 @@ impl DagBlock {
         deploy_count: u32,
         status: BlockStatus,
     ) -> Self {
-        let short_hash = if hash.len() >= 8 {
-            hash[..8].to_string()
-        } else {
-            hash.clone()
-        };
-        let creator_short = if creator.len() >= 8 {
-            creator[..8].to_string()
-        } else {
-            creator.clone()
-        };
+        let short_hash = shorten(&hash, Field::Column);
+        let creator_short = shorten(&creator, Field::Column);
 
         Self {
             hash,
@@ -74,6 +70,8 @@ impl DagBlock {
             pre_state_hash: String::new(),
             post_state_hash: String::new(),
             deploys: Vec::new(),
+            deploys_loaded: false,
+            system_deploys: None,
         }
     }
 
@@ -123,6 +121,12 @@ pub struct GraphEdge {
 }
 
 /// The DAG structure
+///
+/// `children` and `tips` only describe blocks in `blocks`: a child edge is
+/// recorded once both ends are known, and the tips are exactly the blocks
+/// without known children. Edges to a parent that has not arrived yet (events
+/// can come out of order) wait in `waiting` until it does.
+#[derive(Clone)]
 pub struct Dag {
     pub blocks: HashMap<String, DagBlock>,
     pub children: HashMap<String, Vec<String>>, // parent -> children
@@ -130,6 +134,8 @@ pub struct Dag {
     pub graph_rows: Vec<GraphRow>,
     pub sorted_hashes: Vec<String>, // Sorted by block number descending
     pub max_columns: usize,
+    /// Missing parent -> known blocks that cite it
+    waiting: HashMap<String, Vec<String>>,
 }
 
 impl Dag {
@@ -141,36 +147,192 @@ impl Dag {
             graph_rows: Vec::new(),
             sorted_hashes: Vec::new(),
             max_columns: 0,
+            waiting: HashMap::new(),
         }
     }
 
     /// Add or update a block in the DAG
-    pub fn add_block(&mut self, block: DagBlock) {
+    ///
+    /// An update that changes the block's parents moves its edges.
+    pub fn add_block(&mut self, mut block: DagBlock) {
         let hash = block.hash.clone();
+        let previous_parents = self.blocks.get(&hash).map(|b| b.parents.clone());
+
+        // Insert or update the block, keeping deploys already fetched for it
+        if let Some(existing) = self.blocks.get_mut(&hash) {
+            if !block.deploys_loaded && existing.deploys_loaded {
+                block.deploys = std::mem::take(&mut existing.deploys);
+                block.system_deploys = existing.system_deploys.take();
+                block.deploys_loaded = true;
+            }
+        }
         let parents = block.parents.clone();
-        let is_update = self.blocks.contains_key(&hash);
+        self.blocks.insert(hash.clone(), block);
+
+        match previous_parents {
+            Some(previous) if previous == parents => {}
+            Some(previous) => {
+                self.unlink_parents(&hash, &previous);
+                self.link_parents(&hash, &parents);
+            }
+            None => {
+                // Blocks that arrived first and cite this one become its children
+                match self.waiting.remove(&hash) {
+                    Some(children) => {
+                        self.children.insert(hash.clone(), children);
+                    }
+                    None => self.tips.push(hash.clone()),
+                }
+                self.link_parents(&hash, &parents);
+            }
+        }
+        self.debug_validate();
+    }
 
-        // Only update parent-child relationships for new blocks
-        if !is_update {
-            // Update children map
-            for parent in &parents {
-                self.children
+    /// Remove blocks, with their edges, tips and layout rows
+    ///
+    /// A parent left without known children becomes a tip again. Blocks that
+    /// cite a removed block keep waiting for it, should it be added back.
+    pub fn remove_blocks(&mut self, hashes: &[String]) {
+        let removed: HashSet<&String> = hashes
+            .iter()
+            .filter(|h| self.blocks.contains_key(*h))
+            .collect();
+        if removed.is_empty() {
+            return;
+        }
+        for hash in &removed {
+            let Some(block) = self.blocks.remove(*hash) else {
+                continue;
+            };
+            self.unlink_parents(hash, &block.parents);
+            if let Some(children) = self.children.remove(*hash) {
+                self.waiting.insert((*hash).clone(), children);
+            }
+        }
+        // Children removed in the same call no longer wait for anything
+        self.waiting.retain(|_, children| {
+            children.retain(|c| !removed.contains(c));
+            !children.is_empty()
+        });
+        self.tips.retain(|h| !removed.contains(h));
+        self.sorted_hashes.retain(|h| !removed.contains(h));
+        self.graph_rows
+            .retain(|row| !removed.contains(&row.block_hash));
+        self.debug_validate();
+    }
+
+    /// Record `hash` as a child of each of `parents`
+    fn link_parents(&mut self, hash: &str, parents: &[String]) {
+        for parent in dedup(parents) {
+            if self.blocks.contains_key(parent) {
+                let children = self.children.entry(parent.clone()).or_default();
+                if children.is_empty() {
+                    // Parent is no longer a tip
+                    self.tips.retain(|h| h != parent);
+                }
+                children.push(hash.to_string());
+            } else {
+                self.waiting
                     .entry(parent.clone())
-                    .or_insert_with(Vec::new)
-                    .push(hash.clone());
+                    .or_default()
+                    .push(hash.to_string());
+            }
+        }
+    }
 
-                // Parent is no longer a tip
-                if let Some(pos) = self.tips.iter().position(|h| h == parent) {
-                    self.tips.remove(pos);
+    /// Undo [`link_parents`](Self::link_parents)
+    fn unlink_parents(&mut self, hash: &str, parents: &[String]) {
+        for parent in dedup(parents) {
+            let edges = if self.blocks.contains_key(parent) {
+                &mut self.children
+            } else {
+                &mut self.waiting
+            };
+            let Some(children) = edges.get_mut(parent) else {
+                continue;
+            };
+            children.retain(|c| c != hash);
+            if children.is_empty() {
+                edges.remove(parent);
+                if self.blocks.contains_key(parent) {
+                    self.tips.push(parent.clone());
                 }
             }
+        }
+    }
+
+    #[cfg(debug_assertions)]
+    fn debug_validate(&self) {
+        if let Err(e) = self.validate_invariants() {
+            panic!("DAG bookkeeping is inconsistent: {}", e);
+        }
+    }
 
-            // This block is a tip (for now)
-            self.tips.push(hash.clone());
+    #[cfg(not(debug_assertions))]
+    fn debug_validate(&self) {}
+
+    /// Check `children`, `tips` and `waiting` against `blocks`
+    ///
+    /// Tips are exactly the blocks with no known children, every child edge
+    /// joins two known blocks that cite each other, every waiting edge is from
+    /// a known block to an unknown parent, and no layout row names a block
+    /// that is gone. Runs after every change in debug builds.
+    pub(crate) fn validate_invariants(&self) -> Result<(), String> {
+        let mut expected_children: HashMap<&str, HashSet<&str>> = HashMap::new();
+        let mut expected_waiting: HashMap<&str, HashSet<&str>> = HashMap::new();
+        for (hash, block) in &self.blocks {
+            for parent in dedup(&block.parents) {
+                let edges = if self.blocks.contains_key(parent) {
+                    &mut expected_children
+                } else {
+                    &mut expected_waiting
+                };
+                edges.entry(parent).or_default().insert(hash);
+            }
         }
+        check_edges("children", &self.children, &expected_children)?;
+        check_edges("waiting", &self.waiting, &expected_waiting)?;
 
-        // Insert or update the block
-        self.blocks.insert(hash, block);
+        let tips: HashSet<&str> = self.tips.iter().map(String::as_str).collect();
+        if tips.len() != self.tips.len() {
+            return Err("tips has duplicates".to_string());
+        }
+        let expected_tips: HashSet<&str> = self
+            .blocks
+            .keys()
+            .map(String::as_str)
+            .filter(|h| !expected_children.contains_key(h))
+            .collect();
+        if tips != expected_tips {
+            return Err(format!(
+                "tips are {:?}, blocks without children are {:?}",
+                tips, expected_tips
+            ));
+        }
+        if let Some(stale) = self
+            .sorted_hashes
+            .iter()
+            .chain(self.graph_rows.iter().map(|row| &row.block_hash))
+            .find(|h| !self.blocks.contains_key(*h))
+        {
+            return Err(format!("layout names unknown block {}", stale));
+        }
+        Ok(())
+    }
+
+    /// Record the deploys fetched for a block
+    pub fn set_deploys(
+        &mut self,
+        hash: &str,
+        deploys: Vec<DagDeploy>,
+        system_deploys: Option<Vec<SystemDeploy>>,
+    ) {
+        if let Some(block) = self.blocks.get_mut(hash) {
+            block.deploys = deploys;
+            block.system_deploys = system_deploys;
+            block.deploys_loaded = true;
+        }
     }
 
     /// Update block status
@@ -347,6 +509,19 @@ impl Dag {
             .unwrap_or(1);
     }
 
+    /// Take the layout computed on a snapshot of this DAG (e.g. in a background task).
+    /// Blocks added since the snapshot appear after the next layout.
+    /// Blocks removed since the snapshot are left out.
+    pub fn adopt_layout(&mut self, laid_out: Dag) {
+        self.graph_rows = laid_out.graph_rows;
+        self.sorted_hashes = laid_out.sorted_hashes;
+        self.max_columns = laid_out.max_columns;
+        let blocks = &self.blocks;
+        self.graph_rows
+            .retain(|row| blocks.contains_key(&row.block_hash));
+        self.sorted_hashes.retain(|h| blocks.contains_key(h));
+    }
+
     /// Get the maximum column used in the layout (for backwards compat)
     pub fn max_column(&self) -> usize {
         self.max_columns
@@ -368,3 +543,248 @@ impl Default for Dag {
         Self::new()
     }
 }
+
+/// `parents` without repeats, in order
+fn dedup(parents: &[String]) -> Vec<&String> {
+    let mut seen = HashSet::new();
+    parents.iter().filter(|p| seen.insert(*p)).collect()
+}
+
+/// Compare an edge map with the edges the blocks imply
+fn check_edges(
+    name: &str,
+    edges: &HashMap<String, Vec<String>>,
+    expected: &HashMap<&str, HashSet<&str>>,
+) -> Result<(), String> {
+    for (parent, children) in edges {
+        let found: HashSet<&str> = children.iter().map(String::as_str).collect();
+        if found.len() != children.len() {
+            return Err(format!("{} of {} has duplicates", name, parent));
+        }
+        if expected.get(parent.as_str()) != Some(&found) {
+            return Err(format!(
+                "{} of {} is {:?}, expected {:?}",
+                name,
+                parent,
+                found,
+                expected.get(parent.as_str())
+            ));
+        }
+    }
+    if let Some(missing) = expected.keys().find(|p| !edges.contains_key(**p)) {
+        return Err(format!("{} has no entry for {}", name, missing));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(n: u64) -> String {
+        format!("{:064x}", n)
+    }
+
+    fn block(n: u64, parents: &[u64]) -> DagBlock {
+        DagBlock::new(
+            hash(n),
+            n as i64,
+            Utc::now(),
+            format!("validator{}", n % 3),
+            n as i64,
+            parents.iter().map(|p| hash(*p)).collect(),
+            0,
+            BlockStatus::Created,
+        )
+    }
+
+    /// Deterministic xorshift, so failures reproduce
+    struct Rng(u64);
+
+    impl Rng {
+        fn below(&mut self, n: u64) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0 % n
+        }
+
+        fn shuffle<T>(&mut self, items: &mut [T]) {
+            for i in (1..items.len()).rev() {
+                items.swap(i, self.below(i as u64 + 1) as usize);
+            }
+        }
+    }
+
+    /// Blocks `0..count`, each on one to three earlier blocks
+    fn random_dag(rng: &mut Rng, count: u64) -> Vec<DagBlock> {
+        (0..count)
+            .map(|n| {
+                let parents: Vec<u64> = if n == 0 {
+                    Vec::new()
+                } else {
+                    (0..=rng.below(3)).map(|_| rng.below(n)).collect()
+                };
+                block(n, &parents)
+            })
+            .collect()
+    }
+
+    fn sorted(hashes: &[String]) -> Vec<String> {
+        let mut hashes = hashes.to_vec();
+        hashes.sort();
+        hashes
+    }
+
+    fn assert_same_edges(a: &Dag, b: &Dag) {
+        assert_eq!(sorted(&a.tips), sorted(&b.tips));
+        assert_eq!(a.children.len(), b.children.len());
+        for (parent, children) in &a.children {
+            assert_eq!(sorted(children), sorted(&b.children[parent]));
+        }
+    }
+
+    #[test]
+    fn test_child_before_parent_reconciles_tips() {
+        let mut dag = Dag::new();
+        dag.add_block(block(2, &[1]));
+        assert_eq!(dag.tips, vec![hash(2)]);
+        assert!(dag.children.is_empty());
+
+        dag.add_block(block(1, &[0]));
+        dag.add_block(block(0, &[]));
+        assert_eq!(dag.tips, vec![hash(2)]);
+        assert_eq!(dag.children[&hash(0)], vec![hash(1)]);
+        assert_eq!(dag.children[&hash(1)], vec![hash(2)]);
+        dag.validate_invariants().unwrap();
+    }
+
+    #[test]
+    fn test_random_arrival_orders_match_in_order_build() {
+        let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+        for _ in 0..50 {
+            let blocks = random_dag(&mut rng, 40);
+            let mut in_order = Dag::new();
+            for b in &blocks {
+                in_order.add_block(b.clone());
+            }
+
+            // Shuffled, with some blocks delivered twice and some never
+            let mut arrivals: Vec<DagBlock> = blocks
+                .iter()
+                .filter(|_| rng.below(10) != 0)
+                .cloned()
+                .collect();
+            let repeats: Vec<DagBlock> = arrivals
+                .iter()
+                .filter(|_| rng.below(4) == 0)
+                .cloned()
+                .collect();
+            arrivals.extend(repeats);
+            rng.shuffle(&mut arrivals);
+
+            let mut dag = Dag::new();
+            for b in &arrivals {
+                dag.add_block(b.clone());
+                dag.validate_invariants().unwrap();
+            }
+            // Deliver the missing blocks late
+            for b in &blocks {
+                if !dag.blocks.contains_key(&b.hash) {
+                    dag.add_block(b.clone());
+                    dag.validate_invariants().unwrap();
+                }
+            }
+            assert_same_edges(&dag, &in_order);
+        }
+    }
+
+    #[test]
+    fn test_eviction_prunes_edges_and_layout() {
+        let mut rng = Rng(42);
+        for _ in 0..50 {
+            let blocks = random_dag(&mut rng, 40);
+            let mut dag = Dag::new();
+            for b in &blocks {
+                dag.add_block(b.clone());
+            }
+            dag.compute_layout();
+
+            // Evict the oldest blocks in batches, as a retention policy would,
+            // interleaved with random evictions and status changes
+            let mut next = 0;
+            while next < blocks.len() {
+                let batch = 1 + rng.below(5) as usize;
+                let mut evicted: Vec<String> = blocks[next..(next + batch).min(blocks.len())]
+                    .iter()
+                    .map(|b| b.hash.clone())
+                    .collect();
+                evicted.push(hash(rng.below(blocks.len() as u64)));
+                next += batch;
+                dag.remove_blocks(&evicted);
+                dag.validate_invariants().unwrap();
+                for h in &evicted {
+                    assert!(!dag.blocks.contains_key(h));
+                    assert!(!dag.children.contains_key(h));
+                    assert!(dag.children.values().all(|c| !c.contains(h)));
+                }
+                if let Some(h) = dag.tips.first().cloned() {
+                    dag.update_status(&h, BlockStatus::Finalized);
+                    dag.validate_invariants().unwrap();
+                }
+            }
+            assert!(dag.blocks.is_empty());
+            assert!(dag.tips.is_empty() && dag.children.is_empty());
+            assert!(dag.graph_rows.is_empty() && dag.sorted_hashes.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_evicted_parent_restores_tip_and_can_return() {
+        let mut dag = Dag::new();
+        dag.add_block(block(0, &[]));
+        dag.add_block(block(1, &[0]));
+        dag.add_block(block(2, &[0]));
+
+        // The child goes; its parent is a tip again
+        dag.remove_blocks(&[hash(1), hash(2)]);
+        assert_eq!(dag.tips, vec![hash(0)]);
+        assert!(dag.children.is_empty());
+
+        // The parent goes while its child stays, then comes back
+        dag.add_block(block(1, &[0]));
+        dag.remove_blocks(&[hash(0)]);
+        assert_eq!(dag.tips, vec![hash(1)]);
+        dag.add_block(block(0, &[]));
+        assert_eq!(dag.tips, vec![hash(1)]);
+        assert_eq!(dag.children[&hash(0)], vec![hash(1)]);
+        dag.validate_invariants().unwrap();
+    }
+
+    #[test]
+    fn test_update_with_new_parents_moves_edges() {
+        let mut dag = Dag::new();
+        dag.add_block(block(0, &[]));
+        dag.add_block(block(1, &[0]));
+        dag.add_block(block(2, &[1]));
+        dag.add_block(block(2, &[0, 0]));
+        assert_eq!(sorted(&dag.tips), sorted(&[hash(1), hash(2)]));
+        assert_eq!(sorted(&dag.children[&hash(0)]), sorted(&[hash(1), hash(2)]));
+        assert!(!dag.children.contains_key(&hash(1)));
+        dag.validate_invariants().unwrap();
+    }
+
+    #[test]
+    fn test_adopted_layout_drops_evicted_blocks() {
+        let mut dag = Dag::new();
+        for n in 0..5 {
+            dag.add_block(block(n, &if n > 0 { vec![n - 1] } else { vec![] }));
+        }
+        let mut snapshot = dag.clone();
+        snapshot.compute_layout();
+        dag.remove_blocks(&[hash(0), hash(1)]);
+        dag.adopt_layout(snapshot);
+        assert_eq!(dag.layout_len(), 3);
+        dag.validate_invariants().unwrap();
+    }
+}
@@ -0,0 +1,234 @@
+//! Saved DAG viewer preferences
+//!
+//! Display settings are kept between sessions in `dag_ui.json` in the config
+//! directory, a versioned document read and written through [`crate::store`].
+//! On startup each setting comes from its command-line flag if one was given,
+//! else from the saved file, else the default ([`resolve`]). The current
+//! values are saved when the viewer exits.
+//!
+//! A corrupt file, or one written by a newer CLI with a higher schema version,
+//! is ignored with a warning. A corrupt file is replaced on exit; a newer one
+//! is left alone.
+
+use super::app::DEFAULT_RELAYOUT_INTERVAL_MS;
+use crate::store::{self, Document};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+const PREFS_FILE_NAME: &str = "dag_ui.json";
+const PREFS_SCHEMA_VERSION: u32 = 1;
+const PREFS_FIELD: &str = "prefs";
+
+/// Viewer settings in effect
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UiPrefs {
+    pub show_deploys: bool,
+    pub use_color: bool,
+    pub relayout_interval_ms: u64,
+}
+
+impl Default for UiPrefs {
+    fn default() -> Self {
+        Self {
+            show_deploys: true,
+            use_color: true,
+            relayout_interval_ms: DEFAULT_RELAYOUT_INTERVAL_MS,
+        }
+    }
+}
+
+/// Settings from one source; `None` where that source says nothing
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PartialPrefs {
+    #[serde(default)]
+    pub show_deploys: Option<bool>,
+    #[serde(default)]
+    pub use_color: Option<bool>,
+    #[serde(default)]
+    pub relayout_interval_ms: Option<u64>,
+}
+
+impl From<UiPrefs> for PartialPrefs {
+    fn from(prefs: UiPrefs) -> Self {
+        Self {
+            show_deploys: Some(prefs.show_deploys),
+            use_color: Some(prefs.use_color),
+            relayout_interval_ms: Some(prefs.relayout_interval_ms),
+        }
+    }
+}
+
+/// Each setting from `flags`, else `saved`, else the default
+pub fn resolve(flags: &PartialPrefs, saved: &PartialPrefs) -> UiPrefs {
+    let default = UiPrefs::default();
+    UiPrefs {
+        show_deploys: flags
+            .show_deploys
+            .or(saved.show_deploys)
+            .unwrap_or(default.show_deploys),
+        use_color: flags
+            .use_color
+            .or(saved.use_color)
+            .unwrap_or(default.use_color),
+        relayout_interval_ms: flags
+            .relayout_interval_ms
+            .or(saved.relayout_interval_ms)
+            .unwrap_or(default.relayout_interval_ms),
+    }
+}
+
+/// Saved settings from the text of a preferences file, or why it was ignored
+pub fn parse_saved(text: &str) -> Result<PartialPrefs, String> {
+    let document = Document::parse(text).map_err(|e| format!("unreadable: {}", e))?;
+    if document.schema_version() > PREFS_SCHEMA_VERSION {
+        return Err(format!(
+            "written by a newer node_cli (schema version {})",
+            document.schema_version()
+        ));
+    }
+    match document.get::<serde_json::Value>(PREFS_FIELD) {
+        Some(prefs) => serde_json::from_value(prefs).map_err(|e| format!("unreadable: {}", e)),
+        None => Ok(PartialPrefs::default()),
+    }
+}
+
+/// The preferences file
+#[derive(Debug, Clone)]
+pub struct PrefsFile {
+    path: PathBuf,
+}
+
+impl PrefsFile {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// File in the default config directory, if one can be resolved
+    pub fn open_default() -> Option<Self> {
+        crate::utils::config_dir().map(|dir| Self::new(dir.join(PREFS_FILE_NAME)))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Saved settings; nothing saved if the file does not exist
+    ///
+    /// The error says why an existing file was ignored.
+    pub fn load(&self) -> Result<PartialPrefs, String> {
+        match std::fs::read_to_string(&self.path) {
+            Ok(text) => parse_saved(&text),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(PartialPrefs::default()),
+            Err(e) => Err(format!("unreadable: {}", e)),
+        }
+    }
+
+    /// Save `prefs`, unless the file belongs to a newer CLI
+    pub fn save(&self, prefs: &UiPrefs) -> std::io::Result<()> {
+        store::update_document(&self.path, PREFS_SCHEMA_VERSION, |document| {
+            if document.schema_version() > PREFS_SCHEMA_VERSION {
+                return Ok(());
+            }
+            document.set(PREFS_FIELD, &PartialPrefs::from(*prefs))
+        })
+    }
+
+    /// Forget the saved settings
+    pub fn reset(&self) -> std::io::Result<()> {
+        match std::fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_file(name: &str) -> PrefsFile {
+        let dir = std::env::temp_dir().join(format!("node_cli_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        PrefsFile::new(dir.join(PREFS_FILE_NAME))
+    }
+
+    #[test]
+    fn test_flag_beats_saved_beats_default() {
+        let flags = PartialPrefs {
+            show_deploys: Some(true),
+            ..Default::default()
+        };
+        let saved = PartialPrefs {
+            show_deploys: Some(false),
+            use_color: Some(false),
+            relayout_interval_ms: None,
+        };
+        assert_eq!(
+            resolve(&flags, &saved),
+            UiPrefs {
+                show_deploys: true,
+                use_color: false,
+                relayout_interval_ms: DEFAULT_RELAYOUT_INTERVAL_MS,
+            }
+        );
+        assert_eq!(
+            resolve(&PartialPrefs::default(), &PartialPrefs::default()),
+            UiPrefs::default()
+        );
+    }
+
+    #[test]
+    fn test_corrupt_or_newer_file_is_ignored() {
+        assert!(parse_saved("{not json").is_err());
+        assert!(parse_saved("[1, 2]").is_err());
+        assert!(parse_saved(r#"{"schema_version": 1, "prefs": {"use_color": "yes"}}"#).is_err());
+        let err = parse_saved(r#"{"schema_version": 9, "prefs": {}}"#).unwrap_err();
+        assert!(err.contains("schema version 9"), "{}", err);
+
+        // The ignored file leaves every setting to flags and defaults
+        let saved = parse_saved("{not json").unwrap_or_default();
+        assert_eq!(
+            resolve(&PartialPrefs::default(), &saved),
+            UiPrefs::default()
+        );
+
+        // Unknown settings from a same-version file are skipped
+        let saved =
+            parse_saved(r#"{"schema_version": 1, "prefs": {"use_color": false, "theme": "x"}}"#)
+                .unwrap();
+        assert_eq!(saved.use_color, Some(false));
+    }
+
+    #[test]
+    fn test_save_load_reset() {
+        let file = temp_file("dag_prefs");
+        assert_eq!(file.load(), Ok(PartialPrefs::default()));
+
+        let prefs = UiPrefs {
+            show_deploys: false,
+            use_color: true,
+            relayout_interval_ms: 500,
+        };
+        file.save(&prefs).unwrap();
+        assert_eq!(
+            resolve(&PartialPrefs::default(), &file.load().unwrap()),
+            prefs
+        );
+
+        // A corrupt file is replaced on the next save
+        std::fs::write(file.path(), "garbage").unwrap();
+        assert!(file.load().is_err());
+        file.save(&prefs).unwrap();
+        assert!(file.load().is_ok());
+
+        // A newer CLI's file is not overwritten
+        std::fs::write(file.path(), r#"{"schema_version": 2}"#).unwrap();
+        file.save(&prefs).unwrap();
+        assert!(file.load().is_err());
+
+        file.reset().unwrap();
+        file.reset().unwrap();
+        assert_eq!(file.load(), Ok(PartialPrefs::default()));
+        let _ = std::fs::remove_dir_all(file.path().parent().unwrap());
+    }
+}
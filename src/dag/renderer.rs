@@ -1,9 +1,13 @@
+use std::collections::HashSet;
+
 use ratatui::{
     style::{Color, Modifier, Style},
     text::{Line, Span},
 };
 
-use super::model::{BlockStatus, Dag, GraphRow};
+use super::model::{BlockStatus, Dag, DagBlock, GraphRow};
+use crate::identities::Identities;
+use crate::truncation::{self, shorten, Field};
 
 /// Color palette for validators
 const VALIDATOR_COLORS: [Color; 8] = [
@@ -17,19 +21,162 @@ const VALIDATOR_COLORS: [Color; 8] = [
     Color::LightMagenta,
 ];
 
-// Fixed column widths
+// Column widths
 const CREATOR_WIDTH: usize = 10;
 const BLOCK_WIDTH: usize = 7;
 const HASH_WIDTH: usize = 10;
 const DEPLOYS_WIDTH: usize = 10;
 const STATUS_WIDTH: usize = 8;
 const AGE_WIDTH: usize = 8;
-const SPACING: usize = 2; // Space between column groups
+const SPACING: usize = 2; // One space either side of PARENTS
+
+/// Drawn after the deploy count of highlighted blocks
+pub const HIGHLIGHT_MARKER: char = '◆';
+
+// Narrow-terminal widths for CREATOR and HASH
+const COMPACT_CREATOR_WIDTH: usize = 8;
+const COMPACT_HASH_WIDTH: usize = 9;
+
+/// Hex characters in a block hash
+const BLOCK_HASH_LEN: usize = 64;
+
+/// Terminal widths below which the table gives up detail, widest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WidthThresholds {
+    /// Below this, CREATOR and HASH are shortened
+    pub compact: usize,
+    /// Below this, AGE is dropped
+    pub drop_age: usize,
+    /// Below this, DEPLOYS is dropped
+    pub drop_deploys: usize,
+    /// Below this, PARENTS is dropped
+    pub drop_parents: usize,
+}
+
+impl Default for WidthThresholds {
+    fn default() -> Self {
+        Self {
+            compact: 100,
+            drop_age: 90,
+            drop_deploys: 70,
+            drop_parents: 50,
+        }
+    }
+}
+
+/// Columns drawn at one terminal width
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColumnLayout {
+    pub creator: usize,
+    pub hash: usize,
+    pub deploys: bool,
+    pub age: bool,
+    /// Width of the PARENTS region, or `None` when the column is dropped
+    pub parents: Option<usize>,
+}
+
+impl ColumnLayout {
+    /// Choose columns for `width`: shorten CREATOR/HASH, then drop AGE, DEPLOYS
+    /// and PARENTS in that order as the terminal narrows
+    ///
+    /// HASH widens to fit hashes under `--truncate` or `--full`.
+    pub fn for_width(width: usize, thresholds: &WidthThresholds) -> Self {
+        let (creator, hash) = if width < thresholds.compact {
+            (COMPACT_CREATOR_WIDTH, COMPACT_HASH_WIDTH)
+        } else {
+            (CREATOR_WIDTH, HASH_WIDTH)
+        };
+        let hash = hash.max(truncation::current().display_width(BLOCK_HASH_LEN, Field::Column));
+        let mut layout = Self {
+            creator,
+            hash,
+            deploys: width >= thresholds.drop_deploys,
+            age: width >= thresholds.drop_age,
+            parents: None,
+        };
+        if width >= thresholds.drop_parents {
+            layout.parents = Some(width.saturating_sub(layout.fixed_width() + SPACING));
+        }
+        layout
+    }
+
+    /// Width of every column except PARENTS and the spacing around it
+    pub fn fixed_width(&self) -> usize {
+        let mut width = self.creator + BLOCK_WIDTH + self.hash + STATUS_WIDTH;
+        if self.deploys {
+            width += DEPLOYS_WIDTH;
+        }
+        if self.age {
+            width += AGE_WIDTH;
+        }
+        width
+    }
+}
+
+/// `text` centered in `width` columns, or, when it does not fit, the window
+/// starting at `scroll` with an ellipsis on each side that hides text
+pub fn scroll_window(text: &str, width: usize, scroll: usize) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    if chars.len() <= width {
+        let padding = width - chars.len();
+        let left_pad = padding / 2;
+        return format!(
+            "{}{}{}",
+            " ".repeat(left_pad),
+            text,
+            " ".repeat(padding - left_pad)
+        );
+    }
+    if width == 0 {
+        return String::new();
+    }
+    let start = scroll.min(chars.len() - width);
+    let mut window: Vec<char> = chars[start..start + width].to_vec();
+    if start > 0 {
+        window[0] = '…';
+    }
+    if start + width < chars.len() {
+        window[width - 1] = '…';
+    }
+    window.into_iter().collect()
+}
+
+/// Cut `spans` to at most `width` columns, ending in an ellipsis when anything was cut
+pub fn clip_spans(spans: Vec<Span<'static>>, width: usize) -> Vec<Span<'static>> {
+    let total: usize = spans.iter().map(|s| s.content.chars().count()).sum();
+    if total <= width {
+        return spans;
+    }
+    let mut remaining = width;
+    let mut clipped = Vec::new();
+    for span in spans {
+        if remaining == 0 {
+            break;
+        }
+        let len = span.content.chars().count();
+        if len < remaining {
+            remaining -= len;
+            clipped.push(span);
+        } else {
+            let mut cut: String = span.content.chars().take(remaining - 1).collect();
+            cut.push('…');
+            clipped.push(Span::styled(cut, span.style));
+            remaining = 0;
+        }
+    }
+    clipped
+}
 
 /// Renderer for the DAG visualization
 pub struct DagRenderer {
     pub use_color: bool,
     pub show_deploys: bool,
+    /// Validator names shown in place of creator key prefixes
+    pub identities: Identities,
+    /// Widths at which columns are shortened or dropped
+    pub thresholds: WidthThresholds,
+    /// Blocks with deploys from a highlighted deployer, marked after the deploy count
+    pub highlighted: HashSet<String>,
 }
 
 impl DagRenderer {
@@ -37,38 +184,91 @@ impl DagRenderer {
         Self {
             use_color: true,
             show_deploys: true,
+            identities: Identities::default(),
+            thresholds: WidthThresholds::default(),
+            highlighted: HashSet::new(),
         }
     }
 
+    /// Columns to draw at `total_width`
+    pub fn columns(&self, total_width: usize) -> ColumnLayout {
+        ColumnLayout::for_width(total_width, &self.thresholds)
+    }
+
+    /// Creator label cut to `width` characters: the validator name if known, else the key
+    fn creator_label(&self, block: &DagBlock, width: usize) -> String {
+        self.identities.resolve_validator(&block.creator).fit(width)
+    }
+
     /// Get color for a validator based on column index
     fn validator_color(&self, col: usize) -> Color {
         VALIDATOR_COLORS[col % VALIDATOR_COLORS.len()]
     }
 
-    /// Calculate fixed width (everything except PARENTS column)
-    fn fixed_width(&self) -> usize {
-        CREATOR_WIDTH
-            + BLOCK_WIDTH
-            + HASH_WIDTH
-            + SPACING
-            + SPACING
-            + DEPLOYS_WIDTH
-            + STATUS_WIDTH
-            + AGE_WIDTH
+    /// Parents as `hash[creator:#num] | ...`, or bare hashes when that does not fit `width`
+    pub fn parents_text(&self, block: &DagBlock, dag: &Dag, width: usize) -> String {
+        if block.parents.is_empty() {
+            return "(genesis)".to_string();
+        }
+        let enriched: Vec<String> = block
+            .parents
+            .iter()
+            .map(|p| {
+                let short_hash = shorten(p, Field::Column);
+                if let Some(parent_block) = dag.blocks.get(p) {
+                    format!(
+                        "{}[{}:#{}]",
+                        short_hash,
+                        self.creator_label(parent_block, 8),
+                        parent_block.block_number
+                    )
+                } else {
+                    short_hash
+                }
+            })
+            .collect();
+        let enriched_str = enriched.join(" | ");
+        if enriched_str.len() <= width {
+            return enriched_str;
+        }
+        block
+            .parents
+            .iter()
+            .map(|p| shorten(p, Field::Column))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Columns the parents of `row` overflow their region by at `total_width` (0 if they fit)
+    pub fn parents_overflow(&self, row: &GraphRow, dag: &Dag, total_width: usize) -> usize {
+        let Some(width) = self.columns(total_width).parents else {
+            return 0;
+        };
+        dag.blocks
+            .get(&row.block_hash)
+            .map(|block| {
+                self.parents_text(block, dag, width)
+                    .chars()
+                    .count()
+                    .saturating_sub(width)
+            })
+            .unwrap_or(0)
     }
 
-    /// Render a single row of the DAG
+    /// Render a single row of the DAG, with the parents region scrolled right by `parents_scroll`
     pub fn render_row(
         &self,
         row: &GraphRow,
         dag: &Dag,
         selected: bool,
         total_width: usize,
+        parents_scroll: usize,
     ) -> Line<'static> {
         let block = match dag.blocks.get(&row.block_hash) {
             Some(b) => b,
             None => return Line::from(""),
         };
+        let columns = self.columns(total_width);
 
         let mut spans: Vec<Span> = Vec::new();
 
@@ -77,7 +277,11 @@ impl DagRenderer {
         // Creator
         let creator_color = self.validator_color(row.node_column);
         spans.push(Span::styled(
-            format!("{:<width$}", &block.creator_short, width = CREATOR_WIDTH),
+            format!(
+                "{:<width$}",
+                self.creator_label(block, columns.creator - 1),
+                width = columns.creator
+            ),
             Style::default().fg(creator_color),
         ));
 
@@ -101,94 +305,51 @@ impl DagRenderer {
             Style::default().fg(Color::DarkGray)
         };
         spans.push(Span::styled(
-            format!("{:<width$}", &block.short_hash, width = HASH_WIDTH),
+            format!("{:<width$}", &block.short_hash, width = columns.hash),
             hash_style,
         ));
 
-        spans.push(Span::raw(" "));
-
         // === CENTER (centered): PARENTS - uses all available space ===
-        let parents_width = total_width.saturating_sub(self.fixed_width());
-        let parents_str = if block.parents.is_empty() {
-            "(genesis)".to_string()
-        } else {
-            // Try enriched format first: hash[creator:#blocknum] | hash[creator:#blocknum]
-            let enriched: Vec<String> = block
-                .parents
-                .iter()
-                .map(|p| {
-                    let short_hash = if p.len() >= 8 { &p[..8] } else { p.as_str() };
-                    if let Some(parent_block) = dag.blocks.get(p) {
-                        format!(
-                            "{}[{}:#{}]",
-                            short_hash, &parent_block.creator_short, parent_block.block_number
-                        )
-                    } else {
-                        short_hash.to_string()
-                    }
-                })
-                .collect();
-            let enriched_str = enriched.join(" | ");
-
-            // Fall back to simple format if enriched doesn't fit
-            if enriched_str.len() <= parents_width {
-                enriched_str
-            } else {
-                // Simple format: just hashes
-                block
-                    .parents
-                    .iter()
-                    .map(|p| {
-                        if p.len() >= 8 {
-                            p[..8].to_string()
-                        } else {
-                            p.clone()
-                        }
-                    })
-                    .collect::<Vec<_>>()
-                    .join(" ")
-            }
-        };
-
-        // Center the parents string within the available width
-        let parents_display = if parents_str.len() >= parents_width {
-            // Truncate if too long
-            parents_str.chars().take(parents_width).collect::<String>()
-        } else {
-            // Center it
-            let padding = parents_width - parents_str.len();
-            let left_pad = padding / 2;
-            let right_pad = padding - left_pad;
-            format!(
-                "{}{}{}",
-                " ".repeat(left_pad),
-                parents_str,
-                " ".repeat(right_pad)
-            )
-        };
-
-        spans.push(Span::styled(
-            parents_display,
-            Style::default().fg(Color::DarkGray),
-        ));
-
-        spans.push(Span::raw(" "));
+        if let Some(parents_width) = columns.parents {
+            spans.push(Span::raw(" "));
+            let parents_str = self.parents_text(block, dag, parents_width);
+            spans.push(Span::styled(
+                scroll_window(&parents_str, parents_width, parents_scroll),
+                Style::default().fg(Color::DarkGray),
+            ));
+            spans.push(Span::raw(" "));
+        }
 
         // === RIGHT SIDE (right-aligned): DEPLOYS, STATUS, AGE ===
 
         // Deploy count (right-aligned)
-        let deploy_style = if block.deploy_count > 0 {
-            Style::default()
-                .fg(Color::White)
-                .add_modifier(Modifier::BOLD)
-        } else {
-            Style::default().fg(Color::DarkGray)
-        };
-        let deploy_str = format!("{} dep", block.deploy_count);
-        spans.push(Span::styled(
-            format!("{:>width$}", deploy_str, width = DEPLOYS_WIDTH),
-            deploy_style,
-        ));
+        if columns.deploys {
+            let deploy_style = if block.deploy_count > 0 {
+                Style::default()
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::DarkGray)
+            };
+            let deploy_str = format!("{} dep", block.deploy_count);
+            if self.highlighted.contains(&block.hash) {
+                spans.push(Span::styled(
+                    format!("{:>width$}", deploy_str, width = DEPLOYS_WIDTH - 2),
+                    deploy_style,
+                ));
+                spans.push(Span::styled(
+                    format!(" {}", HIGHLIGHT_MARKER),
+                    Style::default()
+                        .fg(Color::LightMagenta)
+                        .add_modifier(Modifier::BOLD),
+                ));
+            } else {
+                spans.push(Span::styled(
+                    format!("{:>width$}", deploy_str, width = DEPLOYS_WIDTH),
+                    deploy_style,
+                ));
+            }
+        }
 
         // Status (right-aligned)
         let (status_str, status_color) = match block.status {
@@ -202,86 +363,67 @@ impl DagRenderer {
         ));
 
         // Age (right-aligned)
-        spans.push(Span::styled(
-            format!("{:>width$}", block.age_string(), width = AGE_WIDTH),
-            Style::default().fg(Color::DarkGray),
-        ));
+        if columns.age {
+            spans.push(Span::styled(
+                format!("{:>width$}", block.age_string(), width = AGE_WIDTH),
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
 
-        Line::from(spans)
+        Line::from(clip_spans(spans, total_width))
     }
 
     /// Render the column header
     pub fn render_header(&self, total_width: usize) -> Line<'static> {
+        let columns = self.columns(total_width);
+        let header_style = Style::default()
+            .fg(Color::DarkGray)
+            .add_modifier(Modifier::BOLD);
         let mut spans = Vec::new();
 
         // Left side (left-aligned)
         spans.push(Span::styled(
-            format!("{:<width$}", "CREATOR", width = CREATOR_WIDTH),
-            Style::default()
-                .fg(Color::DarkGray)
-                .add_modifier(Modifier::BOLD),
+            format!("{:<width$}", "CREATOR", width = columns.creator),
+            header_style,
         ));
         spans.push(Span::styled(
             format!("{:<width$}", "BLOCK", width = BLOCK_WIDTH),
-            Style::default()
-                .fg(Color::DarkGray)
-                .add_modifier(Modifier::BOLD),
+            header_style,
         ));
         spans.push(Span::styled(
-            format!("{:<width$}", "HASH", width = HASH_WIDTH),
-            Style::default()
-                .fg(Color::DarkGray)
-                .add_modifier(Modifier::BOLD),
+            format!("{:<width$}", "HASH", width = columns.hash),
+            header_style,
         ));
 
-        spans.push(Span::raw(" "));
-
         // Center (centered)
-        let parents_width = total_width.saturating_sub(self.fixed_width());
-        let header_text = "PARENTS";
-        let parents_header = if header_text.len() >= parents_width {
-            header_text.to_string()
-        } else {
-            let padding = parents_width - header_text.len();
-            let left_pad = padding / 2;
-            let right_pad = padding - left_pad;
-            format!(
-                "{}{}{}",
-                " ".repeat(left_pad),
-                header_text,
-                " ".repeat(right_pad)
-            )
-        };
-        spans.push(Span::styled(
-            parents_header,
-            Style::default()
-                .fg(Color::DarkGray)
-                .add_modifier(Modifier::BOLD),
-        ));
-
-        spans.push(Span::raw(" "));
+        if let Some(parents_width) = columns.parents {
+            spans.push(Span::raw(" "));
+            spans.push(Span::styled(
+                scroll_window("PARENTS", parents_width, 0),
+                header_style,
+            ));
+            spans.push(Span::raw(" "));
+        }
 
         // Right side (right-aligned)
-        spans.push(Span::styled(
-            format!("{:>width$}", "DEPLOYS", width = DEPLOYS_WIDTH),
-            Style::default()
-                .fg(Color::DarkGray)
-                .add_modifier(Modifier::BOLD),
-        ));
+        if columns.deploys {
+            spans.push(Span::styled(
+                format!("{:>width$}", "DEPLOYS", width = DEPLOYS_WIDTH),
+                header_style,
+            ));
+        }
         spans.push(Span::styled(
             format!("{:>width$}", "STATUS", width = STATUS_WIDTH),
-            Style::default()
-                .fg(Color::DarkGray)
-                .add_modifier(Modifier::BOLD),
-        ));
-        spans.push(Span::styled(
-            format!("{:>width$}", "AGE", width = AGE_WIDTH),
-            Style::default()
-                .fg(Color::DarkGray)
-                .add_modifier(Modifier::BOLD),
+            header_style,
         ));
+        if columns.age {
+            spans.push(Span::styled(
+                format!("{:>width$}", "AGE", width = AGE_WIDTH),
+                header_style,
+            ));
+        }
 
-        Line::from(spans)
+        Line::from(clip_spans(spans, total_width))
     }
 }
 
@@ -290,3 +432,179 @@ impl Default for DagRenderer {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn layout(width: usize) -> ColumnLayout {
+        ColumnLayout::for_width(width, &WidthThresholds::default())
+    }
+
+    /// Block with many parents so the PARENTS cell overflows on narrow terminals
+    fn dag_with_wide_row() -> Dag {
+        let mut dag = Dag::new();
+        let parents: Vec<String> = (0..6).map(|n| format!("{:064x}", n)).collect();
+        for (n, hash) in parents.iter().enumerate() {
+            dag.add_block(DagBlock::new(
+                hash.clone(),
+                n as i64,
+                Utc::now(),
+                format!("{:0>130}", n),
+                n as i64,
+                Vec::new(),
+                0,
+                BlockStatus::Finalized,
+            ));
+        }
+        dag.add_block(DagBlock::new(
+            format!("{:064x}", 99),
+            99,
+            Utc::now(),
+            "04".repeat(65),
+            99,
+            parents,
+            12,
+            BlockStatus::Added,
+        ));
+        dag.compute_layout();
+        dag
+    }
+
+    #[test]
+    fn test_columns_at_200() {
+        let columns = layout(200);
+        assert_eq!((columns.creator, columns.hash), (CREATOR_WIDTH, HASH_WIDTH));
+        assert!(columns.deploys && columns.age);
+        assert_eq!(columns.parents, Some(145));
+    }
+
+    #[test]
+    fn test_columns_at_120() {
+        let columns = layout(120);
+        assert_eq!((columns.creator, columns.hash), (CREATOR_WIDTH, HASH_WIDTH));
+        assert!(columns.deploys && columns.age);
+        assert_eq!(columns.parents, Some(65));
+    }
+
+    #[test]
+    fn test_columns_at_80() {
+        // Compact widths and no AGE leave PARENTS room for a few hashes
+        let columns = layout(80);
+        assert_eq!(
+            (columns.creator, columns.hash),
+            (COMPACT_CREATOR_WIDTH, COMPACT_HASH_WIDTH)
+        );
+        assert!(columns.deploys);
+        assert!(!columns.age);
+        assert_eq!(columns.parents, Some(36));
+    }
+
+    #[test]
+    fn test_columns_at_60() {
+        let columns = layout(60);
+        assert!(!columns.deploys && !columns.age);
+        assert_eq!(columns.parents, Some(26));
+
+        // Below the last threshold only the identifying columns remain
+        assert_eq!(layout(40).parents, None);
+        assert_eq!(layout(40).fixed_width(), 32);
+    }
+
+    #[test]
+    fn test_custom_thresholds() {
+        let thresholds = WidthThresholds {
+            compact: 0,
+            drop_age: 0,
+            drop_deploys: 0,
+            drop_parents: 0,
+        };
+        let columns = ColumnLayout::for_width(60, &thresholds);
+        assert!(columns.deploys && columns.age);
+        assert_eq!(columns.parents, Some(5));
+    }
+
+    #[test]
+    fn test_scroll_window() {
+        assert_eq!(scroll_window("abc", 7, 3), "  abc  ");
+        assert_eq!(scroll_window("abcdefghij", 5, 0), "abcd…");
+        assert_eq!(scroll_window("abcdefghij", 5, 2), "…def…");
+        // Scrolling past the end stops at the last window
+        assert_eq!(scroll_window("abcdefghij", 5, 50), "…ghij");
+        assert_eq!(scroll_window("abc", 0, 0), "");
+    }
+
+    #[test]
+    fn test_clip_spans() {
+        let spans = vec![Span::raw("abcd"), Span::raw("efgh")];
+        let clipped = clip_spans(spans.clone(), 4);
+        assert_eq!(Line::from(clipped).to_string(), "abc…");
+        assert_eq!(
+            Line::from(clip_spans(spans.clone(), 6)).to_string(),
+            "abcde…"
+        );
+        assert_eq!(Line::from(clip_spans(spans, 8)).to_string(), "abcdefgh");
+    }
+
+    #[test]
+    fn test_rows_never_exceed_frame_width() {
+        let dag = dag_with_wide_row();
+        let renderer = DagRenderer::new();
+        for width in [10, 33, 45, 60, 80, 120, 200] {
+            assert!(
+                renderer.render_header(width).width() <= width,
+                "header at {}",
+                width
+            );
+            for row in &dag.graph_rows {
+                for scroll in [0, 8, 1000] {
+                    let line = renderer.render_row(row, &dag, false, width, scroll);
+                    assert!(line.width() <= width, "{:?} at {}", line.to_string(), width);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_parents_overflow_tracks_truncation() {
+        let dag = dag_with_wide_row();
+        let renderer = DagRenderer::new();
+        let wide = dag
+            .graph_rows
+            .iter()
+            .find(|r| r.block_hash == format!("{:064x}", 99))
+            .unwrap();
+        assert!(renderer.parents_overflow(wide, &dag, 80) > 0);
+        assert_eq!(renderer.parents_overflow(wide, &dag, 200), 0);
+        // PARENTS dropped entirely: nothing to scroll
+        assert_eq!(renderer.parents_overflow(wide, &dag, 40), 0);
+    }
+
+    #[test]
+    fn test_full_hashes() {
+        let _full = truncation::scoped::Scoped::set(truncation::Truncation::Full);
+        let dag = dag_with_wide_row();
+        let renderer = DagRenderer::new();
+        let columns = layout(200);
+        assert_eq!(columns.hash, 64);
+        for row in &dag.graph_rows {
+            let line = renderer.render_row(row, &dag, false, 200, 0).to_string();
+            assert!(line.contains(&row.block_hash), "{}", line);
+            assert!(line.chars().count() <= 200, "{}", line);
+        }
+        let wide = format!("{:064x}", 99);
+        let parents = renderer.parents_text(&dag.blocks[&wide], &dag, 1000);
+        assert!(parents.contains(&format!("{:064x}", 5)), "{}", parents);
+        assert!(!parents.contains(truncation::ELLIPSIS));
+    }
+
+    #[test]
+    fn test_shortened_hashes_are_marked() {
+        let dag = dag_with_wide_row();
+        let block = &dag.blocks[&format!("{:064x}", 99)];
+        assert_eq!(block.short_hash, format!("{:08}…", 0));
+        let parents = DagRenderer::new().parents_text(block, &dag, 20);
+        assert!(parents.starts_with("00000000… "), "{}", parents);
+    }
+}
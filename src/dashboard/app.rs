@@ -0,0 +1,343 @@
+use std::io;
+use std::time::Duration;
+
+use crossterm::{
+    event::{self, Event, KeyEventKind},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, Paragraph, Row, Sparkline, Table},
+    Frame, Terminal,
+};
+use tokio::sync::mpsc;
+
+use super::model::{short_hash, AlertKind, Dashboard, DashboardUpdate, NodeState, Pane};
+use crate::truncation::{self, Field};
+
+/// How long to wait for input before drawing again
+const INPUT_POLL: Duration = Duration::from_millis(200);
+
+/// Puts the terminal back however the dashboard exits, including on panic
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn enter() -> io::Result<Self> {
+        enable_raw_mode()?;
+        if let Err(e) = execute!(io::stdout(), EnterAlternateScreen) {
+            let _ = disable_raw_mode();
+            return Err(e);
+        }
+        Ok(Self)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = disable_raw_mode();
+        let _ = execute!(io::stdout(), LeaveAlternateScreen, crossterm::cursor::Show);
+    }
+}
+
+/// The dashboard TUI: drains updates into the view models and draws them
+pub struct DashboardApp {
+    pub dashboard: Dashboard,
+    updates: mpsc::Receiver<DashboardUpdate>,
+}
+
+impl DashboardApp {
+    pub fn new(dashboard: Dashboard, updates: mpsc::Receiver<DashboardUpdate>) -> Self {
+        Self { dashboard, updates }
+    }
+
+    /// Run until `q` or `Esc`
+    pub async fn run(&mut self) -> io::Result<()> {
+        let _guard = TerminalGuard::enter()?;
+        let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+        terminal.clear()?;
+
+        while self.dashboard.running {
+            // While paused, updates stay queued and the background tasks wait on the channel
+            if !self.dashboard.paused {
+                while let Ok(update) = self.updates.try_recv() {
+                    let now = chrono::Utc::now().timestamp_millis();
+                    self.dashboard.apply(update, now);
+                }
+            }
+
+            terminal.draw(|frame| render(&self.dashboard, frame))?;
+
+            if event::poll(INPUT_POLL)? {
+                if let Event::Key(key) = event::read()? {
+                    if key.kind == KeyEventKind::Press {
+                        self.dashboard.handle_key(key.code);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn render(dashboard: &Dashboard, frame: &mut Frame) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage(40),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .split(frame.area());
+    let top = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(rows[0]);
+    let bottom = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+        .split(rows[1]);
+
+    render_health(dashboard, frame, top[0]);
+    render_finality(dashboard, frame, top[1]);
+    render_blocks(dashboard, frame, bottom[0]);
+    render_alerts(dashboard, frame, bottom[1]);
+    render_status(dashboard, frame, rows[2]);
+}
+
+/// Border for `pane`, highlighted when it has focus
+fn pane_block(dashboard: &Dashboard, pane: Pane, detail: String) -> Block<'static> {
+    let number = Pane::ALL.iter().position(|&p| p == pane).unwrap_or(0) + 1;
+    let color = if dashboard.focus == pane {
+        Color::Cyan
+    } else {
+        Color::DarkGray
+    };
+    Block::default()
+        .title(format!(" {} {}{} ", number, pane.title(), detail))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(color))
+}
+
+fn render_health(dashboard: &Dashboard, frame: &mut Frame, area: Rect) {
+    let health = &dashboard.health;
+    let detail = format!(" ({}/{} up)", health.up_count(), health.nodes.len());
+    let rows = health.nodes.iter().map(|node| {
+        let (state, style, peers, lfb) = match &node.state {
+            NodeState::Pending => (
+                "...",
+                Style::default().fg(Color::DarkGray),
+                "-".into(),
+                "-".into(),
+            ),
+            NodeState::Up {
+                peers,
+                lfb_height,
+                ready,
+            } => (
+                if *ready == Some(false) {
+                    "STARTING"
+                } else {
+                    "UP"
+                },
+                Style::default().fg(Color::Green),
+                peers.to_string(),
+                lfb_height.map_or("-".to_string(), |h| h.to_string()),
+            ),
+            NodeState::Down(_) => (
+                "DOWN",
+                Style::default().fg(Color::Red),
+                "-".into(),
+                "-".into(),
+            ),
+        };
+        Row::new(vec![
+            Cell::from(node.name.clone()),
+            Cell::from(state).style(style),
+            Cell::from(peers),
+            Cell::from(lfb),
+        ])
+    });
+    let hash_width = truncation::current()
+        .display_width(64, Field::Column)
+        .max(9) as u16;
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Min(12),
+            Constraint::Length(9),
+            Constraint::Length(6),
+            Constraint::Length(8),
+        ],
+    )
+    .header(
+        Row::new(vec!["NODE", "STATUS", "PEERS", "LFB"])
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+    )
+    .block(pane_block(dashboard, Pane::Health, detail));
+    frame.render_widget(table, area);
+}
+
+fn render_finality(dashboard: &Dashboard, frame: &mut Frame, area: Rect) {
+    let finality = &dashboard.finality;
+    let block = pane_block(dashboard, Pane::Finality, String::new());
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    let parts = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(4), Constraint::Min(0)])
+        .split(inner);
+
+    let value = |v: Option<String>| v.unwrap_or_else(|| "-".to_string());
+    let latest = finality.latest.as_ref();
+    let lag_style = match finality.lag() {
+        Some(lag) if lag > 10 => Style::default().fg(Color::Yellow),
+        _ => Style::default(),
+    };
+    let lines = vec![
+        Line::from(format!(
+            "LFB:  #{} {}",
+            value(latest.map(|s| s.lfb_height.to_string())),
+            latest.map_or(String::new(), |s| short_hash(&s.lfb_hash))
+        )),
+        Line::from(vec![
+            Span::raw("Lag:  "),
+            Span::styled(
+                value(
+                    finality
+                        .lag()
+                        .map(|lag| format!("{} block(s) behind tip", lag)),
+                ),
+                lag_style,
+            ),
+        ]),
+        Line::from(format!(
+            "Fault tolerance: {}",
+            value(
+                finality
+                    .fault_tolerance
+                    .back()
+                    .map(|ft| format!("{:.3}", ft))
+            )
+        )),
+        if finality.stalled {
+            Line::from(Span::styled("STALLED", Style::default().fg(Color::Red)))
+        } else {
+            Line::from("")
+        },
+    ];
+    frame.render_widget(Paragraph::new(lines), parts[0]);
+
+    let data = finality.sparkline();
+    let sparkline = Sparkline::default()
+        .block(Block::default().title("fault tolerance trend"))
+        .data(&data)
+        .max(100)
+        .style(Style::default().fg(Color::Cyan));
+    frame.render_widget(sparkline, parts[1]);
+}
+
+fn render_blocks(dashboard: &Dashboard, frame: &mut Frame, area: Rect) {
+    let lfb = dashboard.finality.lfb_height();
+    let blocks = &dashboard.blocks;
+    let rows = blocks.rows.iter().skip(blocks.scroll).map(|row| {
+        let finalized = matches!((row.height, lfb), (Some(h), Some(lfb)) if h <= lfb);
+        let time = chrono::DateTime::from_timestamp_millis(row.received_at)
+            .map(|t| {
+                t.with_timezone(&chrono::Local)
+                    .format("%H:%M:%S")
+                    .to_string()
+            })
+            .unwrap_or_default();
+        Row::new(vec![
+            Cell::from(row.height.map_or("-".to_string(), |h| h.to_string())),
+            Cell::from(short_hash(&row.hash)),
+            Cell::from(row.creator.as_deref().map_or("-".to_string(), short_hash)),
+            Cell::from(time),
+            if finalized {
+                Cell::from("final").style(Style::default().fg(Color::Green))
+            } else {
+                Cell::from("added")
+            },
+        ])
+    });
+    let hash_width = truncation::current()
+        .display_width(64, Field::Column)
+        .max(9) as u16;
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(8),
+            Constraint::Length(hash_width),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(6),
+        ],
+    )
+    .header(
+        Row::new(vec!["HEIGHT", "HASH", "CREATOR", "SEEN", ""])
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+    )
+    .block(pane_block(
+        dashboard,
+        Pane::Blocks,
+        format!(" ({})", blocks.rows.len()),
+    ));
+    frame.render_widget(table, area);
+}
+
+fn render_alerts(dashboard: &Dashboard, frame: &mut Frame, area: Rect) {
+    let alerts = &dashboard.alerts;
+    let lines: Vec<Line> = alerts
+        .alerts
+        .iter()
+        .skip(alerts.scroll)
+        .map(|alert| {
+            let color = match alert.kind {
+                AlertKind::Stall | AlertKind::Reorg | AlertKind::Equivocation => Color::Red,
+                AlertKind::Resumed => Color::Green,
+            };
+            let time = chrono::DateTime::from_timestamp_millis(alert.raised_at)
+                .map(|t| {
+                    t.with_timezone(&chrono::Local)
+                        .format("%H:%M:%S")
+                        .to_string()
+                })
+                .unwrap_or_default();
+            Line::from(vec![
+                Span::styled(format!("{} ", time), Style::default().fg(Color::DarkGray)),
+                Span::styled(
+                    format!("{:<12} ", alert.kind.label()),
+                    Style::default().fg(color),
+                ),
+                Span::raw(alert.message.clone()),
+            ])
+        })
+        .collect();
+    let detail = format!(" ({})", alerts.alerts.len());
+    let paragraph = Paragraph::new(lines).block(pane_block(dashboard, Pane::Alerts, detail));
+    frame.render_widget(paragraph, area);
+}
+
+fn render_status(dashboard: &Dashboard, frame: &mut Frame, area: Rect) {
+    let state = if dashboard.paused {
+        Span::styled(
+            " PAUSED ",
+            Style::default().fg(Color::Black).bg(Color::Yellow),
+        )
+    } else {
+        Span::styled(" LIVE ", Style::default().fg(Color::Black).bg(Color::Green))
+    };
+    let line = Line::from(vec![
+        state,
+        Span::raw(format!(" {}  ", dashboard.status_message)),
+        Span::styled(
+            "[Tab/1-4] Focus  [j/k] Scroll  [p] Pause  [q] Quit",
+            Style::default().fg(Color::DarkGray),
+        ),
+    ]);
+    frame.render_widget(Paragraph::new(line), area);
+}
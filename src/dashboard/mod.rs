@@ -0,0 +1,7 @@
+pub mod app;
+pub mod model;
+
+pub use app::DashboardApp;
+pub use model::{
+    Alert, AlertKind, BlockRow, Dashboard, DashboardUpdate, FinalitySample, NodeState, Pane,
+};
@@ -0,0 +1,661 @@
+//! View models for the dashboard panes
+//!
+//! Each pane is a plain struct. Background tasks send [`DashboardUpdate`]s
+//! over a channel and [`Dashboard::apply`] folds them in, so everything the
+//! panes show can be tested without a terminal. Times are Unix epoch
+//! milliseconds, as in [`crate::events::BlockEvent`].
+//!
+//! Stalls are raised from the finality samples and reorgs from successive
+//! main chains; equivocations arrive already detected by
+//! [`crate::equivocation::EquivocationTracker`] in the block task.
+
+use crossterm::event::KeyCode;
+use std::collections::{BTreeMap, VecDeque};
+use std::time::Duration;
+
+use crate::equivocation::Equivocation;
+use crate::topology::NodeEndpoint;
+use crate::truncation::{self, Field};
+
+/// Newest blocks kept for the recent blocks pane
+pub const MAX_BLOCK_ROWS: usize = 100;
+
+/// Newest alerts kept for the alerts pane
+pub const MAX_ALERTS: usize = 50;
+
+/// Fault tolerance samples kept for the trend sparkline
+pub const FAULT_TOLERANCE_SAMPLES: usize = 60;
+
+/// Heights of main chain compared between samples for reorgs
+pub const REORG_WINDOW: i64 = 20;
+
+/// What the last health check of a node found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeState {
+    /// Not checked yet
+    Pending,
+    Up {
+        peers: i32,
+        lfb_height: Option<i64>,
+        ready: Option<bool>,
+    },
+    Down(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeHealth {
+    pub name: String,
+    pub address: String,
+    pub state: NodeState,
+    pub checked_at: Option<i64>,
+}
+
+/// Per-node status from the topology
+#[derive(Debug, Clone, Default)]
+pub struct HealthPane {
+    pub nodes: Vec<NodeHealth>,
+}
+
+impl HealthPane {
+    pub fn new(nodes: &[NodeEndpoint]) -> Self {
+        Self {
+            nodes: nodes
+                .iter()
+                .map(|node| NodeHealth {
+                    name: node.name.clone(),
+                    address: node.address(),
+                    state: NodeState::Pending,
+                    checked_at: None,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn update(&mut self, name: &str, state: NodeState, now: i64) {
+        if let Some(node) = self.nodes.iter_mut().find(|node| node.name == name) {
+            node.state = state;
+            node.checked_at = Some(now);
+        }
+    }
+
+    pub fn up_count(&self) -> usize {
+        self.nodes
+            .iter()
+            .filter(|node| matches!(node.state, NodeState::Up { .. }))
+            .count()
+    }
+}
+
+/// One reading of the followed node's finality
+#[derive(Debug, Clone, PartialEq)]
+pub struct FinalitySample {
+    pub lfb_height: i64,
+    pub lfb_hash: String,
+    pub tip_height: Option<i64>,
+    pub fault_tolerance: Option<f64>,
+}
+
+/// Last finalized block, its lag behind the tip, and the fault tolerance trend
+#[derive(Debug, Clone, Default)]
+pub struct FinalityPane {
+    pub latest: Option<FinalitySample>,
+    pub fault_tolerance: VecDeque<f64>,
+    /// When the LFB height last moved
+    pub advanced_at: Option<i64>,
+    pub stalled: bool,
+}
+
+impl FinalityPane {
+    /// Heights the LFB is behind the tip
+    pub fn lag(&self) -> Option<i64> {
+        let latest = self.latest.as_ref()?;
+        Some((latest.tip_height? - latest.lfb_height).max(0))
+    }
+
+    pub fn lfb_height(&self) -> Option<i64> {
+        self.latest.as_ref().map(|sample| sample.lfb_height)
+    }
+
+    /// Fold in a sample, returning the alerts it raises
+    ///
+    /// A stall is raised once when the LFB height has not moved for
+    /// `stall_after`, and cleared with a resumed alert when it moves again. A
+    /// finalized block replaced at the same height, or an LFB that moves
+    /// backwards, is a reorg of finalized history.
+    fn observe(&mut self, sample: FinalitySample, now: i64, stall_after: Duration) -> Vec<Alert> {
+        let mut alerts = Vec::new();
+        let previous = self.latest.take();
+        match &previous {
+            Some(previous) if sample.lfb_height == previous.lfb_height => {
+                if sample.lfb_hash != previous.lfb_hash {
+                    alerts.push(Alert::new(
+                        AlertKind::Reorg,
+                        now,
+                        format!(
+                            "Finalized block at height {} changed from {} to {}",
+                            sample.lfb_height,
+                            short_hash(&previous.lfb_hash),
+                            short_hash(&sample.lfb_hash)
+                        ),
+                    ));
+                }
+            }
+            Some(previous) if sample.lfb_height < previous.lfb_height => {
+                alerts.push(Alert::new(
+                    AlertKind::Reorg,
+                    now,
+                    format!(
+                        "Last finalized block went back from height {} to {}",
+                        previous.lfb_height, sample.lfb_height
+                    ),
+                ));
+                self.advanced_at = Some(now);
+            }
+            _ => {
+                if self.stalled {
+                    alerts.push(Alert::new(
+                        AlertKind::Resumed,
+                        now,
+                        format!("Finalization resumed at height {}", sample.lfb_height),
+                    ));
+                    self.stalled = false;
+                }
+                self.advanced_at = Some(now);
+            }
+        }
+
+        let advanced_at = self.advanced_at.unwrap_or(now);
+        let stalled_for = Duration::from_millis((now - advanced_at).max(0) as u64);
+        if !self.stalled && stalled_for >= stall_after {
+            self.stalled = true;
+            alerts.push(Alert::new(
+                AlertKind::Stall,
+                now,
+                format!(
+                    "Last finalized block stuck at height {} for {}s",
+                    sample.lfb_height,
+                    stalled_for.as_secs()
+                ),
+            ));
+        }
+
+        if let Some(fault_tolerance) = sample.fault_tolerance {
+            if self.fault_tolerance.len() == FAULT_TOLERANCE_SAMPLES {
+                self.fault_tolerance.pop_front();
+            }
+            self.fault_tolerance.push_back(fault_tolerance);
+        }
+        self.latest = Some(sample);
+        alerts
+    }
+
+    /// Fault tolerance trend scaled from -1..=1 to 0..=100 for a sparkline
+    pub fn sparkline(&self) -> Vec<u64> {
+        self.fault_tolerance
+            .iter()
+            .map(|ft| ((ft.clamp(-1.0, 1.0) + 1.0) * 50.0).round() as u64)
+            .collect()
+    }
+}
+
+/// A block from the event stream
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockRow {
+    pub height: Option<i64>,
+    pub hash: String,
+    pub creator: Option<String>,
+    pub received_at: i64,
+}
+
+/// Recent blocks, newest first
+#[derive(Debug, Clone, Default)]
+pub struct BlocksPane {
+    pub rows: VecDeque<BlockRow>,
+    pub scroll: usize,
+}
+
+impl BlocksPane {
+    fn push(&mut self, row: BlockRow) {
+        if self.rows.iter().any(|r| r.hash == row.hash) {
+            return;
+        }
+        self.rows.push_front(row);
+        self.rows.truncate(MAX_BLOCK_ROWS);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertKind {
+    Stall,
+    Resumed,
+    Reorg,
+    Equivocation,
+}
+
+impl AlertKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            AlertKind::Stall => "STALL",
+            AlertKind::Resumed => "RESUMED",
+            AlertKind::Reorg => "REORG",
+            AlertKind::Equivocation => "EQUIVOCATION",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alert {
+    pub kind: AlertKind,
+    pub raised_at: i64,
+    pub message: String,
+}
+
+impl Alert {
+    pub fn new(kind: AlertKind, raised_at: i64, message: String) -> Self {
+        Self {
+            kind,
+            raised_at,
+            message,
+        }
+    }
+}
+
+/// Recent alerts, newest first
+#[derive(Debug, Clone, Default)]
+pub struct AlertsPane {
+    pub alerts: VecDeque<Alert>,
+    pub scroll: usize,
+}
+
+impl AlertsPane {
+    fn push(&mut self, alert: Alert) {
+        self.alerts.push_front(alert);
+        self.alerts.truncate(MAX_ALERTS);
+    }
+}
+
+/// A main-chain block replaced by another at the same height
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reorg {
+    /// Lowest height whose main-chain block changed
+    pub height: i64,
+    /// Main-chain blocks replaced, from `height` to the old tip
+    pub depth: i64,
+    pub old_hash: String,
+    pub new_hash: String,
+}
+
+/// Compares successive main chains over the last [`REORG_WINDOW`] heights
+#[derive(Debug, Clone, Default)]
+pub struct ReorgTracker {
+    main: BTreeMap<i64, String>,
+}
+
+impl ReorgTracker {
+    /// Record `chain` (height, hash) and report the deepest change from the last one
+    pub fn observe(&mut self, chain: &[(i64, String)]) -> Option<Reorg> {
+        let old_tip = self.main.keys().next_back().copied();
+        let reorg = chain
+            .iter()
+            .filter_map(|(height, hash)| {
+                let old = self.main.get(height)?;
+                (old != hash).then(|| (*height, old.clone(), hash.clone()))
+            })
+            .min_by_key(|(height, _, _)| *height)
+            .map(|(height, old_hash, new_hash)| Reorg {
+                height,
+                depth: old_tip.map_or(1, |tip| tip - height + 1),
+                old_hash,
+                new_hash,
+            });
+
+        for (height, hash) in chain {
+            self.main.insert(*height, hash.clone());
+        }
+        if let Some(&tip) = self.main.keys().next_back() {
+            self.main = self.main.split_off(&(tip - REORG_WINDOW + 1));
+        }
+        reorg
+    }
+}
+
+/// What background tasks send the dashboard
+#[derive(Debug, Clone)]
+pub enum DashboardUpdate {
+    Health {
+        name: String,
+        state: NodeState,
+    },
+    Finality(FinalitySample),
+    /// Main chain over the last [`REORG_WINDOW`] heights, as (height, hash)
+    MainChain(Vec<(i64, String)>),
+    Block(BlockRow),
+    Equivocation(Equivocation),
+    Error(String),
+}
+
+/// A dashboard pane, in focus order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pane {
+    Health,
+    Finality,
+    Blocks,
+    Alerts,
+}
+
+impl Pane {
+    pub const ALL: [Pane; 4] = [Pane::Health, Pane::Finality, Pane::Blocks, Pane::Alerts];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Pane::Health => "Node health",
+            Pane::Finality => "Finality",
+            Pane::Blocks => "Recent blocks",
+            Pane::Alerts => "Alerts",
+        }
+    }
+
+    fn index(self) -> usize {
+        Pane::ALL.iter().position(|&p| p == self).unwrap_or(0)
+    }
+
+    pub fn next(self) -> Self {
+        Pane::ALL[(self.index() + 1) % Pane::ALL.len()]
+    }
+
+    pub fn previous(self) -> Self {
+        Pane::ALL[(self.index() + Pane::ALL.len() - 1) % Pane::ALL.len()]
+    }
+}
+
+/// Everything the dashboard shows, and which pane has focus
+#[derive(Debug, Clone)]
+pub struct Dashboard {
+    pub health: HealthPane,
+    pub finality: FinalityPane,
+    pub blocks: BlocksPane,
+    pub alerts: AlertsPane,
+    pub focus: Pane,
+    /// Updates are left queued while paused
+    pub paused: bool,
+    pub running: bool,
+    pub status_message: String,
+    pub stall_after: Duration,
+    reorgs: ReorgTracker,
+}
+
+impl Dashboard {
+    pub fn new(nodes: &[NodeEndpoint], stall_after: Duration) -> Self {
+        Self {
+            health: HealthPane::new(nodes),
+            finality: FinalityPane::default(),
+            blocks: BlocksPane::default(),
+            alerts: AlertsPane::default(),
+            focus: Pane::Health,
+            paused: false,
+            running: true,
+            status_message: "Connecting...".to_string(),
+            stall_after,
+            reorgs: ReorgTracker::default(),
+        }
+    }
+
+    /// Fold in an update received at `now`
+    pub fn apply(&mut self, update: DashboardUpdate, now: i64) {
+        match update {
+            DashboardUpdate::Health { name, state } => self.health.update(&name, state, now),
+            DashboardUpdate::Finality(sample) => {
+                for alert in self.finality.observe(sample, now, self.stall_after) {
+                    self.alerts.push(alert);
+                }
+                self.status_message = "Refreshed".to_string();
+            }
+            DashboardUpdate::MainChain(chain) => {
+                if let Some(reorg) = self.reorgs.observe(&chain) {
+                    self.alerts.push(Alert::new(
+                        AlertKind::Reorg,
+                        now,
+                        format!(
+                            "Main chain replaced from height {} ({} block(s)): {} -> {}",
+                            reorg.height,
+                            reorg.depth,
+                            short_hash(&reorg.old_hash),
+                            short_hash(&reorg.new_hash)
+                        ),
+                    ));
+                }
+            }
+            DashboardUpdate::Block(row) => self.blocks.push(row),
+            DashboardUpdate::Equivocation(equivocation) => {
+                self.alerts.push(Alert::new(
+                    AlertKind::Equivocation,
+                    now,
+                    format!(
+                        "Validator {} created {} blocks with seq_num {}",
+                        short_hash(&equivocation.creator),
+                        equivocation.blocks.len(),
+                        equivocation.seq_num
+                    ),
+                ));
+            }
+            DashboardUpdate::Error(message) => self.status_message = format!("Error: {}", message),
+        }
+    }
+
+    pub fn handle_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => self.running = false,
+            KeyCode::Tab => self.focus = self.focus.next(),
+            KeyCode::BackTab => self.focus = self.focus.previous(),
+            KeyCode::Char(c @ '1'..='4') => {
+                self.focus = Pane::ALL[c as usize - '1' as usize];
+            }
+            KeyCode::Char('p') | KeyCode::Char(' ') => {
+                self.paused = !self.paused;
+                self.status_message = if self.paused {
+                    "Paused".to_string()
+                } else {
+                    "Resumed".to_string()
+                };
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                if let Some(scroll) = self.focused_scroll() {
+                    *scroll = scroll.saturating_sub(1);
+                }
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                let len = match self.focus {
+                    Pane::Blocks => self.blocks.rows.len(),
+                    Pane::Alerts => self.alerts.alerts.len(),
+                    _ => 0,
+                };
+                if let Some(scroll) = self.focused_scroll() {
+                    *scroll = (*scroll + 1).min(len.saturating_sub(1));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn focused_scroll(&mut self) -> Option<&mut usize> {
+        match self.focus {
+            Pane::Blocks => Some(&mut self.blocks.scroll),
+            Pane::Alerts => Some(&mut self.alerts.scroll),
+            _ => None,
+        }
+    }
+}
+
+/// A hash or key shortened for the dashboard's narrow columns
+pub fn short_hash(hash: &str) -> String {
+    truncation::shorten(hash, Field::Column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::equivocation::BlockMeta;
+
+    const STALL: Duration = Duration::from_secs(60);
+
+    fn sample(lfb_height: i64, lfb_hash: &str, tip: i64, ft: f64) -> DashboardUpdate {
+        DashboardUpdate::Finality(FinalitySample {
+            lfb_height,
+            lfb_hash: lfb_hash.to_string(),
+            tip_height: Some(tip),
+            fault_tolerance: Some(ft),
+        })
+    }
+
+    fn kinds(dashboard: &Dashboard) -> Vec<AlertKind> {
+        dashboard
+            .alerts
+            .alerts
+            .iter()
+            .rev()
+            .map(|a| a.kind)
+            .collect()
+    }
+
+    fn dashboard() -> Dashboard {
+        let nodes = [
+            NodeEndpoint::parse("v1=localhost:40413").unwrap(),
+            NodeEndpoint::parse("localhost:40453").unwrap(),
+        ];
+        Dashboard::new(&nodes, STALL)
+    }
+
+    #[test]
+    fn test_health_updates_by_name() {
+        let mut d = dashboard();
+        assert_eq!(d.health.nodes[1].name, "localhost:40453");
+        let up = NodeState::Up {
+            peers: 4,
+            lfb_height: Some(10),
+            ready: Some(true),
+        };
+        d.apply(
+            DashboardUpdate::Health {
+                name: "v1".to_string(),
+                state: up.clone(),
+            },
+            1_000,
+        );
+        d.apply(
+            DashboardUpdate::Health {
+                name: "localhost:40453".to_string(),
+                state: NodeState::Down("refused".to_string()),
+            },
+            1_000,
+        );
+        assert_eq!(d.health.nodes[0].state, up);
+        assert_eq!(d.health.nodes[0].checked_at, Some(1_000));
+        assert_eq!(d.health.up_count(), 1);
+    }
+
+    #[test]
+    fn test_stall_raised_once_and_cleared() {
+        let mut d = dashboard();
+        d.apply(sample(10, "a", 12, 0.5), 0);
+        assert_eq!(d.finality.lag(), Some(2));
+        d.apply(sample(10, "a", 14, 0.4), 30_000);
+        assert!(d.alerts.alerts.is_empty());
+        d.apply(sample(10, "a", 16, 0.3), 60_000);
+        d.apply(sample(10, "a", 18, 0.2), 90_000);
+        assert_eq!(kinds(&d), [AlertKind::Stall]);
+        assert!(d.finality.stalled);
+        d.apply(sample(11, "b", 18, 0.6), 95_000);
+        assert_eq!(kinds(&d), [AlertKind::Stall, AlertKind::Resumed]);
+        assert_eq!(d.finality.sparkline(), [75, 70, 65, 60, 80]);
+    }
+
+    #[test]
+    fn test_finalized_history_changes_are_reorgs() {
+        let mut d = dashboard();
+        d.apply(sample(10, "a", 12, 0.5), 0);
+        d.apply(sample(10, "b", 12, 0.5), 1_000);
+        d.apply(sample(8, "c", 12, 0.5), 2_000);
+        assert_eq!(kinds(&d), [AlertKind::Reorg, AlertKind::Reorg]);
+    }
+
+    #[test]
+    fn test_reorg_tracker() {
+        let chain = |hashes: &[(i64, &str)]| -> Vec<(i64, String)> {
+            hashes.iter().map(|(h, s)| (*h, s.to_string())).collect()
+        };
+        let mut tracker = ReorgTracker::default();
+        assert_eq!(
+            tracker.observe(&chain(&[(1, "a"), (2, "b"), (3, "c")])),
+            None
+        );
+        // Growing the chain is not a reorg
+        assert_eq!(
+            tracker.observe(&chain(&[(2, "b"), (3, "c"), (4, "d")])),
+            None
+        );
+        let reorg = tracker
+            .observe(&chain(&[(2, "b"), (3, "x"), (4, "y"), (5, "z")]))
+            .unwrap();
+        assert_eq!((reorg.height, reorg.depth), (3, 2));
+        assert_eq!(
+            (reorg.old_hash.as_str(), reorg.new_hash.as_str()),
+            ("c", "x")
+        );
+    }
+
+    #[test]
+    fn test_blocks_and_equivocations() {
+        let mut d = dashboard();
+        for n in 0..(MAX_BLOCK_ROWS as i64 + 5) {
+            let row = BlockRow {
+                height: Some(n),
+                hash: format!("{:08x}", n),
+                creator: None,
+                received_at: n,
+            };
+            d.apply(DashboardUpdate::Block(row.clone()), n);
+            d.apply(DashboardUpdate::Block(row), n);
+        }
+        assert_eq!(d.blocks.rows.len(), MAX_BLOCK_ROWS);
+        assert_eq!(d.blocks.rows[0].height, Some(MAX_BLOCK_ROWS as i64 + 4));
+
+        let meta = |hash: &str| BlockMeta {
+            block_hash: hash.to_string(),
+            block_number: 7,
+            creator: "04abcdef0123".to_string(),
+            seq_num: 3,
+        };
+        d.apply(
+            DashboardUpdate::Equivocation(Equivocation {
+                creator: "04abcdef0123".to_string(),
+                seq_num: 3,
+                blocks: vec![meta("a"), meta("b")],
+            }),
+            0,
+        );
+        assert_eq!(kinds(&d), [AlertKind::Equivocation]);
+        assert!(d.alerts.alerts[0].message.contains("04abcdef"));
+    }
+
+    #[test]
+    fn test_keys_switch_focus_pause_and_quit() {
+        let mut d = dashboard();
+        d.handle_key(KeyCode::Tab);
+        assert_eq!(d.focus, Pane::Finality);
+        d.handle_key(KeyCode::BackTab);
+        d.handle_key(KeyCode::BackTab);
+        assert_eq!(d.focus, Pane::Alerts);
+        d.handle_key(KeyCode::Char('3'));
+        assert_eq!(d.focus, Pane::Blocks);
+        d.handle_key(KeyCode::Down);
+        assert_eq!(d.blocks.scroll, 0);
+
+        d.handle_key(KeyCode::Char('p'));
+        assert!(d.paused);
+        d.handle_key(KeyCode::Char(' '));
+        assert!(!d.paused);
+        assert!(d.running);
+        d.handle_key(KeyCode::Char('q'));
+        assert!(!d.running);
+    }
+}
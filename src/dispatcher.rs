@@ -1,130 +1,420 @@
 use crate::args::*;
 use crate::commands::*;
-use crate::error::{NodeCliError, Result};
+use crate::error::{ApiError, NodeCliError, Result};
+use crate::key_guard::{self, SigningTarget};
+use crate::timings::{self, SampleOutcome};
 use crate::utils::print_error;
+use std::path::Path;
+use std::time::Instant;
+
+/// Outcome of a command with no structured result
+fn done(_: ()) -> CommandOutcome {
+    CommandOutcome::Done
+}
+
+/// What the insecure key guard needs from a command that signs deploys
+fn signing_target<'a>(
+    private_key: &'a str,
+    node: &'a NodeConnArgs,
+    guard: &KeyGuardArgs,
+    topology: Option<&'a Path>,
+) -> SigningTarget<'a> {
+    SigningTarget {
+        private_key,
+        host: &node.host,
+        http_port: node.http_port,
+        allow_insecure_key: guard.allow_insecure_key,
+        topology,
+    }
+}
+
+/// Outcome of a command that runs until the user stops it
+fn interactive(_: ()) -> CommandOutcome {
+    CommandOutcome::Interactive
+}
 
 /// Central command dispatcher that routes and executes all CLI commands
 pub struct Dispatcher;
 
 impl Dispatcher {
-    /// Dispatch a command to its appropriate handler
-    pub async fn dispatch(cli: &Cli) -> Result<()> {
-        let result = match &cli.command {
+    /// Dispatch a command to its appropriate handler, returning what it produced
+    ///
+    /// Errors have already been printed when this returns.
+    pub async fn dispatch(cli: &Cli) -> Result<CommandOutcome> {
+        if let Some(dir) = &cli.config_dir {
+            crate::utils::set_config_dir(dir.clone());
+        }
+        crate::utils::set_quiet(cli.quiet);
+        crate::truncation::configure(crate::truncation::Truncation::from_flags(
+            cli.truncate,
+            cli.full,
+        ));
+        crate::capabilities::set_assume_capabilities(cli.assume_capabilities);
+        crate::query_cache::configure(!cli.no_query_cache, cli.query_cache_size);
+        let timed = Self::records_timings(&cli.command) && timings::enabled();
+        if timed {
+            timings::begin();
+            match &cli.command {
+                Commands::Status(a) | Commands::LastFinalizedBlock(a) => {
+                    timings::observe_node(&a.host, a.port)
+                }
+                Commands::Bonds(a) => timings::observe_node(&a.http.host, a.http.port),
+                _ => {}
+            }
+        }
+        let started = Instant::now();
+
+        // Checked before the command runs, so a refused key never reaches the node
+        let result = match Self::guard_signing_key(&cli.command).await {
+            Ok(()) => Self::run(&cli.command, cli.yes).await,
+            Err(e) => Err(e),
+        };
+
+        if timed {
+            let outcome = if result.is_ok() {
+                SampleOutcome::Ok
+            } else {
+                SampleOutcome::Failed
+            };
+            let command = Self::get_command_name(cli);
+            if let Some(sample) = timings::finish(command, started.elapsed(), outcome) {
+                timings::record(&sample);
+            }
+        }
+
+        // Handle errors with better formatting, or as JSON for --output json
+        if let Err(e) = &result {
+            if Self::json_output(&cli.command) {
+                eprintln!("{}", Self::error_json(e));
+            } else {
+                Self::handle_error(e);
+            }
+        }
+        result
+    }
+
+    /// Run a command's handler
+    async fn run(command: &Commands, assume_yes: bool) -> Result<CommandOutcome> {
+        match command {
             Commands::Deploy(args) => deploy_command(args).await.map_err(NodeCliError::from),
-            Commands::Propose(args) => propose_command(args).await.map_err(NodeCliError::from),
+            Commands::Propose(args) => propose_command(args)
+                .await
+                .map(done)
+                .map_err(NodeCliError::from),
             Commands::DeployAndWait(args) => deploy_and_wait_command(args)
                 .await
                 .map_err(NodeCliError::from),
-            Commands::GetData(args) => get_data_command(args).await.map_err(NodeCliError::from),
+            Commands::WaitForDeploy(args) => wait_for_deploy_command(args)
+                .await
+                .map_err(NodeCliError::from),
+            Commands::GetData(args) => get_data_command(args).await.map(done),
+            Commands::DataAtName(args) => data_at_name_command(args).await.map(done),
             Commands::IsFinalized(args) => {
                 is_finalized_command(args).await.map_err(NodeCliError::from)
             }
             Commands::ExploratoryDeploy(args) => exploratory_deploy_command(args)
                 .await
+                .map(done)
                 .map_err(NodeCliError::from),
             Commands::EstimateCost(args) => estimate_cost_command(args)
                 .await
+                .map(done)
+                .map_err(NodeCliError::from),
+            Commands::Analyze(args) => analyze_command(args).map(done),
+            Commands::GeneratePublicKey(args) => generate_public_key_command(args)
+                .map(done)
+                .map_err(NodeCliError::from),
+            Commands::GenerateKeyPair(args) => generate_key_pair_command(args)
+                .map(done)
+                .map_err(NodeCliError::from),
+            Commands::GenerateVaultAddress(args) => generate_vault_address_command(args)
+                .map(done)
                 .map_err(NodeCliError::from),
-            Commands::GeneratePublicKey(args) => {
-                generate_public_key_command(args).map_err(NodeCliError::from)
-            }
-            Commands::GenerateKeyPair(args) => {
-                generate_key_pair_command(args).map_err(NodeCliError::from)
-            }
-            Commands::GenerateVaultAddress(args) => {
-                generate_vault_address_command(args).map_err(NodeCliError::from)
-            }
             Commands::Status(args) => status_command(args).await.map_err(NodeCliError::from),
             Commands::Blocks(args) => blocks_command(args).await.map_err(NodeCliError::from),
             Commands::Bonds(args) => bonds_command(args).await.map_err(NodeCliError::from),
             Commands::ActiveValidators(args) => active_validators_command(args)
                 .await
+                .map(done)
                 .map_err(NodeCliError::from),
             Commands::WalletBalance(args) => wallet_balance_command(args)
                 .await
                 .map_err(NodeCliError::from),
-            Commands::BondStatus(args) => {
-                bond_status_command(args).await.map_err(NodeCliError::from)
-            }
-            Commands::Metrics(args) => metrics_command(args).await.map_err(NodeCliError::from),
-            Commands::BondValidator(args) => bond_validator_command(args)
+            Commands::BondStatus(args) => bond_status_command(args)
+                .await
+                .map(done)
+                .map_err(NodeCliError::from),
+            Commands::Metrics(args) => metrics_command(args)
+                .await
+                .map(done)
+                .map_err(NodeCliError::from),
+            Commands::BondValidator(args) => bond_validator_command(args, assume_yes)
                 .await
+                .map(done)
                 .map_err(NodeCliError::from),
+            Commands::AddStake(args) => add_stake_command(args, assume_yes).await.map(done),
+            Commands::UnbondValidator(args) => {
+                unbond_validator_command(args, assume_yes).await.map(done)
+            }
             Commands::NetworkHealth(args) => network_health_command(args)
                 .await
+                .map(done)
                 .map_err(NodeCliError::from),
             Commands::LastFinalizedBlock(args) => last_finalized_block_command(args)
                 .await
+                .map(done)
                 .map_err(NodeCliError::from),
             Commands::ShowMainChain(args) => show_main_chain_command(args)
                 .await
+                .map(done)
+                .map_err(NodeCliError::from),
+            Commands::Transfer(args) => transfer_command(args, assume_yes)
+                .await
+                .map_err(NodeCliError::from),
+            Commands::BatchTransfer(args) => {
+                batch_transfer_command(args, assume_yes).await.map(done)
+            }
+            Commands::LoadTest(args) => load_test_command(args)
+                .await
+                .map(done)
+                .map_err(NodeCliError::from),
+            Commands::CreateTestWallets(args) => create_test_wallets_command(args).await.map(done),
+            Commands::LatencyProbe(args) => latency_probe_command(args).await.map(done),
+            Commands::GetDeploy(args) => get_deploy_command(args)
+                .await
+                .map(done)
+                .map_err(NodeCliError::from),
+            Commands::BumpDeploy(args) => bump_deploy_command(args).await.map(done),
+            Commands::EpochInfo(args) => epoch_info_command(args)
+                .await
+                .map(done)
                 .map_err(NodeCliError::from),
-            Commands::Transfer(args) => transfer_command(args).await.map_err(NodeCliError::from),
-            Commands::LoadTest(args) => load_test_command(args).await.map_err(NodeCliError::from),
-            Commands::GetDeploy(args) => get_deploy_command(args).await.map_err(NodeCliError::from),
-            Commands::EpochInfo(args) => epoch_info_command(args).await.map_err(NodeCliError::from),
             Commands::ValidatorStatus(args) => validator_status_command(args)
                 .await
+                .map(done)
                 .map_err(NodeCliError::from),
             Commands::EpochRewards(args) => epoch_rewards_command(args)
                 .await
+                .map(done)
                 .map_err(NodeCliError::from),
             Commands::NetworkConsensus(args) => network_consensus_command(args)
                 .await
+                .map(done)
+                .map_err(NodeCliError::from),
+            Commands::PosQuery(args) => pos_query_command(args)
+                .await
+                .map(done)
                 .map_err(NodeCliError::from),
             Commands::GetBlocksByHeight(args) => get_blocks_by_height_command(args)
                 .await
+                .map(done)
                 .map_err(NodeCliError::from),
-            Commands::GetNodeId(args) => get_node_id_command(args).map_err(NodeCliError::from),
-            Commands::WatchEvents(args) => {
-                watch_events_command(args).await.map_err(NodeCliError::from)
-            }
-            Commands::Dag(args) => run_dag(args).await,
+            Commands::GetNodeId(args) => get_node_id_command(args)
+                .map(done)
+                .map_err(NodeCliError::from),
+            Commands::WatchEvents(args) => watch_events_command(args)
+                .await
+                .map(interactive)
+                .map_err(NodeCliError::from),
+            Commands::WatchWallet(args) => watch_wallet_command(args).await.map(interactive),
+            Commands::FunderAudit(args) => funder_audit_command(args).await.map(interactive),
+            Commands::Config(args) => config_command(args).map(done),
+            Commands::Dag(args) => run_dag(args).await.map(interactive),
+            Commands::Dashboard(args) => dashboard_command(args).await.map(interactive),
             Commands::BlockTransfers(args) => block_transfers_command(args)
                 .await
+                .map(done)
                 .map_err(NodeCliError::from),
-        };
-
-        // Handle errors with better formatting
-        if let Err(e) = result {
-            Self::handle_error(&e);
-            return Err(e);
+            Commands::History(args) => history_command(args).await.map(done),
+            Commands::Registry(args) => registry_command(args).await.map(done),
+            Commands::CompareState(args) => compare_state_command(args).await.map(done),
+            Commands::ExportChain(args) => export_chain_command(args).await.map(done),
+            Commands::ChainStats(args) => chain_stats_command(args).await.map(done),
+            Commands::EquivocationCheck(args) => equivocation_check_command(args).await.map(done),
+            Commands::ValidatorChain(args) => validator_chain_command(args).await.map(done),
+            Commands::FollowBlock(args) => follow_block_command(args).await.map(done),
+            Commands::Stats(args) => stats_command(args).map(done),
+            Commands::WebhookTest(args) => webhook_test_command(args).await.map(done),
         }
-
-        Ok(())
     }
 
-    /// Handle errors with appropriate formatting and user-friendly messages
-    fn handle_error(error: &NodeCliError) {
-        match error {
-            NodeCliError::Network(net_err) => {
-                print_error(&format!("Network issue: {}", net_err));
-                eprintln!(" Suggestion: Check your internet connection and node availability");
+    /// Refuse well-known dev keys against remote nodes for commands that sign deploys
+    async fn guard_signing_key(command: &Commands) -> Result<()> {
+        let target = match command {
+            Commands::Deploy(a) => {
+                signing_target(&a.signing.private_key, &a.node, &a.key_guard, None)
             }
-            NodeCliError::Crypto(crypto_err) => {
-                print_error(&format!("Cryptographic issue: {}", crypto_err));
-                eprintln!(" Suggestion: Verify your private/public key format and validity");
+            Commands::DeployAndWait(a) => {
+                signing_target(&a.signing.private_key, &a.node, &a.key_guard, None)
             }
-            NodeCliError::File(file_err) => {
-                print_error(&format!("File operation failed: {}", file_err));
-                eprintln!(" Suggestion: Check file permissions and paths");
+            Commands::Transfer(a) => {
+                signing_target(&a.signing.private_key, &a.node, &a.key_guard, None)
             }
-            NodeCliError::Api(api_err) => {
-                print_error(&format!("API communication failed: {}", api_err));
-                eprintln!(
-                    " Suggestion: Verify the node is running and API endpoints are accessible"
-                );
+            Commands::BatchTransfer(a) => {
+                signing_target(&a.signing.private_key, &a.node, &a.key_guard, None)
             }
-            NodeCliError::Config(config_err) => {
-                print_error(&format!("Configuration issue: {}", config_err));
-                eprintln!(" Suggestion: Check your command arguments and configuration");
+            Commands::BumpDeploy(a) => {
+                signing_target(&a.signing.private_key, &a.node, &a.key_guard, None)
             }
-            NodeCliError::General(msg) => {
-                print_error(msg);
+            Commands::Registry(RegistryArgs {
+                action: RegistryAction::InsertSigned(a),
+            }) if !a.dry_run => signing_target(&a.signing.private_key, &a.node, &a.key_guard, None),
+            Commands::LoadTest(a) => {
+                signing_target(&a.signing.private_key, &a.node, &a.key_guard, None)
             }
+            Commands::CreateTestWallets(a) => signing_target(
+                &a.signing.private_key,
+                &a.node,
+                &a.key_guard,
+                a.topology.as_deref(),
+            ),
+            Commands::LatencyProbe(a) if !a.read_only => {
+                signing_target(&a.signing.private_key, &a.node, &a.key_guard, None)
+            }
+            Commands::BondValidator(a) => signing_target(
+                &a.private_key,
+                &a.node,
+                &a.key_guard,
+                a.pos.topology.as_deref(),
+            ),
+            Commands::AddStake(a) => signing_target(
+                &a.private_key,
+                &a.node,
+                &a.key_guard,
+                a.pos.topology.as_deref(),
+            ),
+            Commands::UnbondValidator(a) => signing_target(
+                &a.private_key,
+                &a.node,
+                &a.key_guard,
+                a.pos.topology.as_deref(),
+            ),
+            _ => return Ok(()),
+        };
+        key_guard::check(&target).await
+    }
+
+    /// [`Self::dispatch`], with the outcome as JSON for embedders
+    pub async fn dispatch_json(cli: &Cli) -> Result<serde_json::Value> {
+        let outcome = Self::dispatch(cli).await?;
+        Ok(serde_json::to_value(outcome)?)
+    }
+
+    /// Process exit code for the result of [`Self::dispatch`]
+    pub fn exit_code(result: &Result<CommandOutcome>) -> u8 {
+        match result {
+            Ok(_) => 0,
+            Err(e) => e.exit_code(),
         }
     }
 
+    /// Handle errors with appropriate formatting and user-friendly messages
+    ///
+    /// The failing node call, when known, is printed on its own line below the error.
+    fn handle_error(error: &NodeCliError) {
+        let (message, suggestion) = match error.root() {
+            NodeCliError::Network(net_err) => (
+                format!("Network issue: {}", net_err),
+                Some("Check your internet connection and node availability"),
+            ),
+            NodeCliError::Crypto(crypto_err) => (
+                format!("Cryptographic issue: {}", crypto_err),
+                Some("Verify your private/public key format and validity"),
+            ),
+            NodeCliError::File(file_err) => (
+                format!("File operation failed: {}", file_err),
+                Some("Check file permissions and paths"),
+            ),
+            NodeCliError::Api(
+                final_state @ (ApiError::DeployExpired { .. }
+                | ApiError::BlockOrphaned { .. }
+                | ApiError::NodeInMaintenance { .. }
+                | ApiError::NotRegistered { .. }
+                | ApiError::RegistryVersionMismatch { .. }),
+            ) => (final_state.to_string(), None),
+            NodeCliError::Api(api_err) => (
+                format!("API communication failed: {}", api_err),
+                Some("Verify the node is running and API endpoints are accessible"),
+            ),
+            NodeCliError::Config(config_err) => (
+                format!("Configuration issue: {}", config_err),
+                Some("Check your command arguments and configuration"),
+            ),
+            NodeCliError::General(msg) => (msg.clone(), None),
+            NodeCliError::Call { .. } => unreachable!("root() strips the call context"),
+        };
+        print_error(&message);
+        if let Some(context) = error.context() {
+            eprintln!(" Context: {}", context);
+        }
+        if let Some(suggestion) = suggestion {
+            eprintln!(" Suggestion: {}", suggestion);
+        }
+    }
+
+    /// Whether the command was run with `--output json` (or the hidden `--json`)
+    fn json_output(command: &Commands) -> bool {
+        match command {
+            Commands::Status(a) | Commands::Metrics(a) | Commands::LastFinalizedBlock(a) => {
+                a.output.is_json()
+            }
+            Commands::Bonds(a) | Commands::ActiveValidators(a) => a.http.output.is_json(),
+            Commands::WalletBalance(a) => a.output.is_json(),
+            Commands::NetworkHealth(a) => a.output.is_json(),
+            Commands::Transfer(a) => a.output.is_json(),
+            Commands::BondStatus(a) => a.output.is_json(),
+            Commands::Analyze(a) => a.output.is_json(),
+            Commands::FunderAudit(a) => a.output.is_json(),
+            Commands::ChainStats(a) => a.output.is_json(),
+            Commands::ValidatorChain(a) => a.output.is_json(),
+            _ => false,
+        }
+    }
+
+    /// An error as the single JSON object written to stderr with `--output json`
+    fn error_json(error: &NodeCliError) -> serde_json::Value {
+        let mut report = serde_json::json!({
+            "error": error.root().to_string(),
+            "exit_code": error.exit_code(),
+        });
+        if let Some(context) = error.context() {
+            report["operation"] = context.operation.clone().into();
+            report["endpoint"] = context.endpoint.clone().into();
+        }
+        report
+    }
+
+    /// Deploy-family and node query commands, whose timings are recorded when enabled
+    ///
+    /// Commands going through `F1r3flyApi` report their node when the client is
+    /// created; the HTTP-only ones are reported from their arguments in `dispatch`.
+    fn records_timings(command: &Commands) -> bool {
+        matches!(
+            command,
+            Commands::Deploy(_)
+                | Commands::Propose(_)
+                | Commands::DeployAndWait(_)
+                | Commands::WaitForDeploy(_)
+                | Commands::Transfer(_)
+                | Commands::BatchTransfer(_)
+                | Commands::BondValidator(_)
+                | Commands::AddStake(_)
+                | Commands::UnbondValidator(_)
+                | Commands::GetData(_)
+                | Commands::IsFinalized(_)
+                | Commands::ExploratoryDeploy(_)
+                | Commands::EstimateCost(_)
+                | Commands::GetDeploy(_)
+                | Commands::BumpDeploy(_)
+                | Commands::WalletBalance(_)
+                | Commands::ShowMainChain(_)
+                | Commands::GetBlocksByHeight(_)
+                | Commands::Status(_)
+                | Commands::Bonds(_)
+                | Commands::LastFinalizedBlock(_)
+        )
+    }
+
     /// Get the command name for logging purposes
     pub fn get_command_name(cli: &Cli) -> &'static str {
         match &cli.command {
@@ -132,9 +422,11 @@ impl Dispatcher {
             Commands::Propose(_) => "propose",
 
             Commands::DeployAndWait(_) => "deploy-and-wait",
+            Commands::WaitForDeploy(_) => "wait-for-deploy",
             Commands::IsFinalized(_) => "is-finalized",
             Commands::ExploratoryDeploy(_) => "exploratory-deploy",
             Commands::EstimateCost(_) => "estimate-cost",
+            Commands::Analyze(_) => "analyze",
             Commands::GeneratePublicKey(_) => "generate-public-key",
             Commands::GenerateKeyPair(_) => "generate-key-pair",
             Commands::GenerateVaultAddress(_) => "generate-vault-address",
@@ -146,23 +438,231 @@ impl Dispatcher {
             Commands::BondStatus(_) => "bond-status",
             Commands::Metrics(_) => "metrics",
             Commands::BondValidator(_) => "bond-validator",
+            Commands::AddStake(_) => "add-stake",
+            Commands::UnbondValidator(_) => "unbond-validator",
             Commands::NetworkHealth(_) => "network-health",
             Commands::LastFinalizedBlock(_) => "last-finalized-block",
             Commands::ShowMainChain(_) => "show-main-chain",
             Commands::Transfer(_) => "transfer",
+            Commands::BatchTransfer(_) => "batch-transfer",
             Commands::LoadTest(_) => "load-test",
+            Commands::CreateTestWallets(_) => "create-test-wallets",
+            Commands::LatencyProbe(_) => "latency-probe",
             Commands::GetDeploy(_) => "get-deploy",
+            Commands::BumpDeploy(_) => "bump-deploy",
             Commands::EpochInfo(_) => "epoch-info",
             Commands::ValidatorStatus(_) => "validator-status",
             Commands::EpochRewards(_) => "epoch-rewards",
             Commands::NetworkConsensus(_) => "network-consensus",
+            Commands::PosQuery(_) => "pos-query",
             Commands::GetBlocksByHeight(_) => "get-blocks-by-height",
             Commands::GetNodeId(_) => "get-node-id",
             Commands::WatchEvents(_) => "watch-events",
+            Commands::WatchWallet(_) => "watch-wallet",
+            Commands::FunderAudit(_) => "funder-audit",
+            Commands::Config(_) => "config",
             Commands::Dag(_) => "dag",
+            Commands::Dashboard(_) => "dashboard",
             Commands::BlockTransfers(_) => "block-transfers",
+            Commands::History(_) => "history",
+            Commands::Registry(_) => "registry",
+            Commands::CompareState(_) => "compare-state",
+            Commands::ExportChain(_) => "export-chain",
+            Commands::ChainStats(_) => "chain-stats",
+            Commands::EquivocationCheck(_) => "equivocation-check",
+            Commands::ValidatorChain(_) => "validator-chain",
+            Commands::FollowBlock(_) => "follow-block",
+            Commands::Stats(_) => "stats",
+            Commands::WebhookTest(_) => "webhook-test",
 
             Commands::GetData(_) => "get-data",
+            Commands::DataAtName(_) => "data-at-name",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::spawn_http_node;
+    use clap::Parser;
+
+    const VALIDATOR: &str = "04ffc016579a68050d655d55df4e09f046";
+
+    async fn mock_node() -> u16 {
+        spawn_http_node(|line, _| {
+            let body = if line.contains("/api/status") {
+                serde_json::json!({
+                    "version": {"api": "1", "node": "F1r3fly 0.1"},
+                    "address": "rnode://abc@localhost",
+                    "networkId": "testnet",
+                    "shardId": "root",
+                    "peers": 2,
+                    "nodes": 3,
+                    "minPhloPrice": 1,
+                })
+            } else if line.contains("/api/blocks/") {
+                serde_json::json!([{"blockInfo": {"blockHash": "a47bdb40", "blockNumber": 128}}])
+            } else if line.contains("/api/explore-deploy") {
+                serde_json::json!({"block": {"bonds": [{"validator": VALIDATOR, "stake": 1000}]}})
+            } else {
+                return ("404 Not Found", String::new());
+            };
+            ("200 OK", body.to_string())
+        })
+        .await
+    }
+
+    async fn dispatch(args: &[&str]) -> Result<CommandOutcome> {
+        let cli = Cli::try_parse_from(std::iter::once("node_cli").chain(args.iter().copied()));
+        Dispatcher::dispatch(&cli.unwrap()).await
+    }
+
+    #[tokio::test]
+    async fn test_structured_outcomes() {
+        let port = mock_node().await.to_string();
+
+        match dispatch(&["status", "-p", &port]).await.unwrap() {
+            CommandOutcome::NodeStatus(status) => {
+                assert_eq!(status.network_id, "testnet");
+                assert_eq!(status.peers, 2);
+            }
+            other => panic!("unexpected outcome {:?}", other),
         }
+        match dispatch(&["bonds", "-p", &port]).await.unwrap() {
+            CommandOutcome::Bonds { bonds } => assert_eq!(
+                bonds,
+                [Bond {
+                    validator: VALIDATOR.to_string(),
+                    stake: 1000,
+                    address: None,
+                    balance: None,
+                }]
+            ),
+            other => panic!("unexpected outcome {:?}", other),
+        }
+        match dispatch(&["blocks", "-p", &port, "-n", "1"]).await.unwrap() {
+            CommandOutcome::Blocks { blocks } => {
+                assert_eq!(blocks[0]["blockInfo"]["blockNumber"], 128)
+            }
+            other => panic!("unexpected outcome {:?}", other),
+        }
+        assert!(matches!(
+            dispatch(&["generate-key-pair"]).await.unwrap(),
+            CommandOutcome::Done
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_json_is_tagged() {
+        let port = mock_node().await.to_string();
+        let argv = ["node_cli", "status", "-p", &port];
+        let json = Dispatcher::dispatch_json(&Cli::try_parse_from(argv).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(json["outcome"], "node_status");
+        assert_eq!(json["networkId"], "testnet");
+
+        // A failed command is an error, not an outcome
+        let argv = ["node_cli", "status", "-p", "1"];
+        assert!(
+            Dispatcher::dispatch_json(&Cli::try_parse_from(argv).unwrap())
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_json_output_errors_are_errors() {
+        let port = spawn_http_node(|_, _| ("500 Internal Server Error", "boom".to_string()))
+            .await
+            .to_string();
+
+        // Text output prints the failure and carries on, as before
+        assert!(matches!(
+            dispatch(&["status", "-p", &port]).await.unwrap(),
+            CommandOutcome::Done
+        ));
+
+        for argv in [
+            ["status", "-p", &port, "--output", "json"],
+            ["bonds", "-p", &port, "--output", "json"],
+            ["last-finalized-block", "-p", &port, "--output", "json"],
+        ] {
+            let err = dispatch(&argv).await.unwrap_err();
+            let report = Dispatcher::error_json(&err);
+            assert!(
+                report["error"].as_str().unwrap().contains("boom"),
+                "{}",
+                report
+            );
+            assert_eq!(report["exit_code"], 1);
+            assert!(report["endpoint"]
+                .as_str()
+                .unwrap()
+                .starts_with(&format!("http://localhost:{}/", port)));
+        }
+
+        let cli = Cli::try_parse_from(["node_cli", "bonds", "--output", "json"]).unwrap();
+        assert!(Dispatcher::json_output(&cli.command));
+        let cli = Cli::try_parse_from(["node_cli", "wallet-balance", "-a", "1111"]).unwrap();
+        assert!(!Dispatcher::json_output(&cli.command));
+        let cli = Cli::try_parse_from(["node_cli", "transfer", "-t", "1111", "-a", "1", "--json"])
+            .unwrap();
+        assert!(Dispatcher::json_output(&cli.command));
+    }
+
+    #[tokio::test]
+    async fn test_insecure_key_refused_before_command_runs() {
+        let argv = [
+            "node_cli",
+            "deploy",
+            "-f",
+            "missing.rho",
+            "-H",
+            "node1.example.invalid",
+        ];
+        let err = Dispatcher::dispatch(&Cli::try_parse_from(argv).unwrap())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Refusing"), "{}", err);
+
+        // With the override the command itself runs and fails on the missing file
+        let argv = [
+            "node_cli",
+            "deploy",
+            "-f",
+            "missing.rho",
+            "-H",
+            "node1.example.invalid",
+            "--allow-insecure-key",
+        ];
+        let err = Dispatcher::dispatch(&Cli::try_parse_from(argv).unwrap())
+            .await
+            .unwrap_err();
+        assert!(!err.to_string().contains("Refusing"), "{}", err);
+    }
+
+    #[test]
+    fn test_exit_codes() {
+        assert_eq!(Dispatcher::exit_code(&Ok(CommandOutcome::Done)), 0);
+        assert_eq!(Dispatcher::exit_code(&Ok(CommandOutcome::Interactive)), 0);
+        let failed = Err(NodeCliError::General("boom".to_string()));
+        assert_eq!(Dispatcher::exit_code(&failed), 1);
+        let expired = Err(NodeCliError::deploy_expired(100, 150));
+        assert_eq!(
+            Dispatcher::exit_code(&expired),
+            crate::error::EXIT_DEPLOY_EXPIRED
+        );
+        let orphaned = Err(NodeCliError::block_orphaned("abc", 10, 12));
+        assert_eq!(
+            Dispatcher::exit_code(&orphaned),
+            crate::error::EXIT_BLOCK_ORPHANED
+        );
+        let maintenance = Err(NodeCliError::node_in_maintenance("upgrade"));
+        assert_eq!(
+            Dispatcher::exit_code(&maintenance),
+            crate::error::EXIT_NODE_MAINTENANCE
+        );
     }
 }
@@ -0,0 +1,536 @@
+//! Effective settings and where each came from (`--print-config`, `config effective`)
+//!
+//! Clap records, as it parses, whether each flag was given on the command line
+//! or left at its default. A port default chosen by `--node-role` is told apart
+//! from the plain default by comparing the two. Settings that fall back to the
+//! environment or the config directory when their flag is absent are resolved
+//! here in the same order the commands resolve them.
+//!
+//! Private keys are never printed: a key is shown as the name of the
+//! well-known dev key it is, or as `<redacted>`.
+
+use crate::args::Cli;
+use crate::history::HISTORY_ENV;
+use crate::identities::{DEFAULT_FILE_NAMES, IDENTITIES_ENV};
+use crate::notify::NOTIFY_COMMAND_ENV;
+use crate::topology::DEFAULT_TOPOLOGY_FILE;
+use crate::transfer_guard::{BALANCE_PERCENT_KEY, DEFAULT_BALANCE_PERCENT, MAX_TRANSFER_KEY};
+use crate::utils::{config_value_in, CONFIG_DIR_ENV, CONFIG_FILE_NAME};
+use crate::webhook::WEBHOOK_URL_KEY;
+use clap::parser::ValueSource;
+use clap::{Arg, ArgMatches, CommandFactory};
+use std::fmt;
+use std::path::PathBuf;
+
+/// Global flags that only control printing the configuration itself
+const PRINT_FLAGS: [&str; 2] = ["print_config", "print_config_only"];
+
+/// Where a setting's value came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// Given on the command line
+    Flag,
+    /// Read from an environment variable
+    Env(&'static str),
+    /// Port default for the `--node-role` node
+    NodeRole(String),
+    /// Read from `config.toml` in the config directory
+    ConfigFile,
+    Default,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Flag => write!(f, "flag"),
+            Source::Env(var) => write!(f, "env {}", var),
+            Source::NodeRole(role) => write!(f, "node-role {}", role),
+            Source::ConfigFile => write!(f, "{}", CONFIG_FILE_NAME),
+            Source::Default => write!(f, "default"),
+        }
+    }
+}
+
+/// One resolved setting
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Setting {
+    /// Flag name, such as `--http-port`
+    pub name: String,
+    pub value: String,
+    pub source: Source,
+}
+
+/// The settings a command runs with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EffectiveConfig {
+    pub command: String,
+    pub settings: Vec<Setting>,
+}
+
+impl EffectiveConfig {
+    /// Resolve against the process environment
+    pub fn from_matches(matches: &ArgMatches) -> Self {
+        Self::resolve(matches, &|var| std::env::var(var).ok())
+    }
+
+    /// Resolve with `env` standing in for the environment
+    pub fn resolve(matches: &ArgMatches, env: &dyn Fn(&str) -> Option<String>) -> Self {
+        let env = |var: &str| env(var).filter(|value| !value.is_empty());
+        let cli = Cli::command();
+        let mut settings = Vec::new();
+        // Global flags reach the subcommand wherever they were given
+        let globals = matches.subcommand().map_or(matches, |(_, sub)| sub);
+
+        let (config_dir, source) = config_dir(globals, &env);
+        settings.push(Setting {
+            name: "--config-dir".to_string(),
+            value: config_dir
+                .as_ref()
+                .map_or("(none)".to_string(), |dir| dir.display().to_string()),
+            source,
+        });
+        for arg in cli.get_arguments() {
+            let id = arg.get_id().as_str();
+            if id == "config_dir" || PRINT_FLAGS.contains(&id) {
+                continue;
+            }
+            if let Some(setting) = flag_setting(arg, globals) {
+                settings.push(setting);
+            }
+        }
+
+        let Some((command, sub_matches)) = matches.subcommand() else {
+            return Self {
+                command: String::new(),
+                settings,
+            };
+        };
+        let config_file = config_dir
+            .as_ref()
+            .and_then(|dir| std::fs::read_to_string(dir.join(CONFIG_FILE_NAME)).ok());
+        let fallback = Fallback {
+            env: &env,
+            config_dir: config_dir.as_ref(),
+            config_file: config_file.as_deref(),
+        };
+        if let Some(sub) = cli.find_subcommand(command) {
+            for arg in sub.get_arguments() {
+                let id = arg.get_id().as_str();
+                if arg.is_global_set() || !relevant(id) {
+                    continue;
+                }
+                if id == "history" {
+                    settings.push(history_setting(sub_matches, &env));
+                    continue;
+                }
+                match flag_setting(arg, sub_matches) {
+                    Some(setting) => settings.push(setting),
+                    None => settings.extend(fallback.setting(arg, sub_matches)),
+                }
+            }
+        }
+        Self {
+            command: command.to_string(),
+            settings,
+        }
+    }
+
+    /// Aligned `name  value  source` lines under a heading
+    pub fn lines(&self) -> Vec<String> {
+        let name_width = self.settings.iter().map(|s| s.name.len()).max();
+        let value_width = self.settings.iter().map(|s| s.value.len()).max();
+        let mut lines = vec![format!("Effective configuration for {}:", self.command)];
+        for setting in &self.settings {
+            lines.push(format!(
+                "  {:<name$}  {:<value$}  {}",
+                setting.name,
+                setting.value,
+                setting.source,
+                name = name_width.unwrap_or(0),
+                value = value_width.unwrap_or(0)
+            ));
+        }
+        lines
+    }
+
+    /// The setting for `name`, such as `--http-port`
+    pub fn get(&self, name: &str) -> Option<&Setting> {
+        self.settings.iter().find(|setting| setting.name == name)
+    }
+}
+
+/// Whether a subcommand flag shapes how the command connects, signs, waits or prints
+fn relevant(id: &str) -> bool {
+    const NAMED: [&str; 19] = [
+        "node_role",
+        "nodes",
+        "verify_on",
+        "topology",
+        "identities",
+        "private_key",
+        "key_file",
+        "keystore",
+        "allow_insecure_key",
+        "history",
+        "notify_command",
+        "webhook_url",
+        "max_transfer",
+        "balance_warn_percent",
+        "json",
+        "format",
+        "output",
+        "color",
+        "verbose",
+    ];
+    NAMED.contains(&id)
+        || id.contains("host")
+        || id.contains("port")
+        || [
+            "interval", "timeout", "wait", "delay", "expires", "max_age", "lifespan",
+        ]
+        .iter()
+        .any(|word| id.contains(word))
+}
+
+fn flag_name(arg: &Arg) -> String {
+    match arg.get_long() {
+        Some(long) => format!("--{}", long),
+        None => arg.get_id().to_string(),
+    }
+}
+
+/// A flag's value and source as clap recorded them, if it has a value
+fn flag_setting(arg: &Arg, matches: &ArgMatches) -> Option<Setting> {
+    let id = arg.get_id().as_str();
+    let raw = matches.get_raw(id)?;
+    let values: Vec<String> = raw.map(|v| v.to_string_lossy().into_owned()).collect();
+    let value = values.join(",");
+    let source = match matches.value_source(id) {
+        Some(ValueSource::CommandLine) => Source::Flag,
+        _ => {
+            let plain_default: Vec<String> = arg
+                .get_default_values()
+                .iter()
+                .map(|v| v.to_string_lossy().into_owned())
+                .collect();
+            let role = matches
+                .try_get_raw("node_role")
+                .ok()
+                .flatten()
+                .and_then(|mut role| role.next())
+                .map(|role| role.to_string_lossy().into_owned());
+            match role {
+                Some(role) if plain_default != values => Source::NodeRole(role),
+                _ => Source::Default,
+            }
+        }
+    };
+    let value = if id == "private_key" {
+        redact_key(&value)
+    } else {
+        value
+    };
+    Some(Setting {
+        name: flag_name(arg),
+        value,
+        source,
+    })
+}
+
+/// A private key as something safe to print
+fn redact_key(key: &str) -> String {
+    match crate::key_guard::insecure_key(key) {
+        Some(name) => format!("well-known {} dev key", name),
+        None => "<redacted>".to_string(),
+    }
+}
+
+/// `--config-dir`, else `FIREFLY_CONFIG_DIR`, else the XDG or home config directory
+fn config_dir(
+    matches: &ArgMatches,
+    env: &dyn Fn(&str) -> Option<String>,
+) -> (Option<PathBuf>, Source) {
+    if let Some(dir) = matches.get_one::<PathBuf>("config_dir") {
+        return (Some(dir.clone()), Source::Flag);
+    }
+    if let Some(dir) = env(CONFIG_DIR_ENV) {
+        return (Some(PathBuf::from(dir)), Source::Env(CONFIG_DIR_ENV));
+    }
+    if let Some(xdg) = env("XDG_CONFIG_HOME") {
+        return (
+            Some(PathBuf::from(xdg).join("node_cli")),
+            Source::Env("XDG_CONFIG_HOME"),
+        );
+    }
+    let home = env("HOME").map(|home| PathBuf::from(home).join(".config").join("node_cli"));
+    (home, Source::Default)
+}
+
+/// `--history` / `--no-history`, else `FIREFLY_HISTORY`
+fn history_setting(matches: &ArgMatches, env: &dyn Fn(&str) -> Option<String>) -> Setting {
+    let given = |id: &str| {
+        matches.value_source(id) == Some(ValueSource::CommandLine) && matches.get_flag(id)
+    };
+    let on_off = |on: bool| if on { "on" } else { "off" }.to_string();
+    let (value, source) = if given("no_history") {
+        (on_off(false), Source::Flag)
+    } else if given("history") {
+        (on_off(true), Source::Flag)
+    } else if let Some(value) = env(HISTORY_ENV) {
+        let on = matches!(
+            value.to_ascii_lowercase().as_str(),
+            "1" | "true" | "yes" | "on"
+        );
+        (on_off(on), Source::Env(HISTORY_ENV))
+    } else {
+        (on_off(false), Source::Default)
+    };
+    Setting {
+        name: "--history".to_string(),
+        value,
+        source,
+    }
+}
+
+/// Where flags that were not given fall back to
+struct Fallback<'a> {
+    env: &'a dyn Fn(&str) -> Option<String>,
+    config_dir: Option<&'a PathBuf>,
+    config_file: Option<&'a str>,
+}
+
+impl Fallback<'_> {
+    fn setting(&self, arg: &Arg, matches: &ArgMatches) -> Option<Setting> {
+        let id = arg.get_id().as_str();
+        let default_file = |names: &[&str]| {
+            let dir = self.config_dir?;
+            names
+                .iter()
+                .map(|name| dir.join(name))
+                .find(|path| path.is_file())
+                .map(|path| path.display().to_string())
+        };
+        let config = |key: &str| {
+            self.config_file
+                .and_then(|content| config_value_in(content, key))
+        };
+        let (value, source) = match id {
+            "identities" => match (self.env)(IDENTITIES_ENV) {
+                Some(path) => (path, Source::Env(IDENTITIES_ENV)),
+                None => (
+                    default_file(&DEFAULT_FILE_NAMES).unwrap_or_else(none),
+                    Source::Default,
+                ),
+            },
+            "topology" => (
+                default_file(&[DEFAULT_TOPOLOGY_FILE]).unwrap_or_else(none),
+                Source::Default,
+            ),
+            "notify_command" => match ((self.env)(NOTIFY_COMMAND_ENV), config("notify_command")) {
+                (Some(command), _) => (command, Source::Env(NOTIFY_COMMAND_ENV)),
+                (None, Some(command)) => (command, Source::ConfigFile),
+                (None, None) => (none(), Source::Default),
+            },
+            "webhook_url" => match config(WEBHOOK_URL_KEY) {
+                Some(url) => (url, Source::ConfigFile),
+                None => (none(), Source::Default),
+            },
+            "max_transfer" => match config(MAX_TRANSFER_KEY) {
+                Some(cap) => (cap, Source::ConfigFile),
+                None => (none(), Source::Default),
+            },
+            "balance_warn_percent" => match config(BALANCE_PERCENT_KEY) {
+                Some(percent) => (percent, Source::ConfigFile),
+                None => (DEFAULT_BALANCE_PERCENT.to_string(), Source::Default),
+            },
+            // The observer falls back to the main node's host
+            "observer_host" => (
+                matches
+                    .try_get_one::<String>("host")
+                    .ok()
+                    .flatten()
+                    .cloned()
+                    .unwrap_or_else(none),
+                Source::Default,
+            ),
+            _ => return None,
+        };
+        Some(Setting {
+            name: flag_name(arg),
+            value,
+            source,
+        })
+    }
+}
+
+fn none() -> String {
+    "(none)".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::DEV_PRIVATE_KEY;
+    use std::collections::HashMap;
+
+    fn resolve(argv: &[&str], env: &[(&str, &str)]) -> EffectiveConfig {
+        let env: HashMap<String, String> = env
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let matches = Cli::command()
+            .try_get_matches_from(std::iter::once("node_cli").chain(argv.iter().copied()))
+            .unwrap();
+        EffectiveConfig::resolve(&matches, &|var| env.get(var).cloned())
+    }
+
+    fn setting(config: &EffectiveConfig, name: &str) -> (String, String) {
+        let setting = config
+            .get(name)
+            .unwrap_or_else(|| panic!("{} not in {:?}", name, config.settings));
+        (setting.value.clone(), setting.source.to_string())
+    }
+
+    fn pair(value: &str, source: &str) -> (String, String) {
+        (value.to_string(), source.to_string())
+    }
+
+    #[test]
+    fn test_defaults_flags_and_node_role() {
+        let config = resolve(&["status"], &[]);
+        assert_eq!(config.command, "status");
+        assert_eq!(setting(&config, "--host"), pair("localhost", "default"));
+        assert_eq!(setting(&config, "--port"), pair("40453", "default"));
+
+        let config = resolve(
+            &["status", "-H", "node.example", "--node-role", "validator1"],
+            &[],
+        );
+        assert_eq!(setting(&config, "--host"), pair("node.example", "flag"));
+        assert_eq!(setting(&config, "--node-role"), pair("validator1", "flag"));
+        assert_eq!(
+            setting(&config, "--port"),
+            pair("40413", "node-role validator1")
+        );
+
+        // A flag beats the role's port
+        let config = resolve(&["status", "--node-role", "validator1", "-p", "9999"], &[]);
+        assert_eq!(setting(&config, "--port"), pair("9999", "flag"));
+
+        // The role whose port is the plain default still reads as default
+        let config = resolve(&["status", "--node-role", "observer"], &[]);
+        assert_eq!(setting(&config, "--port"), pair("40453", "default"));
+    }
+
+    #[test]
+    fn test_private_key_is_never_printed() {
+        let config = resolve(&["deploy", "-f", "x.rho"], &[]);
+        assert_eq!(
+            setting(&config, "--private-key"),
+            pair("well-known bootstrap dev key", "default")
+        );
+
+        let secret = "aa".repeat(32);
+        let config = resolve(&["deploy", "-f", "x.rho", "-k", &secret], &[]);
+        assert_eq!(
+            setting(&config, "--private-key"),
+            pair("<redacted>", "flag")
+        );
+        let printed = config.lines().join("\n");
+        assert!(!printed.contains(&secret));
+        assert!(!printed.contains(DEV_PRIVATE_KEY));
+    }
+
+    #[test]
+    fn test_config_dir_precedence() {
+        let config = resolve(&["status"], &[("HOME", "/home/u")]);
+        assert_eq!(
+            setting(&config, "--config-dir"),
+            pair("/home/u/.config/node_cli", "default")
+        );
+        let config = resolve(
+            &["status"],
+            &[("HOME", "/home/u"), ("XDG_CONFIG_HOME", "/xdg")],
+        );
+        assert_eq!(
+            setting(&config, "--config-dir"),
+            pair("/xdg/node_cli", "env XDG_CONFIG_HOME")
+        );
+        let env = [("XDG_CONFIG_HOME", "/xdg"), (CONFIG_DIR_ENV, "/env")];
+        let config = resolve(&["status"], &env);
+        assert_eq!(
+            setting(&config, "--config-dir"),
+            pair("/env", "env FIREFLY_CONFIG_DIR")
+        );
+        let config = resolve(&["status", "--config-dir", "/flag"], &env);
+        assert_eq!(setting(&config, "--config-dir"), pair("/flag", "flag"));
+        // An empty variable counts as unset
+        let config = resolve(&["status"], &[(CONFIG_DIR_ENV, "")]);
+        assert_eq!(setting(&config, "--config-dir"), pair("(none)", "default"));
+    }
+
+    #[test]
+    fn test_env_and_config_file_fallbacks() {
+        let dir = std::env::temp_dir().join(format!("node_cli_effective_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join(CONFIG_FILE_NAME),
+            "max_transfer_rev = \"500\"\nnotify_command = \"notify-send\"\nwebhook_url = \"http://hooks.local/ff\"\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("identities.csv"), "pubkey,name\n").unwrap();
+        let dir_flag = dir.display().to_string();
+        let transfer = [
+            "transfer",
+            "-t",
+            "1111abc",
+            "-a",
+            "5",
+            "--config-dir",
+            &dir_flag,
+        ];
+
+        let config = resolve(&transfer, &[]);
+        assert_eq!(
+            setting(&config, "--max-transfer"),
+            pair("500", "config.toml")
+        );
+        assert_eq!(
+            setting(&config, "--balance-warn-percent"),
+            pair("50", "default")
+        );
+        assert_eq!(setting(&config, "--history"), pair("off", "default"));
+        assert_eq!(
+            setting(&config, "--webhook-url"),
+            pair("http://hooks.local/ff", "config.toml")
+        );
+
+        let with_flags: Vec<&str> = transfer
+            .iter()
+            .copied()
+            .chain(["--max-transfer", "7", "--history"])
+            .collect();
+        let config = resolve(&with_flags, &[(HISTORY_ENV, "0")]);
+        assert_eq!(setting(&config, "--max-transfer"), pair("7", "flag"));
+        assert_eq!(setting(&config, "--history"), pair("on", "flag"));
+        let config = resolve(&transfer, &[(HISTORY_ENV, "yes")]);
+        assert_eq!(
+            setting(&config, "--history"),
+            pair("on", "env FIREFLY_HISTORY")
+        );
+
+        let status = ["status", "--config-dir", &dir_flag];
+        let config = resolve(&status, &[]);
+        assert_eq!(
+            setting(&config, "--identities"),
+            pair(&dir.join("identities.csv").display().to_string(), "default")
+        );
+        let config = resolve(&status, &[(IDENTITIES_ENV, "/ids.toml")]);
+        assert_eq!(
+            setting(&config, "--identities"),
+            pair("/ids.toml", "env FIREFLY_IDENTITIES")
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,224 @@
+//! Equivocation detection for `equivocation-check`
+//!
+//! A validator equivocates when it creates two different blocks with the same
+//! sequence number. Blocks are grouped by `(creator, seq_num)` and any group
+//! holding more than one distinct block hash is reported. [`detect`] does this
+//! for a fixed set of blocks; [`EquivocationTracker`] does it incrementally for
+//! live mode, forgetting groups that fall more than a height window below the
+//! highest block seen so memory stays bounded.
+
+use crate::f1r3fly_api::BlockHeader;
+use std::collections::{BTreeMap, HashMap};
+
+/// Heights of history live mode keeps by default
+pub const DEFAULT_WINDOW: i64 = 1000;
+
+/// What detection needs to know about a block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockMeta {
+    pub block_hash: String,
+    pub block_number: i64,
+    pub creator: String,
+    pub seq_num: i64,
+}
+
+impl From<&BlockHeader> for BlockMeta {
+    fn from(header: &BlockHeader) -> Self {
+        Self {
+            block_hash: header.block_hash.clone(),
+            block_number: header.block_number,
+            creator: header.sender.clone(),
+            seq_num: header.seq_num,
+        }
+    }
+}
+
+/// Distinct blocks one creator made with the same sequence number
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Equivocation {
+    pub creator: String,
+    pub seq_num: i64,
+    /// Ordered by height, then hash
+    pub blocks: Vec<BlockMeta>,
+}
+
+/// Add `block` to its group unless the hash is already there; returns whether it was new
+fn insert_distinct(group: &mut Vec<BlockMeta>, block: &BlockMeta) -> bool {
+    if group.iter().any(|b| b.block_hash == block.block_hash) {
+        return false;
+    }
+    group.push(block.clone());
+    group.sort_by(|a, b| (a.block_number, &a.block_hash).cmp(&(b.block_number, &b.block_hash)));
+    true
+}
+
+/// Every `(creator, seq_num)` with more than one distinct block, ordered by creator
+/// and sequence number
+///
+/// Blocks without a creator (genesis) are ignored, and a block listed twice
+/// counts once.
+pub fn detect(blocks: &[BlockMeta]) -> Vec<Equivocation> {
+    let mut groups: BTreeMap<(&str, i64), Vec<BlockMeta>> = BTreeMap::new();
+    for block in blocks.iter().filter(|b| !b.creator.is_empty()) {
+        insert_distinct(
+            groups.entry((&block.creator, block.seq_num)).or_default(),
+            block,
+        );
+    }
+    groups
+        .into_iter()
+        .filter(|(_, group)| group.len() > 1)
+        .map(|((creator, seq_num), blocks)| Equivocation {
+            creator: creator.to_string(),
+            seq_num,
+            blocks,
+        })
+        .collect()
+}
+
+/// Incremental [`detect`] over a stream of blocks
+///
+/// Groups whose newest block is more than `window` heights below the highest
+/// block seen are evicted, so a conflicting block arriving later than that is
+/// not caught.
+#[derive(Debug)]
+pub struct EquivocationTracker {
+    window: i64,
+    highest: i64,
+    groups: HashMap<(String, i64), Vec<BlockMeta>>,
+}
+
+impl EquivocationTracker {
+    pub fn new(window: i64) -> Self {
+        Self {
+            window: window.max(0),
+            highest: i64::MIN,
+            groups: HashMap::new(),
+        }
+    }
+
+    /// Number of `(creator, seq_num)` groups held
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// Record a block, returning the conflict if it is a new block for a
+    /// `(creator, seq_num)` that already had one
+    ///
+    /// Blocks already below the window are ignored.
+    pub fn observe(&mut self, block: &BlockMeta) -> Option<Equivocation> {
+        if block.creator.is_empty() || block.block_number < self.floor() {
+            return None;
+        }
+        if block.block_number > self.highest {
+            self.highest = block.block_number;
+            self.evict();
+        }
+
+        let key = (block.creator.clone(), block.seq_num);
+        let group = self.groups.entry(key).or_default();
+        if !insert_distinct(group, block) || group.len() < 2 {
+            return None;
+        }
+        Some(Equivocation {
+            creator: block.creator.clone(),
+            seq_num: block.seq_num,
+            blocks: group.clone(),
+        })
+    }
+
+    /// Lowest height still tracked
+    fn floor(&self) -> i64 {
+        self.highest.saturating_sub(self.window)
+    }
+
+    fn evict(&mut self) {
+        let floor = self.floor();
+        self.groups.retain(|_, group| {
+            group
+                .iter()
+                .map(|b| b.block_number)
+                .max()
+                .is_some_and(|newest| newest >= floor)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(hash: &str, height: i64, creator: &str, seq_num: i64) -> BlockMeta {
+        BlockMeta {
+            block_hash: hash.to_string(),
+            block_number: height,
+            creator: creator.to_string(),
+            seq_num,
+        }
+    }
+
+    #[test]
+    fn test_no_conflict() {
+        let blocks = [
+            block("a1", 1, "val_a", 1),
+            block("a2", 2, "val_a", 2),
+            block("b1", 2, "val_b", 1),
+            // The same block seen twice is not a conflict
+            block("a2", 2, "val_a", 2),
+            // Genesis has no creator
+            block("g0", 0, "", 0),
+            block("g1", 0, "", 0),
+        ];
+        assert!(detect(&blocks).is_empty());
+
+        let mut tracker = EquivocationTracker::new(DEFAULT_WINDOW);
+        assert!(blocks.iter().all(|b| tracker.observe(b).is_none()));
+        assert_eq!(tracker.len(), 3);
+    }
+
+    #[test]
+    fn test_conflict() {
+        let blocks = [
+            block("a5", 5, "val_a", 3),
+            block("b4", 4, "val_b", 2),
+            block("a4", 4, "val_a", 3),
+            block("b5", 5, "val_b", 3),
+        ];
+        assert_eq!(
+            detect(&blocks),
+            [Equivocation {
+                creator: "val_a".to_string(),
+                seq_num: 3,
+                blocks: vec![block("a4", 4, "val_a", 3), block("a5", 5, "val_a", 3)],
+            }]
+        );
+
+        let mut tracker = EquivocationTracker::new(DEFAULT_WINDOW);
+        let alerts: Vec<_> = blocks.iter().filter_map(|b| tracker.observe(b)).collect();
+        assert_eq!(alerts, detect(&blocks));
+        // Seeing either block again raises nothing new
+        assert_eq!(tracker.observe(&block("a5", 5, "val_a", 3)), None);
+    }
+
+    #[test]
+    fn test_eviction_at_window_boundary() {
+        let mut tracker = EquivocationTracker::new(10);
+        tracker.observe(&block("a1", 100, "val_a", 7));
+        tracker.observe(&block("b1", 105, "val_b", 9));
+
+        // Exactly `window` heights below the highest block is still tracked
+        tracker.observe(&block("c1", 110, "val_c", 1));
+        assert_eq!(tracker.len(), 3);
+        assert!(tracker.observe(&block("a2", 100, "val_a", 7)).is_some());
+
+        // One height further and the group is gone, along with late arrivals
+        tracker.observe(&block("c2", 111, "val_c", 2));
+        assert_eq!(tracker.len(), 3);
+        assert_eq!(tracker.observe(&block("a3", 100, "val_a", 7)), None);
+        assert!(tracker.observe(&block("b2", 106, "val_b", 9)).is_some());
+    }
+}
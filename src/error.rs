@@ -1,4 +1,5 @@
 use std::error::Error;
+use std::time::{Duration, Instant};
 
 /// Error types for the f1r3fly client library and CLI
 #[derive(Debug, thiserror::Error)]
@@ -20,6 +21,103 @@ pub enum NodeCliError {
 
     #[error("{0}")]
     General(String),
+
+    /// An error from a node call, with the operation and endpoint that failed
+    #[error("{source} [{context}]")]
+    Call {
+        context: CallContext,
+        source: Box<NodeCliError>,
+    },
+}
+
+/// Which node call failed, against which endpoint, and how long it ran
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallContext {
+    /// RPC or HTTP API operation, e.g. `is_finalized` or `explore-deploy`
+    pub operation: String,
+    /// `host:port` for gRPC, the full URL for HTTP
+    pub endpoint: String,
+    /// Time from the start of the call until it failed
+    pub elapsed: Duration,
+}
+
+impl std::fmt::Display for CallContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} at {}, after {:.2?}",
+            self.operation, self.endpoint, self.elapsed
+        )
+    }
+}
+
+/// A node call in progress, timed from when it was started
+///
+/// Failures passed through [`NodeCall::fail`] come back as
+/// [`NodeCliError::Call`], so the error names the call that produced it.
+#[derive(Debug, Clone)]
+pub struct NodeCall {
+    operation: String,
+    endpoint: String,
+    started: Instant,
+}
+
+impl NodeCall {
+    pub fn start(operation: impl Into<String>, endpoint: impl Into<String>) -> Self {
+        Self {
+            operation: operation.into(),
+            endpoint: endpoint.into(),
+            started: Instant::now(),
+        }
+    }
+
+    /// An HTTP API call, named after the path segment following `/api/`
+    ///
+    /// `http://host:40413/api/explore-deploy` is the `explore-deploy` call.
+    pub fn http(url: &str) -> Self {
+        let path = url
+            .split_once("://")
+            .map_or(url, |(_, rest)| rest.find('/').map_or("", |i| &rest[i..]));
+        let operation = path
+            .strip_prefix("/api/")
+            .and_then(|rest| rest.split(['/', '?']).next())
+            .filter(|segment| !segment.is_empty())
+            .unwrap_or(path);
+        Self::start(operation, url)
+    }
+
+    /// Context for a failure happening now
+    pub fn context(&self) -> CallContext {
+        CallContext {
+            operation: self.operation.clone(),
+            endpoint: self.endpoint.clone(),
+            elapsed: self.started.elapsed(),
+        }
+    }
+
+    /// Wrap a failure of this call with its context
+    pub fn fail(&self, err: impl Into<Box<dyn Error>>) -> NodeCliError {
+        let err: Box<dyn Error> = err.into();
+        NodeCliError::from(err).with_context(self.context())
+    }
+}
+
+/// An error's message followed by the messages of its sources
+///
+/// Transport errors put the useful part in their sources: tonic's
+/// `transport error` only says why in the chain below it.
+fn describe(err: &dyn Error) -> String {
+    let mut message = err.to_string();
+    let mut source = err.source();
+    while let Some(cause) = source {
+        let cause_message = cause.to_string();
+        if !message.contains(&cause_message) {
+            message.push_str(": ");
+            message.push_str(&cause_message);
+        }
+        source = cause.source();
+    }
+    message
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -95,6 +193,182 @@ pub enum ApiError {
 
     #[error("Service unavailable: {0}")]
     ServiceUnavailable(String),
+
+    #[error("node reports version {version} which does not support {feature}; requires >= {required} (pass --assume-capabilities to try anyway)")]
+    UnsupportedFeature {
+        version: String,
+        feature: String,
+        required: String,
+    },
+
+    #[error("Deploy rejected: {reason}")]
+    DeployRejected {
+        reason: DeployRejection,
+        raw: String,
+    },
+
+    #[error("Propose rejected: {reason}")]
+    ProposeRejected {
+        reason: ProposeRejection,
+        raw: String,
+    },
+
+    #[error(
+        "Deploy expired at block {expired_at} (tip is {tip}); it will never be included, resubmit"
+    )]
+    DeployExpired { expired_at: i64, tip: i64 },
+
+    /// The last finalized block reached a block's height without finalizing it
+    #[error("Block {block_hash} at height {height} was orphaned: the last finalized block is at height {last_finalized} and it was not finalized")]
+    BlockOrphaned {
+        block_hash: String,
+        height: i64,
+        last_finalized: i64,
+    },
+
+    /// An exploratory deploy ran but sent nothing back
+    #[error("empty result at block {block}")]
+    NoData { block: String },
+
+    /// An explore-deploy reply that is not the expected JSON
+    #[error("unexpected explore-deploy response (HTTP {status}): {snippet}")]
+    UnexpectedExploreResponse { status: u16, snippet: String },
+
+    /// The node is being upgraded and refuses requests for now
+    #[error("node is in maintenance mode ({detail}); try again once it is back")]
+    NodeInMaintenance { detail: String },
+
+    /// A deploy made it into a block but its execution failed
+    #[error("Deploy {deploy_id} errored during execution: {detail}")]
+    DeployErrored { deploy_id: String, detail: String },
+
+    /// A registry lookup found nothing at the URI
+    #[error("nothing is registered at {uri}")]
+    NotRegistered { uri: String },
+
+    /// A registry entry has another version than the one expected
+    #[error("{uri} is registered with {}, expected version {expected}", .found.map(|v| format!("version {}", v)).unwrap_or_else(|| "no version".to_string()))]
+    RegistryVersionMismatch {
+        uri: String,
+        expected: i64,
+        found: Option<i64>,
+    },
+}
+
+/// Why a node refused to accept a deploy
+///
+/// Classified from the node's rejection message so commands can print a
+/// targeted hint; the raw message is kept alongside in `ApiError::DeployRejected`.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum DeployRejection {
+    #[error("invalid deploy signature")]
+    SignatureInvalid,
+
+    #[error("phlo price below the node minimum{}", .min.map(|m| format!(" of {}", m)).unwrap_or_default())]
+    PhloPriceTooLow { min: Option<i64> },
+
+    #[error("term exceeds the maximum size{}", .max.map(|m| format!(" of {} bytes", m)).unwrap_or_default())]
+    TermTooLarge { max: Option<usize> },
+
+    #[error("deploy expired")]
+    Expired,
+
+    #[error("{0}")]
+    Unknown(String),
+}
+
+impl DeployRejection {
+    /// Classify a node rejection message
+    pub fn classify(raw: &str) -> Self {
+        let lower = raw.to_ascii_lowercase();
+        if lower.contains("signature") {
+            DeployRejection::SignatureInvalid
+        } else if lower.contains("phlo price") || lower.contains("phloprice") {
+            DeployRejection::PhloPriceTooLow {
+                min: number_after(&lower, "minimum"),
+            }
+        } else if lower.contains("term")
+            && (lower.contains("too large") || lower.contains("exceeds"))
+        {
+            DeployRejection::TermTooLarge {
+                max: number_after(&lower, "max").and_then(|m| usize::try_from(m).ok()),
+            }
+        } else if lower.contains("expired") || lower.contains("expiration") {
+            DeployRejection::Expired
+        } else {
+            DeployRejection::Unknown(raw.trim().to_string())
+        }
+    }
+
+    /// What the user can do about the rejection, if anything specific
+    pub fn hint(&self) -> Option<String> {
+        let hint = match self {
+            DeployRejection::SignatureInvalid => {
+                "Check --private-key is a valid 64-character hex secp256k1 key".to_string()
+            }
+            DeployRejection::PhloPriceTooLow { min } => format!(
+                "Deploys are signed with phlo price 1{}; use a node with a lower minimum phlo price",
+                min.map(|m| format!(" but this node requires at least {}", m))
+                    .unwrap_or_default()
+            ),
+            DeployRejection::TermTooLarge { max } => format!(
+                "Shrink the Rholang source{} or split it into several deploys",
+                max.map(|m| format!(" below {} bytes", m))
+                    .unwrap_or_default()
+            ),
+            DeployRejection::Expired => {
+                "Increase --expires-in or drop --expiration, and check the local clock".to_string()
+            }
+            DeployRejection::Unknown(_) => return None,
+        };
+        Some(hint)
+    }
+}
+
+/// Why a node refused to propose a block
+///
+/// Classified from the node's error reply like [`DeployRejection`]; the raw
+/// message is kept alongside in `ApiError::ProposeRejected`.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ProposeRejection {
+    /// The node has no validator identity, or its key is not bonded and active
+    #[error("the proposing key is not an active validator")]
+    NotActiveValidator,
+
+    #[error("{0}")]
+    Unknown(String),
+}
+
+impl ProposeRejection {
+    /// Classify a node propose error message
+    pub fn classify(raw: &str) -> Self {
+        const NOT_ACTIVE_PATTERNS: [&str; 7] = [
+            "notbonded",
+            "not bonded",
+            "not a validator",
+            "not an active validator",
+            "not in the active validator",
+            "no validator identity",
+            "read only",
+        ];
+        let lower = raw.to_ascii_lowercase().replace('-', " ");
+        if NOT_ACTIVE_PATTERNS.iter().any(|p| lower.contains(p)) {
+            ProposeRejection::NotActiveValidator
+        } else {
+            ProposeRejection::Unknown(raw.trim().to_string())
+        }
+    }
+}
+
+/// First integer that follows `marker` in `text`
+fn number_after(text: &str, marker: &str) -> Option<i64> {
+    let rest = &text[text.find(marker)? + marker.len()..];
+    let digits: String = rest
+        .chars()
+        .skip_while(|c| !c.is_ascii_digit())
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    digits.parse().ok()
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -134,15 +408,21 @@ impl From<std::io::Error> for NodeCliError {
 impl From<reqwest::Error> for NodeCliError {
     fn from(err: reqwest::Error) -> Self {
         if err.is_timeout() {
-            NodeCliError::Network(NetworkError::Timeout(err.to_string()))
+            NodeCliError::Network(NetworkError::Timeout(describe(&err)))
         } else if err.is_connect() {
-            NodeCliError::Network(NetworkError::ConnectionFailed(err.to_string()))
+            NodeCliError::Network(NetworkError::ConnectionFailed(describe(&err)))
         } else {
-            NodeCliError::Network(NetworkError::RequestFailed(err.to_string()))
+            NodeCliError::Network(NetworkError::RequestFailed(describe(&err)))
         }
     }
 }
 
+impl From<tonic::transport::Error> for NodeCliError {
+    fn from(err: tonic::transport::Error) -> Self {
+        NodeCliError::Network(NetworkError::ConnectionFailed(describe(&err)))
+    }
+}
+
 impl From<serde_json::Error> for NodeCliError {
     fn from(err: serde_json::Error) -> Self {
         NodeCliError::Api(ApiError::ParseError(err.to_string()))
@@ -175,12 +455,30 @@ impl From<&str> for NodeCliError {
 
 impl From<Box<dyn Error>> for NodeCliError {
     fn from(err: Box<dyn Error>) -> Self {
-        NodeCliError::General(err.to_string())
+        let err = match err.downcast::<NodeCliError>() {
+            Ok(err) => return *err,
+            Err(err) => err,
+        };
+        let err = match err.downcast::<reqwest::Error>() {
+            Ok(err) => return (*err).into(),
+            Err(err) => err,
+        };
+        let err = match err.downcast::<tonic::transport::Error>() {
+            Ok(err) => return (*err).into(),
+            Err(err) => err,
+        };
+        match err.downcast::<tonic::Status>() {
+            Ok(status) => (*status).into(),
+            Err(err) => NodeCliError::General(describe(err.as_ref())),
+        }
     }
 }
 
 impl From<tonic::Status> for NodeCliError {
     fn from(err: tonic::Status) -> Self {
+        if let Some(detail) = crate::maintenance::from_grpc(err.code(), err.message()) {
+            return NodeCliError::node_in_maintenance(&detail);
+        }
         NodeCliError::Api(ApiError::GrpcError(format!(
             "{}: {}",
             err.code(),
@@ -192,6 +490,27 @@ impl From<tonic::Status> for NodeCliError {
 /// Result type alias for convenience
 pub type Result<T> = std::result::Result<T, NodeCliError>;
 
+/// Process exit code when a deploy expired before inclusion
+pub const EXIT_DEPLOY_EXPIRED: u8 = 3;
+
+/// Process exit code when a followed block was orphaned
+pub const EXIT_BLOCK_ORPHANED: u8 = 4;
+
+/// Process exit code when the node is in maintenance mode
+pub const EXIT_NODE_MAINTENANCE: u8 = 5;
+
+/// Process exit code when a deploy was included but errored during execution
+pub const EXIT_DEPLOY_ERRORED: u8 = 6;
+
+/// Process exit code when a registry lookup found nothing at the URI
+pub const EXIT_NOT_REGISTERED: u8 = 7;
+
+/// Process exit code when a registry entry is not at the expected version
+pub const EXIT_VERSION_MISMATCH: u8 = 8;
+
+/// Characters of an unexpected response body kept in the error
+const BODY_SNIPPET_CHARS: usize = 200;
+
 /// Helper functions for creating specific error types
 impl NodeCliError {
     pub fn network_connection_failed(msg: &str) -> Self {
@@ -202,6 +521,15 @@ impl NodeCliError {
         NodeCliError::Network(NetworkError::HttpError(code, msg.to_string()))
     }
 
+    /// An unsuccessful HTTP answer: maintenance mode if the body says so,
+    /// otherwise an HTTP error with the body as its message
+    pub fn http_status(code: u16, body: &str) -> Self {
+        match crate::maintenance::from_http(code, body) {
+            Some(detail) => NodeCliError::node_in_maintenance(&detail),
+            None => NodeCliError::network_http_error(code, body),
+        }
+    }
+
     pub fn crypto_invalid_private_key(msg: &str) -> Self {
         NodeCliError::Crypto(CryptoError::InvalidPrivateKey(msg.to_string()))
     }
@@ -244,4 +572,364 @@ impl NodeCliError {
     pub fn io_error(msg: &str) -> Self {
         NodeCliError::File(FileError::ReadFailed("io".to_string(), msg.to_string()))
     }
+
+    pub fn deploy_rejected(raw: &str) -> Self {
+        NodeCliError::Api(ApiError::DeployRejected {
+            reason: DeployRejection::classify(raw),
+            raw: raw.to_string(),
+        })
+    }
+
+    pub fn propose_rejected(raw: &str) -> Self {
+        NodeCliError::Api(ApiError::ProposeRejected {
+            reason: ProposeRejection::classify(raw),
+            raw: raw.to_string(),
+        })
+    }
+
+    /// Attach the node call that failed, unless the error already names one
+    ///
+    /// The innermost call is the most specific, so an existing context is kept.
+    pub fn with_context(self, context: CallContext) -> Self {
+        match self {
+            NodeCliError::Call { .. } => self,
+            other => NodeCliError::Call {
+                context,
+                source: Box::new(other),
+            },
+        }
+    }
+
+    /// The node call that failed, if known
+    pub fn context(&self) -> Option<&CallContext> {
+        match self {
+            NodeCliError::Call { context, .. } => Some(context),
+            _ => None,
+        }
+    }
+
+    /// The error without its call context
+    pub fn root(&self) -> &NodeCliError {
+        match self {
+            NodeCliError::Call { source, .. } => source.root(),
+            other => other,
+        }
+    }
+
+    /// The classified rejection and raw node message, if this is a deploy rejection
+    pub fn deploy_rejection(&self) -> Option<(&DeployRejection, &str)> {
+        match self.root() {
+            NodeCliError::Api(ApiError::DeployRejected { reason, raw }) => Some((reason, raw)),
+            _ => None,
+        }
+    }
+
+    /// The classified rejection and raw node message, if this is a propose rejection
+    pub fn propose_rejection(&self) -> Option<(&ProposeRejection, &str)> {
+        match self.root() {
+            NodeCliError::Api(ApiError::ProposeRejected { reason, raw }) => Some((reason, raw)),
+            _ => None,
+        }
+    }
+
+    pub fn unsupported_feature(version: &str, feature: &str, required: &str) -> Self {
+        NodeCliError::Api(ApiError::UnsupportedFeature {
+            version: version.to_string(),
+            feature: feature.to_string(),
+            required: required.to_string(),
+        })
+    }
+
+    pub fn deploy_expired(expired_at: i64, tip: i64) -> Self {
+        NodeCliError::Api(ApiError::DeployExpired { expired_at, tip })
+    }
+
+    pub fn block_orphaned(block_hash: &str, height: i64, last_finalized: i64) -> Self {
+        NodeCliError::Api(ApiError::BlockOrphaned {
+            block_hash: block_hash.to_string(),
+            height,
+            last_finalized,
+        })
+    }
+
+    /// `detail` is the node's system deploy error, when it gave one
+    pub fn deploy_errored(deploy_id: &str, detail: Option<&str>) -> Self {
+        NodeCliError::Api(ApiError::DeployErrored {
+            deploy_id: deploy_id.to_string(),
+            detail: detail.unwrap_or("no error message given").to_string(),
+        })
+    }
+
+    pub fn not_registered(uri: &str) -> Self {
+        NodeCliError::Api(ApiError::NotRegistered {
+            uri: uri.to_string(),
+        })
+    }
+
+    pub fn registry_version_mismatch(uri: &str, expected: i64, found: Option<i64>) -> Self {
+        NodeCliError::Api(ApiError::RegistryVersionMismatch {
+            uri: uri.to_string(),
+            expected,
+            found,
+        })
+    }
+
+    pub fn no_data(block: &str) -> Self {
+        NodeCliError::Api(ApiError::NoData {
+            block: block.to_string(),
+        })
+    }
+
+    /// An explore-deploy reply with `status` and the start of `body`
+    pub fn unexpected_explore_response(status: u16, body: &str) -> Self {
+        let body = body.trim();
+        let snippet = match body.char_indices().nth(BODY_SNIPPET_CHARS) {
+            Some((end, _)) => format!("{}...", &body[..end]),
+            None => body.to_string(),
+        };
+        NodeCliError::Api(ApiError::UnexpectedExploreResponse { status, snippet })
+    }
+
+    pub fn node_in_maintenance(detail: &str) -> Self {
+        NodeCliError::Api(ApiError::NodeInMaintenance {
+            detail: detail.to_string(),
+        })
+    }
+
+    /// The node's explanation, if this error says it is in maintenance mode
+    pub fn maintenance_detail(&self) -> Option<&str> {
+        match self.root() {
+            NodeCliError::Api(ApiError::NodeInMaintenance { detail }) => Some(detail),
+            _ => None,
+        }
+    }
+
+    /// True if an exploratory deploy returned no data, as opposed to an
+    /// answer that could not be read
+    pub fn is_no_data(&self) -> bool {
+        matches!(self.root(), NodeCliError::Api(ApiError::NoData { .. }))
+    }
+
+    /// Process exit code for this error
+    pub fn exit_code(&self) -> u8 {
+        match self.root() {
+            NodeCliError::Api(ApiError::DeployExpired { .. }) => EXIT_DEPLOY_EXPIRED,
+            NodeCliError::Api(ApiError::BlockOrphaned { .. }) => EXIT_BLOCK_ORPHANED,
+            NodeCliError::Api(ApiError::NodeInMaintenance { .. }) => EXIT_NODE_MAINTENANCE,
+            NodeCliError::Api(ApiError::DeployErrored { .. }) => EXIT_DEPLOY_ERRORED,
+            NodeCliError::Api(ApiError::NotRegistered { .. }) => EXIT_NOT_REGISTERED,
+            NodeCliError::Api(ApiError::RegistryVersionMismatch { .. }) => EXIT_VERSION_MISMATCH,
+            _ => 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_deploy_rejections() {
+        let corpus = [
+            (
+                "Phlo price 1 is less than minimum price 10.",
+                DeployRejection::PhloPriceTooLow { min: Some(10) },
+            ),
+            (
+                "Deploy rejected: Phlo price 0 is less than minimum price 1",
+                DeployRejection::PhloPriceTooLow { min: Some(1) },
+            ),
+            (
+                "Invalid deploy signature",
+                DeployRejection::SignatureInvalid,
+            ),
+            (
+                "Deploy signature verification failed: Signature(DeployId)",
+                DeployRejection::SignatureInvalid,
+            ),
+            (
+                "Deploy term size 2097152 exceeds maximum of 1048576 bytes",
+                DeployRejection::TermTooLarge { max: Some(1048576) },
+            ),
+            (
+                "Term too large",
+                DeployRejection::TermTooLarge { max: None },
+            ),
+            ("Deploy expired at 1718000000000", DeployRejection::Expired),
+            (
+                "Deploy expiration timestamp 1718000000000 is in the past",
+                DeployRejection::Expired,
+            ),
+            (
+                "Casper instance not available yet. ",
+                DeployRejection::Unknown("Casper instance not available yet.".to_string()),
+            ),
+        ];
+        for (raw, expected) in corpus {
+            assert_eq!(DeployRejection::classify(raw), expected, "{}", raw);
+        }
+    }
+
+    #[test]
+    fn test_deploy_rejected_keeps_raw_message() {
+        let raw = "Phlo price 1 is less than minimum price 10.";
+        let err = NodeCliError::deploy_rejected(raw);
+        assert_eq!(
+            err.to_string(),
+            "API error: Deploy rejected: phlo price below the node minimum of 10"
+        );
+        let (reason, kept) = err.deploy_rejection().unwrap();
+        assert_eq!(kept, raw);
+        assert!(reason.hint().unwrap().contains("at least 10"));
+        assert!(DeployRejection::Unknown(raw.to_string()).hint().is_none());
+        assert!(NodeCliError::parse_error("x").deploy_rejection().is_none());
+    }
+
+    #[test]
+    fn test_classify_propose_rejections() {
+        let not_active = [
+            "Propose failed: NotBonded",
+            "Validator is not bonded",
+            "Node is not a validator",
+            "Read-only node cannot propose",
+            "No validator identity configured",
+        ];
+        for raw in not_active {
+            assert_eq!(
+                ProposeRejection::classify(raw),
+                ProposeRejection::NotActiveValidator,
+                "{}",
+                raw
+            );
+        }
+        assert_eq!(
+            ProposeRejection::classify(" Casper instance not available yet. "),
+            ProposeRejection::Unknown("Casper instance not available yet.".to_string())
+        );
+
+        let boxed: Box<dyn Error> = NodeCliError::propose_rejected("NotBonded").into();
+        let err = NodeCliError::from(boxed);
+        let (reason, raw) = err.propose_rejection().unwrap();
+        assert_eq!(*reason, ProposeRejection::NotActiveValidator);
+        assert_eq!(raw, "NotBonded");
+        assert!(err.deploy_rejection().is_none());
+    }
+
+    #[test]
+    fn test_deploy_expired_survives_boxing() {
+        let boxed: Box<dyn Error> = NodeCliError::deploy_expired(150, 152).into();
+        let err = NodeCliError::from(boxed);
+        assert_eq!(err.exit_code(), EXIT_DEPLOY_EXPIRED);
+        assert!(err.to_string().contains("expired at block 150"), "{}", err);
+
+        let other: Box<dyn Error> = "plain failure".into();
+        assert_eq!(NodeCliError::from(other).exit_code(), 1);
+    }
+
+    #[test]
+    fn test_registry_lookup_exit_codes() {
+        assert_eq!(
+            NodeCliError::not_registered("rho:id:abc").exit_code(),
+            EXIT_NOT_REGISTERED
+        );
+        let mismatch = NodeCliError::registry_version_mismatch("rho:id:abc", 3, Some(2));
+        assert_eq!(mismatch.exit_code(), EXIT_VERSION_MISMATCH);
+        assert_eq!(
+            mismatch.to_string(),
+            "API error: rho:id:abc is registered with version 2, expected version 3"
+        );
+        assert_eq!(
+            NodeCliError::registry_version_mismatch("rho:id:abc", 3, None).to_string(),
+            "API error: rho:id:abc is registered with no version, expected version 3"
+        );
+    }
+
+    #[test]
+    fn test_deploy_errored_survives_boxing() {
+        let boxed: Box<dyn Error> =
+            NodeCliError::deploy_errored("3045ab", Some("Insufficient funds")).into();
+        let err = NodeCliError::from(boxed);
+        assert_eq!(err.exit_code(), EXIT_DEPLOY_ERRORED);
+        assert_eq!(
+            err.to_string(),
+            "API error: Deploy 3045ab errored during execution: Insufficient funds"
+        );
+    }
+
+    #[test]
+    fn test_maintenance_answers_are_classified() {
+        let http =
+            NodeCliError::http_status(503, r#"{"status":"maintenance","message":"upgrade"}"#);
+        assert_eq!(http.maintenance_detail(), Some("upgrade"));
+        assert_eq!(http.exit_code(), EXIT_NODE_MAINTENANCE);
+        assert!(
+            http.to_string().contains("maintenance mode (upgrade)"),
+            "{}",
+            http
+        );
+
+        let plain = NodeCliError::http_status(503, "Service Unavailable");
+        assert!(plain.maintenance_detail().is_none());
+        assert!(matches!(
+            plain,
+            NodeCliError::Network(NetworkError::HttpError(503, _))
+        ));
+
+        let status = tonic::Status::unavailable("node is in maintenance mode");
+        let boxed: Box<dyn Error> = Box::new(status);
+        let grpc = NodeCall::start("is_finalized", "127.0.0.1:40412").fail(boxed);
+        assert_eq!(
+            grpc.maintenance_detail(),
+            Some("node is in maintenance mode")
+        );
+        assert_eq!(grpc.exit_code(), EXIT_NODE_MAINTENANCE);
+
+        let unreachable = NodeCliError::from(tonic::Status::unavailable("tcp connect error"));
+        assert!(unreachable.maintenance_detail().is_none());
+        assert_eq!(unreachable.exit_code(), 1);
+    }
+
+    #[test]
+    fn test_http_call_names_the_api_operation() {
+        let context = |url: &str| NodeCall::http(url).context();
+        assert_eq!(
+            context("http://127.0.0.1:40413/api/explore-deploy").operation,
+            "explore-deploy"
+        );
+        assert_eq!(context("http://h:1/api/blocks/3/9").operation, "blocks");
+        assert_eq!(
+            context("http://h:1/api/deploy/3045?view=full").operation,
+            "deploy"
+        );
+        assert_eq!(context("http://h:1/metrics").operation, "/metrics");
+        assert_eq!(
+            context("http://h:1/api/status").endpoint,
+            "http://h:1/api/status"
+        );
+    }
+
+    #[test]
+    fn test_call_context_wraps_once() {
+        let call = NodeCall::start("is_finalized", "127.0.0.1:40412");
+        let err = call.fail(NodeCliError::deploy_expired(150, 152));
+        assert_eq!(err.context().unwrap().operation, "is_finalized");
+        assert_eq!(err.context().unwrap().endpoint, "127.0.0.1:40412");
+        assert_eq!(err.exit_code(), EXIT_DEPLOY_EXPIRED);
+        assert!(err
+            .to_string()
+            .contains("[is_finalized at 127.0.0.1:40412, after "));
+
+        // An outer call does not replace the context of the call that failed
+        let outer = NodeCall::start("deploy", "http://127.0.0.1:40413/api/deploy/abc");
+        let rewrapped = outer.fail(err);
+        assert_eq!(rewrapped.context().unwrap().operation, "is_finalized");
+        assert!(matches!(
+            rewrapped.root(),
+            NodeCliError::Api(ApiError::DeployExpired { .. })
+        ));
+
+        let boxed: Box<dyn Error> = rewrapped.into();
+        let err = NodeCliError::from(boxed);
+        assert_eq!(err.context().unwrap().endpoint, "127.0.0.1:40412");
+    }
 }
@@ -1,18 +1,68 @@
 //! WebSocket event streaming for real-time deploy finalization
 //!
 //! Connects to the node's `/ws/events` endpoint and provides
-//! deploy finalization notifications without polling.
+//! deploy finalization notifications without polling, and streams of
+//! `block-added` and block lifecycle notifications for watchers.
 //!
 //! Uses `f1r3fly_shared::F1r3flyEvent` for type-safe event deserialization,
 //! matching the node's event format exactly.
+//!
+//! Raw frames can be captured to an NDJSON dump with [`EventDump`] and fed
+//! back later with [`read_dump`] and [`replay`], so a parsing problem seen
+//! live can be reproduced without the node.
+//!
+//! Connecting to the node (`NodeEvents` and the `subscribe_*` functions)
+//! needs the `ws` feature; parsing, dumps and replay do not.
 
-use f1r3fly_shared::rust::shared::f1r3fly_event::{DeployEvent as NodeDeployEvent, F1r3flyEvent};
+#[cfg(feature = "ws")]
+use crate::maintenance::MaintenanceWait;
+use f1r3fly_shared::rust::shared::f1r3fly_event::DeployEvent as NodeDeployEvent;
+#[cfg(feature = "ws")]
+use f1r3fly_shared::rust::shared::f1r3fly_event::F1r3flyEvent;
+#[cfg(feature = "ws")]
 use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "ws")]
 use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+#[cfg(feature = "ws")]
 use std::sync::Arc;
 use std::time::Duration;
+use tokio::sync::mpsc;
+#[cfg(feature = "ws")]
 use tokio::sync::{Mutex, Notify};
-use tokio_tungstenite::tungstenite::Message;
+#[cfg(feature = "ws")]
+use tokio_tungstenite::tungstenite::{self, Message};
+
+/// Wait before reconnecting to a node's event stream
+#[cfg(feature = "ws")]
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// How long to wait after a failed connect to a node's event stream
+///
+/// A node refusing the upgrade because it is in maintenance mode is retried
+/// less often, with one warning for the whole outage instead of one per try.
+#[cfg(feature = "ws")]
+pub(crate) fn connect_retry_delay(
+    maintenance: &mut MaintenanceWait,
+    err: &tungstenite::Error,
+    delay: Duration,
+) -> Duration {
+    if let Some(detail) = crate::maintenance::from_ws(err) {
+        let err = crate::error::NodeCliError::node_in_maintenance(&detail);
+        if let Some(backoff) = maintenance.on_error(&err, delay) {
+            return backoff;
+        }
+    }
+    tracing::warn!(
+        "WebSocket connect failed: {}, retrying in {}s",
+        err,
+        delay.as_secs()
+    );
+    delay
+}
 
 /// A deploy finalization event from the node
 #[derive(Debug, Clone)]
@@ -39,12 +89,14 @@ impl From<NodeDeployEvent> for DeployEvent {
 /// Connects to a node's `/ws/events` endpoint and tracks
 /// `block-finalised` events. Callers use `wait_for_deploy`
 /// to wait for a specific deploy to be finalized.
+#[cfg(feature = "ws")]
 #[derive(Clone)]
 pub struct NodeEvents {
     pending: Arc<Mutex<HashMap<String, Arc<Notify>>>>,
     results: Arc<Mutex<HashMap<String, DeployEvent>>>,
 }
 
+#[cfg(feature = "ws")]
 impl NodeEvents {
     /// Connect to a node's WebSocket event stream
     ///
@@ -58,9 +110,12 @@ impl NodeEvents {
             let pending = pending.clone();
             let results = results.clone();
             async move {
+                let mut maintenance = MaintenanceWait::new();
                 loop {
+                    let mut delay = RECONNECT_DELAY;
                     match tokio_tungstenite::connect_async(&url).await {
                         Ok((mut stream, _)) => {
+                            maintenance.on_success();
                             tracing::info!("WebSocket connected to {}", url);
                             while let Some(msg) = stream.next().await {
                                 let text = match msg {
@@ -72,28 +127,10 @@ impl NodeEvents {
                                     }
                                 };
 
-                                // The node sends events in an envelope:
-                                // {"event": "block-finalised", "schema-version": 1, "payload": {...}}
-                                // F1r3flyEvent expects internally-tagged format:
-                                // {"event": "block-finalised", "block-hash": "...", "deploys": [...]}
-                                // Unwrap: merge payload fields into top-level, remove envelope keys.
-                                let mut envelope: serde_json::Value =
-                                    match serde_json::from_str(&text) {
-                                        Ok(v) => v,
-                                        Err(_) => continue,
-                                    };
-
-                                if let Some(payload) = envelope.get("payload").cloned() {
-                                    if let (Some(top), Some(inner)) =
-                                        (envelope.as_object_mut(), payload.as_object())
-                                    {
-                                        for (k, v) in inner {
-                                            top.insert(k.clone(), v.clone());
-                                        }
-                                        top.remove("payload");
-                                        top.remove("schema-version");
-                                    }
-                                }
+                                let envelope = match unwrap_envelope(&text) {
+                                    Some(v) => v,
+                                    None => continue,
+                                };
 
                                 let event: F1r3flyEvent = match serde_json::from_value(envelope) {
                                     Ok(e) => e,
@@ -125,10 +162,10 @@ impl NodeEvents {
                             tracing::info!("WebSocket disconnected, reconnecting in 5s...");
                         }
                         Err(e) => {
-                            tracing::warn!("WebSocket connect failed: {}, retrying in 5s", e);
+                            delay = connect_retry_delay(&mut maintenance, &e, RECONNECT_DELAY);
                         }
                     }
-                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    tokio::time::sleep(delay).await;
                 }
             }
         });
@@ -180,3 +217,454 @@ impl NodeEvents {
         results.get(deploy_id).cloned()
     }
 }
+
+/// Flatten a node event envelope into the internally-tagged form
+///
+/// The node sends events in an envelope:
+/// `{"event": "block-finalised", "schema-version": 1, "payload": {...}}`.
+/// `F1r3flyEvent` expects `{"event": "block-finalised", "block-hash": "...", "deploys": [...]}`,
+/// so the payload fields are merged into the top level and the envelope keys removed.
+fn unwrap_envelope(text: &str) -> Option<serde_json::Value> {
+    let mut envelope: serde_json::Value = serde_json::from_str(text).ok()?;
+    if let Some(payload) = envelope.get("payload").cloned() {
+        if let (Some(top), Some(inner)) = (envelope.as_object_mut(), payload.as_object()) {
+            for (k, v) in inner {
+                top.insert(k.clone(), v.clone());
+            }
+            top.remove("payload");
+            top.remove("schema-version");
+        }
+    }
+    Some(envelope)
+}
+
+/// A `block-added` event from the node
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockAdded {
+    pub block_hash: String,
+    pub block_number: Option<i64>,
+    /// Public key of the validator that created the block
+    pub creator: Option<String>,
+    pub seq_num: Option<i64>,
+}
+
+impl BlockAdded {
+    /// Parse a raw event message, returning `None` for any other event
+    pub fn from_message(text: &str) -> Option<Self> {
+        let event = unwrap_envelope(text)?;
+        if event.get("event")?.as_str()? != "block-added" {
+            return None;
+        }
+        Some(Self {
+            block_hash: event.get("block-hash")?.as_str()?.to_string(),
+            block_number: event.get("block-number").and_then(|n| n.as_i64()),
+            creator: event
+                .get("creator")
+                .and_then(|c| c.as_str())
+                .map(str::to_string),
+            seq_num: event.get("seq-num").and_then(|n| n.as_i64()),
+        })
+    }
+}
+
+/// Which step of a block's lifecycle an event reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockEventKind {
+    /// Proposed by its creator (`block-created`, sent by the creating node only)
+    Created,
+    /// Validated and added to the node's DAG (`block-added`)
+    Added,
+    /// Finalized (`block-finalised`)
+    Finalised,
+}
+
+/// A block lifecycle event from the node, stamped when it was received
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockEvent {
+    pub kind: BlockEventKind,
+    pub block_hash: String,
+    pub block_number: Option<i64>,
+    /// Unix epoch milliseconds
+    pub received_at: i64,
+}
+
+impl BlockEvent {
+    /// Parse a raw event message, returning `None` for non-block events
+    pub fn from_message(text: &str, received_at: i64) -> Option<Self> {
+        let event = unwrap_envelope(text)?;
+        let kind = match event.get("event")?.as_str()? {
+            "block-created" => BlockEventKind::Created,
+            "block-added" => BlockEventKind::Added,
+            "block-finalised" => BlockEventKind::Finalised,
+            _ => return None,
+        };
+        Some(Self {
+            kind,
+            block_hash: event.get("block-hash")?.as_str()?.to_string(),
+            block_number: event.get("block-number").and_then(|n| n.as_i64()),
+            received_at,
+        })
+    }
+}
+
+/// Subscribe to a node's `block-added` events
+///
+/// `ws_url` is the base WebSocket URL as for [`NodeEvents::connect`]. The
+/// connection is retried every 5s after a failure or disconnect; blocks added
+/// while disconnected are not replayed. The background task stops once the
+/// receiver is dropped.
+#[cfg(feature = "ws")]
+pub fn subscribe_blocks_added(ws_url: &str) -> mpsc::UnboundedReceiver<BlockAdded> {
+    subscribe(ws_url, BlockAdded::from_message)
+}
+
+/// Subscribe to a node's `block-created`, `block-added` and `block-finalised`
+/// events, reconnecting as [`subscribe_blocks_added`] does
+#[cfg(feature = "ws")]
+pub fn subscribe_block_events(ws_url: &str) -> mpsc::UnboundedReceiver<BlockEvent> {
+    subscribe(ws_url, |text| {
+        BlockEvent::from_message(text, chrono::Utc::now().timestamp_millis())
+    })
+}
+
+/// Subscribe to every raw text frame a node sends, reconnecting as
+/// [`subscribe_blocks_added`] does, for watchers that parse events themselves
+#[cfg(feature = "ws")]
+pub fn subscribe_frames(ws_url: &str) -> mpsc::UnboundedReceiver<String> {
+    subscribe(ws_url, |text| Some(text.to_string()))
+}
+
+/// Forward every message `parse` accepts until the receiver is dropped
+#[cfg(feature = "ws")]
+fn subscribe<T, F>(ws_url: &str, parse: F) -> mpsc::UnboundedReceiver<T>
+where
+    T: Send + 'static,
+    F: Fn(&str) -> Option<T> + Send + 'static,
+{
+    let url = format!("{}/ws/events", ws_url);
+    let (tx, rx) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        let mut maintenance = MaintenanceWait::new();
+        loop {
+            let mut delay = RECONNECT_DELAY;
+            match tokio_tungstenite::connect_async(&url).await {
+                Ok((mut stream, _)) => {
+                    maintenance.on_success();
+                    tracing::info!("WebSocket connected to {}", url);
+                    while let Some(msg) = stream.next().await {
+                        let text = match msg {
+                            Ok(Message::Text(t)) => t,
+                            Ok(_) => continue,
+                            Err(e) => {
+                                tracing::debug!("WebSocket error: {}", e);
+                                break;
+                            }
+                        };
+                        if let Some(item) = parse(text.as_str()) {
+                            if tx.send(item).is_err() {
+                                return;
+                            }
+                        }
+                    }
+                    tracing::info!("WebSocket disconnected, reconnecting in 5s...");
+                }
+                Err(e) => {
+                    delay = connect_retry_delay(&mut maintenance, &e, RECONNECT_DELAY);
+                }
+            }
+            if tx.is_closed() {
+                return;
+            }
+            tokio::time::sleep(delay).await;
+        }
+    });
+
+    rx
+}
+
+/// One raw WebSocket text frame, as captured with `--dump-events`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedFrame {
+    /// Client clock when the frame arrived, Unix epoch milliseconds
+    pub received_at: i64,
+    /// The frame exactly as the node sent it
+    pub text: String,
+}
+
+impl RecordedFrame {
+    /// A frame received now
+    pub fn now(text: &str) -> Self {
+        Self {
+            received_at: chrono::Utc::now().timestamp_millis(),
+            text: text.to_string(),
+        }
+    }
+}
+
+/// Appends raw frames to an NDJSON dump file, one per line, as they arrive
+///
+/// Frames are written before anything parses them, so the dump holds what
+/// the node sent even when parsing fails. Each line is written with a single
+/// unbuffered write; a crash can at worst leave the last line partial, and
+/// reopening the file starts a fresh line after it.
+pub struct EventDump {
+    file: File,
+    path: PathBuf,
+    offset: u64,
+}
+
+impl EventDump {
+    /// Open `path` for appending, creating it if needed
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(path)?;
+        let mut offset = file.metadata()?.len();
+        if offset > 0 {
+            let mut last = [0u8; 1];
+            file.seek(SeekFrom::Start(offset - 1))?;
+            file.read_exact(&mut last)?;
+            if last[0] != b'\n' {
+                file.write_all(b"\n")?;
+                offset += 1;
+            }
+        }
+        Ok(Self {
+            file,
+            path: path.to_path_buf(),
+            offset,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append `frame`, returning the byte offset its line starts at
+    pub fn record(&mut self, frame: &RecordedFrame) -> io::Result<u64> {
+        let mut line = serde_json::to_vec(frame)?;
+        line.push(b'\n');
+        self.file.write_all(&line)?;
+        let start = self.offset;
+        self.offset += line.len() as u64;
+        Ok(start)
+    }
+
+    /// Where a frame that failed to parse can be found, for warnings
+    pub fn locate(&self, offset: u64) -> String {
+        format!("byte offset {} in {}", offset, self.path.display())
+    }
+}
+
+/// Frames read back from a dump
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DumpContents {
+    pub frames: Vec<RecordedFrame>,
+    /// Lines that are not frames, such as one cut short by a crash
+    pub skipped: usize,
+}
+
+impl DumpContents {
+    pub fn parse(contents: &str) -> Self {
+        let mut dump = Self::default();
+        for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+            match serde_json::from_str(line) {
+                Ok(frame) => dump.frames.push(frame),
+                Err(_) => dump.skipped += 1,
+            }
+        }
+        dump
+    }
+}
+
+/// Read a dump written by [`EventDump`]
+pub fn read_dump(path: &Path) -> io::Result<DumpContents> {
+    Ok(DumpContents::parse(&std::fs::read_to_string(path)?))
+}
+
+/// Pause before `next` when replaying at `speed` times the recorded pace
+///
+/// A speed of 0 replays without pauses; frames recorded out of clock order
+/// are not delayed.
+pub fn replay_delay(previous: &RecordedFrame, next: &RecordedFrame, speed: f64) -> Duration {
+    let gap = next.received_at.saturating_sub(previous.received_at);
+    if speed <= 0.0 || gap <= 0 {
+        return Duration::ZERO;
+    }
+    Duration::from_secs_f64(gap as f64 / 1_000.0 / speed)
+}
+
+/// Send `frames` in order, paced by [`replay_delay`], until the receiver is dropped
+pub fn replay(frames: Vec<RecordedFrame>, speed: f64) -> mpsc::UnboundedReceiver<RecordedFrame> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let mut previous: Option<RecordedFrame> = None;
+        for frame in frames {
+            if let Some(previous) = &previous {
+                tokio::time::sleep(replay_delay(previous, &frame, speed)).await;
+            }
+            if tx.send(frame.clone()).is_err() {
+                return;
+            }
+            previous = Some(frame);
+        }
+    });
+    rx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dump_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("node_cli_{}_{}.ndjson", name, std::process::id()))
+    }
+
+    fn synthetic_frames() -> Vec<RecordedFrame> {
+        [
+            r#"{"event":"started","schema-version":1}"#,
+            r#"{"event":"block-created","schema-version":1,"payload":{"block-hash":"a1","block-number":5}}"#,
+            "not json at all",
+            r#"{"event":"block-added","schema-version":1,"payload":{"block-hash":"a1","block-number":5}}"#,
+            "{\"event\":\"block-finalised\",\n\"payload\":{\"block-hash\":\"a1\"}}",
+        ]
+        .iter()
+        .enumerate()
+        .map(|(i, text)| RecordedFrame {
+            received_at: 1_000 + i as i64 * 250,
+            text: text.to_string(),
+        })
+        .collect()
+    }
+
+    fn parsed(frames: &[RecordedFrame]) -> Vec<BlockEvent> {
+        frames
+            .iter()
+            .filter_map(|f| BlockEvent::from_message(&f.text, f.received_at))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_dump_round_trip() {
+        let path = dump_path("dump_round_trip");
+        let _ = std::fs::remove_file(&path);
+        let frames = synthetic_frames();
+
+        let mut dump = EventDump::open(&path).unwrap();
+        let offsets: Vec<u64> = frames.iter().map(|f| dump.record(f).unwrap()).collect();
+        assert_eq!(offsets[0], 0);
+        // Each offset points at the start of its frame's line
+        let contents = std::fs::read_to_string(&path).unwrap();
+        for (offset, frame) in offsets.iter().zip(&frames) {
+            let line = contents[*offset as usize..].lines().next().unwrap();
+            assert_eq!(serde_json::from_str::<RecordedFrame>(line).unwrap(), *frame);
+        }
+
+        let read = read_dump(&path).unwrap();
+        assert_eq!(read.frames, frames);
+        assert_eq!(read.skipped, 0);
+
+        let mut replayed = Vec::new();
+        let mut rx = replay(read.frames, 0.0);
+        while let Some(frame) = rx.recv().await {
+            replayed.push(frame);
+        }
+        assert_eq!(parsed(&replayed), parsed(&frames));
+        assert_eq!(parsed(&frames).len(), 3);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_partial_final_line_is_skipped_and_appended_after() {
+        let path = dump_path("dump_partial");
+        let frames = synthetic_frames();
+        let mut contents = String::new();
+        for frame in &frames[..2] {
+            contents.push_str(&serde_json::to_string(frame).unwrap());
+            contents.push('\n');
+        }
+        contents.push_str(r#"{"received_at":1750,"text":"{\"ev"#);
+        std::fs::write(&path, &contents).unwrap();
+
+        let read = read_dump(&path).unwrap();
+        assert_eq!(read.frames, frames[..2]);
+        assert_eq!(read.skipped, 1);
+
+        let mut dump = EventDump::open(&path).unwrap();
+        let offset = dump.record(&frames[3]).unwrap();
+        assert_eq!(offset, contents.len() as u64 + 1);
+        let read = read_dump(&path).unwrap();
+        assert_eq!(
+            read.frames,
+            [frames[0].clone(), frames[1].clone(), frames[3].clone()]
+        );
+        assert_eq!(read.skipped, 1);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_replay_delay() {
+        let at = |received_at| RecordedFrame {
+            received_at,
+            text: String::new(),
+        };
+        assert_eq!(
+            replay_delay(&at(0), &at(1_000), 1.0),
+            Duration::from_secs(1)
+        );
+        assert_eq!(
+            replay_delay(&at(0), &at(1_000), 4.0),
+            Duration::from_millis(250)
+        );
+        assert_eq!(replay_delay(&at(0), &at(1_000), 0.0), Duration::ZERO);
+        assert_eq!(replay_delay(&at(1_000), &at(0), 1.0), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_block_added_from_envelope() {
+        let text = r#"{"event":"block-added","schema-version":1,"payload":{"block-hash":"abc","block-number":7,"deploys":[],"creator":"04aa","seq-num":3}}"#;
+        assert_eq!(
+            BlockAdded::from_message(text),
+            Some(BlockAdded {
+                block_hash: "abc".to_string(),
+                block_number: Some(7),
+                creator: Some("04aa".to_string()),
+                seq_num: Some(3),
+            })
+        );
+    }
+
+    #[test]
+    fn test_other_events_are_not_block_added() {
+        let text =
+            r#"{"event":"block-finalised","schema-version":1,"payload":{"block-hash":"abc"}}"#;
+        assert_eq!(BlockAdded::from_message(text), None);
+        assert_eq!(BlockAdded::from_message("not json"), None);
+    }
+
+    #[test]
+    fn test_block_events_from_envelope() {
+        let text = r#"{"event":"block-finalised","schema-version":1,"payload":{"block-hash":"abc","block-number":7,"deploys":[]}}"#;
+        assert_eq!(
+            BlockEvent::from_message(text, 42),
+            Some(BlockEvent {
+                kind: BlockEventKind::Finalised,
+                block_hash: "abc".to_string(),
+                block_number: Some(7),
+                received_at: 42,
+            })
+        );
+        let text = r#"{"event":"block-created","schema-version":1,"payload":{"block-hash":"abc"}}"#;
+        let event = BlockEvent::from_message(text, 0).unwrap();
+        assert_eq!(event.kind, BlockEventKind::Created);
+        assert_eq!(event.block_number, None);
+
+        let text = r#"{"event":"node-started","schema-version":1,"payload":{"address":"x"}}"#;
+        assert_eq!(BlockEvent::from_message(text, 0), None);
+    }
+}
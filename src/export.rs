@@ -0,0 +1,530 @@
+//! Chain export as NDJSON
+//!
+//! Streams a block height range from a node's HTTP API in chunks: each
+//! chunk's block list comes from `/api/blocks/{start}/{end}` and each block's
+//! deploys from `/api/block/{hash}`. Output is written and flushed one height
+//! at a time, so memory stays bounded by a single height and an interrupted
+//! export can resume from the first height that was not written. Blocks from
+//! nodes that report system deploys carry them in a `system_deploys` list.
+
+use crate::error::{NodeCall, NodeCliError, Result};
+use crate::f1r3fly_api::{BlockDeploy, BlockDetail, BlockHeader, BlockSummary, SystemDeploy};
+use serde::Serialize;
+use std::io::Write;
+use std::str::FromStr;
+
+/// Heights requested per ranged-blocks call
+pub const DEFAULT_CHUNK_SIZE: i64 = 50;
+
+/// Where deploys are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DeployLayout {
+    /// Nested in each block line
+    #[default]
+    Inline,
+    /// One line per deploy in a second file, keyed by `block_hash`
+    Separate,
+}
+
+impl FromStr for DeployLayout {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "inline" => Ok(DeployLayout::Inline),
+            "separate" => Ok(DeployLayout::Separate),
+            other => Err(format!(
+                "unknown deploy layout '{}' (expected inline or separate)",
+                other
+            )),
+        }
+    }
+}
+
+/// A deploy line in separate mode
+#[derive(Serialize)]
+struct DeployLine<'a> {
+    block_hash: &'a str,
+    block_number: i64,
+    #[serde(flatten)]
+    deploy: &'a BlockDeploy,
+}
+
+/// A block line in separate mode
+#[derive(Serialize)]
+struct BlockLine<'a> {
+    #[serde(flatten)]
+    header: &'a BlockHeader,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system_deploys: Option<&'a Vec<SystemDeploy>>,
+}
+
+/// Writes blocks (and in separate mode, deploys) as NDJSON
+pub struct NdjsonWriter<W: Write> {
+    blocks: W,
+    deploys: Option<W>,
+}
+
+impl<W: Write> NdjsonWriter<W> {
+    /// One line per block with its deploys nested
+    pub fn inline(blocks: W) -> Self {
+        Self {
+            blocks,
+            deploys: None,
+        }
+    }
+
+    /// Block lines without deploys, plus one line per deploy in `deploys`
+    pub fn separate(blocks: W, deploys: W) -> Self {
+        Self {
+            blocks,
+            deploys: Some(deploys),
+        }
+    }
+
+    /// Write one block, returning the number of deploys written
+    pub fn write_block(&mut self, block: &BlockDetail) -> Result<usize> {
+        match &mut self.deploys {
+            None => write_line(&mut self.blocks, block)?,
+            Some(deploys) => {
+                let line = BlockLine {
+                    header: &block.header,
+                    system_deploys: block.system_deploys.as_ref(),
+                };
+                write_line(&mut self.blocks, &line)?;
+                for deploy in &block.deploys {
+                    let line = DeployLine {
+                        block_hash: &block.header.block_hash,
+                        block_number: block.header.block_number,
+                        deploy,
+                    };
+                    write_line(deploys, &line)?;
+                }
+            }
+        }
+        Ok(block.deploys.len())
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        self.blocks.flush()?;
+        if let Some(deploys) = &mut self.deploys {
+            deploys.flush()?;
+        }
+        Ok(())
+    }
+
+    /// The underlying writers, blocks first
+    pub fn into_inner(self) -> (W, Option<W>) {
+        (self.blocks, self.deploys)
+    }
+}
+
+fn write_line<W: Write, T: Serialize>(out: &mut W, value: &T) -> Result<()> {
+    serde_json::to_writer(&mut *out, value)?;
+    out.write_all(b"\n")?;
+    Ok(())
+}
+
+/// Ranged block listing and block detail over a node's HTTP API
+#[derive(Debug, Clone)]
+pub struct ChainSource {
+    base_url: String,
+    client: reqwest::Client,
+}
+
+impl ChainSource {
+    pub fn new(host: &str, http_port: u16) -> Self {
+        Self {
+            base_url: format!("http://{}:{}", host, http_port),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    async fn get_json(&self, path: &str) -> Result<serde_json::Value> {
+        let url = format!("{}{}", self.base_url, path);
+        let call = NodeCall::http(&url);
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| call.fail(e))?;
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            return Err(call.fail(NodeCliError::network_http_error(status.as_u16(), &text)));
+        }
+        response.json().await.map_err(|e| call.fail(e))
+    }
+
+    /// Raw entries of `/api/blocks/{start}/{end}`, for fields the typed views drop (e.g. bonds)
+    pub async fn ranged_json(&self, start: i64, end: i64) -> Result<Vec<serde_json::Value>> {
+        let json = self
+            .get_json(&format!("/api/blocks/{}/{}", start, end))
+            .await?;
+        match json {
+            serde_json::Value::Array(list) => Ok(list),
+            _ => Err(NodeCliError::parse_error(
+                "Ranged blocks response is not a list",
+            )),
+        }
+    }
+
+    /// Blocks at heights `start..=end`, sorted by height then hash
+    pub async fn blocks_in_range(&self, start: i64, end: i64) -> Result<Vec<BlockSummary>> {
+        let list = self.ranged_json(start, end).await?;
+        let mut blocks: Vec<BlockSummary> =
+            list.iter().filter_map(BlockSummary::from_json).collect();
+        blocks
+            .sort_by(|a, b| (a.block_number, &a.block_hash).cmp(&(b.block_number, &b.block_hash)));
+        blocks.dedup_by(|a, b| a.block_hash == b.block_hash);
+        Ok(blocks)
+    }
+
+    /// Block headers (with parent hashes) at heights `start..=end`, sorted by height then hash
+    pub async fn headers_in_range(&self, start: i64, end: i64) -> Result<Vec<BlockHeader>> {
+        let list = self.ranged_json(start, end).await?;
+        let mut headers: Vec<BlockHeader> = list
+            .into_iter()
+            .filter_map(|json| {
+                let info = json.get("blockInfo").cloned().unwrap_or(json);
+                serde_json::from_value(info).ok()
+            })
+            .collect();
+        headers
+            .sort_by(|a, b| (a.block_number, &a.block_hash).cmp(&(b.block_number, &b.block_hash)));
+        headers.dedup_by(|a, b| a.block_hash == b.block_hash);
+        Ok(headers)
+    }
+
+    pub async fn last_finalized(&self) -> Result<BlockSummary> {
+        let json = self.get_json("/api/last-finalized-block").await?;
+        BlockSummary::from_json(&json).ok_or_else(|| {
+            NodeCliError::parse_error("Last finalized block response is missing blockHash")
+        })
+    }
+
+    pub async fn block(&self, block_hash: &str) -> Result<BlockDetail> {
+        let json = self.get_json(&format!("/api/block/{}", block_hash)).await?;
+        Ok(BlockDetail::from_json(&json)?)
+    }
+}
+
+/// Counters reported after each chunk and at the end of an export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExportProgress {
+    /// First height not yet written
+    pub next_height: i64,
+    pub end: i64,
+    /// Blocks written
+    pub blocks: usize,
+    pub deploys: usize,
+    /// Blocks left out by `system_errors_only`
+    pub skipped: usize,
+    /// Blocks whose node did not report system deploys
+    pub unreported: usize,
+}
+
+/// Export heights `start..=end`, calling `progress` after each chunk
+///
+/// With `system_errors_only`, only blocks with a failed system deploy are
+/// written. On failure the error names the height to pass to
+/// `--resume-from`; every height below it has been written and flushed.
+pub async fn export_range<W: Write>(
+    source: &ChainSource,
+    start: i64,
+    end: i64,
+    chunk_size: i64,
+    system_errors_only: bool,
+    out: &mut NdjsonWriter<W>,
+    mut progress: impl FnMut(&ExportProgress),
+) -> Result<ExportProgress> {
+    let mut state = ExportProgress {
+        next_height: start,
+        end,
+        blocks: 0,
+        deploys: 0,
+        skipped: 0,
+        unreported: 0,
+    };
+    let interrupted = |height: i64, e: NodeCliError| {
+        NodeCliError::General(format!(
+            "Export interrupted at height {}: {}. Resume with --resume-from {}",
+            height, e, height
+        ))
+    };
+
+    while state.next_height <= end {
+        let chunk_end = (state.next_height + chunk_size.max(1) - 1).min(end);
+        let listed = source
+            .blocks_in_range(state.next_height, chunk_end)
+            .await
+            .map_err(|e| interrupted(state.next_height, e))?;
+
+        for height in listed.chunk_by(|a, b| a.block_number == b.block_number) {
+            let number = height[0].block_number;
+            if number < state.next_height || number > chunk_end {
+                continue;
+            }
+
+            // Fetch the whole height before writing so a failure leaves no partial height
+            let mut details = Vec::with_capacity(height.len());
+            for summary in height {
+                let detail = source
+                    .block(&summary.block_hash)
+                    .await
+                    .map_err(|e| interrupted(number, e))?;
+                details.push(detail);
+            }
+            for detail in &details {
+                if detail.system_deploys.is_none() {
+                    state.unreported += 1;
+                }
+                if system_errors_only && detail.failed_system_deploys().is_empty() {
+                    state.skipped += 1;
+                    continue;
+                }
+                state.deploys += out.write_block(detail)?;
+                state.blocks += 1;
+            }
+            out.flush()?;
+            state.next_height = number + 1;
+        }
+
+        state.next_height = chunk_end + 1;
+        progress(&state);
+    }
+
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::spawn_http_node;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    const TIP: i64 = 4;
+
+    fn light_block(n: i64) -> serde_json::Value {
+        let parents: Vec<String> = (n > 0).then(|| format!("b{}", n - 1)).into_iter().collect();
+        json!({
+            "blockHash": format!("b{}", n),
+            "blockNumber": n,
+            "sender": "04aa",
+            "seqNum": n,
+            "timestamp": 1_700_000_000_000i64 + n,
+            "shardId": "root",
+            "parentsHashList": parents,
+            "preStateHash": format!("pre{}", n),
+            "postStateHash": format!("post{}", n),
+            "deployCount": n % 2,
+            "bonds": [{"validator": "04aa", "stake": 100}, {"validator": "04bb", "stake": 100}]
+        })
+    }
+
+    /// Heights 0..=TIP, one block each; odd heights carry one deploy and
+    /// height 2 a failed system deploy
+    async fn spawn_chain(fail_at_3: Arc<AtomicBool>) -> u16 {
+        spawn_http_node(move |line, _body| {
+            let path = line.split_whitespace().nth(1).unwrap_or("");
+            if let Some(range) = path.strip_prefix("/api/blocks/") {
+                let (s, e) = range.split_once('/').unwrap();
+                let (s, e): (i64, i64) = (s.parse().unwrap(), e.parse().unwrap());
+                let blocks: Vec<_> = (s..=e.min(TIP)).map(light_block).collect();
+                return ("200 OK", json!(blocks).to_string());
+            }
+            let n: i64 = path
+                .strip_prefix("/api/block/b")
+                .and_then(|n| n.parse().ok())
+                .unwrap_or(-1);
+            if n < 0 || n > TIP || (n == 3 && fail_at_3.load(Ordering::SeqCst)) {
+                return ("503 Service Unavailable", "unavailable".to_string());
+            }
+            let deploys: Vec<_> = (0..n % 2)
+                .map(|_| {
+                    json!({
+                        "deployer": "04cc",
+                        "term": "Nil",
+                        "timestamp": 1,
+                        "sig": format!("sig{}", n),
+                        "sigAlgorithm": "secp256k1",
+                        "phloPrice": 1,
+                        "phloLimit": 50000,
+                        "validAfterBlockNumber": n - 1,
+                        "cost": 120,
+                        "errored": false,
+                        "systemDeployError": ""
+                    })
+                })
+                .collect();
+            // Height 2's CloseBlock failed
+            let close_block = if n == 2 {
+                json!({"systemDeployType": "CloseBlockSystemDeploy", "failed": true,
+                       "errorMsg": "Unable to close block"})
+            } else {
+                json!({"systemDeployType": "CloseBlockSystemDeploy", "failed": false})
+            };
+            let block = json!({
+                "blockInfo": light_block(n),
+                "deploys": deploys,
+                "systemDeploys": [close_block]
+            });
+            ("200 OK", block.to_string())
+        })
+        .await
+    }
+
+    fn lines(out: &[u8]) -> Vec<serde_json::Value> {
+        String::from_utf8_lossy(out)
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect()
+    }
+
+    fn keys(value: &serde_json::Value) -> Vec<&str> {
+        let mut keys: Vec<&str> = value
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(|k| k.as_str())
+            .collect();
+        keys.sort();
+        keys
+    }
+
+    #[tokio::test]
+    async fn test_export_inline_schema() {
+        let port = spawn_chain(Arc::new(AtomicBool::new(false))).await;
+        let source = ChainSource::new("127.0.0.1", port);
+        let mut out = NdjsonWriter::inline(Vec::new());
+        let mut chunks = 0;
+
+        let done = export_range(&source, 0, TIP, 2, false, &mut out, |_| chunks += 1)
+            .await
+            .unwrap();
+        assert_eq!((done.blocks, done.deploys, done.next_height), (5, 2, 5));
+        assert_eq!(chunks, 3);
+
+        let blocks = lines(&out.into_inner().0);
+        assert_eq!(blocks.len(), 5);
+        assert_eq!(
+            keys(&blocks[0]),
+            vec![
+                "block_hash",
+                "block_number",
+                "bonds_count",
+                "deploy_count",
+                "deploys",
+                "parents",
+                "post_state_hash",
+                "pre_state_hash",
+                "sender",
+                "seq_num",
+                "shard_id",
+                "system_deploys",
+                "timestamp"
+            ]
+        );
+        // A block without deploys still emits a complete line
+        assert_eq!(blocks[0]["deploys"], json!([]));
+        assert_eq!(blocks[0]["bonds_count"], 2);
+        assert_eq!(blocks[1]["parents"], json!(["b0"]));
+        assert_eq!(
+            keys(&blocks[1]["deploys"][0]),
+            vec![
+                "cost",
+                "deployer",
+                "errored",
+                "phlo_limit",
+                "phlo_price",
+                "sig",
+                "system_deploy_error",
+                "term",
+                "timestamp",
+                "valid_after_block_number"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_export_separate_deploys() {
+        let port = spawn_chain(Arc::new(AtomicBool::new(false))).await;
+        let source = ChainSource::new("127.0.0.1", port);
+        let mut out = NdjsonWriter::separate(Vec::new(), Vec::new());
+
+        export_range(&source, 0, TIP, DEFAULT_CHUNK_SIZE, false, &mut out, |_| {})
+            .await
+            .unwrap();
+        let (blocks, deploys) = out.into_inner();
+        let blocks = lines(&blocks);
+        let deploys = lines(&deploys.unwrap());
+
+        assert_eq!(blocks.len(), 5);
+        assert!(blocks.iter().all(|b| b.get("deploys").is_none()));
+        assert_eq!(deploys.len(), 2);
+        assert_eq!(deploys[0]["block_hash"], "b1");
+        assert_eq!(deploys[0]["block_number"], 1);
+        assert_eq!(deploys[1]["sig"], "sig3");
+    }
+
+    #[tokio::test]
+    async fn test_export_resumes_after_interruption() {
+        let fail = Arc::new(AtomicBool::new(true));
+        let port = spawn_chain(fail.clone()).await;
+        let source = ChainSource::new("127.0.0.1", port);
+        let mut out = NdjsonWriter::inline(Vec::new());
+
+        let err = export_range(&source, 0, TIP, 2, false, &mut out, |_| {})
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("--resume-from 3"), "{}", err);
+        let (written, _) = out.into_inner();
+        let heights: Vec<i64> = lines(&written)
+            .iter()
+            .map(|b| b["block_number"].as_i64().unwrap())
+            .collect();
+        assert_eq!(heights, vec![0, 1, 2]);
+
+        // Append the rest to the same output
+        fail.store(false, Ordering::SeqCst);
+        let mut out = NdjsonWriter::inline(written);
+        export_range(&source, 3, TIP, 2, false, &mut out, |_| {})
+            .await
+            .unwrap();
+        let heights: Vec<i64> = lines(&out.into_inner().0)
+            .iter()
+            .map(|b| b["block_number"].as_i64().unwrap())
+            .collect();
+        assert_eq!(heights, vec![0, 1, 2, 3, 4]);
+    }
+
+    #[tokio::test]
+    async fn test_export_system_errors_only() {
+        let port = spawn_chain(Arc::new(AtomicBool::new(false))).await;
+        let source = ChainSource::new("127.0.0.1", port);
+        let mut out = NdjsonWriter::inline(Vec::new());
+
+        let done = export_range(&source, 0, TIP, 2, true, &mut out, |_| {})
+            .await
+            .unwrap();
+        assert_eq!((done.blocks, done.skipped, done.unreported), (1, 4, 0));
+        let blocks = lines(&out.into_inner().0);
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0]["block_hash"], "b2");
+        assert_eq!(blocks[0]["system_deploys"][0]["kind"], "CloseBlock");
+        assert_eq!(
+            blocks[0]["system_deploys"][0]["error"],
+            "Unable to close block"
+        );
+    }
+
+    #[test]
+    fn test_deploy_layout_from_str() {
+        assert_eq!("inline".parse(), Ok(DeployLayout::Inline));
+        assert_eq!("Separate".parse(), Ok(DeployLayout::Separate));
+        assert!("both".parse::<DeployLayout>().is_err());
+    }
+}
@@ -2,14 +2,20 @@
 //!
 //! The implementation is split across `grpc/` submodules:
 //! - `grpc::deploy` deploy, propose, full_deploy, build_deploy_msg
-//! - `grpc::query` exploratory_deploy, get_data_at_deploy_id, find_deploy_grpc
+//! - `grpc::query` exploratory_deploy, get_data_at_deploy_id, get_data_at_name, find_deploy_grpc,
+//!   find_deploy, get_block_deploy
 //! - `grpc::blocks` show_main_chain, get_blocks_by_height, is_finalized, tip sampling
-//! - `grpc::http` get_deploy_block_hash, get_deploy_detail
+//! - `grpc::http` get_deploy_block_hash, get_deploy_detail, get_block_summary,
+//!   get_block_detail, get_last_finalized_block(_json), get_node_status, get_tip_block_number
+//! - `grpc::inclusion` wait_for_inclusion
 
+use crate::error::NodeCliError;
 use serde::{Deserialize, Serialize};
 
 // Re-export the client and helpers from the grpc module
-pub use crate::grpc::query::extract_par_data;
+pub use crate::grpc::query::{
+    extract_par_data, par_to_rholang_json, public_name, unforgeable_name,
+};
 pub use crate::grpc::F1r3flyApi;
 
 /// Node status from `/api/status`.
@@ -47,6 +53,9 @@ pub struct NodeStatus {
     pub current_epoch: Option<i64>,
     #[serde(rename = "epochLength", default)]
     pub epoch_length: Option<i32>,
+    /// Blocks after its valid-after block number that a deploy stays valid
+    #[serde(rename = "deployLifespan", default)]
+    pub deploy_lifespan: Option<i64>,
 }
 
 /// Deploy execution detail from the node's `/api/deploy/{id}` endpoint.
@@ -81,6 +90,302 @@ pub struct DeployDetail {
     pub valid_after_block_number: Option<i64>,
 }
 
+/// Block a deploy landed in, from the gRPC `findDeploy` lookup
+///
+/// `cost` and `errored` come from the block's deploy list; they are `None`
+/// when the block could not be read.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DeployInfo {
+    pub deploy_id: String,
+    pub block_hash: String,
+    pub block_number: i64,
+    /// Phlo the deploy used
+    pub cost: Option<u64>,
+    /// Whether the deploy's execution failed
+    pub errored: Option<bool>,
+}
+
+/// Block header fields from `/api/block/{hash}`.
+///
+/// Accepts both the flat `LightBlockInfo` shape and the `{"blockInfo": {...}}`
+/// wrapper via [`BlockSummary::from_json`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockSummary {
+    #[serde(rename = "blockHash")]
+    pub block_hash: String,
+    #[serde(rename = "blockNumber", default)]
+    pub block_number: i64,
+    #[serde(default)]
+    pub sender: String,
+    #[serde(rename = "seqNum", default)]
+    pub seq_num: i64,
+    #[serde(default)]
+    pub timestamp: i64,
+    #[serde(rename = "shardId", default)]
+    pub shard_id: String,
+    #[serde(rename = "preStateHash", default)]
+    pub pre_state_hash: String,
+    #[serde(rename = "postStateHash", default)]
+    pub post_state_hash: String,
+    #[serde(rename = "deployCount", default)]
+    pub deploy_count: i32,
+}
+
+impl BlockSummary {
+    /// Parse a block response, unwrapping `blockInfo` if present
+    pub fn from_json(json: &serde_json::Value) -> Option<Self> {
+        let info = json.get("blockInfo").unwrap_or(json);
+        serde_json::from_value(info.clone()).ok()
+    }
+}
+
+/// `faultTolerance` of a block response, unwrapping `blockInfo` if present
+pub fn fault_tolerance(json: &serde_json::Value) -> Option<f64> {
+    json.get("blockInfo")
+        .unwrap_or(json)
+        .get("faultTolerance")?
+        .as_f64()
+}
+
+/// Reply of `/api/explore-deploy` and `/api/explore-deploy-by-block-hash`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExploreDeployResponse {
+    /// Values sent on the term's return channel, in the node's Rholang JSON
+    pub expr: Vec<serde_json::Value>,
+    /// Block the term was evaluated against
+    #[serde(default)]
+    pub block: Option<BlockSummary>,
+}
+
+impl ExploreDeployResponse {
+    /// Parse a reply from its HTTP status and body
+    ///
+    /// A failure status, or a body that is not JSON with an `expr` list (an
+    /// HTML error page, say), is an `UnexpectedExploreResponse` error carrying
+    /// the status and the start of the body.
+    pub fn parse(status: u16, body: &str) -> Result<Self, NodeCliError> {
+        if !(200..300).contains(&status) {
+            return Err(NodeCliError::unexpected_explore_response(status, body));
+        }
+        serde_json::from_str(body)
+            .map_err(|_| NodeCliError::unexpected_explore_response(status, body))
+    }
+
+    /// True if the term sent nothing back
+    pub fn is_empty(&self) -> bool {
+        self.expr.is_empty()
+    }
+}
+
+/// Block header fields exported by `export-chain`.
+///
+/// Deserialized from the node's camelCase `blockInfo`; serialized with the
+/// stable snake_case names documented on the command.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockHeader {
+    #[serde(rename(deserialize = "blockHash"))]
+    pub block_hash: String,
+    #[serde(rename(deserialize = "blockNumber"), default)]
+    pub block_number: i64,
+    #[serde(default)]
+    pub sender: String,
+    #[serde(rename(deserialize = "seqNum"), default)]
+    pub seq_num: i64,
+    #[serde(default)]
+    pub timestamp: i64,
+    #[serde(rename(deserialize = "shardId"), default)]
+    pub shard_id: String,
+    #[serde(rename(deserialize = "parentsHashList"), default)]
+    pub parents: Vec<String>,
+    #[serde(rename(deserialize = "preStateHash"), default)]
+    pub pre_state_hash: String,
+    #[serde(rename(deserialize = "postStateHash"), default)]
+    pub post_state_hash: String,
+    #[serde(rename(deserialize = "deployCount"), default)]
+    pub deploy_count: i32,
+    /// Number of validators in the block's bonds snapshot
+    #[serde(skip_deserializing)]
+    pub bonds_count: usize,
+}
+
+impl From<&BlockHeader> for BlockSummary {
+    fn from(header: &BlockHeader) -> Self {
+        Self {
+            block_hash: header.block_hash.clone(),
+            block_number: header.block_number,
+            sender: header.sender.clone(),
+            seq_num: header.seq_num,
+            timestamp: header.timestamp,
+            shard_id: header.shard_id.clone(),
+            pre_state_hash: header.pre_state_hash.clone(),
+            post_state_hash: header.post_state_hash.clone(),
+            deploy_count: header.deploy_count,
+        }
+    }
+}
+
+/// A deploy as listed in `/api/block/{hash}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockDeploy {
+    pub sig: String,
+    #[serde(default)]
+    pub deployer: String,
+    #[serde(default)]
+    pub term: String,
+    #[serde(default)]
+    pub timestamp: i64,
+    #[serde(rename(deserialize = "phloPrice"), default)]
+    pub phlo_price: i64,
+    #[serde(rename(deserialize = "phloLimit"), default)]
+    pub phlo_limit: i64,
+    #[serde(rename(deserialize = "validAfterBlockNumber"), default)]
+    pub valid_after_block_number: i64,
+    #[serde(default)]
+    pub cost: u64,
+    #[serde(default)]
+    pub errored: bool,
+    #[serde(rename(deserialize = "systemDeployError"), default)]
+    pub system_deploy_error: Option<String>,
+}
+
+/// Kind of a system deploy the validator runs alongside user deploys
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SystemDeployKind {
+    /// Charges a deploy's phlo up front
+    PreCharge,
+    /// Refunds a deploy's unused phlo
+    Refund,
+    /// Closes the block (rewards and epoch bookkeeping)
+    CloseBlock,
+    /// Slashes an equivocating validator
+    Slash,
+    /// A kind this client does not know, as the node named it
+    Other(String),
+}
+
+impl SystemDeployKind {
+    /// Parse the node's name for a kind, with or without a `SystemDeploy` or `Deploy` suffix
+    pub fn parse(raw: &str) -> Self {
+        let name = raw.trim();
+        let base = name
+            .strip_suffix("SystemDeploy")
+            .or_else(|| name.strip_suffix("Deploy"))
+            .unwrap_or(name);
+        match base.replace(['_', '-'], "").to_ascii_lowercase().as_str() {
+            "precharge" => Self::PreCharge,
+            "refund" => Self::Refund,
+            "closeblock" => Self::CloseBlock,
+            "slash" => Self::Slash,
+            _ => Self::Other(name.to_string()),
+        }
+    }
+}
+
+impl std::fmt::Display for SystemDeployKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(match self {
+            Self::PreCharge => "PreCharge",
+            Self::Refund => "Refund",
+            Self::CloseBlock => "CloseBlock",
+            Self::Slash => "Slash",
+            Self::Other(name) => name,
+        })
+    }
+}
+
+/// Serialized by name, as displayed
+impl Serialize for SystemDeployKind {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// A system deploy listed with a block, on nodes that report them
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SystemDeploy {
+    pub kind: SystemDeployKind,
+    pub failed: bool,
+    pub error: Option<String>,
+}
+
+impl SystemDeploy {
+    /// Parse one `systemDeploys` entry
+    ///
+    /// The kind is read from `type` or `systemDeployType` and the error from
+    /// `error`, `errorMsg` or `systemDeployError`. An entry counts as failed
+    /// when it says so or carries a non-empty error.
+    pub fn from_json(json: &serde_json::Value) -> Option<Self> {
+        let field = |names: &[&str]| {
+            names
+                .iter()
+                .find_map(|name| json.get(*name).and_then(|v| v.as_str()))
+        };
+        let kind = SystemDeployKind::parse(field(&["type", "systemDeployType"])?);
+        let error = field(&["error", "errorMsg", "systemDeployError"])
+            .filter(|e| !e.is_empty())
+            .map(str::to_string);
+        let failed = json
+            .get("failed")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+            || error.is_some();
+        Some(Self {
+            kind,
+            failed,
+            error,
+        })
+    }
+}
+
+/// A block with its deploys, from `/api/block/{hash}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BlockDetail {
+    #[serde(flatten)]
+    pub header: BlockHeader,
+    pub deploys: Vec<BlockDeploy>,
+    /// `None` when the node does not report system deploys
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system_deploys: Option<Vec<SystemDeploy>>,
+}
+
+impl BlockDetail {
+    /// Parse a `{"blockInfo": {...}, "deploys": [...]}` block response
+    ///
+    /// Nodes that report system deploys add a `systemDeploys` list, next to
+    /// `deploys` or inside `blockInfo`.
+    pub fn from_json(json: &serde_json::Value) -> Result<Self, serde_json::Error> {
+        let info = json.get("blockInfo").unwrap_or(json);
+        let mut header: BlockHeader = serde_json::from_value(info.clone())?;
+        header.bonds_count = info
+            .get("bonds")
+            .and_then(|b| b.as_array())
+            .map_or(0, |b| b.len());
+        let deploys = match json.get("deploys") {
+            Some(deploys) => serde_json::from_value(deploys.clone())?,
+            None => Vec::new(),
+        };
+        let system_deploys = json
+            .get("systemDeploys")
+            .or_else(|| info.get("systemDeploys"))
+            .and_then(|list| list.as_array())
+            .map(|list| list.iter().filter_map(SystemDeploy::from_json).collect());
+        Ok(Self {
+            header,
+            deploys,
+            system_deploys,
+        })
+    }
+
+    /// System deploys that failed; empty when the node does not report them
+    pub fn failed_system_deploys(&self) -> Vec<&SystemDeploy> {
+        self.system_deploys
+            .iter()
+            .flatten()
+            .filter(|d| d.failed)
+            .collect()
+    }
+}
+
 /// Result of a full deploy-and-wait operation
 #[derive(Debug, Clone)]
 pub struct DeployResult {
@@ -93,8 +398,140 @@ pub struct DeployResult {
     pub data: Vec<f1r3fly_models::rhoapi::Par>,
 }
 
+/// A deploy accepted by the node, with the valid-after block number it was signed with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubmittedDeploy {
+    pub deploy_id: String,
+    /// `None` when the tip could not be read and the deploy went out with 0
+    pub valid_after_block_number: Option<i64>,
+}
+
+/// A deploy included in a block that may not be finalized yet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncludedDeploy {
+    pub deploy_id: String,
+    pub block_hash: String,
+}
+
+/// Validity window of a deploy: it can only be included in blocks up to
+/// `valid_after_block_number + lifespan`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeployExpiry {
+    pub valid_after_block_number: i64,
+    pub lifespan: i64,
+}
+
+impl DeployExpiry {
+    /// Last block number the deploy can be included in
+    pub fn last_valid_block(&self) -> i64 {
+        self.valid_after_block_number + self.lifespan
+    }
+
+    pub fn is_expired(&self, tip: i64) -> bool {
+        tip > self.last_valid_block()
+    }
+}
+
+/// How waiting for a deploy to land in a block ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InclusionOutcome {
+    Included {
+        block_hash: String,
+    },
+    /// The chain tip passed the deploy's last valid block; it will never be included
+    Expired {
+        expired_at: i64,
+        tip: i64,
+    },
+    TimedOut {
+        attempts: u32,
+    },
+}
+
+/// Outcome of a propose call.
+///
+/// `Proposed` carries the block hash extracted from the node's reply together
+/// with the reply itself, since node versions differ in how they phrase it.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ProposeResult {
-    Proposed(String),
+    Proposed {
+        block_hash: String,
+        raw_message: String,
+    },
     Skipped(String),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn block_info() -> serde_json::Value {
+        json!({
+            "blockHash": "b7",
+            "blockNumber": 7,
+            "sender": "04aa",
+            "parentsHashList": ["b6"],
+            "deployCount": 0,
+            "bonds": [{"validator": "04aa", "stake": 100}]
+        })
+    }
+
+    #[test]
+    fn test_block_detail_without_system_deploys() {
+        // Nodes before system deploy reporting
+        let detail =
+            BlockDetail::from_json(&json!({"blockInfo": block_info(), "deploys": []})).unwrap();
+        assert_eq!(detail.header.block_number, 7);
+        assert_eq!(detail.system_deploys, None);
+        assert!(detail.failed_system_deploys().is_empty());
+        let exported = serde_json::to_value(&detail).unwrap();
+        assert!(exported.get("system_deploys").is_none());
+    }
+
+    #[test]
+    fn test_block_detail_with_system_deploys() {
+        let detail = BlockDetail::from_json(&json!({
+            "blockInfo": block_info(),
+            "deploys": [],
+            "systemDeploys": [
+                {"systemDeployType": "PreChargeSystemDeploy", "failed": false, "errorMsg": ""},
+                {"systemDeployType": "CloseBlockSystemDeploy", "failed": true,
+                 "errorMsg": "Unable to close block: epoch rewards failed"},
+                {"type": "slash", "error": "Validator not bonded"},
+                {"type": "Mint"},
+                {"failed": true}
+            ]
+        }))
+        .unwrap();
+        let system = detail.system_deploys.as_ref().unwrap();
+        // The entry without a kind is dropped
+        assert_eq!(system.len(), 4);
+        assert_eq!(system[0].kind, SystemDeployKind::PreCharge);
+        assert!(!system[0].failed);
+        assert_eq!(system[0].error, None);
+        assert_eq!(system[3].kind, SystemDeployKind::Other("Mint".to_string()));
+
+        let failed = detail.failed_system_deploys();
+        assert_eq!(failed.len(), 2);
+        assert_eq!(failed[0].kind, SystemDeployKind::CloseBlock);
+        assert_eq!(
+            failed[0].error.as_deref(),
+            Some("Unable to close block: epoch rewards failed")
+        );
+        // A non-empty error marks a failure even without the flag
+        assert_eq!(failed[1].kind, SystemDeployKind::Slash);
+
+        let exported = serde_json::to_value(&detail).unwrap();
+        assert_eq!(exported["system_deploys"][1]["kind"], "CloseBlock");
+        assert_eq!(exported["system_deploys"][1]["failed"], true);
+    }
+
+    #[test]
+    fn test_system_deploys_inside_block_info() {
+        let mut info = block_info();
+        info["systemDeploys"] = json!([]);
+        let detail = BlockDetail::from_json(&json!({"blockInfo": info})).unwrap();
+        assert_eq!(detail.system_deploys, Some(Vec::new()));
+    }
+}
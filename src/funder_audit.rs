@@ -0,0 +1,501 @@
+//! Balance reconciliation for a shared funder account (`funder-audit`)
+//!
+//! Every deploy that touches the funder's vault is attributed to the key that
+//! signed it. Deploys signed by the funder are debits of the amounts they
+//! transfer plus the phlo they paid for; recognised transfers to the funder
+//! from other keys are credits. The [`Ledger`] keeps a running expectation,
+//! starting balance + credits - debits - phlo, and compares it with the
+//! balance the node reports. A gap beyond the tolerance on [`DRIFT_CHECKS`]
+//! checks in a row raises the drift alarm: money moved in a way the scan did
+//! not see, such as a transfer made by a contract this client does not
+//! recognise (see [`crate::wallet_watch`]).
+
+use crate::f1r3fly_api::BlockDetail;
+use crate::identities::{truncate_key, Identities};
+use crate::vault::DUST_FACTOR;
+use crate::wallet_watch::{Direction, WalletWatch};
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+
+/// Gap in dust between expected and reported balance that is not drift
+pub const DEFAULT_TOLERANCE: u64 = 10_000_000;
+
+/// Recent attributions kept in memory
+pub const DEFAULT_WINDOW: usize = 1000;
+
+/// Consecutive checks beyond the tolerance that raise the alarm
+///
+/// A balance read just after a block lands can be ahead of the events seen
+/// so far, so one check out of tolerance is not yet drift.
+pub const DRIFT_CHECKS: u32 = 2;
+
+/// Which way a deploy moved the funder's balance
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Flow {
+    Debit,
+    Credit,
+}
+
+/// A deploy that moved the funder's balance, and who signed it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Attribution {
+    pub block_hash: String,
+    pub block_number: i64,
+    pub deploy_id: String,
+    /// Public key that signed the deploy
+    pub deployer: String,
+    /// Name of the deployer from the identities file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub flow: Flow,
+    /// Dust transferred; 0 for an errored deploy or a term with no recognised transfer
+    pub amount: u64,
+    /// Dust paid for phlo by the funder (debits only)
+    pub phlo: u64,
+    pub errored: bool,
+}
+
+impl Attribution {
+    /// Name if known, otherwise the truncated key
+    pub fn who(&self) -> String {
+        self.name
+            .clone()
+            .unwrap_or_else(|| truncate_key(&self.deployer))
+    }
+}
+
+/// Attributions for the deploys in `block` that touch the watched funder
+pub fn attribute(
+    block: &BlockDetail,
+    funder: &WalletWatch,
+    identities: &Identities,
+) -> Vec<Attribution> {
+    let mut attributions = Vec::new();
+    for deploy in &block.deploys {
+        for found in funder.match_deploy(block, deploy) {
+            let (flow, phlo) = match found.direction {
+                Direction::Outgoing => (
+                    Flow::Debit,
+                    deploy.cost.saturating_mul(deploy.phlo_price.max(0) as u64),
+                ),
+                Direction::Incoming => (Flow::Credit, 0),
+            };
+            attributions.push(Attribution {
+                block_hash: found.block_hash,
+                block_number: found.block_number,
+                deploy_id: found.deploy_id,
+                deployer: deploy.deployer.clone(),
+                name: identities
+                    .get(&deploy.deployer)
+                    .map(|identity| identity.name.clone()),
+                flow,
+                // An errored deploy transfers nothing but still pays for phlo
+                amount: if deploy.errored {
+                    0
+                } else {
+                    found.amount.unwrap_or(0)
+                },
+                phlo,
+                errored: deploy.errored,
+            });
+        }
+    }
+    attributions
+}
+
+/// Totals for one deployer
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DeployerSpend {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub deploys: u64,
+    /// Dust sent out of the funder
+    pub debited: u64,
+    /// Dust sent to the funder
+    pub credited: u64,
+    /// Dust the funder paid for this deployer's phlo
+    pub phlo: u64,
+}
+
+impl DeployerSpend {
+    /// Dust this deployer took out of the funder, transfers and phlo
+    pub fn spent(&self) -> u64 {
+        self.debited.saturating_add(self.phlo)
+    }
+}
+
+/// Outcome of comparing the expected balance with the node's
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Check {
+    /// Within the tolerance
+    Balanced,
+    /// Out of tolerance, but not yet for [`DRIFT_CHECKS`] checks in a row
+    Unsettled,
+    /// Out of tolerance for [`DRIFT_CHECKS`] checks or more
+    Drift,
+}
+
+/// Running reconciliation of the funder's balance
+#[derive(Debug, Clone)]
+pub struct Ledger {
+    pub starting_balance: u64,
+    pub debits: u64,
+    pub credits: u64,
+    pub phlo: u64,
+    /// Balance at the last check
+    pub current: Option<u64>,
+    pub tolerance: u64,
+    /// Attributions seen so far
+    pub count: u64,
+    /// The most recent attributions, oldest first
+    pub recent: VecDeque<Attribution>,
+    pub by_deployer: BTreeMap<String, DeployerSpend>,
+    window: usize,
+    unsettled: u32,
+}
+
+impl Ledger {
+    pub fn new(starting_balance: u64, tolerance: u64, window: usize) -> Self {
+        Self {
+            starting_balance,
+            debits: 0,
+            credits: 0,
+            phlo: 0,
+            current: None,
+            tolerance,
+            count: 0,
+            recent: VecDeque::new(),
+            by_deployer: BTreeMap::new(),
+            window: window.max(1),
+            unsettled: 0,
+        }
+    }
+
+    pub fn record(&mut self, attribution: Attribution) {
+        let spend = self
+            .by_deployer
+            .entry(attribution.deployer.to_ascii_lowercase())
+            .or_default();
+        spend.name = spend.name.take().or_else(|| attribution.name.clone());
+        spend.deploys += 1;
+        spend.phlo += attribution.phlo;
+        self.phlo += attribution.phlo;
+        match attribution.flow {
+            Flow::Debit => {
+                spend.debited += attribution.amount;
+                self.debits += attribution.amount;
+            }
+            Flow::Credit => {
+                spend.credited += attribution.amount;
+                self.credits += attribution.amount;
+            }
+        }
+        self.count += 1;
+        if self.recent.len() == self.window {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(attribution);
+    }
+
+    /// Balance in dust the observed deploys account for
+    pub fn expected(&self) -> i64 {
+        self.starting_balance as i64 + self.credits as i64 - self.debits as i64 - self.phlo as i64
+    }
+
+    /// Reported minus expected balance, once a balance has been checked
+    ///
+    /// Negative drift is money that left the funder unseen.
+    pub fn drift(&self) -> Option<i64> {
+        self.current.map(|current| current as i64 - self.expected())
+    }
+
+    /// Compare `balance` reported by the node with the expectation
+    pub fn check(&mut self, balance: u64) -> Check {
+        self.current = Some(balance);
+        if self.drift().unwrap_or(0).unsigned_abs() <= self.tolerance {
+            self.unsettled = 0;
+            return Check::Balanced;
+        }
+        self.unsettled += 1;
+        if self.unsettled >= DRIFT_CHECKS {
+            Check::Drift
+        } else {
+            Check::Unsettled
+        }
+    }
+
+    /// Deployers by dust spent, largest first
+    pub fn spend_table(&self) -> Vec<(&str, &DeployerSpend)> {
+        let mut rows: Vec<_> = self
+            .by_deployer
+            .iter()
+            .map(|(key, spend)| (key.as_str(), spend))
+            .collect();
+        rows.sort_by(|a, b| b.1.spent().cmp(&a.1.spent()).then(a.0.cmp(b.0)));
+        rows
+    }
+
+    pub fn summary(&self) -> Summary {
+        Summary {
+            starting_balance: self.starting_balance,
+            debits: self.debits,
+            credits: self.credits,
+            phlo: self.phlo,
+            expected: self.expected(),
+            balance: self.current,
+            drift: self.drift(),
+            attributions: self.count,
+        }
+    }
+}
+
+/// Reconciliation totals in dust
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Summary {
+    pub starting_balance: u64,
+    pub debits: u64,
+    pub credits: u64,
+    pub phlo: u64,
+    pub expected: i64,
+    pub balance: Option<u64>,
+    pub drift: Option<i64>,
+    pub attributions: u64,
+}
+
+impl Summary {
+    /// One line with the amounts in tokens
+    pub fn line(&self) -> String {
+        let tokens = |dust: i64| dust as f64 / DUST_FACTOR as f64;
+        format!(
+            "start {} | debits {} | credits {} | phlo {} | expected {} | balance {} | drift {}",
+            tokens(self.starting_balance as i64),
+            tokens(self.debits as i64),
+            tokens(self.credits as i64),
+            tokens(self.phlo as i64),
+            tokens(self.expected),
+            self.balance
+                .map_or("-".to_string(), |b| tokens(b as i64).to_string()),
+            self.drift
+                .map_or("-".to_string(), |d| format!("{:+}", tokens(d))),
+        )
+    }
+}
+
+/// A line of `--json` output
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum AuditEvent<'a> {
+    Attribution(&'a Attribution),
+    Summary(&'a Summary),
+    Drift(&'a Summary),
+    /// A row of the final spend table
+    Deployer {
+        deployer: &'a str,
+        #[serde(flatten)]
+        spend: &'a DeployerSpend,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::f1r3fly_api::BlockDeploy;
+    use crate::key_guard::DEV_PRIVATE_KEY;
+    use crate::utils::CryptoUtils;
+    use crate::wallet_watch::WatchTarget;
+
+    const OTHER_ADDRESS: &str = "1111AtahZeefej4tvVR6ti9TJtv8yxLebvyDoX4tKnbX9DZGQCExL";
+
+    fn funder() -> (String, String) {
+        let secret_key = CryptoUtils::decode_private_key(DEV_PRIVATE_KEY).unwrap();
+        let public_key = CryptoUtils::derive_public_key(&secret_key);
+        let key = CryptoUtils::serialize_public_key(&public_key, false);
+        let address = CryptoUtils::generate_vault_address(&key).unwrap();
+        (key, address)
+    }
+
+    fn deploy(sig: &str, deployer: &str, term: String, cost: u64, errored: bool) -> BlockDeploy {
+        serde_json::from_value(serde_json::json!({
+            "sig": sig,
+            "deployer": deployer,
+            "term": term,
+            "cost": cost,
+            "phloPrice": 1,
+            "errored": errored,
+        }))
+        .unwrap()
+    }
+
+    fn block(number: i64, deploys: Vec<BlockDeploy>) -> BlockDetail {
+        let json = serde_json::json!({
+            "blockInfo": {"blockHash": format!("b{}", number), "blockNumber": number},
+        });
+        let mut block = BlockDetail::from_json(&json).unwrap();
+        block.deploys = deploys;
+        block
+    }
+
+    fn transfer(from: &str, to: &str, dust: u64) -> String {
+        crate::contract_templates::transfer_contract(from, to, dust).unwrap()
+    }
+
+    #[test]
+    fn test_attribution_of_debits_and_credits() {
+        let (key, address) = funder();
+        let watch = WalletWatch::new(&[WatchTarget::Address(address.clone())]).unwrap();
+        let identities =
+            Identities::from_csv(&format!("pubkey,name\n{},ci-funder\n", key)).unwrap();
+        let stranger = "04".to_string() + &"ab".repeat(64);
+        let fixture = block(
+            7,
+            vec![
+                deploy(
+                    "out",
+                    &key,
+                    transfer(&address, OTHER_ADDRESS, 500),
+                    40,
+                    false,
+                ),
+                deploy(
+                    "failed",
+                    &key,
+                    transfer(&address, OTHER_ADDRESS, 900),
+                    30,
+                    true,
+                ),
+                deploy(
+                    "in",
+                    &stranger,
+                    transfer(OTHER_ADDRESS, &address, 200),
+                    50,
+                    false,
+                ),
+                deploy(
+                    "unrelated",
+                    &stranger,
+                    "new x in { x!(1) }".to_string(),
+                    5,
+                    false,
+                ),
+            ],
+        );
+
+        let found = attribute(&fixture, &watch, &identities);
+        let summary: Vec<_> = found
+            .iter()
+            .map(|a| (a.deploy_id.as_str(), a.flow, a.amount, a.phlo))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("out", Flow::Debit, 500, 40),
+                ("failed", Flow::Debit, 0, 30),
+                ("in", Flow::Credit, 200, 0),
+            ]
+        );
+        assert_eq!(found[0].who(), "ci-funder");
+        assert_eq!(found[2].name, None);
+        assert_eq!(found[2].who(), truncate_key(&stranger));
+    }
+
+    #[test]
+    fn test_reconciliation_balances_observed_flows() {
+        let (key, address) = funder();
+        let watch = WalletWatch::new(&[WatchTarget::PublicKey(key.clone())]).unwrap();
+        let mut ledger = Ledger::new(10_000, 5, DEFAULT_WINDOW);
+        let blocks = [
+            block(
+                1,
+                vec![deploy(
+                    "a",
+                    &key,
+                    transfer(&address, OTHER_ADDRESS, 1000),
+                    20,
+                    false,
+                )],
+            ),
+            block(
+                2,
+                vec![deploy(
+                    "b",
+                    "04ff",
+                    transfer(OTHER_ADDRESS, &address, 300),
+                    9,
+                    false,
+                )],
+            ),
+        ];
+        for block in &blocks {
+            for attribution in attribute(block, &watch, &Identities::default()) {
+                ledger.record(attribution);
+            }
+        }
+        assert_eq!(ledger.expected(), 10_000 - 1000 - 20 + 300);
+        assert_eq!(ledger.check(9_280), Check::Balanced);
+        // Within the tolerance either way
+        assert_eq!(ledger.check(9_284), Check::Balanced);
+        assert_eq!(ledger.drift(), Some(4));
+
+        let table = ledger.spend_table();
+        assert_eq!(table.len(), 2);
+        assert_eq!(table[0].0, key);
+        assert_eq!(table[0].1.spent(), 1020);
+        assert_eq!(table[1].1.credited, 300);
+    }
+
+    #[test]
+    fn test_unobserved_debit_raises_drift_alarm() {
+        let mut ledger = Ledger::new(10_000, 5, DEFAULT_WINDOW);
+        assert_eq!(ledger.check(10_000), Check::Balanced);
+
+        // 2500 dust leaves through a contract the scan cannot see
+        assert_eq!(ledger.check(7_500), Check::Unsettled);
+        assert_eq!(ledger.check(7_500), Check::Drift);
+        assert_eq!(ledger.check(7_500), Check::Drift);
+        assert_eq!(ledger.drift(), Some(-2500));
+        let summary = ledger.summary();
+        assert_eq!(summary.drift, Some(-2500));
+        assert!(
+            summary.line().ends_with("drift -0.000025"),
+            "{}",
+            summary.line()
+        );
+
+        // A one-off gap that closes again is not drift
+        let mut ledger = Ledger::new(10_000, 5, DEFAULT_WINDOW);
+        assert_eq!(ledger.check(9_000), Check::Unsettled);
+        assert_eq!(ledger.check(10_000), Check::Balanced);
+        assert_eq!(ledger.check(9_000), Check::Unsettled);
+    }
+
+    #[test]
+    fn test_recent_window_is_bounded() {
+        let mut ledger = Ledger::new(0, 0, 3);
+        for i in 0..5 {
+            ledger.record(Attribution {
+                block_hash: format!("b{}", i),
+                block_number: i,
+                deploy_id: format!("d{}", i),
+                deployer: "04AA".to_string(),
+                name: None,
+                flow: Flow::Credit,
+                amount: 10,
+                phlo: 0,
+                errored: false,
+            });
+        }
+        let ids: Vec<_> = ledger.recent.iter().map(|a| a.deploy_id.as_str()).collect();
+        assert_eq!(ids, ["d2", "d3", "d4"]);
+        assert_eq!(ledger.count, 5);
+        assert_eq!(ledger.credits, 50);
+        // Deployers are keyed case-insensitively
+        assert_eq!(ledger.by_deployer.len(), 1);
+        assert_eq!(ledger.by_deployer["04aa"].deploys, 5);
+
+        let json = serde_json::to_value(AuditEvent::Attribution(&ledger.recent[0])).unwrap();
+        assert_eq!(json["type"], "attribution");
+        assert_eq!(json["flow"], "credit");
+        assert!(json.get("name").is_none());
+    }
+}
@@ -1,10 +1,13 @@
 //! Block queries, finalization checks, and tip sampling
 
 use super::F1r3flyApi;
-use f1r3fly_models::casper::v1::deploy_service_client::DeployServiceClient;
+use crate::error::NodeCliError;
+use crate::maintenance::MaintenanceWait;
+use crate::utils::Backoff;
 use f1r3fly_models::casper::v1::is_finalized_response::Message as IsFinalizedResponseMessage;
 use f1r3fly_models::casper::{BlocksQuery, BlocksQueryByHeight, IsFinalizedQuery, LightBlockInfo};
 use std::sync::atomic::Ordering;
+use std::time::Duration;
 
 const BLOCK_SAMPLE_DEPTH: u32 = 8;
 const TIP_SAMPLE_ATTEMPTS: usize = 2;
@@ -12,18 +15,36 @@ const TIP_SAMPLE_TIMEOUT_SECS: u64 = 2;
 const TIP_SAMPLE_DELAY_MS: u64 = 50;
 
 impl<'a> F1r3flyApi<'a> {
+    /// Poll `isFinalized` until the block is finalized or `max_attempts` polls
+    /// have been made, `retry_delay` apart
     pub async fn is_finalized(
         &self,
         block_hash: &str,
         max_attempts: u32,
-        retry_delay_sec: u64,
+        retry_delay: Duration,
     ) -> Result<bool, Box<dyn std::error::Error>> {
+        self.is_finalized_with_backoff(block_hash, max_attempts, &Backoff::fixed(retry_delay))
+            .await
+    }
+
+    /// [`is_finalized`](Self::is_finalized) with delays from `backoff`
+    ///
+    /// Polls answered with maintenance mode are spaced further apart and do
+    /// not count toward `max_attempts`.
+    pub async fn is_finalized_with_backoff(
+        &self,
+        block_hash: &str,
+        max_attempts: u32,
+        backoff: &Backoff,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        let call = self.grpc_call("is_finalized");
+        let mut maintenance = MaintenanceWait::new();
         let mut attempts = 0;
 
         loop {
             attempts += 1;
 
-            let mut client = DeployServiceClient::connect(self.grpc_url()).await?;
+            let mut client = self.deploy_client().await.map_err(|e| call.fail(e))?;
 
             let query = IsFinalizedQuery {
                 hash: block_hash.to_string(),
@@ -31,10 +52,11 @@ impl<'a> F1r3flyApi<'a> {
 
             match client.is_finalized(query).await {
                 Ok(response) => {
+                    maintenance.on_success();
                     if let Some(message) = &response.get_ref().message {
                         match message {
                             IsFinalizedResponseMessage::Error(_) => {
-                                return Err("Error checking finalization status".into());
+                                return Err(call.fail("Error checking finalization status").into());
                             }
                             IsFinalizedResponseMessage::IsFinalized(is_finalized) => {
                                 if *is_finalized {
@@ -44,9 +66,20 @@ impl<'a> F1r3flyApi<'a> {
                         }
                     }
                 }
-                Err(_) => {
+                Err(status) => {
+                    let err = NodeCliError::from(status.clone());
+                    if let Some(delay) = maintenance.on_error(&err, backoff.delay(attempts)) {
+                        attempts -= 1;
+                        tokio::time::sleep(delay).await;
+                        continue;
+                    }
                     if attempts >= max_attempts {
-                        return Err("Failed to connect to node after maximum attempts".into());
+                        return Err(call
+                            .fail(format!(
+                                "Failed to connect to node after maximum attempts: {}",
+                                status
+                            ))
+                            .into());
                     }
                 }
             }
@@ -55,7 +88,7 @@ impl<'a> F1r3flyApi<'a> {
                 return Ok(false);
             }
 
-            tokio::time::sleep(tokio::time::Duration::from_secs(retry_delay_sec)).await;
+            backoff.wait(attempts - 1).await;
         }
     }
 
@@ -65,22 +98,27 @@ impl<'a> F1r3flyApi<'a> {
     ) -> Result<Vec<LightBlockInfo>, Box<dyn std::error::Error>> {
         use f1r3fly_models::casper::v1::block_info_response::Message;
 
-        let mut client = DeployServiceClient::connect(self.grpc_url()).await?;
+        let call = self.grpc_call("show_main_chain");
+        let mut client = self.deploy_client().await.map_err(|e| call.fail(e))?;
 
         let query = BlocksQuery {
             depth: depth as i32,
         };
 
-        let mut stream = client.show_main_chain(query).await?.into_inner();
+        let mut stream = client
+            .show_main_chain(query)
+            .await
+            .map_err(|e| call.fail(e))?
+            .into_inner();
 
         let mut blocks = Vec::new();
-        while let Some(response) = stream.message().await? {
+        while let Some(response) = stream.message().await.map_err(|e| call.fail(e))? {
             if let Some(message) = response.message {
                 match message {
                     Message::Error(service_error) => {
-                        return Err(
-                            format!("gRPC Error: {}", service_error.messages.join("; ")).into()
-                        );
+                        return Err(call
+                            .fail(format!("gRPC Error: {}", service_error.messages.join("; ")))
+                            .into());
                     }
                     Message::BlockInfo(block_info) => {
                         blocks.push(block_info);
@@ -99,23 +137,28 @@ impl<'a> F1r3flyApi<'a> {
     ) -> Result<Vec<LightBlockInfo>, Box<dyn std::error::Error>> {
         use f1r3fly_models::casper::v1::block_info_response::Message;
 
-        let mut client = DeployServiceClient::connect(self.grpc_url()).await?;
+        let call = self.grpc_call("get_blocks_by_heights");
+        let mut client = self.deploy_client().await.map_err(|e| call.fail(e))?;
 
         let query = BlocksQueryByHeight {
             start_block_number,
             end_block_number,
         };
 
-        let mut stream = client.get_blocks_by_heights(query).await?.into_inner();
+        let mut stream = client
+            .get_blocks_by_heights(query)
+            .await
+            .map_err(|e| call.fail(e))?
+            .into_inner();
 
         let mut blocks = Vec::new();
-        while let Some(response) = stream.message().await? {
+        while let Some(response) = stream.message().await.map_err(|e| call.fail(e))? {
             if let Some(message) = response.message {
                 match message {
                     Message::Error(service_error) => {
-                        return Err(
-                            format!("gRPC Error: {}", service_error.messages.join("; ")).into()
-                        );
+                        return Err(call
+                            .fail(format!("gRPC Error: {}", service_error.messages.join("; ")))
+                            .into());
                     }
                     Message::BlockInfo(block_info) => {
                         blocks.push(block_info);
@@ -152,6 +195,7 @@ impl<'a> F1r3flyApi<'a> {
     }
 
     async fn get_current_block_number_sampled(&self) -> Result<i64, Box<dyn std::error::Error>> {
+        let call = self.grpc_call("show_main_chain");
         let mut best_tip: Option<i64> = None;
         let mut min_tip: Option<i64> = None;
         let mut max_tip: Option<i64> = None;
@@ -206,7 +250,9 @@ impl<'a> F1r3flyApi<'a> {
             }
             Ok(best_tip)
         } else {
-            Err("Failed to sample current block number from main chain".into())
+            Err(call
+                .fail("Failed to sample current block number from main chain")
+                .into())
         }
     }
 }
@@ -3,19 +3,19 @@
 use super::F1r3flyApi;
 use blake2::{Blake2b, Digest};
 use f1r3fly_models::casper::v1::deploy_response::Message as DeployResponseMessage;
-use f1r3fly_models::casper::v1::deploy_service_client::DeployServiceClient;
 use f1r3fly_models::casper::v1::propose_response::Message as ProposeResponseMessage;
-use f1r3fly_models::casper::v1::propose_service_client::ProposeServiceClient;
 use f1r3fly_models::casper::{DeployDataProto, ProposeQuery};
 use f1r3fly_models::ByteString;
 use prost::Message;
-use secp256k1::{Message as Secp256k1Message, Secp256k1};
+use secp256k1::Message as Secp256k1Message;
 use std::time::{Instant, SystemTime, UNIX_EPOCH};
 use typenum::U32;
 
-use crate::f1r3fly_api::ProposeResult;
+use crate::error::{DeployRejection, NodeCliError};
+use crate::f1r3fly_api::{ProposeResult, SubmittedDeploy};
+use crate::utils::CryptoUtils;
 
-const DEPLOY_VALIDITY_WINDOW_BLOCKS: i64 = 50;
+use super::DEPLOY_VALIDITY_WINDOW_BLOCKS;
 
 impl<'a> F1r3flyApi<'a> {
     pub async fn deploy(
@@ -25,10 +25,49 @@ impl<'a> F1r3flyApi<'a> {
         language: &str,
         expiration_timestamp: i64,
     ) -> Result<String, Box<dyn std::error::Error>> {
+        self.submit_deploy(
+            rho_code,
+            use_bigger_phlo_price,
+            language,
+            expiration_timestamp,
+        )
+        .await
+        .map(|submitted| submitted.deploy_id)
+    }
+
+    /// Like [`deploy`](Self::deploy), also returning the valid-after block number
+    /// the deploy was signed with so callers can tell when it expires
+    pub async fn submit_deploy(
+        &self,
+        rho_code: &str,
+        use_bigger_phlo_price: bool,
+        language: &str,
+        expiration_timestamp: i64,
+    ) -> Result<SubmittedDeploy, Box<dyn std::error::Error>> {
+        self.submit_deploy_with_phlo_price(
+            rho_code,
+            use_bigger_phlo_price,
+            language,
+            expiration_timestamp,
+            super::DEFAULT_PHLO_PRICE,
+        )
+        .await
+    }
+
+    /// Like [`submit_deploy`](Self::submit_deploy), signed with `phlo_price`
+    /// instead of the default price of 1
+    pub async fn submit_deploy_with_phlo_price(
+        &self,
+        rho_code: &str,
+        use_bigger_phlo_price: bool,
+        language: &str,
+        expiration_timestamp: i64,
+        phlo_price: i64,
+    ) -> Result<SubmittedDeploy, Box<dyn std::error::Error>> {
         let phlo_limit: i64 = if use_bigger_phlo_price {
-            5_000_000_000
+            super::BIGGER_PHLO_LIMIT
         } else {
-            50_000
+            super::DEFAULT_PHLO_LIMIT
         };
 
         let tip_lookup_start = Instant::now();
@@ -41,11 +80,11 @@ impl<'a> F1r3flyApi<'a> {
                     window = DEPLOY_VALIDITY_WINDOW_BLOCKS,
                     "Setting validity window"
                 );
-                block_num
+                Some(block_num)
             }
             Err(e) => {
                 tracing::warn!("Could not get current block number ({}), using VABN=0", e);
-                0
+                None
             }
         };
         tracing::debug!(elapsed = ?tip_lookup_start.elapsed(), "Tip selection");
@@ -57,61 +96,70 @@ impl<'a> F1r3flyApi<'a> {
         let deployment = self.build_deploy_msg(
             rho_code.to_string(),
             phlo_limit,
+            phlo_price,
             language.to_string(),
-            current_block,
+            current_block.unwrap_or(0),
             expiration_timestamp,
             None,
         );
 
+        let call = self.grpc_call("do_deploy");
         let connect_start = Instant::now();
-        let mut deploy_service_client = DeployServiceClient::connect(self.grpc_url()).await?;
+        let mut deploy_service_client = self.deploy_client().await.map_err(|e| call.fail(e))?;
         tracing::debug!(elapsed = ?connect_start.elapsed(), "gRPC connect");
 
         let do_deploy_start = Instant::now();
-        let deploy_response = deploy_service_client.do_deploy(deployment).await?;
+        let deploy_response = deploy_service_client
+            .do_deploy(deployment)
+            .await
+            .map_err(|status| call.fail(Self::deploy_status_error(status)))?;
         tracing::debug!(elapsed = ?do_deploy_start.elapsed(), "do_deploy RPC");
 
         let deploy_message = deploy_response
             .get_ref()
             .message
             .as_ref()
-            .ok_or("Deploy result not found")?;
+            .ok_or_else(|| call.fail("Deploy result not found"))?;
 
         match deploy_message {
-            DeployResponseMessage::Error(service_error) => Err(service_error.clone().into()),
-            DeployResponseMessage::Result(result) => Self::extract_deploy_id(result),
+            DeployResponseMessage::Error(service_error) => Err(call
+                .fail(NodeCliError::deploy_rejected(
+                    &service_error.messages.join("; "),
+                ))
+                .into()),
+            DeployResponseMessage::Result(result) => Ok(SubmittedDeploy {
+                deploy_id: Self::extract_deploy_id(result).map_err(|e| call.fail(e))?,
+                valid_after_block_number: current_block,
+            }),
         }
     }
 
     pub async fn propose(&self) -> Result<ProposeResult, Box<dyn std::error::Error>> {
-        let mut propose_client = ProposeServiceClient::connect(self.grpc_url()).await?;
+        let call = self.grpc_call("propose");
+        let mut propose_client = self.propose_client().await.map_err(|e| call.fail(e))?;
 
         let propose_response = propose_client
             .propose(ProposeQuery { is_async: false })
-            .await?
+            .await
+            .map_err(|e| call.fail(e))?
             .into_inner();
 
-        let message = propose_response.message.ok_or("Missing propose response")?;
+        let message = propose_response
+            .message
+            .ok_or_else(|| call.fail("Missing propose response"))?;
 
         match message {
-            ProposeResponseMessage::Result(block_hash) => {
-                if let Some(hash) = block_hash
-                    .strip_prefix("Success! Block ")
-                    .and_then(|s| s.strip_suffix(" created and added."))
-                {
-                    Ok(ProposeResult::Proposed(hash.to_string()))
-                } else if Self::is_recoverable_propose_error(&block_hash) {
-                    Ok(ProposeResult::Skipped(block_hash))
-                } else {
-                    Ok(ProposeResult::Proposed(block_hash))
-                }
+            ProposeResponseMessage::Result(raw_message) => {
+                Ok(Self::parse_propose_message(&raw_message).map_err(|e| call.fail(e))?)
             }
             ProposeResponseMessage::Error(error) => {
                 let error_message = error.messages.join("; ");
                 if Self::is_recoverable_propose_error(&error_message) {
                     Ok(ProposeResult::Skipped(error_message))
                 } else {
-                    Err(format!("Propose error: {:?}", error).into())
+                    Err(call
+                        .fail(NodeCliError::propose_rejected(&error_message))
+                        .into())
                 }
             }
         }
@@ -168,24 +216,43 @@ impl<'a> F1r3flyApi<'a> {
         let deployment = self.build_deploy_msg(
             rho_code.to_string(),
             phlo_limit,
+            super::DEFAULT_PHLO_PRICE,
             language.to_string(),
             current_block,
             expiration_timestamp,
             timestamp_override,
         );
 
-        let mut client = DeployServiceClient::connect(self.grpc_url()).await?;
-        let deploy_response = client.do_deploy(deployment).await?;
+        let call = self.grpc_call("do_deploy");
+        let mut client = self.deploy_client().await.map_err(|e| call.fail(e))?;
+        let deploy_response = client
+            .do_deploy(deployment)
+            .await
+            .map_err(|status| call.fail(Self::deploy_status_error(status)))?;
 
         let deploy_message = deploy_response
             .get_ref()
             .message
             .as_ref()
-            .ok_or("Deploy result not found")?;
+            .ok_or_else(|| call.fail("Deploy result not found"))?;
 
         match deploy_message {
-            DeployResponseMessage::Error(service_error) => Err(service_error.clone().into()),
-            DeployResponseMessage::Result(result) => Self::extract_deploy_id(result),
+            DeployResponseMessage::Error(service_error) => Err(call
+                .fail(NodeCliError::deploy_rejected(
+                    &service_error.messages.join("; "),
+                ))
+                .into()),
+            DeployResponseMessage::Result(result) => {
+                Ok(Self::extract_deploy_id(result).map_err(|e| call.fail(e))?)
+            }
+        }
+    }
+
+    /// A rejected deploy can also surface as a gRPC status; keep other statuses as they are
+    fn deploy_status_error(status: tonic::Status) -> Box<dyn std::error::Error> {
+        match DeployRejection::classify(status.message()) {
+            DeployRejection::Unknown(_) => status.into(),
+            _ => NodeCliError::deploy_rejected(status.message()).into(),
         }
     }
 
@@ -208,6 +275,29 @@ impl<'a> F1r3flyApi<'a> {
         }
     }
 
+    /// Interpret a successful propose reply.
+    ///
+    /// Older nodes return the bare block hash, newer ones wrap it in a sentence
+    /// such as `Success! Block <hash> created and added.`. Recoverable skip
+    /// messages become `Skipped`; anything else without a hash is an error that
+    /// still carries the raw reply.
+    pub(crate) fn parse_propose_message(raw_message: &str) -> Result<ProposeResult, String> {
+        if Self::is_recoverable_propose_error(raw_message) {
+            return Ok(ProposeResult::Skipped(raw_message.to_string()));
+        }
+
+        match extract_block_hash(raw_message) {
+            Some(block_hash) => Ok(ProposeResult::Proposed {
+                block_hash,
+                raw_message: raw_message.to_string(),
+            }),
+            None => Err(format!(
+                "Propose response did not contain a block hash: {}",
+                raw_message.trim()
+            )),
+        }
+    }
+
     fn is_recoverable_propose_error(error_message: &str) -> bool {
         let normalized = error_message.to_ascii_lowercase();
         const RECOVERABLE_PATTERNS: [&str; 6] = [
@@ -225,6 +315,7 @@ impl<'a> F1r3flyApi<'a> {
         &self,
         code: String,
         phlo_limit: i64,
+        phlo_price: i64,
         language: String,
         valid_after_block_number: i64,
         expiration_timestamp: i64,
@@ -240,7 +331,7 @@ impl<'a> F1r3flyApi<'a> {
         let projection = DeployDataProto {
             term: code.clone(),
             timestamp,
-            phlo_price: 1,
+            phlo_price,
             phlo_limit,
             valid_after_block_number,
             shard_id: "root".into(),
@@ -254,17 +345,15 @@ impl<'a> F1r3flyApi<'a> {
         let serialized = projection.encode_to_vec();
         let digest = blake2b_256_hash(&serialized);
 
-        let secp = Secp256k1::new();
         let message = Secp256k1Message::from_digest(digest.into());
-        let signature = secp.sign_ecdsa(message, &self.signing_key);
+        let signature = CryptoUtils::secp().sign_ecdsa(message, &self.signing_key);
         let sig_bytes = signature.serialize_der().to_vec();
-        let public_key = self.signing_key.public_key(&secp);
-        let pub_key_bytes = public_key.serialize_uncompressed().to_vec();
+        let pub_key_bytes = self.public_key.serialize_uncompressed().to_vec();
 
         DeployDataProto {
             term: code,
             timestamp,
-            phlo_price: 1,
+            phlo_price,
             phlo_limit,
             valid_after_block_number,
             shard_id: "root".into(),
@@ -277,6 +366,30 @@ impl<'a> F1r3flyApi<'a> {
     }
 }
 
+/// Length of a hex-encoded block hash (Blake2b-256)
+const BLOCK_HASH_HEX_LEN: usize = 64;
+
+/// Find the first standalone 64-character hex run in `text`.
+///
+/// Longer hex runs (deploy signatures, public keys) are skipped so they are
+/// never mistaken for a block hash.
+pub(crate) fn extract_block_hash(text: &str) -> Option<String> {
+    let mut run_start: Option<usize> = None;
+    for (i, c) in text
+        .char_indices()
+        .chain(std::iter::once((text.len(), ' ')))
+    {
+        if c.is_ascii_hexdigit() {
+            run_start.get_or_insert(i);
+        } else if let Some(start) = run_start.take() {
+            if i - start == BLOCK_HASH_HEX_LEN {
+                return Some(text[start..i].to_ascii_lowercase());
+            }
+        }
+    }
+    None
+}
+
 fn blake2b_256_hash(data: &[u8]) -> [u8; 32] {
     let mut blake = Blake2b::<U32>::new();
     blake.update(data);
@@ -285,3 +398,75 @@ fn blake2b_256_hash(data: &[u8]) -> [u8; 32] {
     result.copy_from_slice(&hash);
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASH: &str = "9f3c1a0b2d4e5f60718293a4b5c6d7e8f9a0b1c2d3e4f5061728394a5b6c7d8e";
+
+    #[test]
+    fn test_propose_bare_hash() {
+        let result = F1r3flyApi::parse_propose_message(HASH).unwrap();
+        assert_eq!(
+            result,
+            ProposeResult::Proposed {
+                block_hash: HASH.to_string(),
+                raw_message: HASH.to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_propose_success_sentence() {
+        let raw = format!("Success! Block {} created and added.", HASH);
+        match F1r3flyApi::parse_propose_message(&raw).unwrap() {
+            ProposeResult::Proposed {
+                block_hash,
+                raw_message,
+            } => {
+                assert_eq!(block_hash, HASH);
+                assert_eq!(raw_message, raw);
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_propose_multiline_reply() {
+        let raw = format!(
+            "Success!\nBlock {}\ncreated and added.\n",
+            HASH.to_uppercase()
+        );
+        match F1r3flyApi::parse_propose_message(&raw).unwrap() {
+            ProposeResult::Proposed { block_hash, .. } => assert_eq!(block_hash, HASH),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_propose_ignores_longer_hex_runs() {
+        let deployer = "04".to_string() + &"ab".repeat(64);
+        let raw = format!("Deployer {} proposed block {}", deployer, HASH);
+        match F1r3flyApi::parse_propose_message(&raw).unwrap() {
+            ProposeResult::Proposed { block_hash, .. } => assert_eq!(block_hash, HASH),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_propose_recoverable_message_is_skipped() {
+        let raw = "Must wait for more blocks from other validators";
+        assert_eq!(
+            F1r3flyApi::parse_propose_message(raw).unwrap(),
+            ProposeResult::Skipped(raw.to_string())
+        );
+    }
+
+    #[test]
+    fn test_propose_without_hash_is_error() {
+        let err = F1r3flyApi::parse_propose_message("Success! Block created.").unwrap_err();
+        assert!(err.contains("did not contain a block hash"));
+        assert!(err.contains("Success! Block created."));
+    }
+}
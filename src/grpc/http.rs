@@ -1,7 +1,11 @@
-//! HTTP-based methods on F1r3flyApi (deploy lookup, deploy detail)
+//! HTTP-based methods on F1r3flyApi (deploy lookup, deploy detail, block summary,
+//! node status, tip height, explore-deploy)
 
 use super::F1r3flyApi;
-use crate::f1r3fly_api::DeployDetail;
+use crate::error::{NodeCall, NodeCliError};
+use crate::f1r3fly_api::{
+    BlockDetail, BlockSummary, DeployDetail, ExploreDeployResponse, NodeStatus,
+};
 
 impl<'a> F1r3flyApi<'a> {
     pub async fn get_deploy_block_hash(
@@ -13,12 +17,14 @@ impl<'a> F1r3flyApi<'a> {
             "http://{}:{}/api/deploy/{}",
             self.node_host, http_port, deploy_id
         );
+        let call = NodeCall::http(&url);
         let client = reqwest::Client::new();
 
         match client.get(&url).send().await {
             Ok(response) => {
                 if response.status().is_success() {
-                    let deploy_info: serde_json::Value = response.json().await?;
+                    let deploy_info: serde_json::Value =
+                        response.json().await.map_err(|e| call.fail(e))?;
                     if let Some(block_hash) = deploy_info.get("blockHash").and_then(|v| v.as_str())
                     {
                         Ok(Some(block_hash.to_string()))
@@ -36,18 +42,25 @@ impl<'a> F1r3flyApi<'a> {
 
                     if error_body.contains("Couldn't find block containing deploy with id:") {
                         Ok(None)
+                    } else if let Some(detail) =
+                        crate::maintenance::from_http(status.as_u16(), &error_body)
+                    {
+                        Err(call.fail(NodeCliError::node_in_maintenance(&detail)).into())
                     } else {
-                        Err(format!(
-                            "HTTP error {}: {} - Response: {}",
-                            status,
-                            status.canonical_reason().unwrap_or("Unknown"),
-                            error_body
-                        )
-                        .into())
+                        Err(call
+                            .fail(NodeCliError::network_http_error(
+                                status.as_u16(),
+                                &format!(
+                                    "{} - Response: {}",
+                                    status.canonical_reason().unwrap_or("Unknown"),
+                                    error_body
+                                ),
+                            ))
+                            .into())
                     }
                 }
             }
-            Err(e) => Err(format!("Network error: {}", e).into()),
+            Err(e) => Err(call.fail(e).into()),
         }
     }
 
@@ -60,8 +73,9 @@ impl<'a> F1r3flyApi<'a> {
             "http://{}:{}/api/deploy/{}",
             self.node_host, http_port, deploy_id
         );
+        let call = NodeCall::http(&url);
         let client = reqwest::Client::new();
-        let response = client.get(&url).send().await?;
+        let response = client.get(&url).send().await.map_err(|e| call.fail(e))?;
 
         if !response.status().is_success() {
             return Ok(None);
@@ -69,7 +83,10 @@ impl<'a> F1r3flyApi<'a> {
 
         // None is reserved for 404 (handled above). A JSON parse error is a real
         // problem — schema mismatch, malformed response, etc. — and must surface.
-        let detail = response.json::<DeployDetail>().await?;
+        let detail = response
+            .json::<DeployDetail>()
+            .await
+            .map_err(|e| call.fail(e))?;
         Ok(Some(detail))
     }
 
@@ -84,14 +101,182 @@ impl<'a> F1r3flyApi<'a> {
             "http://{}:{}/api/deploy/{}",
             self.node_host, http_port, deploy_id
         );
+        let call = NodeCall::http(&url);
+        let client = reqwest::Client::new();
+        let response = client.get(&url).send().await.map_err(|e| call.fail(e))?;
+
+        if !response.status().is_success() {
+            return Ok(None);
+        }
+
+        let json: serde_json::Value = response.json().await.map_err(|e| call.fail(e))?;
+        Ok(Some(json))
+    }
+
+    /// Get a block's header fields, including pre/post state hashes.
+    /// Returns `None` when the node does not know the block.
+    pub async fn get_block_summary(
+        &self,
+        block_hash: &str,
+        http_port: u16,
+    ) -> Result<Option<BlockSummary>, Box<dyn std::error::Error>> {
+        let url = format!(
+            "http://{}:{}/api/block/{}",
+            self.node_host, http_port, block_hash
+        );
+        let call = NodeCall::http(&url);
+        let client = reqwest::Client::new();
+        let response = client.get(&url).send().await.map_err(|e| call.fail(e))?;
+
+        if !response.status().is_success() {
+            return Ok(None);
+        }
+
+        let json: serde_json::Value = response.json().await.map_err(|e| call.fail(e))?;
+        BlockSummary::from_json(&json)
+            .map(Some)
+            .ok_or_else(|| call.fail("Block response is missing blockHash").into())
+    }
+
+    /// Get a block with its deploys.
+    /// Returns `None` when the node does not know the block.
+    pub async fn get_block_detail(
+        &self,
+        block_hash: &str,
+        http_port: u16,
+    ) -> Result<Option<BlockDetail>, Box<dyn std::error::Error>> {
+        let url = format!(
+            "http://{}:{}/api/block/{}",
+            self.node_host, http_port, block_hash
+        );
+        let call = NodeCall::http(&url);
+        let client = reqwest::Client::new();
+        let response = client.get(&url).send().await.map_err(|e| call.fail(e))?;
+
+        if !response.status().is_success() {
+            return Ok(None);
+        }
+
+        let json: serde_json::Value = response.json().await.map_err(|e| call.fail(e))?;
+        Ok(Some(
+            BlockDetail::from_json(&json).map_err(|e| call.fail(e))?,
+        ))
+    }
+
+    /// Get the last finalized block's header fields.
+    /// Returns `None` when the node does not answer with a block.
+    pub async fn get_last_finalized_block(
+        &self,
+        http_port: u16,
+    ) -> Result<Option<BlockSummary>, Box<dyn std::error::Error>> {
+        Ok(self
+            .get_last_finalized_block_json(http_port)
+            .await?
+            .as_ref()
+            .and_then(BlockSummary::from_json))
+    }
+
+    /// Get `/api/last-finalized-block` as the node returned it, for fields
+    /// [`BlockSummary`] leaves out such as `faultTolerance`.
+    /// Returns `None` when the node does not answer with a block.
+    pub async fn get_last_finalized_block_json(
+        &self,
+        http_port: u16,
+    ) -> Result<Option<serde_json::Value>, Box<dyn std::error::Error>> {
+        let url = format!(
+            "http://{}:{}/api/last-finalized-block",
+            self.node_host, http_port
+        );
+        let call = NodeCall::http(&url);
         let client = reqwest::Client::new();
-        let response = client.get(&url).send().await?;
+        let response = client.get(&url).send().await.map_err(|e| call.fail(e))?;
 
         if !response.status().is_success() {
             return Ok(None);
         }
 
-        let json: serde_json::Value = response.json().await?;
+        let json: serde_json::Value = response.json().await.map_err(|e| call.fail(e))?;
         Ok(Some(json))
     }
+
+    /// Get the node's `/api/status`.
+    /// Returns `None` when the node does not answer with a status.
+    pub async fn get_node_status(
+        &self,
+        http_port: u16,
+    ) -> Result<Option<NodeStatus>, Box<dyn std::error::Error>> {
+        let url = format!("http://{}:{}/api/status", self.node_host, http_port);
+        let call = NodeCall::http(&url);
+        let client = reqwest::Client::new();
+        let response = client.get(&url).send().await.map_err(|e| call.fail(e))?;
+
+        if !response.status().is_success() {
+            return Ok(None);
+        }
+
+        Ok(response.json().await.ok())
+    }
+
+    /// Get the block number of the chain tip from `/api/blocks/1`.
+    /// Returns `None` when the node answers without a block.
+    pub async fn get_tip_block_number(
+        &self,
+        http_port: u16,
+    ) -> Result<Option<i64>, Box<dyn std::error::Error>> {
+        let url = format!("http://{}:{}/api/blocks/1", self.node_host, http_port);
+        let call = NodeCall::http(&url);
+        let client = reqwest::Client::new();
+        let response = client.get(&url).send().await.map_err(|e| call.fail(e))?;
+
+        if !response.status().is_success() {
+            return Ok(None);
+        }
+
+        // Entries are either flat LightBlockInfo or wrapped as {"blockInfo": {...}}
+        let json: serde_json::Value = response.json().await.map_err(|e| call.fail(e))?;
+        Ok(json.as_array().and_then(|blocks| {
+            blocks
+                .iter()
+                .filter_map(|b| b.get("blockInfo").unwrap_or(b).get("blockNumber")?.as_i64())
+                .max()
+        }))
+    }
+
+    /// Run an exploratory deploy over HTTP, at `block_hash` or the node's latest state
+    ///
+    /// The reply is returned even when the term sent nothing back, for the
+    /// caller to tell apart from a failed call. A failure status or a reply
+    /// without an `expr` list is an `UnexpectedExploreResponse` error.
+    pub async fn explore_deploy_http(
+        &self,
+        rho_code: &str,
+        block_hash: Option<&str>,
+        http_port: u16,
+    ) -> Result<ExploreDeployResponse, Box<dyn std::error::Error>> {
+        let client = reqwest::Client::new();
+        let (url, request) = match block_hash {
+            Some(hash) => {
+                let url = format!(
+                    "http://{}:{}/api/explore-deploy-by-block-hash",
+                    self.node_host, http_port
+                );
+                let body = serde_json::json!({
+                    "term": rho_code,
+                    "blockHash": hash,
+                    "usePreStateHash": false
+                });
+                (url.clone(), client.post(&url).json(&body))
+            }
+            None => {
+                let url = format!("http://{}:{}/api/explore-deploy", self.node_host, http_port);
+                (url.clone(), client.post(&url).body(rho_code.to_string()))
+            }
+        };
+        let call = NodeCall::http(&url);
+        let response = request.send().await.map_err(|e| call.fail(e))?;
+        let status = response.status().as_u16();
+        let text = response.text().await.map_err(|e| call.fail(e))?;
+        let parsed = ExploreDeployResponse::parse(status, &text).map_err(|e| call.fail(e))?;
+        Ok(parsed)
+    }
 }
@@ -0,0 +1,236 @@
+//! Waiting for a deploy to be included in a block
+//!
+//! A deploy can only be included while the chain tip is within its validity
+//! window (valid-after block number plus the node's deploy lifespan). During a
+//! finalization stall the window can close before the deploy is picked up, and
+//! the deploy is then silently dropped. Polling also tracks the tip height so
+//! the wait stops as soon as that happens instead of running out the timeout.
+//!
+//! A node in maintenance mode is waited out (see [`crate::maintenance`]).
+
+use super::F1r3flyApi;
+use crate::error::NodeCliError;
+use crate::f1r3fly_api::{DeployExpiry, InclusionOutcome};
+use crate::maintenance::MaintenanceWait;
+use crate::utils::Backoff;
+
+impl<'a> F1r3flyApi<'a> {
+    /// Poll `/api/deploy/{id}` until the deploy is in a block, it expires, or
+    /// `max_attempts` polls have been made, `backoff` apart
+    ///
+    /// Expiry is only checked when `expiry` is known. A failed tip lookup is
+    /// logged and skipped for that poll. Polls answered with maintenance mode
+    /// are spaced further apart and do not count toward `max_attempts`.
+    pub async fn wait_for_inclusion(
+        &self,
+        deploy_id: &str,
+        http_port: u16,
+        expiry: Option<DeployExpiry>,
+        backoff: &Backoff,
+        max_attempts: u32,
+    ) -> Result<InclusionOutcome, Box<dyn std::error::Error>> {
+        let max_attempts = max_attempts.max(1);
+        let mut maintenance = MaintenanceWait::new();
+        let mut attempt = 0;
+        while attempt < max_attempts {
+            let found = match self.get_deploy_block_hash(deploy_id, http_port).await {
+                Ok(found) => found,
+                Err(e) => {
+                    let e = NodeCliError::from(e);
+                    match maintenance.on_error(&e, backoff.delay(attempt)) {
+                        Some(delay) => {
+                            tokio::time::sleep(delay).await;
+                            continue;
+                        }
+                        None => return Err(e.into()),
+                    }
+                }
+            };
+            maintenance.on_success();
+            attempt += 1;
+
+            if let Some(block_hash) = found {
+                tracing::debug!(deploy_id, block_hash, attempt, "Deploy found in block");
+                return Ok(InclusionOutcome::Included { block_hash });
+            }
+
+            if let Some(expiry) = expiry {
+                match self.get_tip_block_number(http_port).await {
+                    Ok(Some(tip)) if expiry.is_expired(tip) => {
+                        tracing::warn!(
+                            deploy_id,
+                            tip,
+                            last_valid_block = expiry.last_valid_block(),
+                            "Deploy expired before inclusion"
+                        );
+                        return Ok(InclusionOutcome::Expired {
+                            expired_at: expiry.last_valid_block(),
+                            tip,
+                        });
+                    }
+                    Ok(tip) => {
+                        tracing::debug!(deploy_id, ?tip, attempt, "Deploy not in a block yet")
+                    }
+                    Err(e) => tracing::warn!(attempt, "Tip lookup failed: {}", e),
+                }
+            }
+
+            if attempt < max_attempts {
+                backoff.wait(attempt - 1).await;
+            }
+        }
+
+        Ok(InclusionOutcome::TimedOut {
+            attempts: max_attempts,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key_guard::DEV_PRIVATE_KEY;
+    use crate::test_support::spawn_http_node;
+    use std::sync::atomic::{AtomicI64, AtomicU32, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    const DEPLOY_ID: &str = "3045022100aa";
+    const POLL: Duration = Duration::from_millis(5);
+
+    fn poll() -> Backoff {
+        Backoff::fixed(POLL)
+    }
+
+    /// Node whose tip advances by `step` on every `/api/blocks/1` poll, starting
+    /// at `start`, and which includes the deploy on poll `included_on` (if any)
+    async fn advancing_node(start: i64, step: i64, included_on: Option<u32>) -> u16 {
+        let tip = Arc::new(AtomicI64::new(start));
+        let deploy_polls = Arc::new(AtomicU32::new(0));
+        spawn_http_node(move |line, _body| {
+            if line.contains("/api/deploy/") {
+                let poll = deploy_polls.fetch_add(1, Ordering::SeqCst) + 1;
+                if included_on.is_some_and(|n| poll >= n) {
+                    ("200 OK", r#"{"blockHash":"b1"}"#.to_string())
+                } else {
+                    ("404 Not Found", "{}".to_string())
+                }
+            } else if line.contains("/api/blocks/1") {
+                let height = tip.fetch_add(step, Ordering::SeqCst);
+                ("200 OK", format!(r#"[{{"blockNumber":{}}}]"#, height))
+            } else {
+                ("404 Not Found", "{}".to_string())
+            }
+        })
+        .await
+    }
+
+    fn expiry(valid_after_block_number: i64, lifespan: i64) -> Option<DeployExpiry> {
+        Some(DeployExpiry {
+            valid_after_block_number,
+            lifespan,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_stops_once_tip_passes_expiry() {
+        // Tip goes 12, 14, 16: past the last valid block 15 on the third poll
+        let port = advancing_node(12, 2, None).await;
+        let api = F1r3flyApi::new(DEV_PRIVATE_KEY, "127.0.0.1", 1).unwrap();
+        let outcome = api
+            .wait_for_inclusion(DEPLOY_ID, port, expiry(10, 5), &poll(), 100)
+            .await
+            .unwrap();
+        assert_eq!(
+            outcome,
+            InclusionOutcome::Expired {
+                expired_at: 15,
+                tip: 16
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_inclusion_wins_over_expiry() {
+        let port = advancing_node(12, 2, Some(2)).await;
+        let api = F1r3flyApi::new(DEV_PRIVATE_KEY, "127.0.0.1", 1).unwrap();
+        let outcome = api
+            .wait_for_inclusion(DEPLOY_ID, port, expiry(10, 5), &poll(), 100)
+            .await
+            .unwrap();
+        assert_eq!(
+            outcome,
+            InclusionOutcome::Included {
+                block_hash: "b1".to_string()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unknown_expiry_waits_out_attempts() {
+        let port = advancing_node(1_000, 10, None).await;
+        let api = F1r3flyApi::new(DEV_PRIVATE_KEY, "127.0.0.1", 1).unwrap();
+        let outcome = api
+            .wait_for_inclusion(DEPLOY_ID, port, None, &poll(), 3)
+            .await
+            .unwrap();
+        assert_eq!(outcome, InclusionOutcome::TimedOut { attempts: 3 });
+    }
+
+    #[tokio::test]
+    async fn test_maintenance_does_not_use_up_attempts() {
+        // Five polls in maintenance, then pending twice, then included: more
+        // polls than the three attempts allowed, but only three are healthy
+        let deploy_polls = Arc::new(AtomicU32::new(0));
+        let polls = deploy_polls.clone();
+        let port = spawn_http_node(move |line, _body| {
+            if !line.contains("/api/deploy/") {
+                return ("404 Not Found", "{}".to_string());
+            }
+            match polls.fetch_add(1, Ordering::SeqCst) + 1 {
+                1..=5 => (
+                    "503 Service Unavailable",
+                    r#"{"status":"maintenance","message":"Upgrading"}"#.to_string(),
+                ),
+                6 | 7 => ("404 Not Found", "{}".to_string()),
+                _ => ("200 OK", r#"{"blockHash":"b1"}"#.to_string()),
+            }
+        })
+        .await;
+        let api = F1r3flyApi::new(DEV_PRIVATE_KEY, "127.0.0.1", 1).unwrap();
+        let outcome = api
+            .wait_for_inclusion(DEPLOY_ID, port, None, &poll(), 3)
+            .await
+            .unwrap();
+        assert_eq!(
+            outcome,
+            InclusionOutcome::Included {
+                block_hash: "b1".to_string()
+            }
+        );
+        assert_eq!(deploy_polls.load(Ordering::SeqCst), 8);
+    }
+
+    #[tokio::test]
+    async fn test_other_server_errors_still_fail_the_wait() {
+        let port =
+            spawn_http_node(|_line, _body| ("503 Service Unavailable", "overloaded".to_string()))
+                .await;
+        let api = F1r3flyApi::new(DEV_PRIVATE_KEY, "127.0.0.1", 1).unwrap();
+        let err = NodeCliError::from(
+            api.wait_for_inclusion(DEPLOY_ID, port, None, &poll(), 3)
+                .await
+                .unwrap_err(),
+        );
+        assert!(err.maintenance_detail().is_none());
+        assert!(err.to_string().contains("overloaded"), "{}", err);
+    }
+
+    #[test]
+    fn test_expiry_boundary() {
+        let expiry = expiry(100, 50).unwrap();
+        assert_eq!(expiry.last_valid_block(), 150);
+        assert!(!expiry.is_expired(150));
+        assert!(expiry.is_expired(151));
+    }
+}
@@ -3,20 +3,46 @@
 mod blocks;
 mod deploy;
 mod http;
+mod inclusion;
 pub mod query;
 
-use secp256k1::SecretKey;
+use crate::error::NodeCall;
+use crate::query_cache::QueryCache;
+use crate::utils::CryptoUtils;
+use f1r3fly_models::casper::v1::deploy_service_client::DeployServiceClient;
+use f1r3fly_models::casper::v1::propose_service_client::ProposeServiceClient;
+use secp256k1::{PublicKey, SecretKey};
 use std::sync::atomic::AtomicI64;
 use std::sync::Arc;
+use tokio::sync::OnceCell;
+use tonic::transport::{Channel, Endpoint};
 
 const TIP_FLOOR_UNSET: i64 = -1;
 
+/// Phlo limit for deploys made with `use_bigger_phlo_price` (phlo price is 1)
+pub const BIGGER_PHLO_LIMIT: i64 = 5_000_000_000;
+/// Phlo limit for regular deploys
+pub const DEFAULT_PHLO_LIMIT: i64 = 50_000;
+/// Phlo price of every deploy the CLI signs unless a command asks for more
+pub const DEFAULT_PHLO_PRICE: i64 = 1;
+/// Deploy lifespan in blocks assumed when the node does not report one
+pub const DEPLOY_VALIDITY_WINDOW_BLOCKS: i64 = 50;
+
 /// Client for interacting with the F1r3fly node via gRPC and HTTP
+///
+/// The signing key is parsed and its public key derived once, at construction.
+/// The gRPC channel is dialled on the first gRPC call and shared by later ones,
+/// so commands that only make HTTP calls never connect over gRPC.
+/// Exploratory deploys pinned to a block are cached (see [`crate::query_cache`]).
 pub struct F1r3flyApi<'a> {
     pub(crate) signing_key: SecretKey,
+    pub(crate) public_key: PublicKey,
     pub(crate) node_host: &'a str,
     pub(crate) grpc_port: u16,
     pub(crate) tip_floor: Arc<AtomicI64>,
+    pub(crate) channel: OnceCell<Channel>,
+    /// Results of exploratory deploys pinned to a block
+    pub(crate) explore_cache: QueryCache<(String, String, u64)>,
 }
 
 impl<'a> F1r3flyApi<'a> {
@@ -25,20 +51,196 @@ impl<'a> F1r3flyApi<'a> {
         node_host: &'a str,
         grpc_port: u16,
     ) -> std::result::Result<Self, crate::error::NodeCliError> {
-        let key_bytes = hex::decode(signing_key)?;
-        let key_array: [u8; 32] = key_bytes.try_into().map_err(|_| {
-            crate::error::NodeCliError::crypto_invalid_private_key("key must be 32 bytes")
-        })?;
-        let secret_key = SecretKey::from_byte_array(key_array)?;
-        Ok(F1r3flyApi {
+        let secret_key = CryptoUtils::decode_private_key(signing_key)?;
+        Ok(Self::with_key(secret_key, node_host, grpc_port))
+    }
+
+    /// Client for commands that only read, such as exploratory deploys
+    ///
+    /// The node does not ask for reads to be signed, so there is no need for a
+    /// real key: `private_key` is used when given (a `--private-key` override),
+    /// otherwise a throwaway key is generated for this client.
+    pub fn for_queries(
+        private_key: Option<&str>,
+        node_host: &'a str,
+        grpc_port: u16,
+    ) -> std::result::Result<Self, crate::error::NodeCliError> {
+        match private_key {
+            Some(key) => Self::new(key, node_host, grpc_port),
+            None => {
+                let (secret_key, _) = CryptoUtils::generate_key_pair()?;
+                Ok(Self::with_key(secret_key, node_host, grpc_port))
+            }
+        }
+    }
+
+    fn with_key(secret_key: SecretKey, node_host: &'a str, grpc_port: u16) -> Self {
+        crate::timings::observe_node(node_host, grpc_port);
+        F1r3flyApi {
             signing_key: secret_key,
+            public_key: CryptoUtils::derive_public_key(&secret_key),
             node_host,
             grpc_port,
             tip_floor: Arc::new(AtomicI64::new(TIP_FLOOR_UNSET)),
-        })
+            channel: OnceCell::new(),
+            explore_cache: QueryCache::from_settings(),
+        }
+    }
+
+    /// Public key of the signing key
+    pub fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+
+    /// Uncompressed public key as hex
+    pub fn public_key_hex(&self) -> String {
+        CryptoUtils::serialize_public_key(&self.public_key, false)
+    }
+
+    /// Vault address of the signing key
+    pub fn vault_address(&self) -> crate::error::Result<String> {
+        CryptoUtils::generate_vault_address(&self.public_key_hex())
     }
 
     pub(crate) fn grpc_url(&self) -> String {
         format!("http://{}:{}/", self.node_host, self.grpc_port)
     }
+
+    /// Start timing a gRPC call, so its failure names `operation` and this node
+    pub(crate) fn grpc_call(&self, operation: &'static str) -> NodeCall {
+        NodeCall::start(operation, format!("{}:{}", self.node_host, self.grpc_port))
+    }
+
+    /// The shared gRPC channel, connecting on first use
+    ///
+    /// A failed connect is not cached; the next call dials again.
+    pub(crate) async fn channel(&self) -> Result<Channel, tonic::transport::Error> {
+        self.channel
+            .get_or_try_init(|| async { Endpoint::from_shared(self.grpc_url())?.connect().await })
+            .await
+            .cloned()
+    }
+
+    pub(crate) async fn deploy_client(
+        &self,
+    ) -> Result<DeployServiceClient<Channel>, tonic::transport::Error> {
+        Ok(DeployServiceClient::new(self.channel().await?))
+    }
+
+    pub(crate) async fn propose_client(
+        &self,
+    ) -> Result<ProposeServiceClient<Channel>, tonic::transport::Error> {
+        Ok(ProposeServiceClient::new(self.channel().await?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key_guard::DEV_PRIVATE_KEY;
+    use std::time::Instant;
+
+    #[test]
+    fn test_key_parsed_once_and_exposed() {
+        let api = F1r3flyApi::new(DEV_PRIVATE_KEY, "127.0.0.1", 1).unwrap();
+        let secret_key = CryptoUtils::decode_private_key(DEV_PRIVATE_KEY).unwrap();
+        assert_eq!(
+            api.public_key(),
+            &CryptoUtils::derive_public_key(&secret_key)
+        );
+        assert_eq!(api.public_key_hex().len(), 130);
+        assert!(api.vault_address().unwrap().starts_with("1111"));
+        assert!(F1r3flyApi::new("zz", "127.0.0.1", 1).is_err());
+    }
+
+    #[test]
+    fn test_query_client_key() {
+        let given = F1r3flyApi::for_queries(Some(DEV_PRIVATE_KEY), "127.0.0.1", 1).unwrap();
+        assert_eq!(
+            given.public_key(),
+            F1r3flyApi::new(DEV_PRIVATE_KEY, "127.0.0.1", 1)
+                .unwrap()
+                .public_key()
+        );
+        assert!(F1r3flyApi::for_queries(Some("zz"), "127.0.0.1", 1).is_err());
+
+        // Without one each client gets its own throwaway key, never the dev key
+        let first = F1r3flyApi::for_queries(None, "127.0.0.1", 1).unwrap();
+        let second = F1r3flyApi::for_queries(None, "127.0.0.1", 1).unwrap();
+        assert_ne!(first.public_key(), second.public_key());
+        assert_ne!(first.public_key(), given.public_key());
+    }
+
+    #[test]
+    fn test_construction_does_not_dial_grpc() {
+        // Nothing listens on port 1; construction still succeeds without connecting
+        let api = F1r3flyApi::new(DEV_PRIVATE_KEY, "127.0.0.1", 1).unwrap();
+        assert!(api.channel.get().is_none());
+    }
+
+    #[test]
+    fn test_construct_and_sign_reuses_secp_context() {
+        CryptoUtils::secp();
+        let inits = CryptoUtils::secp_context_inits();
+        assert_eq!(inits, 1);
+
+        const ROUNDS: u32 = 200;
+        let start = Instant::now();
+        for _ in 0..ROUNDS {
+            let api = F1r3flyApi::new(DEV_PRIVATE_KEY, "127.0.0.1", 1).unwrap();
+            let deploy = api.build_deploy_msg(
+                "Nil".to_string(),
+                DEFAULT_PHLO_LIMIT,
+                DEFAULT_PHLO_PRICE,
+                "rholang".to_string(),
+                0,
+                0,
+                Some(0),
+            );
+            assert!(!deploy.sig.is_empty());
+        }
+        let per_round = start.elapsed() / ROUNDS;
+        println!("construct + sign: {:?} per round", per_round);
+
+        assert_eq!(CryptoUtils::secp_context_inits(), inits);
+    }
+
+    /// A local port with nothing listening on it
+    fn closed_port() -> u16 {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap().port()
+    }
+
+    #[tokio::test]
+    async fn test_refused_grpc_call_names_operation_and_endpoint() {
+        let port = closed_port();
+        let api = F1r3flyApi::new(DEV_PRIVATE_KEY, "127.0.0.1", port).unwrap();
+        let err = crate::error::NodeCliError::from(api.show_main_chain(1).await.unwrap_err());
+
+        let context = err.context().expect("call context");
+        assert_eq!(context.operation, "show_main_chain");
+        assert_eq!(context.endpoint, format!("127.0.0.1:{}", port));
+        let message = err.to_string();
+        assert!(message.contains("Connection failed"), "{}", message);
+        assert!(
+            message.contains(&format!("[show_main_chain at 127.0.0.1:{}, after ", port)),
+            "{}",
+            message
+        );
+    }
+
+    #[tokio::test]
+    async fn test_refused_http_call_names_operation_and_endpoint() {
+        let port = closed_port();
+        let api = F1r3flyApi::new(DEV_PRIVATE_KEY, "127.0.0.1", 1).unwrap();
+        let err = crate::error::NodeCliError::from(api.get_node_status(port).await.unwrap_err());
+
+        let context = err.context().expect("call context");
+        assert_eq!(context.operation, "status");
+        assert_eq!(
+            context.endpoint,
+            format!("http://127.0.0.1:{}/api/status", port)
+        );
+        assert!(err.to_string().contains("Connection failed"), "{}", err);
+    }
 }
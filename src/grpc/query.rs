@@ -1,23 +1,101 @@
 //! Query operations (exploratory deploy, data reads, deploy lookup)
 
 use super::F1r3flyApi;
-use f1r3fly_models::casper::v1::deploy_service_client::DeployServiceClient;
+use crate::f1r3fly_api::DeployInfo;
 use f1r3fly_models::casper::v1::exploratory_deploy_response::Message as ExploratoryDeployResponseMessage;
 use f1r3fly_models::casper::v1::rho_data_response;
 use f1r3fly_models::casper::{
-    DataAtNameByBlockQuery, ExploratoryDeployQuery, FindDeployQuery, LightBlockInfo,
+    BlockInfo, BlockQuery, DataAtNameByBlockQuery, ExploratoryDeployQuery, FindDeployQuery,
+    LightBlockInfo,
 };
+use f1r3fly_models::rhoapi::expr::ExprInstance;
 use f1r3fly_models::rhoapi::g_unforgeable::UnfInstance;
-use f1r3fly_models::rhoapi::{GDeployId, GUnforgeable, Par};
+use f1r3fly_models::rhoapi::{Expr, GDeployId, GPrivate, GUnforgeable, Par};
+use serde_json::{json, Value};
 
 impl<'a> F1r3flyApi<'a> {
+    /// Run an exploratory deploy, answering from the query cache when it is
+    /// pinned to a block that was already queried with the same term
     pub async fn exploratory_deploy(
         &self,
         rho_code: &str,
         block_hash: Option<&str>,
         use_pre_state_hash: bool,
     ) -> Result<(String, String, u64), Box<dyn std::error::Error>> {
-        let mut client = DeployServiceClient::connect(self.grpc_url()).await?;
+        self.explore_cache
+            .get_or_fetch(rho_code, block_hash, use_pre_state_hash, || {
+                self.exploratory_deploy_uncached(rho_code, block_hash, use_pre_state_hash)
+            })
+            .await
+    }
+
+    async fn exploratory_deploy_uncached(
+        &self,
+        rho_code: &str,
+        block_hash: Option<&str>,
+        use_pre_state_hash: bool,
+    ) -> Result<(String, String, u64), Box<dyn std::error::Error>> {
+        let (data, block, cost) = self
+            .exploratory_deploy_pars(rho_code, block_hash, use_pre_state_hash)
+            .await?;
+        let data = if !data.is_empty() {
+            data.iter()
+                .enumerate()
+                .map(|(i, par)| {
+                    extract_par_data(par)
+                        .unwrap_or_else(|| format!("Result {}: Complex data structure", i + 1))
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        } else {
+            "No data returned".to_string()
+        };
+
+        let block_info = block
+            .map(|b| {
+                format!(
+                    "Block hash: {}, Block number: {}",
+                    b.block_hash, b.block_number
+                )
+            })
+            .unwrap_or_else(|| "No block info".to_string());
+
+        Ok((data, block_info, cost))
+    }
+
+    /// Run an exploratory deploy and answer in the HTTP explore-deploy shape
+    ///
+    /// `expr` holds each value the term returned as Rholang JSON (see
+    /// [`par_to_rholang_json`]) and `block` the `blockHash` and `blockNumber`
+    /// it was evaluated against, so callers can share the HTTP parsers. Not
+    /// cached.
+    pub async fn exploratory_deploy_json(
+        &self,
+        rho_code: &str,
+        block_hash: Option<&str>,
+    ) -> Result<Value, Box<dyn std::error::Error>> {
+        let (data, block, _) = self
+            .exploratory_deploy_pars(rho_code, block_hash, false)
+            .await?;
+        let expr: Vec<Value> = data.iter().map(par_to_rholang_json).collect();
+        Ok(match block {
+            Some(block) => json!({
+                "expr": expr,
+                "block": {"blockHash": block.block_hash, "blockNumber": block.block_number}
+            }),
+            None => json!({ "expr": expr }),
+        })
+    }
+
+    /// The values an exploratory deploy returned, the block it ran against and its cost
+    async fn exploratory_deploy_pars(
+        &self,
+        rho_code: &str,
+        block_hash: Option<&str>,
+        use_pre_state_hash: bool,
+    ) -> Result<(Vec<Par>, Option<LightBlockInfo>, u64), Box<dyn std::error::Error>> {
+        let call = self.grpc_call("exploratory_deploy");
+        let mut client = self.deploy_client().await.map_err(|e| call.fail(e))?;
 
         let query = ExploratoryDeployQuery {
             term: rho_code.to_string(),
@@ -25,48 +103,24 @@ impl<'a> F1r3flyApi<'a> {
             use_pre_state_hash,
         };
 
-        let response = client.exploratory_deploy(query).await?;
+        let response = client
+            .exploratory_deploy(query)
+            .await
+            .map_err(|e| call.fail(e))?;
         let resp = response.get_ref();
         let cost = resp.cost;
 
         let message = resp
             .message
             .as_ref()
-            .ok_or("Exploratory deploy result not found")?;
+            .ok_or_else(|| call.fail("Exploratory deploy result not found"))?;
 
         match message {
             ExploratoryDeployResponseMessage::Error(service_error) => {
-                Err(service_error.clone().into())
+                Err(call.fail(service_error.clone()).into())
             }
             ExploratoryDeployResponseMessage::Result(result) => {
-                let data = if !result.post_block_data.is_empty() {
-                    result
-                        .post_block_data
-                        .iter()
-                        .enumerate()
-                        .map(|(i, par)| {
-                            extract_par_data(par).unwrap_or_else(|| {
-                                format!("Result {}: Complex data structure", i + 1)
-                            })
-                        })
-                        .collect::<Vec<_>>()
-                        .join("\n")
-                } else {
-                    "No data returned".to_string()
-                };
-
-                let block_info = result
-                    .block
-                    .as_ref()
-                    .map(|b| {
-                        format!(
-                            "Block hash: {}, Block number: {}",
-                            b.block_hash, b.block_number
-                        )
-                    })
-                    .unwrap_or_else(|| "No block info".to_string());
-
-                Ok((data, block_info, cost))
+                Ok((result.post_block_data.clone(), result.block.clone(), cost))
             }
         }
     }
@@ -85,24 +139,57 @@ impl<'a> F1r3flyApi<'a> {
             }],
             ..Default::default()
         };
+        self.get_data_at_name_in_block(par, block_hash).await
+    }
+
+    /// Data at `name` in the newest of the last `depth` main-chain blocks that holds any
+    ///
+    /// The node's `getDataAtName` reads one block's state, so the main chain
+    /// is walked back from the tip one block at a time. Returns `None` if
+    /// none of the blocks has data at the name.
+    pub async fn get_data_at_name(
+        &self,
+        name: &Par,
+        depth: u32,
+    ) -> Result<Option<(LightBlockInfo, Vec<Par>)>, Box<dyn std::error::Error>> {
+        let mut blocks = self.show_main_chain(depth).await?;
+        blocks.sort_by(|a, b| b.block_number.cmp(&a.block_number));
+        for block in blocks {
+            let data = self
+                .get_data_at_name_in_block(name.clone(), &block.block_hash)
+                .await?;
+            if !data.is_empty() {
+                return Ok(Some((block, data)));
+            }
+        }
+        Ok(None)
+    }
 
-        let mut client = DeployServiceClient::connect(self.grpc_url()).await?;
+    /// Data sent on `name` and not yet consumed in the state after `block_hash`
+    pub async fn get_data_at_name_in_block(
+        &self,
+        name: Par,
+        block_hash: &str,
+    ) -> Result<Vec<Par>, Box<dyn std::error::Error>> {
+        let call = self.grpc_call("get_data_at_name");
+        let mut client = self.deploy_client().await.map_err(|e| call.fail(e))?;
 
         let response = client
             .get_data_at_name(DataAtNameByBlockQuery {
-                par: Some(par),
+                par: Some(name),
                 block_hash: block_hash.to_string(),
                 use_pre_state_hash: false,
             })
-            .await?
+            .await
+            .map_err(|e| call.fail(e))?
             .into_inner();
 
         match response.message {
             Some(rho_data_response::Message::Payload(payload)) => Ok(payload.par),
-            Some(rho_data_response::Message::Error(err)) => {
-                Err(format!("getDataAtName error: {}", err.messages.join("; ")).into())
-            }
-            None => Err("getDataAtName: empty response".into()),
+            Some(rho_data_response::Message::Error(err)) => Err(call
+                .fail(format!("getDataAtName error: {}", err.messages.join("; ")))
+                .into()),
+            None => Err(call.fail("getDataAtName: empty response").into()),
         }
     }
 
@@ -111,13 +198,15 @@ impl<'a> F1r3flyApi<'a> {
         deploy_id: &str,
     ) -> Result<Option<LightBlockInfo>, Box<dyn std::error::Error>> {
         let deploy_id_bytes = hex::decode(deploy_id)?;
-        let mut client = DeployServiceClient::connect(self.grpc_url()).await?;
+        let call = self.grpc_call("find_deploy");
+        let mut client = self.deploy_client().await.map_err(|e| call.fail(e))?;
 
         let response = client
             .find_deploy(FindDeployQuery {
                 deploy_id: deploy_id_bytes.into(),
             })
-            .await?
+            .await
+            .map_err(|e| call.fail(e))?
             .into_inner();
 
         use f1r3fly_models::casper::v1::find_deploy_response::Message;
@@ -127,6 +216,175 @@ impl<'a> F1r3flyApi<'a> {
             None => Ok(None),
         }
     }
+
+    /// Block holding `deploy_id`, with the deploy's cost and errored flag, over gRPC only
+    ///
+    /// For nodes that expose no HTTP port. `None` if the deploy is in no block
+    /// yet. The cost and errored flag are read from the block's deploy list;
+    /// if the block cannot be fetched they are left unset rather than failing
+    /// the lookup.
+    pub async fn find_deploy(
+        &self,
+        deploy_id: &str,
+    ) -> Result<Option<DeployInfo>, Box<dyn std::error::Error>> {
+        let Some(block) = self.find_deploy_grpc(deploy_id).await? else {
+            return Ok(None);
+        };
+        let (cost, errored) = match self.get_block_deploy(&block.block_hash, deploy_id).await {
+            Ok(deploy) => deploy.map_or((None, None), |d| (Some(d.cost), Some(d.errored))),
+            Err(e) => {
+                tracing::debug!(
+                    block_hash = %block.block_hash,
+                    "Block of deploy {} unavailable over gRPC: {}",
+                    deploy_id,
+                    e
+                );
+                (None, None)
+            }
+        };
+        Ok(Some(DeployInfo {
+            deploy_id: deploy_id.to_string(),
+            block_hash: block.block_hash,
+            block_number: block.block_number,
+            cost,
+            errored,
+        }))
+    }
+
+    /// `deploy_id`'s entry in the deploy list of `block_hash`, over gRPC
+    ///
+    /// Carries the execution outcome (`cost`, `errored`, `system_deploy_error`)
+    /// for nodes whose HTTP API has no deploy detail view. `None` if the block
+    /// does not hold the deploy.
+    pub async fn get_block_deploy(
+        &self,
+        block_hash: &str,
+        deploy_id: &str,
+    ) -> Result<Option<f1r3fly_models::casper::DeployInfo>, Box<dyn std::error::Error>> {
+        let block = self.get_block_grpc(block_hash).await?;
+        Ok(block_deploy(&block.deploys, deploy_id).cloned())
+    }
+
+    async fn get_block_grpc(
+        &self,
+        block_hash: &str,
+    ) -> Result<BlockInfo, Box<dyn std::error::Error>> {
+        let call = self.grpc_call("get_block");
+        let mut client = self.deploy_client().await.map_err(|e| call.fail(e))?;
+
+        let response = client
+            .get_block(BlockQuery {
+                hash: block_hash.to_string(),
+            })
+            .await
+            .map_err(|e| call.fail(e))?
+            .into_inner();
+
+        use f1r3fly_models::casper::v1::block_response::Message;
+        match response.message {
+            Some(Message::BlockInfo(block_info)) => Ok(block_info),
+            Some(Message::Error(err)) => Err(call
+                .fail(format!("getBlock error: {}", err.messages.join("; ")))
+                .into()),
+            None => Err(call.fail("getBlock: empty response").into()),
+        }
+    }
+}
+
+/// `deploy_id` among a block's deploys, matched on the signature
+fn block_deploy<'a>(
+    deploys: &'a [f1r3fly_models::casper::DeployInfo],
+    deploy_id: &str,
+) -> Option<&'a f1r3fly_models::casper::DeployInfo> {
+    deploys
+        .iter()
+        .find(|deploy| deploy.sig.eq_ignore_ascii_case(deploy_id))
+}
+
+/// The public name `@"name"`
+pub fn public_name(name: &str) -> Par {
+    Par {
+        exprs: vec![Expr {
+            expr_instance: Some(ExprInstance::GString(name.to_string())),
+        }],
+        ..Default::default()
+    }
+}
+
+/// The unforgeable name with hex id `id`, as `convert_rholang_to_json` prints them
+pub fn unforgeable_name(id: &str) -> Result<Par, crate::error::NodeCliError> {
+    let bytes = hex::decode(id.trim()).map_err(|e| {
+        crate::error::NodeCliError::config_invalid_value(
+            "name",
+            &format!("'{}' is not an unforgeable name's hex id: {}", id, e),
+        )
+    })?;
+    Ok(Par {
+        unforgeables: vec![GUnforgeable {
+            unf_instance: Some(UnfInstance::GPrivateBody(GPrivate { id: bytes.into() })),
+        }],
+        ..Default::default()
+    })
+}
+
+/// A `Par` in the Rholang JSON of the HTTP API, for `convert_rholang_to_json`
+///
+/// Ground values, collections and unforgeable names are converted; any other
+/// process (a send, a receive, ...) becomes a string naming what it is.
+pub fn par_to_rholang_json(par: &Par) -> Value {
+    let mut items: Vec<Value> = par.exprs.iter().map(expr_to_rholang_json).collect();
+    items.extend(par.unforgeables.iter().map(unforgeable_to_rholang_json));
+    if items.is_empty() {
+        if let Some(kind) = extract_par_data(par) {
+            items.push(json!({"ExprString": {"data": format!("<{}>", kind)}}));
+        }
+    }
+    match items.len() {
+        1 => items.remove(0),
+        _ => json!({"ExprPar": {"data": items}}),
+    }
+}
+
+fn expr_to_rholang_json(expr: &Expr) -> Value {
+    let list = |ps: &[Par]| ps.iter().map(par_to_rholang_json).collect::<Vec<_>>();
+    match &expr.expr_instance {
+        Some(ExprInstance::GBool(b)) => json!({"ExprBool": {"data": b}}),
+        Some(ExprInstance::GInt(i)) => json!({"ExprInt": {"data": i}}),
+        Some(ExprInstance::GString(s)) => json!({"ExprString": {"data": s}}),
+        Some(ExprInstance::GUri(uri)) => json!({"ExprUri": {"data": uri}}),
+        Some(ExprInstance::GByteArray(bytes)) => {
+            json!({"ExprBytes": {"data": hex::encode(bytes)}})
+        }
+        Some(ExprInstance::EListBody(l)) => json!({"ExprList": {"data": list(&l.ps)}}),
+        Some(ExprInstance::ETupleBody(t)) => json!({"ExprTuple": {"data": list(&t.ps)}}),
+        Some(ExprInstance::ESetBody(set)) => json!({"ExprSet": {"data": list(&set.ps)}}),
+        Some(ExprInstance::EMapBody(map)) => {
+            let pairs: Vec<Value> = map
+                .kvs
+                .iter()
+                .map(|kv| {
+                    let side = |p: &Option<Par>| {
+                        p.as_ref()
+                            .map(par_to_rholang_json)
+                            .unwrap_or_else(|| json!({"ExprPar": {"data": []}}))
+                    };
+                    json!([side(&kv.key), side(&kv.value)])
+                })
+                .collect();
+            json!({"ExprMap": {"data": pairs}})
+        }
+        _ => json!({"ExprString": {"data": "<Complex expression>"}}),
+    }
+}
+
+fn unforgeable_to_rholang_json(unforgeable: &GUnforgeable) -> Value {
+    let (kind, id) = match &unforgeable.unf_instance {
+        Some(UnfInstance::GPrivateBody(p)) => ("UnforgPrivate", hex::encode(&p.id)),
+        Some(UnfInstance::GDeployIdBody(d)) => ("UnforgDeploy", hex::encode(&d.sig)),
+        Some(UnfInstance::GDeployerIdBody(d)) => ("UnforgDeployer", hex::encode(&d.public_key)),
+        _ => return json!({"ExprString": {"data": "<Unforgeable>"}}),
+    };
+    json!({"ExprUnforg": {"data": {kind: {"data": id}}}})
 }
 
 pub fn extract_par_data(par: &Par) -> Option<String> {
@@ -154,3 +412,75 @@ pub fn extract_par_data(par: &Par) -> Option<String> {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rholang_helpers::convert_rholang_to_json;
+    use f1r3fly_models::rhoapi::{EList, KeyValuePair, ParMap};
+
+    fn expr(instance: ExprInstance) -> Par {
+        Par {
+            exprs: vec![Expr {
+                expr_instance: Some(instance),
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_par_to_rholang_json_converts_data() {
+        let map = expr(ExprInstance::EMapBody(ParMap {
+            kvs: vec![KeyValuePair {
+                key: Some(public_name("total")),
+                value: Some(expr(ExprInstance::GInt(42))),
+            }],
+            ..Default::default()
+        }));
+        let list = expr(ExprInstance::EListBody(EList {
+            ps: vec![
+                map,
+                expr(ExprInstance::GBool(true)),
+                expr(ExprInstance::GByteArray(vec![0x04, 0xaa].into())),
+                unforgeable_name("c0ffee").unwrap(),
+            ],
+            ..Default::default()
+        }));
+        assert_eq!(
+            convert_rholang_to_json(&par_to_rholang_json(&list)).unwrap(),
+            json!([{"total": 42}, true, "04aa", "c0ffee"])
+        );
+        assert_eq!(
+            convert_rholang_to_json(&par_to_rholang_json(&Par::default())).unwrap(),
+            Value::Null
+        );
+    }
+
+    #[test]
+    fn test_block_deploy_matches_the_signature() {
+        let deploy = |sig: &str, cost: u64, errored: bool| f1r3fly_models::casper::DeployInfo {
+            sig: sig.to_string(),
+            cost,
+            errored,
+            ..Default::default()
+        };
+        let deploys = [deploy("3044aa", 120, false), deploy("3044bb", 95, true)];
+        let found = block_deploy(&deploys, "3044BB").unwrap();
+        assert_eq!((found.cost, found.errored), (95, true));
+        assert_eq!(block_deploy(&deploys, "3044aa").unwrap().cost, 120);
+        assert!(block_deploy(&deploys, "3044cc").is_none());
+    }
+
+    #[test]
+    fn test_names() {
+        assert_eq!(
+            par_to_rholang_json(&public_name("results")),
+            json!({"ExprString": {"data": "results"}})
+        );
+        assert_eq!(
+            par_to_rholang_json(&unforgeable_name(" C0FFEE ").unwrap()),
+            json!({"ExprUnforg": {"data": {"UnforgPrivate": {"data": "c0ffee"}}}})
+        );
+        assert!(unforgeable_name("not hex").is_err());
+    }
+}
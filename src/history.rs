@@ -0,0 +1,544 @@
+//! Local deploy history
+//!
+//! Deploy-family commands can append one JSON record per line to
+//! `<config dir>/history.jsonl`. Writing is best-effort: a failure to open or
+//! append to the file is logged as a warning and never fails the deploy.
+
+use crate::f1r3fly_api::{DeployResult, IncludedDeploy};
+use crate::store;
+use crate::utils::now_millis;
+use crate::vault::DUST_FACTOR;
+use blake2::{Blake2b, Digest};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use typenum::U32;
+
+/// Environment variable enabling history by default (`1`, `true`, `yes`, `on`)
+pub const HISTORY_ENV: &str = "FIREFLY_HISTORY";
+
+/// Environment variable overriding the history file location
+pub const HISTORY_FILE_ENV: &str = "FIREFLY_HISTORY_FILE";
+
+const HISTORY_FILE_NAME: &str = "history.jsonl";
+
+/// Version written in the history file's header line
+const HISTORY_SCHEMA_VERSION: u32 = 1;
+
+/// Final state of a recorded deploy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryOutcome {
+    /// Deploy accepted by the node, inclusion not tracked
+    Submitted,
+    /// Deploy included in a block, finalization not awaited
+    Included,
+    /// Deploy included in a finalized block without errors
+    Finalized,
+    /// Deploy included in a block but execution errored
+    Errored,
+    /// The command failed before a final state was known
+    Failed,
+    /// Finalized on the deploy node but a `--verify-on` node disagreed
+    #[serde(rename = "verification_mismatch")]
+    VerificationMismatch,
+}
+
+impl std::fmt::Display for HistoryOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Submitted => "submitted",
+            Self::Included => "included",
+            Self::Finalized => "finalized",
+            Self::Errored => "errored",
+            Self::Failed => "failed",
+            Self::VerificationMismatch => "verification_mismatch",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// One line of the history file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryRecord {
+    /// Unix timestamp in milliseconds
+    pub timestamp: i64,
+    /// CLI command that produced the record (e.g. `deploy-and-wait`)
+    pub command: String,
+    pub host: String,
+    /// gRPC port the deploy was sent to
+    pub port: u16,
+    #[serde(default)]
+    pub http_port: Option<u16>,
+    #[serde(default)]
+    pub deploy_id: Option<String>,
+    #[serde(default)]
+    pub block_hash: Option<String>,
+    /// Source file of the deployed term, if any
+    #[serde(default)]
+    pub source: Option<String>,
+    /// Short Blake2b-256 hash of the deployed term
+    pub term_hash: String,
+    pub outcome: HistoryOutcome,
+    #[serde(default)]
+    pub error: Option<String>,
+    /// Unix timestamp in milliseconds at which finalization was observed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finalized_at: Option<i64>,
+    /// Deploy ID this record's deploy replaced (`bump-deploy`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub supersedes: Option<String>,
+    /// Deploy ID of the replacement submitted for this record's deploy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub superseded_by: Option<String>,
+    /// Whole tokens sent, for `transfer` records written before `amount_dust`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amount: Option<u64>,
+    /// Dust sent, for `transfer`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amount_dust: Option<u64>,
+}
+
+impl HistoryRecord {
+    /// Create a record stamped with the current time
+    pub fn new(command: &str, host: &str, port: u16, term: &str) -> Self {
+        Self {
+            timestamp: now_millis(),
+            command: command.to_string(),
+            host: host.to_string(),
+            port,
+            http_port: None,
+            deploy_id: None,
+            block_hash: None,
+            source: None,
+            term_hash: term_hash(term),
+            outcome: HistoryOutcome::Submitted,
+            error: None,
+            finalized_at: None,
+            supersedes: None,
+            superseded_by: None,
+            amount: None,
+            amount_dust: None,
+        }
+    }
+
+    /// Fill in the deploy ID, block hash and outcome from a finished deploy
+    ///
+    /// Deploy-and-wait results are only returned once the block is finalized,
+    /// so the current time is recorded as when finalization was observed.
+    pub fn complete(mut self, result: &DeployResult) -> Self {
+        self.deploy_id = Some(result.deploy_id.clone());
+        self.block_hash = Some(result.block_hash.clone());
+        self.finalized_at = Some(now_millis());
+        if result.errored {
+            self.outcome = HistoryOutcome::Errored;
+            self.error = result.system_deploy_error.clone();
+        } else {
+            self.outcome = HistoryOutcome::Finalized;
+        }
+        self
+    }
+
+    /// Fill in the deploy ID and block hash of a deploy whose finalization was not awaited
+    pub fn included(mut self, included: &IncludedDeploy) -> Self {
+        self.deploy_id = Some(included.deploy_id.clone());
+        self.block_hash = Some(included.block_hash.clone());
+        self.outcome = HistoryOutcome::Included;
+        self
+    }
+
+    /// Downgrade a finalized record after verification nodes disagreed
+    pub fn verification_mismatch(mut self, details: impl std::fmt::Display) -> Self {
+        self.outcome = HistoryOutcome::VerificationMismatch;
+        self.error = Some(details.to_string());
+        self
+    }
+
+    /// Mark the record as failed with the given error
+    pub fn fail(mut self, error: impl std::fmt::Display) -> Self {
+        self.outcome = HistoryOutcome::Failed;
+        self.error = Some(error.to_string());
+        self
+    }
+
+    /// True if this record is for exactly `deploy_id` (case-insensitive)
+    pub fn is_deploy(&self, deploy_id: &str) -> bool {
+        self.deploy_id
+            .as_deref()
+            .is_some_and(|id| id.eq_ignore_ascii_case(deploy_id))
+    }
+
+    /// True if the deploy ID or block hash starts with `prefix` (case-insensitive)
+    pub fn matches_prefix(&self, prefix: &str) -> bool {
+        let prefix = prefix.to_ascii_lowercase();
+        [&self.deploy_id, &self.block_hash]
+            .iter()
+            .filter_map(|v| v.as_deref())
+            .any(|v| v.to_ascii_lowercase().starts_with(&prefix))
+    }
+}
+
+/// Short hash identifying a deployed term without storing the term itself
+pub fn term_hash(term: &str) -> String {
+    let hash = Blake2b::<U32>::new()
+        .chain_update(term.as_bytes())
+        .finalize();
+    hex::encode(&hash[..8])
+}
+
+/// Whether history is on when neither `--history` nor `--no-history` is given
+pub fn enabled_by_default() -> bool {
+    std::env::var(HISTORY_ENV)
+        .map(|v| matches!(v.to_ascii_lowercase().as_str(), "1" | "true" | "yes" | "on"))
+        .unwrap_or(false)
+}
+
+/// Location of the history file, if a config directory can be resolved
+pub fn history_path() -> Option<PathBuf> {
+    if let Ok(path) = std::env::var(HISTORY_FILE_ENV) {
+        if !path.is_empty() {
+            return Some(PathBuf::from(path));
+        }
+    }
+    crate::utils::config_dir().map(|dir| dir.join(HISTORY_FILE_NAME))
+}
+
+/// Append a record as a single line and flush it before returning
+pub fn append_record(path: &Path, record: &HistoryRecord) -> std::io::Result<()> {
+    store::append_line(
+        path,
+        HISTORY_SCHEMA_VERSION,
+        &serde_json::to_string(record)?,
+    )
+}
+
+/// Best-effort append to the default history file
+///
+/// Returns `false` (after logging a warning) if the record could not be written.
+pub fn record(record: &HistoryRecord) -> bool {
+    let Some(path) = history_path() else {
+        tracing::warn!("Deploy history enabled but no config directory could be resolved");
+        return false;
+    };
+    record_to(&path, record)
+}
+
+/// Best-effort append to an explicit history file
+pub fn record_to(path: &Path, record: &HistoryRecord) -> bool {
+    match append_record(path, record) {
+        Ok(()) => true,
+        Err(e) => {
+            tracing::warn!(
+                path = %path.display(),
+                "Failed to write deploy history: {}",
+                e
+            );
+            false
+        }
+    }
+}
+
+/// Read all records, skipping lines that fail to parse
+///
+/// A missing file is treated as an empty history.
+pub fn read_records(path: &Path) -> std::io::Result<Vec<HistoryRecord>> {
+    let mut records = Vec::new();
+    for (number, line) in store::read_lines(path)? {
+        match serde_json::from_str::<HistoryRecord>(&line) {
+            Ok(record) => records.push(record),
+            Err(e) => tracing::warn!(line = number, "Skipping malformed history record: {}", e),
+        }
+    }
+    Ok(records)
+}
+
+/// Parse a `--since` window such as `90s`, `30m`, `24h` or `7d`
+///
+/// A bare number is interpreted as hours.
+pub fn parse_since(value: &str) -> Result<Duration, String> {
+    crate::utils::parse_duration(value, "h").map_err(|e| format!("Invalid --since value: {}", e))
+}
+
+/// When finalization of `deploy_id` was observed, from the most recent history record that saw it
+///
+/// Best-effort: a missing or unreadable history file yields `None`.
+pub fn recorded_finalization(deploy_id: &str) -> Option<i64> {
+    let records = read_records(&history_path()?).ok()?;
+    records
+        .iter()
+        .rev()
+        .filter(|r| r.is_deploy(deploy_id))
+        .find_map(|r| r.finalized_at)
+}
+
+/// Mark every record of `old_deploy_id` as superseded and append `replacement`
+///
+/// The file is rewritten under its lock. Records are edited as raw JSON so
+/// fields unknown to this CLI survive, and lines that fail to parse are kept
+/// verbatim. `replacement` is written with `supersedes` set to the old ID.
+/// Returns the number of records that were marked.
+pub fn supersede(
+    path: &Path,
+    old_deploy_id: &str,
+    replacement: &HistoryRecord,
+) -> std::io::Result<usize> {
+    let _lock = store::FileLock::acquire(path)?;
+    let mut marked = 0;
+    let mut lines = Vec::new();
+    for (_, line) in store::read_lines(path)? {
+        let is_old = serde_json::from_str::<HistoryRecord>(&line)
+            .is_ok_and(|record| record.is_deploy(old_deploy_id));
+        let mut fields = match serde_json::from_str::<serde_json::Value>(&line) {
+            Ok(serde_json::Value::Object(fields)) if is_old => fields,
+            _ => {
+                lines.push(line);
+                continue;
+            }
+        };
+        fields.insert(
+            "superseded_by".to_string(),
+            serde_json::Value::from(replacement.deploy_id.clone()),
+        );
+        lines.push(serde_json::to_string(&fields)?);
+        marked += 1;
+    }
+
+    let mut replacement = replacement.clone();
+    replacement.supersedes = Some(old_deploy_id.to_string());
+    lines.push(serde_json::to_string(&replacement)?);
+    store::rewrite_lines(path, HISTORY_SCHEMA_VERSION, &lines)?;
+    Ok(marked)
+}
+
+/// Amounts in dust of the last `count` finalized transfers, most recent first
+pub fn recent_transfer_amounts(records: &[HistoryRecord], count: usize) -> Vec<u64> {
+    records
+        .iter()
+        .rev()
+        .filter(|r| r.command == "transfer" && r.outcome == HistoryOutcome::Finalized)
+        .filter_map(|r| {
+            r.amount_dust
+                .or_else(|| r.amount.map(|tokens| tokens.saturating_mul(DUST_FACTOR)))
+        })
+        .take(count)
+        .collect()
+}
+
+/// Records newer than `since` ago, oldest first
+pub fn filter_since(records: Vec<HistoryRecord>, since: Duration) -> Vec<HistoryRecord> {
+    let cutoff = now_millis() - since.as_millis() as i64;
+    records
+        .into_iter()
+        .filter(|r| r.timestamp >= cutoff)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn sample() -> HistoryRecord {
+        let mut record =
+            HistoryRecord::new("deploy-and-wait", "localhost", 40412, "new x in { x!(1) }");
+        record.deploy_id = Some("3045022100abcdef".to_string());
+        record.block_hash = Some("9f3c1a0b2d4e".to_string());
+        record.source = Some("contract.rho".to_string());
+        record.outcome = HistoryOutcome::Finalized;
+        record
+    }
+
+    #[test]
+    fn test_record_schema_roundtrip() {
+        let record = sample();
+        let line = serde_json::to_string(&record).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["command"], "deploy-and-wait");
+        assert_eq!(value["outcome"], "finalized");
+        assert_eq!(value["term_hash"].as_str().unwrap().len(), 16);
+        assert_eq!(
+            serde_json::from_str::<HistoryRecord>(&line).unwrap(),
+            record
+        );
+    }
+
+    #[test]
+    fn test_record_tolerates_missing_optional_fields() {
+        let line = r#"{"timestamp":1,"command":"deploy","host":"h","port":1,"term_hash":"00","outcome":"submitted"}"#;
+        let record: HistoryRecord = serde_json::from_str(line).unwrap();
+        assert_eq!(record.deploy_id, None);
+        assert_eq!(record.outcome, HistoryOutcome::Submitted);
+    }
+
+    #[test]
+    fn test_complete_and_fail() {
+        let result = DeployResult {
+            deploy_id: "3045".to_string(),
+            block_hash: "abcd".to_string(),
+            block_number: Some(7),
+            cost: None,
+            errored: true,
+            system_deploy_error: Some("out of phlo".to_string()),
+            data: Vec::new(),
+        };
+        let record = HistoryRecord::new("transfer", "localhost", 40412, "").complete(&result);
+        assert_eq!(record.outcome, HistoryOutcome::Errored);
+        assert_eq!(record.block_hash.as_deref(), Some("abcd"));
+        assert_eq!(record.error.as_deref(), Some("out of phlo"));
+        assert!(record.finalized_at.is_some());
+
+        let included = IncludedDeploy {
+            deploy_id: "3045".to_string(),
+            block_hash: "abcd".to_string(),
+        };
+        let record = HistoryRecord::new("deploy", "localhost", 40412, "").included(&included);
+        assert_eq!(record.outcome, HistoryOutcome::Included);
+        assert_eq!(record.block_hash.as_deref(), Some("abcd"));
+        assert_eq!(record.finalized_at, None);
+        assert_eq!(record.outcome.to_string(), "included");
+
+        let record = HistoryRecord::new("deploy", "localhost", 40412, "").fail("timeout");
+        assert_eq!(record.outcome, HistoryOutcome::Failed);
+        assert_eq!(record.error.as_deref(), Some("timeout"));
+        assert_eq!(record.finalized_at, None);
+    }
+
+    #[test]
+    fn test_verification_mismatch_outcome() {
+        let record = sample().verification_mismatch("MISMATCH (obs:40453: block not finalized)");
+        assert_eq!(record.outcome, HistoryOutcome::VerificationMismatch);
+        let line = serde_json::to_string(&record).unwrap();
+        assert!(
+            line.contains(r#""outcome":"verification_mismatch""#),
+            "{}",
+            line
+        );
+        assert_eq!(
+            serde_json::from_str::<HistoryRecord>(&line).unwrap(),
+            record
+        );
+        assert_eq!(record.outcome.to_string(), "verification_mismatch");
+    }
+
+    #[test]
+    fn test_matches_prefix() {
+        let record = sample();
+        assert!(record.matches_prefix("3045"));
+        assert!(record.matches_prefix("9F3C"));
+        assert!(!record.matches_prefix("ffff"));
+    }
+
+    #[test]
+    fn test_parse_since() {
+        assert_eq!(parse_since("90s").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_since("30m").unwrap(), Duration::from_secs(1800));
+        assert_eq!(parse_since("24h").unwrap(), Duration::from_secs(86_400));
+        assert_eq!(parse_since("7d").unwrap(), Duration::from_secs(604_800));
+        assert_eq!(parse_since("2").unwrap(), Duration::from_secs(7200));
+        assert!(parse_since("h").is_err());
+        assert!(parse_since("5y").is_err());
+    }
+
+    #[test]
+    fn test_filter_since() {
+        let mut old = sample();
+        old.timestamp -= 2 * 86_400_000;
+        let recent = sample();
+        let kept = filter_since(vec![old, recent.clone()], Duration::from_secs(86_400));
+        assert_eq!(kept, vec![recent]);
+    }
+
+    #[test]
+    fn test_recent_transfer_amounts() {
+        let transfer = |dust: u64, outcome: HistoryOutcome| {
+            let mut record = HistoryRecord::new("transfer", "localhost", 40412, "transfer");
+            record.amount_dust = Some(dust);
+            record.outcome = outcome;
+            record
+        };
+        // Written before amount_dust, in whole tokens
+        let mut legacy = transfer(0, HistoryOutcome::Finalized);
+        legacy.amount_dust = None;
+        legacy.amount = Some(1);
+        let records = [
+            legacy,
+            transfer(2, HistoryOutcome::Finalized),
+            sample(),
+            transfer(3, HistoryOutcome::Failed),
+            transfer(4, HistoryOutcome::Finalized),
+        ];
+        assert_eq!(recent_transfer_amounts(&records, 10), [4, 2, DUST_FACTOR]);
+        assert_eq!(recent_transfer_amounts(&records, 2), [4, 2]);
+    }
+
+    #[test]
+    fn test_append_and_read() {
+        let dir = std::env::temp_dir().join(format!("node_cli_history_{}", std::process::id()));
+        let path = dir.join("history.jsonl");
+        let _ = std::fs::remove_file(&path);
+
+        append_record(&path, &sample()).unwrap();
+        append_record(&path, &sample()).unwrap();
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"not json\n")
+            .unwrap();
+
+        let records = read_records(&path).unwrap();
+        assert_eq!(records.len(), 2);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_supersede_marks_old_records_and_appends_replacement() {
+        let dir = std::env::temp_dir().join(format!("node_cli_supersede_{}", std::process::id()));
+        let path = dir.join("history.jsonl");
+        let _ = std::fs::remove_file(&path);
+
+        let mut original = sample();
+        original.outcome = HistoryOutcome::Submitted;
+        original.block_hash = None;
+        append_record(&path, &original).unwrap();
+        let mut other = sample();
+        other.deploy_id = Some("3045ffff".to_string());
+        append_record(&path, &other).unwrap();
+        // Unknown fields and unparseable lines survive the rewrite
+        let mut extended = serde_json::to_value(&original).unwrap();
+        extended["label"] = serde_json::json!("nightly");
+        store::append_line(&path, HISTORY_SCHEMA_VERSION, &extended.to_string()).unwrap();
+        store::append_line(&path, HISTORY_SCHEMA_VERSION, "not json").unwrap();
+
+        let mut replacement = original.clone();
+        replacement.command = "bump-deploy".to_string();
+        replacement.deploy_id = Some("3046bump".to_string());
+        let marked = supersede(&path, "3045022100ABCDEF", &replacement).unwrap();
+        assert_eq!(marked, 2);
+
+        let records = read_records(&path).unwrap();
+        assert_eq!(records.len(), 4);
+        assert_eq!(records[0].superseded_by.as_deref(), Some("3046bump"));
+        assert_eq!(records[1].superseded_by, None);
+        assert_eq!(records[2].superseded_by.as_deref(), Some("3046bump"));
+        assert_eq!(records[3].deploy_id.as_deref(), Some("3046bump"));
+        assert_eq!(records[3].supersedes.as_deref(), Some("3045022100ABCDEF"));
+        assert_eq!(records[3].superseded_by, None);
+
+        let raw = std::fs::read_to_string(&path).unwrap();
+        assert!(raw.contains(r#""label":"nightly""#));
+        assert!(raw.contains("\nnot json\n"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_unwritable_history_does_not_fail() {
+        // A regular file used as a directory makes the write fail on every platform
+        let blocker = std::env::temp_dir().join(format!("node_cli_blocker_{}", std::process::id()));
+        std::fs::write(&blocker, b"").unwrap();
+        let path = blocker.join("history.jsonl");
+
+        assert!(!record_to(&path, &sample()));
+        let _ = std::fs::remove_file(&blocker);
+    }
+}
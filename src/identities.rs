@@ -0,0 +1,424 @@
+//! Validator identity mapping
+//!
+//! Maps validator public keys to human-readable names so commands can print
+//! `validator1 (04ffc016579a6805…)` instead of a bare key. Mappings are read
+//! from a CSV or TOML file given with `--identities`, the `FIREFLY_IDENTITIES`
+//! environment variable, or `identities.toml` / `identities.csv` in the config
+//! directory.
+//!
+//! CSV: one `pubkey,name[,address[,contact]]` row per validator. A header row
+//! starting with `pubkey` and lines starting with `#` are skipped.
+//!
+//! TOML: either a `[[validator]]` table per validator with `pubkey`, `name`,
+//! and optional `address` / `contact` string keys, or a `[validators]` table
+//! of `"<pubkey>" = "<name>"` pairs.
+
+use crate::error::{NodeCliError, Result};
+use crate::truncation::{self, Field};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Environment variable pointing at the default identities file
+pub const IDENTITIES_ENV: &str = "FIREFLY_IDENTITIES";
+
+/// Identities files looked for in the config directory, in order
+pub const DEFAULT_FILE_NAMES: [&str; 2] = ["identities.toml", "identities.csv"];
+
+/// Human-readable identity for a validator key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidatorIdentity {
+    pub pubkey: String,
+    pub name: String,
+    pub address: Option<String>,
+    pub contact: Option<String>,
+}
+
+/// How to show a validator key, with its name when one is known
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisplayName {
+    pub key: String,
+    pub name: Option<String>,
+}
+
+impl DisplayName {
+    /// Name if known, otherwise the truncated key
+    pub fn short(&self) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None => truncate_key(&self.key),
+        }
+    }
+
+    /// Name alongside the truncated key, or just the truncated key when unknown
+    pub fn with_key(&self) -> String {
+        match &self.name {
+            Some(name) => format!("{} ({})", name, truncate_key(&self.key)),
+            None => truncate_key(&self.key),
+        }
+    }
+
+    /// Fit into a fixed-width column: the name or the key, cut with an ellipsis
+    pub fn fit(&self, width: usize) -> String {
+        truncate_with_ellipsis(self.name.as_deref().unwrap_or(&self.key), width)
+    }
+}
+
+impl std::fmt::Display for DisplayName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.with_key())
+    }
+}
+
+/// Shorten a long key under the `--truncate`/`--full` policy
+pub fn truncate_key(key: &str) -> String {
+    truncation::shorten(key, Field::Key)
+}
+
+/// Cut `s` to at most `width` characters, ending in `…` when shortened
+pub fn truncate_with_ellipsis(s: &str, width: usize) -> String {
+    if s.chars().count() <= width {
+        return s.to_string();
+    }
+    if width == 0 {
+        return String::new();
+    }
+    let mut out: String = s.chars().take(width - 1).collect();
+    out.push('…');
+    out
+}
+
+/// Loaded pubkey → identity mapping; empty when no file is configured
+#[derive(Debug, Clone, Default)]
+pub struct Identities {
+    by_key: HashMap<String, ValidatorIdentity>,
+}
+
+impl Identities {
+    /// Load from an explicit file, or fall back to the environment / config directory
+    ///
+    /// An explicit or env-configured file that cannot be read is an error; a
+    /// missing default file just yields an empty mapping.
+    pub fn load_or_default(path: Option<&Path>) -> Result<Self> {
+        if let Some(path) = path {
+            return Self::load(path);
+        }
+        if let Ok(path) = std::env::var(IDENTITIES_ENV) {
+            if !path.is_empty() {
+                return Self::load(Path::new(&path));
+            }
+        }
+        match Self::default_path() {
+            Some(path) => Self::load(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// First existing default identities file in the config directory
+    pub fn default_path() -> Option<PathBuf> {
+        let dir = crate::utils::config_dir()?;
+        DEFAULT_FILE_NAMES
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|path| path.is_file())
+    }
+
+    /// Load a CSV or TOML identities file, chosen by extension
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            NodeCliError::file_read_failed(&path.display().to_string(), &e.to_string())
+        })?;
+        let is_csv = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+        let parsed = if is_csv {
+            Self::from_csv(&content)
+        } else {
+            Self::from_toml(&content)
+        };
+        parsed.map_err(|e| NodeCliError::config_invalid_value(&path.display().to_string(), &e))
+    }
+
+    /// Parse `pubkey,name[,address[,contact]]` rows
+    pub fn from_csv(content: &str) -> std::result::Result<Self, String> {
+        let mut identities = Self::default();
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields = split_csv_line(line).map_err(|e| format!("line {}: {}", i + 1, e))?;
+            if fields[0].eq_ignore_ascii_case("pubkey") {
+                continue;
+            }
+            if fields.len() < 2 || fields[1].is_empty() {
+                return Err(format!("line {}: expected pubkey,name", i + 1));
+            }
+            identities.insert(ValidatorIdentity {
+                pubkey: fields[0].clone(),
+                name: fields[1].clone(),
+                address: fields.get(2).filter(|v| !v.is_empty()).cloned(),
+                contact: fields.get(3).filter(|v| !v.is_empty()).cloned(),
+            })?;
+        }
+        Ok(identities)
+    }
+
+    /// Parse the `[[validator]]` / `[validators]` TOML subset described in the module docs
+    pub fn from_toml(content: &str) -> std::result::Result<Self, String> {
+        enum Section {
+            None,
+            Validator(HashMap<String, String>),
+            Validators,
+        }
+
+        let mut identities = Self::default();
+        let mut section = Section::None;
+
+        fn finish(
+            identities: &mut Identities,
+            fields: HashMap<String, String>,
+        ) -> std::result::Result<(), String> {
+            let get = |k: &str| fields.get(k).filter(|v| !v.is_empty()).cloned();
+            let pubkey = get("pubkey").ok_or("[[validator]] entry is missing pubkey")?;
+            let name =
+                get("name").ok_or_else(|| format!("validator {} is missing name", pubkey))?;
+            identities.insert(ValidatorIdentity {
+                pubkey,
+                name,
+                address: get("address"),
+                contact: get("contact"),
+            })
+        }
+
+        for (i, raw) in content.lines().enumerate() {
+            let line = strip_toml_comment(raw).trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if line.starts_with('[') {
+                if let Section::Validator(fields) = std::mem::replace(&mut section, Section::None) {
+                    finish(&mut identities, fields)?;
+                }
+                section = match line {
+                    "[[validator]]" => Section::Validator(HashMap::new()),
+                    "[validators]" => Section::Validators,
+                    other => return Err(format!("line {}: unsupported table {}", i + 1, other)),
+                };
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {}: expected key = \"value\"", i + 1))?;
+            let key = unquote(key.trim());
+            let value = parse_toml_string(value.trim())
+                .ok_or_else(|| format!("line {}: value must be a quoted string", i + 1))?;
+
+            match &mut section {
+                Section::Validator(fields) => {
+                    fields.insert(key, value);
+                }
+                Section::Validators => identities.insert(ValidatorIdentity {
+                    pubkey: key,
+                    name: value,
+                    address: None,
+                    contact: None,
+                })?,
+                Section::None => {
+                    return Err(format!("line {}: key outside of a table", i + 1));
+                }
+            }
+        }
+
+        if let Section::Validator(fields) = section {
+            finish(&mut identities, fields)?;
+        }
+        Ok(identities)
+    }
+
+    fn insert(&mut self, identity: ValidatorIdentity) -> std::result::Result<(), String> {
+        let key = identity.pubkey.trim().to_ascii_lowercase();
+        if key.is_empty() {
+            return Err("empty pubkey".to_string());
+        }
+        self.by_key.insert(key, identity);
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_key.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_key.is_empty()
+    }
+
+    /// Identity for a key, matched case-insensitively
+    pub fn get(&self, pubkey: &str) -> Option<&ValidatorIdentity> {
+        self.by_key.get(&pubkey.trim().to_ascii_lowercase())
+    }
+
+    /// Display name for a validator key; unknown keys keep their key
+    pub fn resolve_validator(&self, pubkey: &str) -> DisplayName {
+        DisplayName {
+            key: pubkey.to_string(),
+            name: self.get(pubkey).map(|identity| identity.name.clone()),
+        }
+    }
+}
+
+/// Split a CSV line, honouring double-quoted fields with `""` escapes
+fn split_csv_line(line: &str) -> std::result::Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut field).trim().to_string()),
+            _ => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err("unterminated quote".to_string());
+    }
+    fields.push(field.trim().to_string());
+    Ok(fields)
+}
+
+fn strip_toml_comment(line: &str) -> &str {
+    let mut in_quotes = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            '#' if !in_quotes => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+fn parse_toml_string(value: &str) -> Option<String> {
+    let inner = value.strip_prefix('"')?.strip_suffix('"')?;
+    Some(inner.replace("\\\"", "\"").replace("\\\\", "\\"))
+}
+
+fn unquote(key: &str) -> String {
+    parse_toml_string(key).unwrap_or_else(|| key.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY1: &str = "04ffc016579a68050d655d55df4e09f04605164543e257c8e6df10361e6068a533";
+    const KEY2: &str = "04837a4cff833e3157e3135d7b40b8e1f33c6e6b5a4342eb9e9e8f0a7b5c3d2e1f";
+
+    #[test]
+    fn test_from_csv() {
+        let csv = format!(
+            "# team validators\npubkey,name,address,contact\n{},validator1,1111abc,ops@example.com\n\"{}\",\"Smith, Jane\"\n",
+            KEY1, KEY2
+        );
+        let identities = Identities::from_csv(&csv).unwrap();
+        assert_eq!(identities.len(), 2);
+
+        let v1 = identities.get(KEY1).unwrap();
+        assert_eq!(v1.name, "validator1");
+        assert_eq!(v1.address.as_deref(), Some("1111abc"));
+        assert_eq!(v1.contact.as_deref(), Some("ops@example.com"));
+        assert_eq!(identities.get(KEY2).unwrap().name, "Smith, Jane");
+
+        assert!(Identities::from_csv(KEY1).is_err());
+        assert!(Identities::from_csv("\"unterminated,name").is_err());
+    }
+
+    #[test]
+    fn test_from_toml_tables() {
+        let toml = format!(
+            r#"
+# Shard validators
+[[validator]]
+pubkey = "{}"
+name = "validator1"   # bootstrap
+contact = "ops@example.com"
+
+[[validator]]
+pubkey = "{}"
+name = "validator #2"
+"#,
+            KEY1, KEY2
+        );
+        let identities = Identities::from_toml(&toml).unwrap();
+        assert_eq!(identities.len(), 2);
+        assert_eq!(
+            identities.get(KEY1).unwrap().contact.as_deref(),
+            Some("ops@example.com")
+        );
+        assert_eq!(identities.get(KEY2).unwrap().name, "validator #2");
+    }
+
+    #[test]
+    fn test_from_toml_simple_map() {
+        let toml = format!("[validators]\n\"{}\" = \"alice\"\n", KEY1);
+        let identities = Identities::from_toml(&toml).unwrap();
+        assert_eq!(identities.get(KEY1).unwrap().name, "alice");
+
+        assert!(Identities::from_toml("name = \"x\"").is_err());
+        assert!(Identities::from_toml("[[validator]]\nname = \"x\"").is_err());
+        assert!(Identities::from_toml("[servers]").is_err());
+        assert!(Identities::from_toml("[validators]\nkey = unquoted").is_err());
+    }
+
+    #[test]
+    fn test_resolve_validator() {
+        let identities = Identities::from_csv(&format!("{},validator1", KEY1)).unwrap();
+
+        let named = identities.resolve_validator(&KEY1.to_ascii_uppercase());
+        assert_eq!(named.short(), "validator1");
+        assert_eq!(named.with_key(), "validator1 (04FFC016579A6805…)");
+
+        let unknown = identities.resolve_validator(KEY2);
+        assert_eq!(unknown.name, None);
+        assert_eq!(unknown.short(), "04837a4cff833e31…");
+        assert_eq!(unknown.with_key(), unknown.short());
+        assert_eq!(
+            Identities::default().resolve_validator("04ab").short(),
+            "04ab"
+        );
+    }
+
+    #[test]
+    fn test_fit_fixed_width() {
+        let identities =
+            Identities::from_csv(&format!("{},a-very-long-validator-name\n{},v1", KEY1, KEY2))
+                .unwrap();
+        assert_eq!(identities.resolve_validator(KEY1).fit(9), "a-very-l…");
+        assert_eq!(identities.resolve_validator(KEY1).fit(9).chars().count(), 9);
+        assert_eq!(identities.resolve_validator(KEY2).fit(9), "v1");
+        assert_eq!(
+            Identities::default().resolve_validator(KEY1).fit(8),
+            "04ffc01…"
+        );
+        assert_eq!(identities.resolve_validator(KEY1).fit(0), "");
+        assert_eq!(truncate_with_ellipsis("validätor", 5), "vali…");
+    }
+
+    #[test]
+    fn test_full_keys() {
+        let identities = Identities::from_csv(&format!("{},validator1", KEY1)).unwrap();
+        let _full = truncation::scoped::Scoped::set(truncation::Truncation::Full);
+        assert_eq!(truncate_key(KEY2), KEY2);
+        assert_eq!(
+            identities.resolve_validator(KEY1).with_key(),
+            format!("validator1 ({})", KEY1)
+        );
+    }
+}
@@ -0,0 +1,347 @@
+//! Refusing well-known dev keys against remote nodes
+//!
+//! The dev shard's keys are public: the bootstrap key is this CLI's default
+//! `--private-key`, and the docker shard's validator keys are in its README.
+//! A script written for the dev shard and pointed at a production host would
+//! sign with one of them. Before a deploy-family command signs anything,
+//! [`evaluate`] decides from the key, the target host and the topology's
+//! `environment` whether to go ahead. A refusal is overridden with
+//! `--allow-insecure-key`.
+//!
+//! The topology's `shard_id`, when set, is also compared with the shard the
+//! node reports; a mismatch only warns.
+//!
+//! Commands that only make sense on a dev shard, whatever key they sign with,
+//! use [`require_dev_target`] with the same notion of a dev node.
+
+use crate::error::{NodeCliError, Result};
+use crate::staking::StakingQueries;
+use crate::topology::Topology;
+use crate::utils::print_warning;
+use std::net::IpAddr;
+use std::path::Path;
+
+/// Well-known bootstrap validator private key used in dev/test Docker setups.
+/// NOT for production use.
+pub const DEV_PRIVATE_KEY: &str =
+    "5f668a7ee96d944a4494cc947e4005e172d7ab3461ee5538f1f2a45a835e9657";
+
+/// Keys that must not sign for a real shard, by name
+pub const INSECURE_KEYS: &[(&str, &str)] = &[
+    ("bootstrap", DEV_PRIVATE_KEY),
+    (
+        "validator1",
+        "357cdc4201a5650830e0bc5a03299a30038d9934ba4c7ab73ec164ad82471ff9",
+    ),
+    (
+        "validator2",
+        "2c02138097d019d263c1d5383fcaddb1ba6416a0f4e64e3a617fe3af45b7851d",
+    ),
+    (
+        "validator3",
+        "b67533f1f99c0ecaedb7d829e430b1c0e605bda10f339f65d5567cb5bd77cbcb",
+    ),
+    (
+        "validator4",
+        "5ff3514bf79a7d18e8dd974c699678ba63b7762ce8d78c532346e52f0ad219cd",
+    ),
+];
+
+/// Topology `environment` under which the insecure keys may be used anywhere
+pub const DEV_ENVIRONMENT: &str = "dev";
+
+/// Name of the well-known key `private_key` is, if any
+pub fn insecure_key(private_key: &str) -> Option<&'static str> {
+    let key = private_key.trim();
+    let key = key.strip_prefix("0x").unwrap_or(key);
+    INSECURE_KEYS
+        .iter()
+        .find(|(_, known)| known.eq_ignore_ascii_case(key))
+        .map(|(name, _)| *name)
+}
+
+/// Where a node is, as far as key safety goes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HostClass {
+    /// This machine: `localhost`, `127.0.0.0/8` or `::1`
+    Local,
+    Remote,
+}
+
+impl HostClass {
+    pub fn of(host: &str) -> Self {
+        let host = host.trim();
+        let unbracketed = host
+            .strip_prefix('[')
+            .and_then(|h| h.strip_suffix(']'))
+            .unwrap_or(host);
+        let local = host.eq_ignore_ascii_case("localhost")
+            || unbracketed
+                .parse::<IpAddr>()
+                .is_ok_and(|ip| ip.is_loopback());
+        if local {
+            HostClass::Local
+        } else {
+            HostClass::Remote
+        }
+    }
+}
+
+/// Whether a command may sign
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyDecision {
+    Allow,
+    /// Go ahead, but say what is happening
+    Warn(String),
+    Refuse(String),
+}
+
+/// Whether a node is somewhere dev keys and fixtures belong: a local node,
+/// or a shard whose topology `environment` is `dev`
+pub fn is_dev_target(host_class: HostClass, environment: Option<&str>) -> bool {
+    host_class == HostClass::Local
+        || environment.is_some_and(|env| env.eq_ignore_ascii_case(DEV_ENVIRONMENT))
+}
+
+/// Decide whether a key may sign for a node
+///
+/// `key` is the name from [`insecure_key`], `None` for any other key.
+/// Well-known keys are allowed against local nodes and shards marked `dev`;
+/// anywhere else they are refused unless `allow_insecure_key` is set, which
+/// turns the refusal into a warning.
+pub fn evaluate(
+    key: Option<&str>,
+    host: &str,
+    host_class: HostClass,
+    environment: Option<&str>,
+    allow_insecure_key: bool,
+) -> KeyDecision {
+    let Some(key) = key else {
+        return KeyDecision::Allow;
+    };
+    if is_dev_target(host_class, environment) {
+        return KeyDecision::Allow;
+    }
+    if allow_insecure_key {
+        return KeyDecision::Warn(format!(
+            "Signing with the well-known {} dev key against {} (--allow-insecure-key)",
+            key, host
+        ));
+    }
+    KeyDecision::Refuse(format!(
+        "Refusing to sign with the well-known {} dev key against {}, which is not a local node. \
+         Pass your own key with --private-key, set \"environment\": \"{}\" in the topology file \
+         if this is a dev shard, or pass --allow-insecure-key.",
+        key, host, DEV_ENVIRONMENT
+    ))
+}
+
+/// Refuse a dev-only command against a node that is not a dev target
+///
+/// `allowed` is set when the command's `override_flag` was given, which turns
+/// the refusal into a warning.
+pub fn require_dev_target(
+    command: &str,
+    host: &str,
+    topology: Option<&Path>,
+    override_flag: &str,
+    allowed: bool,
+) -> Result<()> {
+    let topology = Topology::active(topology)?;
+    if is_dev_target(HostClass::of(host), topology.environment.as_deref()) {
+        return Ok(());
+    }
+    if allowed {
+        print_warning(&format!(
+            "Running {} against {}, which is not a dev node ({})",
+            command, host, override_flag
+        ));
+        return Ok(());
+    }
+    Err(NodeCliError::General(format!(
+        "Refusing to run {} against {}, which is not a local node. Set \"environment\": \"{}\" \
+         in the topology file if this is a dev shard, or pass {}.",
+        command, host, DEV_ENVIRONMENT, override_flag
+    )))
+}
+
+/// Warning when the node's shard is not the one the topology expects
+pub fn shard_mismatch(expected: Option<&str>, actual: &str, host: &str) -> Option<String> {
+    let expected = expected?;
+    (expected != actual).then(|| {
+        format!(
+            "{} reports shard '{}' but the topology expects '{}'",
+            host, actual, expected
+        )
+    })
+}
+
+/// Signing about to happen, as seen by the guard
+#[derive(Debug, Clone, Copy)]
+pub struct SigningTarget<'a> {
+    pub private_key: &'a str,
+    pub host: &'a str,
+    /// For the shard ID in `/api/status`
+    pub http_port: u16,
+    pub allow_insecure_key: bool,
+    /// `--topology`, where the command has it; otherwise the default file
+    pub topology: Option<&'a Path>,
+}
+
+/// Refuse or warn about `target` before anything is signed or sent
+///
+/// The key decision needs no network access. The shard check reads the
+/// node's status only when the topology names a shard, and a node that does
+/// not answer is left to the command itself to report.
+pub async fn check(target: &SigningTarget<'_>) -> Result<()> {
+    let topology = Topology::active(target.topology)?;
+    let decision = evaluate(
+        insecure_key(target.private_key),
+        target.host,
+        HostClass::of(target.host),
+        topology.environment.as_deref(),
+        target.allow_insecure_key,
+    );
+    match decision {
+        KeyDecision::Allow => {}
+        KeyDecision::Warn(message) => print_warning(&message),
+        KeyDecision::Refuse(message) => return Err(NodeCliError::General(message)),
+    }
+
+    if let Some(expected) = topology.shard_id.as_deref() {
+        match StakingQueries::new(target.host, target.http_port)
+            .shard_id()
+            .await
+        {
+            Ok(actual) => {
+                if let Some(warning) = shard_mismatch(Some(expected), &actual, target.host) {
+                    print_warning(&warning);
+                }
+            }
+            Err(e) => tracing::debug!("Shard check skipped: {}", e),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OWN_KEY: &str = "aaaa7ee96d944a4494cc947e4005e172d7ab3461ee5538f1f2a45a835e9657";
+
+    #[test]
+    fn test_insecure_keys_are_recognized() {
+        assert_eq!(insecure_key(DEV_PRIVATE_KEY), Some("bootstrap"));
+        assert_eq!(
+            insecure_key(" 0x5FF3514BF79A7D18E8DD974C699678BA63B7762CE8D78C532346E52F0AD219CD "),
+            Some("validator4")
+        );
+        assert_eq!(insecure_key(OWN_KEY), None);
+    }
+
+    #[test]
+    fn test_host_classes() {
+        for host in [
+            "localhost",
+            "LOCALHOST",
+            "127.0.0.1",
+            "127.1.2.3",
+            "::1",
+            "[::1]",
+        ] {
+            assert_eq!(HostClass::of(host), HostClass::Local, "{}", host);
+        }
+        for host in [
+            "node1.example.com",
+            "10.0.0.2",
+            "0.0.0.0",
+            "localhost.example.com",
+        ] {
+            assert_eq!(HostClass::of(host), HostClass::Remote, "{}", host);
+        }
+    }
+
+    #[test]
+    fn test_every_combination() {
+        for key in [Some("bootstrap"), None] {
+            for host_class in [HostClass::Local, HostClass::Remote] {
+                for environment in [Some("dev"), Some("DEV"), Some("production"), None] {
+                    for allow in [false, true] {
+                        let decision = evaluate(key, "h", host_class, environment, allow);
+                        let safe = key.is_none()
+                            || host_class == HostClass::Local
+                            || environment.is_some_and(|e| e.eq_ignore_ascii_case("dev"));
+                        let case = (key, host_class, environment, allow);
+                        match decision {
+                            KeyDecision::Allow => assert!(safe, "{:?}", case),
+                            KeyDecision::Warn(message) => {
+                                assert!(!safe && allow, "{:?}", case);
+                                assert!(message.contains("bootstrap"), "{}", message);
+                            }
+                            KeyDecision::Refuse(message) => {
+                                assert!(!safe && !allow, "{:?}", case);
+                                assert!(message.contains("--allow-insecure-key"), "{}", message);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_shard_mismatch() {
+        assert_eq!(shard_mismatch(None, "root", "h"), None);
+        assert_eq!(shard_mismatch(Some("root"), "root", "h"), None);
+        let warning = shard_mismatch(Some("root"), "mainnet", "h").unwrap();
+        assert!(warning.contains("'mainnet'"), "{}", warning);
+    }
+
+    #[tokio::test]
+    async fn test_refusal_happens_before_any_request() {
+        // Port 1 has nothing listening; a refusal must not need the node
+        let target = SigningTarget {
+            private_key: INSECURE_KEYS[0].1,
+            host: "node1.example.invalid",
+            http_port: 1,
+            allow_insecure_key: false,
+            topology: None,
+        };
+        let err = check(&target).await.unwrap_err();
+        assert!(err.to_string().contains("Refusing"), "{}", err);
+
+        let local = SigningTarget {
+            host: "localhost",
+            ..target
+        };
+        assert!(check(&local).await.is_ok());
+    }
+
+    #[test]
+    fn test_dev_only_commands() {
+        assert!(is_dev_target(HostClass::Local, None));
+        assert!(is_dev_target(HostClass::Remote, Some("DEV")));
+        assert!(!is_dev_target(HostClass::Remote, Some("prod")));
+
+        let flag = "--allow-non-dev";
+        assert!(require_dev_target("create-test-wallets", "127.0.0.1", None, flag, false).is_ok());
+        let err = require_dev_target(
+            "create-test-wallets",
+            "node1.example.invalid",
+            None,
+            flag,
+            false,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("--allow-non-dev"), "{}", err);
+        assert!(require_dev_target(
+            "create-test-wallets",
+            "node1.example.invalid",
+            None,
+            flag,
+            true
+        )
+        .is_ok());
+    }
+}
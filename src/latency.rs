@@ -0,0 +1,223 @@
+//! Per-stage timings for `latency-probe`
+//!
+//! Each probe round times the stages of the deploy pipeline (or, read-only,
+//! an exploratory deploy and a status call). [`StageTimings`] keeps the
+//! samples of each stage in the order stages were first recorded and
+//! summarizes them with [`crate::utils::stats`]. A [`Baseline`] is those
+//! summaries saved to a file, so a later run against the same node can be
+//! compared with it stage by stage.
+
+use crate::error::{NodeCliError, Result};
+use crate::store::{self, Document};
+use crate::utils::stats::{Comparison, Summary};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Deployed by each round: no state, no transfer, a few phlo
+pub const PROBE_TERM: &str = "new x in { x!(0) }";
+
+/// Deploy sent until the node accepts it into its deploy pool
+pub const STAGE_SUBMIT: &str = "submit";
+/// Propose call, with `--propose`
+pub const STAGE_PROPOSE: &str = "propose";
+/// Pool acceptance until the deploy is in a block
+pub const STAGE_INCLUSION: &str = "inclusion";
+/// Inclusion until the block is finalized
+pub const STAGE_FINALIZATION: &str = "finalization";
+/// Exploratory deploy of the probe term, read-only
+pub const STAGE_EXPLORATORY: &str = "exploratory";
+/// `/api/status` round trip, read-only
+pub const STAGE_STATUS: &str = "status";
+
+const BASELINE_SCHEMA_VERSION: u32 = 1;
+
+/// Millisecond samples per stage
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StageTimings {
+    stages: Vec<(String, Vec<u64>)>,
+}
+
+impl StageTimings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add one sample of `stage`
+    pub fn record(&mut self, stage: &str, ms: u64) {
+        match self.stages.iter_mut().find(|(name, _)| name == stage) {
+            Some((_, samples)) => samples.push(ms),
+            None => self.stages.push((stage.to_string(), vec![ms])),
+        }
+    }
+
+    /// Samples of `stage` in the order recorded
+    pub fn samples(&self, stage: &str) -> &[u64] {
+        self.stages
+            .iter()
+            .find(|(name, _)| name == stage)
+            .map_or(&[], |(_, samples)| samples.as_slice())
+    }
+
+    /// Summary of every stage, in the order stages were first recorded
+    pub fn summaries(&self) -> Vec<StageSummary> {
+        self.stages
+            .iter()
+            .filter_map(|(stage, samples)| {
+                Some(StageSummary {
+                    stage: stage.clone(),
+                    summary: Summary::from_samples(samples.iter().copied())?,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Distribution of one stage's samples
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StageSummary {
+    pub stage: String,
+    #[serde(flatten)]
+    pub summary: Summary,
+}
+
+/// A stage measured both in the baseline and now
+#[derive(Debug, Clone, PartialEq)]
+pub struct StageChange {
+    pub stage: String,
+    pub comparison: Comparison,
+}
+
+/// Stage summaries saved by an earlier run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Baseline {
+    /// Node the baseline was measured against, `host:port`
+    pub target: String,
+    pub read_only: bool,
+    /// Unix epoch milliseconds
+    pub created_at: i64,
+    pub stages: Vec<StageSummary>,
+}
+
+impl Baseline {
+    /// Saved baseline; `Ok(None)` if the file does not exist
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        let Some(document) = store::read_document(path)? else {
+            return Ok(None);
+        };
+        if document.schema_version() > BASELINE_SCHEMA_VERSION {
+            return Err(NodeCliError::config_invalid_value(
+                "baseline",
+                &format!(
+                    "{} was written by a newer node_cli (schema version {})",
+                    path.display(),
+                    document.schema_version()
+                ),
+            ));
+        }
+        document
+            .get::<Baseline>("baseline")
+            .map(Some)
+            .ok_or_else(|| {
+                NodeCliError::config_invalid_value(
+                    "baseline",
+                    &format!("{} holds no latency baseline", path.display()),
+                )
+            })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut document = Document::new(BASELINE_SCHEMA_VERSION);
+        document.set("baseline", self)?;
+        store::write_atomic(path, document.to_pretty_string()?.as_bytes())?;
+        Ok(())
+    }
+
+    /// Stages in both the baseline and `current`, in the order of `current`
+    pub fn compare(&self, current: &[StageSummary]) -> Vec<StageChange> {
+        current
+            .iter()
+            .filter_map(|now| {
+                let before = self.stages.iter().find(|s| s.stage == now.stage)?;
+                Some(StageChange {
+                    stage: now.stage.clone(),
+                    comparison: Comparison::new(before.summary, now.summary),
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summaries_keep_stage_order() {
+        let mut timings = StageTimings::new();
+        for (submit, inclusion) in [(40, 3000), (20, 1000), (30, 2000)] {
+            timings.record(STAGE_SUBMIT, submit);
+            timings.record(STAGE_INCLUSION, inclusion);
+        }
+        timings.record(STAGE_FINALIZATION, 500);
+        assert_eq!(timings.samples(STAGE_SUBMIT), [40, 20, 30]);
+        assert!(timings.samples(STAGE_PROPOSE).is_empty());
+
+        let summaries = timings.summaries();
+        let stages: Vec<&str> = summaries.iter().map(|s| s.stage.as_str()).collect();
+        assert_eq!(stages, [STAGE_SUBMIT, STAGE_INCLUSION, STAGE_FINALIZATION]);
+        assert_eq!(summaries[0].summary.min, 20);
+        assert_eq!(summaries[0].summary.p50, 30);
+        assert_eq!(summaries[1].summary.p95, 3000);
+        assert_eq!(summaries[2].summary.count, 1);
+        assert!(StageTimings::new().summaries().is_empty());
+    }
+
+    #[test]
+    fn test_compare_matches_stages_by_name() {
+        let mut before = StageTimings::new();
+        let mut after = StageTimings::new();
+        for ms in [100, 110, 120] {
+            before.record(STAGE_SUBMIT, ms);
+            before.record(STAGE_PROPOSE, ms);
+            after.record(STAGE_SUBMIT, ms * 2);
+            after.record(STAGE_INCLUSION, ms);
+        }
+        let baseline = Baseline {
+            target: "localhost:40412".to_string(),
+            read_only: false,
+            created_at: 0,
+            stages: before.summaries(),
+        };
+        let changes = baseline.compare(&after.summaries());
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].stage, STAGE_SUBMIT);
+        assert_eq!(changes[0].comparison.p50_change(), 1.0);
+        assert!(changes[0].comparison.is_regression(0.2, 3));
+    }
+
+    #[test]
+    fn test_baseline_round_trip() {
+        let dir = std::env::temp_dir().join(format!("node_cli_latency_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join("baseline.json");
+        assert_eq!(Baseline::load(&path).unwrap(), None);
+
+        let mut timings = StageTimings::new();
+        timings.record(STAGE_STATUS, 12);
+        timings.record(STAGE_EXPLORATORY, 80);
+        let baseline = Baseline {
+            target: "localhost:40412".to_string(),
+            read_only: true,
+            created_at: 1_700_000_000_000,
+            stages: timings.summaries(),
+        };
+        baseline.save(&path).unwrap();
+        assert_eq!(Baseline::load(&path).unwrap(), Some(baseline));
+
+        std::fs::write(&path, r#"{"schema_version": 1}"#).unwrap();
+        assert!(Baseline::load(&path).is_err());
+        std::fs::write(&path, r#"{"schema_version": 2, "baseline": {}}"#).unwrap();
+        assert!(Baseline::load(&path).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
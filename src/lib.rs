@@ -1,14 +1,58 @@
 // Library modules
+pub mod balance_cache;
+pub mod batch;
+pub mod batch_transfer;
+pub mod block_follow;
+pub mod block_groups;
+pub mod capabilities;
+pub mod chain_stats;
+pub mod confirmations;
 pub mod connection_manager;
+pub mod contract_templates;
+pub mod cross_node;
+pub mod equivocation;
 pub mod error;
 pub mod events;
+pub mod export;
 pub mod f1r3fly_api;
+pub mod funder_audit;
 pub mod grpc;
+pub mod history;
+pub mod identities;
+pub mod key_guard;
+pub mod latency;
+pub mod load_batches;
+pub mod maintenance;
+pub mod notify;
+pub mod payload;
+pub mod pinned;
+pub mod ports;
+pub mod prefix;
+pub mod preflight;
+pub mod query_cache;
 pub mod registry;
 pub mod rholang_helpers;
 pub mod signing;
+pub mod staking;
+pub mod store;
+pub mod term_analysis;
+pub mod test_wallets;
+pub mod timeline;
+pub mod timings;
+pub mod topology;
+pub mod transfer_guard;
+pub mod truncation;
 pub mod utils;
+pub mod validator_chain;
+pub mod validator_keys;
+pub mod validator_status;
 pub mod vault;
+pub mod verification;
+pub mod wallet_watch;
+pub mod webhook;
+
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_support;
 
 // CLI modules (behind "cli" feature)
 #[cfg(feature = "cli")]
@@ -16,14 +60,25 @@ pub mod args;
 #[cfg(feature = "cli")]
 pub mod commands;
 #[cfg(feature = "cli")]
-pub mod dag;
-#[cfg(feature = "cli")]
 pub mod dispatcher;
+#[cfg(feature = "cli")]
+pub mod effective_config;
+
+// WebSocket subscriptions (behind "ws" feature)
+#[cfg(feature = "ws")]
+pub mod chain_head;
+
+// Terminal UIs (behind "tui" feature)
+#[cfg(feature = "tui")]
+pub mod dag;
+#[cfg(feature = "tui")]
+pub mod dashboard;
 
 // Re-export primary types
 pub use connection_manager::{ConnectionConfig, ConnectionError, F1r3flyConnectionManager};
 pub use error::{NodeCliError, Result};
+#[cfg(feature = "ws")]
 pub use events::NodeEvents;
-pub use f1r3fly_api::{DeployDetail, DeployResult, F1r3flyApi, ProposeResult};
+pub use f1r3fly_api::{BlockSummary, DeployDetail, DeployResult, F1r3flyApi, ProposeResult};
 pub use grpc::query::extract_par_data;
 pub use vault::{TransferResult, DUST_FACTOR};
@@ -0,0 +1,300 @@
+//! Propose-batch accounting for `load-test --mode propose-batches`
+//!
+//! In this mode load-test benchmarks a validator's own propose path: it
+//! submits a batch of transfers back to back, proposes on the same node and
+//! reads the proposed block's deploys to count how many of its own made it
+//! in. A deploy left out stays pending and is looked for in later blocks, so
+//! each batch row counts the batch's own deploys apart from ones carried over
+//! from earlier batches. [`BatchLedger`] does that bookkeeping without any
+//! I/O.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Batch size when `--batch-size` is not given
+pub const DEFAULT_BATCH_SIZE: usize = 10;
+
+/// How load-test drives the node, for `--mode`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LoadMode {
+    /// One transfer at a time, waiting for someone to include and finalize it
+    #[default]
+    Sequential,
+    /// Batches of transfers, each followed by a propose on the same node
+    ProposeBatches,
+}
+
+impl fmt::Display for LoadMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LoadMode::Sequential => "sequential",
+            LoadMode::ProposeBatches => "propose-batches",
+        })
+    }
+}
+
+impl FromStr for LoadMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "sequential" => Ok(LoadMode::Sequential),
+            "propose-batches" => Ok(LoadMode::ProposeBatches),
+            _ => Err(format!(
+                "unknown mode '{}' (expected sequential or propose-batches)",
+                s
+            )),
+        }
+    }
+}
+
+/// Timestamp for the next deploy: the clock, but always after the previous one
+///
+/// Back-to-back deploys of the same term from one key within a millisecond
+/// would be identical and the node would drop all but one as duplicates.
+pub fn next_timestamp(previous: Option<i64>, now_millis: i64) -> i64 {
+    match previous {
+        Some(previous) if previous >= now_millis => previous + 1,
+        _ => now_millis,
+    }
+}
+
+/// A submitted deploy not yet seen in a proposed block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingDeploy {
+    pub deploy_id: String,
+    /// Batch that submitted it
+    pub batch: u32,
+}
+
+/// One batch: what was submitted and what its propose included
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BatchRow {
+    /// 1-based
+    pub batch: u32,
+    pub submitted: usize,
+    /// Deploys the node refused
+    pub rejected: usize,
+    /// This batch's deploys in the proposed block
+    pub included: usize,
+    /// Earlier batches' deploys in the proposed block
+    pub carried_included: usize,
+    /// Deploys of this and earlier batches still pending afterwards
+    pub pending_after: usize,
+    pub propose_latency: Duration,
+    pub block_hash: Option<String>,
+    /// Why the propose produced no block
+    pub skipped: Option<String>,
+}
+
+/// Totals over every batch
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BatchTotals {
+    pub batches: usize,
+    pub submitted: usize,
+    pub rejected: usize,
+    /// Own deploys found in proposed blocks, whichever batch sent them
+    pub included: usize,
+    /// Included in a later batch's block than their own
+    pub carried: usize,
+    /// Never seen in a proposed block
+    pub pending: usize,
+    pub blocks: usize,
+}
+
+/// Pending deploys and per-batch rows
+#[derive(Debug, Clone, Default)]
+pub struct BatchLedger {
+    pending: Vec<PendingDeploy>,
+    rows: Vec<BatchRow>,
+}
+
+impl BatchLedger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Open the next batch and return its number
+    pub fn start_batch(&mut self) -> u32 {
+        let batch = self.rows.len() as u32 + 1;
+        self.rows.push(BatchRow {
+            batch,
+            ..BatchRow::default()
+        });
+        batch
+    }
+
+    fn current(&mut self) -> &mut BatchRow {
+        if self.rows.is_empty() {
+            self.start_batch();
+        }
+        self.rows.last_mut().expect("a batch was just started")
+    }
+
+    /// A deploy of the current batch was accepted into the node's pool
+    pub fn submitted(&mut self, deploy_id: impl Into<String>) {
+        let row = self.current();
+        row.submitted += 1;
+        let batch = row.batch;
+        self.pending.push(PendingDeploy {
+            deploy_id: deploy_id.into(),
+            batch,
+        });
+    }
+
+    /// A deploy of the current batch was refused
+    pub fn rejected(&mut self) {
+        self.current().rejected += 1;
+    }
+
+    /// The current batch's propose produced `block_hash` holding `deploy_ids`
+    ///
+    /// Pending deploys found in the block are settled, whichever batch sent
+    /// them; the rest carry over to the next batch.
+    pub fn proposed(
+        &mut self,
+        block_hash: &str,
+        deploy_ids: &[String],
+        latency: Duration,
+    ) -> &BatchRow {
+        let in_block: HashSet<&str> = deploy_ids.iter().map(String::as_str).collect();
+        let batch = self.current().batch;
+        let (found, left): (Vec<_>, Vec<_>) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|p| in_block.contains(p.deploy_id.as_str()));
+        self.pending = left;
+        let own = found.iter().filter(|p| p.batch == batch).count();
+        let pending_after = self.pending.len();
+
+        let row = self.current();
+        row.included = own;
+        row.carried_included = found.len() - own;
+        row.pending_after = pending_after;
+        row.propose_latency = latency;
+        row.block_hash = Some(block_hash.to_string());
+        row
+    }
+
+    /// The current batch's propose made no block, so everything carries over
+    pub fn skipped(&mut self, reason: impl Into<String>, latency: Duration) -> &BatchRow {
+        let pending_after = self.pending.len();
+        let row = self.current();
+        row.pending_after = pending_after;
+        row.propose_latency = latency;
+        row.skipped = Some(reason.into());
+        row
+    }
+
+    pub fn rows(&self) -> &[BatchRow] {
+        &self.rows
+    }
+
+    pub fn pending(&self) -> &[PendingDeploy] {
+        &self.pending
+    }
+
+    pub fn totals(&self) -> BatchTotals {
+        let mut totals = BatchTotals {
+            batches: self.rows.len(),
+            pending: self.pending.len(),
+            ..BatchTotals::default()
+        };
+        for row in &self.rows {
+            totals.submitted += row.submitted;
+            totals.rejected += row.rejected;
+            totals.included += row.included + row.carried_included;
+            totals.carried += row.carried_included;
+            totals.blocks += usize::from(row.block_hash.is_some());
+        }
+        totals
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn test_modes() {
+        for mode in [LoadMode::Sequential, LoadMode::ProposeBatches] {
+            assert_eq!(mode.to_string().parse::<LoadMode>(), Ok(mode));
+        }
+        assert!("batches".parse::<LoadMode>().is_err());
+    }
+
+    #[test]
+    fn test_timestamps_always_increase() {
+        assert_eq!(next_timestamp(None, 1000), 1000);
+        assert_eq!(next_timestamp(Some(999), 1000), 1000);
+        assert_eq!(next_timestamp(Some(1000), 1000), 1001);
+        // A clock stepping back still yields a later timestamp
+        assert_eq!(next_timestamp(Some(1005), 1000), 1006);
+    }
+
+    #[test]
+    fn test_leftovers_carry_into_later_batches() {
+        let mut ledger = BatchLedger::new();
+        let ms = Duration::from_millis;
+
+        assert_eq!(ledger.start_batch(), 1);
+        for id in ["a1", "a2", "a3"] {
+            ledger.submitted(id);
+        }
+        ledger.rejected();
+        let row = ledger
+            .proposed("b1", &ids(&["a1", "x", "a3"]), ms(40))
+            .clone();
+        assert_eq!((row.submitted, row.rejected), (3, 1));
+        assert_eq!(
+            (row.included, row.carried_included, row.pending_after),
+            (2, 0, 1)
+        );
+        assert_eq!(ledger.pending()[0].deploy_id, "a2");
+
+        // Nothing to propose: a2 stays pending along with the new deploys
+        ledger.start_batch();
+        ledger.submitted("b1");
+        let row = ledger.skipped("NoNewDeploys", ms(5)).clone();
+        assert_eq!(row.pending_after, 2);
+        assert_eq!(row.block_hash, None);
+
+        ledger.start_batch();
+        ledger.submitted("c1");
+        ledger.submitted("c2");
+        let row = ledger
+            .proposed("b3", &ids(&["a2", "b1", "c2"]), ms(60))
+            .clone();
+        assert_eq!(row.included, 1);
+        assert_eq!(row.carried_included, 2);
+        assert_eq!(row.pending_after, 1);
+
+        let totals = ledger.totals();
+        assert_eq!(totals.batches, 3);
+        assert_eq!(totals.submitted, 6);
+        assert_eq!(totals.rejected, 1);
+        assert_eq!(totals.included, 5);
+        assert_eq!(totals.carried, 2);
+        assert_eq!(totals.pending, 1);
+        assert_eq!(totals.blocks, 2);
+        assert_eq!(totals.included + totals.pending, totals.submitted);
+    }
+
+    #[test]
+    fn test_a_deploy_is_settled_once() {
+        let mut ledger = BatchLedger::new();
+        ledger.start_batch();
+        ledger.submitted("a");
+        ledger.proposed("b1", &ids(&["a"]), Duration::ZERO);
+        ledger.start_batch();
+        // The same deploy reported again in a later block is not ours any more
+        let row = ledger.proposed("b2", &ids(&["a"]), Duration::ZERO);
+        assert_eq!((row.included, row.carried_included), (0, 0));
+        assert_eq!(ledger.totals().included, 1);
+    }
+}
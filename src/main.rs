@@ -1,17 +1,41 @@
-use clap::Parser;
-use node_cli::args::Cli;
+use clap::{CommandFactory, FromArgMatches};
+use node_cli::args::{unrecognized_subcommand, unrecognized_subcommand_message, Cli};
 use node_cli::dispatcher::Dispatcher;
-use node_cli::error::Result;
+use node_cli::effective_config::EffectiveConfig;
+use std::process::ExitCode;
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> ExitCode {
     tracing_subscriber::fmt()
         .with_env_filter(
             tracing_subscriber::EnvFilter::from_default_env()
                 .add_directive(tracing::Level::WARN.into()),
         )
+        // Logs go to stderr so `--output json` leaves only the document on stdout
+        .with_writer(std::io::stderr)
         .init();
 
-    let cli = Cli::parse();
-    Dispatcher::dispatch(&cli).await
+    let matches = match Cli::command().try_get_matches() {
+        Ok(matches) => matches,
+        Err(e) => match unrecognized_subcommand(&e) {
+            Some(typed) => {
+                eprintln!("{}", unrecognized_subcommand_message(&typed));
+                return ExitCode::from(2);
+            }
+            None => e.exit(),
+        },
+    };
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    if cli.print_config || cli.print_config_only {
+        let lines = EffectiveConfig::from_matches(&matches).lines();
+        if cli.print_config_only {
+            println!("{}", lines.join("\n"));
+            return ExitCode::SUCCESS;
+        }
+        // On stderr, so the command's own output can still be piped
+        eprintln!("{}", lines.join("\n"));
+    }
+    // Commands print their own output and the dispatcher has already reported any error
+    let result = Dispatcher::dispatch(&cli).await;
+    ExitCode::from(Dispatcher::exit_code(&result))
 }
@@ -0,0 +1,236 @@
+//! Recognising a node in maintenance (read-only) mode
+//!
+//! During an upgrade a node answers HTTP calls with `503 Service Unavailable`
+//! and a JSON body such as
+//! `{"status": "maintenance", "message": "Upgrading to v0.13.2"}`, refuses
+//! WebSocket upgrades the same way, and fails gRPC calls with `UNAVAILABLE`
+//! and a message mentioning maintenance. Those answers become
+//! [`ApiError::NodeInMaintenance`](crate::error::ApiError::NodeInMaintenance)
+//! instead of a generic connection error.
+//!
+//! One-shot commands fail on it with
+//! [`EXIT_NODE_MAINTENANCE`](crate::error::EXIT_NODE_MAINTENANCE). Polling
+//! loops keep waiting through it with [`MaintenanceWait`]: polls made while the
+//! node is in maintenance are spaced further apart and do not count against
+//! the loop's attempts.
+
+use crate::error::NodeCliError;
+use std::time::Duration;
+
+/// How much longer than its usual interval a loop waits while the node is in maintenance
+pub const MAINTENANCE_BACKOFF_FACTOR: u32 = 4;
+/// Longest wait between polls of a node in maintenance
+pub const MAX_MAINTENANCE_BACKOFF: Duration = Duration::from_secs(60);
+
+/// `status` or `mode` values of a maintenance body
+const MAINTENANCE_MODES: [&str; 3] = ["maintenance", "read-only", "readonly"];
+/// Phrases of a gRPC `UNAVAILABLE` message sent during maintenance
+const GRPC_MAINTENANCE_PHRASES: [&str; 2] = ["maintenance", "read-only mode"];
+/// Detail reported when the node gives no message of its own
+const NO_DETAIL: &str = "no details given";
+
+/// The node's explanation, if `status` and `body` are a maintenance answer
+///
+/// Only a 503 whose body is a JSON object with `"status"` or `"mode"` set to
+/// `maintenance` or `read-only`, or with `"maintenance": true` or
+/// `"readOnly": true`, counts; any other 503 is an ordinary HTTP error.
+pub fn from_http(status: u16, body: &str) -> Option<String> {
+    if status != 503 {
+        return None;
+    }
+    let json: serde_json::Value = serde_json::from_str(body.trim()).ok()?;
+    let object = json.as_object()?;
+    let mode_matches = ["status", "mode"].iter().any(|key| {
+        object
+            .get(*key)
+            .and_then(|v| v.as_str())
+            .is_some_and(|mode| {
+                MAINTENANCE_MODES
+                    .iter()
+                    .any(|known| mode.eq_ignore_ascii_case(known))
+            })
+    });
+    let flagged = ["maintenance", "readOnly"]
+        .iter()
+        .any(|key| object.get(*key).and_then(|v| v.as_bool()) == Some(true));
+    if !mode_matches && !flagged {
+        return None;
+    }
+    let detail = ["message", "reason"]
+        .iter()
+        .find_map(|key| object.get(*key).and_then(|v| v.as_str()))
+        .map(str::trim)
+        .filter(|message| !message.is_empty())
+        .unwrap_or(NO_DETAIL);
+    Some(detail.to_string())
+}
+
+/// The node's explanation, if a gRPC failure is a maintenance answer
+///
+/// Only `UNAVAILABLE` with a message mentioning maintenance or read-only mode
+/// counts; `UNAVAILABLE` on its own usually means the node is unreachable.
+pub fn from_grpc(code: tonic::Code, message: &str) -> Option<String> {
+    if code != tonic::Code::Unavailable {
+        return None;
+    }
+    let lower = message.to_lowercase();
+    if !GRPC_MAINTENANCE_PHRASES
+        .iter()
+        .any(|phrase| lower.contains(phrase))
+    {
+        return None;
+    }
+    Some(message.trim().to_string())
+}
+
+/// The node's explanation, if a refused WebSocket upgrade is a maintenance answer
+#[cfg(feature = "ws")]
+pub fn from_ws(err: &tokio_tungstenite::tungstenite::Error) -> Option<String> {
+    match err {
+        tokio_tungstenite::tungstenite::Error::Http(response) => {
+            let body = response.body().as_deref().unwrap_or_default();
+            from_http(response.status().as_u16(), &String::from_utf8_lossy(body))
+        }
+        _ => None,
+    }
+}
+
+/// Wait between polls of a node in maintenance, for a loop that normally waits `interval`
+pub fn backoff(interval: Duration) -> Duration {
+    (interval * MAINTENANCE_BACKOFF_FACTOR).min(MAX_MAINTENANCE_BACKOFF)
+}
+
+/// A polling loop's view of whether the node is in maintenance
+///
+/// The first maintenance answer in a row is logged as a warning; later ones
+/// only at debug level, so a long upgrade prints one line rather than one per
+/// poll. The first successful poll afterwards says the node is back.
+#[derive(Debug, Default)]
+pub struct MaintenanceWait {
+    /// Polls answered with maintenance since the last successful one
+    polls: u32,
+}
+
+impl MaintenanceWait {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How long to wait before polling again, if `err` says the node is in
+    /// maintenance; `None` for any other error
+    pub fn on_error(&mut self, err: &NodeCliError, interval: Duration) -> Option<Duration> {
+        let detail = err.maintenance_detail()?;
+        let delay = backoff(interval);
+        if self.polls == 0 {
+            tracing::warn!(
+                "Node is in maintenance mode ({}), waiting... (checking every {:?})",
+                detail,
+                delay
+            );
+        } else {
+            tracing::debug!(polls = self.polls + 1, "Node still in maintenance mode");
+        }
+        self.polls += 1;
+        Some(delay)
+    }
+
+    /// A poll got through; note it if the node was in maintenance
+    pub fn on_success(&mut self) {
+        if self.polls > 0 {
+            tracing::warn!(
+                "Node is out of maintenance mode after {} poll(s), resuming",
+                self.polls
+            );
+            self.polls = 0;
+        }
+    }
+
+    /// Whether the last poll found the node in maintenance
+    pub fn in_maintenance(&self) -> bool {
+        self.polls > 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_http_maintenance_bodies() {
+        let maintenance = [
+            (
+                r#"{"status":"maintenance","message":"Upgrading to v0.13.2"}"#,
+                "Upgrading to v0.13.2",
+            ),
+            (
+                r#"{"mode": "READ-ONLY", "reason": "block store migration "}"#,
+                "block store migration",
+            ),
+            (r#"{"maintenance": true}"#, NO_DETAIL),
+            (r#" {"readOnly": true, "message": ""} "#, NO_DETAIL),
+            (r#"{"status":"readonly","message":"snapshot"}"#, "snapshot"),
+        ];
+        for (body, detail) in maintenance {
+            assert_eq!(from_http(503, body).as_deref(), Some(detail), "{}", body);
+        }
+
+        let ordinary = [
+            (503, "Service Unavailable"),
+            (503, r#"{"status":"starting"}"#),
+            (503, r#"{"maintenance": false, "message": "maintenance"}"#),
+            (503, r#"["maintenance"]"#),
+            (500, r#"{"status":"maintenance"}"#),
+            (200, r#"{"status":"maintenance"}"#),
+        ];
+        for (status, body) in ordinary {
+            assert_eq!(from_http(status, body), None, "{} {}", status, body);
+        }
+    }
+
+    #[test]
+    fn test_classify_grpc_maintenance_status() {
+        assert_eq!(
+            from_grpc(
+                tonic::Code::Unavailable,
+                " Node is in maintenance mode: upgrade in progress "
+            )
+            .as_deref(),
+            Some("Node is in maintenance mode: upgrade in progress")
+        );
+        assert!(from_grpc(tonic::Code::Unavailable, "Casper is in READ-ONLY MODE").is_some());
+        assert_eq!(
+            from_grpc(tonic::Code::Unavailable, "error trying to connect"),
+            None
+        );
+        assert_eq!(from_grpc(tonic::Code::Internal, "maintenance mode"), None);
+    }
+
+    #[test]
+    fn test_backoff_is_longer_and_capped() {
+        assert_eq!(backoff(Duration::from_secs(5)), Duration::from_secs(20));
+        assert_eq!(
+            backoff(Duration::from_millis(10)),
+            Duration::from_millis(40)
+        );
+        assert_eq!(backoff(Duration::from_secs(30)), MAX_MAINTENANCE_BACKOFF);
+    }
+
+    #[test]
+    fn test_wait_tracks_maintenance_and_recovery() {
+        let mut wait = MaintenanceWait::new();
+        let interval = Duration::from_millis(10);
+        let other = NodeCliError::network_http_error(503, "Service Unavailable");
+        assert_eq!(wait.on_error(&other, interval), None);
+        assert!(!wait.in_maintenance());
+
+        let maintenance = NodeCliError::node_in_maintenance("upgrade");
+        assert_eq!(
+            wait.on_error(&maintenance, interval),
+            Some(Duration::from_millis(40))
+        );
+        assert!(wait.on_error(&maintenance, interval).is_some());
+        assert!(wait.in_maintenance());
+        wait.on_success();
+        assert!(!wait.in_maintenance());
+    }
+}
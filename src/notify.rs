@@ -0,0 +1,222 @@
+//! Completion notifications for long waits (`--notify`)
+//!
+//! When a wait for finalization ends, successfully or not, [`notify_completion`]
+//! rings the terminal bell with a bold summary line and runs the configured
+//! notifier command, if any. The command comes from `FIREFLY_NOTIFY_COMMAND`,
+//! or the `notify_command` key of `config.toml` in the config directory. It
+//! runs through `sh -c` with the message and the status (`success` or
+//! `failure`) appended as arguments, and with the same details in
+//! `FIREFLY_NOTIFY_*` environment variables.
+//!
+//! A notification never fails the operation it reports on: a missing or
+//! failing notifier is only logged at debug level.
+
+use crate::utils::{config_value, config_value_in, format_duration};
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+use std::time::Duration;
+
+/// Environment variable holding the notifier command; overrides the config file
+pub const NOTIFY_COMMAND_ENV: &str = "FIREFLY_NOTIFY_COMMAND";
+
+/// How long the notifier may run before it is abandoned
+const NOTIFIER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Hex characters of the deploy ID shown in a notification
+const DEPLOY_REF_LEN: usize = 12;
+
+/// A wait that has ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion<'a> {
+    /// CLI command that waited, e.g. `transfer`
+    pub command: &'a str,
+    pub deploy_id: Option<&'a str>,
+    pub succeeded: bool,
+    pub elapsed: Duration,
+}
+
+impl Completion<'_> {
+    pub fn status(&self) -> &'static str {
+        if self.succeeded {
+            "success"
+        } else {
+            "failure"
+        }
+    }
+
+    /// One line such as `transfer 3045022100ab succeeded after 5m 12s`
+    pub fn message(&self) -> String {
+        let mut message = self.command.to_string();
+        if let Some(deploy_id) = self.deploy_id {
+            message.push(' ');
+            message.extend(deploy_id.chars().take(DEPLOY_REF_LEN));
+        }
+        format!(
+            "{} {} after {}",
+            message,
+            if self.succeeded {
+                "succeeded"
+            } else {
+                "failed"
+            },
+            format_duration(self.elapsed)
+        )
+    }
+}
+
+/// `notify_command` from a `config.toml`, if set
+fn command_from_config(content: &str) -> Option<String> {
+    config_value_in(content, "notify_command")
+}
+
+/// The configured notifier command, if any
+pub fn notify_command() -> Option<String> {
+    if let Ok(command) = std::env::var(NOTIFY_COMMAND_ENV) {
+        if !command.is_empty() {
+            return Some(command);
+        }
+    }
+    config_value("notify_command")
+}
+
+/// Run `command` for `completion`, waiting at most [`NOTIFIER_TIMEOUT`]
+///
+/// `working_dir` is where the notifier runs, the current directory if `None`.
+pub async fn run_notifier(
+    command: &str,
+    completion: &Completion<'_>,
+    working_dir: Option<&Path>,
+) -> std::result::Result<(), String> {
+    let mut notifier = tokio::process::Command::new("sh");
+    notifier
+        .arg("-c")
+        .arg(format!("{} \"$@\"", command))
+        .arg("node_cli")
+        .arg(completion.message())
+        .arg(completion.status())
+        .env("FIREFLY_NOTIFY_MESSAGE", completion.message())
+        .env("FIREFLY_NOTIFY_STATUS", completion.status())
+        .env("FIREFLY_NOTIFY_COMMAND", completion.command)
+        .env(
+            "FIREFLY_NOTIFY_DEPLOY_ID",
+            completion.deploy_id.unwrap_or(""),
+        )
+        .env(
+            "FIREFLY_NOTIFY_ELAPSED_SECS",
+            completion.elapsed.as_secs().to_string(),
+        )
+        .stdin(std::process::Stdio::null())
+        .kill_on_drop(true);
+    if let Some(dir) = working_dir {
+        notifier.current_dir(dir);
+    }
+
+    let mut child = notifier
+        .spawn()
+        .map_err(|e| format!("could not start '{}': {}", command, e))?;
+    match tokio::time::timeout(NOTIFIER_TIMEOUT, child.wait()).await {
+        Ok(Ok(status)) if status.success() => Ok(()),
+        Ok(Ok(status)) => Err(format!("'{}' exited with {}", command, status)),
+        Ok(Err(e)) => Err(format!("'{}' failed: {}", command, e)),
+        Err(_) => Err(format!(
+            "'{}' did not finish within {}s",
+            command,
+            NOTIFIER_TIMEOUT.as_secs()
+        )),
+    }
+}
+
+/// Announce the end of a wait when `--notify` is set
+///
+/// Prints the bell and a bold summary on a terminal (a plain line otherwise),
+/// then runs the configured notifier.
+pub async fn notify_completion(enabled: bool, completion: &Completion<'_>) {
+    if !enabled {
+        return;
+    }
+    let mut stdout = std::io::stdout();
+    let line = if stdout.is_terminal() {
+        format!("\x07\x1b[1m{}\x1b[0m", completion.message())
+    } else {
+        completion.message()
+    };
+    let _ = writeln!(stdout, "{}", line);
+
+    let Some(command) = notify_command() else {
+        return;
+    };
+    if let Err(e) = run_notifier(&command, completion, None).await {
+        tracing::debug!("Notifier failed: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn completion(succeeded: bool) -> Completion<'static> {
+        Completion {
+            command: "transfer",
+            deploy_id: Some("3045022100abcdef0123456789"),
+            succeeded,
+            elapsed: Duration::from_secs(312),
+        }
+    }
+
+    #[test]
+    fn test_message_has_context() {
+        assert_eq!(
+            completion(true).message(),
+            "transfer 3045022100ab succeeded after 5m 12s"
+        );
+        let failed = Completion {
+            deploy_id: None,
+            ..completion(false)
+        };
+        assert_eq!(failed.message(), "transfer failed after 5m 12s");
+        assert_eq!(failed.status(), "failure");
+    }
+
+    #[test]
+    fn test_command_from_config() {
+        let config = "# node_cli settings\nnotify_command = \"notify-send -u critical\"\n";
+        assert_eq!(
+            command_from_config(config).as_deref(),
+            Some("notify-send -u critical")
+        );
+        assert_eq!(
+            command_from_config("notify_command = 'say'").as_deref(),
+            Some("say")
+        );
+        assert_eq!(command_from_config("[other]\nnotify_command = \"x\""), None);
+        assert_eq!(command_from_config("notify_command = \"\""), None);
+    }
+
+    #[tokio::test]
+    async fn test_notifier_receives_arguments() {
+        let dir = std::env::temp_dir().join(format!("node_cli_notify_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("notifier.sh");
+        std::fs::write(
+            &script,
+            "#!/bin/sh\nprintf '%s\\n' \"$1\" \"$2\" \"$FIREFLY_NOTIFY_DEPLOY_ID\" > received.txt\n",
+        )
+        .unwrap();
+
+        let command = format!("sh {}", script.display());
+        run_notifier(&command, &completion(false), Some(&dir))
+            .await
+            .unwrap();
+        let received = std::fs::read_to_string(dir.join("received.txt")).unwrap();
+        assert_eq!(
+            received,
+            "transfer 3045022100ab failed after 5m 12s\nfailure\n3045022100abcdef0123456789\n"
+        );
+
+        // A failing notifier is reported to the caller, which only logs it
+        assert!(run_notifier("false", &completion(true), Some(&dir))
+            .await
+            .is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
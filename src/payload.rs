@@ -0,0 +1,351 @@
+//! Deploy payloads and the language they are written in
+//!
+//! A deploy's `language` field tells the node how to interpret its term. Text
+//! languages such as Rholang are sent as-is; binary payloads such as WASM
+//! modules are sent base64-encoded, because the term is a protobuf string and
+//! raw bytes would be corrupted by a UTF-8 round-trip. A binary payload file
+//! may hold either the raw bytes or their base64 text.
+
+use crate::error::{NodeCliError, Result};
+use std::fmt;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Language used when none is given
+pub const DEFAULT_LANGUAGE: &str = "rholang";
+
+/// Languages accepted without `--language-unchecked`
+pub const KNOWN_LANGUAGES: &[&str] = &["rholang", "metta", "wasm"];
+
+/// Known languages whose payloads are source text
+const TEXT_LANGUAGES: &[&str] = &["rholang", "metta"];
+
+/// Normalize a language name, rejecting unknown names unless `unchecked`
+pub fn validate_language(name: &str, unchecked: bool) -> Result<String> {
+    let name = name.trim().to_ascii_lowercase();
+    if name.is_empty() {
+        return Err(NodeCliError::config_invalid_value(
+            "language",
+            "must not be empty",
+        ));
+    }
+    if !unchecked && !KNOWN_LANGUAGES.contains(&name.as_str()) {
+        return Err(NodeCliError::config_invalid_value(
+            "language",
+            &format!(
+                "unknown language '{}' (known: {}); pass --language-unchecked to send it anyway",
+                name,
+                KNOWN_LANGUAGES.join(", ")
+            ),
+        ));
+    }
+    Ok(name)
+}
+
+/// True when payloads in `language` are binary and travel base64-encoded
+pub fn is_binary_language(language: &str) -> bool {
+    !TEXT_LANGUAGES.contains(&language)
+}
+
+/// A deploy term ready to submit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Payload {
+    /// Term as sent to the node (base64 for binary languages)
+    pub term: String,
+    /// Size of the payload itself in bytes, before any encoding
+    pub size: usize,
+    /// Whether the term is base64-encoded binary
+    pub binary: bool,
+}
+
+impl Payload {
+    /// Build the term for `bytes` read from a payload file
+    pub fn from_bytes(bytes: Vec<u8>, language: &str) -> Result<Self> {
+        if !is_binary_language(language) {
+            let term = String::from_utf8(bytes).map_err(|_| {
+                NodeCliError::parse_error(&format!(
+                    "{} payload is not valid UTF-8 text; pick a binary --language",
+                    language
+                ))
+            })?;
+            return Ok(Self {
+                size: term.len(),
+                term,
+                binary: false,
+            });
+        }
+
+        // Files that already hold base64 text are passed through
+        if let Ok(text) = std::str::from_utf8(&bytes) {
+            if let Some(decoded) = base64_decode(text.trim()) {
+                return Ok(Self {
+                    term: text.trim().to_string(),
+                    size: decoded.len(),
+                    binary: true,
+                });
+            }
+        }
+        Ok(Self {
+            term: base64_encode(&bytes),
+            size: bytes.len(),
+            binary: true,
+        })
+    }
+
+    /// Decoded payload bytes
+    pub fn bytes(&self) -> Vec<u8> {
+        if self.binary {
+            base64_decode(&self.term).unwrap_or_default()
+        } else {
+            self.term.as_bytes().to_vec()
+        }
+    }
+}
+
+/// Read a payload file for `language` without assuming it is text
+pub fn read_payload(path: &Path, language: &str) -> Result<Payload> {
+    let bytes = std::fs::read(path)
+        .map_err(|e| NodeCliError::file_read_failed(&path.display().to_string(), &e.to_string()))?;
+    Payload::from_bytes(bytes, language)
+}
+
+/// `--file` value that reads the payload from stdin
+pub const STDIN_PATH: &str = "-";
+
+/// Where a deploy term comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    File(PathBuf),
+    Stdin,
+    /// Given on the command line with `--code`
+    Inline(String),
+}
+
+impl Source {
+    /// Source named by `--file` or `--code`, `--code` winning if both are set
+    pub fn new(file: Option<&Path>, code: Option<&str>) -> Self {
+        match (code, file) {
+            (Some(code), _) => Source::Inline(code.to_string()),
+            (None, Some(path)) if path == Path::new(STDIN_PATH) => Source::Stdin,
+            (None, Some(path)) => Source::File(path.to_path_buf()),
+            (None, None) => Source::Stdin,
+        }
+    }
+
+    /// The file read, if the term comes from one
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Source::File(path) => Some(path),
+            _ => None,
+        }
+    }
+
+    /// Read the payload for `language`
+    ///
+    /// Input that is empty, or only whitespace for a text language, is an
+    /// error rather than an empty deploy.
+    pub fn read(&self, language: &str) -> Result<Payload> {
+        self.read_from(std::io::stdin().lock(), language)
+    }
+
+    /// [`read`](Self::read), taking stdin from `stdin`
+    pub fn read_from(&self, mut stdin: impl Read, language: &str) -> Result<Payload> {
+        let bytes = match self {
+            Source::File(path) => std::fs::read(path).map_err(|e| {
+                NodeCliError::file_read_failed(&path.display().to_string(), &e.to_string())
+            })?,
+            Source::Stdin => {
+                let mut bytes = Vec::new();
+                stdin
+                    .read_to_end(&mut bytes)
+                    .map_err(|e| NodeCliError::file_read_failed("stdin", &e.to_string()))?;
+                bytes
+            }
+            Source::Inline(code) => code.clone().into_bytes(),
+        };
+        let payload = Payload::from_bytes(bytes, language)?;
+        if payload.size == 0 || (!payload.binary && payload.term.trim().is_empty()) {
+            let flag = match self {
+                Source::Inline(_) => "code",
+                _ => "file",
+            };
+            return Err(NodeCliError::config_invalid_value(
+                flag,
+                &format!("{} is empty, nothing to send", self),
+            ));
+        }
+        Ok(payload)
+    }
+}
+
+impl fmt::Display for Source {
+    /// How the source is named in progress lines and the deploy history
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::File(path) => write!(f, "{}", path.display()),
+            Source::Stdin => f.write_str("<stdin>"),
+            Source::Inline(_) => f.write_str("<inline code>"),
+        }
+    }
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Standard padded base64
+pub fn base64_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = ((chunk[0] as u32) << 16)
+            | ((*chunk.get(1).unwrap_or(&0) as u32) << 8)
+            | *chunk.get(2).unwrap_or(&0) as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64_ALPHABET[((n >> (18 - 6 * i)) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Decode standard padded base64; `None` for anything else, including empty input
+pub fn base64_decode(text: &str) -> Option<Vec<u8>> {
+    let text = text.as_bytes();
+    if text.is_empty() || text.len() % 4 != 0 {
+        return None;
+    }
+    let mut out = Vec::with_capacity(text.len() / 4 * 3);
+    let chunks = text.len() / 4;
+    for (index, chunk) in text.chunks(4).enumerate() {
+        let padding = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+        if padding > 2 || (padding > 0 && index + 1 != chunks) {
+            return None;
+        }
+        let mut n = 0u32;
+        for &c in &chunk[..4 - padding] {
+            let value = BASE64_ALPHABET.iter().position(|&a| a == c)? as u32;
+            n = (n << 6) | value;
+        }
+        n <<= 6 * padding as u32;
+        let decoded = [(n >> 16) as u8, (n >> 8) as u8, n as u8];
+        out.extend_from_slice(&decoded[..3 - padding]);
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_language() {
+        assert_eq!(validate_language("rholang", false).unwrap(), "rholang");
+        assert_eq!(validate_language(" WASM ", false).unwrap(), "wasm");
+        let err = validate_language("cobol", false).unwrap_err();
+        assert!(err.to_string().contains("--language-unchecked"));
+        assert_eq!(validate_language("cobol", true).unwrap(), "cobol");
+        assert!(validate_language("", true).is_err());
+    }
+
+    #[test]
+    fn test_source_precedence() {
+        let path = Path::new("x.rho");
+        assert_eq!(
+            Source::new(Some(path), None),
+            Source::File(PathBuf::from("x.rho"))
+        );
+        assert_eq!(Source::new(Some(Path::new("-")), None), Source::Stdin);
+        assert_eq!(
+            Source::new(Some(path), Some("new r in { r!(1) }")),
+            Source::Inline("new r in { r!(1) }".to_string())
+        );
+        assert_eq!(Source::Stdin.to_string(), "<stdin>");
+        assert_eq!(Source::Stdin.path(), None);
+        assert_eq!(Source::new(Some(path), None).to_string(), "x.rho");
+    }
+
+    #[test]
+    fn test_source_reads_stdin_and_inline_code() {
+        let term = "new r in { r!(1 + 1) }\n";
+        let payload = Source::Stdin
+            .read_from(term.as_bytes(), DEFAULT_LANGUAGE)
+            .unwrap();
+        assert_eq!(payload.term, term);
+        assert_eq!(payload.size, term.len());
+
+        let inline = Source::Inline("new r in { r!(1 + 1) }".to_string());
+        let payload = inline
+            .read_from(std::io::empty(), DEFAULT_LANGUAGE)
+            .unwrap();
+        assert_eq!(payload.size, 22);
+
+        let bytes = [0x00, 0x61, 0x73, 0x6d];
+        let wasm = Source::Stdin.read_from(&bytes[..], "wasm").unwrap();
+        assert_eq!(wasm.bytes(), bytes);
+    }
+
+    #[test]
+    fn test_source_rejects_empty_input() {
+        let err = Source::Stdin
+            .read_from(" \n\t".as_bytes(), DEFAULT_LANGUAGE)
+            .unwrap_err();
+        assert!(err.to_string().contains("<stdin> is empty"), "{}", err);
+        let err = Source::Inline(String::new())
+            .read_from(std::io::empty(), DEFAULT_LANGUAGE)
+            .unwrap_err();
+        assert!(err.to_string().contains("'code'"), "{}", err);
+        assert!(Source::Stdin.read_from(std::io::empty(), "wasm").is_err());
+        assert!(Source::File(PathBuf::from("/nonexistent/x.rho"))
+            .read(DEFAULT_LANGUAGE)
+            .is_err());
+    }
+
+    #[test]
+    fn test_base64_round_trip() {
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foo"), "Zm9v");
+        assert_eq!(base64_decode("Zm9vYg==").unwrap(), b"foob");
+        let bytes: Vec<u8> = (0..=255).collect();
+        assert_eq!(base64_decode(&base64_encode(&bytes)).unwrap(), bytes);
+        assert!(base64_decode("Zm9").is_none());
+        assert!(base64_decode("Zg==Zm9v").is_none());
+        assert!(base64_decode("Z!9v").is_none());
+    }
+
+    #[test]
+    fn test_binary_payload_file_round_trip() {
+        let path =
+            std::env::temp_dir().join(format!("node_cli_payload_{}.wasm", std::process::id()));
+        // WASM header followed by bytes that are not valid UTF-8
+        let bytes = vec![
+            0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0xff, 0xfe, 0x80,
+        ];
+        std::fs::write(&path, &bytes).unwrap();
+
+        let payload = read_payload(&path, "wasm").unwrap();
+        assert!(payload.binary);
+        assert_eq!(payload.size, bytes.len());
+        assert_eq!(payload.bytes(), bytes);
+
+        // The same module stored as base64 text is passed through unchanged
+        std::fs::write(&path, format!("{}\n", base64_encode(&bytes))).unwrap();
+        let encoded = read_payload(&path, "wasm").unwrap();
+        assert_eq!(encoded, payload);
+
+        // A text language refuses the binary file instead of mangling it
+        std::fs::write(&path, &bytes).unwrap();
+        assert!(read_payload(&path, "rholang").is_err());
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_text_payload_is_sent_verbatim() {
+        let payload = Payload::from_bytes(b"new out in { Nil }".to_vec(), "rholang").unwrap();
+        assert!(!payload.binary);
+        assert_eq!(payload.term, "new out in { Nil }");
+        assert_eq!(payload.size, 18);
+    }
+}
@@ -0,0 +1,608 @@
+//! Exploratory deploys pinned to a reference block
+//!
+//! PoS queries resolve a reference block (the main-chain tip or the last
+//! finalized block) once and run every exploratory deploy against that hash so
+//! the results describe a single consistent state. The reference can be pruned
+//! or re-org'd away between resolving it and querying it, in which case the
+//! node reports the block as unknown or returns no data. [`PinnedQueryClient`]
+//! detects those failures, re-resolves the reference and retries, sharing the
+//! refreshed block with sibling queries running concurrently.
+//!
+//! [`PinnedQueryClient::explore_batch`] runs several queries against the same
+//! block in a single exploratory deploy (see [`crate::batch`]), falling back to
+//! one deploy per query if the node rejects the combined term.
+
+use crate::batch::{build_batch_term, demultiplex};
+use crate::error::{NodeCall, NodeCliError};
+use crate::f1r3fly_api::{BlockSummary, ExploreDeployResponse, F1r3flyApi};
+use crate::query_cache::QueryCache;
+use futures_util::future::join_all;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Default number of times a pinned query re-resolves its reference block
+pub const DEFAULT_MAX_REFRESHES: u32 = 3;
+
+const REFRESH_BACKOFF: Duration = Duration::from_millis(250);
+
+/// Start of the message of no-data errors ([`NodeCliError::no_data`]), which are retried
+const EMPTY_RESULT: &str = "empty result";
+
+/// Which block pinned queries are evaluated against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReferenceBlock {
+    /// Head of the main chain
+    #[default]
+    Tip,
+    /// Last finalized block
+    LastFinalized,
+}
+
+impl std::str::FromStr for ReferenceBlock {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "tip" => Ok(Self::Tip),
+            "lfb" | "finalized" | "last-finalized" => Ok(Self::LastFinalized),
+            other => Err(format!(
+                "unknown reference block '{}' (expected tip or lfb)",
+                other
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for ReferenceBlock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Tip => write!(f, "tip"),
+            Self::LastFinalized => write!(f, "lfb"),
+        }
+    }
+}
+
+/// The block pinned queries currently run against
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PinnedBlock {
+    pub hash: String,
+    pub number: i64,
+}
+
+/// True for errors meaning the pinned block is gone or has no state yet
+pub fn is_block_race_error(message: &str) -> bool {
+    let message = message.to_ascii_lowercase();
+    message.contains(EMPTY_RESULT)
+        || message.contains("block not found")
+        || message.contains("unknown block")
+        || message.contains("no block")
+        || message.contains("couldn't find block")
+        || message.contains("could not find block")
+        || (message.contains("404") && message.contains("block"))
+}
+
+/// Runs exploratory deploys against a shared reference block, refreshing it on block races
+pub struct PinnedQueryClient<'a> {
+    api: &'a F1r3flyApi<'a>,
+    http_port: u16,
+    reference: ReferenceBlock,
+    max_refreshes: u32,
+    refresh_backoff: Duration,
+    batch: bool,
+    current: Mutex<Option<PinnedBlock>>,
+    cache: QueryCache<serde_json::Value>,
+}
+
+impl<'a> PinnedQueryClient<'a> {
+    pub fn new(api: &'a F1r3flyApi<'a>, http_port: u16, reference: ReferenceBlock) -> Self {
+        Self {
+            api,
+            http_port,
+            reference,
+            max_refreshes: DEFAULT_MAX_REFRESHES,
+            refresh_backoff: REFRESH_BACKOFF,
+            batch: true,
+            current: Mutex::new(None),
+            cache: QueryCache::from_settings(),
+        }
+    }
+
+    pub fn with_max_refreshes(mut self, max_refreshes: u32) -> Self {
+        self.max_refreshes = max_refreshes;
+        self
+    }
+
+    /// Base delay before each refresh; grows linearly with the attempt number
+    pub fn with_refresh_backoff(mut self, backoff: Duration) -> Self {
+        self.refresh_backoff = backoff;
+        self
+    }
+
+    /// Whether [`Self::explore_batch`] combines its queries into one deploy
+    pub fn with_batching(mut self, batch: bool) -> Self {
+        self.batch = batch;
+        self
+    }
+
+    pub fn reference(&self) -> ReferenceBlock {
+        self.reference
+    }
+
+    /// The current reference block, resolving it on first use
+    pub async fn block(&self) -> Result<PinnedBlock, Box<dyn std::error::Error>> {
+        if let Some(block) = self.current.lock().unwrap().clone() {
+            return Ok(block);
+        }
+        let block = self.resolve().await?;
+        Ok(self.current.lock().unwrap().get_or_insert(block).clone())
+    }
+
+    /// Look up the reference block on the node
+    async fn resolve(&self) -> Result<PinnedBlock, Box<dyn std::error::Error>> {
+        match self.reference {
+            ReferenceBlock::Tip => {
+                let main_chain = self.api.show_main_chain(1).await?;
+                let tip = main_chain.first().ok_or("No blocks found in main chain")?;
+                Ok(PinnedBlock {
+                    hash: tip.block_hash.clone(),
+                    number: tip.block_number,
+                })
+            }
+            ReferenceBlock::LastFinalized => {
+                let url = format!(
+                    "http://{}:{}/api/last-finalized-block",
+                    self.api.node_host, self.http_port
+                );
+                let call = NodeCall::http(&url);
+                let response = reqwest::Client::new()
+                    .get(&url)
+                    .send()
+                    .await
+                    .map_err(|e| call.fail(e))?;
+                if !response.status().is_success() {
+                    return Err(call
+                        .fail(format!(
+                            "Failed to get last finalized block: HTTP {}",
+                            response.status()
+                        ))
+                        .into());
+                }
+                let json: serde_json::Value = response.json().await.map_err(|e| call.fail(e))?;
+                let block = BlockSummary::from_json(&json)
+                    .ok_or("Last finalized block response is missing blockHash")?;
+                Ok(PinnedBlock {
+                    hash: block.block_hash,
+                    number: block.block_number,
+                })
+            }
+        }
+    }
+
+    /// Replace `stale` with a freshly resolved block
+    ///
+    /// If a sibling query already refreshed past `stale`, its block is reused.
+    async fn refresh(
+        &self,
+        stale: &PinnedBlock,
+    ) -> Result<PinnedBlock, Box<dyn std::error::Error>> {
+        if let Some(current) = self.current.lock().unwrap().clone() {
+            if current.hash != stale.hash {
+                return Ok(current);
+            }
+        }
+        let fresh = self.resolve().await?;
+        *self.current.lock().unwrap() = Some(fresh.clone());
+        Ok(fresh)
+    }
+
+    /// Run `query` against the reference block, refreshing and retrying on block races
+    pub async fn run<T, F, Fut>(
+        &self,
+        label: &str,
+        mut query: F,
+    ) -> Result<T, Box<dyn std::error::Error>>
+    where
+        F: FnMut(PinnedBlock) -> Fut,
+        Fut: Future<Output = Result<T, Box<dyn std::error::Error>>>,
+    {
+        let mut refreshes = 0;
+        loop {
+            let block = self.block().await?;
+            match query(block.clone()).await {
+                Ok(value) => return Ok(value),
+                Err(e) if refreshes < self.max_refreshes && is_block_race_error(&e.to_string()) => {
+                    refreshes += 1;
+                    tokio::time::sleep(self.refresh_backoff * refreshes).await;
+                    let fresh = self.refresh(&block).await?;
+                    tracing::warn!(
+                        query = label,
+                        attempt = refreshes,
+                        stale = %block.hash,
+                        fresh = %fresh.hash,
+                        "Reference block ({}) raced the query, retrying: {}",
+                        self.reference,
+                        e
+                    );
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// gRPC exploratory deploy pinned to the reference block
+    ///
+    /// Returns the same `(data, block_info, cost)` tuple as
+    /// [`F1r3flyApi::exploratory_deploy`]; "No data returned" counts as a block race.
+    pub async fn exploratory_deploy(
+        &self,
+        label: &str,
+        rho_code: &str,
+    ) -> Result<(String, String, u64), Box<dyn std::error::Error>> {
+        self.run(label, |block| async move {
+            let result = self
+                .api
+                .exploratory_deploy(rho_code, Some(&block.hash), false)
+                .await?;
+            if result.0 == "No data returned" {
+                return Err(NodeCliError::no_data(&block.hash).into());
+            }
+            Ok(result)
+        })
+        .await
+    }
+
+    /// HTTP exploratory deploy pinned to the reference block
+    ///
+    /// Returns the raw `/api/explore-deploy-by-block-hash` response; an empty
+    /// `expr` array counts as a block race, and fails with a no-data error
+    /// once refreshing the block does not help. A failure status or a reply
+    /// without an `expr` list fails with the HTTP status and the start of the
+    /// body. Responses are cached per block.
+    pub async fn explore_deploy_http(
+        &self,
+        label: &str,
+        rho_code: &str,
+    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+        let url = format!(
+            "http://{}:{}/api/explore-deploy-by-block-hash",
+            self.api.node_host, self.http_port
+        );
+        let client = reqwest::Client::new();
+
+        self.run(label, |block| {
+            let client = client.clone();
+            let url = url.clone();
+            async move {
+                let hash = block.hash.clone();
+                self.cache
+                    .get_or_fetch(rho_code, Some(&hash), false, || {
+                        Self::explore_at(client, url, rho_code, block)
+                    })
+                    .await
+            }
+        })
+        .await
+    }
+
+    /// One explore-deploy-by-block-hash request against `block`
+    async fn explore_at(
+        client: reqwest::Client,
+        url: String,
+        rho_code: &str,
+        block: PinnedBlock,
+    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+        let body = serde_json::json!({
+            "term": rho_code,
+            "blockHash": block.hash,
+            "usePreStateHash": false
+        });
+        let call = NodeCall::http(&url);
+        let response = client
+            .post(&url)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| call.fail(e))?;
+        let status = response.status();
+        let text = response.text().await.map_err(|e| call.fail(e))?;
+        let parsed =
+            ExploreDeployResponse::parse(status.as_u16(), &text).map_err(|e| call.fail(e))?;
+        if parsed.is_empty() {
+            return Err(NodeCliError::no_data(&block.hash).into());
+        }
+        // Callers get the whole reply, including block fields the model leaves out
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    /// HTTP exploratory deploys of `terms`, batched into one round trip when enabled
+    ///
+    /// Returns one `explore_deploy_http`-shaped response per term, in order.
+    /// If the batched deploy fails (some node versions limit exploratory term
+    /// complexity) the queries are retried one deploy each.
+    pub async fn explore_batch(
+        &self,
+        label: &str,
+        terms: &[&str],
+    ) -> Result<Vec<serde_json::Value>, Box<dyn std::error::Error>> {
+        if self.batch && terms.len() > 1 {
+            match self.explore_batched(label, terms).await {
+                Ok(results) => return Ok(results),
+                Err(e) => tracing::warn!(
+                    query = label,
+                    "Batched query failed, running {} queries separately: {}",
+                    terms.len(),
+                    e
+                ),
+            }
+        }
+        join_all(
+            terms
+                .iter()
+                .map(|term| self.explore_deploy_http(label, term)),
+        )
+        .await
+        .into_iter()
+        .collect()
+    }
+
+    async fn explore_batched(
+        &self,
+        label: &str,
+        terms: &[&str],
+    ) -> Result<Vec<serde_json::Value>, Box<dyn std::error::Error>> {
+        let term = build_batch_term(terms)?;
+        let response = self.explore_deploy_http(label, &term).await?;
+        Ok(demultiplex(&response, terms.len())?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key_guard::DEV_PRIVATE_KEY;
+    use crate::test_support::spawn_http_node;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_reference_block_from_str() {
+        assert_eq!("tip".parse::<ReferenceBlock>(), Ok(ReferenceBlock::Tip));
+        assert_eq!(
+            "LFB".parse::<ReferenceBlock>(),
+            Ok(ReferenceBlock::LastFinalized)
+        );
+        assert!("head".parse::<ReferenceBlock>().is_err());
+    }
+
+    #[test]
+    fn test_is_block_race_error() {
+        assert!(is_block_race_error("Block not found: abc123"));
+        assert!(is_block_race_error(
+            "HTTP error 404 Not Found for block abc: {}"
+        ));
+        assert!(is_block_race_error("empty result at block abc"));
+        assert!(!is_block_race_error("HTTP error 500 Internal Server Error"));
+        assert!(!is_block_race_error("connection refused"));
+    }
+
+    /// Mock node whose last finalized block advances on every lookup and which
+    /// only knows the newest block for explore-deploy-by-block-hash
+    async fn spawn_racing_node(lfb_lookups: Arc<AtomicUsize>) -> u16 {
+        spawn_http_node(move |line, body| {
+            if line.contains("/api/last-finalized-block") {
+                let n = lfb_lookups.fetch_add(1, Ordering::SeqCst) + 1;
+                let block = serde_json::json!({
+                    "blockInfo": { "blockHash": format!("lfb{}", n), "blockNumber": 100 + n }
+                });
+                ("200 OK", block.to_string())
+            } else if line.contains("/api/explore-deploy-by-block-hash") {
+                let request: serde_json::Value = serde_json::from_str(body).unwrap_or_default();
+                let current = format!("lfb{}", lfb_lookups.load(Ordering::SeqCst));
+                if request["blockHash"] == current.as_str() && current != "lfb1" {
+                    let result = serde_json::json!({
+                        "expr": [{ "ExprInt": { "data": 10 } }],
+                        "block": { "blockHash": current }
+                    });
+                    ("200 OK", result.to_string())
+                } else {
+                    ("404 Not Found", "Block not found".to_string())
+                }
+            } else {
+                ("404 Not Found", "{}".to_string())
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_pinned_query_refreshes_after_block_not_found() {
+        let lfb_lookups = Arc::new(AtomicUsize::new(0));
+        let port = spawn_racing_node(lfb_lookups.clone()).await;
+        let api = F1r3flyApi::new(DEV_PRIVATE_KEY, "127.0.0.1", 1).unwrap();
+        let client = PinnedQueryClient::new(&api, port, ReferenceBlock::LastFinalized)
+            .with_refresh_backoff(Duration::from_millis(1));
+
+        let result = client
+            .explore_deploy_http("epoch-length", "@0!(10)")
+            .await
+            .unwrap();
+        assert_eq!(result["expr"][0]["ExprInt"]["data"], 10);
+        assert_eq!(lfb_lookups.load(Ordering::SeqCst), 2);
+        assert_eq!(client.block().await.unwrap().hash, "lfb2");
+        assert_eq!(client.block().await.unwrap().number, 102);
+    }
+
+    #[tokio::test]
+    async fn test_pinned_query_gives_up_after_max_refreshes() {
+        let lfb_lookups = Arc::new(AtomicUsize::new(0));
+        let port = spawn_racing_node(lfb_lookups.clone()).await;
+        let api = F1r3flyApi::new(DEV_PRIVATE_KEY, "127.0.0.1", 1).unwrap();
+        let client = PinnedQueryClient::new(&api, port, ReferenceBlock::LastFinalized)
+            .with_max_refreshes(0)
+            .with_refresh_backoff(Duration::from_millis(1));
+
+        let err = client
+            .explore_deploy_http("epoch-length", "@0!(10)")
+            .await
+            .unwrap_err();
+        assert!(is_block_race_error(&err.to_string()));
+        assert_eq!(lfb_lookups.load(Ordering::SeqCst), 1);
+    }
+
+    /// Mock node that rejects batched terms and answers single queries
+    /// with the number they send on `return`
+    async fn spawn_batch_limited_node(explore_calls: Arc<AtomicUsize>) -> u16 {
+        spawn_http_node(move |line, body| {
+            if line.contains("/api/last-finalized-block") {
+                let block = serde_json::json!({
+                    "blockInfo": { "blockHash": "lfb", "blockNumber": 7 }
+                });
+                ("200 OK", block.to_string())
+            } else if line.contains("/api/explore-deploy-by-block-hash") {
+                explore_calls.fetch_add(1, Ordering::SeqCst);
+                let request: serde_json::Value = serde_json::from_str(body).unwrap_or_default();
+                let term = request["term"].as_str().unwrap_or_default();
+                if term.contains("batchReturn") {
+                    return ("400 Bad Request", "term too complex".to_string());
+                }
+                let value = if term.contains("return!(1)") { 1 } else { 2 };
+                let result = serde_json::json!({
+                    "expr": [{ "ExprInt": { "data": value } }],
+                    "block": { "blockHash": "lfb" }
+                });
+                ("200 OK", result.to_string())
+            } else {
+                ("404 Not Found", "{}".to_string())
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_explore_batch_falls_back_to_single_queries() {
+        let explore_calls = Arc::new(AtomicUsize::new(0));
+        let port = spawn_batch_limited_node(explore_calls.clone()).await;
+        let api = F1r3flyApi::new(DEV_PRIVATE_KEY, "127.0.0.1", 1).unwrap();
+        let terms = [
+            "new return in { return!(1) }",
+            "new return in { return!(2) }",
+        ];
+
+        let client = PinnedQueryClient::new(&api, port, ReferenceBlock::LastFinalized);
+        let results = client.explore_batch("pos", &terms).await.unwrap();
+        assert_eq!(results[0]["expr"][0]["ExprInt"]["data"], 1);
+        assert_eq!(results[1]["expr"][0]["ExprInt"]["data"], 2);
+        // One rejected batch, then one deploy per query
+        assert_eq!(explore_calls.load(Ordering::SeqCst), 3);
+
+        explore_calls.store(0, Ordering::SeqCst);
+        let client =
+            PinnedQueryClient::new(&api, port, ReferenceBlock::LastFinalized).with_batching(false);
+        client.explore_batch("pos", &terms).await.unwrap();
+        assert_eq!(explore_calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_repeated_pinned_query_is_cached() {
+        let explore_calls = Arc::new(AtomicUsize::new(0));
+        let port = spawn_batch_limited_node(explore_calls.clone()).await;
+        let api = F1r3flyApi::new(DEV_PRIVATE_KEY, "127.0.0.1", 1).unwrap();
+        let client = PinnedQueryClient::new(&api, port, ReferenceBlock::LastFinalized);
+
+        for _ in 0..3 {
+            let result = client
+                .explore_deploy_http("pos", "new return in { return!(1) }")
+                .await
+                .unwrap();
+            assert_eq!(result["expr"][0]["ExprInt"]["data"], 1);
+        }
+        assert_eq!(explore_calls.load(Ordering::SeqCst), 1);
+    }
+
+    /// Mock node answering explore-deploys by the term sent: a result, no
+    /// result, or a proxy's HTML error page
+    async fn spawn_fixture_node() -> u16 {
+        spawn_http_node(|line, body| {
+            if line.contains("/api/last-finalized-block") {
+                let block = serde_json::json!({
+                    "blockInfo": { "blockHash": "lfb", "blockNumber": 7 }
+                });
+                return ("200 OK", block.to_string());
+            }
+            let request: serde_json::Value = serde_json::from_str(body).unwrap_or_default();
+            let term = request["term"].as_str().unwrap_or_default();
+            if term.contains("healthy") {
+                let result = serde_json::json!({
+                    "expr": [{ "ExprInt": { "data": 3 } }],
+                    "block": { "blockHash": "lfb", "blockNumber": 7, "bonds": [] }
+                });
+                ("200 OK", result.to_string())
+            } else if term.contains("empty") {
+                let result = serde_json::json!({ "expr": [], "block": { "blockHash": "lfb" } });
+                ("200 OK", result.to_string())
+            } else {
+                (
+                    "200 OK",
+                    "<html><body><h1>502 Bad Gateway</h1></body></html>".to_string(),
+                )
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_explore_response_fixtures() {
+        let port = spawn_fixture_node().await;
+        let api = F1r3flyApi::new(DEV_PRIVATE_KEY, "127.0.0.1", 1).unwrap();
+        let client =
+            PinnedQueryClient::new(&api, port, ReferenceBlock::LastFinalized).with_max_refreshes(0);
+
+        let result = client
+            .explore_deploy_http("pos", "new healthy in { healthy!(3) }")
+            .await
+            .unwrap();
+        assert_eq!(result["expr"][0]["ExprInt"]["data"], 3);
+        assert!(result["block"]["bonds"].is_array());
+
+        let err = NodeCliError::from(
+            client
+                .explore_deploy_http("pos", "new empty in { Nil }")
+                .await
+                .unwrap_err(),
+        );
+        assert!(err.is_no_data());
+        assert!(is_block_race_error(&err.to_string()));
+
+        let err = NodeCliError::from(
+            client
+                .explore_deploy_http("pos", "new html in { Nil }")
+                .await
+                .unwrap_err(),
+        );
+        assert!(!err.is_no_data());
+        match err.root() {
+            NodeCliError::Api(crate::error::ApiError::UnexpectedExploreResponse {
+                status,
+                snippet,
+            }) => {
+                assert_eq!(*status, 200);
+                assert!(snippet.contains("502 Bad Gateway"), "{}", snippet);
+            }
+            other => panic!("unexpected error: {}", other),
+        }
+    }
+
+    #[test]
+    fn test_unexpected_response_keeps_a_snippet() {
+        let page = format!("<html>{}</html>", "x".repeat(1000));
+        let err = ExploreDeployResponse::parse(500, &page).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("HTTP 500"), "{}", message);
+        assert!(message.len() < 300, "{}", message);
+        assert!(ExploreDeployResponse::parse(200, r#"{"block": {}}"#).is_err());
+        assert!(ExploreDeployResponse::parse(200, r#"{"expr": []}"#)
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,177 @@
+//! Ports of the standard shard
+//!
+//! The docker shard the CLI targets out of the box runs a bootstrap node,
+//! three validators and a read-only observer on one host. Each node has a
+//! block of ten ports; the ones the CLI talks to are
+//!
+//! | Node       | gRPC external | gRPC internal | HTTP  |
+//! |------------|---------------|---------------|-------|
+//! | bootstrap  | 40401         | 40402         | 40403 |
+//! | validator1 | 40411         | 40412         | 40413 |
+//! | validator2 | 40421         | 40422         | 40423 |
+//! | validator3 | 40431         | 40432         | 40433 |
+//! | observer   | 40451         | 40452         | 40453 |
+//!
+//! Command defaults are drawn from this table: commands that deploy or
+//! propose default to validator1, commands that only read default to the
+//! observer, and WebSocket event streams to the bootstrap node's HTTP port.
+//! `--node-role` points a command at any node of the table.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// Ports of one node in the standard shard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShardNode {
+    pub role: NodeRole,
+    /// Public API: deploys and queries
+    pub grpc_external: u16,
+    /// Public API plus propose; what the CLI's gRPC defaults use
+    pub grpc_internal: u16,
+    /// HTTP API and WebSocket events
+    pub http: u16,
+}
+
+pub const BOOTSTRAP: ShardNode = ShardNode {
+    role: NodeRole::Bootstrap,
+    grpc_external: 40401,
+    grpc_internal: 40402,
+    http: 40403,
+};
+
+pub const VALIDATOR1: ShardNode = ShardNode {
+    role: NodeRole::Validator1,
+    grpc_external: 40411,
+    grpc_internal: 40412,
+    http: 40413,
+};
+
+pub const VALIDATOR2: ShardNode = ShardNode {
+    role: NodeRole::Validator2,
+    grpc_external: 40421,
+    grpc_internal: 40422,
+    http: 40423,
+};
+
+pub const VALIDATOR3: ShardNode = ShardNode {
+    role: NodeRole::Validator3,
+    grpc_external: 40431,
+    grpc_internal: 40432,
+    http: 40433,
+};
+
+pub const OBSERVER: ShardNode = ShardNode {
+    role: NodeRole::Observer,
+    grpc_external: 40451,
+    grpc_internal: 40452,
+    http: 40453,
+};
+
+/// Every node of the standard shard, bootstrap first
+pub const STANDARD_SHARD: [ShardNode; 5] =
+    [BOOTSTRAP, VALIDATOR1, VALIDATOR2, VALIDATOR3, OBSERVER];
+
+/// The standard shard node `port` belongs to, gRPC or HTTP
+pub fn node_with_port(port: u16) -> Option<ShardNode> {
+    STANDARD_SHARD
+        .into_iter()
+        .find(|node| [node.grpc_external, node.grpc_internal, node.http].contains(&port))
+}
+
+/// The two nodes, when `grpc_port` and `http_port` are ports of different
+/// standard shard nodes; ports outside the table never conflict
+pub fn split_nodes(grpc_port: u16, http_port: u16) -> Option<(ShardNode, ShardNode)> {
+    let grpc_node = node_with_port(grpc_port)?;
+    let http_node = node_with_port(http_port)?;
+    (grpc_node != http_node).then_some((grpc_node, http_node))
+}
+
+/// A node of the standard shard, for `--node-role`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeRole {
+    Bootstrap,
+    Validator1,
+    Validator2,
+    Validator3,
+    Observer,
+}
+
+impl NodeRole {
+    /// Name as given to `--node-role`
+    pub fn name(self) -> &'static str {
+        match self {
+            NodeRole::Bootstrap => "bootstrap",
+            NodeRole::Validator1 => "validator1",
+            NodeRole::Validator2 => "validator2",
+            NodeRole::Validator3 => "validator3",
+            NodeRole::Observer => "observer",
+        }
+    }
+
+    /// The node's ports
+    pub fn node(self) -> ShardNode {
+        match self {
+            NodeRole::Bootstrap => BOOTSTRAP,
+            NodeRole::Validator1 => VALIDATOR1,
+            NodeRole::Validator2 => VALIDATOR2,
+            NodeRole::Validator3 => VALIDATOR3,
+            NodeRole::Observer => OBSERVER,
+        }
+    }
+}
+
+impl fmt::Display for NodeRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for NodeRole {
+    type Err = String;
+
+    /// Names are matched exactly, as the port defaults they select are
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        STANDARD_SHARD
+            .iter()
+            .map(|node| node.role)
+            .find(|role| role.name() == s)
+            .ok_or_else(|| {
+                format!(
+                    "unknown node role '{}' (expected bootstrap, validator1, validator2, validator3 or observer)",
+                    s
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matrix_layout() {
+        for node in STANDARD_SHARD {
+            assert_eq!(node.role.node(), node);
+            assert_eq!(node.role.name().parse::<NodeRole>(), Ok(node.role));
+            // One block of ten ports per node
+            assert_eq!(node.grpc_internal, node.grpc_external + 1);
+            assert_eq!(node.http, node.grpc_external + 2);
+            assert_eq!(node.grpc_external % 10, 1);
+        }
+        assert!("Observer".parse::<NodeRole>().is_err());
+        assert!("validator4".parse::<NodeRole>().is_err());
+    }
+
+    #[test]
+    fn test_split_nodes() {
+        assert_eq!(node_with_port(40412), Some(VALIDATOR1));
+        assert_eq!(node_with_port(40453), Some(OBSERVER));
+        assert_eq!(node_with_port(40404), None);
+
+        assert_eq!(split_nodes(40452, 40453), None);
+        assert_eq!(split_nodes(40411, 40413), None);
+        assert_eq!(split_nodes(40412, 40453), Some((VALIDATOR1, OBSERVER)));
+        assert_eq!(split_nodes(50051, 40453), None);
+        assert_eq!(split_nodes(40412, 8080), None);
+    }
+}
@@ -0,0 +1,442 @@
+//! Prefix lookup of block hashes and deploy IDs
+//!
+//! Hashes copied from the DAG viewer or watch output are often cut to their
+//! first 8–16 characters. An identifier shorter than full length is treated as
+//! a prefix and expanded by scanning the last `--search-depth` heights below
+//! the tip: exactly one match resolves to the full identifier, more than one
+//! is an error listing the candidates, and none asks for the full hash.
+//!
+//! Block hashes and deploy IDs are both hex, so one is easily pasted where
+//! the other belongs. When a lookup fails, [`wrong_kind_hint`] says what the
+//! identifier looks like instead, and [`failed_lookup_hints`] spends one
+//! extra request checking whether it is that.
+
+use crate::chain_stats::fetch_headers;
+use crate::error::{NodeCliError, Result};
+use crate::export::{ChainSource, DEFAULT_CHUNK_SIZE};
+use crate::f1r3fly_api::F1r3flyApi;
+use std::collections::BTreeSet;
+
+/// Heights searched for a prefix unless `--search-depth` says otherwise
+pub const DEFAULT_SEARCH_DEPTH: i64 = 500;
+
+/// Candidates listed in an ambiguity error
+const MAX_LISTED: usize = 10;
+
+/// What is being looked up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdKind {
+    /// 64 hex characters
+    BlockHash,
+    /// DER-encoded signature, about 140 hex characters
+    DeployId,
+}
+
+impl IdKind {
+    /// Length below which an identifier is taken as a prefix
+    fn full_len(self) -> usize {
+        match self {
+            IdKind::BlockHash => 64,
+            IdKind::DeployId => 128,
+        }
+    }
+
+    /// Flag the identifier was given with
+    fn field(self) -> &'static str {
+        match self {
+            IdKind::BlockHash => "block-hash",
+            IdKind::DeployId => "deploy-id",
+        }
+    }
+
+    /// Whether `id` needs resolving
+    pub fn is_prefix(self, id: &str) -> bool {
+        id.len() < self.full_len()
+    }
+
+    fn other(self) -> Self {
+        match self {
+            IdKind::BlockHash => IdKind::DeployId,
+            IdKind::DeployId => IdKind::BlockHash,
+        }
+    }
+}
+
+/// Longest deploy ID: a DER signature with both integers padded
+const MAX_DEPLOY_ID_LEN: usize = 146;
+
+/// What a full-length `id` looks like, if its length says
+///
+/// Block hashes are exactly 64 hex characters and deploy IDs 128 to 146;
+/// prefixes and anything that is not hex look like neither.
+pub fn looks_like(id: &str) -> Option<IdKind> {
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    match id.len() {
+        64 => Some(IdKind::BlockHash),
+        128..=MAX_DEPLOY_ID_LEN => Some(IdKind::DeployId),
+        _ => None,
+    }
+}
+
+/// Why a lookup of `id` as `expected` may have failed, if the identifier shows it
+pub fn wrong_kind_hint(expected: IdKind, id: &str) -> Option<String> {
+    let id = id.trim();
+    if let Some(stripped) = id.strip_prefix("0x").or_else(|| id.strip_prefix("0X")) {
+        if looks_like(stripped).is_some() {
+            return Some("drop the 0x prefix; the node expects bare hex".to_string());
+        }
+    }
+    if !id.is_empty() && !id.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Some(format!(
+            "this is not hex (length {}); block hashes and deploy IDs are hex strings",
+            id.len()
+        ));
+    }
+    match looks_like(id) {
+        Some(kind) if kind != expected => Some(match kind {
+            IdKind::DeployId => format!(
+                "this looks like a deploy ID (length {}); did you mean `get-deploy`?",
+                id.len()
+            ),
+            IdKind::BlockHash => format!(
+                "this looks like a block hash (length {}); try `is-finalized` or `blocks --block-hash`",
+                id.len()
+            ),
+        }),
+        _ => None,
+    }
+}
+
+/// Hint lines for a failed lookup of `id` as `expected`
+///
+/// When `id` looks like the other kind, a single request asks the node for
+/// it and a note says where it was found. A failed probe adds nothing.
+pub async fn failed_lookup_hints(
+    api: &F1r3flyApi<'_>,
+    http_port: u16,
+    expected: IdKind,
+    id: &str,
+) -> Vec<String> {
+    let Some(hint) = wrong_kind_hint(expected, id) else {
+        return Vec::new();
+    };
+    let mut lines = vec![format!("Hint: {}", hint)];
+    if looks_like(id) != Some(expected.other()) {
+        return lines;
+    }
+    let found = match expected.other() {
+        IdKind::DeployId => match api.get_deploy_default(id, http_port).await {
+            Ok(Some(deploy)) => Some(probe_note(
+                IdKind::DeployId,
+                deploy.get("blockHash").and_then(|v| v.as_str()),
+            )),
+            _ => None,
+        },
+        IdKind::BlockHash => match api.get_block_summary(id, http_port).await {
+            Ok(Some(block)) => Some(probe_note(
+                IdKind::BlockHash,
+                Some(&block.block_number.to_string()),
+            )),
+            _ => None,
+        },
+    };
+    lines.extend(found);
+    lines
+}
+
+/// Note for an identifier the node knows as `found`; `location` is the
+/// deploy's block hash or the block's height
+pub fn probe_note(found: IdKind, location: Option<&str>) -> String {
+    match (found, location) {
+        (IdKind::DeployId, Some(block)) => {
+            format!("Note: a deploy with this ID exists in block {}", block)
+        }
+        (IdKind::DeployId, None) => "Note: a deploy with this ID exists".to_string(),
+        (IdKind::BlockHash, Some(height)) => {
+            format!("Note: a block with this hash exists at height {}", height)
+        }
+        (IdKind::BlockHash, None) => "Note: a block with this hash exists".to_string(),
+    }
+}
+
+/// The one candidate starting with `prefix`, ignoring case
+///
+/// `depth` is only used in the not-found message.
+pub fn match_prefix<'a>(
+    kind: IdKind,
+    prefix: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+    depth: i64,
+) -> Result<String> {
+    if prefix.is_empty() || !prefix.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(NodeCliError::config_invalid_value(
+            kind.field(),
+            &format!("'{}' is not a hex prefix", prefix),
+        ));
+    }
+    let prefix = prefix.to_ascii_lowercase();
+    let matches: BTreeSet<&str> = candidates
+        .into_iter()
+        .filter(|c| c.to_ascii_lowercase().starts_with(&prefix))
+        .collect();
+    let mut matches = matches.into_iter();
+    match (matches.next(), matches.len()) {
+        (Some(only), 0) => Ok(only.to_string()),
+        (None, _) => Err(NodeCliError::config_invalid_value(
+            kind.field(),
+            &format!(
+                "no match for '{}' in the last {} blocks, supply the full hash",
+                prefix, depth
+            ),
+        )),
+        (Some(first), rest) => {
+            let mut listed: Vec<&str> = std::iter::once(first)
+                .chain(matches.by_ref().take(MAX_LISTED - 1))
+                .collect();
+            let unlisted = rest + 1 - listed.len();
+            if unlisted > 0 {
+                listed.push("...");
+            }
+            Err(NodeCliError::config_invalid_value(
+                kind.field(),
+                &format!(
+                    "'{}' matches {} identifiers: {}",
+                    prefix,
+                    rest + 1,
+                    listed.join(", ")
+                ),
+            ))
+        }
+    }
+}
+
+/// Expand `prefix` by scanning heights `tip - depth + 1..=tip`
+///
+/// Deploy IDs are looked up in the blocks that have deploys, fetching each
+/// one's detail.
+pub async fn resolve(
+    source: &ChainSource,
+    kind: IdKind,
+    prefix: &str,
+    tip: i64,
+    depth: i64,
+) -> Result<String> {
+    if depth < 1 {
+        return Err(NodeCliError::config_invalid_value(
+            "search-depth",
+            "must be at least 1",
+        ));
+    }
+    let start = (tip - depth + 1).max(0);
+    let headers = fetch_headers(source, start, tip, DEFAULT_CHUNK_SIZE).await?;
+    match kind {
+        IdKind::BlockHash => match_prefix(
+            kind,
+            prefix,
+            headers.iter().map(|h| h.block_hash.as_str()),
+            depth,
+        ),
+        IdKind::DeployId => {
+            let mut sigs = Vec::new();
+            for header in headers.iter().filter(|h| h.deploy_count > 0) {
+                let block = source.block(&header.block_hash).await?;
+                sigs.extend(block.deploys.into_iter().map(|d| d.sig));
+            }
+            match_prefix(kind, prefix, sigs.iter().map(String::as_str), depth)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key_guard::DEV_PRIVATE_KEY;
+    use crate::test_support::spawn_http_node;
+
+    const BLOCKS: [&str; 4] = [
+        "a47bdb405fc3ccba00000000000000000000000000000000000000000000000a",
+        "a47bdb4177aa0b1200000000000000000000000000000000000000000000000b",
+        "c6f93059d8bb3a0a00000000000000000000000000000000000000000000000c",
+        "207c329164cdbaaa00000000000000000000000000000000000000000000000d",
+    ];
+
+    fn message(result: Result<String>) -> String {
+        result.unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_unique_prefix() {
+        assert_eq!(
+            match_prefix(IdKind::BlockHash, "C6F930", BLOCKS, 500).unwrap(),
+            BLOCKS[2]
+        );
+        // The same block listed twice (e.g. in overlapping chunks) is one match
+        let repeated = [BLOCKS[3], BLOCKS[3]];
+        assert_eq!(
+            match_prefix(IdKind::BlockHash, "207c", repeated, 500).unwrap(),
+            BLOCKS[3]
+        );
+        assert!(IdKind::BlockHash.is_prefix("c6f93059"));
+        assert!(!IdKind::BlockHash.is_prefix(BLOCKS[2]));
+    }
+
+    #[test]
+    fn test_ambiguous_prefix() {
+        let err = message(match_prefix(IdKind::BlockHash, "a47bdb4", BLOCKS, 500));
+        assert!(err.contains("matches 2 identifiers"), "{}", err);
+        assert!(
+            err.contains(BLOCKS[0]) && err.contains(BLOCKS[1]),
+            "{}",
+            err
+        );
+
+        let many: Vec<String> = (0..12).map(|i| format!("ab{:062x}", i)).collect();
+        let err = message(match_prefix(
+            IdKind::BlockHash,
+            "ab",
+            many.iter().map(String::as_str),
+            500,
+        ));
+        assert!(err.contains("matches 12 identifiers"), "{}", err);
+        assert!(err.ends_with(", ..."), "{}", err);
+    }
+
+    #[test]
+    fn test_prefix_not_found() {
+        assert_eq!(
+            message(match_prefix(IdKind::DeployId, "3045ff", BLOCKS, 200)),
+            "Configuration error: Invalid value for 'deploy-id': no match for '3045ff' in the last 200 blocks, supply the full hash"
+        );
+        assert!(match_prefix(IdKind::BlockHash, "", BLOCKS, 500).is_err());
+        assert!(match_prefix(IdKind::BlockHash, "xyz", BLOCKS, 500).is_err());
+    }
+
+    const DEPLOY_ID: &str =
+        "3045022100e1a8b2f7c6d5e4f3a2b1c0d9e8f7a6b5c4d3e2f1a0b9c8d7e6f5a4b3c2d1e0f90220\
+                             1f2e3d4c5b6a79881f2e3d4c5b6a79881f2e3d4c5b6a79881f2e3d4c5b6a7988";
+
+    #[test]
+    fn test_looks_like() {
+        assert_eq!(DEPLOY_ID.len(), 142);
+        assert_eq!(looks_like(BLOCKS[0]), Some(IdKind::BlockHash));
+        assert_eq!(
+            looks_like(&BLOCKS[0].to_uppercase()),
+            Some(IdKind::BlockHash)
+        );
+        assert_eq!(looks_like(DEPLOY_ID), Some(IdKind::DeployId));
+        assert_eq!(looks_like(&"ab".repeat(64)), Some(IdKind::DeployId));
+        assert_eq!(looks_like(&"ab".repeat(73)), Some(IdKind::DeployId));
+        // Prefixes, lengths between the two and non-hex look like neither
+        assert_eq!(looks_like("a47bdb40"), None);
+        assert_eq!(looks_like(&"a".repeat(63)), None);
+        assert_eq!(looks_like(&"a".repeat(100)), None);
+        assert_eq!(looks_like(&"a".repeat(148)), None);
+        assert_eq!(looks_like(&format!("{}g", &BLOCKS[0][..63])), None);
+        assert_eq!(looks_like(""), None);
+    }
+
+    #[test]
+    fn test_wrong_kind_hint() {
+        let hint = wrong_kind_hint(IdKind::BlockHash, DEPLOY_ID).unwrap();
+        assert_eq!(
+            hint,
+            "this looks like a deploy ID (length 142); did you mean `get-deploy`?"
+        );
+        let hint = wrong_kind_hint(IdKind::DeployId, BLOCKS[1]).unwrap();
+        assert!(
+            hint.starts_with("this looks like a block hash (length 64)"),
+            "{}",
+            hint
+        );
+        assert!(hint.contains("`is-finalized`"), "{}", hint);
+
+        // The right kind, or a prefix, has nothing to add
+        assert_eq!(wrong_kind_hint(IdKind::BlockHash, BLOCKS[1]), None);
+        assert_eq!(wrong_kind_hint(IdKind::DeployId, DEPLOY_ID), None);
+        assert_eq!(wrong_kind_hint(IdKind::DeployId, "3045"), None);
+        assert_eq!(wrong_kind_hint(IdKind::BlockHash, &"a".repeat(100)), None);
+
+        let hint = wrong_kind_hint(IdKind::BlockHash, &format!("0x{}", BLOCKS[0])).unwrap();
+        assert!(hint.contains("0x prefix"), "{}", hint);
+        let hint = wrong_kind_hint(IdKind::DeployId, "not-a-hash").unwrap();
+        assert!(hint.starts_with("this is not hex (length 10)"), "{}", hint);
+    }
+
+    #[tokio::test]
+    async fn test_failed_lookup_probes_other_kind_once() {
+        let requests = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = requests.clone();
+        let port = spawn_http_node(move |line, _| {
+            counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            if line.contains("/api/deploy/") {
+                let body = serde_json::json!({"blockHash": BLOCKS[3], "blockNumber": 4});
+                ("200 OK", body.to_string())
+            } else {
+                ("404 Not Found", String::new())
+            }
+        })
+        .await;
+        let api = F1r3flyApi::new(DEV_PRIVATE_KEY, "127.0.0.1", 1).unwrap();
+
+        let lines = failed_lookup_hints(&api, port, IdKind::BlockHash, DEPLOY_ID).await;
+        assert_eq!(lines.len(), 2, "{:?}", lines);
+        assert_eq!(
+            lines[1],
+            format!("Note: a deploy with this ID exists in block {}", BLOCKS[3])
+        );
+        assert_eq!(requests.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        // A block the node does not know only gets the hint
+        let lines = failed_lookup_hints(&api, port, IdKind::DeployId, BLOCKS[0]).await;
+        assert_eq!(lines.len(), 1, "{:?}", lines);
+        assert_eq!(requests.load(std::sync::atomic::Ordering::SeqCst), 2);
+
+        // Nothing to hint, nothing requested
+        assert!(
+            failed_lookup_hints(&api, port, IdKind::BlockHash, BLOCKS[0])
+                .await
+                .is_empty()
+        );
+        assert_eq!(requests.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_deploy_id_over_http() {
+        let port = spawn_http_node(|line, _| {
+            let body = if line.contains("/api/blocks/") {
+                serde_json::json!([
+                    {"blockHash": BLOCKS[0], "blockNumber": 9, "deployCount": 1},
+                    {"blockHash": BLOCKS[2], "blockNumber": 10, "deployCount": 0},
+                ])
+            } else if line.contains(BLOCKS[0]) {
+                serde_json::json!({
+                    "blockInfo": {"blockHash": BLOCKS[0], "blockNumber": 9},
+                    "deploys": [{"sig": "3045022100beef"}, {"sig": "3045022100cafe"}],
+                })
+            } else {
+                return ("404 Not Found", String::new());
+            };
+            ("200 OK", body.to_string())
+        })
+        .await;
+        let source = ChainSource::new("127.0.0.1", port);
+
+        assert_eq!(
+            resolve(&source, IdKind::DeployId, "3045022100CA", 10, 5)
+                .await
+                .unwrap(),
+            "3045022100cafe"
+        );
+        assert_eq!(
+            resolve(&source, IdKind::BlockHash, "c6f9", 10, 5)
+                .await
+                .unwrap(),
+            BLOCKS[2]
+        );
+        assert!(resolve(&source, IdKind::DeployId, "3045022100", 10, 5)
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,191 @@
+//! Connectivity pre-flight for compound commands
+//!
+//! Deploy-and-wait, transfer and the staking commands deploy over gRPC and then
+//! poll over HTTP, so a wrong HTTP port only shows up after the deploy has been
+//! sent. These commands check both endpoints first. When the configured HTTP
+//! port does not answer, the ports just above the gRPC port are tried, following
+//! the standard node layout (40412 → 40413 for the internal gRPC port,
+//! 40401 → 40403 for the external one).
+
+use crate::error::{NodeCliError, Result};
+use std::time::Duration;
+
+/// Offsets from the gRPC port tried when the configured HTTP port does not answer
+pub const HTTP_PORT_OFFSETS: [u16; 2] = [1, 2];
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// HTTP port a compound command should poll, as found by the pre-flight
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HttpPortCheck {
+    /// The configured port answered
+    Configured(u16),
+    /// The configured port did not answer but this one next to the gRPC port did
+    Detected { configured: u16, detected: u16 },
+}
+
+impl HttpPortCheck {
+    pub fn port(&self) -> u16 {
+        match self {
+            HttpPortCheck::Configured(port) => *port,
+            HttpPortCheck::Detected { detected, .. } => *detected,
+        }
+    }
+}
+
+/// HTTP ports to probe, in order: the configured one, then the offsets from gRPC
+pub fn candidate_http_ports(grpc_port: u16, http_port: u16) -> Vec<u16> {
+    let mut ports = vec![http_port];
+    for offset in HTTP_PORT_OFFSETS {
+        if let Some(port) = grpc_port.checked_add(offset) {
+            if !ports.contains(&port) {
+                ports.push(port);
+            }
+        }
+    }
+    ports
+}
+
+/// Whether the gRPC endpoint accepts connections
+pub async fn grpc_reachable(host: &str, port: u16) -> bool {
+    matches!(
+        tokio::time::timeout(PROBE_TIMEOUT, tokio::net::TcpStream::connect((host, port))).await,
+        Ok(Ok(_))
+    )
+}
+
+/// Whether `host:port` answers the node's `/api/status` endpoint
+pub async fn http_status_ok(client: &reqwest::Client, host: &str, port: u16) -> bool {
+    let url = format!("http://{}:{}/api/status", host, port);
+    match client.get(&url).timeout(PROBE_TIMEOUT).send().await {
+        Ok(response) => response.status().is_success(),
+        Err(_) => false,
+    }
+}
+
+/// Check the gRPC endpoint is up and find the HTTP port that serves `/api/status`
+pub async fn check_node(host: &str, grpc_port: u16, http_port: u16) -> Result<HttpPortCheck> {
+    if !grpc_reachable(host, grpc_port).await {
+        return Err(NodeCliError::network_connection_failed(&format!(
+            "gRPC endpoint {}:{} is not reachable; check --host and --grpc-port",
+            host, grpc_port
+        )));
+    }
+
+    let client = reqwest::Client::new();
+    let candidates = candidate_http_ports(grpc_port, http_port);
+    for &port in &candidates {
+        if http_status_ok(&client, host, port).await {
+            return Ok(if port == http_port {
+                HttpPortCheck::Configured(port)
+            } else {
+                HttpPortCheck::Detected {
+                    configured: http_port,
+                    detected: port,
+                }
+            });
+        }
+    }
+
+    let probed = candidates
+        .iter()
+        .map(|p| p.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    Err(NodeCliError::network_connection_failed(&format!(
+        "no HTTP status endpoint on {} (probed ports {}); pass the node's HTTP port with --http-port",
+        host, probed
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{bind_consecutive, serve_http, spawn_http_node};
+
+    /// A port with nothing listening on it
+    async fn closed_port() -> u16 {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        listener.local_addr().unwrap().port()
+    }
+
+    fn status_node(line: &str, _body: &str) -> (&'static str, String) {
+        if line.contains("/api/status") {
+            ("200 OK", r#"{"version":"test"}"#.to_string())
+        } else {
+            ("404 Not Found", "{}".to_string())
+        }
+    }
+
+    fn not_found(_line: &str, _body: &str) -> (&'static str, String) {
+        ("404 Not Found", "{}".to_string())
+    }
+
+    /// gRPC stand-in plus the two ports above it, returning the gRPC port
+    async fn scrambled_node(
+        plus_one: fn(&str, &str) -> (&'static str, String),
+        plus_two: fn(&str, &str) -> (&'static str, String),
+    ) -> u16 {
+        let mut listeners = bind_consecutive(3).await;
+        let grpc_port = listeners[0].local_addr().unwrap().port();
+        serve_http(listeners.pop().unwrap(), plus_two);
+        serve_http(listeners.pop().unwrap(), plus_one);
+        serve_http(listeners.pop().unwrap(), not_found);
+        grpc_port
+    }
+
+    #[test]
+    fn test_candidate_ports() {
+        assert_eq!(candidate_http_ports(40412, 40413), vec![40413, 40414]);
+        assert_eq!(
+            candidate_http_ports(40401, 40413),
+            vec![40413, 40402, 40403]
+        );
+        assert_eq!(candidate_http_ports(u16::MAX, 40413), vec![40413]);
+    }
+
+    #[tokio::test]
+    async fn test_configured_port_is_kept() {
+        let grpc = spawn_http_node(not_found).await;
+        let http = spawn_http_node(status_node).await;
+        let check = check_node("127.0.0.1", grpc, http).await.unwrap();
+        assert_eq!(check, HttpPortCheck::Configured(http));
+    }
+
+    #[tokio::test]
+    async fn test_http_port_detected_next_to_grpc() {
+        let grpc = scrambled_node(not_found, status_node).await;
+        let configured = closed_port().await;
+        let check = check_node("127.0.0.1", grpc, configured).await.unwrap();
+        assert_eq!(
+            check,
+            HttpPortCheck::Detected {
+                configured,
+                detected: grpc + 2
+            }
+        );
+        assert_eq!(check.port(), grpc + 2);
+    }
+
+    #[tokio::test]
+    async fn test_failure_lists_probed_ports() {
+        let grpc = scrambled_node(not_found, not_found).await;
+        let configured = closed_port().await;
+        let err = check_node("127.0.0.1", grpc, configured)
+            .await
+            .unwrap_err()
+            .to_string();
+        for port in [configured, grpc + 1, grpc + 2] {
+            assert!(err.contains(&port.to_string()), "{}", err);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_grpc_fails_before_http() {
+        let http = spawn_http_node(status_node).await;
+        let err = check_node("127.0.0.1", closed_port().await, http)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("gRPC endpoint"), "{}", err);
+    }
+}
@@ -0,0 +1,252 @@
+//! In-memory cache of exploratory deploy results
+//!
+//! The state at a block never changes, so an exploratory deploy pinned to a
+//! block hash always returns the same result. [`QueryCache`] keeps recent
+//! results keyed by the Blake2b-256 hash of the term, the block hash and the
+//! pre/post state flag, and evicts the least recently used entry once full.
+//! Queries without a block hash run against whatever the node's tip is at the
+//! time and are never cached.
+//!
+//! Caches live for one process. `--no-query-cache` turns them off and
+//! `--query-cache-size` sets how many results each one holds. Hits and misses
+//! are added to the timing sample as `query_cache_hits` and
+//! `query_cache_misses`.
+
+use blake2::{Blake2b, Digest};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use typenum::U32;
+
+/// Results each cache holds unless `--query-cache-size` says otherwise
+pub const DEFAULT_CAPACITY: usize = 256;
+
+/// Capacity of caches created from here on; 0 disables caching
+static CAPACITY: AtomicUsize = AtomicUsize::new(DEFAULT_CAPACITY);
+
+/// Set the capacity of caches created for the rest of the process
+/// (`--no-query-cache`, `--query-cache-size`)
+pub fn configure(enabled: bool, capacity: usize) {
+    CAPACITY.store(if enabled { capacity } else { 0 }, Ordering::Relaxed);
+}
+
+/// What a cached result depends on
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct QueryKey {
+    term_hash: [u8; 32],
+    block_hash: String,
+    use_pre_state_hash: bool,
+}
+
+impl QueryKey {
+    /// Key for a query pinned to `block_hash`, or `None` for an unpinned query
+    pub fn new(term: &str, block_hash: Option<&str>, use_pre_state_hash: bool) -> Option<Self> {
+        let block_hash = block_hash.filter(|hash| !hash.is_empty())?;
+        let mut term_hash = [0u8; 32];
+        term_hash.copy_from_slice(&Blake2b::<U32>::digest(term.as_bytes()));
+        Some(Self {
+            term_hash,
+            block_hash: block_hash.to_ascii_lowercase(),
+            use_pre_state_hash,
+        })
+    }
+}
+
+/// Least recently used cache of pinned query results
+#[derive(Debug)]
+pub struct QueryCache<V> {
+    capacity: usize,
+    /// Least recently used first
+    entries: Mutex<VecDeque<(QueryKey, V)>>,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+impl<V: Clone> QueryCache<V> {
+    /// Cache holding at most `capacity` results; 0 caches nothing
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::new(VecDeque::new()),
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+        }
+    }
+
+    /// Cache sized by `--query-cache-size`, or disabled by `--no-query-cache`
+    pub fn from_settings() -> Self {
+        Self::new(CAPACITY.load(Ordering::Relaxed))
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    pub fn misses(&self) -> usize {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// Cached result for `key`, marking it most recently used
+    pub fn get(&self, key: &QueryKey) -> Option<V> {
+        let mut entries = self.entries.lock().unwrap();
+        let position = entries.iter().position(|(k, _)| k == key)?;
+        let entry = entries.remove(position)?;
+        let value = entry.1.clone();
+        entries.push_back(entry);
+        Some(value)
+    }
+
+    /// Store a result, evicting the least recently used ones past capacity
+    pub fn insert(&self, key: QueryKey, value: V) {
+        if !self.is_enabled() {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|(k, _)| *k != key);
+        entries.push_back((key, value));
+        while entries.len() > self.capacity {
+            entries.pop_front();
+        }
+    }
+
+    /// The cached result of a pinned query, or the result of `fetch`, which is
+    /// cached if it succeeds
+    ///
+    /// Unpinned queries always call `fetch` and are not counted as misses.
+    pub async fn get_or_fetch<E, F, Fut>(
+        &self,
+        term: &str,
+        block_hash: Option<&str>,
+        use_pre_state_hash: bool,
+        fetch: F,
+    ) -> Result<V, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<V, E>>,
+    {
+        let key = match QueryKey::new(term, block_hash, use_pre_state_hash) {
+            Some(key) if self.is_enabled() => key,
+            _ => return fetch().await,
+        };
+        if let Some(value) = self.get(&key) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            crate::timings::count("query_cache_hits", 1);
+            return Ok(value);
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        crate::timings::count("query_cache_misses", 1);
+        let value = fetch().await?;
+        self.insert(key, value.clone());
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLOCK: &str = "a1b2c3";
+
+    async fn lookup(
+        cache: &QueryCache<u64>,
+        term: &str,
+        block_hash: Option<&str>,
+        calls: &AtomicUsize,
+    ) -> u64 {
+        cache
+            .get_or_fetch(term, block_hash, false, || async {
+                Ok::<_, String>(calls.fetch_add(1, Ordering::SeqCst) as u64)
+            })
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_pinned_queries_hit() {
+        let cache = QueryCache::new(8);
+        let calls = AtomicUsize::new(0);
+        let first = lookup(&cache, "@0!(1)", Some(BLOCK), &calls).await;
+        assert_eq!(lookup(&cache, "@0!(1)", Some(BLOCK), &calls).await, first);
+        assert_eq!(
+            lookup(&cache, "@0!(1)", Some(&BLOCK.to_uppercase()), &calls).await,
+            first
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!((cache.hits(), cache.misses()), (2, 1));
+
+        // A different term, block or state flag is a different query
+        lookup(&cache, "@0!(2)", Some(BLOCK), &calls).await;
+        lookup(&cache, "@0!(1)", Some("d4e5f6"), &calls).await;
+        cache
+            .get_or_fetch("@0!(1)", Some(BLOCK), true, || async {
+                Ok::<_, String>(99)
+            })
+            .await
+            .unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert_eq!(cache.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_unpinned_and_failed_queries_are_not_cached() {
+        let cache = QueryCache::new(8);
+        let calls = AtomicUsize::new(0);
+        for _ in 0..3 {
+            lookup(&cache, "@0!(1)", None, &calls).await;
+            lookup(&cache, "@0!(1)", Some(""), &calls).await;
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 6);
+        assert!(cache.is_empty());
+        assert_eq!((cache.hits(), cache.misses()), (0, 0));
+
+        let failed = cache
+            .get_or_fetch("@0!(1)", Some(BLOCK), false, || async {
+                Err::<u64, _>("block not found")
+            })
+            .await;
+        assert!(failed.is_err());
+        assert!(cache.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_eviction_respects_capacity() {
+        let cache = QueryCache::new(2);
+        let calls = AtomicUsize::new(0);
+        lookup(&cache, "a", Some(BLOCK), &calls).await;
+        lookup(&cache, "b", Some(BLOCK), &calls).await;
+        // Touch "a" so "b" is the least recently used when "c" arrives
+        lookup(&cache, "a", Some(BLOCK), &calls).await;
+        lookup(&cache, "c", Some(BLOCK), &calls).await;
+        assert_eq!(cache.len(), 2);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        lookup(&cache, "a", Some(BLOCK), &calls).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        lookup(&cache, "b", Some(BLOCK), &calls).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_disabled_cache_always_fetches() {
+        let cache = QueryCache::new(0);
+        let calls = AtomicUsize::new(0);
+        lookup(&cache, "a", Some(BLOCK), &calls).await;
+        lookup(&cache, "a", Some(BLOCK), &calls).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert!(cache.is_empty());
+        assert_eq!(cache.misses(), 0);
+    }
+}
@@ -1,13 +1,16 @@
 //! F1r3fly Registry Operations
 //!
 //! Provides cryptographic functions for interacting with F1r3fly's
-//! `rho:registry:insertSigned:secp256k1` system contract.
+//! `rho:registry:insertSigned:secp256k1` system contract, the deploy term
+//! that publishes a contract at the URI of the signing key, and the
+//! exploratory term that looks a URI up.
 
+use crate::utils::CryptoUtils;
 use blake2::digest::consts::U32;
 use blake2::{Blake2b, Digest};
 use chrono::{DateTime, Utc};
 use prost::Message as _;
-use secp256k1::{Message, PublicKey, Secp256k1, SecretKey};
+use secp256k1::{Message, PublicKey, SecretKey};
 
 /// Generate a signature for `insertSigned` registry operation
 ///
@@ -67,12 +70,123 @@ pub fn generate_insert_signed_signature(
     let hash = Blake2b::<U32>::new().chain_update(par).finalize();
     let message = Message::from_digest(hash.into());
 
-    Secp256k1::new()
+    CryptoUtils::secp()
         .sign_ecdsa(message, key)
         .serialize_der()
         .to_vec()
 }
 
+/// Name the contract passed to `insertSigned` is bound to when none is given
+pub const DEFAULT_CONTRACT_NAME: &str = "contract";
+
+/// Check `name` can be bound with `new`: a letter or `_`, then letters, digits or `_`
+pub fn validate_contract_name(name: &str) -> Result<(), String> {
+    let mut chars = name.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("'{}' is not a Rholang name", name))
+    }
+}
+
+/// Deploy term that registers `contract` at the signing key's URI
+///
+/// `contract` runs in the scope of a fresh name `name`, which it is expected
+/// to listen on; a read-only bundle of that name is inserted with `version`
+/// and `signature` (from [`generate_insert_signed_signature`], for the same
+/// key and the deploy's timestamp). The URI is printed on stdout once the
+/// registry answers.
+pub fn insert_signed_term(
+    contract: &str,
+    name: &str,
+    public_key: &PublicKey,
+    version: i64,
+    signature: &[u8],
+) -> String {
+    format!(
+        r#"new {name}, insertSigned(`rho:registry:insertSigned:secp256k1`), uriOut, stdout(`rho:io:stdout`) in {{
+ {contract} |
+ insertSigned!(
+ "{public_key}".hexToBytes(),
+ ({version}, bundle+{{*{name}}}),
+ "{signature}".hexToBytes(),
+ *uriOut
+ ) |
+ for (@uri <- uriOut) {{
+ stdout!(("Registered at", uri))
+ }}
+}}"#,
+        contract = contract.trim(),
+        public_key = hex::encode(public_key.serialize_uncompressed()),
+        signature = hex::encode(signature),
+    )
+}
+
+/// Check `uri` is a registry URI that can be quoted in a Rholang URI literal
+pub fn validate_lookup_uri(uri: &str) -> Result<(), String> {
+    let valid = uri
+        .strip_prefix("rho:")
+        .is_some_and(|rest| !rest.is_empty())
+        && uri
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, ':' | '_' | '-' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "'{}' is not a registry URI (expected rho:id:... or a builtin such as rho:vault:system)",
+            uri
+        ))
+    }
+}
+
+/// Exploratory term returning what `rho:registry:lookup` has at `uri`
+pub fn lookup_term(uri: &str) -> String {
+    format!(
+        r#"new return, rl(`rho:registry:lookup`), ch in {{
+ rl!(`{}`, *ch) |
+ for (@entry <- ch) {{
+ return!(entry)
+ }}
+}}"#,
+        uri
+    )
+}
+
+/// What a registry lookup returned, converted to plain JSON
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegistryEntry {
+    /// Version of an `insertSigned` entry, the first element of its `(version, value)` tuple
+    pub version: Option<i64>,
+    /// The whole entry
+    pub value: serde_json::Value,
+}
+
+/// Read the `expr` list of a [`lookup_term`] explore-deploy
+///
+/// `None` means nothing is registered at the URI: the lookup sent nothing or
+/// Nil. Entries that are not a `(version, value)` tuple (an `insertArbitrary`
+/// entry, say) have no version.
+pub fn parse_lookup(expr: &[serde_json::Value]) -> Result<Option<RegistryEntry>, String> {
+    let Some(first) = expr.first() else {
+        return Ok(None);
+    };
+    let value = crate::rholang_helpers::convert_rholang_to_json(first)
+        .map_err(|e| format!("Could not read the registry entry: {}", e))?;
+    if value.is_null() {
+        return Ok(None);
+    }
+    let version = value
+        .as_array()
+        .filter(|items| items.len() == 2)
+        .and_then(|items| items[0].as_i64());
+    Ok(Some(RegistryEntry { version, value }))
+}
+
 /// Convert a public key to a F1r3fly registry URI
 ///
 /// The URI format is: `rho:id:<zbase32-encoded-hash-with-crc14>`
@@ -115,14 +229,11 @@ fn compute_crc14(data: &[u8]) -> [u8; 2] {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::test_keypair;
 
     #[test]
     fn test_uri_is_deterministic() {
-        let secp = Secp256k1::new();
-        let private_key_hex = "5f668a7ee96d944a4494cc947e4005e172d7ab3461ee5538f1f2a45a835e9657";
-        let secret_key_bytes = hex::decode(private_key_hex).unwrap();
-        let secret_key = SecretKey::from_byte_array(secret_key_bytes.try_into().unwrap()).unwrap();
-        let public_key = secret_key.public_key(&secp);
+        let public_key = test_keypair(0).public_key;
 
         let uri1 = public_key_to_uri(&public_key);
         let uri2 = public_key_to_uri(&public_key);
@@ -131,32 +242,102 @@ mod tests {
 
     #[test]
     fn test_uri_format() {
-        let secp = Secp256k1::new();
-        let private_key_hex = "5f668a7ee96d944a4494cc947e4005e172d7ab3461ee5538f1f2a45a835e9657";
-        let secret_key_bytes = hex::decode(private_key_hex).unwrap();
-        let secret_key = SecretKey::from_byte_array(secret_key_bytes.try_into().unwrap()).unwrap();
-        let public_key = secret_key.public_key(&secp);
-
-        let uri = public_key_to_uri(&public_key);
+        let uri = public_key_to_uri(&test_keypair(0).public_key);
         assert!(uri.starts_with("rho:id:"));
     }
 
     #[test]
     fn test_different_keys_produce_different_uris() {
-        let secp = Secp256k1::new();
+        let uri1 = public_key_to_uri(&test_keypair(0).public_key);
+        let uri2 = public_key_to_uri(&test_keypair(1).public_key);
+        assert_ne!(uri1, uri2);
+    }
 
-        let key1_hex = "5f668a7ee96d944a4494cc947e4005e172d7ab3461ee5538f1f2a45a835e9657";
-        let key1_bytes: [u8; 32] = hex::decode(key1_hex).unwrap().try_into().unwrap();
-        let secret_key1 = SecretKey::from_byte_array(key1_bytes).unwrap();
-        let public_key1 = secret_key1.public_key(&secp);
+    #[test]
+    fn test_validate_contract_name() {
+        for name in ["contract", "_c", "Counter2"] {
+            assert!(validate_contract_name(name).is_ok(), "{}", name);
+        }
+        for name in ["", "2c", "my-contract", "c in { Nil } | new x"] {
+            assert!(validate_contract_name(name).is_err(), "{}", name);
+        }
+    }
 
-        let key2_hex = "1234567890abcdef1234567890abcdef1234567890abcdef1234567890abcdef";
-        let key2_bytes: [u8; 32] = hex::decode(key2_hex).unwrap().try_into().unwrap();
-        let secret_key2 = SecretKey::from_byte_array(key2_bytes).unwrap();
-        let public_key2 = secret_key2.public_key(&secp);
+    #[test]
+    fn test_insert_signed_term() {
+        let key = test_keypair(0);
+        let contract = "contract counter(ret) = { ret!(1) }\n";
+        let term = insert_signed_term(contract, "counter", &key.public_key, 3, &[0xab, 0x01]);
 
-        let uri1 = public_key_to_uri(&public_key1);
-        let uri2 = public_key_to_uri(&public_key2);
-        assert_ne!(uri1, uri2);
+        assert!(term.starts_with("new counter, insertSigned("));
+        assert!(term.contains("contract counter(ret) = { ret!(1) } |"));
+        assert!(term.contains(&format!(
+            "\"{}\".hexToBytes()",
+            hex::encode(key.public_key.serialize_uncompressed())
+        )));
+        assert!(term.contains("(3, bundle+{*counter})"));
+        assert!(term.contains("\"ab01\".hexToBytes()"));
+        assert_eq!(term.matches('{').count(), term.matches('}').count());
+    }
+
+    #[test]
+    fn test_validate_lookup_uri() {
+        for uri in [
+            "rho:id:o5uwzh36eokuma4gbrzt4w99w43cesbgi1j7jjomcyt4ztbd98x5gy",
+            "rho:rchain:revVault",
+            "rho:vault:system",
+        ] {
+            assert!(validate_lookup_uri(uri).is_ok(), "{}", uri);
+        }
+        for uri in ["", "rho:", "id:abc", "rho:id:a`, *x) | y!(`z", "rho:id:a b"] {
+            assert!(validate_lookup_uri(uri).is_err(), "{}", uri);
+        }
+    }
+
+    #[test]
+    fn test_lookup_term() {
+        let term = lookup_term("rho:vault:system");
+        assert!(term.starts_with("new return, rl(`rho:registry:lookup`), ch in {"));
+        assert!(term.contains("rl!(`rho:vault:system`, *ch)"));
+        assert!(term.contains("return!(entry)"));
+    }
+
+    #[test]
+    fn test_parse_lookup() {
+        use serde_json::json;
+
+        let signed = json!({"ExprTuple": {"data": [
+            {"ExprInt": {"data": 2}},
+            {"ExprUnforg": {"data": {"UnforgPrivate": {"data": "9C3F"}}}}
+        ]}});
+        assert_eq!(
+            parse_lookup(&[signed]).unwrap(),
+            Some(RegistryEntry {
+                version: Some(2),
+                value: json!([2, "9c3f"]),
+            })
+        );
+
+        let arbitrary = json!({"ExprString": {"data": "hello"}});
+        let entry = parse_lookup(&[arbitrary]).unwrap().unwrap();
+        assert_eq!(entry.version, None);
+        assert_eq!(entry.value, json!("hello"));
+
+        assert_eq!(parse_lookup(&[]).unwrap(), None);
+        assert_eq!(
+            parse_lookup(&[json!({"ExprPar": {"data": []}})]).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_insert_signed_signature_covers_version() {
+        let key = test_keypair(0);
+        let timestamp = DateTime::from_timestamp_millis(1_700_000_000_000).unwrap();
+        let sign = |version| {
+            generate_insert_signed_signature(&key.secret_key, timestamp, &key.public_key, version)
+        };
+        assert_eq!(sign(1), sign(1));
+        assert_ne!(sign(1), sign(2));
     }
 }
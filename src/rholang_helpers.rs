@@ -5,19 +5,23 @@
 
 /// Convert a Rholang expression (from explore-deploy) to plain JSON
 ///
-/// Recursively unwraps ExprMap, ExprString, ExprInt, ExprBool, etc.
-/// into standard JSON types.
+/// Recursively unwraps the externally tagged `Expr*` wrappers into standard
+/// JSON types:
+///
+/// - `ExprMap` → object (non-string keys are converted and stringified)
+/// - `ExprString`, `ExprInt`, `ExprBool` → their value
+/// - `ExprList`, `ExprTuple` → array
+/// - `ExprSet` → array, sorted so output is deterministic
+/// - `ExprUri` → string
+/// - `ExprBytes` → lowercase hex string
+/// - `ExprUnforg` → the unforgeable name's hex id
+/// - `ExprPar` → `null` when empty (Nil), the single element when it holds
+///   one expression, otherwise an array
 pub fn convert_rholang_to_json(
     value: &serde_json::Value,
 ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
     if let Some(expr_map) = value.get("ExprMap").and_then(|v| v.get("data")) {
-        let mut result = serde_json::Map::new();
-        if let Some(map_obj) = expr_map.as_object() {
-            for (key, val) in map_obj {
-                result.insert(key.clone(), convert_rholang_to_json(val)?);
-            }
-        }
-        return Ok(serde_json::Value::Object(result));
+        return convert_map(expr_map);
     }
 
     if let Some(expr_str) = value.get("ExprString").and_then(|v| v.get("data")) {
@@ -32,6 +36,50 @@ pub fn convert_rholang_to_json(
         return Ok(expr_bool.clone());
     }
 
+    if let Some(expr_uri) = value.get("ExprUri").and_then(|v| v.get("data")) {
+        return Ok(expr_uri.clone());
+    }
+
+    if let Some(expr_bytes) = value.get("ExprBytes").and_then(|v| v.get("data")) {
+        return Ok(serde_json::Value::String(bytes_to_hex(expr_bytes)?));
+    }
+
+    for wrapper in ["ExprList", "ExprTuple"] {
+        if let Some(items) = value.get(wrapper).and_then(|v| v.get("data")) {
+            return convert_rholang_to_json(items);
+        }
+    }
+
+    if let Some(items) = value.get("ExprSet").and_then(|v| v.get("data")) {
+        let mut result = match convert_rholang_to_json(items)? {
+            serde_json::Value::Array(arr) => arr,
+            other => vec![other],
+        };
+        result.sort_by(compare_json);
+        return Ok(serde_json::Value::Array(result));
+    }
+
+    if let Some(par) = value.get("ExprPar").and_then(|v| v.get("data")) {
+        return match convert_rholang_to_json(par)? {
+            serde_json::Value::Array(mut arr) if arr.len() <= 1 => {
+                Ok(arr.pop().unwrap_or(serde_json::Value::Null))
+            }
+            other => Ok(other),
+        };
+    }
+
+    if let Some(unforg) = value.get("ExprUnforg").and_then(|v| v.get("data")) {
+        // {"UnforgPrivate": {"data": "<hex>"}} and the Deploy/Deployer variants
+        if let Some(id) = unforg
+            .as_object()
+            .and_then(|o| o.values().next())
+            .and_then(|v| v.get("data"))
+        {
+            return Ok(serde_json::Value::String(bytes_to_hex(id)?));
+        }
+        return Ok(unforg.clone());
+    }
+
     if let Some(arr) = value.as_array() {
         let mut result = Vec::new();
         for item in arr {
@@ -43,6 +91,60 @@ pub fn convert_rholang_to_json(
     Ok(value.clone())
 }
 
+/// Convert `ExprMap` data, given either as an object or as `[key, value]` pairs
+fn convert_map(data: &serde_json::Value) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    let mut result = serde_json::Map::new();
+    if let Some(map_obj) = data.as_object() {
+        for (key, val) in map_obj {
+            result.insert(key.clone(), convert_rholang_to_json(val)?);
+        }
+    } else if let Some(pairs) = data.as_array() {
+        for pair in pairs {
+            let (key, val) = match pair.as_array().map(|p| p.as_slice()) {
+                Some([key, val]) => (key, val),
+                _ => match (pair.get("key"), pair.get("value")) {
+                    (Some(key), Some(val)) => (key, val),
+                    _ => return Err(format!("Unrecognized ExprMap entry: {}", pair).into()),
+                },
+            };
+            let key = match convert_rholang_to_json(key)? {
+                serde_json::Value::String(s) => s,
+                other => other.to_string(),
+            };
+            result.insert(key, convert_rholang_to_json(val)?);
+        }
+    }
+    Ok(serde_json::Value::Object(result))
+}
+
+/// Render `ExprBytes` data (hex string or array of byte values) as lowercase hex
+fn bytes_to_hex(data: &serde_json::Value) -> Result<String, Box<dyn std::error::Error>> {
+    if let Some(s) = data.as_str() {
+        return Ok(s.to_ascii_lowercase());
+    }
+    if let Some(arr) = data.as_array() {
+        let bytes = arr
+            .iter()
+            .map(|b| b.as_u64().filter(|b| *b <= 0xff).map(|b| b as u8))
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(|| format!("Invalid byte array: {}", data))?;
+        return Ok(hex::encode(bytes));
+    }
+    Err(format!("Unsupported ExprBytes data: {}", data).into())
+}
+
+/// Order set members: numbers numerically, strings lexically, anything else by its JSON text
+fn compare_json(a: &serde_json::Value, b: &serde_json::Value) -> std::cmp::Ordering {
+    match (a, b) {
+        (serde_json::Value::Number(x), serde_json::Value::Number(y)) => x
+            .as_f64()
+            .partial_cmp(&y.as_f64())
+            .unwrap_or(std::cmp::Ordering::Equal),
+        (serde_json::Value::String(x), serde_json::Value::String(y)) => x.cmp(y),
+        _ => a.to_string().cmp(&b.to_string()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -113,4 +215,203 @@ mod tests {
         let result = convert_rholang_to_json(&input).unwrap();
         assert_eq!(result, json!(["a", "b", 1]));
     }
+
+    #[test]
+    fn test_convert_expr_list_and_tuple() {
+        let list =
+            json!({"ExprList": {"data": [{"ExprInt": {"data": 1}}, {"ExprInt": {"data": 2}}]}});
+        assert_eq!(convert_rholang_to_json(&list).unwrap(), json!([1, 2]));
+
+        let tuple = json!({"ExprTuple": {"data": [
+        {"ExprBool": {"data": false}},
+        {"ExprString": {"data": "Insufficient funds"}}
+        ]}});
+        assert_eq!(
+            convert_rholang_to_json(&tuple).unwrap(),
+            json!([false, "Insufficient funds"])
+        );
+    }
+
+    #[test]
+    fn test_convert_expr_set_is_sorted() {
+        let input = json!({"ExprSet": {"data": [
+        {"ExprInt": {"data": 10}},
+        {"ExprInt": {"data": 9}},
+        {"ExprInt": {"data": 100}}
+        ]}});
+        assert_eq!(
+            convert_rholang_to_json(&input).unwrap(),
+            json!([9, 10, 100])
+        );
+
+        let input = json!({"ExprSet": {"data": [
+        {"ExprString": {"data": "b"}},
+        {"ExprString": {"data": "a"}}
+        ]}});
+        assert_eq!(convert_rholang_to_json(&input).unwrap(), json!(["a", "b"]));
+    }
+
+    #[test]
+    fn test_convert_expr_uri() {
+        let input = json!({"ExprUri": {"data": "rho:id:abc123"}});
+        assert_eq!(
+            convert_rholang_to_json(&input).unwrap(),
+            json!("rho:id:abc123")
+        );
+    }
+
+    #[test]
+    fn test_convert_expr_bytes() {
+        let input = json!({"ExprBytes": {"data": "04ABCDEF"}});
+        assert_eq!(convert_rholang_to_json(&input).unwrap(), json!("04abcdef"));
+
+        let input = json!({"ExprBytes": {"data": [4, 171, 205, 239]}});
+        assert_eq!(convert_rholang_to_json(&input).unwrap(), json!("04abcdef"));
+
+        let input = json!({"ExprBytes": {"data": [256]}});
+        assert!(convert_rholang_to_json(&input).is_err());
+    }
+
+    #[test]
+    fn test_convert_expr_unforg() {
+        let input = json!({"ExprUnforg": {"data": {"UnforgPrivate": {"data": "A1B2"}}}});
+        assert_eq!(convert_rholang_to_json(&input).unwrap(), json!("a1b2"));
+    }
+
+    #[test]
+    fn test_convert_expr_par() {
+        // Nil
+        let input = json!({"ExprPar": {"data": []}});
+        assert_eq!(convert_rholang_to_json(&input).unwrap(), json!(null));
+
+        // Single expression is unwrapped, recursively
+        let input = json!({"ExprPar": {"data": [
+        {"ExprPar": {"data": [{"ExprInt": {"data": 7}}]}}
+        ]}});
+        assert_eq!(convert_rholang_to_json(&input).unwrap(), json!(7));
+
+        // Several expressions stay an array
+        let input = json!({"ExprPar": {"data": [
+        {"ExprInt": {"data": 1}},
+        {"ExprString": {"data": "x"}}
+        ]}});
+        assert_eq!(convert_rholang_to_json(&input).unwrap(), json!([1, "x"]));
+    }
+
+    #[test]
+    fn test_convert_map_with_pair_entries() {
+        let input = json!({"ExprMap": {"data": [
+        [{"ExprBytes": {"data": "04AA"}}, {"ExprInt": {"data": 5}}],
+        [{"ExprInt": {"data": 2}}, {"ExprString": {"data": "two"}}]
+        ]}});
+        assert_eq!(
+            convert_rholang_to_json(&input).unwrap(),
+            json!({"04aa": 5, "2": "two"})
+        );
+
+        let input = json!({"ExprMap": {"data": ["not a pair"]}});
+        assert!(convert_rholang_to_json(&input).is_err());
+    }
+
+    #[test]
+    fn test_convert_unknown_passes_through() {
+        let input = json!({"ExprWildcard": {"data": {}}});
+        assert_eq!(convert_rholang_to_json(&input).unwrap(), input);
+        assert_eq!(convert_rholang_to_json(&json!(3)).unwrap(), json!(3));
+    }
+
+    /// explore-deploy `expr` for a PoS `getBonds` / `getActiveValidators` style query
+    #[test]
+    fn test_convert_nested_pos_response() {
+        let input = json!([{"ExprTuple": {"data": [
+        {"ExprBool": {"data": true}},
+        {"ExprMap": {"data": {
+        "bonds": {"ExprMap": {"data": [
+        [{"ExprBytes": {"data": "04FFC016579A68050D655D55DF4E09F04605164543E257C8E6DF10361E6068A5336588E9A6CA0ED4E70E4BF4A2E73A6C37A6B4FFE7B6F0BA2D4B0F0E6A5D9C8B7A"}},
+        {"ExprPar": {"data": [{"ExprInt": {"data": 1000}}]}}],
+        [{"ExprBytes": {"data": "04837A4CFF833E3157E3135D7B40B8E1F33C6E6B5A4342EB9E9E8F0A7B5C3D2E1F9E8D7C6B5A49382716A5B4C3D2E1F0A9B8C7D6E5F4A3B2C1D0E9F8A7B6C5D4E3"}},
+        {"ExprPar": {"data": [{"ExprInt": {"data": 2000}}]}}]
+        ]}},
+        "active": {"ExprSet": {"data": [
+        {"ExprBytes": {"data": "04FFC016579A68050D655D55DF4E09F04605164543E257C8E6DF10361E6068A5336588E9A6CA0ED4E70E4BF4A2E73A6C37A6B4FFE7B6F0BA2D4B0F0E6A5D9C8B7A"}},
+        {"ExprBytes": {"data": "04837A4CFF833E3157E3135D7B40B8E1F33C6E6B5A4342EB9E9E8F0A7B5C3D2E1F9E8D7C6B5A49382716A5B4C3D2E1F0A9B8C7D6E5F4A3B2C1D0E9F8A7B6C5D4E3"}}
+        ]}},
+        "quarantine": {"ExprPar": {"data": []}}
+        }}}
+        ]}}]);
+
+        let result = convert_rholang_to_json(&input).unwrap();
+        assert_eq!(
+            result,
+            json!([[true, {
+            "bonds": {
+            "04ffc016579a68050d655d55df4e09f04605164543e257c8e6df10361e6068a5336588e9a6ca0ed4e70e4bf4a2e73a6c37a6b4ffe7b6f0ba2d4b0f0e6a5d9c8b7a": 1000,
+            "04837a4cff833e3157e3135d7b40b8e1f33c6e6b5a4342eb9e9e8f0a7b5c3d2e1f9e8d7c6b5a49382716a5b4c3d2e1f0a9b8c7d6e5f4a3b2c1d0e9f8a7b6c5d4e3": 2000
+            },
+            "active": [
+            "04837a4cff833e3157e3135d7b40b8e1f33c6e6b5a4342eb9e9e8f0a7b5c3d2e1f9e8d7c6b5a49382716a5b4c3d2e1f0a9b8c7d6e5f4a3b2c1d0e9f8a7b6c5d4e3",
+            "04ffc016579a68050d655d55df4e09f04605164543e257c8e6df10361e6068a5336588e9a6ca0ed4e70e4bf4a2e73a6c37a6b4ffe7b6f0ba2d4b0f0e6a5d9c8b7a"
+            ],
+            "quarantine": null
+            }]])
+        );
+    }
+
+    /// explore-deploy `expr` for a registry lookup returning a tuple of a URI and a contract name
+    #[test]
+    fn test_convert_nested_registry_response() {
+        let input = json!([{"ExprPar": {"data": [{"ExprTuple": {"data": [
+        {"ExprString": {"data": "ok"}},
+        {"ExprTuple": {"data": [
+        {"ExprUri": {"data": "rho:id:o5uwzh36eokuma4gbrzt4w99w43cesbgi1j7jjomcyt4ztbd98x5gy"}},
+        {"ExprUnforg": {"data": {"UnforgPrivate": {"data": "9C3F1D0E"}}}},
+        {"ExprList": {"data": [
+        {"ExprMap": {"data": {"version": {"ExprInt": {"data": 2}}}}},
+        {"ExprPar": {"data": []}}
+        ]}}
+        ]}}
+        ]}}]}}]);
+
+        let result = convert_rholang_to_json(&input).unwrap();
+        assert_eq!(
+            result,
+            json!([[
+            "ok",
+            [
+            "rho:id:o5uwzh36eokuma4gbrzt4w99w43cesbgi1j7jjomcyt4ztbd98x5gy",
+            "9c3f1d0e",
+            [{"version": 2}, null]
+            ]
+            ]])
+        );
+    }
+
+    /// epoch-rewards returns a map keyed by validator public key bytes; the
+    /// rewards parser expects `{ "<lowercase hex pubkey>": <integer reward> }`
+    #[test]
+    fn test_convert_epoch_rewards_fixture() {
+        let expr = json!([{"ExprMap": {"data": [
+        [{"ExprBytes": {"data": "04FFC016579A68050D655D55DF4E09F04605164543E257C8E6DF10361E6068A5336588E9A6CA0ED4E70E4BF4A2E73A6C37A6B4FFE7B6F0BA2D4B0F0E6A5D9C8B7A"}},
+        {"ExprInt": {"data": 125}}],
+        [{"ExprBytes": {"data": "04837A4CFF833E3157E3135D7B40B8E1F33C6E6B5A4342EB9E9E8F0A7B5C3D2E1F9E8D7C6B5A49382716A5B4C3D2E1F0A9B8C7D6E5F4A3B2C1D0E9F8A7B6C5D4E3"}},
+        {"ExprInt": {"data": 0}}]
+        ]}}]);
+
+        let converted = convert_rholang_to_json(&expr).unwrap();
+        let rewards = converted[0]
+            .as_object()
+            .expect("rewards should be an object");
+        assert_eq!(rewards.len(), 2);
+        for (key, reward) in rewards {
+            assert_eq!(key.len(), 130);
+            assert!(key
+                .chars()
+                .all(|c| c.is_ascii_hexdigit() && !c.is_ascii_uppercase()));
+            assert!(reward.as_i64().is_some());
+        }
+        assert_eq!(
+            rewards["04ffc016579a68050d655d55df4e09f04605164543e257c8e6df10361e6068a5336588e9a6ca0ed4e70e4bf4a2e73a6c37a6b4ffe7b6f0ba2d4b0f0e6a5d9c8b7a"],
+            json!(125)
+        );
+    }
 }
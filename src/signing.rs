@@ -2,8 +2,9 @@
 //
 // This module provides signing functions used by both gRPC and HTTP clients.
 
+use crate::utils::CryptoUtils;
 use blake2::{Blake2b, Digest};
-use secp256k1::{Message as Secp256k1Message, Secp256k1, SecretKey};
+use secp256k1::{Message as Secp256k1Message, SecretKey};
 use typenum::U32;
 
 /// Sign deploy data using secp256k1
@@ -35,9 +36,8 @@ pub fn sign_deploy_data(
     let mut digest = [0u8; 32];
     digest.copy_from_slice(&hash);
 
-    let secp = Secp256k1::new();
     let message = Secp256k1Message::from_digest(digest);
-    let signature = secp.sign_ecdsa(message, private_key);
+    let signature = CryptoUtils::secp().sign_ecdsa(message, private_key);
 
     Ok(signature.serialize_der().to_vec())
 }
@@ -60,9 +60,10 @@ impl std::error::Error for SigningError {}
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::test_keypair;
 
     fn test_private_key() -> SecretKey {
-        SecretKey::from_byte_array([0x42; 32]).expect("32 bytes is valid")
+        test_keypair(0).secret_key
     }
 
     #[test]
@@ -85,4 +86,26 @@ mod tests {
         let sig2 = sign_deploy_data(data, timestamp, &private_key).unwrap();
         assert_eq!(sig1, sig2);
     }
+
+    #[test]
+    fn test_sign_deploy_data_verifies() {
+        let key = test_keypair(0);
+        let data = b"new x in { x!(1) }";
+        let timestamp = 1234567890i64;
+
+        let signature = sign_deploy_data(data, timestamp, &key.secret_key).unwrap();
+        let digest: [u8; 32] = Blake2b::<U32>::new()
+            .chain_update(data)
+            .chain_update(timestamp.to_le_bytes())
+            .finalize()
+            .into();
+        let signature = secp256k1::ecdsa::Signature::from_der(&signature).unwrap();
+        assert!(CryptoUtils::secp()
+            .verify_ecdsa(
+                Secp256k1Message::from_digest(digest),
+                &signature,
+                &key.public_key
+            )
+            .is_ok());
+    }
 }
@@ -0,0 +1,1100 @@
+//! Validator staking helpers shared by the bond, add-stake and unbond commands
+//!
+//! Builds the PoS deploy templates and runs the pre-flight checks (is the key
+//! bonded, does the deployer's vault cover stake plus phlo) and post-deploy
+//! verification against an observer's explore-deploy HTTP API.
+//!
+//! Every PoS query trusts whatever `rho:system:pos` resolves to. With
+//! `--verify-pos` the resolved registry entry is first compared against the
+//! URIs or fingerprints expected for the shard (see [`PosExpectation`]).
+
+use crate::error::{NodeCall, NodeCliError, Result};
+use crate::grpc::BIGGER_PHLO_LIMIT;
+use crate::query_cache::QueryCache;
+use crate::rholang_helpers::convert_rholang_to_json;
+use crate::utils::CryptoUtils;
+use crate::validator_status::epoch_boundary_from;
+use crate::vault::build_balance_query;
+use blake2::{Blake2b, Digest};
+use futures_util::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use typenum::U32;
+
+/// Maximum phlo cost (in dust) of a staking deploy; these use the bigger phlo limit at price 1
+pub const STAKING_PHLO_COST: u64 = BIGGER_PHLO_LIMIT as u64;
+
+const BONDS_QUERY: &str = r#"new return, rl(`rho:registry:lookup`), poSCh in { rl!(`rho:system:pos`, *poSCh) | for(@(_, PoS) <- poSCh) { @PoS!("getBonds", *return) } }"#;
+
+const POS_ENTRY_QUERY: &str = r#"new return, rl(`rho:registry:lookup`), poSCh in { rl!(`rho:system:pos`, *poSCh) | for(@entry <- poSCh) { return!(entry) } }"#;
+
+const ACTIVE_VALIDATORS_QUERY: &str = r#"new return, rl(`rho:registry:lookup`), poSCh in { rl!(`rho:system:pos`, *poSCh) | for(@(_, PoS) <- poSCh) { @PoS!("getActiveValidators", *return) } }"#;
+
+/// Argument of a PoS method call made by `pos-query`
+///
+/// A value that parses as an integer is passed as a Rholang int; anything
+/// else, or a value wrapped in double quotes, as a string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PosArg {
+    Int(i64),
+    Str(String),
+}
+
+impl FromStr for PosArg {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        if let Some(quoted) = s.strip_prefix('"').and_then(|rest| rest.strip_suffix('"')) {
+            return Ok(PosArg::Str(quoted.to_string()));
+        }
+        Ok(s.parse()
+            .map(PosArg::Int)
+            .unwrap_or_else(|_| PosArg::Str(s.to_string())))
+    }
+}
+
+impl fmt::Display for PosArg {
+    /// The argument as a Rholang literal
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PosArg::Int(n) => write!(f, "{}", n),
+            // JSON string escapes are valid in Rholang string literals
+            PosArg::Str(s) => write!(f, "{}", serde_json::Value::String(s.clone())),
+        }
+    }
+}
+
+/// Rholang that calls the PoS contract's `method` with `args` and sends its
+/// answer on `return`, in the same shape as the built-in PoS queries
+pub fn build_pos_method_query(method: &str, args: &[PosArg]) -> Result<String> {
+    if method.is_empty()
+        || !method
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        return Err(NodeCliError::config_invalid_value(
+            "method",
+            &format!("'{}' is not a PoS method name", method),
+        ));
+    }
+    let params: String = args.iter().map(|arg| format!("{}, ", arg)).collect();
+    Ok(format!(
+        r#"new return, rl(`rho:registry:lookup`), poSCh in {{ rl!(`rho:system:pos`, *poSCh) | for(@(_, PoS) <- poSCh) {{ @PoS!("{}", {}*return) }} }}"#,
+        method, params
+    ))
+}
+
+/// Validator stake before and after a staking deploy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StakeChange {
+    pub old: i64,
+    pub new: i64,
+}
+
+impl StakeChange {
+    pub fn delta(&self) -> i64 {
+        self.new - self.old
+    }
+
+    /// Check the stake grew by exactly `expected`
+    pub fn verify(&self, expected: u64) -> Result<()> {
+        if self.delta() == expected as i64 {
+            Ok(())
+        } else {
+            Err(NodeCliError::General(format!(
+                "Stake changed by {} ({} → {}), expected an increase of {}",
+                self.delta(),
+                self.old,
+                self.new,
+                expected
+            )))
+        }
+    }
+}
+
+/// How long PoS keeps a withdrawn bond, from `getQuarantineLength` and `getEpochLength`
+///
+/// A withdrawn validator stays in the bonds map until the first epoch
+/// boundary at least a quarantine length after the withdrawal block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WithdrawalDelay {
+    pub quarantine_length: i64,
+    /// `None` when the contract did not report one
+    pub epoch_length: Option<i64>,
+}
+
+impl WithdrawalDelay {
+    /// Earliest block the bond leaves the bonds map, for a withdrawal in block `block`
+    pub fn removal_block(&self, block: i64) -> Option<i64> {
+        let epoch_length = self.epoch_length.filter(|&length| length > 0)?;
+        Some(epoch_boundary_from(
+            block + self.quarantine_length,
+            epoch_length,
+        ))
+    }
+
+    pub fn describe(&self) -> String {
+        match self.epoch_length {
+            Some(epoch_length) => format!(
+                "quarantine {} blocks, then the next epoch boundary (epoch length {})",
+                self.quarantine_length, epoch_length
+            ),
+            None => format!("quarantine {} blocks", self.quarantine_length),
+        }
+    }
+}
+
+/// Where a validator stands in the bonds map after its withdraw deploy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnbondCheck {
+    /// No longer bonded
+    Removed,
+    /// Still bonded, waiting out quarantine
+    Scheduled { stake: i64 },
+}
+
+/// Check a vault balance covers `stake` plus the maximum phlo cost of the deploy
+pub fn check_balance_covers(balance: i64, stake: u64) -> Result<()> {
+    let required = stake as i128 + STAKING_PHLO_COST as i128;
+    if (balance as i128) < required {
+        return Err(NodeCliError::General(format!(
+            "Insufficient balance: {} available, {} required ({} stake + {} phlo)",
+            balance, required, stake, STAKING_PHLO_COST
+        )));
+    }
+    Ok(())
+}
+
+/// What to do when the PoS contract is not the one expected (`--verify-pos`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PosVerifyMode {
+    Off,
+    /// Print a warning and continue
+    Warn,
+    /// Stop before running the command
+    Refuse,
+}
+
+impl FromStr for PosVerifyMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "off" => Ok(PosVerifyMode::Off),
+            "warn" => Ok(PosVerifyMode::Warn),
+            "refuse" => Ok(PosVerifyMode::Refuse),
+            other => Err(format!(
+                "unknown PoS verification mode '{}' (expected off, warn or refuse)",
+                other
+            )),
+        }
+    }
+}
+
+/// The registry entry `rho:system:pos` resolved to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PosIdentity {
+    /// First element of the entry when it is a URI
+    pub uri: Option<String>,
+    /// Blake2b-256 of the entry as JSON, hex
+    pub fingerprint: String,
+}
+
+impl PosIdentity {
+    /// Identity from the explore-deploy response of the registry lookup
+    pub fn from_response(response: &serde_json::Value) -> Result<Self> {
+        let expr = response.get("expr").cloned().unwrap_or_default();
+        let converted = convert_rholang_to_json(&expr)
+            .map_err(|e| NodeCliError::parse_error(&e.to_string()))?;
+        let entry = converted
+            .get(0)
+            .filter(|entry| !entry.is_null())
+            .ok_or_else(|| {
+                NodeCliError::parse_error(&format!("rho:system:pos did not resolve: {}", expr))
+            })?;
+        let uri = entry
+            .get(0)
+            .and_then(|first| first.as_str())
+            .filter(|first| first.starts_with("rho:"))
+            .map(str::to_string);
+        let digest = Blake2b::<U32>::digest(entry.to_string().as_bytes());
+        Ok(Self {
+            uri,
+            fingerprint: hex::encode(digest),
+        })
+    }
+}
+
+impl fmt::Display for PosIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.uri {
+            Some(uri) => write!(f, "{} (fingerprint {})", uri, self.fingerprint),
+            None => write!(f, "fingerprint {}", self.fingerprint),
+        }
+    }
+}
+
+/// Known-good PoS contracts for one shard; matching any URI or fingerprint is enough
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PosExpectation {
+    #[serde(default)]
+    pub uris: Vec<String>,
+    #[serde(default)]
+    pub fingerprints: Vec<String>,
+}
+
+impl PosExpectation {
+    pub fn matches(&self, found: &PosIdentity) -> bool {
+        found
+            .uri
+            .as_ref()
+            .is_some_and(|uri| self.uris.contains(uri))
+            || self
+                .fingerprints
+                .iter()
+                .any(|f| f.eq_ignore_ascii_case(&found.fingerprint))
+    }
+}
+
+/// Result of comparing the PoS contract against the shard's expectation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PosCheck {
+    Verified(PosIdentity),
+    Mismatch {
+        found: PosIdentity,
+        expected: PosExpectation,
+    },
+    /// Nothing is configured for the shard
+    Unconfigured(PosIdentity),
+}
+
+impl PosCheck {
+    pub fn new(found: PosIdentity, expected: Option<&PosExpectation>) -> Self {
+        match expected {
+            None => PosCheck::Unconfigured(found),
+            Some(expected) if expected.matches(&found) => PosCheck::Verified(found),
+            Some(expected) => PosCheck::Mismatch {
+                found,
+                expected: expected.clone(),
+            },
+        }
+    }
+
+    /// One line describing the result for `shard`
+    pub fn describe(&self, shard: &str) -> String {
+        match self {
+            PosCheck::Verified(found) => {
+                format!("PoS contract for shard '{}' verified: {}", shard, found)
+            }
+            PosCheck::Unconfigured(found) => format!(
+                "No expected PoS contract configured for shard '{}'; found {}",
+                shard, found
+            ),
+            PosCheck::Mismatch { found, expected } => {
+                let expected: Vec<&str> = expected
+                    .uris
+                    .iter()
+                    .chain(&expected.fingerprints)
+                    .map(String::as_str)
+                    .collect();
+                format!(
+                    "PoS contract for shard '{}' is not the expected one: found {}, expected {}",
+                    shard,
+                    found,
+                    expected.join(" or ")
+                )
+            }
+        }
+    }
+}
+
+/// Read-only PoS and vault queries against an observer's HTTP API
+///
+/// Queries pinned to a block are cached and shared between clones.
+#[derive(Debug, Clone)]
+pub struct StakingQueries {
+    base_url: String,
+    client: reqwest::Client,
+    cache: Arc<QueryCache<serde_json::Value>>,
+}
+
+impl StakingQueries {
+    pub fn new(host: &str, http_port: u16) -> Self {
+        Self {
+            base_url: format!("http://{}:{}", host, http_port),
+            client: reqwest::Client::new(),
+            cache: Arc::new(QueryCache::from_settings()),
+        }
+    }
+
+    /// Run an exploratory deploy, pinned to `block_hash` when given
+    async fn explore(&self, term: &str, block_hash: Option<&str>) -> Result<serde_json::Value> {
+        self.cache
+            .get_or_fetch(term, block_hash, false, || {
+                self.explore_uncached(term, block_hash)
+            })
+            .await
+    }
+
+    async fn explore_uncached(
+        &self,
+        term: &str,
+        block_hash: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        let (url, body) = match block_hash {
+            Some(hash) => (
+                format!("{}/api/explore-deploy-by-block-hash", self.base_url),
+                serde_json::json!({ "term": term, "blockHash": hash, "usePreStateHash": false }),
+            ),
+            None => (
+                format!("{}/api/explore-deploy", self.base_url),
+                serde_json::json!({ "term": term }),
+            ),
+        };
+        let call = NodeCall::http(&url);
+        let response = self
+            .client
+            .post(&url)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| call.fail(e))?;
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            return Err(call.fail(NodeCliError::network_http_error(status.as_u16(), &text)));
+        }
+        response.json().await.map_err(|e| call.fail(e))
+    }
+
+    /// What `rho:system:pos` resolves to on this node
+    ///
+    /// Never cached, so a changed registry entry is always seen.
+    pub async fn pos_identity(&self) -> Result<PosIdentity> {
+        let response = self.explore_uncached(POS_ENTRY_QUERY, None).await?;
+        PosIdentity::from_response(&response)
+    }
+
+    /// Shard ID from `/api/status`
+    pub async fn shard_id(&self) -> Result<String> {
+        let url = format!("{}/api/status", self.base_url);
+        let call = NodeCall::http(&url);
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| call.fail(e))?;
+        let status: serde_json::Value = response.json().await.map_err(|e| call.fail(e))?;
+        Ok(status
+            .get("shardId")
+            .and_then(|s| s.as_str())
+            .unwrap_or_default()
+            .to_string())
+    }
+
+    /// The node's shard and how its PoS contract compares with `expectations`,
+    /// keyed by shard ID
+    pub async fn check_pos(
+        &self,
+        expectations: &BTreeMap<String, PosExpectation>,
+    ) -> Result<(String, PosCheck)> {
+        let shard = self.shard_id().await?;
+        let found = self.pos_identity().await?;
+        let check = PosCheck::new(found, expectations.get(&shard));
+        Ok((shard, check))
+    }
+
+    /// Raw explore-deploy response of the `getBonds` query
+    pub async fn bonds_response(&self, block_hash: Option<&str>) -> Result<serde_json::Value> {
+        self.explore(BONDS_QUERY, block_hash).await
+    }
+
+    /// Raw explore-deploy response of a call to the PoS contract's `method`
+    pub async fn pos_method_response(
+        &self,
+        method: &str,
+        args: &[PosArg],
+        block_hash: Option<&str>,
+    ) -> Result<serde_json::Value> {
+        self.explore(&build_pos_method_query(method, args)?, block_hash)
+            .await
+    }
+
+    /// Current bonds as lowercase validator key → stake
+    pub async fn bonds(&self, block_hash: Option<&str>) -> Result<HashMap<String, i64>> {
+        let response = self.bonds_response(block_hash).await?;
+        parse_bonds(&response)
+    }
+
+    /// Current active validator set as lowercase keys
+    pub async fn active_validators(&self, block_hash: Option<&str>) -> Result<Vec<String>> {
+        let response = self.explore(ACTIVE_VALIDATORS_QUERY, block_hash).await?;
+        parse_active_validators(&response)
+    }
+
+    /// Stake bonded for `public_key`, or `None` if it is not bonded
+    pub async fn stake_of(
+        &self,
+        public_key: &str,
+        block_hash: Option<&str>,
+    ) -> Result<Option<i64>> {
+        let bonds = self.bonds(block_hash).await?;
+        Ok(bonds.get(&public_key.to_ascii_lowercase()).copied())
+    }
+
+    /// Vault balance in dust; a vault that does not exist yet has balance 0
+    pub async fn vault_balance(&self, address: &str) -> Result<i64> {
+        self.vault_balance_at(address, None).await
+    }
+
+    /// Vault balance in dust after `block_hash`, or at the tip when `None`
+    pub async fn vault_balance_at(&self, address: &str, block_hash: Option<&str>) -> Result<i64> {
+        let response = self
+            .explore(&build_balance_query(address), block_hash)
+            .await?;
+        let expr = response.get("expr").cloned().unwrap_or_default();
+        let converted = convert_rholang_to_json(&expr)
+            .map_err(|e| NodeCliError::parse_error(&e.to_string()))?;
+        let balance = converted.get(0).and_then(|v| v.as_i64()).ok_or_else(|| {
+            NodeCliError::parse_error(&format!("Unexpected balance result: {}", expr))
+        })?;
+        Ok(balance.max(0))
+    }
+
+    /// Vault balances in dust of `addresses`, `concurrency` queries at a time
+    ///
+    /// An address whose query failed is left out.
+    pub async fn vault_balances(
+        &self,
+        addresses: &[String],
+        concurrency: usize,
+    ) -> HashMap<String, i64> {
+        stream::iter(addresses)
+            .map(|address| async move { (address, self.vault_balance(address).await) })
+            .buffer_unordered(concurrency.max(1))
+            .filter_map(|(address, balance)| async move {
+                match balance {
+                    Ok(balance) => Some((address.clone(), balance)),
+                    Err(e) => {
+                        tracing::debug!("Balance of {} unavailable: {}", address, e);
+                        None
+                    }
+                }
+            })
+            .collect()
+            .await
+    }
+
+    /// Stake of an already bonded key; errors if the key is not bonded
+    pub async fn require_bonded(&self, public_key: &str) -> Result<i64> {
+        self.stake_of(public_key, None).await?.ok_or_else(|| {
+            NodeCliError::General(format!(
+                "Validator {} is not bonded. Use bond-validator to bond it first.",
+                public_key
+            ))
+        })
+    }
+
+    /// Errors if the key already has a bond
+    pub async fn require_not_bonded(&self, public_key: &str) -> Result<()> {
+        match self.stake_of(public_key, None).await? {
+            Some(stake) => Err(NodeCliError::General(format!(
+                "Validator {} is already bonded with stake {}. Use add-stake to increase it.",
+                public_key, stake
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Vault balance of `address`; errors unless it covers `stake` plus phlo
+    pub async fn require_balance(&self, address: &str, stake: u64) -> Result<i64> {
+        let balance = self.vault_balance(address).await?;
+        check_balance_covers(balance, stake)?;
+        Ok(balance)
+    }
+
+    /// Compare the stake at `block_hash` against `old`, expecting an increase of `amount`
+    pub async fn verify_stake_increase(
+        &self,
+        public_key: &str,
+        block_hash: &str,
+        old: i64,
+        amount: u64,
+    ) -> Result<StakeChange> {
+        let new = self
+            .stake_of(public_key, Some(block_hash))
+            .await?
+            .ok_or_else(|| {
+                NodeCliError::General(format!(
+                    "Validator {} has no bond at block {}",
+                    public_key, block_hash
+                ))
+            })?;
+        let change = StakeChange { old, new };
+        change.verify(amount)?;
+        Ok(change)
+    }
+
+    /// Quarantine and epoch length of the PoS contract; only the quarantine is required
+    pub async fn withdrawal_delay(&self) -> Result<WithdrawalDelay> {
+        let quarantine = self
+            .pos_method_response("getQuarantineLength", &[], None)
+            .await?;
+        let epoch_length = match self.pos_method_response("getEpochLength", &[], None).await {
+            Ok(response) => parse_pos_int(&response, "epoch length").ok(),
+            Err(e) => {
+                tracing::debug!("Epoch length unavailable: {}", e);
+                None
+            }
+        };
+        Ok(WithdrawalDelay {
+            quarantine_length: parse_pos_int(&quarantine, "quarantine length")?,
+            epoch_length,
+        })
+    }
+
+    /// Whether `public_key` is still bonded at `block_hash`, after its withdraw deploy
+    pub async fn check_unbonded(&self, public_key: &str, block_hash: &str) -> Result<UnbondCheck> {
+        Ok(match self.stake_of(public_key, Some(block_hash)).await? {
+            None => UnbondCheck::Removed,
+            Some(stake) => UnbondCheck::Scheduled { stake },
+        })
+    }
+}
+
+/// Integer answer of a PoS query such as `getQuarantineLength`
+pub fn parse_pos_int(response: &serde_json::Value, what: &str) -> Result<i64> {
+    let expr = response.get("expr").cloned().unwrap_or_default();
+    let converted =
+        convert_rholang_to_json(&expr).map_err(|e| NodeCliError::parse_error(&e.to_string()))?;
+    converted
+        .get(0)
+        .and_then(|v| v.as_i64())
+        .ok_or_else(|| NodeCliError::parse_error(&format!("{}: '{}'", what, expr)))
+}
+
+/// Bonds from an explore-deploy response
+///
+/// Reads the `getBonds` map from `expr`, falling back to the `block.bonds`
+/// list that unpinned explore-deploy responses carry.
+pub fn parse_bonds(response: &serde_json::Value) -> Result<HashMap<String, i64>> {
+    if let Some(expr) = response
+        .get("expr")
+        .filter(|e| e.as_array().is_some_and(|a| !a.is_empty()))
+    {
+        let converted =
+            convert_rholang_to_json(expr).map_err(|e| NodeCliError::parse_error(&e.to_string()))?;
+        if let Some(map) = converted.get(0).and_then(|m| m.as_object()) {
+            return Ok(map
+                .iter()
+                .filter_map(|(key, stake)| Some((key.to_ascii_lowercase(), stake.as_i64()?)))
+                .collect());
+        }
+    }
+
+    let bonds = response
+        .get("block")
+        .and_then(|b| b.get("bonds"))
+        .and_then(|b| b.as_array())
+        .ok_or_else(|| NodeCliError::parse_error("No bonds data found in response"))?;
+    Ok(bonds
+        .iter()
+        .filter_map(|bond| {
+            let validator = bond.get("validator")?.as_str()?;
+            Some((validator.to_ascii_lowercase(), bond.get("stake")?.as_i64()?))
+        })
+        .collect())
+}
+
+/// Active validator keys from a `getActiveValidators` explore-deploy response
+pub fn parse_active_validators(response: &serde_json::Value) -> Result<Vec<String>> {
+    let expr = response.get("expr").cloned().unwrap_or_default();
+    let converted =
+        convert_rholang_to_json(&expr).map_err(|e| NodeCliError::parse_error(&e.to_string()))?;
+    let keys = converted
+        .get(0)
+        .and_then(|set| set.as_array())
+        .ok_or_else(|| {
+            NodeCliError::parse_error(&format!("Unexpected active validators result: {}", expr))
+        })?;
+    Ok(keys
+        .iter()
+        .filter_map(|key| key.as_str().map(str::to_ascii_lowercase))
+        .collect())
+}
+
+/// Shown in place of the vault address of a validator key that does not parse
+pub const INVALID_KEY: &str = "(invalid key)";
+
+/// Balance queries in flight at once for `--with-balances`
+pub const DEFAULT_BALANCE_CONCURRENCY: usize = 8;
+
+/// Vault address of a validator key, given compressed or uncompressed
+///
+/// `None` when the key is not hex or not a point on the curve.
+pub fn validator_address(public_key_hex: &str) -> Option<String> {
+    let bytes = hex::decode(public_key_hex).ok()?;
+    let key = secp256k1::PublicKey::from_slice(&bytes).ok()?;
+    CryptoUtils::generate_vault_address(&hex::encode(key.serialize_uncompressed())).ok()
+}
+
+/// Where one key stands in the bonds map, for `bond-status`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BondStatus {
+    pub public_key: String,
+    pub bonded: bool,
+    pub stake: Option<i64>,
+    /// Share of `total_stake` held by the key, as a fraction
+    pub stake_share: Option<f64>,
+    pub total_stake: i64,
+    pub validators: usize,
+    /// Whether the key is in the active set; `None` when that query failed
+    pub active: Option<bool>,
+    /// Smallest existing bond, a working stake for a new validator
+    pub min_stake: Option<i64>,
+}
+
+impl BondStatus {
+    pub fn new(
+        public_key: &str,
+        bonds: &HashMap<String, i64>,
+        active_validators: Option<&[String]>,
+    ) -> Self {
+        let key = public_key.to_ascii_lowercase();
+        let stake = bonds.get(&key).copied();
+        let total_stake: i64 = bonds.values().sum();
+        Self {
+            public_key: public_key.to_string(),
+            bonded: stake.is_some(),
+            stake,
+            stake_share: stake
+                .filter(|_| total_stake > 0)
+                .map(|stake| stake as f64 / total_stake as f64),
+            total_stake,
+            validators: bonds.len(),
+            active: active_validators.map(|active| active.contains(&key)),
+            min_stake: bonds.values().min().copied(),
+        }
+    }
+
+    /// Compact human-readable report
+    pub fn render(&self) -> String {
+        let mut lines = Vec::new();
+        let Some(stake) = self.stake else {
+            lines.push(" Validator is NOT BONDED".to_string());
+            lines.push(format!(" Public key: {}", self.public_key));
+            lines.push(format!(
+                " Network: {} validator(s), {} total stake",
+                self.validators, self.total_stake
+            ));
+            let stake = self
+                .min_stake
+                .map_or("<AMOUNT>".to_string(), |min| min.to_string());
+            lines.push(format!(
+                " To bond: node_cli bond-validator --stake {} --private-key <KEY>",
+                stake
+            ));
+            if self.min_stake.is_some() {
+                lines.push(format!(
+                    "          ({} is the smallest current bond)",
+                    stake
+                ));
+            }
+            return lines.join("\n");
+        };
+
+        lines.push(" Validator is BONDED".to_string());
+        lines.push(format!(" Public key: {}", self.public_key));
+        lines.push(format!(
+            " Stake: {} ({:.1}% of {} total across {} validator(s))",
+            stake,
+            self.stake_share.unwrap_or_default() * 100.0,
+            self.total_stake,
+            self.validators
+        ));
+        match self.active {
+            Some(true) => lines.push(" Active: yes".to_string()),
+            Some(false) => {
+                lines.push(" Active: no".to_string());
+                lines.push(
+                    " Quarantine: bonded but not yet in the active set; it joins at an epoch \
+                     boundary once the quarantine period has passed (see validator-status --explain)"
+                        .to_string(),
+                );
+            }
+            None => lines.push(" Active: unknown (active validator query failed)".to_string()),
+        }
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::spawn_http_node;
+    use serde_json::json;
+
+    const KEY: &str = "04ffc016579a68050d655d55df4e09f04605164543e257c8e6df10361e6068a533";
+
+    fn bonds_response(stake: i64) -> String {
+        json!({
+            "expr": [{"ExprMap": {"data": [
+                [{"ExprBytes": {"data": KEY.to_ascii_uppercase()}}, {"ExprInt": {"data": stake}}],
+                [{"ExprBytes": {"data": "04aa"}}, {"ExprInt": {"data": 5000}}]
+            ]}}],
+            "block": {"blockHash": "b1"}
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_stake_change_verify() {
+        let change = StakeChange {
+            old: 1000,
+            new: 1500,
+        };
+        assert_eq!(change.delta(), 500);
+        assert!(change.verify(500).is_ok());
+
+        let err = change.verify(600).unwrap_err().to_string();
+        assert!(err.contains("1000 → 1500"));
+        assert!(err.contains("expected an increase of 600"));
+        assert!(StakeChange {
+            old: 1000,
+            new: 900
+        }
+        .verify(0)
+        .is_err());
+    }
+
+    #[test]
+    fn test_check_balance_covers() {
+        assert!(check_balance_covers(1000 + STAKING_PHLO_COST as i64, 1000).is_ok());
+        let err = check_balance_covers(STAKING_PHLO_COST as i64 + 999, 1000).unwrap_err();
+        assert!(err.to_string().contains("Insufficient balance"));
+        assert!(check_balance_covers(i64::MAX, u64::MAX).is_err());
+    }
+
+    #[test]
+    fn test_parse_bonds_block_fallback() {
+        let response = json!({"block": {"bonds": [
+            {"validator": "04AB", "stake": 100},
+            {"validator": "04cd", "stake": 200}
+        ]}});
+        let bonds = parse_bonds(&response).unwrap();
+        assert_eq!(bonds.get("04ab"), Some(&100));
+        assert_eq!(bonds.get("04cd"), Some(&200));
+        assert!(parse_bonds(&json!({})).is_err());
+    }
+
+    #[test]
+    fn test_build_pos_method_query() {
+        assert_eq!(
+            build_pos_method_query("getBonds", &[]).unwrap(),
+            BONDS_QUERY
+        );
+
+        let args: Vec<PosArg> = ["42", "-7", "abc", "\"100\"", "say \"hi\""]
+            .iter()
+            .map(|a| a.parse().unwrap())
+            .collect();
+        assert_eq!(
+            args,
+            [
+                PosArg::Int(42),
+                PosArg::Int(-7),
+                PosArg::Str("abc".into()),
+                PosArg::Str("100".into()),
+                PosArg::Str("say \"hi\"".into()),
+            ]
+        );
+        let query = build_pos_method_query("getRewards", &args).unwrap();
+        assert!(
+            query.contains(r#"@PoS!("getRewards", 42, -7, "abc", "100", "say \"hi\"", *return)"#)
+        );
+
+        for bad in ["", "get Bonds", "getBonds\", *return)"] {
+            assert!(build_pos_method_query(bad, &[]).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_withdrawal_delay() {
+        let delay = WithdrawalDelay {
+            quarantine_length: 50,
+            epoch_length: Some(10),
+        };
+        // Withdrawn at 123: quarantine ends at 173, removed at the boundary 180
+        assert_eq!(delay.removal_block(123), Some(180));
+        assert_eq!(delay.removal_block(130), Some(180));
+        assert!(delay.describe().contains("epoch length 10"));
+
+        let unknown_epoch = WithdrawalDelay {
+            epoch_length: None,
+            ..delay
+        };
+        assert_eq!(unknown_epoch.removal_block(123), None);
+        assert_eq!(unknown_epoch.describe(), "quarantine 50 blocks");
+
+        let response = json!({"expr": [{"ExprInt": {"data": 50}}]});
+        assert_eq!(parse_pos_int(&response, "quarantine length").unwrap(), 50);
+        assert!(parse_pos_int(&json!({}), "quarantine length").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_check_unbonded_against_pinned_block() {
+        let port = spawn_http_node(|_, body| {
+            let request: serde_json::Value = serde_json::from_str(body).unwrap_or_default();
+            if request["blockHash"] == "b1" {
+                return ("200 OK", bonds_response(1000));
+            }
+            let without_key = json!({
+                "expr": [{"ExprMap": {"data": [
+                    [{"ExprBytes": {"data": "04aa"}}, {"ExprInt": {"data": 5000}}]
+                ]}}]
+            });
+            ("200 OK", without_key.to_string())
+        })
+        .await;
+        let queries = StakingQueries::new("127.0.0.1", port);
+
+        assert_eq!(
+            queries.check_unbonded(KEY, "b1").await.unwrap(),
+            UnbondCheck::Scheduled { stake: 1000 }
+        );
+        assert_eq!(
+            queries.check_unbonded(KEY, "b2").await.unwrap(),
+            UnbondCheck::Removed
+        );
+    }
+
+    #[tokio::test]
+    async fn test_verify_stake_increase_against_pinned_block() {
+        let port = spawn_http_node(|line, body| {
+            let request: serde_json::Value = serde_json::from_str(body).unwrap_or_default();
+            if line.contains("/api/explore-deploy-by-block-hash") && request["blockHash"] == "b1" {
+                ("200 OK", bonds_response(1500))
+            } else {
+                ("404 Not Found", "Block not found".to_string())
+            }
+        })
+        .await;
+        let queries = StakingQueries::new("127.0.0.1", port);
+
+        let change = queries
+            .verify_stake_increase(KEY, "b1", 1000, 500)
+            .await
+            .unwrap();
+        assert_eq!(
+            change,
+            StakeChange {
+                old: 1000,
+                new: 1500
+            }
+        );
+
+        let err = queries
+            .verify_stake_increase(KEY, "b1", 1200, 500)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("1200 → 1500"));
+        assert!(queries
+            .verify_stake_increase(KEY, "b2", 1000, 500)
+            .await
+            .is_err());
+    }
+
+    fn fixture_bonds() -> HashMap<String, i64> {
+        HashMap::from([
+            ("04aa".to_string(), 1000),
+            ("04bb".to_string(), 2000),
+            (KEY.to_string(), 1000),
+        ])
+    }
+
+    #[test]
+    fn test_render_bonded_and_active() {
+        let active = vec!["04aa".to_string(), KEY.to_string()];
+        let status = BondStatus::new(
+            &KEY.to_ascii_uppercase(),
+            &fixture_bonds(),
+            Some(active.as_slice()),
+        );
+        assert_eq!(status.stake, Some(1000));
+        assert_eq!(status.total_stake, 4000);
+        let out = status.render();
+        assert!(out.contains("Validator is BONDED"));
+        assert!(out.contains("Stake: 1000 (25.0% of 4000 total across 3 validator(s))"));
+        assert!(out.contains("Active: yes"));
+        assert!(!out.contains("Quarantine"));
+    }
+
+    #[test]
+    fn test_render_bonded_but_inactive() {
+        let active = vec!["04aa".to_string(), "04bb".to_string()];
+        let out = BondStatus::new(KEY, &fixture_bonds(), Some(active.as_slice())).render();
+        assert!(out.contains("Active: no"));
+        assert!(out.contains("Quarantine: bonded but not yet in the active set"));
+
+        let unknown = BondStatus::new(KEY, &fixture_bonds(), None);
+        assert_eq!(unknown.active, None);
+        assert!(unknown.render().contains("Active: unknown"));
+    }
+
+    #[test]
+    fn test_render_not_bonded_suggests_bond() {
+        let status = BondStatus::new("04ff", &fixture_bonds(), Some(&[][..]));
+        assert!(!status.bonded);
+        assert_eq!(status.min_stake, Some(1000));
+        let out = status.render();
+        assert!(out.contains("Validator is NOT BONDED"));
+        assert!(out.contains("Network: 3 validator(s), 4000 total stake"));
+        assert!(out.contains("node_cli bond-validator --stake 1000 --private-key <KEY>"));
+
+        let empty = BondStatus::new("04ff", &HashMap::new(), None).render();
+        assert!(empty.contains("--stake <AMOUNT>"));
+        assert!(!empty.contains("smallest current bond"));
+    }
+
+    #[test]
+    fn test_parse_active_validators() {
+        let response = json!({"expr": [{"ExprSet": {"data": [
+            {"ExprBytes": {"data": "04BB"}},
+            {"ExprBytes": {"data": "04AA"}}
+        ]}}]});
+        assert_eq!(
+            parse_active_validators(&response).unwrap(),
+            vec!["04aa".to_string(), "04bb".to_string()]
+        );
+        assert!(parse_active_validators(&json!({"expr": []})).is_err());
+    }
+
+    const POS_URI: &str = "rho:id:m3xk7h8r54dtqtwsrnxqzhe81baswey66nzw6m533nyd45ptyoybqr";
+
+    /// Node on shard `root` whose `rho:system:pos` resolves to `uri`
+    async fn pos_node(uri: &'static str) -> StakingQueries {
+        let port = spawn_http_node(move |line, _body| {
+            if line.contains("/api/status") {
+                ("200 OK", json!({"shardId": "root"}).to_string())
+            } else if line.contains("/api/explore-deploy") {
+                let entry = json!({
+                    "expr": [{"ExprTuple": {"data": [
+                        {"ExprUri": {"data": uri}},
+                        {"ExprUnforg": {"data": {"UnforgPrivate": {"data": "9C3F"}}}}
+                    ]}}]
+                });
+                ("200 OK", entry.to_string())
+            } else {
+                ("404 Not Found", "{}".to_string())
+            }
+        })
+        .await;
+        StakingQueries::new("127.0.0.1", port)
+    }
+
+    fn expecting(uri: &str) -> BTreeMap<String, PosExpectation> {
+        BTreeMap::from([(
+            "root".to_string(),
+            PosExpectation {
+                uris: vec![uri.to_string()],
+                fingerprints: Vec::new(),
+            },
+        )])
+    }
+
+    #[tokio::test]
+    async fn test_pos_check_matches_expected_uri() {
+        let queries = pos_node(POS_URI).await;
+        let (shard, check) = queries.check_pos(&expecting(POS_URI)).await.unwrap();
+        assert_eq!(shard, "root");
+        let PosCheck::Verified(found) = &check else {
+            panic!("{:?}", check);
+        };
+        assert_eq!(found.uri.as_deref(), Some(POS_URI));
+        assert_eq!(found.fingerprint.len(), 64);
+
+        // The fingerprint alone is enough
+        let by_fingerprint = BTreeMap::from([(
+            "root".to_string(),
+            PosExpectation {
+                uris: Vec::new(),
+                fingerprints: vec![found.fingerprint.to_ascii_uppercase()],
+            },
+        )]);
+        let (_, check) = queries.check_pos(&by_fingerprint).await.unwrap();
+        assert!(matches!(check, PosCheck::Verified(_)));
+    }
+
+    #[tokio::test]
+    async fn test_pos_check_reports_mismatch() {
+        let queries = pos_node("rho:id:impostor").await;
+        let (shard, check) = queries.check_pos(&expecting(POS_URI)).await.unwrap();
+        assert!(matches!(check, PosCheck::Mismatch { .. }), "{:?}", check);
+        let message = check.describe(&shard);
+        assert!(message.contains("found rho:id:impostor"), "{}", message);
+        assert!(
+            message.contains(&format!("expected {}", POS_URI)),
+            "{}",
+            message
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pos_check_unconfigured_shard() {
+        let queries = pos_node(POS_URI).await;
+        let (shard, check) = queries.check_pos(&BTreeMap::new()).await.unwrap();
+        assert!(matches!(check, PosCheck::Unconfigured(_)));
+        assert!(check
+            .describe(&shard)
+            .starts_with("No expected PoS contract configured for shard 'root'"));
+
+        assert_eq!("Warn".parse::<PosVerifyMode>(), Ok(PosVerifyMode::Warn));
+        assert!("strict".parse::<PosVerifyMode>().is_err());
+    }
+
+    #[test]
+    fn test_validator_address_derivation() {
+        // The bootstrap validator's key, as in docs/commands/keys.md
+        let uncompressed = "04ffc016579a68050d655d55df4e09f04605164543e257c8e6df10361e6068a5336588e9b355ea859c5ab4285a5ef0efdf62bc28b80320ce99e26bb1607b3ad93d";
+        let compressed = "03ffc016579a68050d655d55df4e09f04605164543e257c8e6df10361e6068a533";
+        let address = "1111AtahZeefej4tvVR6ti9TJtv8yxLebT31SCEVDCKMNikBk5r3g";
+        assert_eq!(validator_address(uncompressed).as_deref(), Some(address));
+        assert_eq!(validator_address(compressed).as_deref(), Some(address));
+        assert_eq!(
+            validator_address(&uncompressed.to_ascii_uppercase()).as_deref(),
+            Some(address)
+        );
+
+        // Not hex, truncated, or not on the curve
+        let off_curve = format!("04{}", "00".repeat(64));
+        for malformed in ["zz", KEY, "04aa", off_curve.as_str()] {
+            assert_eq!(validator_address(malformed), None, "{}", malformed);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_vault_balances_skip_failures() {
+        let addresses: Vec<String> = (0..5).map(crate::test_support::test_address).collect();
+        let failing = addresses[3].clone();
+        let port = spawn_http_node(move |_, body| {
+            if body.contains(&failing) {
+                return ("500 Internal Server Error", String::new());
+            }
+            let response = crate::test_support::rholang::explore_response(vec![
+                crate::test_support::rholang::expr_int(700),
+            ]);
+            ("200 OK", response.to_string())
+        })
+        .await;
+        let queries = StakingQueries::new("127.0.0.1", port);
+
+        let balances = queries.vault_balances(&addresses, 2).await;
+        assert_eq!(balances.len(), 4);
+        assert!(!balances.contains_key(&addresses[3]));
+        assert!(balances.values().all(|balance| *balance == 700));
+    }
+}
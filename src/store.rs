@@ -0,0 +1,458 @@
+//! Shared persistence for local CLI state files
+//!
+//! Several invocations can run at once (a cron job next to an interactive
+//! shell), so every file under the config directory goes through this module:
+//!
+//! - Writers take an advisory lock, a `<file>.lock` created exclusively next to
+//!   the file. Waiting is bounded; a lock older than [`STALE_LOCK_AGE`] is
+//!   assumed to belong to a crashed process and is broken.
+//! - Whole-file rewrites go to a uniquely named temporary file in the same
+//!   directory that is renamed over the original, so readers never see a
+//!   partial file.
+//! - JSON documents carry a `schema_version` field. Fields this version of the
+//!   CLI does not know are kept on rewrite, so a file written by a newer CLI
+//!   survives an update from an older one.
+//! - JSONL logs start with a `{"schema_version": N}` header line, which record
+//!   parsers skip like any other line that is not a record.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Field holding a file's schema version
+pub const SCHEMA_VERSION_FIELD: &str = "schema_version";
+
+/// How long a writer waits for another instance to release a lock
+pub const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A lock file older than this is assumed to belong to a crashed process
+pub const STALE_LOCK_AGE: Duration = Duration::from_secs(30);
+
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Create the parent directory of `path` if needed
+pub fn ensure_parent(path: &Path) -> std::io::Result<()> {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => std::fs::create_dir_all(parent),
+        _ => Ok(()),
+    }
+}
+
+/// Path with `suffix` appended to the file name (`a.json` -> `a.json.lock`)
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// Exclusive advisory lock on a state file, released on drop
+#[derive(Debug)]
+pub struct FileLock {
+    path: PathBuf,
+}
+
+impl FileLock {
+    /// Lock `target`, waiting up to [`LOCK_TIMEOUT`]
+    pub fn acquire(target: &Path) -> std::io::Result<Self> {
+        Self::acquire_with_timeout(target, LOCK_TIMEOUT)
+    }
+
+    pub fn acquire_with_timeout(target: &Path, timeout: Duration) -> std::io::Result<Self> {
+        ensure_parent(target)?;
+        let path = sibling(target, ".lock");
+        let start = Instant::now();
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    // Owner pid is informational only; staleness is judged by age
+                    let _ = write!(file, "{}", std::process::id());
+                    return Ok(Self { path });
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    if lock_is_stale(&path) {
+                        let _ = std::fs::remove_file(&path);
+                        continue;
+                    }
+                    if start.elapsed() >= timeout {
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::WouldBlock,
+                            format!(
+                                "another node_cli instance holds the lock on {} (waited {:?}); \
+                                 retry, or delete {} if no other instance is running",
+                                target.display(),
+                                timeout,
+                                path.display()
+                            ),
+                        ));
+                    }
+                    std::thread::sleep(LOCK_RETRY_INTERVAL);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Path of the lock file itself
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+fn lock_is_stale(path: &Path) -> bool {
+    std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age > STALE_LOCK_AGE)
+}
+
+/// Replace `path` with `contents` via a temporary file and rename
+///
+/// Callers that read-modify-write should hold the file's [`FileLock`].
+pub fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    ensure_parent(path)?;
+    let tmp = sibling(
+        path,
+        &format!(
+            ".{}.{}.tmp",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ),
+    );
+    let result = (|| {
+        let mut file = std::fs::File::create(&tmp)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        std::fs::rename(&tmp, path)
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    result
+}
+
+/// A versioned JSON object whose unknown fields survive a rewrite
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Document {
+    fields: Map<String, Value>,
+}
+
+impl Document {
+    /// Empty document at `schema_version`
+    pub fn new(schema_version: u32) -> Self {
+        let mut fields = Map::new();
+        fields.insert(SCHEMA_VERSION_FIELD.to_string(), schema_version.into());
+        Self { fields }
+    }
+
+    /// Parse a document; anything but a JSON object is an error
+    pub fn parse(text: &str) -> std::io::Result<Self> {
+        match serde_json::from_str(text)? {
+            Value::Object(fields) => Ok(Self { fields }),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "expected a JSON object",
+            )),
+        }
+    }
+
+    /// Schema version the file was written with; 0 for files that predate versioning
+    pub fn schema_version(&self) -> u32 {
+        self.fields
+            .get(SCHEMA_VERSION_FIELD)
+            .and_then(Value::as_u64)
+            .map_or(0, |v| v as u32)
+    }
+
+    /// Raise the schema version to at least `version`, never lowering one written by a newer CLI
+    pub fn upgrade_to(&mut self, version: u32) {
+        if self.schema_version() < version {
+            self.fields
+                .insert(SCHEMA_VERSION_FIELD.to_string(), version.into());
+        }
+    }
+
+    /// Deserialize one field; `None` if absent or of an unexpected shape
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        self.fields
+            .get(key)
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+    }
+
+    /// Set one field, leaving every other field as it was
+    pub fn set<T: Serialize>(&mut self, key: &str, value: &T) -> std::io::Result<()> {
+        self.fields
+            .insert(key.to_string(), serde_json::to_value(value)?);
+        Ok(())
+    }
+
+    pub fn to_pretty_string(&self) -> std::io::Result<String> {
+        Ok(serde_json::to_string_pretty(&self.fields)?)
+    }
+}
+
+/// Read a JSON document; `Ok(None)` if the file does not exist
+pub fn read_document(path: &Path) -> std::io::Result<Option<Document>> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => Document::parse(&text).map(Some),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Locked read-modify-write of a JSON document
+///
+/// `update` sees the current document, or an empty one at `schema_version` if
+/// the file is missing or unreadable (the latter is logged and rebuilt). The
+/// result is written atomically, stamped with at least `schema_version`.
+pub fn update_document<R>(
+    path: &Path,
+    schema_version: u32,
+    update: impl FnOnce(&mut Document) -> std::io::Result<R>,
+) -> std::io::Result<R> {
+    let _lock = FileLock::acquire(path)?;
+    let mut document = match read_document(path) {
+        Ok(Some(document)) => document,
+        Ok(None) => Document::new(schema_version),
+        Err(e) => {
+            tracing::warn!(path = %path.display(), "Unreadable state file, rebuilding: {}", e);
+            Document::new(schema_version)
+        }
+    };
+    let result = update(&mut document)?;
+    document.upgrade_to(schema_version);
+    write_atomic(path, document.to_pretty_string()?.as_bytes())?;
+    Ok(result)
+}
+
+/// JSONL header line for `schema_version`
+pub fn header_line(schema_version: u32) -> String {
+    format!("{{\"{}\":{}}}\n", SCHEMA_VERSION_FIELD, schema_version)
+}
+
+/// Append one line to a JSONL log under its lock
+///
+/// A new or empty file gets the schema header first.
+pub fn append_line(path: &Path, schema_version: u32, line: &str) -> std::io::Result<()> {
+    let _lock = FileLock::acquire(path)?;
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    let mut out = String::new();
+    if file.metadata()?.len() == 0 {
+        out.push_str(&header_line(schema_version));
+    }
+    out.push_str(line.trim_end_matches('\n'));
+    out.push('\n');
+    file.write_all(out.as_bytes())?;
+    file.flush()
+}
+
+/// Record lines of a JSONL log with their 1-based line numbers, without the header
+///
+/// A missing file has no lines. Invalid UTF-8 (e.g. a write cut short by a
+/// crash) is replaced rather than failing the read, so the line is simply
+/// rejected by the record parser.
+pub fn read_lines(path: &Path) -> std::io::Result<Vec<(usize, String)>> {
+    let file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut lines = Vec::new();
+    for (i, line) in BufReader::new(file).split(b'\n').enumerate() {
+        let line = String::from_utf8_lossy(&line?).into_owned();
+        if line.trim().is_empty() || is_header(&line) {
+            continue;
+        }
+        lines.push((i + 1, line));
+    }
+    Ok(lines)
+}
+
+/// Replace a JSONL log's records with `lines`, keeping the schema header
+///
+/// Lines are written back verbatim, so fields unknown to this CLI survive.
+/// Callers should hold the file's [`FileLock`].
+pub fn rewrite_lines(path: &Path, schema_version: u32, lines: &[String]) -> std::io::Result<()> {
+    let mut out = header_line(schema_version);
+    for line in lines {
+        out.push_str(line);
+        out.push('\n');
+    }
+    write_atomic(path, out.as_bytes())
+}
+
+fn is_header(line: &str) -> bool {
+    matches!(
+        serde_json::from_str::<Value>(line),
+        Ok(Value::Object(fields)) if fields.len() == 1 && fields.contains_key(SCHEMA_VERSION_FIELD)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("node_cli_store_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_unknown_fields_survive_rewrite() {
+        let dir = temp_dir("unknown");
+        let path = dir.join("state.json");
+        ensure_parent(&path).unwrap();
+        std::fs::write(
+            &path,
+            r#"{"schema_version": 3, "entries": {"a": 1}, "from_the_future": [1, 2]}"#,
+        )
+        .unwrap();
+
+        update_document(&path, 1, |doc| {
+            let mut entries: Map<String, Value> = doc.get("entries").unwrap();
+            entries.insert("b".to_string(), 2.into());
+            doc.set("entries", &entries)
+        })
+        .unwrap();
+
+        let doc = read_document(&path).unwrap().unwrap();
+        assert_eq!(doc.schema_version(), 3);
+        assert_eq!(doc.get::<Vec<i32>>("from_the_future"), Some(vec![1, 2]));
+        assert_eq!(doc.get::<Map<String, Value>>("entries").unwrap().len(), 2);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_corrupt_document_is_rebuilt() {
+        let dir = temp_dir("corrupt");
+        let path = dir.join("state.json");
+        ensure_parent(&path).unwrap();
+        std::fs::write(&path, b"{ not json").unwrap();
+
+        update_document(&path, 2, |doc| doc.set("k", &"v")).unwrap();
+        let doc = read_document(&path).unwrap().unwrap();
+        assert_eq!(doc.schema_version(), 2);
+        assert_eq!(doc.get::<String>("k").as_deref(), Some("v"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_held_lock_times_out_with_clear_error() {
+        let dir = temp_dir("held");
+        let path = dir.join("state.json");
+        let held = FileLock::acquire(&path).unwrap();
+
+        let err = FileLock::acquire_with_timeout(&path, Duration::from_millis(50)).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::WouldBlock);
+        assert!(err
+            .to_string()
+            .contains("another node_cli instance holds the lock"));
+        drop(held);
+        assert!(FileLock::acquire_with_timeout(&path, Duration::from_millis(50)).is_ok());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_stale_lock_is_broken() {
+        let dir = temp_dir("stale");
+        let path = dir.join("state.json");
+        ensure_parent(&path).unwrap();
+        let lock = sibling(&path, ".lock");
+        std::fs::write(&lock, b"").unwrap();
+        let old = std::time::SystemTime::now() - STALE_LOCK_AGE - Duration::from_secs(1);
+        std::fs::File::options()
+            .write(true)
+            .open(&lock)
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+
+        let held = FileLock::acquire_with_timeout(&path, Duration::from_millis(50)).unwrap();
+        drop(held);
+        assert!(!lock.exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_updates_lose_nothing() {
+        let dir = temp_dir("concurrent_doc");
+        let path = Arc::new(dir.join("counter.json"));
+
+        let tasks: Vec<_> = (0..8)
+            .map(|task| {
+                let path = path.clone();
+                tokio::task::spawn_blocking(move || {
+                    for i in 0..25 {
+                        update_document(&path, 1, |doc| {
+                            let count: u64 = doc.get("count").unwrap_or(0);
+                            doc.set("count", &(count + 1))?;
+                            doc.set(&format!("task{}_{}", task, i), &true)
+                        })
+                        .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        let doc = read_document(&path).unwrap().unwrap();
+        assert_eq!(doc.get::<u64>("count"), Some(200));
+        assert_eq!(doc.fields.len(), 200 + 2);
+        let leftovers: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name() != "counter.json")
+            .collect();
+        assert!(leftovers.is_empty(), "temp or lock files left behind");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_appends_stay_valid_jsonl() {
+        let dir = temp_dir("concurrent_log");
+        let path = Arc::new(dir.join("log.jsonl"));
+
+        let tasks: Vec<_> = (0..8)
+            .map(|task| {
+                let path = path.clone();
+                tokio::task::spawn_blocking(move || {
+                    for i in 0..25 {
+                        let line =
+                            serde_json::json!({"task": task, "i": i, "pad": "x".repeat(512)});
+                        append_line(&path, 1, &line.to_string()).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        let text = std::fs::read_to_string(&*path).unwrap();
+        assert_eq!(text.lines().next(), Some(r#"{"schema_version":1}"#));
+        let lines = read_lines(&path).unwrap();
+        assert_eq!(lines.len(), 200);
+        for (_, line) in &lines {
+            serde_json::from_str::<Value>(line).expect("corrupted line");
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
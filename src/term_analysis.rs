@@ -0,0 +1,426 @@
+//! Size and complexity of a Rholang term before it is deployed
+//!
+//! A node rejects terms that are too large or too complex to parse, and the
+//! rejection says little about why. [`analyze`] measures a term from its token
+//! stream: bytes, lines, an approximate AST node count and the names bound by
+//! top-level `new`s. String literals and URIs count toward the byte size but
+//! are a single node each, so a contract carrying a large embedded document is
+//! big without being complex.
+//!
+//! The measures are compared with [`Limits`], taken from flags, else
+//! `config.toml`, else the defaults. Nodes do not report their limits, so the
+//! defaults are conservative: the byte limit is gRPC's default 4 MiB message
+//! size. A term at [`WARN_PERCENT`] of a limit is flagged; one at the limit is
+//! refused unless the command is forced.
+
+use crate::error::{NodeCliError, Result};
+use crate::utils::{config_value, print_progress, print_warning};
+
+/// `config.toml` key holding the byte limit
+pub const MAX_TERM_BYTES_KEY: &str = "max_term_bytes";
+
+/// `config.toml` key holding the node count limit
+pub const MAX_TERM_NODES_KEY: &str = "max_term_nodes";
+
+/// gRPC's default maximum message size
+pub const DEFAULT_MAX_TERM_BYTES: u64 = 4 * 1024 * 1024;
+
+pub const DEFAULT_MAX_TERM_NODES: u64 = 100_000;
+
+/// Share of a limit, in percent, at which a term is flagged
+pub const WARN_PERCENT: u64 = 80;
+
+/// A lexical token, as far as counting goes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Token<'a> {
+    Open,
+    Close,
+    /// Identifier, keyword or number
+    Word(&'a str),
+    /// String literal or backquoted URI, with its quotes
+    Literal(&'a str),
+    /// Operators and separators
+    Punct,
+}
+
+/// Split `term` into tokens, skipping whitespace and comments
+///
+/// Strings and URIs are single tokens, so braces inside them do not count.
+/// An unterminated string or comment runs to the end of the term.
+pub(crate) fn tokenize(term: &str) -> Vec<Token<'_>> {
+    let bytes = term.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i];
+        match c {
+            b if b.is_ascii_whitespace() => i += 1,
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i += 2;
+                while i < bytes.len() && !(bytes[i] == b'*' && bytes.get(i + 1) == Some(&b'/')) {
+                    i += 1;
+                }
+                i = (i + 2).min(bytes.len());
+            }
+            b'"' | b'`' => {
+                let start = i;
+                i += 1;
+                while i < bytes.len() && bytes[i] != c {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                i = (i + 1).min(bytes.len());
+                tokens.push(Token::Literal(&term[start..i]));
+            }
+            b'{' | b'(' | b'[' => {
+                i += 1;
+                tokens.push(Token::Open);
+            }
+            b'}' | b')' | b']' => {
+                i += 1;
+                tokens.push(Token::Close);
+            }
+            b if b.is_ascii_alphanumeric() || b == b'_' || b >= 0x80 => {
+                let start = i;
+                while i < bytes.len()
+                    && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_' || bytes[i] >= 0x80)
+                {
+                    i += 1;
+                }
+                tokens.push(Token::Word(&term[start..i]));
+            }
+            _ => {
+                i += 1;
+                tokens.push(Token::Punct);
+            }
+        }
+    }
+    tokens
+}
+
+/// Measures of one term
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TermStats {
+    pub bytes: usize,
+    pub lines: usize,
+    /// Identifiers, literals and bracketed groups; operators are not counted
+    pub nodes: usize,
+    /// Names bound by `new`s outside any brackets
+    pub top_level_bindings: usize,
+    /// Deepest bracket nesting
+    pub max_depth: usize,
+}
+
+/// Measure `term`
+pub fn analyze(term: &str) -> TermStats {
+    let mut stats = TermStats {
+        bytes: term.len(),
+        lines: term.lines().count(),
+        ..TermStats::default()
+    };
+    let mut depth = 0usize;
+    // Depth of the top-level `new` whose names are being counted
+    let mut binding_depth: Option<usize> = None;
+    for token in tokenize(term) {
+        match token {
+            Token::Open => {
+                stats.nodes += 1;
+                depth += 1;
+                stats.max_depth = stats.max_depth.max(depth);
+            }
+            Token::Close => depth = depth.saturating_sub(1),
+            Token::Literal(_) => stats.nodes += 1,
+            Token::Word(word) => {
+                stats.nodes += 1;
+                match (word, binding_depth) {
+                    ("new", None) if depth == 0 => binding_depth = Some(depth),
+                    ("in", Some(d)) if d == depth => binding_depth = None,
+                    (_, Some(d)) if d == depth => stats.top_level_bindings += 1,
+                    _ => {}
+                }
+            }
+            Token::Punct => {}
+        }
+    }
+    stats
+}
+
+/// Limits a term is checked against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    pub max_bytes: u64,
+    pub max_nodes: u64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_bytes: DEFAULT_MAX_TERM_BYTES,
+            max_nodes: DEFAULT_MAX_TERM_NODES,
+        }
+    }
+}
+
+impl Limits {
+    /// Flags, else `config.toml`, else the defaults
+    ///
+    /// A config value that is not a number is ignored with a warning.
+    pub fn resolve(max_bytes: Option<u64>, max_nodes: Option<u64>) -> Self {
+        let from_config = |key: &str| {
+            let value = config_value(key)?;
+            match value.parse() {
+                Ok(parsed) => Some(parsed),
+                Err(_) => {
+                    print_warning(&format!(
+                        "Ignoring {} = \"{}\" in config.toml: not a number",
+                        key, value
+                    ));
+                    None
+                }
+            }
+        };
+        Self {
+            max_bytes: max_bytes
+                .or_else(|| from_config(MAX_TERM_BYTES_KEY))
+                .unwrap_or(DEFAULT_MAX_TERM_BYTES),
+            max_nodes: max_nodes
+                .or_else(|| from_config(MAX_TERM_NODES_KEY))
+                .unwrap_or(DEFAULT_MAX_TERM_NODES),
+        }
+    }
+}
+
+/// How close a measure is to its limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Ok,
+    /// At or above [`WARN_PERCENT`] of the limit
+    Near,
+    /// At or above the limit
+    Over,
+}
+
+/// One measure against its limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Measure {
+    pub name: &'static str,
+    pub value: u64,
+    pub limit: u64,
+}
+
+impl Measure {
+    pub fn percent(&self) -> u64 {
+        if self.limit == 0 {
+            return 100;
+        }
+        (self.value as u128 * 100 / self.limit as u128) as u64
+    }
+
+    pub fn level(&self) -> Level {
+        if self.value >= self.limit {
+            Level::Over
+        } else if self.value as u128 * 100 >= self.limit as u128 * WARN_PERCENT as u128 {
+            Level::Near
+        } else {
+            Level::Ok
+        }
+    }
+}
+
+impl TermStats {
+    /// The measures that have limits
+    pub fn measures(&self, limits: &Limits) -> [Measure; 2] {
+        [
+            Measure {
+                name: "size",
+                value: self.bytes as u64,
+                limit: limits.max_bytes,
+            },
+            Measure {
+                name: "nodes",
+                value: self.nodes as u64,
+                limit: limits.max_nodes,
+            },
+        ]
+    }
+
+    /// The worst level of any measure
+    pub fn level(&self, limits: &Limits) -> Level {
+        self.measures(limits)
+            .iter()
+            .map(Measure::level)
+            .max()
+            .unwrap_or(Level::Ok)
+    }
+
+    /// Diagnostics for display, one per line
+    pub fn lines(&self, limits: &Limits) -> Vec<String> {
+        let [size, nodes] = self.measures(limits);
+        let flag = |measure: &Measure| match measure.level() {
+            Level::Ok => "",
+            Level::Near => "  near limit",
+            Level::Over => "  OVER LIMIT",
+        };
+        vec![
+            format!(
+                "Size:      {} bytes ({}% of {}){}",
+                self.bytes,
+                size.percent(),
+                limits.max_bytes,
+                flag(&size)
+            ),
+            format!("Lines:     {}", self.lines),
+            format!(
+                "Nodes:     ~{} ({}% of {}){}",
+                self.nodes,
+                nodes.percent(),
+                limits.max_nodes,
+                flag(&nodes)
+            ),
+            format!("Top-level new bindings: {}", self.top_level_bindings),
+            format!("Max nesting: {}", self.max_depth),
+        ]
+    }
+}
+
+/// Check a term before it is submitted
+///
+/// Diagnostics are printed when `verbose` is set or a measure is near its
+/// limit. A term over a limit is refused unless `force` is set, which turns
+/// the refusal into a warning.
+pub fn check_before_deploy(
+    stats: &TermStats,
+    limits: &Limits,
+    verbose: bool,
+    force: bool,
+) -> Result<()> {
+    let level = stats.level(limits);
+    if verbose || level > Level::Ok {
+        print_progress("Term diagnostics:");
+        for line in stats.lines(limits) {
+            print_progress(&format!("  {}", line));
+        }
+    }
+    match level {
+        Level::Ok => Ok(()),
+        Level::Near => {
+            print_warning(&format!(
+                "Term is at {}% or more of a limit; the node may reject it",
+                WARN_PERCENT
+            ));
+            Ok(())
+        }
+        Level::Over if force => {
+            print_warning("Term exceeds a limit; deploying anyway (--force)");
+            Ok(())
+        }
+        Level::Over => Err(NodeCliError::General(
+            "Term exceeds a size or complexity limit and would likely be rejected. \
+             Split the contract, raise --max-term-bytes/--max-term-nodes, or pass --force."
+                .to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HELLO: &str = r#"new stdout(`rho:io:stdout`), ack in {
+  // a comment with { braces }
+  stdout!("Hello, {world}!") |
+  for (_ <- ack) { Nil }
+}
+"#;
+
+    #[test]
+    fn test_tokens_ignore_braces_in_strings_and_comments() {
+        let stats = analyze(HELLO);
+        assert_eq!(stats.bytes, HELLO.len());
+        assert_eq!(stats.lines, 5);
+        assert_eq!(stats.top_level_bindings, 2);
+        assert_eq!(stats.max_depth, 2);
+        // new stdout ( uri ack in { stdout ( "..." for ( _ ack { Nil
+        assert_eq!(stats.nodes, 16);
+    }
+
+    #[test]
+    fn test_string_literals_count_toward_size_not_nodes() {
+        let small = r#"@"doc"!("x")"#;
+        let big = format!(r#"@"doc"!("{}")"#, "x".repeat(100_000));
+        let (small, big) = (analyze(small), analyze(&big));
+        assert_eq!(small.nodes, big.nodes);
+        assert!(big.bytes > 100_000);
+
+        let limits = Limits {
+            max_bytes: 50_000,
+            max_nodes: 100,
+        };
+        let [size, nodes] = big.measures(&limits);
+        assert_eq!(size.level(), Level::Over);
+        assert_eq!(nodes.level(), Level::Ok);
+    }
+
+    #[test]
+    fn test_nested_news_are_not_top_level() {
+        let term = "new a, b, c in { new d in { Nil } | new e in { Nil } } | new f in { Nil }";
+        let stats = analyze(term);
+        assert_eq!(stats.top_level_bindings, 4);
+    }
+
+    #[test]
+    fn test_unterminated_input_does_not_panic() {
+        for term in ["\"abc", "/* open", "{{{", "}}}", "`rho:", "\"\\"] {
+            analyze(term);
+        }
+        assert_eq!(analyze("}}} {").max_depth, 1);
+    }
+
+    #[test]
+    fn test_levels() {
+        let measure = |value| Measure {
+            name: "size",
+            value,
+            limit: 100,
+        };
+        assert_eq!(measure(79).level(), Level::Ok);
+        assert_eq!(measure(80).level(), Level::Near);
+        assert_eq!(measure(99).level(), Level::Near);
+        assert_eq!(measure(100).level(), Level::Over);
+        assert_eq!(measure(150).percent(), 150);
+    }
+
+    #[test]
+    fn test_check_before_deploy() {
+        let limits = Limits {
+            max_bytes: 10,
+            max_nodes: 1000,
+        };
+        let stats = analyze("@\"a long channel name\"!(1)");
+        let err = check_before_deploy(&stats, &limits, false, false).unwrap_err();
+        assert!(err.to_string().contains("--force"), "{}", err);
+        assert!(check_before_deploy(&stats, &limits, false, true).is_ok());
+        assert!(check_before_deploy(&analyze("Nil"), &limits, true, false).is_ok());
+    }
+
+    #[test]
+    fn test_representative_contract() {
+        // A registry-style contract: many small sends and receives
+        let mut term = String::from("new ret, lookup(`rho:registry:lookup`) in {\n");
+        for i in 0..200 {
+            term.push_str(&format!(
+                "  contract @\"method{i}\"(arg, return) = {{ return!(arg + {i}) }} |\n"
+            ));
+        }
+        term.push_str("  Nil\n}\n");
+        let stats = analyze(&term);
+        assert_eq!(stats.top_level_bindings, 2);
+        assert_eq!(stats.lines, 203);
+        assert!(stats.nodes > 200 * 8, "{}", stats.nodes);
+        assert_eq!(stats.level(&Limits::default()), Level::Ok);
+    }
+}
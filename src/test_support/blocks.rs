@@ -0,0 +1,279 @@
+//! Blocks, block deploys and bond sets with sensible defaults
+
+use super::deploys::{deploy_signature, FIXTURE_TERM, FIXTURE_TIMESTAMP};
+use super::keys::test_keypair;
+use super::rholang::{expr_int, expr_map};
+use crate::f1r3fly_api::{BlockDeploy, BlockDetail, BlockHeader, BlockSummary, SystemDeploy};
+use crate::grpc::{DEFAULT_PHLO_LIMIT, DEFAULT_PHLO_PRICE};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// Validators bonded in a default [`BlockFixture`]
+pub const DEFAULT_VALIDATORS: u32 = 3;
+/// Stake of each default validator
+pub const DEFAULT_STAKE: i64 = 1000;
+
+/// Validators and their stakes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BondsFixture {
+    bonds: Vec<(String, i64)>,
+}
+
+impl BondsFixture {
+    /// Fixture keys `0..validators`, each with `stake`
+    pub fn new(validators: u32, stake: i64) -> Self {
+        Self {
+            bonds: (0..validators)
+                .map(|n| (test_keypair(n).public_key_hex(), stake))
+                .collect(),
+        }
+    }
+
+    /// Add or replace the bond of `validator` (a public key in hex)
+    pub fn with(mut self, validator: &str, stake: i64) -> Self {
+        self.bonds.retain(|(key, _)| key != validator);
+        self.bonds.push((validator.to_string(), stake));
+        self
+    }
+
+    /// Remove the bond of `validator`
+    pub fn without(mut self, validator: &str) -> Self {
+        self.bonds.retain(|(key, _)| key != validator);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.bonds.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bonds.is_empty()
+    }
+
+    /// As [`parse_bonds`](crate::staking::parse_bonds) returns them
+    pub fn to_map(&self) -> HashMap<String, i64> {
+        self.bonds
+            .iter()
+            .map(|(key, stake)| (key.to_ascii_lowercase(), *stake))
+            .collect()
+    }
+
+    /// The `bonds` list of a block's `blockInfo`
+    pub fn to_block_json(&self) -> Value {
+        self.bonds
+            .iter()
+            .map(|(validator, stake)| json!({"validator": validator, "stake": stake}))
+            .collect()
+    }
+
+    /// A `getBonds` explore-deploy result, the map of key to stake
+    pub fn to_expr(&self) -> Value {
+        expr_map(
+            self.bonds
+                .iter()
+                .map(|(validator, stake)| (validator.as_str(), expr_int(*stake))),
+        )
+    }
+}
+
+impl Default for BondsFixture {
+    fn default() -> Self {
+        Self::new(DEFAULT_VALIDATORS, DEFAULT_STAKE)
+    }
+}
+
+/// The `n`th fixture key's deploy of the fixture term, as listed in a block
+///
+/// Its `sig` is [`deploy_signature(n)`](deploy_signature) in hex; it cost 100
+/// phlo and did not error.
+pub fn block_deploy(n: u32) -> BlockDeploy {
+    BlockDeploy {
+        sig: hex::encode(deploy_signature(n)),
+        deployer: test_keypair(n).public_key_hex(),
+        term: FIXTURE_TERM.to_string(),
+        timestamp: FIXTURE_TIMESTAMP,
+        phlo_price: DEFAULT_PHLO_PRICE,
+        phlo_limit: DEFAULT_PHLO_LIMIT,
+        valid_after_block_number: 0,
+        cost: 100,
+        errored: false,
+        system_deploy_error: None,
+    }
+}
+
+/// A block, built from defaults and overrides
+///
+/// `BlockFixture::new(n)` is block `n` with hash `b{n}` on parent `b{n-1}`,
+/// created by fixture key 0 with sequence number `n`, a second after the
+/// previous height (from [`FIXTURE_TIMESTAMP`]), in shard `root`, with no
+/// deploys and the [default bonds](BondsFixture::default). It does not report
+/// system deploys until [`system_deploys`](Self::system_deploys) is set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockFixture {
+    header: BlockHeader,
+    deploys: Vec<BlockDeploy>,
+    bonds: BondsFixture,
+    system_deploys: Option<Vec<SystemDeploy>>,
+}
+
+impl BlockFixture {
+    pub fn new(number: i64) -> Self {
+        let bonds = BondsFixture::default();
+        Self {
+            header: BlockHeader {
+                block_hash: format!("b{}", number),
+                block_number: number,
+                sender: test_keypair(0).public_key_hex(),
+                seq_num: number,
+                timestamp: FIXTURE_TIMESTAMP + number * 1000,
+                shard_id: "root".to_string(),
+                parents: if number > 0 {
+                    vec![format!("b{}", number - 1)]
+                } else {
+                    Vec::new()
+                },
+                pre_state_hash: format!("pre{}", number),
+                post_state_hash: format!("post{}", number),
+                deploy_count: 0,
+                bonds_count: bonds.len(),
+            },
+            deploys: Vec::new(),
+            bonds,
+            system_deploys: None,
+        }
+    }
+
+    pub fn hash(mut self, hash: &str) -> Self {
+        self.header.block_hash = hash.to_string();
+        self
+    }
+
+    /// Parent hashes, main parent first
+    pub fn parents(mut self, parents: &[&str]) -> Self {
+        self.header.parents = parents.iter().map(|p| p.to_string()).collect();
+        self
+    }
+
+    /// Creator public key in hex
+    pub fn sender(mut self, sender: &str) -> Self {
+        self.header.sender = sender.to_string();
+        self
+    }
+
+    pub fn seq_num(mut self, seq_num: i64) -> Self {
+        self.header.seq_num = seq_num;
+        self
+    }
+
+    /// Unix epoch milliseconds
+    pub fn timestamp(mut self, timestamp: i64) -> Self {
+        self.header.timestamp = timestamp;
+        self
+    }
+
+    /// Add a deploy, counting it in `deploy_count`
+    pub fn deploy(mut self, deploy: BlockDeploy) -> Self {
+        self.deploys.push(deploy);
+        self.header.deploy_count = self.deploys.len() as i32;
+        self
+    }
+
+    pub fn bonds(mut self, bonds: BondsFixture) -> Self {
+        self.header.bonds_count = bonds.len();
+        self.bonds = bonds;
+        self
+    }
+
+    pub fn system_deploys(mut self, system_deploys: Vec<SystemDeploy>) -> Self {
+        self.system_deploys = Some(system_deploys);
+        self
+    }
+
+    pub fn summary(&self) -> BlockSummary {
+        BlockSummary::from(&self.header)
+    }
+
+    pub fn header(&self) -> BlockHeader {
+        self.header.clone()
+    }
+
+    pub fn detail(&self) -> BlockDetail {
+        BlockDetail {
+            header: self.header.clone(),
+            deploys: self.deploys.clone(),
+            system_deploys: self.system_deploys.clone(),
+        }
+    }
+
+    /// The block's `blockInfo`, as the ranged block listing returns it
+    pub fn info_json(&self) -> Value {
+        let header = &self.header;
+        json!({
+            "blockHash": header.block_hash,
+            "blockNumber": header.block_number,
+            "sender": header.sender,
+            "seqNum": header.seq_num,
+            "timestamp": header.timestamp,
+            "shardId": header.shard_id,
+            "parentsHashList": header.parents,
+            "preStateHash": header.pre_state_hash,
+            "postStateHash": header.post_state_hash,
+            "deployCount": header.deploy_count,
+            "bonds": self.bonds.to_block_json()
+        })
+    }
+
+    /// The `block-finalised` frame the node's `/ws/events` sends for the block
+    pub fn finalized_event(&self) -> String {
+        let header = &self.header;
+        json!({
+            "event": "block-finalised",
+            "schema-version": 1,
+            "payload": {
+                "block-hash": header.block_hash,
+                "block-number": header.block_number,
+                "creator": header.sender,
+                "seq-num": header.seq_num,
+                "deploys": []
+            }
+        })
+        .to_string()
+    }
+
+    /// The `/api/block/{hash}` response for the block
+    pub fn to_json(&self) -> Value {
+        let deploys: Vec<Value> = self
+            .deploys
+            .iter()
+            .map(|d| {
+                json!({
+                    "sig": d.sig,
+                    "deployer": d.deployer,
+                    "term": d.term,
+                    "timestamp": d.timestamp,
+                    "sigAlgorithm": "secp256k1",
+                    "phloPrice": d.phlo_price,
+                    "phloLimit": d.phlo_limit,
+                    "validAfterBlockNumber": d.valid_after_block_number,
+                    "cost": d.cost,
+                    "errored": d.errored,
+                    "systemDeployError": d.system_deploy_error.clone().unwrap_or_default()
+                })
+            })
+            .collect();
+        let mut json = json!({"blockInfo": self.info_json(), "deploys": deploys});
+        if let Some(system_deploys) = &self.system_deploys {
+            json["systemDeploys"] = system_deploys
+                .iter()
+                .map(|d| {
+                    json!({
+                        "systemDeployType": d.kind.to_string(),
+                        "failed": d.failed,
+                        "errorMsg": d.error.clone().unwrap_or_default()
+                    })
+                })
+                .collect();
+        }
+        json
+    }
+}
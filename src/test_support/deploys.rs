@@ -0,0 +1,39 @@
+//! Signed deploys of a trivial term
+
+use super::keys::test_keypair;
+use crate::grpc::{F1r3flyApi, DEFAULT_PHLO_LIMIT, DEFAULT_PHLO_PRICE};
+use crate::signing::sign_deploy_data;
+use f1r3fly_models::casper::DeployDataProto;
+
+/// Term every fixture deploy carries
+pub const FIXTURE_TERM: &str = "Nil";
+/// Timestamp (Unix epoch milliseconds) every fixture deploy is signed with
+pub const FIXTURE_TIMESTAMP: i64 = 1_700_000_000_000;
+
+/// [`FIXTURE_TERM`] deployed by the `n`th fixture key, signed as `deploy` signs it
+///
+/// Default phlo limit and price, valid after block 0, no expiration, shard `root`.
+pub fn signed_deploy(n: u32) -> DeployDataProto {
+    let key = test_keypair(n);
+    let api =
+        F1r3flyApi::new(&key.private_key_hex(), "localhost", 0).expect("fixture keys are valid");
+    api.build_deploy_msg(
+        FIXTURE_TERM.to_string(),
+        DEFAULT_PHLO_LIMIT,
+        DEFAULT_PHLO_PRICE,
+        "rholang".to_string(),
+        0,
+        0,
+        Some(FIXTURE_TIMESTAMP),
+    )
+}
+
+/// [`sign_deploy_data`] over [`FIXTURE_TERM`] at [`FIXTURE_TIMESTAMP`] with the `n`th fixture key
+pub fn deploy_signature(n: u32) -> Vec<u8> {
+    sign_deploy_data(
+        FIXTURE_TERM.as_bytes(),
+        FIXTURE_TIMESTAMP,
+        &test_keypair(n).secret_key,
+    )
+    .expect("signing never fails for a valid key")
+}
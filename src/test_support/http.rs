@@ -0,0 +1,101 @@
+//! Mock HTTP node shared by unit tests
+
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Read one HTTP request, returning (request line, body)
+pub(crate) async fn read_request(socket: &mut TcpStream) -> (String, String) {
+    let (head, body) = read_request_head(socket).await;
+    let line = head.lines().next().unwrap_or("").to_string();
+    (line, body)
+}
+
+/// Read one HTTP request, returning (request line and headers, body)
+pub(crate) async fn read_request_head(socket: &mut TcpStream) -> (String, String) {
+    let mut data = Vec::new();
+    let mut buf = [0u8; 4096];
+    loop {
+        let n = socket.read(&mut buf).await.unwrap_or(0);
+        if n == 0 {
+            break;
+        }
+        data.extend_from_slice(&buf[..n]);
+        let text = String::from_utf8_lossy(&data).to_string();
+        if let Some((head, body)) = text.split_once("\r\n\r\n") {
+            let length = head
+                .lines()
+                .find_map(|l| {
+                    let (name, value) = l.split_once(':')?;
+                    name.eq_ignore_ascii_case("content-length")
+                        .then(|| value.trim().parse::<usize>().ok())?
+                })
+                .unwrap_or(0);
+            if body.len() >= length {
+                return (head.to_string(), body.to_string());
+            }
+        }
+    }
+    (String::new(), String::new())
+}
+
+/// Serve HTTP on a random local port, answering each request with `handler(request line, body)`
+///
+/// The handler returns a status line such as `"200 OK"` and a JSON body.
+pub(crate) async fn spawn_http_node<F>(handler: F) -> u16
+where
+    F: Fn(&str, &str) -> (&'static str, String) + Send + Sync + 'static,
+{
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    serve_http(listener, handler);
+    port
+}
+
+/// Serve HTTP on an already bound listener (see [`spawn_http_node`])
+pub(crate) fn serve_http<F>(listener: TcpListener, handler: F)
+where
+    F: Fn(&str, &str) -> (&'static str, String) + Send + Sync + 'static,
+{
+    let handler = Arc::new(handler);
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            answer(&mut socket, &*handler).await;
+        }
+    });
+}
+
+/// Read one request from `socket` and write `handler`'s reply
+pub(crate) async fn answer<F>(socket: &mut TcpStream, handler: &F)
+where
+    F: Fn(&str, &str) -> (&'static str, String),
+{
+    let (line, body) = read_request(socket).await;
+    let (status, response) = handler(&line, &body);
+    let reply = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        response.len(),
+        response
+    );
+    let _ = socket.write_all(reply.as_bytes()).await;
+}
+
+/// Bind `count` listeners on consecutive local ports
+pub(crate) async fn bind_consecutive(count: u16) -> Vec<TcpListener> {
+    'retry: loop {
+        let first = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = first.local_addr().unwrap().port();
+        let mut listeners = vec![first];
+        for offset in 1..count {
+            let Some(port) = base.checked_add(offset) else {
+                continue 'retry;
+            };
+            match TcpListener::bind(("127.0.0.1", port)).await {
+                Ok(listener) => listeners.push(listener),
+                Err(_) => continue 'retry,
+            }
+        }
+        return listeners;
+    }
+}
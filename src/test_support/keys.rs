@@ -0,0 +1,64 @@
+//! Keypairs derived from an index
+
+use crate::utils::CryptoUtils;
+use blake2::digest::consts::U32;
+use blake2::{Blake2b, Digest};
+use secp256k1::{PublicKey, SecretKey};
+
+/// Prefix hashed with the index to derive a fixture key
+const KEY_DOMAIN: &[u8] = b"node_cli test key ";
+
+/// A fixture keypair with its encodings
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestKeypair {
+    pub index: u32,
+    pub secret_key: SecretKey,
+    pub public_key: PublicKey,
+}
+
+impl TestKeypair {
+    /// Private key as 64 hex characters, as the CLI's `--private-key` takes it
+    pub fn private_key_hex(&self) -> String {
+        CryptoUtils::serialize_private_key(&self.secret_key)
+    }
+
+    /// Uncompressed public key as hex (`04...`), the form deploys carry
+    pub fn public_key_hex(&self) -> String {
+        CryptoUtils::serialize_public_key(&self.public_key, false)
+    }
+
+    /// Vault (REV) address of the key
+    pub fn address(&self) -> String {
+        CryptoUtils::generate_vault_address(&self.public_key_hex())
+            .expect("a valid public key has a vault address")
+    }
+}
+
+/// The `n`th fixture keypair
+///
+/// The private key is the Blake2b-256 hash of `"node_cli test key "` and `n`
+/// as four big-endian bytes, rehashed in the (never yet seen) case that the
+/// hash is not a valid secp256k1 scalar.
+pub fn test_keypair(n: u32) -> TestKeypair {
+    let mut seed: [u8; 32] = Blake2b::<U32>::new()
+        .chain_update(KEY_DOMAIN)
+        .chain_update(n.to_be_bytes())
+        .finalize()
+        .into();
+    let secret_key = loop {
+        match SecretKey::from_byte_array(seed) {
+            Ok(key) => break key,
+            Err(_) => seed = Blake2b::<U32>::digest(seed).into(),
+        }
+    };
+    TestKeypair {
+        index: n,
+        public_key: CryptoUtils::derive_public_key(&secret_key),
+        secret_key,
+    }
+}
+
+/// Vault address of the `n`th fixture keypair
+pub fn test_address(n: u32) -> String {
+    test_keypair(n).address()
+}
@@ -0,0 +1,215 @@
+//! Deterministic fixtures for tests against this crate
+//!
+//! Enabled by the `test-support` feature, and always for the crate's own
+//! tests:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! node_cli = { git = "https://github.com/F1R3FLY-io/rust-client", features = ["test-support"] }
+//! ```
+//!
+//! - [`test_keypair`]`(n)`: a keypair derived from an index, with its hex
+//!   encodings and vault address ([`test_address`])
+//! - [`signed_deploy`]`(n)`: a signed `Nil` deploy from key `n`, and
+//!   [`deploy_signature`] for the [`signing`](crate::signing) API
+//! - [`BlockFixture`], [`BondsFixture`] and [`block_deploy`]: typed blocks
+//!   and the node's JSON for them, with builder-style overrides
+//! - [`rholang`]: explore-deploy expression JSON (`ExprMap`, `ExprTuple`, ...)
+//!
+//! # Stability
+//!
+//! Fixture outputs are part of the public test API. The keys and addresses
+//! derived for an index, the fixture term and timestamp, and the signatures
+//! made from them are pinned by the snapshot tests in this module; changing
+//! any of them breaks downstream tests that compare against literals, so it
+//! is a breaking change and goes out only in a major release. Defaults of
+//! the block and bond builders follow the same rule.
+
+mod blocks;
+mod deploys;
+#[cfg(test)]
+mod http;
+mod keys;
+pub mod rholang;
+#[cfg(all(test, feature = "ws"))]
+mod ws;
+
+pub use blocks::{block_deploy, BlockFixture, BondsFixture, DEFAULT_STAKE, DEFAULT_VALIDATORS};
+pub use deploys::{deploy_signature, signed_deploy, FIXTURE_TERM, FIXTURE_TIMESTAMP};
+#[cfg(test)]
+pub(crate) use http::{bind_consecutive, read_request_head, serve_http, spawn_http_node};
+pub use keys::{test_address, test_keypair, TestKeypair};
+#[cfg(all(test, feature = "ws"))]
+pub(crate) use ws::{spawn_event_node, WsSession};
+
+#[cfg(test)]
+mod tests {
+    use super::rholang::*;
+    use super::*;
+    use crate::f1r3fly_api::{SystemDeploy, SystemDeployKind};
+    use crate::rholang_helpers::convert_rholang_to_json;
+    use crate::staking::parse_bonds;
+    use blake2::digest::consts::U32;
+    use blake2::{Blake2b, Digest};
+    use prost::Message as _;
+    use secp256k1::ecdsa::Signature;
+    use secp256k1::Message;
+    use serde_json::json;
+
+    /// (private key, uncompressed public key, vault address) of fixture keys 0..3
+    const KEYS: [(&str, &str, &str); 3] = [
+        (
+            "b842da456ce201cea246b28fcb83fcd194ed77ac7d12821140daf90e1643b52d",
+            "04027008b8bccfb4417d6a19028cfa3a6b9da28269692548630695810880467841883d270fcffd3ad4b5553340f6a10f41d50cb9a723b7e8ac524d0b9a9f9e4e0a",
+            "11112gAXgDaXozfmaKfiiESmkpwgvunGoFnH6pmmeor8h9u2kYp2D",
+        ),
+        (
+            "602cfdf0e6a649817800ae1be98a201dec775da1b970bbc0d67ed2b3b5d204f5",
+            "041a40b1ecbb59212bc6f22b3bdef4c672e7e34b24fb3a4c46b30190c71655157c4139a93c8e3389783bf4cb660413ff2be0009285e88c9d1ea77ddfb3b6cbe54e",
+            "1111nKYWxCKxwzXb3a67fYKKNDx5P6sGvQuXxsAvN4MJztzVwdN1w",
+        ),
+        (
+            "c1c39cb3dbb67d5a2f38ba7b5649f4b3bf2f0f636d54d2bbe919bbc3903463c0",
+            "040e7af5f1be534f91a79e18cfc080056fe582893562c6bcb4d0b9dc70150bf8f1ad43f0cc0af29df5cf79efc5ba3f295eda7241039fe7d7271cf85c6de63167c1",
+            "11112nHmy2bzXL21h1vY892RiZ8vMWn9dD6Dy7AyvMyameFrwnZZH7",
+        ),
+    ];
+
+    /// DER signatures of fixture keys 0..3 over the fixture term and timestamp
+    const SIGNATURES: [&str; 3] = [
+        "3044022027b9416429cfdadf51208b8ca8e70dcb6e8d86c3e7bd91767df0be7c20ecb2730220040185b8a47a61a30c7df2ba1ebb8a88067226b76117fb4060e37607c9e59c3e",
+        "3044022063c737b52d2c4de3aa236296c3405bf09693b1889fc675b063cf2b27bd1ab97d0220776df5fc17c678630ff59f1371f3a58b58931f4e4f3f311a1bf68dbef657aa11",
+        "3045022100faca74157eaf55b76f67e6663508c90f3a0e84b22ffd448c578325294a4418ce022042b8297fcba5842b98c2823c9137cd9ed948ed6739bf5ca87f5f477e37c0c126",
+    ];
+
+    #[test]
+    fn test_keypair_snapshot() {
+        for (n, (private, public, address)) in KEYS.iter().enumerate() {
+            let key = test_keypair(n as u32);
+            assert_eq!(key.index, n as u32);
+            assert_eq!(key.private_key_hex(), *private, "key {}", n);
+            assert_eq!(key.public_key_hex(), *public, "key {}", n);
+            assert_eq!(key.address(), *address, "key {}", n);
+            assert_eq!(test_address(n as u32), *address);
+        }
+        assert_eq!(test_keypair(7), test_keypair(7));
+    }
+
+    #[test]
+    fn test_deploy_signature_snapshot() {
+        assert_eq!(
+            (FIXTURE_TERM, FIXTURE_TIMESTAMP),
+            ("Nil", 1_700_000_000_000)
+        );
+        for (n, signature) in SIGNATURES.iter().enumerate() {
+            assert_eq!(
+                hex::encode(deploy_signature(n as u32)),
+                *signature,
+                "key {}",
+                n
+            );
+        }
+    }
+
+    #[test]
+    fn test_signed_deploy_verifies() {
+        let deploy = signed_deploy(1);
+        assert_eq!(deploy, signed_deploy(1));
+        assert_eq!(deploy.term, FIXTURE_TERM);
+        assert_eq!(deploy.timestamp, FIXTURE_TIMESTAMP);
+        assert_eq!(deploy.sig_algorithm, "secp256k1");
+        assert_eq!(hex::encode(&deploy.deployer), KEYS[1].1);
+
+        // The signature covers the deploy with its signature fields cleared
+        let mut projection = deploy.clone();
+        projection.sig = Default::default();
+        projection.deployer = Default::default();
+        projection.sig_algorithm = String::new();
+        projection.language = String::new();
+        let digest: [u8; 32] = Blake2b::<U32>::digest(projection.encode_to_vec()).into();
+        let signature = Signature::from_der(&deploy.sig).unwrap();
+        crate::utils::CryptoUtils::secp()
+            .verify_ecdsa(
+                Message::from_digest(digest),
+                &signature,
+                &test_keypair(1).public_key,
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_block_fixture_round_trips() {
+        let failed = SystemDeploy {
+            kind: SystemDeployKind::CloseBlock,
+            failed: true,
+            error: Some("Unable to close block".to_string()),
+        };
+        let block = BlockFixture::new(5)
+            .deploy(block_deploy(2))
+            .bonds(BondsFixture::new(2, 500).with("04aa", 10))
+            .system_deploys(vec![failed]);
+        let detail = crate::f1r3fly_api::BlockDetail::from_json(&block.to_json()).unwrap();
+        assert_eq!(detail, block.detail());
+        assert_eq!(detail.header.bonds_count, 3);
+        assert_eq!(detail.header.parents, vec!["b4".to_string()]);
+        assert_eq!(detail.deploys[0].sig, SIGNATURES[2]);
+        assert_eq!(
+            crate::f1r3fly_api::BlockSummary::from_json(&block.info_json()),
+            Some(block.summary())
+        );
+        // Genesis has no parents; overrides replace defaults
+        let genesis = BlockFixture::new(0).hash("g").sender(KEYS[1].1);
+        assert!(genesis.header().parents.is_empty());
+        assert_eq!(genesis.summary().block_hash, "g");
+    }
+
+    #[test]
+    fn test_bonds_fixture_parses() {
+        let bonds = BondsFixture::default().without(KEYS[2].1);
+        let expected = bonds.to_map();
+        assert_eq!(expected.len(), 2);
+        assert_eq!(expected.get(KEYS[0].1), Some(&DEFAULT_STAKE));
+        assert_eq!(
+            parse_bonds(&explore_response(vec![bonds.to_expr()])).unwrap(),
+            expected
+        );
+        assert_eq!(
+            parse_bonds(&json!({"block": {"bonds": bonds.to_block_json()}})).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_rholang_fixtures_convert() {
+        let value = expr_map([
+            ("name", expr_string("Alice")),
+            ("balance", expr_int(100)),
+            ("tags", expr_set(vec![expr_string("b"), expr_string("a")])),
+            ("key", expr_bytes(&[0x04, 0xab])),
+            ("ret", expr_unforg("A1B2")),
+            ("registry", expr_uri("rho:id:abc")),
+            ("history", expr_list(vec![expr_bool(true), expr_nil()])),
+        ]);
+        assert_eq!(
+            convert_rholang_to_json(&value).unwrap(),
+            json!({
+                "name": "Alice",
+                "balance": 100,
+                "tags": ["a", "b"],
+                "key": "04ab",
+                "ret": "a1b2",
+                "registry": "rho:id:abc",
+                "history": [true, null]
+            })
+        );
+        let pairs = expr_map_pairs([(expr_int(1), expr_string("one"))]);
+        assert_eq!(
+            convert_rholang_to_json(&pairs).unwrap(),
+            json!({"1": "one"})
+        );
+        assert_eq!(
+            convert_rholang_to_json(&transfer_result(Some("Insufficient funds"))).unwrap(),
+            json!([false, "Insufficient funds"])
+        );
+    }
+}
@@ -0,0 +1,81 @@
+//! Rholang expressions as the node's explore-deploy JSON renders them
+//!
+//! Each helper builds one externally tagged `Expr*` value, so a response for
+//! [`convert_rholang_to_json`](crate::rholang_helpers::convert_rholang_to_json)
+//! consumers can be written out as nested calls instead of raw JSON.
+
+use serde_json::{json, Value};
+
+pub fn expr_string(s: &str) -> Value {
+    json!({"ExprString": {"data": s}})
+}
+
+pub fn expr_int(i: i64) -> Value {
+    json!({"ExprInt": {"data": i}})
+}
+
+pub fn expr_bool(b: bool) -> Value {
+    json!({"ExprBool": {"data": b}})
+}
+
+pub fn expr_uri(uri: &str) -> Value {
+    json!({"ExprUri": {"data": uri}})
+}
+
+/// Bytes as the node sends them, a hex string
+pub fn expr_bytes(bytes: &[u8]) -> Value {
+    json!({"ExprBytes": {"data": hex::encode(bytes)}})
+}
+
+/// A private unforgeable name with id `hex_id`
+pub fn expr_unforg(hex_id: &str) -> Value {
+    json!({"ExprUnforg": {"data": {"UnforgPrivate": {"data": hex_id}}}})
+}
+
+pub fn expr_list(items: Vec<Value>) -> Value {
+    json!({"ExprList": {"data": items}})
+}
+
+pub fn expr_tuple(items: Vec<Value>) -> Value {
+    json!({"ExprTuple": {"data": items}})
+}
+
+pub fn expr_set(items: Vec<Value>) -> Value {
+    json!({"ExprSet": {"data": items}})
+}
+
+/// A map with string keys, in the object form
+pub fn expr_map<'a>(entries: impl IntoIterator<Item = (&'a str, Value)>) -> Value {
+    let data: serde_json::Map<String, Value> = entries
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect();
+    json!({"ExprMap": {"data": data}})
+}
+
+/// A map with arbitrary keys, in the `[key, value]` pairs form
+pub fn expr_map_pairs(entries: impl IntoIterator<Item = (Value, Value)>) -> Value {
+    let data: Vec<Value> = entries
+        .into_iter()
+        .map(|(key, value)| json!([key, value]))
+        .collect();
+    json!({"ExprMap": {"data": data}})
+}
+
+/// `Nil`
+pub fn expr_nil() -> Value {
+    json!({"ExprPar": {"data": []}})
+}
+
+/// An explore-deploy response whose `expr` holds `exprs`
+pub fn explore_response(exprs: Vec<Value>) -> Value {
+    json!({"expr": exprs, "block": {"blockHash": "b0", "blockNumber": 0}})
+}
+
+/// A vault transfer result, `(true, Nil)` on success or `(false, reason)`
+pub fn transfer_result(error: Option<&str>) -> Value {
+    match error {
+        None => expr_tuple(vec![expr_bool(true), expr_nil()]),
+        Some(reason) => expr_tuple(vec![expr_bool(false), expr_string(reason)]),
+    }
+}
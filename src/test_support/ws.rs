@@ -0,0 +1,120 @@
+//! Mock node serving a scripted `/ws/events` feed next to its HTTP API
+
+use super::http::answer;
+use futures_util::{SinkExt, StreamExt};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::Message;
+
+/// What the mock sends on one WebSocket connection
+#[derive(Debug, Clone, Default)]
+pub(crate) struct WsSession {
+    pub frames: Vec<String>,
+    /// Close the connection after the frames instead of holding it open
+    pub disconnect: bool,
+}
+
+impl WsSession {
+    pub(crate) fn stay(frames: Vec<String>) -> Self {
+        Self {
+            frames,
+            disconnect: false,
+        }
+    }
+
+    pub(crate) fn disconnect(frames: Vec<String>) -> Self {
+        Self {
+            frames,
+            disconnect: true,
+        }
+    }
+}
+
+/// A running mock node; counts its WebSocket connections
+pub(crate) struct EventNode {
+    pub port: u16,
+    accepted: Arc<AtomicUsize>,
+    open: Arc<AtomicUsize>,
+}
+
+impl EventNode {
+    /// WebSocket connections accepted so far
+    pub(crate) fn accepted(&self) -> usize {
+        self.accepted.load(Ordering::SeqCst)
+    }
+
+    /// WebSocket connections the client has not closed yet
+    pub(crate) fn open(&self) -> usize {
+        self.open.load(Ordering::SeqCst)
+    }
+}
+
+/// Serve WebSocket upgrades from `sessions` and other requests with `handler`
+///
+/// The `n`th connection is sent `sessions[n]`; connections past the script
+/// are held open with nothing sent. `handler` is as for
+/// [`spawn_http_node`](super::spawn_http_node).
+pub(crate) async fn spawn_event_node<F>(sessions: Vec<WsSession>, handler: F) -> EventNode
+where
+    F: Fn(&str, &str) -> (&'static str, String) + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let node = EventNode {
+        port: listener.local_addr().unwrap().port(),
+        accepted: Arc::default(),
+        open: Arc::default(),
+    };
+    let accepted = node.accepted.clone();
+    let open = node.open.clone();
+    let handler = Arc::new(handler);
+    tokio::spawn(async move {
+        while let Ok((mut socket, _)) = listener.accept().await {
+            if !is_upgrade(&socket).await {
+                let handler = handler.clone();
+                tokio::spawn(async move { answer(&mut socket, &*handler).await });
+                continue;
+            }
+            let session = sessions
+                .get(accepted.fetch_add(1, Ordering::SeqCst))
+                .cloned()
+                .unwrap_or_default();
+            let open = open.clone();
+            open.fetch_add(1, Ordering::SeqCst);
+            tokio::spawn(async move {
+                if let Ok(mut ws) = tokio_tungstenite::accept_async(socket).await {
+                    for frame in session.frames {
+                        if ws.send(Message::text(frame)).await.is_err() {
+                            break;
+                        }
+                    }
+                    if session.disconnect {
+                        let _ = ws.close(None).await;
+                    } else {
+                        while let Some(Ok(msg)) = ws.next().await {
+                            if msg.is_close() {
+                                break;
+                            }
+                        }
+                    }
+                }
+                open.fetch_sub(1, Ordering::SeqCst);
+            });
+        }
+    });
+    node
+}
+
+/// Whether the request waiting on `socket` asks for a WebSocket upgrade
+async fn is_upgrade(socket: &TcpStream) -> bool {
+    let mut buf = [0u8; 4096];
+    loop {
+        let n = socket.peek(&mut buf).await.unwrap_or(0);
+        let head = String::from_utf8_lossy(&buf[..n]).to_ascii_lowercase();
+        if n == 0 || n == buf.len() || head.contains("\r\n\r\n") {
+            return head.contains("upgrade: websocket");
+        }
+        tokio::time::sleep(Duration::from_millis(1)).await;
+    }
+}
@@ -0,0 +1,434 @@
+//! Funded wallets for test fixtures (`create-test-wallets`)
+//!
+//! Key pairs are generated and written to the manifest before anything is
+//! sent, so an interrupted run can be resumed from it. Wallets are funded by
+//! one transfer deploy per chunk of recipients, and the manifest is rewritten
+//! after each chunk. Every funded wallet's balance is then read back with
+//! concurrent exploratory deploys and its status recorded.
+//!
+//! A chunk whose deploy was sent but not recorded before an interruption is
+//! funded again on resume, so a wallet counts as verified when its balance is
+//! at least the requested amount.
+
+use crate::error::{NodeCliError, Result};
+use crate::staking::StakingQueries;
+use crate::store::write_atomic;
+use crate::utils::CryptoUtils;
+use futures_util::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Recipients funded by one deploy
+pub const DEFAULT_CHUNK_SIZE: usize = 20;
+
+/// Balance queries in flight at once
+pub const DEFAULT_VERIFY_CONCURRENCY: usize = 8;
+
+/// Manifest name when the output is a directory
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+pub const MANIFEST_VERSION: u32 = 1;
+
+/// Where a wallet is in the funding process
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WalletStatus {
+    /// Keys generated, nothing sent yet
+    Generated,
+    /// Funding deploy finalized, balance not checked yet
+    Funded,
+    /// Balance is at least the requested amount
+    Verified,
+    /// Funding or verification failed; see `error`
+    Failed,
+}
+
+/// One generated wallet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TestWallet {
+    pub index: usize,
+    pub private_key: String,
+    pub public_key: String,
+    pub address: String,
+    pub status: WalletStatus,
+    /// Deploy that funded the wallet
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub funding_deploy: Option<String>,
+    /// Balance in dust at verification
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub balance: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl TestWallet {
+    pub fn generate(index: usize) -> Result<Self> {
+        let (secret_key, public_key) = CryptoUtils::generate_key_pair()?;
+        let public_key = CryptoUtils::serialize_public_key(&public_key, false);
+        let address = CryptoUtils::generate_vault_address(&public_key)?;
+        Ok(Self {
+            index,
+            private_key: CryptoUtils::serialize_private_key(&secret_key),
+            public_key,
+            address,
+            status: WalletStatus::Generated,
+            funding_deploy: None,
+            balance: None,
+            error: None,
+        })
+    }
+
+    /// Whether a funding deploy still has to be sent
+    ///
+    /// A wallet whose deploy finalized but whose balance did not check out is
+    /// not funded twice.
+    pub fn needs_funding(&self) -> bool {
+        self.funding_deploy.is_none()
+            && matches!(self.status, WalletStatus::Generated | WalletStatus::Failed)
+    }
+}
+
+/// Wallets and how far funding got
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub schema_version: u32,
+    /// Vault address of the funder
+    pub funder: String,
+    /// Dust sent to each wallet
+    pub amount_dust: u64,
+    pub wallets: Vec<TestWallet>,
+}
+
+/// Wallet counts by status
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Tally {
+    pub verified: usize,
+    pub funded: usize,
+    pub failed: usize,
+    pub pending: usize,
+}
+
+impl Manifest {
+    /// `count` fresh wallets, none funded yet
+    pub fn generate(funder: &str, amount_dust: u64, count: usize) -> Result<Self> {
+        Ok(Self {
+            schema_version: MANIFEST_VERSION,
+            funder: funder.to_string(),
+            amount_dust,
+            wallets: (0..count)
+                .map(TestWallet::generate)
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    /// Positions of the wallets still to fund, `chunk_size` per deploy
+    pub fn funding_chunks(&self, chunk_size: usize) -> Vec<Vec<usize>> {
+        let pending: Vec<usize> = self
+            .wallets
+            .iter()
+            .enumerate()
+            .filter(|(_, wallet)| wallet.needs_funding())
+            .map(|(i, _)| i)
+            .collect();
+        pending
+            .chunks(chunk_size.max(1))
+            .map(<[usize]>::to_vec)
+            .collect()
+    }
+
+    /// Record the finalized deploy that funded `chunk`
+    pub fn mark_funded(&mut self, chunk: &[usize], deploy_id: &str) {
+        for &i in chunk {
+            let wallet = &mut self.wallets[i];
+            wallet.status = WalletStatus::Funded;
+            wallet.funding_deploy = Some(deploy_id.to_string());
+            wallet.error = None;
+        }
+    }
+
+    /// Record that funding `chunk` failed; it is retried on resume
+    pub fn mark_funding_failed(&mut self, chunk: &[usize], error: &str) {
+        for &i in chunk {
+            let wallet = &mut self.wallets[i];
+            wallet.status = WalletStatus::Failed;
+            wallet.error = Some(format!("funding failed: {}", error));
+        }
+    }
+
+    /// Record the balance read for the wallet at `i`
+    pub fn record_balance(&mut self, i: usize, balance: Result<i64>) {
+        let amount = self.amount_dust;
+        let wallet = &mut self.wallets[i];
+        match balance {
+            Ok(balance) => {
+                let balance = balance.max(0) as u64;
+                wallet.balance = Some(balance);
+                if balance >= amount {
+                    wallet.status = WalletStatus::Verified;
+                    wallet.error = None;
+                } else {
+                    wallet.status = WalletStatus::Failed;
+                    wallet.error = Some(format!(
+                        "balance {} dust, expected at least {}",
+                        balance, amount
+                    ));
+                }
+            }
+            Err(e) => {
+                wallet.status = WalletStatus::Failed;
+                wallet.error = Some(format!("balance query failed: {}", e));
+            }
+        }
+    }
+
+    /// Positions of the wallets whose balance should be checked
+    pub fn to_verify(&self) -> Vec<usize> {
+        self.wallets
+            .iter()
+            .enumerate()
+            .filter(|(_, wallet)| wallet.funding_deploy.is_some())
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    pub fn tally(&self) -> Tally {
+        let mut tally = Tally::default();
+        for wallet in &self.wallets {
+            match wallet.status {
+                WalletStatus::Verified => tally.verified += 1,
+                WalletStatus::Funded => tally.funded += 1,
+                WalletStatus::Failed => tally.failed += 1,
+                WalletStatus::Generated => tally.pending += 1,
+            }
+        }
+        tally
+    }
+}
+
+/// Read every funded wallet's balance, `concurrency` queries at a time
+pub async fn verify_balances(
+    queries: &StakingQueries,
+    manifest: &mut Manifest,
+    concurrency: usize,
+) {
+    let positions = manifest.to_verify();
+    let addresses: Vec<(usize, String)> = positions
+        .into_iter()
+        .map(|i| (i, manifest.wallets[i].address.clone()))
+        .collect();
+    let balances: Vec<(usize, Result<i64>)> = stream::iter(addresses)
+        .map(|(i, address)| async move { (i, queries.vault_balance(&address).await) })
+        .buffer_unordered(concurrency.max(1))
+        .collect()
+        .await;
+    for (i, balance) in balances {
+        manifest.record_balance(i, balance);
+    }
+}
+
+/// Where the manifest goes: a `.json` file, or a directory that also gets one
+/// file per wallet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Output {
+    File(PathBuf),
+    Directory(PathBuf),
+}
+
+impl Output {
+    pub fn new(path: &Path) -> Self {
+        let json = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+        if json {
+            Output::File(path.to_path_buf())
+        } else {
+            Output::Directory(path.to_path_buf())
+        }
+    }
+
+    pub fn manifest_path(&self) -> PathBuf {
+        match self {
+            Output::File(path) => path.clone(),
+            Output::Directory(dir) => dir.join(MANIFEST_FILE),
+        }
+    }
+
+    /// The manifest left by an earlier run
+    pub fn load(&self) -> Result<Manifest> {
+        let path = self.manifest_path();
+        let content = std::fs::read_to_string(&path).map_err(|e| {
+            NodeCliError::file_read_failed(&path.display().to_string(), &e.to_string())
+        })?;
+        serde_json::from_str(&content)
+            .map_err(|e| NodeCliError::parse_error(&format!("{}: {}", path.display(), e)))
+    }
+
+    /// Write the manifest, and with a directory one `wallet-NNNN.json` per wallet
+    pub fn write(&self, manifest: &Manifest) -> Result<()> {
+        let write = |path: &Path, json: String| {
+            write_atomic(path, json.as_bytes()).map_err(|e| {
+                NodeCliError::file_write_failed(&path.display().to_string(), &e.to_string())
+            })
+        };
+        if let Output::Directory(dir) = self {
+            for wallet in &manifest.wallets {
+                let path = dir.join(format!("wallet-{:04}.json", wallet.index));
+                write(&path, serde_json::to_string_pretty(wallet)?)?;
+            }
+        }
+        write(
+            &self.manifest_path(),
+            serde_json::to_string_pretty(manifest)?,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::spawn_http_node;
+    use crate::vault::build_multi_transfer_rholang;
+    use crate::wallet_watch::transfer_calls;
+
+    const FUNDER: &str = "1111AtahZeefej4tvVR6ti9TJtv8yxLebvyDoX4tKnbX9DZGQCExL";
+
+    #[test]
+    fn test_funding_chunks() {
+        let mut manifest = Manifest::generate(FUNDER, 500, 5).unwrap();
+        assert_eq!(manifest.funding_chunks(10), [vec![0, 1, 2, 3, 4]]);
+        assert_eq!(
+            manifest.funding_chunks(2),
+            [vec![0, 1], vec![2, 3], vec![4]]
+        );
+
+        // Funded wallets are skipped on resume; failed deploys are retried
+        manifest.mark_funded(&[0, 1], "d1");
+        manifest.mark_funding_failed(&[2, 3], "timed out");
+        assert_eq!(manifest.funding_chunks(2), [vec![2, 3], vec![4]]);
+        assert_eq!(
+            manifest.tally(),
+            Tally {
+                verified: 0,
+                funded: 2,
+                failed: 2,
+                pending: 1,
+            }
+        );
+
+        // A funded wallet whose balance is short is not funded again
+        manifest.record_balance(0, Ok(100));
+        assert_eq!(manifest.wallets[0].status, WalletStatus::Failed);
+        assert_eq!(manifest.funding_chunks(10), [vec![2, 3, 4]]);
+        assert_eq!(manifest.to_verify(), [0, 1]);
+    }
+
+    #[test]
+    fn test_multi_transfer_term_is_recognised() {
+        let manifest = Manifest::generate(FUNDER, 700, 3).unwrap();
+        let recipients: Vec<&str> = manifest
+            .wallets
+            .iter()
+            .map(|w| w.address.as_str())
+            .collect();
+        let term = build_multi_transfer_rholang(FUNDER, &recipients, 700);
+        let calls = transfer_calls(&term);
+        assert_eq!(calls.len(), 3);
+        for (call, address) in calls.iter().zip(&recipients) {
+            assert_eq!(call.to_address, *address);
+            assert_eq!(call.amount, Some(700));
+        }
+    }
+
+    async fn verify_with_mock(count: usize, short: usize) -> Manifest {
+        let mut manifest = Manifest::generate(FUNDER, 1000, count).unwrap();
+        let short_address = manifest.wallets[short].address.clone();
+        let port = spawn_http_node(move |_, body| {
+            let balance = if body.contains(&short_address) {
+                10
+            } else {
+                1000
+            };
+            (
+                "200 OK",
+                serde_json::json!({"expr": [{"ExprInt": {"data": balance}}]}).to_string(),
+            )
+        })
+        .await;
+        let all: Vec<usize> = (0..count).collect();
+        for chunk in manifest.funding_chunks(DEFAULT_CHUNK_SIZE) {
+            manifest.mark_funded(&chunk, "deploy");
+        }
+        assert!(manifest.funding_chunks(DEFAULT_CHUNK_SIZE).is_empty());
+        assert_eq!(manifest.to_verify(), all);
+        let queries = StakingQueries::new("127.0.0.1", port);
+        verify_balances(&queries, &mut manifest, 3).await;
+        manifest
+    }
+
+    #[tokio::test]
+    async fn test_verification_within_one_chunk() {
+        let manifest = verify_with_mock(4, 2).await;
+        let statuses: Vec<_> = manifest.wallets.iter().map(|w| w.status).collect();
+        assert_eq!(
+            statuses,
+            [
+                WalletStatus::Verified,
+                WalletStatus::Verified,
+                WalletStatus::Failed,
+                WalletStatus::Verified,
+            ]
+        );
+        assert_eq!(manifest.wallets[2].balance, Some(10));
+        assert_eq!(
+            manifest.wallets[2].error.as_deref(),
+            Some("balance 10 dust, expected at least 1000")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_verification_across_chunks() {
+        let count = DEFAULT_CHUNK_SIZE * 2 + 5;
+        let manifest = verify_with_mock(count, count - 1).await;
+        assert_eq!(
+            manifest.tally(),
+            Tally {
+                verified: count - 1,
+                funded: 0,
+                failed: 1,
+                pending: 0,
+            }
+        );
+        assert!(manifest.wallets.iter().all(|w| w.balance.is_some()));
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_node_fails_verification() {
+        let mut manifest = Manifest::generate(FUNDER, 1000, 2).unwrap();
+        manifest.mark_funded(&[0, 1], "deploy");
+        verify_balances(&StakingQueries::new("127.0.0.1", 1), &mut manifest, 2).await;
+        assert_eq!(manifest.tally().failed, 2);
+        // Still counts as funded, so a resume verifies instead of funding again
+        assert!(manifest.funding_chunks(10).is_empty());
+    }
+
+    #[test]
+    fn test_output_round_trip() {
+        let dir = std::env::temp_dir().join(format!("node_cli_wallets_{}", std::process::id()));
+        let mut manifest = Manifest::generate(FUNDER, 5, 2).unwrap();
+        manifest.mark_funded(&[1], "d1");
+
+        let output = Output::new(&dir);
+        assert_eq!(output, Output::Directory(dir.clone()));
+        output.write(&manifest).unwrap();
+        assert!(dir.join("wallet-0000.json").is_file());
+        assert!(dir.join("wallet-0001.json").is_file());
+        assert_eq!(output.load().unwrap(), manifest);
+
+        let file = Output::new(&dir.join("wallets.JSON"));
+        assert_eq!(file.manifest_path(), dir.join("wallets.JSON"));
+        file.write(&manifest).unwrap();
+        assert_eq!(file.load().unwrap(), manifest);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,292 @@
+//! Deploy lifecycle timeline
+//!
+//! Reconstructs when a deploy was created, included in a block and
+//! finalized from whatever the node and local history can tell us, and
+//! renders the known stages with the durations between them. The rendering
+//! helpers take any list of stages and are shared with `follow-block`. Stages with no
+//! data are shown as pending (not reached yet) or unknown (reached, time not
+//! recorded).
+
+use crate::utils::format_duration;
+use chrono::{TimeZone, Utc};
+use serde_json::json;
+use std::time::Duration;
+
+/// When a deploy's block was finalized, as far as can be told
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Finalization {
+    /// Observed by a deploy-and-wait run and recorded in local history
+    Recorded(i64),
+    /// Finalized no later than this time (timestamp of the last finalized block)
+    NoLaterThan(i64),
+    /// Finalized, but when is unknown
+    Unknown,
+    /// Not finalized yet
+    Pending,
+}
+
+/// Time of one lifecycle stage
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StageTime {
+    At(i64),
+    /// Upper bound only
+    NoLaterThan(i64),
+    Unknown,
+    Pending,
+}
+
+impl StageTime {
+    fn millis(&self) -> Option<i64> {
+        match self {
+            StageTime::At(ms) | StageTime::NoLaterThan(ms) => Some(*ms),
+            StageTime::Unknown | StageTime::Pending => None,
+        }
+    }
+}
+
+/// Lifecycle of a deploy, all times in Unix epoch milliseconds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeployTimeline {
+    /// Timestamp signed into the deploy
+    pub deployed: Option<i64>,
+    /// Whether the deploy has been included in a block
+    pub in_block: bool,
+    /// Timestamp of the including block
+    pub included: Option<i64>,
+    pub finalization: Finalization,
+}
+
+impl DeployTimeline {
+    /// The three stages in order with their times
+    pub fn stages(&self) -> [(&'static str, StageTime); 3] {
+        let deployed = self.deployed.map_or(StageTime::Unknown, StageTime::At);
+        let included = match (self.in_block, self.included) {
+            (false, _) => StageTime::Pending,
+            (true, Some(ms)) => StageTime::At(ms),
+            (true, None) => StageTime::Unknown,
+        };
+        let finalized = match (self.in_block, self.finalization) {
+            (false, _) | (_, Finalization::Pending) => StageTime::Pending,
+            (_, Finalization::Recorded(ms)) => StageTime::At(ms),
+            (_, Finalization::NoLaterThan(ms)) => StageTime::NoLaterThan(ms),
+            (_, Finalization::Unknown) => StageTime::Unknown,
+        };
+        [
+            ("deployed", deployed),
+            ("included", included),
+            ("finalized", finalized),
+        ]
+    }
+
+    /// One line per stage: name, time and how long after the previous stage
+    pub fn render(&self) -> Vec<String> {
+        render_stages(&self.stages())
+    }
+
+    /// Durations between consecutive stages, e.g.
+    /// "deployed → included: 14s, included → finalized: 1m 32s"
+    pub fn summary(&self) -> String {
+        summarize_stages(&self.stages())
+    }
+
+    /// Timestamps as epoch millis (null when not known) for `--format json`
+    pub fn to_json(&self) -> serde_json::Value {
+        let [(_, deployed), (_, included), (_, finalized)] = self.stages();
+        let finalization = match finalized {
+            StageTime::At(_) => "recorded",
+            StageTime::NoLaterThan(_) => "upper_bound",
+            StageTime::Unknown => "unknown",
+            StageTime::Pending => "pending",
+        };
+        json!({
+            "deployed_at": deployed.millis(),
+            "included_at": included.millis(),
+            "finalized_at": finalized.millis(),
+            "finalization": finalization,
+        })
+    }
+}
+
+/// One line per stage: name, time and how long after the previous stage
+///
+/// Shared by every lifecycle view (deploys here, blocks in
+/// [`crate::block_follow`]) so they read the same.
+pub fn render_stages(stages: &[(&str, StageTime)]) -> Vec<String> {
+    stages
+        .iter()
+        .enumerate()
+        .map(|(i, (name, time))| {
+            let when = match time {
+                StageTime::At(ms) => format_millis(*ms),
+                StageTime::NoLaterThan(ms) => format!("by {}", format_millis(*ms)),
+                StageTime::Unknown => "unknown".to_string(),
+                StageTime::Pending => "pending".to_string(),
+            };
+            match i.checked_sub(1).map(|p| stage_gap(&stages[p].1, time)) {
+                Some(Some(gap)) => format!("{:<10} {}  (+{})", name, when, gap),
+                _ => format!("{:<10} {}", name, when),
+            }
+        })
+        .collect()
+}
+
+/// Durations between consecutive stages, e.g. "created → added: 2s"
+pub fn summarize_stages(stages: &[(&str, StageTime)]) -> String {
+    stages
+        .windows(2)
+        .map(|pair| {
+            let (from, from_time) = pair[0];
+            let (to, to_time) = pair[1];
+            let gap = match to_time {
+                StageTime::Pending => "pending".to_string(),
+                _ => stage_gap(&from_time, &to_time).unwrap_or_else(|| "unknown".to_string()),
+            };
+            format!("{} → {}: {}", from, to, gap)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Time between two stages; `None` unless both are known
+fn stage_gap(from: &StageTime, to: &StageTime) -> Option<String> {
+    let gap = to.millis()? - from.millis()?;
+    let formatted = format_duration(Duration::from_millis(gap.unsigned_abs()));
+    Some(match (gap < 0, to) {
+        (true, _) => format!("-{} (clock skew)", formatted),
+        (false, StageTime::NoLaterThan(_)) => format!("≤{}", formatted),
+        (false, _) => formatted,
+    })
+}
+
+fn format_millis(ms: i64) -> String {
+    Utc.timestamp_millis_opt(ms)
+        .single()
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S%.3f UTC").to_string())
+        .unwrap_or_else(|| ms.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const T0: i64 = 1_767_225_600_000; // 2026-01-01 00:00:00 UTC
+
+    fn timeline(
+        deployed: Option<i64>,
+        included: Option<i64>,
+        finalization: Finalization,
+    ) -> DeployTimeline {
+        DeployTimeline {
+            deployed,
+            in_block: included.is_some(),
+            included,
+            finalization,
+        }
+    }
+
+    #[test]
+    fn test_summary_all_stages_known() {
+        let t = timeline(
+            Some(T0),
+            Some(T0 + 14_000),
+            Finalization::Recorded(T0 + 106_000),
+        );
+        assert_eq!(
+            t.summary(),
+            "deployed → included: 14s, included → finalized: 1m 32s"
+        );
+        let lines = t.render();
+        assert_eq!(lines[0], "deployed   2026-01-01 00:00:00.000 UTC");
+        assert_eq!(lines[1], "included   2026-01-01 00:00:14.000 UTC  (+14s)");
+        assert_eq!(
+            lines[2],
+            "finalized  2026-01-01 00:01:46.000 UTC  (+1m 32s)"
+        );
+    }
+
+    #[test]
+    fn test_summary_partial_data() {
+        // Not in a block yet: finalization is pending whatever the flag says
+        let t = timeline(Some(T0), None, Finalization::Unknown);
+        assert_eq!(
+            t.summary(),
+            "deployed → included: pending, included → finalized: pending"
+        );
+
+        let t = timeline(Some(T0), Some(T0 + 5_000), Finalization::Pending);
+        assert_eq!(
+            t.summary(),
+            "deployed → included: 5s, included → finalized: pending"
+        );
+
+        let t = timeline(Some(T0), Some(T0 + 5_000), Finalization::Unknown);
+        assert_eq!(
+            t.summary(),
+            "deployed → included: 5s, included → finalized: unknown"
+        );
+        assert_eq!(t.render()[2], "finalized  unknown");
+
+        // In a block whose header could not be fetched
+        let t = DeployTimeline {
+            deployed: Some(T0),
+            in_block: true,
+            included: None,
+            finalization: Finalization::Recorded(T0 + 30_000),
+        };
+        assert_eq!(
+            t.summary(),
+            "deployed → included: unknown, included → finalized: unknown"
+        );
+        assert_eq!(t.render()[1], "included   unknown");
+
+        // Basic view without the deploy timestamp
+        let t = timeline(None, Some(T0), Finalization::NoLaterThan(T0 + 60_000));
+        assert_eq!(
+            t.summary(),
+            "deployed → included: unknown, included → finalized: ≤1m"
+        );
+        assert_eq!(t.render()[0], "deployed   unknown");
+        assert_eq!(
+            t.render()[2],
+            "finalized  by 2026-01-01 00:01:00.000 UTC  (+≤1m)"
+        );
+    }
+
+    #[test]
+    fn test_clock_skew_is_flagged() {
+        let t = timeline(Some(T0 + 3_000), Some(T0), Finalization::Pending);
+        assert_eq!(
+            t.summary(),
+            "deployed → included: -3s (clock skew), included → finalized: pending"
+        );
+    }
+
+    #[test]
+    fn test_to_json_uses_epoch_millis() {
+        let t = timeline(
+            Some(T0),
+            Some(T0 + 14_000),
+            Finalization::NoLaterThan(T0 + 20_000),
+        );
+        assert_eq!(
+            t.to_json(),
+            json!({
+                "deployed_at": T0,
+                "included_at": T0 + 14_000,
+                "finalized_at": T0 + 20_000,
+                "finalization": "upper_bound"
+            })
+        );
+
+        let t = timeline(None, None, Finalization::Pending);
+        assert_eq!(
+            t.to_json(),
+            json!({
+                "deployed_at": null,
+                "included_at": null,
+                "finalized_at": null,
+                "finalization": "pending"
+            })
+        );
+    }
+}
@@ -0,0 +1,497 @@
+//! Opt-in local timing samples
+//!
+//! When `FIREFLY_METRICS` is on, deploy-family and query commands append one
+//! sample per run to `<config dir>/timings.jsonl`: the command name, a short
+//! hash of the node address, how long each phase took, and whether the command
+//! succeeded. Nothing else is stored and nothing is sent anywhere.
+//!
+//! The file is capped at [`MAX_FILE_BYTES`]. When an append takes it over the
+//! cap it is rewritten with the newest samples that fit in half of it. Lines
+//! that fail to parse (e.g. from a crash mid-write) are skipped when reading
+//! and dropped at the next rewrite. Like the deploy history, recording is
+//! best-effort and never fails a command.
+
+use crate::history::term_hash;
+use crate::store::{self, FileLock};
+use crate::utils::{now_millis, Summary};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Environment variable turning sample recording on (`1`, `true`, `yes`, `on`)
+pub const METRICS_ENV: &str = "FIREFLY_METRICS";
+
+/// Environment variable overriding the samples file location
+pub const METRICS_FILE_ENV: &str = "FIREFLY_METRICS_FILE";
+
+const METRICS_FILE_NAME: &str = "timings.jsonl";
+
+/// Version written in the samples file's header line
+const METRICS_SCHEMA_VERSION: u32 = 1;
+
+/// Size above which the samples file is compacted
+pub const MAX_FILE_BYTES: u64 = 1024 * 1024;
+
+/// Phase name holding the whole command's duration
+pub const TOTAL_PHASE: &str = "total";
+
+/// How a sampled command ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SampleOutcome {
+    Ok,
+    Failed,
+}
+
+/// One line of the samples file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimingSample {
+    /// Unix timestamp in milliseconds
+    pub timestamp: i64,
+    pub command: String,
+    /// Short hash of the node's `host:port`, or `unknown`
+    pub node: String,
+    /// Milliseconds per phase; always has `total`
+    pub phases: BTreeMap<String, u64>,
+    /// Event counts such as query cache hits; omitted when nothing was counted
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub counters: BTreeMap<String, u64>,
+    pub outcome: SampleOutcome,
+}
+
+/// Short, non-reversible identifier for a node address
+pub fn node_hash(host: &str, port: u16) -> String {
+    term_hash(&format!("{}:{}", host, port))
+}
+
+/// Phase durations and node gathered while one command runs
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TimingCollector {
+    node: Option<String>,
+    phases: BTreeMap<String, u64>,
+    counters: BTreeMap<String, u64>,
+}
+
+impl TimingCollector {
+    /// Add `duration` to a phase; a phase seen twice accumulates
+    pub fn phase(&mut self, name: &str, duration: Duration) {
+        *self.phases.entry(name.to_string()).or_default() += duration.as_millis() as u64;
+    }
+
+    /// Add `n` to a counter
+    pub fn count(&mut self, name: &str, n: u64) {
+        *self.counters.entry(name.to_string()).or_default() += n;
+    }
+
+    /// Remember the first node the command talked to
+    pub fn observe_node(&mut self, host: &str, port: u16) {
+        self.node.get_or_insert_with(|| node_hash(host, port));
+    }
+
+    pub fn into_sample(
+        mut self,
+        command: &str,
+        total: Duration,
+        outcome: SampleOutcome,
+    ) -> TimingSample {
+        self.phases
+            .insert(TOTAL_PHASE.to_string(), total.as_millis() as u64);
+        TimingSample {
+            timestamp: now_millis(),
+            command: command.to_string(),
+            node: self.node.unwrap_or_else(|| "unknown".to_string()),
+            phases: self.phases,
+            counters: self.counters,
+            outcome,
+        }
+    }
+}
+
+/// Collector for the running command; `None` unless [`begin`] was called
+static CURRENT: Mutex<Option<TimingCollector>> = Mutex::new(None);
+
+fn with_current(f: impl FnOnce(&mut TimingCollector)) {
+    if let Ok(mut current) = CURRENT.lock() {
+        if let Some(collector) = current.as_mut() {
+            f(collector);
+        }
+    }
+}
+
+/// Start collecting phases for a command
+pub fn begin() {
+    if let Ok(mut current) = CURRENT.lock() {
+        *current = Some(TimingCollector::default());
+    }
+}
+
+/// Record a phase duration; a no-op unless collection has begun
+pub fn phase(name: &str, duration: Duration) {
+    with_current(|collector| collector.phase(name, duration));
+}
+
+/// Add to a counter; a no-op unless collection has begun
+pub fn count(name: &str, n: u64) {
+    with_current(|collector| collector.count(name, n));
+}
+
+/// Record the node being talked to; a no-op unless collection has begun
+pub fn observe_node(host: &str, port: u16) {
+    with_current(|collector| collector.observe_node(host, port));
+}
+
+/// Stop collecting and build the sample; `None` if collection never began
+pub fn finish(command: &str, total: Duration, outcome: SampleOutcome) -> Option<TimingSample> {
+    let collector = CURRENT.lock().ok()?.take()?;
+    Some(collector.into_sample(command, total, outcome))
+}
+
+/// Whether samples are recorded (`FIREFLY_METRICS`)
+pub fn enabled() -> bool {
+    std::env::var(METRICS_ENV)
+        .map(|v| matches!(v.to_ascii_lowercase().as_str(), "1" | "true" | "yes" | "on"))
+        .unwrap_or(false)
+}
+
+/// Location of the samples file, if a config directory can be resolved
+pub fn metrics_path() -> Option<PathBuf> {
+    if let Ok(path) = std::env::var(METRICS_FILE_ENV) {
+        if !path.is_empty() {
+            return Some(PathBuf::from(path));
+        }
+    }
+    crate::utils::config_dir().map(|dir| dir.join(METRICS_FILE_NAME))
+}
+
+/// Append a sample, compacting the file if it grows past `max_bytes`
+pub fn append_sample(path: &Path, sample: &TimingSample, max_bytes: u64) -> std::io::Result<()> {
+    store::append_line(
+        path,
+        METRICS_SCHEMA_VERSION,
+        &serde_json::to_string(sample)?,
+    )?;
+    if std::fs::metadata(path)?.len() > max_bytes {
+        compact(path, max_bytes / 2)?;
+    }
+    Ok(())
+}
+
+/// Rewrite the file with the newest valid samples fitting in `target_bytes`
+///
+/// The new contents go to a temporary file that is renamed over the old one,
+/// so an interrupted compaction leaves the previous file intact. Kept lines are
+/// copied verbatim, so fields added by a newer CLI survive.
+pub fn compact(path: &Path, target_bytes: u64) -> std::io::Result<()> {
+    let _lock = FileLock::acquire(path)?;
+    let mut kept = Vec::new();
+    let mut size = store::header_line(METRICS_SCHEMA_VERSION).len() as u64;
+    for (_, line) in store::read_lines(path)?.into_iter().rev() {
+        if serde_json::from_str::<TimingSample>(&line).is_err() {
+            continue;
+        }
+        size += line.len() as u64 + 1;
+        if size > target_bytes {
+            break;
+        }
+        kept.push(line);
+    }
+    kept.reverse();
+    store::rewrite_lines(path, METRICS_SCHEMA_VERSION, &kept)
+}
+
+/// Best-effort append to the default samples file
+pub fn record(sample: &TimingSample) -> bool {
+    let Some(path) = metrics_path() else {
+        tracing::warn!("Timing metrics enabled but no config directory could be resolved");
+        return false;
+    };
+    match append_sample(&path, sample, MAX_FILE_BYTES) {
+        Ok(()) => true,
+        Err(e) => {
+            tracing::warn!(path = %path.display(), "Failed to write timing sample: {}", e);
+            false
+        }
+    }
+}
+
+/// Read all samples, skipping lines that fail to parse
+///
+/// A missing file is treated as no samples.
+pub fn read_samples(path: &Path) -> std::io::Result<Vec<TimingSample>> {
+    let mut samples = Vec::new();
+    for (number, line) in store::read_lines(path)? {
+        match serde_json::from_str::<TimingSample>(&line) {
+            Ok(sample) => samples.push(sample),
+            Err(e) => tracing::warn!(line = number, "Skipping malformed timing sample: {}", e),
+        }
+    }
+    Ok(samples)
+}
+
+/// Summaries for one command on one node
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupStats {
+    pub command: String,
+    pub node: String,
+    pub ok: usize,
+    pub failed: usize,
+    /// Per phase, over successful runs only
+    pub phases: BTreeMap<String, Summary>,
+    /// Counter totals over all runs
+    pub counters: BTreeMap<String, u64>,
+}
+
+/// Group samples taken in `[from, to)` by command and node
+///
+/// Failed runs are counted but left out of the percentiles, since they
+/// usually end on a timeout rather than a measurement.
+pub fn summarize(samples: &[TimingSample], from: i64, to: i64) -> Vec<GroupStats> {
+    type Group<'s> = (
+        usize,
+        usize,
+        BTreeMap<&'s str, Vec<u64>>,
+        BTreeMap<&'s str, u64>,
+    );
+    let mut groups: BTreeMap<(&str, &str), Group> = BTreeMap::new();
+    for sample in samples
+        .iter()
+        .filter(|s| s.timestamp >= from && s.timestamp < to)
+    {
+        let (ok, failed, phases, counters) = groups
+            .entry((sample.command.as_str(), sample.node.as_str()))
+            .or_default();
+        for (counter, n) in &sample.counters {
+            *counters.entry(counter.as_str()).or_default() += n;
+        }
+        match sample.outcome {
+            SampleOutcome::Ok => {
+                *ok += 1;
+                for (phase, ms) in &sample.phases {
+                    phases.entry(phase.as_str()).or_default().push(*ms);
+                }
+            }
+            SampleOutcome::Failed => *failed += 1,
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(
+            |((command, node), (ok, failed, phases, counters))| GroupStats {
+                command: command.to_string(),
+                node: node.to_string(),
+                ok,
+                failed,
+                phases: phases
+                    .into_iter()
+                    .filter_map(|(phase, ms)| Some((phase.to_string(), Summary::from_samples(ms)?)))
+                    .collect(),
+                counters: counters
+                    .into_iter()
+                    .map(|(counter, n)| (counter.to_string(), n))
+                    .collect(),
+            },
+        )
+        .collect()
+}
+
+/// Window length back from `now`, and the same length before it, as `[from, to)` pairs
+pub fn windows(now: i64, window: Duration) -> ((i64, i64), (i64, i64)) {
+    let len = window.as_millis() as i64;
+    let current = (now - len, now + 1);
+    let previous = (now - 2 * len, now - len);
+    (previous, current)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::Comparison;
+    use std::fs::OpenOptions;
+    use std::io::Write;
+
+    const HOUR: i64 = 3_600_000;
+
+    /// Fresh path under the temp dir, unique to this process and test
+    fn temp_path(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("node_cli_timings_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("{}.jsonl", test));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn sample(timestamp: i64, command: &str, node: &str, inclusion_ms: u64) -> TimingSample {
+        TimingSample {
+            timestamp,
+            command: command.to_string(),
+            node: node.to_string(),
+            phases: BTreeMap::from([
+                ("inclusion".to_string(), inclusion_ms),
+                (TOTAL_PHASE.to_string(), inclusion_ms + 100),
+            ]),
+            counters: BTreeMap::new(),
+            outcome: SampleOutcome::Ok,
+        }
+    }
+
+    #[test]
+    fn test_collector_builds_sample() {
+        let mut collector = TimingCollector::default();
+        collector.observe_node("node0", 40402);
+        collector.observe_node("node1", 40402);
+        collector.phase("inclusion", Duration::from_millis(300));
+        collector.phase("inclusion", Duration::from_millis(200));
+        let sample =
+            collector.into_sample("deploy-and-wait", Duration::from_secs(2), SampleOutcome::Ok);
+        assert_eq!(sample.node, node_hash("node0", 40402));
+        assert_eq!(sample.phases["inclusion"], 500);
+        assert_eq!(sample.phases[TOTAL_PHASE], 2000);
+        assert!(!sample.node.contains("node0"));
+    }
+
+    #[test]
+    fn test_counters_are_summed() {
+        let mut collector = TimingCollector::default();
+        collector.count("query_cache_hits", 2);
+        collector.count("query_cache_hits", 3);
+        let sample = collector.into_sample("bonds", Duration::from_millis(5), SampleOutcome::Ok);
+        assert_eq!(sample.counters["query_cache_hits"], 5);
+
+        let mut failed = sample.clone();
+        failed.outcome = SampleOutcome::Failed;
+        let plain = TimingCollector::default().into_sample(
+            "bonds",
+            Duration::from_millis(5),
+            SampleOutcome::Ok,
+        );
+        // Samples without counters keep the old line format
+        assert!(!serde_json::to_string(&plain).unwrap().contains("counters"));
+
+        let samples = [sample, failed, plain];
+        let groups = summarize(&samples, 0, i64::MAX);
+        assert_eq!(groups[0].counters["query_cache_hits"], 10);
+    }
+
+    #[test]
+    fn test_sample_without_node_is_unknown() {
+        let sample = TimingCollector::default().into_sample(
+            "status",
+            Duration::from_millis(5),
+            SampleOutcome::Failed,
+        );
+        assert_eq!(sample.node, "unknown");
+        let json = serde_json::to_string(&sample).unwrap();
+        assert!(json.contains(r#""outcome":"failed""#), "{}", json);
+    }
+
+    #[test]
+    fn test_append_compacts_to_newest() {
+        let path = temp_path("compacts");
+        let line_len = serde_json::to_string(&sample(0, "deploy", "n", 0))
+            .unwrap()
+            .len() as u64
+            + 1;
+        let max_bytes = line_len * 10;
+
+        for i in 0..25 {
+            append_sample(&path, &sample(i, "deploy", "n", 0), max_bytes).unwrap();
+            assert!(std::fs::metadata(&path).unwrap().len() <= max_bytes);
+        }
+        let samples = read_samples(&path).unwrap();
+        assert!(!samples.is_empty() && samples.len() <= 10);
+        assert_eq!(samples.last().unwrap().timestamp, 24);
+        assert!(samples.windows(2).all(|w| w[0].timestamp < w[1].timestamp));
+    }
+
+    #[test]
+    fn test_corrupt_lines_are_skipped_and_dropped() {
+        let path = temp_path("corrupt");
+        append_sample(&path, &sample(1, "deploy", "n", 10), MAX_FILE_BYTES).unwrap();
+        {
+            let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+            file.write_all(b"{\"timestamp\":2,\"comm\n\xff\xfe garbage\n\n")
+                .unwrap();
+        }
+        append_sample(&path, &sample(3, "deploy", "n", 30), MAX_FILE_BYTES).unwrap();
+
+        let samples = read_samples(&path).unwrap();
+        assert_eq!(
+            samples.iter().map(|s| s.timestamp).collect::<Vec<_>>(),
+            [1, 3]
+        );
+
+        compact(&path, MAX_FILE_BYTES).unwrap();
+        let contents = std::fs::read(&path).unwrap();
+        assert!(!contents.windows(7).any(|w| w == b"garbage"));
+        assert_eq!(read_samples(&path).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_missing_file_is_empty() {
+        assert!(read_samples(&temp_path("missing")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_summarize_groups_by_command_and_node() {
+        let mut samples: Vec<TimingSample> = (0..10)
+            .map(|i| sample(i, "deploy-and-wait", "a", 1000 + i as u64 * 100))
+            .collect();
+        samples.push(sample(5, "deploy-and-wait", "b", 50));
+        samples.push(sample(5, "transfer", "a", 70));
+        let mut failed = sample(6, "deploy-and-wait", "a", 99_999);
+        failed.outcome = SampleOutcome::Failed;
+        samples.push(failed);
+        // Outside the window
+        samples.push(sample(100, "deploy-and-wait", "a", 1));
+
+        let groups = summarize(&samples, 0, 100);
+        let keys: Vec<_> = groups
+            .iter()
+            .map(|g| (g.command.as_str(), g.node.as_str()))
+            .collect();
+        assert_eq!(
+            keys,
+            [
+                ("deploy-and-wait", "a"),
+                ("deploy-and-wait", "b"),
+                ("transfer", "a")
+            ]
+        );
+
+        let main = &groups[0];
+        assert_eq!((main.ok, main.failed), (10, 1));
+        let inclusion = main.phases["inclusion"];
+        assert_eq!(inclusion.count, 10);
+        assert_eq!(inclusion.p50, 1400);
+        assert_eq!(inclusion.p95, 1900);
+        assert_eq!(main.phases[TOTAL_PHASE].p50, 1500);
+    }
+
+    #[test]
+    fn test_week_over_week_regression() {
+        let now = 1_000 * HOUR;
+        let week = Duration::from_secs(7 * 24 * 3600);
+        let (previous, current) = windows(now, week);
+
+        let mut samples = Vec::new();
+        for i in 0..20 {
+            samples.push(sample(
+                previous.0 + i * HOUR,
+                "deploy-and-wait",
+                "a",
+                10_000,
+            ));
+            samples.push(sample(current.0 + i * HOUR, "deploy-and-wait", "a", 16_000));
+        }
+
+        let before = &summarize(&samples, previous.0, previous.1)[0];
+        let after = &summarize(&samples, current.0, current.1)[0];
+        assert_eq!((before.ok, after.ok), (20, 20));
+        let comparison = Comparison::new(before.phases["inclusion"], after.phases["inclusion"]);
+        assert!((comparison.p50_change() - 0.6).abs() < 1e-9);
+        assert!(comparison.is_regression(0.2, 5));
+    }
+}
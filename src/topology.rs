@@ -0,0 +1,215 @@
+//! Node topology files
+//!
+//! Multi-node commands accept either `--node` flags or a topology file
+//! listing the nodes of a shard. A topology file is JSON:
+//!
+//! ```json
+//! {
+//!   "nodes": [
+//!     { "name": "validator1", "host": "localhost", "grpc_port": 40412, "http_port": 40413 },
+//!     { "name": "observer", "host": "localhost", "http_port": 40453 }
+//!   ],
+//!   "pos": {
+//!     "root": { "uris": ["rho:id:m3xk7h8r54dtqtwsrnxqzhe81baswey66nzw6m533nyd45ptyoybqr"] }
+//!   }
+//! }
+//! ```
+//!
+//! The optional `pos` map gives, per shard ID, the PoS contract URIs or
+//! fingerprints that `--verify-pos` accepts. Staking commands read it from
+//! `--topology`, or from `topology.json` in the config directory.
+//!
+//! The optional `environment` and `shard_id` describe the shard for the
+//! insecure key guard in [`crate::key_guard`]: `"environment": "dev"` lets the
+//! well-known dev keys sign against remote nodes, and a node reporting another
+//! shard ID than `shard_id` is warned about.
+
+use crate::error::{NodeCliError, Result};
+use crate::staking::PosExpectation;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Topology file looked for in the config directory
+pub const DEFAULT_TOPOLOGY_FILE: &str = "topology.json";
+
+/// A single node reachable over HTTP (and optionally gRPC)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeEndpoint {
+    /// Display name; defaults to `host:http_port`
+    #[serde(default)]
+    pub name: String,
+    pub host: String,
+    #[serde(default)]
+    pub grpc_port: Option<u16>,
+    pub http_port: u16,
+}
+
+impl NodeEndpoint {
+    /// Parse a `--node` value of the form `host:http_port` or `name=host:http_port`
+    pub fn parse(spec: &str) -> Result<Self> {
+        let spec = spec.trim();
+        let (name, address) = match spec.split_once('=') {
+            Some((name, address)) => (name.trim().to_string(), address.trim()),
+            None => (String::new(), spec),
+        };
+
+        let (host, port) = address.rsplit_once(':').ok_or_else(|| {
+            NodeCliError::config_invalid_value("node", &format!("'{}' is not host:port", spec))
+        })?;
+        if host.is_empty() {
+            return Err(NodeCliError::config_invalid_value(
+                "node",
+                &format!("'{}' has an empty host", spec),
+            ));
+        }
+        let http_port = port.parse::<u16>().map_err(|_| {
+            NodeCliError::config_invalid_value("node", &format!("'{}' has an invalid port", spec))
+        })?;
+
+        Ok(Self {
+            name,
+            host: host.to_string(),
+            grpc_port: None,
+            http_port,
+        }
+        .with_default_name())
+    }
+
+    /// `host:http_port`, used for display and as the default name
+    pub fn address(&self) -> String {
+        format!("{}:{}", self.host, self.http_port)
+    }
+
+    fn with_default_name(mut self) -> Self {
+        if self.name.is_empty() {
+            self.name = self.address();
+        }
+        self
+    }
+}
+
+/// The set of nodes making up a shard
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Topology {
+    #[serde(default)]
+    pub nodes: Vec<NodeEndpoint>,
+    /// Expected PoS contract by shard ID
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub pos: BTreeMap<String, PosExpectation>,
+    /// `dev` lets the well-known dev keys sign against these nodes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment: Option<String>,
+    /// Shard ID the nodes are expected to report
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shard_id: Option<String>,
+}
+
+impl Topology {
+    /// Load a topology file
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            NodeCliError::file_read_failed(&path.display().to_string(), &e.to_string())
+        })?;
+        Self::from_json(&content).map_err(|e| {
+            NodeCliError::config_invalid_value(&path.display().to_string(), &e.to_string())
+        })
+    }
+
+    /// Parse topology JSON, filling in default node names
+    pub fn from_json(content: &str) -> Result<Self> {
+        let topology: Topology = serde_json::from_str(content)?;
+        Ok(Self {
+            nodes: topology
+                .nodes
+                .into_iter()
+                .map(NodeEndpoint::with_default_name)
+                .collect(),
+            ..topology
+        })
+    }
+
+    /// Combine nodes from an optional topology file with `--node` specs
+    ///
+    /// Topology nodes come first, followed by the flag nodes in order.
+    pub fn resolve(path: Option<&Path>, specs: &[String]) -> Result<Self> {
+        let mut topology = match path {
+            Some(path) => Self::load(path)?,
+            None => Self::default(),
+        };
+        for spec in specs {
+            topology.nodes.push(NodeEndpoint::parse(spec)?);
+        }
+        Ok(topology)
+    }
+
+    /// The topology at `path`, else [`DEFAULT_TOPOLOGY_FILE`] in the config
+    /// directory; empty when there is no default file
+    pub fn active(path: Option<&Path>) -> Result<Self> {
+        if let Some(path) = path {
+            return Self::load(path);
+        }
+        match crate::utils::config_dir().map(|dir| dir.join(DEFAULT_TOPOLOGY_FILE)) {
+            Some(path) if path.exists() => Self::load(&path),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// Expected PoS contracts from the [active](Self::active) topology
+    pub fn pos_expectations(path: Option<&Path>) -> Result<BTreeMap<String, PosExpectation>> {
+        Ok(Self::active(path)?.pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_node_spec() {
+        let node = NodeEndpoint::parse("localhost:40413").unwrap();
+        assert_eq!(node.name, "localhost:40413");
+        assert_eq!(node.host, "localhost");
+        assert_eq!(node.http_port, 40413);
+
+        let node = NodeEndpoint::parse("validator2=10.0.0.2:40423").unwrap();
+        assert_eq!(node.name, "validator2");
+        assert_eq!(node.host, "10.0.0.2");
+
+        assert!(NodeEndpoint::parse("localhost").is_err());
+        assert!(NodeEndpoint::parse(":40413").is_err());
+        assert!(NodeEndpoint::parse("localhost:http").is_err());
+    }
+
+    #[test]
+    fn test_topology_from_json() {
+        let json = r#"{"nodes":[
+            {"name":"validator1","host":"localhost","grpc_port":40412,"http_port":40413},
+            {"host":"localhost","http_port":40453}
+        ]}"#;
+        let topology = Topology::from_json(json).unwrap();
+        assert_eq!(topology.nodes.len(), 2);
+        assert_eq!(topology.nodes[0].grpc_port, Some(40412));
+        assert_eq!(topology.nodes[1].name, "localhost:40453");
+        assert!(topology.pos.is_empty());
+
+        let json = r#"{"pos":{"root":{"fingerprints":["ab01"]}}}"#;
+        let topology = Topology::from_json(json).unwrap();
+        assert!(topology.nodes.is_empty());
+        assert_eq!(topology.pos["root"].fingerprints, ["ab01"]);
+        assert_eq!(topology.environment, None);
+
+        let json = r#"{"environment":"dev","shard_id":"root"}"#;
+        let topology = Topology::from_json(json).unwrap();
+        assert_eq!(topology.environment.as_deref(), Some("dev"));
+        assert_eq!(topology.shard_id.as_deref(), Some("root"));
+    }
+
+    #[test]
+    fn test_resolve_appends_flag_nodes() {
+        let topology =
+            Topology::resolve(None, &["a=h1:1".to_string(), "h2:2".to_string()]).unwrap();
+        let names: Vec<&str> = topology.nodes.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(names, vec!["a", "h2:2"]);
+    }
+}
@@ -0,0 +1,368 @@
+//! Guardrails against mistyped transfer amounts
+//!
+//! Before `transfer` asks for confirmation the amount is checked three ways:
+//! against a soft cap (`max_transfer_rev` in `config.toml`, unset by default),
+//! against a share of the sender's balance, and against the user's recent
+//! transfers in the local history file. Above the cap the prompt asks for the
+//! exact amount, and `--yes` alone is refused: only `--allow-large-transfer`
+//! lets such a transfer through without a prompt. The balance and history
+//! checks only add notes to the prompt, so `--yes` skips them.
+//!
+//! Separately, a transfer the sender's balance cannot pay for, amount plus the
+//! most the deploy can spend on phlo, is refused before anything is deployed
+//! unless `--force` is given.
+
+use crate::grpc::{BIGGER_PHLO_LIMIT, DEFAULT_PHLO_LIMIT, DEFAULT_PHLO_PRICE};
+use crate::utils::{config_value, confirmation_phrase, print_warning};
+use crate::vault::{format_rev, RevAmount};
+
+/// `config.toml` key holding the soft cap in tokens
+pub const MAX_TRANSFER_KEY: &str = "max_transfer_rev";
+
+/// `config.toml` key holding the share of the balance that triggers a warning
+pub const BALANCE_PERCENT_KEY: &str = "balance_warn_percent";
+
+/// Share of the sender's balance above which a transfer is flagged
+pub const DEFAULT_BALANCE_PERCENT: u8 = 50;
+
+/// Recent transfers an amount is compared with
+pub const RECENT_TRANSFERS: usize = 10;
+
+/// How many times the largest recent transfer an amount must be to be flagged
+pub const JUMP_FACTOR: u64 = 10;
+
+/// Limits a transfer amount is checked against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Guardrails {
+    /// Soft cap in tokens; above it the exact amount must be typed
+    pub max_transfer: Option<u64>,
+    /// Warn above this share of the sender's balance; 0 turns the check off
+    pub balance_percent: u8,
+}
+
+impl Default for Guardrails {
+    fn default() -> Self {
+        Self {
+            max_transfer: None,
+            balance_percent: DEFAULT_BALANCE_PERCENT,
+        }
+    }
+}
+
+impl Guardrails {
+    /// Flags, else `config.toml`, else the defaults
+    ///
+    /// A config value that is not a number is ignored with a warning.
+    pub fn resolve(max_transfer: Option<u64>, balance_percent: Option<u8>) -> Self {
+        let from_config = |key: &str| {
+            let value = config_value(key)?;
+            match value.parse() {
+                Ok(parsed) => Some(parsed),
+                Err(_) => {
+                    print_warning(&format!(
+                        "Ignoring {} = \"{}\" in config.toml: not a number",
+                        key, value
+                    ));
+                    None
+                }
+            }
+        };
+        Self {
+            max_transfer: max_transfer.or_else(|| from_config(MAX_TRANSFER_KEY)),
+            balance_percent: balance_percent
+                .or_else(|| from_config(BALANCE_PERCENT_KEY))
+                .unwrap_or(DEFAULT_BALANCE_PERCENT),
+        }
+    }
+}
+
+/// Notes for the prompt about sending `amount`
+///
+/// `balance` is the sender's balance in dust, if known; `recent` holds the
+/// amounts in dust of the user's recent transfers.
+pub fn amount_notes(
+    amount: RevAmount,
+    balance: Option<u64>,
+    recent: &[u64],
+    guardrails: &Guardrails,
+) -> Vec<String> {
+    let mut notes = Vec::new();
+    let dust = amount.dust();
+    if let (Some(balance), true) = (balance, guardrails.balance_percent > 0) {
+        let dust = dust as u128;
+        if dust * 100 > balance as u128 * guardrails.balance_percent as u128 {
+            let share = if balance == 0 {
+                "more than all".to_string()
+            } else {
+                format!("{}%", dust * 100 / balance as u128)
+            };
+            notes.push(format!(
+                "This is {} of the sender's balance of {} tokens",
+                share,
+                format_rev(balance.into())
+            ));
+        }
+    }
+    if let Some(&largest) = recent.iter().max().filter(|&&largest| largest > 0) {
+        if dust >= largest.saturating_mul(JUMP_FACTOR) {
+            notes.push(format!(
+                "This is {}× larger than your last {} transfers (largest {} tokens)",
+                dust / largest,
+                recent.len(),
+                format_rev(largest.into())
+            ));
+        }
+    }
+    notes
+}
+
+/// Most a transfer deploy can spend on phlo, in dust
+pub fn max_phlo_fee(bigger_phlo: bool) -> u64 {
+    let limit = if bigger_phlo {
+        BIGGER_PHLO_LIMIT
+    } else {
+        DEFAULT_PHLO_LIMIT
+    };
+    (limit * DEFAULT_PHLO_PRICE) as u64
+}
+
+/// Errors unless `balance` dust covers `amount_dust` plus `fee` dust
+pub fn check_sender_balance(balance: i64, amount_dust: u64, fee: u64) -> Result<(), String> {
+    let required = amount_dust as u128 + fee as u128;
+    let have = balance.max(0) as u128;
+    if have < required {
+        return Err(format!(
+            "insufficient balance: have {} tokens, need {} tokens ({} tokens + up to {} tokens of phlo). \
+             Pass --force to send it anyway.",
+            format_rev(have),
+            format_rev(required),
+            format_rev(amount_dust.into()),
+            format_rev(fee.into())
+        ));
+    }
+    Ok(())
+}
+
+/// What to do before sending a transfer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    /// Send without asking
+    Proceed,
+    /// Ask the user to type `phrase`, showing `notes` with the summary
+    Confirm { phrase: String, notes: Vec<String> },
+    /// Do not send; the message says which flag is needed
+    Refuse(String),
+}
+
+/// Decide how sending `amount` is confirmed
+///
+/// `confirm_above` is the `--confirm-above` threshold for typing the amount,
+/// `assume_yes` the global `--yes` and `allow_large` `--allow-large-transfer`.
+/// A transfer above the cap is never sent on `--yes` alone.
+pub fn decide(
+    amount: RevAmount,
+    confirm_above: u64,
+    guardrails: &Guardrails,
+    notes: Vec<String>,
+    assume_yes: bool,
+    allow_large: bool,
+) -> Decision {
+    if let (Some(cap), false) = (guardrails.max_transfer, allow_large) {
+        if amount > RevAmount::from_tokens(cap) {
+            if assume_yes {
+                return Decision::Refuse(format!(
+                    "Refusing to transfer {} tokens: above the {} token cap ({}). \
+                     Pass --allow-large-transfer to send it.",
+                    amount, cap, MAX_TRANSFER_KEY
+                ));
+            }
+            let mut notes = notes;
+            notes.insert(0, format!("Above your {} token transfer cap", cap));
+            return Decision::Confirm {
+                phrase: amount.to_string(),
+                notes,
+            };
+        }
+    }
+    if assume_yes {
+        return Decision::Proceed;
+    }
+    Decision::Confirm {
+        phrase: confirmation_phrase(amount, RevAmount::from_tokens(confirm_above)),
+        notes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vault::DUST_FACTOR;
+
+    #[test]
+    fn test_check_sender_balance() {
+        let fee = max_phlo_fee(false);
+        assert_eq!(fee, 50_000);
+        assert!(check_sender_balance(300_050_000, 300_000_000, fee).is_ok());
+        assert_eq!(
+            check_sender_balance(300_049_999, 300_000_000, fee).unwrap_err(),
+            "insufficient balance: have 3.00049999 tokens, need 3.0005 tokens \
+             (3 tokens + up to 0.0005 tokens of phlo). Pass --force to send it anyway."
+        );
+        assert!(check_sender_balance(-5, 0, 0).is_ok());
+        assert!(check_sender_balance(-5, 0, 1).is_err());
+
+        // Amount plus fee past u64::MAX neither wraps nor panics
+        let big_fee = max_phlo_fee(true);
+        assert!(check_sender_balance(i64::MAX, u64::MAX, big_fee).is_err());
+        let message = check_sender_balance(0, u64::MAX, big_fee).unwrap_err();
+        assert!(
+            message.contains("need 184467440787.09551615 tokens"),
+            "{}",
+            message
+        );
+        assert!(check_sender_balance(i64::MAX, i64::MAX as u64 - big_fee, big_fee).is_ok());
+    }
+
+    fn tokens(amount: &str) -> RevAmount {
+        amount.parse().unwrap()
+    }
+
+    fn capped(cap: u64) -> Guardrails {
+        Guardrails {
+            max_transfer: Some(cap),
+            ..Guardrails::default()
+        }
+    }
+
+    #[test]
+    fn test_decide_without_cap() {
+        let none = Guardrails::default();
+        assert_eq!(
+            decide(tokens("5"), 1000, &none, vec![], false, false),
+            Decision::Confirm {
+                phrase: "yes".to_string(),
+                notes: vec![],
+            }
+        );
+        assert_eq!(
+            decide(
+                tokens("5000"),
+                1000,
+                &none,
+                vec!["n".to_string()],
+                false,
+                false
+            ),
+            Decision::Confirm {
+                phrase: "5000".to_string(),
+                notes: vec!["n".to_string()],
+            }
+        );
+        assert_eq!(
+            decide(tokens("5000000"), 1000, &none, vec![], true, false),
+            Decision::Proceed
+        );
+        // A fraction above --confirm-above is typed as given, without trailing zeros
+        assert_eq!(
+            decide(tokens("1000.50"), 1000, &none, vec![], false, false),
+            Decision::Confirm {
+                phrase: "1000.5".to_string(),
+                notes: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn test_decide_at_and_above_cap() {
+        let cap = capped(100);
+        // At the cap is not above it
+        assert_eq!(
+            decide(tokens("100"), 1000, &cap, vec![], true, false),
+            Decision::Proceed
+        );
+        assert_eq!(
+            decide(tokens("100"), 1000, &cap, vec![], false, false),
+            Decision::Confirm {
+                phrase: "yes".to_string(),
+                notes: vec![],
+            }
+        );
+
+        // Above it the amount must be typed, even below --confirm-above
+        assert_eq!(
+            decide(
+                tokens("101"),
+                1000,
+                &cap,
+                vec!["n".to_string()],
+                false,
+                false
+            ),
+            Decision::Confirm {
+                phrase: "101".to_string(),
+                notes: vec![
+                    "Above your 100 token transfer cap".to_string(),
+                    "n".to_string()
+                ],
+            }
+        );
+        let refused = decide(tokens("101"), 1000, &cap, vec![], true, false);
+        assert!(
+            matches!(&refused, Decision::Refuse(msg) if msg.contains("--allow-large-transfer")),
+            "{:?}",
+            refused
+        );
+
+        // --allow-large-transfer lifts the cap but not --confirm-above
+        assert_eq!(
+            decide(tokens("101"), 1000, &cap, vec![], true, true),
+            Decision::Proceed
+        );
+        assert_eq!(
+            decide(tokens("101"), 1000, &cap, vec![], false, true),
+            Decision::Confirm {
+                phrase: "yes".to_string(),
+                notes: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn test_balance_note() {
+        let guardrails = Guardrails::default();
+        let balance = Some(100 * DUST_FACTOR);
+        assert!(amount_notes(tokens("50"), balance, &[], &guardrails).is_empty());
+        assert_eq!(
+            amount_notes(tokens("51"), balance, &[], &guardrails),
+            ["This is 51% of the sender's balance of 100 tokens"]
+        );
+        assert_eq!(
+            amount_notes(tokens("1"), Some(0), &[], &guardrails),
+            ["This is more than all of the sender's balance of 0 tokens"]
+        );
+        // Unknown balance, or the check turned off
+        assert!(amount_notes(tokens("1000"), None, &[], &guardrails).is_empty());
+        let off = Guardrails {
+            balance_percent: 0,
+            ..guardrails
+        };
+        assert!(amount_notes(tokens("1000"), balance, &[], &off).is_empty());
+    }
+
+    #[test]
+    fn test_magnitude_jump_note() {
+        let guardrails = Guardrails::default();
+        let recent = [10, 8, 10, 5].map(|amount| amount * DUST_FACTOR);
+        assert!(amount_notes(tokens("99"), None, &recent, &guardrails).is_empty());
+        assert_eq!(
+            amount_notes(tokens("1000"), None, &recent, &guardrails),
+            ["This is 100× larger than your last 4 transfers (largest 10 tokens)"]
+        );
+        assert!(amount_notes(tokens("1000"), None, &[], &guardrails).is_empty());
+        assert!(amount_notes(tokens("1000"), None, &[0], &guardrails).is_empty());
+        assert_eq!(
+            amount_notes(tokens("5"), None, &[DUST_FACTOR / 2], &guardrails),
+            ["This is 10× larger than your last 1 transfers (largest 0.5 tokens)"]
+        );
+    }
+}
@@ -0,0 +1,219 @@
+//! Shortening long values in human output
+//!
+//! Tables and pretty output shorten hashes and keys so lines do not wrap.
+//! Each kind of value has its own default width; the global `--truncate <n>`
+//! sets one width for all of them and `--full` turns shortening off. A
+//! shortened value keeps its leading characters, which still work as an ID
+//! prefix, and ends in [`ELLIPSIS`] so it is plain that it is not the whole
+//! value and cannot be copied as is. JSON output never goes through here.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Marks a shortened value
+pub const ELLIPSIS: char = '…';
+
+/// What kind of value is being shown, for its default width
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    /// Block, state and deploy hashes in listings
+    Hash,
+    /// Validator and deployer public keys
+    Key,
+    /// Hashes and keys in narrow table columns, such as the DAG view's
+    Column,
+}
+
+impl Field {
+    /// Characters kept when neither `--truncate` nor `--full` is given
+    pub fn default_width(self) -> usize {
+        match self {
+            Field::Hash | Field::Key => 16,
+            Field::Column => 8,
+        }
+    }
+}
+
+/// How long values are shortened, from `--truncate` and `--full`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Truncation {
+    /// Each field's default width
+    #[default]
+    PerField,
+    /// The same number of characters for every field
+    Width(usize),
+    /// Nothing is shortened
+    Full,
+}
+
+impl Truncation {
+    pub fn from_flags(truncate: Option<usize>, full: bool) -> Self {
+        match (full, truncate) {
+            (true, _) => Truncation::Full,
+            (false, Some(width)) => Truncation::Width(width),
+            (false, None) => Truncation::PerField,
+        }
+    }
+
+    /// Characters kept of a `field` value, `None` when nothing is cut
+    pub fn width(self, field: Field) -> Option<usize> {
+        match self {
+            Truncation::PerField => Some(field.default_width()),
+            Truncation::Width(width) => Some(width),
+            Truncation::Full => None,
+        }
+    }
+
+    /// `value` as it is shown: whole, or cut at a character boundary with
+    /// [`ELLIPSIS`] appended
+    pub fn apply(self, value: &str, field: Field) -> String {
+        let Some(width) = self.width(field) else {
+            return value.to_string();
+        };
+        match value.char_indices().nth(width) {
+            Some((end, _)) => format!("{}{}", &value[..end], ELLIPSIS),
+            None => value.to_string(),
+        }
+    }
+
+    /// Columns a `field` value of `len` characters takes once shown, for
+    /// sizing table columns
+    pub fn display_width(self, len: usize, field: Field) -> usize {
+        match self.width(field) {
+            Some(width) if len > width => width + 1,
+            _ => len,
+        }
+    }
+}
+
+/// The process-wide policy, as [`encode`]d
+static POLICY: AtomicUsize = AtomicUsize::new(0);
+
+/// 0 for [`Truncation::PerField`], `usize::MAX` for [`Truncation::Full`],
+/// otherwise the width plus one
+fn encode(policy: Truncation) -> usize {
+    match policy {
+        Truncation::PerField => 0,
+        Truncation::Width(width) => width.saturating_add(1).min(usize::MAX - 1),
+        Truncation::Full => usize::MAX,
+    }
+}
+
+fn decode(encoded: usize) -> Truncation {
+    match encoded {
+        0 => Truncation::PerField,
+        usize::MAX => Truncation::Full,
+        encoded => Truncation::Width(encoded - 1),
+    }
+}
+
+/// Set the policy for the rest of the process
+pub fn configure(policy: Truncation) {
+    POLICY.store(encode(policy), Ordering::Relaxed);
+}
+
+/// The policy set by [`configure`]
+pub fn current() -> Truncation {
+    #[cfg(test)]
+    if let Some(policy) = scoped::get() {
+        return policy;
+    }
+    decode(POLICY.load(Ordering::Relaxed))
+}
+
+/// `value` shortened under the current policy
+pub fn shorten(value: &str, field: Field) -> String {
+    current().apply(value, field)
+}
+
+/// A policy for the current thread, for tests that check output under
+/// `--full` or `--truncate` without touching the process-wide setting
+#[cfg(test)]
+pub mod scoped {
+    use super::Truncation;
+    use std::cell::Cell;
+
+    thread_local! {
+        static POLICY: Cell<Option<Truncation>> = const { Cell::new(None) };
+    }
+
+    /// Applies until dropped
+    pub struct Scoped(());
+
+    impl Scoped {
+        pub fn set(policy: Truncation) -> Self {
+            POLICY.with(|p| p.set(Some(policy)));
+            Scoped(())
+        }
+    }
+
+    impl Drop for Scoped {
+        fn drop(&mut self) {
+            POLICY.with(|p| p.set(None));
+        }
+    }
+
+    pub(super) fn get() -> Option<Truncation> {
+        POLICY.with(|p| p.get())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASH: &str = "79574d57d0a5b6ee2cb9a0f33ef3b9d5e09c2f8b3a9d5e1f2a3b4c5d6e7f8091";
+
+    #[test]
+    fn test_defaults_per_field() {
+        let policy = Truncation::default();
+        assert_eq!(policy.apply(HASH, Field::Hash), "79574d57d0a5b6ee…");
+        assert_eq!(policy.apply(HASH, Field::Column), "79574d57…");
+        assert_eq!(policy.apply("abcd", Field::Hash), "abcd");
+        // A value exactly as long as the width is not marked
+        assert_eq!(policy.apply(&HASH[..16], Field::Hash), &HASH[..16]);
+    }
+
+    #[test]
+    fn test_flags() {
+        assert_eq!(Truncation::from_flags(None, false), Truncation::PerField);
+        assert_eq!(Truncation::from_flags(Some(6), false), Truncation::Width(6));
+        assert_eq!(Truncation::from_flags(Some(6), true), Truncation::Full);
+
+        let width = Truncation::Width(6);
+        assert_eq!(width.apply(HASH, Field::Hash), "79574d…");
+        assert_eq!(width.apply(HASH, Field::Column), "79574d…");
+        for field in [Field::Hash, Field::Key, Field::Column] {
+            assert_eq!(Truncation::Full.apply(HASH, field), HASH);
+            assert_eq!(Truncation::Full.display_width(HASH.len(), field), 64);
+        }
+        assert_eq!(width.display_width(64, Field::Hash), 7);
+        assert_eq!(width.display_width(4, Field::Hash), 4);
+    }
+
+    #[test]
+    fn test_never_splits_a_character() {
+        let name = "validätor-ñame-✓✓✓";
+        for width in 0..name.chars().count() + 2 {
+            let shown = Truncation::Width(width).apply(name, Field::Key);
+            let kept = shown.trim_end_matches(ELLIPSIS);
+            assert!(name.starts_with(kept), "{}", shown);
+            assert_eq!(kept.chars().count(), width.min(name.chars().count()));
+        }
+        assert_eq!(Truncation::Width(6).apply(name, Field::Key), "validä…");
+    }
+
+    #[test]
+    fn test_process_policy_round_trips() {
+        for policy in [
+            Truncation::PerField,
+            Truncation::Width(0),
+            Truncation::Width(12),
+            Truncation::Full,
+        ] {
+            assert_eq!(decode(encode(policy)), policy);
+        }
+
+        let _scope = scoped::Scoped::set(Truncation::Full);
+        assert_eq!(shorten(HASH, Field::Column), HASH);
+    }
+}
@@ -0,0 +1,208 @@
+//! Polling delays that start short and grow
+//!
+//! A deploy usually takes tens of seconds to reach a block, so polling at a
+//! fixed short interval mostly asks the node the same question again. A
+//! [`Backoff`] starts at `initial` and multiplies each delay by `multiplier`
+//! up to `max`. Waits keep their wall-clock timeouts: [`Backoff::polls_within`]
+//! turns a timeout into the number of polls that fit in it.
+
+use super::duration::{polls_within, MIN_POLL_INTERVAL};
+use rand::Rng;
+use std::time::Duration;
+
+/// Growth of each delay over the one before
+pub const DEFAULT_BACKOFF_MULTIPLIER: f64 = 2.0;
+/// Default longest delay, as a multiple of the first
+pub const DEFAULT_BACKOFF_CEILING: u32 = 4;
+/// Largest share by which jitter moves a delay either way
+pub const BACKOFF_JITTER: f64 = 0.1;
+
+/// Delays between the polls of a wait
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Backoff {
+    /// Delay after the first poll
+    pub initial: Duration,
+    /// Growth of each delay over the one before; below 1 counts as 1
+    pub multiplier: f64,
+    /// Longest delay
+    pub max: Duration,
+    /// Move each delay by up to [`BACKOFF_JITTER`] either way, so clients
+    /// started together do not poll in step
+    pub jitter: bool,
+}
+
+impl Backoff {
+    /// Delays doubling from `initial` up to `max`, with jitter
+    ///
+    /// `initial` is at least [`MIN_POLL_INTERVAL`] and `max` at least `initial`.
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        let initial = initial.max(MIN_POLL_INTERVAL);
+        Self {
+            initial,
+            multiplier: DEFAULT_BACKOFF_MULTIPLIER,
+            max: max.max(initial),
+            jitter: true,
+        }
+    }
+
+    /// `initial` growing to `max`, or to [`DEFAULT_BACKOFF_CEILING`] times
+    /// `initial` when no `max` is given
+    pub fn up_to(initial: Duration, max: Option<Duration>) -> Self {
+        Self::new(
+            initial,
+            max.unwrap_or(initial.max(MIN_POLL_INTERVAL) * DEFAULT_BACKOFF_CEILING),
+        )
+    }
+
+    /// The same delay every time, without jitter
+    pub fn fixed(interval: Duration) -> Self {
+        Self {
+            initial: interval,
+            multiplier: 1.0,
+            max: interval,
+            jitter: false,
+        }
+    }
+
+    /// Delay before retry `retry`, counting from 0 for the one after the first
+    /// poll, before jitter
+    pub fn delay(&self, retry: u32) -> Duration {
+        if self.initial >= self.max {
+            return self.max;
+        }
+        let exponent = retry.min(i32::MAX as u32) as i32;
+        let secs = self.initial.as_secs_f64() * self.multiplier.max(1.0).powi(exponent);
+        if !secs.is_finite() || secs >= self.max.as_secs_f64() {
+            self.max
+        } else {
+            Duration::from_secs_f64(secs)
+        }
+    }
+
+    /// [`delay`](Self::delay) with jitter, when enabled
+    pub fn jittered_delay(&self, retry: u32) -> Duration {
+        let delay = self.delay(retry);
+        if !self.jitter {
+            return delay;
+        }
+        let spread = rand::thread_rng().gen_range(-BACKOFF_JITTER..=BACKOFF_JITTER);
+        delay.mul_f64(1.0 + spread)
+    }
+
+    /// Sleep before retry `retry`
+    pub async fn wait(&self, retry: u32) {
+        tokio::time::sleep(self.jittered_delay(retry)).await;
+    }
+
+    /// Number of delays that fit in `timeout`, at least one
+    ///
+    /// This is the number of polls a wait of `timeout` makes. With a fixed
+    /// delay it is [`polls_within`], so a timeout given as attempts times
+    /// interval keeps its attempts.
+    pub fn polls_within(&self, timeout: Duration) -> u32 {
+        if self.max.is_zero() {
+            return polls_within(timeout, self.max);
+        }
+        let mut polls: u32 = 0;
+        let mut elapsed = Duration::ZERO;
+        loop {
+            let delay = self.delay(polls);
+            if delay >= self.max {
+                // Every delay from here on is `max`
+                let rest =
+                    timeout.saturating_sub(elapsed).as_millis() / self.max.as_millis().max(1);
+                return (polls as u128 + rest).clamp(1, u32::MAX as u128) as u32;
+            }
+            if elapsed + delay > timeout {
+                return polls.max(1);
+            }
+            elapsed += delay;
+            polls += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(s: u64) -> Duration {
+        Duration::from_secs(s)
+    }
+
+    fn steady(initial: u64, max: u64) -> Backoff {
+        Backoff {
+            jitter: false,
+            ..Backoff::new(secs(initial), secs(max))
+        }
+    }
+
+    #[test]
+    fn test_delays_grow_to_max() {
+        let backoff = steady(1, 8);
+        let delays: Vec<Duration> = (0..6).map(|retry| backoff.delay(retry)).collect();
+        assert_eq!(delays, [1, 2, 4, 8, 8, 8].map(secs));
+        assert_eq!(steady(3, 10).delay(2), secs(10));
+        assert_eq!(backoff.delay(u32::MAX), secs(8));
+
+        let slow = Backoff {
+            multiplier: 1.5,
+            ..steady(2, 60)
+        };
+        assert_eq!(slow.delay(2), Duration::from_millis(4_500));
+    }
+
+    #[test]
+    fn test_new_keeps_delays_in_order() {
+        let backoff = Backoff::new(secs(10), secs(2));
+        assert_eq!(backoff.max, secs(10));
+        assert_eq!(
+            Backoff::new(Duration::ZERO, Duration::ZERO).initial,
+            MIN_POLL_INTERVAL
+        );
+        assert_eq!(Backoff::up_to(secs(2), None).max, secs(8));
+        assert_eq!(Backoff::up_to(secs(2), Some(secs(30))).max, secs(30));
+    }
+
+    #[test]
+    fn test_polls_within_timeout() {
+        // 1 + 2 + 4 = 7s, then one more 8s delay fits in 15s
+        assert_eq!(steady(1, 8).polls_within(secs(15)), 4);
+        assert_eq!(steady(1, 8).polls_within(secs(14)), 3);
+        assert_eq!(steady(1, 8).polls_within(secs(60)), 9);
+        assert_eq!(steady(1, 8).polls_within(Duration::ZERO), 1);
+        assert_eq!(
+            steady(1, 8).polls_within(secs(u32::MAX as u64 * 60)),
+            u32::MAX
+        );
+    }
+
+    #[test]
+    fn test_fixed_matches_attempt_counts() {
+        // --max-attempts 12 --retry-delay 5s keeps its 12 attempts
+        assert_eq!(Backoff::fixed(secs(5)).polls_within(secs(60)), 12);
+        for (timeout, interval) in [(60, 2), (5, 2), (1, 5)] {
+            assert_eq!(
+                Backoff::fixed(secs(interval)).polls_within(secs(timeout)),
+                polls_within(secs(timeout), secs(interval))
+            );
+        }
+        assert_eq!(Backoff::fixed(Duration::ZERO).polls_within(secs(3)), 3_000);
+        assert_eq!(Backoff::fixed(secs(5)).jittered_delay(7), secs(5));
+    }
+
+    #[test]
+    fn test_jitter_stays_within_bounds() {
+        let backoff = Backoff::new(secs(1), secs(8));
+        for retry in 0..50 {
+            let delay = backoff.jittered_delay(retry % 5).as_secs_f64();
+            let nominal = backoff.delay(retry % 5).as_secs_f64();
+            assert!(
+                (delay - nominal).abs() <= nominal * BACKOFF_JITTER + 1e-9,
+                "{} vs {}",
+                delay,
+                nominal
+            );
+        }
+    }
+}
@@ -1,15 +1,36 @@
 use crate::error::{NodeCliError, Result};
+use blake2::{Blake2b, Digest};
 use f1r3fly_crypto::rust::public_key::PublicKey;
 use f1r3fly_rholang::rust::interpreter::util::vault_address::VaultAddress;
 use hex;
 use secp256k1::rand;
 use secp256k1::{PublicKey as Secp256k1PublicKey, Secp256k1, SecretKey};
-use std::fs;
 use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::LazyLock;
+use typenum::U32;
+
+static SECP_CONTEXT_INITS: AtomicUsize = AtomicUsize::new(0);
+
+/// Process-wide secp256k1 context; building one is costly, so it is built once on first use
+static SECP: LazyLock<Secp256k1<secp256k1::All>> = LazyLock::new(|| {
+    SECP_CONTEXT_INITS.fetch_add(1, Ordering::Relaxed);
+    Secp256k1::new()
+});
 
 pub struct CryptoUtils;
 
 impl CryptoUtils {
+    /// Shared secp256k1 context for signing and key derivation
+    pub fn secp() -> &'static Secp256k1<secp256k1::All> {
+        &SECP
+    }
+
+    /// How many times the shared context has been built (at most 1)
+    pub fn secp_context_inits() -> usize {
+        SECP_CONTEXT_INITS.load(Ordering::Relaxed)
+    }
+
     /// Decode a hex-encoded private key
     pub fn decode_private_key(private_key_hex: &str) -> Result<SecretKey> {
         let private_key_bytes = hex::decode(private_key_hex)?;
@@ -23,16 +44,14 @@ impl CryptoUtils {
 
     /// Generate a new random key pair
     pub fn generate_key_pair() -> Result<(SecretKey, Secp256k1PublicKey)> {
-        let secp = Secp256k1::new();
         let secret_key = SecretKey::new(&mut rand::rng());
-        let public_key = secret_key.public_key(&secp);
+        let public_key = secret_key.public_key(Self::secp());
         Ok((secret_key, public_key))
     }
 
     /// Derive public key from private key
     pub fn derive_public_key(private_key: &SecretKey) -> Secp256k1PublicKey {
-        let secp = Secp256k1::new();
-        private_key.public_key(&secp)
+        private_key.public_key(Self::secp())
     }
 
     /// Serialize public key to hex (compressed or uncompressed)
@@ -74,11 +93,11 @@ impl CryptoUtils {
         let private_key_hex = Self::serialize_private_key(private_key);
         let public_key_hex = Self::serialize_public_key(public_key, compressed);
 
-        fs::write(private_key_file, private_key_hex).map_err(|e| {
+        crate::store::write_atomic(private_key_file, private_key_hex.as_bytes()).map_err(|e| {
             NodeCliError::file_write_failed(&private_key_file.display().to_string(), &e.to_string())
         })?;
 
-        fs::write(public_key_file, public_key_hex).map_err(|e| {
+        crate::store::write_atomic(public_key_file, public_key_hex.as_bytes()).map_err(|e| {
             NodeCliError::file_write_failed(&public_key_file.display().to_string(), &e.to_string())
         })?;
 
@@ -118,3 +137,112 @@ impl CryptoUtils {
         }
     }
 }
+
+/// Characters per group in [`group_address`]
+pub const ADDRESS_GROUP: usize = 4;
+
+/// An address in groups of four for reading aloud or copying by hand
+///
+/// The last group also takes any leftover characters and is bracketed, so the
+/// end of the address, where the checksum lives, stands out.
+pub fn group_address(address: &str) -> String {
+    let chars: Vec<char> = address.trim().chars().collect();
+    if chars.len() <= ADDRESS_GROUP {
+        return chars.into_iter().collect();
+    }
+    let mut groups: Vec<String> = chars
+        .chunks(ADDRESS_GROUP)
+        .map(|group| group.iter().collect())
+        .collect();
+    if groups.len() > 1 && groups[groups.len() - 1].len() < ADDRESS_GROUP {
+        let leftover = groups.pop().unwrap_or_default();
+        if let Some(last) = groups.last_mut() {
+            last.push_str(&leftover);
+        }
+    }
+    let last = groups.pop().unwrap_or_default();
+    groups.push(format!("[{}]", last));
+    groups.join(" ")
+}
+
+/// The Bitcoin base58 alphabet vault addresses are written in; it leaves out
+/// `0`, `O`, `I` and `l`
+pub const BASE58_ALPHABET: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Whether every character of `text` is in [`BASE58_ALPHABET`]
+pub fn is_base58(text: &str) -> bool {
+    text.chars().all(|c| BASE58_ALPHABET.contains(c))
+}
+
+/// Whether a vault address's trailing four checksum bytes match its payload
+///
+/// A vault address is the base58 encoding of a 36-byte payload (prefix and
+/// key hash) followed by the first four bytes of its Blake2b-256 hash.
+pub fn address_checksum_ok(address: &str) -> bool {
+    let Ok(bytes) = bs58::decode(address.trim()).into_vec() else {
+        return false;
+    };
+    if bytes.len() != 40 {
+        return false;
+    }
+    let (payload, checksum) = bytes.split_at(36);
+    let hash = Blake2b::<U32>::new().chain_update(payload).finalize();
+    hash[..4] == *checksum
+}
+
+/// An address for human output: grouped, and flagged if its checksum is wrong
+pub fn display_address(address: &str) -> String {
+    if address_checksum_ok(address) {
+        group_address(address)
+    } else {
+        format!("{} (checksum mismatch)", group_address(address))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The bootstrap dev key's vault address, as in docs/commands/keys.md
+    const BOOTSTRAP_ADDRESS: &str = "1111AtahZeefej4tvVR6ti9TJtv8yxLebT31SCEVDCKMNikBk5r3g";
+    const BOOTSTRAP_PUBLIC_KEY: &str = "04ffc016579a68050d655d55df4e09f04605164543e257c8e6df10361e6068a5336588e9b355ea859c5ab4285a5ef0efdf62bc28b80320ce99e26bb1607b3ad93d";
+
+    #[test]
+    fn test_group_known_addresses() {
+        assert_eq!(
+            group_address(BOOTSTRAP_ADDRESS),
+            "1111 Atah Zeef ej4t vVR6 ti9T Jtv8 yxLe bT31 SCEV DCKM NikB [k5r3g]"
+        );
+        assert_eq!(
+            group_address("111127RX5ZgiAdRaQy4AWy57RdvAAckdELReEBxzvWYVvdnR32PiHA"),
+            "1111 27RX 5Zgi AdRa Qy4A Wy57 RdvA Ackd ELRe EBxz vWYV vdnR [32PiHA]"
+        );
+        assert_eq!(group_address("12345678"), "1234 [5678]");
+        assert_eq!(group_address("abc"), "abc");
+        // Grouping never loses characters
+        assert_eq!(
+            group_address(BOOTSTRAP_ADDRESS).replace(['[', ']', ' '], ""),
+            BOOTSTRAP_ADDRESS
+        );
+    }
+
+    #[test]
+    fn test_address_checksums() {
+        assert!(address_checksum_ok(BOOTSTRAP_ADDRESS));
+        assert!(address_checksum_ok(
+            &CryptoUtils::generate_vault_address(BOOTSTRAP_PUBLIC_KEY).unwrap()
+        ));
+        // One character changed
+        assert!(!address_checksum_ok(
+            "1111AtahZeefej4tvVR6ti9TJtv8yxLebT31SCEVDCKMNikBk5r3h"
+        ));
+        assert!(!address_checksum_ok("not base58 0OIl"));
+        assert!(!address_checksum_ok("1111"));
+
+        assert!(!display_address(BOOTSTRAP_ADDRESS).contains("mismatch"));
+        assert!(
+            display_address("1111AtahZeefej4tvVR6ti9TJtv8yxLebT31SCEVDCKMNikBk5r3h")
+                .ends_with("(checksum mismatch)")
+        );
+    }
+}
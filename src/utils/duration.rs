@@ -0,0 +1,184 @@
+use chrono::{TimeZone, Utc};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Units accepted by [`parse_duration`], largest first, in milliseconds
+const UNITS: [(&str, u64); 6] = [
+    ("w", 604_800_000),
+    ("d", 86_400_000),
+    ("h", 3_600_000),
+    ("m", 60_000),
+    ("s", 1_000),
+    ("ms", 1),
+];
+
+/// Parse a duration such as `500ms`, `30s`, `5m`, `24h`, `7d`, `2w` or `1h30m`
+///
+/// Compound values list their units largest first, each at most once. A bare
+/// number is interpreted in `default_unit`, which must be one of the suffixes
+/// above.
+pub fn parse_duration(value: &str, default_unit: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let invalid = || format!("Invalid duration '{}': expected e.g. 30s or 1h30m", value);
+    let too_large = || format!("Duration '{}' is too large", value);
+
+    let mut rest = value;
+    let mut millis: u64 = 0;
+    let mut smallest = None;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let (number, tail) = rest.split_at(digits);
+        let unit_len = tail
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(tail.len());
+        let (unit, tail) = tail.split_at(unit_len);
+        rest = tail;
+
+        let amount: u64 = number.parse().map_err(|_| invalid())?;
+        let unit = match unit.trim() {
+            "" if number.len() == value.len() => default_unit,
+            "" => return Err(invalid()),
+            other => other,
+        };
+        let rank = UNITS
+            .iter()
+            .position(|(suffix, _)| *suffix == unit)
+            .ok_or_else(|| format!("Invalid duration unit '{}': use ms, s, m, h, d or w", unit))?;
+        if smallest.is_some_and(|smallest| rank <= smallest) {
+            return Err(invalid());
+        }
+        smallest = Some(rank);
+
+        millis = amount
+            .checked_mul(UNITS[rank].1)
+            .and_then(|part| millis.checked_add(part))
+            .ok_or_else(too_large)?;
+    }
+    if smallest.is_none() {
+        return Err(invalid());
+    }
+    Ok(Duration::from_millis(millis))
+}
+
+/// Floor for polling intervals, so a zero `--check-interval` cannot spin
+pub const MIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Number of polls `interval` apart that fit in `timeout`, at least one
+pub fn polls_within(timeout: Duration, interval: Duration) -> u32 {
+    let polls = timeout.as_millis() / interval.as_millis().max(1);
+    polls.clamp(1, u32::MAX as u128) as u32
+}
+
+/// Format a duration compactly: `850ms`, `14s`, `1m 32s`, `2h 5m`, `3d 4h`
+pub fn format_duration(duration: Duration) -> String {
+    let millis = duration.as_millis() as u64;
+    let secs = millis / 1_000;
+    match secs {
+        0 => format!("{}ms", millis),
+        1..=59 => format!("{}s", secs),
+        60..=3_599 => format_pair(secs / 60, "m", secs % 60, "s"),
+        3_600..=86_399 => format_pair(secs / 3_600, "h", secs % 3_600 / 60, "m"),
+        _ => format_pair(secs / 86_400, "d", secs % 86_400 / 3_600, "h"),
+    }
+}
+
+fn format_pair(major: u64, major_unit: &str, minor: u64, minor_unit: &str) -> String {
+    if minor == 0 {
+        format!("{}{}", major, major_unit)
+    } else {
+        format!("{}{} {}{}", major, major_unit, minor, minor_unit)
+    }
+}
+
+/// Format a Unix epoch millisecond timestamp as `2024-06-19 14:03:27` (UTC),
+/// or the raw number if it is out of range
+pub fn format_timestamp(timestamp_ms: i64) -> String {
+    Utc.timestamp_millis_opt(timestamp_ms)
+        .single()
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| timestamp_ms.to_string())
+}
+
+/// Current Unix time in milliseconds
+pub fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Failed to get system time")
+        .as_millis() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(
+            parse_duration("500ms", "s").unwrap(),
+            Duration::from_millis(500)
+        );
+        assert_eq!(parse_duration("30s", "h").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("5m", "s").unwrap(), Duration::from_secs(300));
+        assert_eq!(
+            parse_duration("2w", "s").unwrap(),
+            Duration::from_secs(1_209_600)
+        );
+        assert_eq!(parse_duration("45", "s").unwrap(), Duration::from_secs(45));
+        assert_eq!(parse_duration("2", "h").unwrap(), Duration::from_secs(7200));
+        assert!(parse_duration("", "s").is_err());
+        assert!(parse_duration("s", "s").is_err());
+        assert!(parse_duration("5y", "s").is_err());
+        assert!(parse_duration("-5s", "s").is_err());
+    }
+
+    #[test]
+    fn test_parse_compound_duration() {
+        assert_eq!(
+            parse_duration("1h30m", "s").unwrap(),
+            Duration::from_secs(5_400)
+        );
+        assert_eq!(
+            parse_duration("2m 15s", "s").unwrap(),
+            Duration::from_secs(135)
+        );
+        assert_eq!(
+            parse_duration("1s500ms", "s").unwrap(),
+            Duration::from_millis(1_500)
+        );
+        assert_eq!(parse_duration("0s", "s").unwrap(), Duration::ZERO);
+        // Units largest first, each once; a bare number only on its own
+        assert!(parse_duration("30m1h", "s").is_err());
+        assert!(parse_duration("5m5m", "s").is_err());
+        assert!(parse_duration("1h30", "s").is_err());
+        assert!(parse_duration("1h-30m", "s").is_err());
+        assert!(parse_duration("5000 millis", "s").is_err());
+        assert!(parse_duration("99999999999w", "s").is_err());
+    }
+
+    #[test]
+    fn test_polls_within() {
+        let secs = Duration::from_secs;
+        assert_eq!(polls_within(secs(60), secs(2)), 30);
+        assert_eq!(polls_within(secs(5), secs(2)), 2);
+        assert_eq!(polls_within(secs(1), secs(5)), 1);
+        assert_eq!(polls_within(secs(3), Duration::from_millis(500)), 6);
+        assert_eq!(polls_within(secs(3), Duration::ZERO), 3_000);
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_millis(850)), "850ms");
+        assert_eq!(format_duration(Duration::from_secs(14)), "14s");
+        assert_eq!(format_duration(Duration::from_secs(92)), "1m 32s");
+        assert_eq!(format_duration(Duration::from_secs(120)), "2m");
+        assert_eq!(format_duration(Duration::from_secs(7_500)), "2h 5m");
+        assert_eq!(format_duration(Duration::from_secs(273_600)), "3d 4h");
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(1_718_805_807_000), "2024-06-19 14:03:27");
+        assert_eq!(format_timestamp(i64::MAX), i64::MAX.to_string());
+    }
+}
@@ -1,3 +1,4 @@
+use crate::error::{NodeCall, NodeCliError};
 use reqwest;
 use serde_json;
 use std::time::Instant;
@@ -17,24 +18,44 @@ impl HttpClient {
         &self,
         url: &str,
     ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
-        let response = self.client.get(url).send().await?;
+        let call = NodeCall::http(url);
+        let response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| call.fail(e))?;
 
         if response.status().is_success() {
-            let text = response.text().await?;
-            let json: serde_json::Value = serde_json::from_str(&text)?;
+            let text = response.text().await.map_err(|e| call.fail(e))?;
+            let json: serde_json::Value = serde_json::from_str(&text).map_err(|e| call.fail(e))?;
             Ok(json)
         } else {
-            Err(format!("HTTP {}: {}", response.status(), response.text().await?).into())
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            Err(call
+                .fail(NodeCliError::http_status(status.as_u16(), &text))
+                .into())
         }
     }
 
     pub async fn get_text(&self, url: &str) -> Result<String, Box<dyn std::error::Error>> {
-        let response = self.client.get(url).send().await?;
+        let call = NodeCall::http(url);
+        let response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| call.fail(e))?;
 
         if response.status().is_success() {
-            Ok(response.text().await?)
+            Ok(response.text().await.map_err(|e| call.fail(e))?)
         } else {
-            Err(format!("HTTP {}: {}", response.status(), response.text().await?).into())
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            Err(call
+                .fail(NodeCliError::http_status(status.as_u16(), &text))
+                .into())
         }
     }
 
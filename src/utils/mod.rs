@@ -1,7 +1,17 @@
+pub mod backoff;
 pub mod crypto;
+pub mod duration;
 pub mod http;
 pub mod output;
+pub mod paths;
+pub mod qr;
+pub mod rho_helpers;
+pub mod stats;
 
+pub use backoff::*;
 pub use crypto::*;
+pub use duration::*;
 pub use http::*;
 pub use output::*;
+pub use paths::*;
+pub use stats::*;
@@ -1,4 +1,7 @@
+use crate::error::NodeCliError;
 use serde_json;
+use std::io::{BufRead, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 
 // Emoji constants
@@ -15,37 +18,95 @@ pub const EMOJI_KEY: &str = "";
 pub const EMOJI_LINK: &str = "";
 pub const EMOJI_WARNING: &str = "";
 
+static QUIET: AtomicBool = AtomicBool::new(false);
+static STDOUT_RESERVED: AtomicBool = AtomicBool::new(false);
+
+/// Hide progress output for the rest of the process (`--quiet`)
+///
+/// Results, warnings and errors are still printed.
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+pub fn is_quiet() -> bool {
+    #[cfg(test)]
+    if let Some(quiet) = capture::quiet() {
+        return quiet;
+    }
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Keep stdout for a command's JSON result (`transfer --json`)
+///
+/// Everything this module prints, including confirmation prompts, goes to
+/// stderr instead for the rest of the process.
+pub fn reserve_stdout(reserved: bool) {
+    STDOUT_RESERVED.store(reserved, Ordering::Relaxed);
+}
+
+fn stdout_reserved() -> bool {
+    STDOUT_RESERVED.load(Ordering::Relaxed)
+}
+
+/// Write one line to stdout, or to the test capture when one is active
+fn emit(line: &str) {
+    #[cfg(test)]
+    if capture::push(line) {
+        return;
+    }
+    if stdout_reserved() {
+        eprintln!("{}", line);
+    } else {
+        println!("{}", line);
+    }
+}
+
+/// Emit a progress line unless `--quiet` is set
+fn progress(line: &str) {
+    if !is_quiet() {
+        emit(line);
+    }
+}
+
 // Output formatting functions
 pub fn print_info(message: &str) {
-    println!("{} {}", EMOJI_INFO, message);
+    progress(&format!("{} {}", EMOJI_INFO, message));
+}
+
+/// A progress line printed as is, hidden by `--quiet`
+pub fn print_progress(message: &str) {
+    progress(message);
 }
 
 pub fn print_success(message: &str) {
-    println!("{} {}", EMOJI_SUCCESS, message);
+    emit(&format!("{} {}", EMOJI_SUCCESS, message));
 }
 
 pub fn print_error(message: &str) {
-    println!("{} {}", EMOJI_ERROR, message);
+    emit(&format!("{} {}", EMOJI_ERROR, message));
 }
 
 pub fn print_search(message: &str) {
-    println!("{} {}", EMOJI_SEARCH, message);
+    progress(&format!("{} {}", EMOJI_SEARCH, message));
 }
 
 pub fn print_time(message: &str, duration: Duration) {
-    println!("{} {}: {:.2?}", EMOJI_TIME, message, duration);
+    progress(&format!("{} {}: {:.2?}", EMOJI_TIME, message, duration));
 }
 
 pub fn print_file_info(filename: &str, size: usize) {
-    println!("{} Reading Rholang from: {}", EMOJI_FILE, filename);
-    println!("{} Code size: {} bytes", EMOJI_INFO, size);
+    progress(&format!(
+        "{} Reading Rholang from: {}",
+        EMOJI_FILE, filename
+    ));
+    progress(&format!("{} Code size: {} bytes", EMOJI_INFO, size));
 }
 
 pub fn print_connection(host: &str, port: u16) {
-    println!(
+    progress(&format!(
         "{} Connecting to F1r3fly node at {}:{}",
         EMOJI_CONNECT, host, port
-    );
+    ));
 }
 
 pub fn print_block_info(block_hash: &str) {
@@ -53,7 +114,7 @@ pub fn print_block_info(block_hash: &str) {
 }
 
 pub fn print_rocket(message: &str) {
-    println!("{} {}", EMOJI_ROCKET, message);
+    progress(&format!("{} {}", EMOJI_ROCKET, message));
 }
 
 pub fn print_key(key_type: &str, key_value: &str) {
@@ -70,7 +131,135 @@ pub fn print_json_pretty(
 }
 
 pub fn print_warning(message: &str) {
-    println!("{} {}", EMOJI_WARNING, message);
+    emit(&format!("{} {}", EMOJI_WARNING, message));
+}
+
+/// Quiet zone around a full-size QR code, in modules
+pub const QR_QUIET_ZONE: usize = 4;
+/// Quiet zone of the compact fallback; most phone scanners cope with one
+pub const QR_COMPACT_QUIET_ZONE: usize = 1;
+
+/// How an address QR code fits the terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QrFit {
+    /// Medium error correction and the standard quiet zone
+    Full,
+    /// Low error correction, a smaller version, and a thin quiet zone
+    Compact,
+    /// Not even the compact code fits; `needed` is its width in columns
+    TooNarrow { needed: usize },
+}
+
+/// Pick a QR layout for a terminal `width` columns wide
+///
+/// An unknown width, as when output is piped, gets the full code.
+pub fn fit_qr(width: Option<usize>, full_width: usize, compact_width: usize) -> QrFit {
+    match width {
+        None => QrFit::Full,
+        Some(width) if width >= full_width => QrFit::Full,
+        Some(width) if width >= compact_width => QrFit::Compact,
+        Some(_) => QrFit::TooNarrow {
+            needed: compact_width,
+        },
+    }
+}
+
+/// Width of the terminal in columns, if it can be told
+///
+/// Asks the terminal when stdout is one, then falls back to `COLUMNS`.
+pub fn terminal_width() -> Option<usize> {
+    #[cfg(feature = "tui")]
+    if std::io::stdout().is_terminal() {
+        if let Ok((columns, _)) = crossterm::terminal::size() {
+            return Some(columns as usize);
+        }
+    }
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.trim().parse().ok())
+}
+
+/// The lines [`print_qr`] prints for `data` on a terminal `width` columns wide
+pub fn qr_lines(data: &str, width: Option<usize>) -> Result<Vec<String>, String> {
+    use super::qr::{EcLevel, QrCode};
+
+    let full = QrCode::encode(data.as_bytes(), EcLevel::Medium)?;
+    let compact = QrCode::encode(data.as_bytes(), EcLevel::Low)?;
+    match fit_qr(
+        width,
+        full.rendered_width(QR_QUIET_ZONE),
+        compact.rendered_width(QR_COMPACT_QUIET_ZONE),
+    ) {
+        QrFit::Full => Ok(full.render(QR_QUIET_ZONE)),
+        QrFit::Compact => Ok(compact.render(QR_COMPACT_QUIET_ZONE)),
+        QrFit::TooNarrow { needed } => Err(format!(
+            "The terminal is {} columns wide; the QR code needs at least {}. Widen the window and run again.",
+            width.unwrap_or_default(),
+            needed
+        )),
+    }
+}
+
+/// Draw `data` as a QR code sized to the terminal, or warn if it cannot fit
+pub fn print_qr(data: &str) {
+    match qr_lines(data, terminal_width()) {
+        Ok(lines) => {
+            for line in lines {
+                emit(&line);
+            }
+        }
+        Err(message) => print_warning(&message),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ReportLine {
+    Result(String),
+    Progress(String),
+}
+
+/// A command's result, collected while it runs and printed once it is done
+///
+/// Result lines are printed even with `--quiet`; notes and timings are hidden
+/// with the rest of the progress output.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    lines: Vec<ReportLine>,
+}
+
+impl Report {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn line(&mut self, line: impl Into<String>) -> &mut Self {
+        self.lines.push(ReportLine::Result(line.into()));
+        self
+    }
+
+    /// A status line such as "Deployment successful!", hidden by `--quiet`
+    pub fn note(&mut self, line: impl Into<String>) -> &mut Self {
+        self.lines.push(ReportLine::Progress(line.into()));
+        self
+    }
+
+    /// A timing line in [`print_time`]'s format, hidden by `--quiet`
+    pub fn time(&mut self, label: &str, duration: Duration) -> &mut Self {
+        self.note(format!("{} {}: {:.2?}", EMOJI_TIME, label, duration))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    pub fn print(&self) {
+        for line in &self.lines {
+            match line {
+                ReportLine::Result(text) => emit(text),
+                ReportLine::Progress(text) => progress(text),
+            }
+        }
+    }
 }
 
 pub fn print_bond_status(is_bonded: bool) {
@@ -104,3 +293,278 @@ pub fn format_operation_result(success: bool, operation: &str, duration: Duratio
     }
     print_time("Time taken", duration);
 }
+
+/// Text the user must type to confirm a transfer: the amount itself above `threshold`, otherwise "yes"
+pub fn confirmation_phrase<T: PartialOrd + ToString>(amount: T, threshold: T) -> String {
+    if amount > threshold {
+        amount.to_string()
+    } else {
+        "yes".to_string()
+    }
+}
+
+/// Ask for confirmation before an irreversible operation
+///
+/// Prints `summary` and reads one line from `input`, which must equal
+/// `expected`. `assume_yes` (the global `--yes` flag) skips the prompt; without
+/// it a non-interactive stdin is an error, so a broken pipeline never runs the
+/// operation silently.
+pub fn confirm_operation(
+    action: &str,
+    summary: &[(&str, String)],
+    expected: &str,
+    assume_yes: bool,
+    interactive: bool,
+    input: &mut impl BufRead,
+) -> crate::error::Result<()> {
+    if assume_yes {
+        return Ok(());
+    }
+    if !interactive {
+        return Err(NodeCliError::General(format!(
+            "Refusing to {} without confirmation: stdin is not a terminal. Pass --yes to proceed.",
+            action
+        )));
+    }
+
+    let width = summary
+        .iter()
+        .map(|(label, _)| label.len())
+        .max()
+        .unwrap_or(0);
+    let mut out: Box<dyn Write> = if stdout_reserved() {
+        Box::new(std::io::stderr())
+    } else {
+        Box::new(std::io::stdout())
+    };
+    writeln!(out, "About to {}:", action)?;
+    for (label, value) in summary {
+        writeln!(
+            out,
+            "  {:<width$}  {}",
+            format!("{}:", label),
+            value,
+            width = width + 1
+        )?;
+    }
+    write!(out, "Type \"{}\" to confirm: ", expected)?;
+    out.flush()?;
+
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+    if answer.trim() == expected {
+        Ok(())
+    } else {
+        Err(NodeCliError::General(format!(
+            "Cancelled: {} not confirmed",
+            action
+        )))
+    }
+}
+
+/// [`confirm_operation`] reading from the process's stdin
+pub fn confirm_on_stdin(
+    action: &str,
+    summary: &[(&str, String)],
+    expected: &str,
+    assume_yes: bool,
+) -> crate::error::Result<()> {
+    let stdin = std::io::stdin();
+    let interactive = stdin.is_terminal();
+    confirm_operation(
+        action,
+        summary,
+        expected,
+        assume_yes,
+        interactive,
+        &mut stdin.lock(),
+    )
+}
+
+/// Output capture for tests asserting on what a command prints
+#[cfg(test)]
+pub mod capture {
+    use std::cell::RefCell;
+
+    thread_local! {
+        static CAPTURE: RefCell<Option<(bool, Vec<String>)>> = const { RefCell::new(None) };
+    }
+
+    /// Collects this thread's output until [`Capture::finish`] or drop
+    ///
+    /// `quiet` stands in for `--quiet` without touching the process-wide flag.
+    pub struct Capture(());
+
+    impl Capture {
+        pub fn start(quiet: bool) -> Self {
+            CAPTURE.with(|c| *c.borrow_mut() = Some((quiet, Vec::new())));
+            Capture(())
+        }
+
+        pub fn finish(self) -> Vec<String> {
+            CAPTURE.with(|c| {
+                c.borrow_mut()
+                    .take()
+                    .map(|(_, lines)| lines)
+                    .unwrap_or_default()
+            })
+        }
+    }
+
+    impl Drop for Capture {
+        fn drop(&mut self) {
+            CAPTURE.with(|c| c.borrow_mut().take());
+        }
+    }
+
+    pub(super) fn quiet() -> Option<bool> {
+        CAPTURE.with(|c| c.borrow().as_ref().map(|(quiet, _)| *quiet))
+    }
+
+    pub(super) fn push(line: &str) -> bool {
+        CAPTURE.with(|c| match c.borrow_mut().as_mut() {
+            Some((_, lines)) => {
+                lines.push(line.to_string());
+                true
+            }
+            None => false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary() -> Vec<(&'static str, String)> {
+        vec![("Amount", "5000 tokens".to_string())]
+    }
+
+    #[test]
+    fn test_confirm_requires_yes_when_not_a_tty() {
+        let err = confirm_operation(
+            "transfer",
+            &summary(),
+            "yes",
+            false,
+            false,
+            &mut "yes\n".as_bytes(),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("Pass --yes"));
+
+        assert!(confirm_operation(
+            "transfer",
+            &summary(),
+            "yes",
+            true,
+            false,
+            &mut "".as_bytes()
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_confirm_checks_typed_answer() {
+        assert!(confirm_operation(
+            "bond",
+            &summary(),
+            "yes",
+            false,
+            true,
+            &mut " yes \n".as_bytes()
+        )
+        .is_ok());
+        assert!(confirm_operation(
+            "bond",
+            &summary(),
+            "yes",
+            false,
+            true,
+            &mut "y\n".as_bytes()
+        )
+        .is_err());
+        assert!(
+            confirm_operation("bond", &summary(), "yes", false, true, &mut "".as_bytes()).is_err()
+        );
+    }
+
+    #[test]
+    fn test_large_transfer_requires_amount() {
+        assert_eq!(confirmation_phrase(1000, 1000), "yes");
+        let phrase = confirmation_phrase(5000, 1000);
+        assert_eq!(phrase, "5000");
+
+        assert!(confirm_operation(
+            "transfer",
+            &summary(),
+            &phrase,
+            false,
+            true,
+            &mut "yes\n".as_bytes()
+        )
+        .is_err());
+        assert!(confirm_operation(
+            "transfer",
+            &summary(),
+            &phrase,
+            false,
+            true,
+            &mut "5000\n".as_bytes()
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_quiet_keeps_results_and_errors() {
+        let render = || {
+            print_info("Connecting");
+            print_time("Time taken", Duration::from_millis(1500));
+            let mut report = Report::new();
+            report
+                .note("Deployment successful!")
+                .line("Deploy ID: 3045")
+                .time("Total time", Duration::from_millis(1500));
+            report.print();
+            print_error("Deploy failed");
+        };
+
+        let capture = capture::Capture::start(false);
+        render();
+        assert_eq!(
+            capture.finish(),
+            [
+                " Connecting",
+                " Time taken: 1.50s",
+                "Deployment successful!",
+                "Deploy ID: 3045",
+                " Total time: 1.50s",
+                " Deploy failed",
+            ]
+        );
+
+        let capture = capture::Capture::start(true);
+        render();
+        assert_eq!(capture.finish(), ["Deploy ID: 3045", " Deploy failed"]);
+    }
+
+    #[test]
+    fn test_qr_falls_back_to_compact_then_warns() {
+        assert_eq!(fit_qr(None, 41, 31), QrFit::Full);
+        assert_eq!(fit_qr(Some(80), 41, 31), QrFit::Full);
+        assert_eq!(fit_qr(Some(35), 41, 31), QrFit::Compact);
+        assert_eq!(fit_qr(Some(20), 41, 31), QrFit::TooNarrow { needed: 31 });
+
+        let address = "1111AtahZeefej4tvVR6ti9TJtv8yxLebT31SCEVDCKMNikBk5r3g";
+        let width = |lines: &[String]| lines[0].chars().count();
+        assert_eq!(width(&qr_lines(address, Some(80)).unwrap()), 41);
+        assert_eq!(width(&qr_lines(address, Some(35)).unwrap()), 31);
+        let err = qr_lines(address, Some(20)).unwrap_err();
+        assert!(err.contains("at least 31"), "{}", err);
+
+        let capture = capture::Capture::start(true);
+        print_qr(address);
+        // The code is a result, so --quiet does not hide it
+        assert!(!capture.finish().is_empty());
+    }
+}
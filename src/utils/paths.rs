@@ -0,0 +1,75 @@
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+/// Environment variable overriding the CLI configuration directory
+pub const CONFIG_DIR_ENV: &str = "FIREFLY_CONFIG_DIR";
+
+static CONFIG_DIR_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Use `dir` for local CLI state for the rest of the process
+///
+/// Set once at startup from `--config-dir`; later calls are ignored.
+pub fn set_config_dir(dir: PathBuf) {
+    let _ = CONFIG_DIR_OVERRIDE.set(dir);
+}
+
+/// Directory holding local CLI state (history, caches, preferences)
+///
+/// Resolution order: `--config-dir` (see [`set_config_dir`]),
+/// `FIREFLY_CONFIG_DIR`, `$XDG_CONFIG_HOME/node_cli`, `$HOME/.config/node_cli`.
+/// Returns `None` when none of these are set.
+pub fn config_dir() -> Option<PathBuf> {
+    if let Some(dir) = CONFIG_DIR_OVERRIDE.get() {
+        return Some(dir.clone());
+    }
+    if let Ok(dir) = std::env::var(CONFIG_DIR_ENV) {
+        if !dir.is_empty() {
+            return Some(PathBuf::from(dir));
+        }
+    }
+    if let Ok(xdg) = std::env::var("XDG_CONFIG_HOME") {
+        if !xdg.is_empty() {
+            return Some(PathBuf::from(xdg).join("node_cli"));
+        }
+    }
+    std::env::var("HOME")
+        .ok()
+        .filter(|home| !home.is_empty())
+        .map(|home| PathBuf::from(home).join(".config").join("node_cli"))
+}
+
+/// Settings file in the config directory
+pub const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// Value of top-level `key` in a `config.toml`, if set
+///
+/// Only top-level `key = "value"` lines are read; tables and other keys are
+/// skipped, and an empty value counts as unset.
+pub fn config_value_in(content: &str, key: &str) -> Option<String> {
+    for line in content.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            return None;
+        }
+        let Some((name, value)) = line.split_once('=') else {
+            continue;
+        };
+        if name.trim() != key {
+            continue;
+        }
+        let value = value.trim();
+        let value = value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+            .unwrap_or(value);
+        return Some(value.to_string()).filter(|v| !v.is_empty());
+    }
+    None
+}
+
+/// Value of top-level `key` in `config.toml` in the config directory, if set
+pub fn config_value(key: &str) -> Option<String> {
+    let path = config_dir()?.join(CONFIG_FILE_NAME);
+    config_value_in(&std::fs::read_to_string(path).ok()?, key)
+}
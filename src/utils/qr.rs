@@ -0,0 +1,615 @@
+//! QR codes for addresses, drawn in the terminal
+//!
+//! A small byte-mode encoder for versions 1 to 6 (up to 106 bytes at level
+//! M), which is plenty for a vault address. [`QrCode::render`] draws two
+//! module rows per text line with Unicode half blocks, light modules as
+//! foreground, so the code scans on the usual light-on-dark terminal.
+
+/// Error correction level
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EcLevel {
+    /// Recovers about 7% of the code; the smallest symbol for the data
+    Low,
+    /// Recovers about 15%
+    Medium,
+}
+
+impl EcLevel {
+    /// The two format-information bits for the level
+    fn format_bits(self) -> u32 {
+        match self {
+            EcLevel::Low => 0b01,
+            EcLevel::Medium => 0b00,
+        }
+    }
+}
+
+/// Highest version the encoder produces
+pub const MAX_VERSION: usize = 6;
+
+/// Blocks of a version at one level: error correction codewords per block,
+/// then the number of blocks and data codewords in each. Versions 1 to 6
+/// have a single block size.
+#[derive(Debug, Clone, Copy)]
+struct Blocks {
+    ec_per_block: usize,
+    count: usize,
+    data_per_block: usize,
+}
+
+impl Blocks {
+    fn of(version: usize, ec: EcLevel) -> Self {
+        let (ec_per_block, count, data_per_block) = match (version, ec) {
+            (1, EcLevel::Low) => (7, 1, 19),
+            (1, EcLevel::Medium) => (10, 1, 16),
+            (2, EcLevel::Low) => (10, 1, 34),
+            (2, EcLevel::Medium) => (16, 1, 28),
+            (3, EcLevel::Low) => (15, 1, 55),
+            (3, EcLevel::Medium) => (26, 1, 44),
+            (4, EcLevel::Low) => (20, 1, 80),
+            (4, EcLevel::Medium) => (18, 2, 32),
+            (5, EcLevel::Low) => (26, 1, 108),
+            (5, EcLevel::Medium) => (24, 2, 43),
+            (6, EcLevel::Low) => (18, 2, 68),
+            (6, EcLevel::Medium) => (16, 4, 27),
+            _ => unreachable!("version {} is not supported", version),
+        };
+        Self {
+            ec_per_block,
+            count,
+            data_per_block,
+        }
+    }
+
+    fn data_codewords(&self) -> usize {
+        self.count * self.data_per_block
+    }
+}
+
+/// A QR symbol's modules, `true` for dark
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QrCode {
+    size: usize,
+    modules: Vec<bool>,
+}
+
+impl QrCode {
+    /// Encode `data` in byte mode in the smallest version that holds it
+    pub fn encode(data: &[u8], ec: EcLevel) -> Result<Self, String> {
+        let version = (1..=MAX_VERSION)
+            .find(|&v| 4 + 8 + data.len() * 8 <= Blocks::of(v, ec).data_codewords() * 8)
+            .ok_or_else(|| {
+                format!(
+                    "{} bytes do not fit in a version {} QR code",
+                    data.len(),
+                    MAX_VERSION
+                )
+            })?;
+        let blocks = Blocks::of(version, ec);
+        let codewords = interleave(&data_codewords(data, blocks.data_codewords()), blocks);
+
+        let mut builder = Builder::new(version);
+        builder.draw_function_patterns();
+        builder.draw_codewords(&codewords);
+        let mask = (0..8)
+            .min_by_key(|&mask| {
+                let mut candidate = builder.clone();
+                candidate.apply_mask(mask);
+                candidate.draw_format(ec, mask);
+                candidate.penalty()
+            })
+            .unwrap_or(0);
+        builder.apply_mask(mask);
+        builder.draw_format(ec, mask);
+        Ok(Self {
+            size: builder.size,
+            modules: builder.modules,
+        })
+    }
+
+    /// Modules per side
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_dark(&self, row: usize, col: usize) -> bool {
+        row < self.size && col < self.size && self.modules[row * self.size + col]
+    }
+
+    /// Text columns [`render`](Self::render) takes with a quiet zone of `quiet` modules
+    pub fn rendered_width(&self, quiet: usize) -> usize {
+        self.size + 2 * quiet
+    }
+
+    /// Lines of half blocks, two module rows per line, light modules drawn
+    pub fn render(&self, quiet: usize) -> Vec<String> {
+        let quiet = quiet as isize;
+        let span = self.size as isize + quiet;
+        let light = |row: isize, col: isize| {
+            row < 0
+                || col < 0
+                || row >= self.size as isize
+                || col >= self.size as isize
+                || !self.is_dark(row as usize, col as usize)
+        };
+        let mut lines = Vec::new();
+        let mut row = -quiet;
+        while row < span {
+            let line = (-quiet..span)
+                .map(|col| match (light(row, col), light(row + 1, col)) {
+                    (true, true) => '█',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (false, false) => ' ',
+                })
+                .collect();
+            lines.push(line);
+            row += 2;
+        }
+        lines
+    }
+}
+
+/// Mode indicator, length, data, terminator and padding, as codewords
+fn data_codewords(data: &[u8], capacity: usize) -> Vec<u8> {
+    let mut bits: Vec<bool> = Vec::with_capacity(capacity * 8);
+    let mut push = |value: u32, len: usize| {
+        for i in (0..len).rev() {
+            bits.push((value >> i) & 1 == 1);
+        }
+    };
+    push(0b0100, 4);
+    push(data.len() as u32, 8);
+    for &byte in data {
+        push(byte as u32, 8);
+    }
+    let terminator = (capacity * 8 - bits.len()).min(4);
+    bits.extend(std::iter::repeat_n(false, terminator));
+    while bits.len() % 8 != 0 {
+        bits.push(false);
+    }
+    let mut codewords: Vec<u8> = bits
+        .chunks(8)
+        .map(|byte| byte.iter().fold(0u8, |acc, &bit| (acc << 1) | bit as u8))
+        .collect();
+    for pad in [0xEC, 0x11].into_iter().cycle() {
+        if codewords.len() >= capacity {
+            break;
+        }
+        codewords.push(pad);
+    }
+    codewords
+}
+
+/// Split the data into blocks, add error correction, and interleave
+fn interleave(data: &[u8], blocks: Blocks) -> Vec<u8> {
+    let generator = rs_generator(blocks.ec_per_block);
+    let split: Vec<&[u8]> = data.chunks(blocks.data_per_block).collect();
+    let ec: Vec<Vec<u8>> = split
+        .iter()
+        .map(|block| rs_remainder(block, &generator))
+        .collect();
+    let mut out = Vec::new();
+    for i in 0..blocks.data_per_block {
+        out.extend(split.iter().filter_map(|block| block.get(i)));
+    }
+    for i in 0..blocks.ec_per_block {
+        out.extend(ec.iter().map(|block| block[i]));
+    }
+    out
+}
+
+/// Multiply in GF(256) with the QR polynomial x^8 + x^4 + x^3 + x^2 + 1
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0u8;
+    while b != 0 {
+        if b & 1 == 1 {
+            product ^= a;
+        }
+        let carry = a & 0x80 != 0;
+        a <<= 1;
+        if carry {
+            a ^= 0x1D;
+        }
+        b >>= 1;
+    }
+    product
+}
+
+/// Coefficients of the Reed-Solomon generator of `degree`, highest first,
+/// leading 1 omitted
+fn rs_generator(degree: usize) -> Vec<u8> {
+    let mut coefficients = vec![0u8; degree];
+    coefficients[degree - 1] = 1;
+    let mut root = 1u8;
+    for _ in 0..degree {
+        for j in 0..degree {
+            coefficients[j] = gf_mul(coefficients[j], root);
+            if j + 1 < degree {
+                coefficients[j] ^= coefficients[j + 1];
+            }
+        }
+        root = gf_mul(root, 0x02);
+    }
+    coefficients
+}
+
+fn rs_remainder(data: &[u8], generator: &[u8]) -> Vec<u8> {
+    let mut remainder = vec![0u8; generator.len()];
+    for &byte in data {
+        let factor = byte ^ remainder[0];
+        remainder.remove(0);
+        remainder.push(0);
+        for (r, &g) in remainder.iter_mut().zip(generator) {
+            *r ^= gf_mul(g, factor);
+        }
+    }
+    remainder
+}
+
+/// A symbol being drawn, with the modules that function patterns own
+#[derive(Clone)]
+struct Builder {
+    version: usize,
+    size: usize,
+    modules: Vec<bool>,
+    function: Vec<bool>,
+}
+
+impl Builder {
+    fn new(version: usize) -> Self {
+        let size = version * 4 + 17;
+        Self {
+            version,
+            size,
+            modules: vec![false; size * size],
+            function: vec![false; size * size],
+        }
+    }
+
+    fn set_function(&mut self, row: usize, col: usize, dark: bool) {
+        self.modules[row * self.size + col] = dark;
+        self.function[row * self.size + col] = true;
+    }
+
+    fn draw_function_patterns(&mut self) {
+        let size = self.size;
+        for i in 0..size {
+            self.set_function(6, i, i % 2 == 0);
+            self.set_function(i, 6, i % 2 == 0);
+        }
+        for (row, col) in [(3, 3), (3, size - 4), (size - 4, 3)] {
+            self.draw_finder(row, col);
+        }
+        if self.version >= 2 {
+            let center = size - 7;
+            for dr in -2isize..=2 {
+                for dc in -2isize..=2 {
+                    let ring = dr.abs().max(dc.abs());
+                    self.set_function(
+                        (center as isize + dr) as usize,
+                        (center as isize + dc) as usize,
+                        ring != 1,
+                    );
+                }
+            }
+        }
+        // Reserve the format areas; draw_format fills them
+        self.draw_format(EcLevel::Low, 0);
+    }
+
+    /// Finder pattern centred on (row, col), with its separator
+    fn draw_finder(&mut self, row: usize, col: usize) {
+        for dr in -4isize..=4 {
+            for dc in -4isize..=4 {
+                let (r, c) = (row as isize + dr, col as isize + dc);
+                if r < 0 || c < 0 || r >= self.size as isize || c >= self.size as isize {
+                    continue;
+                }
+                let ring = dr.abs().max(dc.abs());
+                self.set_function(r as usize, c as usize, ring != 2 && ring != 4);
+            }
+        }
+    }
+
+    fn draw_format(&mut self, ec: EcLevel, mask: u32) {
+        let data = ec.format_bits() << 3 | mask;
+        let mut remainder = data;
+        for _ in 0..10 {
+            remainder = (remainder << 1) ^ ((remainder >> 9) * 0x537);
+        }
+        let bits = (data << 10 | remainder) ^ 0x5412;
+        let bit = |i: usize| (bits >> i) & 1 == 1;
+        let size = self.size;
+
+        for i in 0..=5 {
+            self.set_function(i, 8, bit(i));
+        }
+        self.set_function(7, 8, bit(6));
+        self.set_function(8, 8, bit(7));
+        self.set_function(8, 7, bit(8));
+        for i in 9..15 {
+            self.set_function(8, 14 - i, bit(i));
+        }
+        for i in 0..8 {
+            self.set_function(8, size - 1 - i, bit(i));
+        }
+        for i in 8..15 {
+            self.set_function(size - 15 + i, 8, bit(i));
+        }
+        self.set_function(size - 8, 8, true);
+    }
+
+    /// Place codewords in the two-column zigzag from the bottom right
+    fn draw_codewords(&mut self, codewords: &[u8]) {
+        let size = self.size as isize;
+        let total_bits = codewords.len() * 8;
+        let mut i = 0;
+        let mut right = size - 1;
+        while right >= 1 {
+            if right == 6 {
+                right = 5;
+            }
+            let upward = (right + 1) & 2 == 0;
+            for vert in 0..size {
+                for j in 0..2 {
+                    let col = (right - j) as usize;
+                    let row = (if upward { size - 1 - vert } else { vert }) as usize;
+                    if !self.function[row * self.size + col] && i < total_bits {
+                        self.modules[row * self.size + col] =
+                            (codewords[i >> 3] >> (7 - (i & 7))) & 1 == 1;
+                        i += 1;
+                    }
+                }
+            }
+            right -= 2;
+        }
+    }
+
+    fn apply_mask(&mut self, mask: u32) {
+        for row in 0..self.size {
+            for col in 0..self.size {
+                let index = row * self.size + col;
+                if !self.function[index] && mask_bit(mask, row, col) {
+                    self.modules[index] = !self.modules[index];
+                }
+            }
+        }
+    }
+
+    fn dark(&self, row: usize, col: usize) -> bool {
+        self.modules[row * self.size + col]
+    }
+
+    /// Lower is better: long runs, 2x2 blocks, finder-like runs and imbalance
+    fn penalty(&self) -> usize {
+        let size = self.size;
+        let mut penalty = 0;
+        let line = |i: usize, horizontal: bool| -> Vec<bool> {
+            (0..size)
+                .map(|j| {
+                    if horizontal {
+                        self.dark(i, j)
+                    } else {
+                        self.dark(j, i)
+                    }
+                })
+                .collect()
+        };
+        const FINDER: [bool; 11] = [
+            true, false, true, true, true, false, true, false, false, false, false,
+        ];
+        for i in 0..size {
+            for horizontal in [true, false] {
+                let modules = line(i, horizontal);
+                let mut run = 1;
+                for j in 1..=size {
+                    if j < size && modules[j] == modules[j - 1] {
+                        run += 1;
+                    } else {
+                        if run >= 5 {
+                            penalty += run - 2;
+                        }
+                        run = 1;
+                    }
+                }
+                for window in modules.windows(FINDER.len()) {
+                    if window == FINDER || window.iter().rev().eq(FINDER.iter()) {
+                        penalty += 40;
+                    }
+                }
+            }
+        }
+        for row in 0..size - 1 {
+            for col in 0..size - 1 {
+                let dark = self.dark(row, col);
+                if dark == self.dark(row, col + 1)
+                    && dark == self.dark(row + 1, col)
+                    && dark == self.dark(row + 1, col + 1)
+                {
+                    penalty += 3;
+                }
+            }
+        }
+        let dark = self.modules.iter().filter(|&&m| m).count();
+        let percent = dark * 100 / self.modules.len();
+        penalty + percent.abs_diff(50) / 5 * 10
+    }
+}
+
+fn mask_bit(mask: u32, row: usize, col: usize) -> bool {
+    let (r, c) = (row, col);
+    match mask {
+        0 => (r + c) % 2 == 0,
+        1 => r % 2 == 0,
+        2 => c % 3 == 0,
+        3 => (r + c) % 3 == 0,
+        4 => (r / 2 + c / 3) % 2 == 0,
+        5 => (r * c) % 2 + (r * c) % 3 == 0,
+        6 => ((r * c) % 2 + (r * c) % 3) % 2 == 0,
+        _ => ((r + c) % 2 + (r * c) % 3) % 2 == 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A decoder that only trusts the symbol: it reads the format
+    /// information, works out the reserved areas from the size, unmasks,
+    /// checks every block's error correction and parses the byte segment
+    fn decode(code: &QrCode) -> Result<Vec<u8>, String> {
+        let size = code.size();
+        if size < 21 || (size - 17) % 4 != 0 {
+            return Err(format!("bad size {}", size));
+        }
+        let version = (size - 17) / 4;
+        for (row, col) in [(0, 0), (0, size - 7), (size - 7, 0)] {
+            for i in 0..7 {
+                if !code.is_dark(row + i, col) || !code.is_dark(row, col + i) {
+                    return Err("finder pattern missing".into());
+                }
+            }
+        }
+
+        // First copy of the format information, most significant bit first
+        let mut positions: Vec<(usize, usize)> = (0..=5).map(|c| (8, c)).collect();
+        positions.extend([(8, 7), (8, 8), (7, 8)]);
+        positions.extend((0..=5).rev().map(|r| (r, 8)));
+        let raw = positions
+            .iter()
+            .fold(0u32, |acc, &(r, c)| acc << 1 | code.is_dark(r, c) as u32);
+        let format = raw ^ 0x5412;
+        let mut check = format >> 10;
+        for _ in 0..10 {
+            check = (check << 1) ^ ((check >> 9) * 0x537);
+        }
+        if check & 0x3FF != format & 0x3FF {
+            return Err("format information does not check".into());
+        }
+        let ec = match format >> 13 {
+            0b01 => EcLevel::Low,
+            0b00 => EcLevel::Medium,
+            other => return Err(format!("unexpected level bits {:02b}", other)),
+        };
+        let mask = (format >> 10) & 7;
+
+        let reserved = |r: usize, c: usize| {
+            let near = |a: usize, b: usize| a.abs_diff(b) <= 2;
+            (r < 9 && c < 9)
+                || (r < 9 && c >= size - 8)
+                || (r >= size - 8 && c < 9)
+                || r == 6
+                || c == 6
+                || (version >= 2 && near(r, size - 7) && near(c, size - 7))
+        };
+        let mut bits = Vec::new();
+        let mut right = size as isize - 1;
+        while right >= 1 {
+            if right == 6 {
+                right = 5;
+            }
+            let upward = (right + 1) & 2 == 0;
+            for vert in 0..size {
+                for j in 0..2 {
+                    let c = (right - j) as usize;
+                    let r = if upward { size - 1 - vert } else { vert };
+                    if !reserved(r, c) {
+                        bits.push(code.is_dark(r, c) ^ mask_bit(mask, r, c));
+                    }
+                }
+            }
+            right -= 2;
+        }
+        let codewords: Vec<u8> = bits
+            .chunks_exact(8)
+            .map(|byte| byte.iter().fold(0u8, |acc, &b| acc << 1 | b as u8))
+            .collect();
+
+        let blocks = Blocks::of(version, ec);
+        let data_len = blocks.data_codewords();
+        let generator = rs_generator(blocks.ec_per_block);
+        let mut data = Vec::new();
+        for b in 0..blocks.count {
+            let block: Vec<u8> = (0..blocks.data_per_block)
+                .map(|i| codewords[i * blocks.count + b])
+                .collect();
+            let ec: Vec<u8> = (0..blocks.ec_per_block)
+                .map(|i| codewords[data_len + i * blocks.count + b])
+                .collect();
+            if rs_remainder(&block, &generator) != ec {
+                return Err(format!("block {} fails error correction", b));
+            }
+            data.extend(block);
+        }
+
+        let bit = |i: usize| (data[i / 8] >> (7 - i % 8)) & 1;
+        let read = |from: usize, len: usize| {
+            (from..from + len).fold(0usize, |a, i| a << 1 | bit(i) as usize)
+        };
+        if read(0, 4) != 0b0100 {
+            return Err("not a byte-mode segment".into());
+        }
+        let len = read(4, 8);
+        Ok((0..len).map(|i| read(12 + i * 8, 8) as u8).collect())
+    }
+
+    const ADDRESS: &str = "1111AtahZeefej4tvVR6ti9TJtv8yxLebvyDoX4tKnbX9DZGQCExL";
+
+    #[test]
+    fn test_addresses_round_trip() {
+        for ec in [EcLevel::Low, EcLevel::Medium] {
+            let code = QrCode::encode(ADDRESS.as_bytes(), ec).unwrap();
+            assert_eq!(decode(&code).unwrap(), ADDRESS.as_bytes(), "{:?}", ec);
+        }
+        // 53 bytes is exactly version 3's capacity at level L
+        assert_eq!(
+            QrCode::encode(ADDRESS.as_bytes(), EcLevel::Low)
+                .unwrap()
+                .size(),
+            29
+        );
+        assert_eq!(
+            QrCode::encode(ADDRESS.as_bytes(), EcLevel::Medium)
+                .unwrap()
+                .size(),
+            33
+        );
+    }
+
+    #[test]
+    fn test_every_version_round_trips() {
+        for len in [1, 17, 30, 60, 84, 106] {
+            let data: Vec<u8> = (0..len).map(|i| (i * 37 % 251) as u8).collect();
+            let code = QrCode::encode(&data, EcLevel::Medium).unwrap();
+            assert_eq!(decode(&code).unwrap(), data, "{} bytes", len);
+        }
+        assert!(QrCode::encode(&[0u8; 107], EcLevel::Medium).is_err());
+    }
+
+    #[test]
+    fn test_known_reed_solomon_remainder() {
+        // The worked example from the standard: "01234567" at 1-M
+        let data = [
+            0x10, 0x20, 0x0C, 0x56, 0x61, 0x80, 0xEC, 0x11, 0xEC, 0x11, 0xEC, 0x11, 0xEC, 0x11,
+            0xEC, 0x11,
+        ];
+        assert_eq!(
+            rs_remainder(&data, &rs_generator(10)),
+            [0xA5, 0x24, 0xD4, 0xC1, 0xED, 0x36, 0xC7, 0x87, 0x2C, 0x55]
+        );
+    }
+
+    #[test]
+    fn test_render_uses_half_blocks() {
+        let code = QrCode::encode(ADDRESS.as_bytes(), EcLevel::Low).unwrap();
+        let lines = code.render(1);
+        // 29 modules and a one-module border on each side, two rows a line
+        assert_eq!(lines.len(), 16);
+        assert!(lines
+            .iter()
+            .all(|line| line.chars().count() == code.rendered_width(1)));
+        // The quiet zone row above the finder patterns is light
+        assert!(lines[0].starts_with('█'));
+    }
+}
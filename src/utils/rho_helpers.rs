@@ -0,0 +1,72 @@
+/// Opens a placeholder such as `{{to}}` in a Rholang template
+pub const PLACEHOLDER_START: &str = "{{";
+
+/// Closes a placeholder
+pub const PLACEHOLDER_END: &str = "}}";
+
+/// Replace each `{{name}}` in `template` with its value from `values`
+///
+/// The template is read once, left to right, so a value that itself contains
+/// `{{...}}` is inserted as is and never filled in turn. A placeholder with no
+/// value, or one that is never closed, is an error naming it, so a misspelt
+/// placeholder is not deployed as Rholang.
+pub fn fill_placeholders(template: &str, values: &[(&str, &str)]) -> Result<String, String> {
+    let mut filled = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find(PLACEHOLDER_START) {
+        filled.push_str(&rest[..start]);
+        let after = &rest[start + PLACEHOLDER_START.len()..];
+        let Some(end) = after.find(PLACEHOLDER_END) else {
+            let name: String = after.chars().take_while(|c| !c.is_whitespace()).collect();
+            return Err(format!("placeholder {{{{{} is not closed", name));
+        };
+        let name = after[..end].trim();
+        let (_, value) = values
+            .iter()
+            .find(|(known, _)| *known == name)
+            .ok_or_else(|| format!("placeholder {{{{{}}}}} was not filled in", name))?;
+        filled.push_str(value);
+        rest = &after[end + PLACEHOLDER_END.len()..];
+    }
+    filled.push_str(rest);
+    Ok(filled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fill_placeholders() {
+        let values = [("to", "1111bb"), ("amount", "5")];
+        assert_eq!(
+            fill_placeholders(r#"@v!("transfer", "{{to}}", {{ amount }})"#, &values).unwrap(),
+            r#"@v!("transfer", "1111bb", 5)"#
+        );
+        // Repeated placeholders are all filled; unused values are fine
+        assert_eq!(
+            fill_placeholders("{{amount}} of {{amount}}", &values).unwrap(),
+            "5 of 5"
+        );
+        assert_eq!(fill_placeholders("Nil", &values).unwrap(), "Nil");
+    }
+
+    #[test]
+    fn test_unfilled_placeholders_are_errors() {
+        let values = [("to", "1111bb")];
+        assert_eq!(
+            fill_placeholders("{{to}} {{amuont}}", &values).unwrap_err(),
+            "placeholder {{amuont}} was not filled in"
+        );
+        assert_eq!(
+            fill_placeholders("{{to}} {{amount", &values).unwrap_err(),
+            "placeholder {{amount is not closed"
+        );
+    }
+
+    #[test]
+    fn test_values_are_not_filled_again() {
+        let values = [("to", "{{amount}}"), ("amount", "5")];
+        assert_eq!(fill_placeholders("{{to}}", &values).unwrap(), "{{amount}}");
+    }
+}
@@ -0,0 +1,152 @@
+//! Percentile summaries of timing samples and window-over-window comparison
+//!
+//! Shared by the `load-test` and `latency-probe` reports and the `stats` and
+//! `chain-stats` commands. Samples are usually durations in milliseconds;
+//! percentiles use the nearest-rank method, so every reported value is one of
+//! the samples.
+
+use serde::{Deserialize, Serialize};
+
+/// Nearest-rank percentile of an ascending slice, `p` in `0.0..=100.0`
+pub fn percentile(sorted: &[u64], p: f64) -> Option<u64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = ((p.clamp(0.0, 100.0) / 100.0) * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
+}
+
+/// Distribution of a set of samples, in milliseconds
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Summary {
+    pub count: usize,
+    pub min: u64,
+    pub p50: u64,
+    pub p95: u64,
+    pub max: u64,
+    pub mean: f64,
+}
+
+impl Summary {
+    /// Summarize samples in any order; `None` when there are none
+    pub fn from_samples(samples: impl IntoIterator<Item = u64>) -> Option<Self> {
+        let mut sorted: Vec<u64> = samples.into_iter().collect();
+        sorted.sort_unstable();
+        let count = sorted.len();
+        Some(Self {
+            count,
+            min: *sorted.first()?,
+            p50: percentile(&sorted, 50.0)?,
+            p95: percentile(&sorted, 95.0)?,
+            max: *sorted.last()?,
+            mean: sorted.iter().sum::<u64>() as f64 / count as f64,
+        })
+    }
+}
+
+/// Two summaries of the same measurement over consecutive windows
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Comparison {
+    pub before: Summary,
+    pub after: Summary,
+}
+
+impl Comparison {
+    pub fn new(before: Summary, after: Summary) -> Self {
+        Self { before, after }
+    }
+
+    /// Relative change of the median, e.g. `0.25` for 25% slower
+    pub fn p50_change(&self) -> f64 {
+        relative_change(self.before.p50, self.after.p50)
+    }
+
+    /// Relative change of the 95th percentile
+    pub fn p95_change(&self) -> f64 {
+        relative_change(self.before.p95, self.after.p95)
+    }
+
+    /// True when both windows have at least `min_samples` samples and the
+    /// median or the 95th percentile grew by more than `threshold` (0.2 = 20%)
+    pub fn is_regression(&self, threshold: f64, min_samples: usize) -> bool {
+        self.before.count >= min_samples
+            && self.after.count >= min_samples
+            && (self.p50_change() > threshold || self.p95_change() > threshold)
+    }
+}
+
+fn relative_change(before: u64, after: u64) -> f64 {
+    if before == 0 {
+        return if after == 0 { 0.0 } else { f64::INFINITY };
+    }
+    (after as f64 - before as f64) / before as f64
+}
+
+/// Format a relative change as a signed percentage, e.g. `+25%`
+pub fn format_change(change: f64) -> String {
+    if change.is_infinite() {
+        "new".to_string()
+    } else {
+        format!("{:+.0}%", change * 100.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile_nearest_rank() {
+        let sorted: Vec<u64> = (1..=100).collect();
+        assert_eq!(percentile(&sorted, 50.0), Some(50));
+        assert_eq!(percentile(&sorted, 95.0), Some(95));
+        assert_eq!(percentile(&sorted, 0.0), Some(1));
+        assert_eq!(percentile(&sorted, 100.0), Some(100));
+        assert_eq!(percentile(&[7], 95.0), Some(7));
+        assert_eq!(percentile(&[], 50.0), None);
+    }
+
+    #[test]
+    fn test_summary_ignores_input_order() {
+        let summary = Summary::from_samples([300, 100, 200, 400, 1000]).unwrap();
+        assert_eq!(summary.count, 5);
+        assert_eq!(summary.min, 100);
+        assert_eq!(summary.p50, 300);
+        assert_eq!(summary.p95, 1000);
+        assert_eq!(summary.max, 1000);
+        assert_eq!(summary.mean, 400.0);
+        assert!(Summary::from_samples(Vec::new()).is_none());
+    }
+
+    #[test]
+    fn test_regression_detected_on_slower_window() {
+        let before = Summary::from_samples((0..20).map(|i| 1000 + i * 10)).unwrap();
+        let after = Summary::from_samples((0..20).map(|i| 1500 + i * 10)).unwrap();
+        let comparison = Comparison::new(before, after);
+        assert!(comparison.p50_change() > 0.4);
+        assert!(comparison.is_regression(0.2, 5));
+        assert!(!comparison.is_regression(0.6, 5));
+        // Too few samples to call it
+        assert!(!comparison.is_regression(0.2, 50));
+        // Faster is never a regression
+        assert!(!Comparison::new(after, before).is_regression(0.2, 5));
+    }
+
+    #[test]
+    fn test_regression_on_tail_only() {
+        let mut slow_tail: Vec<u64> = vec![1000; 18];
+        slow_tail.extend([5000, 5000]);
+        let before = Summary::from_samples(vec![1000; 20]).unwrap();
+        let after = Summary::from_samples(slow_tail).unwrap();
+        let comparison = Comparison::new(before, after);
+        assert_eq!(comparison.p50_change(), 0.0);
+        assert!(comparison.is_regression(0.2, 5));
+    }
+
+    #[test]
+    fn test_format_change() {
+        assert_eq!(format_change(0.254), "+25%");
+        assert_eq!(format_change(-0.1), "-10%");
+        assert_eq!(format_change(f64::INFINITY), "new");
+    }
+}
@@ -0,0 +1,278 @@
+//! One validator's blocks by sequence number, for `validator-chain`
+//!
+//! Blocks in a height range are fetched chunk by chunk and only the given
+//! creator's are kept, so memory grows with that validator's blocks rather
+//! than the range. [`analyze`] orders them by `seq_num` and reports missing
+//! sequence numbers (blocks not seen in the range, or never produced),
+//! sequence numbers held by more than one block (equivocations, which
+//! `equivocation-check` reports across all validators) and the average time
+//! between consecutive sequence numbers.
+
+use crate::error::{NodeCliError, Result};
+use crate::export::ChainSource;
+use serde::Serialize;
+
+/// A block made by the validator being viewed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChainBlock {
+    pub block_hash: String,
+    pub block_number: i64,
+    pub seq_num: i64,
+    /// Unix epoch milliseconds; 0 when missing
+    pub timestamp: i64,
+    /// The validator's own latest block cited in the justifications, when the
+    /// node lists them
+    pub previous: Option<String>,
+}
+
+impl ChainBlock {
+    /// Parse a ranged-blocks entry, `None` unless `creator` made it
+    ///
+    /// The creator is compared ignoring case.
+    pub fn from_json(json: &serde_json::Value, creator: &str) -> Option<Self> {
+        let info = json.get("blockInfo").unwrap_or(json);
+        let sender = info.get("sender")?.as_str()?;
+        if !sender.eq_ignore_ascii_case(creator) {
+            return None;
+        }
+        let previous = info
+            .get("justifications")
+            .and_then(|j| j.as_array())
+            .into_iter()
+            .flatten()
+            .find(|j| {
+                j.get("validator")
+                    .and_then(|v| v.as_str())
+                    .is_some_and(|v| v.eq_ignore_ascii_case(creator))
+            })
+            .and_then(|j| j.get("latestBlockHash"))
+            .and_then(|h| h.as_str())
+            .map(str::to_string);
+        Some(Self {
+            block_hash: info.get("blockHash")?.as_str()?.to_string(),
+            block_number: info.get("blockNumber").and_then(|n| n.as_i64())?,
+            seq_num: info.get("seqNum").and_then(|n| n.as_i64())?,
+            timestamp: info.get("timestamp").and_then(|t| t.as_i64()).unwrap_or(0),
+            previous,
+        })
+    }
+}
+
+/// Consecutive sequence numbers with no block, inclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SeqGap {
+    pub from: i64,
+    pub to: i64,
+}
+
+impl SeqGap {
+    pub fn missing(&self) -> i64 {
+        self.to - self.from + 1
+    }
+}
+
+/// A sequence number held by more than one distinct block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SeqDuplicate {
+    pub seq_num: i64,
+    pub block_hashes: Vec<String>,
+}
+
+/// A validator's blocks ordered by sequence number, with what is off about them
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValidatorChain {
+    pub validator: String,
+    /// Ordered by sequence number, then height and hash
+    pub blocks: Vec<ChainBlock>,
+    pub gaps: Vec<SeqGap>,
+    pub duplicates: Vec<SeqDuplicate>,
+    /// Mean milliseconds between consecutive sequence numbers, over pairs
+    /// that both have a timestamp; `None` with fewer than two such blocks
+    pub average_interval_ms: Option<f64>,
+}
+
+impl ValidatorChain {
+    /// Lowest and highest sequence number seen
+    pub fn seq_range(&self) -> Option<(i64, i64)> {
+        Some((self.blocks.first()?.seq_num, self.blocks.last()?.seq_num))
+    }
+}
+
+/// Order `blocks` by sequence number and find gaps and duplicates
+///
+/// A block listed twice counts once. Gaps are only looked for between the
+/// lowest and highest sequence number seen; a negative interval from clock
+/// skew counts as zero.
+pub fn analyze(validator: &str, mut blocks: Vec<ChainBlock>) -> ValidatorChain {
+    blocks.sort_by(|a, b| {
+        (a.seq_num, a.block_number, &a.block_hash).cmp(&(b.seq_num, b.block_number, &b.block_hash))
+    });
+    blocks.dedup_by(|a, b| a.block_hash == b.block_hash);
+
+    let mut gaps = Vec::new();
+    let mut duplicates: Vec<SeqDuplicate> = Vec::new();
+    let mut intervals = Vec::new();
+    for pair in blocks.windows(2) {
+        let (prev, next) = (&pair[0], &pair[1]);
+        if next.seq_num == prev.seq_num {
+            match duplicates.last_mut() {
+                Some(dup) if dup.seq_num == next.seq_num => {
+                    dup.block_hashes.push(next.block_hash.clone())
+                }
+                _ => duplicates.push(SeqDuplicate {
+                    seq_num: next.seq_num,
+                    block_hashes: vec![prev.block_hash.clone(), next.block_hash.clone()],
+                }),
+            }
+            continue;
+        }
+        if next.seq_num > prev.seq_num + 1 {
+            gaps.push(SeqGap {
+                from: prev.seq_num + 1,
+                to: next.seq_num - 1,
+            });
+        }
+        if next.seq_num == prev.seq_num + 1 && prev.timestamp > 0 && next.timestamp > 0 {
+            intervals.push((next.timestamp - prev.timestamp).max(0));
+        }
+    }
+    let average_interval_ms = (!intervals.is_empty())
+        .then(|| intervals.iter().sum::<i64>() as f64 / intervals.len() as f64);
+
+    ValidatorChain {
+        validator: validator.to_string(),
+        blocks,
+        gaps,
+        duplicates,
+        average_interval_ms,
+    }
+}
+
+/// `creator`'s blocks at heights `start..=end`, `chunk_size` heights per request
+pub async fn collect(
+    source: &ChainSource,
+    creator: &str,
+    start: i64,
+    end: i64,
+    chunk_size: i64,
+) -> Result<Vec<ChainBlock>> {
+    if chunk_size < 1 {
+        return Err(NodeCliError::config_invalid_value(
+            "chunk-size",
+            "must be at least 1",
+        ));
+    }
+    let mut blocks = Vec::new();
+    let mut chunk_start = start;
+    while chunk_start <= end {
+        let chunk_end = (chunk_start + chunk_size - 1).min(end);
+        blocks.extend(
+            source
+                .ranged_json(chunk_start, chunk_end)
+                .await?
+                .iter()
+                .filter_map(|json| ChainBlock::from_json(json, creator))
+                .filter(|b| (chunk_start..=chunk_end).contains(&b.block_number)),
+        );
+        chunk_start = chunk_end + 1;
+    }
+    Ok(blocks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::spawn_http_node;
+
+    fn block(hash: &str, height: i64, seq_num: i64, timestamp: i64) -> ChainBlock {
+        ChainBlock {
+            block_hash: hash.to_string(),
+            block_number: height,
+            seq_num,
+            timestamp,
+            previous: None,
+        }
+    }
+
+    #[test]
+    fn test_gaps_and_duplicates() {
+        let blocks = vec![
+            block("b7", 14, 7, 70_000),
+            block("b3", 6, 3, 30_000),
+            block("b4", 8, 4, 40_000),
+            block("b7x", 14, 7, 70_500),
+            // Seen twice, e.g. from overlapping requests
+            block("b4", 8, 4, 40_000),
+            block("b8", 16, 8, 85_000),
+            block("b7y", 15, 7, 71_000),
+        ];
+        let chain = analyze("04aa", blocks);
+        let order: Vec<&str> = chain.blocks.iter().map(|b| b.block_hash.as_str()).collect();
+        assert_eq!(order, ["b3", "b4", "b7", "b7x", "b7y", "b8"]);
+        assert_eq!(chain.seq_range(), Some((3, 8)));
+        assert_eq!(chain.gaps, [SeqGap { from: 5, to: 6 }]);
+        assert_eq!(chain.gaps[0].missing(), 2);
+        assert_eq!(
+            chain.duplicates,
+            [SeqDuplicate {
+                seq_num: 7,
+                block_hashes: vec!["b7".to_string(), "b7x".to_string(), "b7y".to_string()],
+            }]
+        );
+        // 3→4 and 7→8 only; the gap and the duplicates add no interval
+        assert_eq!(chain.average_interval_ms, Some(12_000.0));
+    }
+
+    #[test]
+    fn test_intervals_skip_missing_timestamps() {
+        let chain = analyze(
+            "04aa",
+            vec![
+                block("a", 1, 1, 10_000),
+                block("b", 2, 2, 0),
+                block("c", 3, 3, 20_000),
+                // Clock running behind clamps to zero
+                block("d", 4, 4, 19_000),
+            ],
+        );
+        assert!(chain.gaps.is_empty() && chain.duplicates.is_empty());
+        assert_eq!(chain.average_interval_ms, Some(0.0));
+        assert_eq!(analyze("04aa", Vec::new()).seq_range(), None);
+        assert_eq!(
+            analyze("04aa", vec![block("a", 1, 1, 10_000)]).average_interval_ms,
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_collect_filters_by_sender() {
+        let port = spawn_http_node(|line, _body| {
+            let body = if line.contains("/api/blocks/1/2") {
+                serde_json::json!([
+                    {"blockHash": "a1", "blockNumber": 1, "sender": "04AA", "seqNum": 1,
+                     "justifications": [
+                        {"validator": "04bb", "latestBlockHash": "b0"},
+                        {"validator": "04aa", "latestBlockHash": "a0"}
+                     ]},
+                    {"blockHash": "b1", "blockNumber": 2, "sender": "04bb", "seqNum": 1},
+                ])
+            } else if line.contains("/api/blocks/3/3") {
+                serde_json::json!([
+                    {"blockInfo": {"blockHash": "a2", "blockNumber": 3, "sender": "04aa", "seqNum": 2}}
+                ])
+            } else {
+                return ("404 Not Found", String::new());
+            };
+            ("200 OK", body.to_string())
+        })
+        .await;
+        let source = ChainSource::new("127.0.0.1", port);
+
+        let blocks = collect(&source, "04aa", 1, 3, 2).await.unwrap();
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].previous.as_deref(), Some("a0"));
+        assert_eq!(blocks[1].block_hash, "a2");
+        assert_eq!(blocks[1].previous, None);
+        assert!(collect(&source, "04aa", 1, 3, 0).await.is_err());
+    }
+}
@@ -0,0 +1,153 @@
+//! Finding a local key that is an active validator
+//!
+//! When a node refuses to propose because its validator is not active,
+//! `propose --suggest` looks for a key that is: each private key file in the
+//! keystore directory, and the dev shard's well-known keys, are reduced to
+//! public keys and matched against the node's active validator set. Only
+//! entry names and public keys leave this module; private keys are dropped as
+//! soon as the public key is derived.
+
+use crate::error::Result;
+use crate::key_guard::INSECURE_KEYS;
+use crate::utils::{config_dir, CryptoUtils};
+use std::path::{Path, PathBuf};
+
+/// Keystore directory under the config directory
+pub const KEYSTORE_DIR: &str = "keys";
+
+/// A locally available key, by name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalKey {
+    /// Keystore file name, or `dev:<name>` for a well-known dev key
+    pub name: String,
+    /// Uncompressed public key, lowercase hex
+    pub public_key: String,
+}
+
+/// Uncompressed public key of a hex private key, lowercase hex
+pub fn public_key_of(private_key: &str) -> Result<String> {
+    let key = private_key.trim();
+    let key = key.strip_prefix("0x").unwrap_or(key);
+    let secret_key = CryptoUtils::decode_private_key(key)?;
+    let public_key = CryptoUtils::derive_public_key(&secret_key);
+    Ok(CryptoUtils::serialize_public_key(&public_key, false))
+}
+
+/// `--keystore`, else `keys` in the config directory
+pub fn keystore_dir(explicit: Option<&Path>) -> Option<PathBuf> {
+    explicit
+        .map(Path::to_path_buf)
+        .or_else(|| config_dir().map(|dir| dir.join(KEYSTORE_DIR)))
+}
+
+/// Keys in `dir`, one per file holding a hex private key, sorted by name
+///
+/// Files that are not private keys (public keys, notes, unreadable files)
+/// are skipped, and a missing directory has no keys.
+pub fn read_keystore(dir: &Path) -> Vec<LocalKey> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut keys: Vec<LocalKey> = entries
+        .flatten()
+        .filter(|entry| entry.path().is_file())
+        .filter_map(|entry| {
+            let contents = std::fs::read_to_string(entry.path()).ok()?;
+            let public_key = public_key_of(&contents).ok()?;
+            Some(LocalKey {
+                name: entry.file_name().to_string_lossy().into_owned(),
+                public_key,
+            })
+        })
+        .collect();
+    keys.sort_by(|a, b| a.name.cmp(&b.name));
+    keys
+}
+
+/// The dev shard's well-known keys, named `dev:<name>`
+pub fn dev_keys() -> Vec<LocalKey> {
+    INSECURE_KEYS
+        .iter()
+        .filter_map(|(name, private_key)| {
+            Some(LocalKey {
+                name: format!("dev:{}", name),
+                public_key: public_key_of(private_key).ok()?,
+            })
+        })
+        .collect()
+}
+
+/// Local keys whose public key is in `active`, in `local`'s order
+pub fn active_matches<'a>(active: &[String], local: &'a [LocalKey]) -> Vec<&'a LocalKey> {
+    local
+        .iter()
+        .filter(|key| {
+            active
+                .iter()
+                .any(|validator| validator.eq_ignore_ascii_case(&key.public_key))
+        })
+        .collect()
+}
+
+/// Why the node would not propose, naming the key's public identity
+pub fn not_active_message(public_key: &str, host: &str) -> String {
+    format!(
+        "This key's validator identity {} is not in the active validator set on {}",
+        public_key, host
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(name: &str, public_key: &str) -> LocalKey {
+        LocalKey {
+            name: name.to_string(),
+            public_key: public_key.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_active_matches() {
+        let local = [key("a.key", "04aa"), key("b.key", "04bb"), key("c", "04cc")];
+        let active = ["04CC".to_string(), "04aa".to_string(), "04dd".to_string()];
+        let names: Vec<_> = active_matches(&active, &local)
+            .iter()
+            .map(|k| k.name.as_str())
+            .collect();
+        assert_eq!(names, ["a.key", "c"]);
+        assert!(active_matches(&[], &local).is_empty());
+        assert!(active_matches(&active, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_dev_keys_derive_public_keys() {
+        let keys = dev_keys();
+        assert_eq!(keys.len(), INSECURE_KEYS.len());
+        assert_eq!(keys[0].name, "dev:bootstrap");
+        for key in &keys {
+            assert_eq!(key.public_key.len(), 130, "{}", key.name);
+            assert!(key.public_key.starts_with("04"), "{}", key.name);
+        }
+    }
+
+    #[test]
+    fn test_read_keystore_skips_other_files() {
+        let dir = std::env::temp_dir().join(format!("node_cli_keystore_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let private_key = INSECURE_KEYS[1].1;
+        let public_key = public_key_of(private_key).unwrap();
+        std::fs::write(dir.join("validator.key"), format!("{}\n", private_key)).unwrap();
+        std::fs::write(dir.join("validator.pub"), &public_key).unwrap();
+        std::fs::write(dir.join("notes.txt"), "not a key").unwrap();
+
+        let keys = read_keystore(&dir);
+        assert_eq!(keys, [key("validator.key", &public_key)]);
+        // Nothing but the name and public key is kept
+        assert!(!format!("{:?}", keys).contains(private_key));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(read_keystore(&dir).is_empty());
+    }
+}
@@ -0,0 +1,464 @@
+//! Decision walk behind `validator-status --explain`
+//!
+//! The command gathers evidence from the node (bonds, active set, PoS
+//! parameters, recent block senders, the node's own status) into a
+//! [`StatusEvidence`]; [`explain`] turns it into a list of checks and a
+//! one-sentence conclusion without touching the network, so every branch can
+//! be tested in isolation.
+//!
+//! Activation timing assumes the PoS rule that a bond becomes active at the
+//! first epoch boundary at least a quarantine length after the bonding block.
+
+/// Everything the explanation is derived from
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct StatusEvidence {
+    /// Block the bonds and active set were read at
+    pub reference_block: i64,
+    /// Stake in the bonds map, `None` when the key is not bonded
+    pub stake: Option<i64>,
+    pub active: bool,
+    pub quarantine_length: i64,
+    /// Epoch length in blocks, if the node reported one
+    pub epoch_length: Option<i64>,
+    /// First block in the scanned window whose bonds include the key, when the
+    /// window also shows the key unbonded before it
+    pub bonded_at: Option<i64>,
+    /// Recent main-chain blocks scanned for the key as sender
+    pub recent_blocks: usize,
+    /// How many of those the key proposed
+    pub proposed: usize,
+    /// Height of the newest block the key proposed in the window
+    pub last_proposed: Option<i64>,
+    /// `isValidator` from the queried node's `/api/status`
+    pub node_is_validator: Option<bool>,
+}
+
+/// Outcome of one check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckResult {
+    Pass,
+    Fail,
+    /// Could not be determined from the available data
+    Unknown,
+}
+
+impl CheckResult {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Self::Pass => "✓",
+            Self::Fail => "✗",
+            Self::Unknown => "?",
+        }
+    }
+}
+
+/// One step of the walk, with the numbers it used
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub result: CheckResult,
+    pub question: String,
+    pub evidence: String,
+}
+
+impl Check {
+    fn new(result: CheckResult, question: &str, evidence: String) -> Self {
+        Self {
+            result,
+            question: question.to_string(),
+            evidence,
+        }
+    }
+}
+
+/// Where a bonded-but-inactive validator stands in quarantine
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuarantineProgress {
+    /// Block at which the bond is expected to become active
+    pub active_at: i64,
+    pub blocks_remaining: i64,
+    /// False when the bonding block is unknown and `active_at` is only the
+    /// next epoch boundary, i.e. the earliest possible activation
+    pub exact: bool,
+}
+
+/// First epoch boundary at or after `block`
+pub(crate) fn epoch_boundary_from(block: i64, epoch_length: i64) -> i64 {
+    (block + epoch_length - 1).div_euclid(epoch_length) * epoch_length
+}
+
+/// Expected activation of a bond, from the bonding block if known
+pub fn quarantine_progress(evidence: &StatusEvidence) -> Option<QuarantineProgress> {
+    let epoch_length = evidence.epoch_length.filter(|&length| length > 0)?;
+    let current = evidence.reference_block;
+    let (active_at, exact) = match evidence.bonded_at {
+        Some(bonded_at) => (
+            epoch_boundary_from(bonded_at + evidence.quarantine_length, epoch_length),
+            true,
+        ),
+        None => (epoch_boundary_from(current + 1, epoch_length), false),
+    };
+    Some(QuarantineProgress {
+        active_at,
+        blocks_remaining: (active_at - current).max(0),
+        exact,
+    })
+}
+
+/// The checks, conclusion and suggested next step
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Explanation {
+    pub checks: Vec<Check>,
+    pub conclusion: String,
+    pub next_action: String,
+}
+
+/// Walk the decision tree over gathered evidence
+pub fn explain(evidence: &StatusEvidence) -> Explanation {
+    let mut checks = Vec::new();
+
+    let Some(stake) = evidence.stake else {
+        checks.push(Check::new(
+            CheckResult::Fail,
+            "Is the key bonded?",
+            format!("not in the bonds map at block {}", evidence.reference_block),
+        ));
+        return Explanation {
+            checks,
+            conclusion: "The key is not bonded, so it cannot propose blocks.".to_string(),
+            next_action: "Bond it with `bond-validator --stake <AMOUNT>` using this key."
+                .to_string(),
+        };
+    };
+    checks.push(Check::new(
+        CheckResult::Pass,
+        "Is the key bonded?",
+        format!("stake {} at block {}", stake, evidence.reference_block),
+    ));
+
+    if !evidence.active {
+        checks.push(Check::new(
+            CheckResult::Fail,
+            "Is it in the active set?",
+            format!("not active at block {}", evidence.reference_block),
+        ));
+        let progress = quarantine_progress(evidence);
+        let bonded_at = evidence
+            .bonded_at
+            .map_or("unknown (before the scanned window)".to_string(), |b| {
+                b.to_string()
+            });
+        let (result, detail, conclusion) = match progress {
+            Some(p) if p.exact && p.blocks_remaining == 0 => (
+                CheckResult::Fail,
+                format!(
+                    "bonded at block {}, quarantine {} blocks, expected active from block {}, now {}",
+                    bonded_at, evidence.quarantine_length, p.active_at, evidence.reference_block
+                ),
+                "The bond should already be active but is not; the active set may lag by an epoch or the bond was changed.".to_string(),
+            ),
+            Some(p) => (
+                CheckResult::Unknown,
+                format!(
+                    "bonded at block {}, quarantine {} blocks, epoch length {}, {} block {} ({} blocks away)",
+                    bonded_at,
+                    evidence.quarantine_length,
+                    evidence.epoch_length.unwrap_or_default(),
+                    if p.exact { "active at" } else { "next epoch boundary at" },
+                    p.active_at,
+                    p.blocks_remaining
+                ),
+                if p.exact {
+                    format!(
+                        "The key is bonded but still in quarantine and should become active at block {}.",
+                        p.active_at
+                    )
+                } else {
+                    format!(
+                        "The key is bonded but still in quarantine; it can become active at block {} at the earliest.",
+                        p.active_at
+                    )
+                },
+            ),
+            None => (
+                CheckResult::Unknown,
+                format!(
+                    "bonded at block {}, quarantine {} blocks, epoch length unknown",
+                    bonded_at, evidence.quarantine_length
+                ),
+                "The key is bonded but still in quarantine.".to_string(),
+            ),
+        };
+        checks.push(Check::new(result, "How far through quarantine?", detail));
+        let next_action = if result == CheckResult::Fail {
+            "Re-check after the next epoch boundary; if still inactive, compare `bonds` on several nodes."
+        } else {
+            "Wait for the epoch transition; no action is needed."
+        };
+        return Explanation {
+            checks,
+            conclusion,
+            next_action: next_action.to_string(),
+        };
+    }
+    checks.push(Check::new(
+        CheckResult::Pass,
+        "Is it in the active set?",
+        format!("active at block {}", evidence.reference_block),
+    ));
+
+    let proposing = evidence.proposed > 0;
+    checks.push(Check::new(
+        if evidence.recent_blocks == 0 {
+            CheckResult::Unknown
+        } else if proposing {
+            CheckResult::Pass
+        } else {
+            CheckResult::Fail
+        },
+        "Has it proposed recently?",
+        match evidence.last_proposed {
+            Some(last) => format!(
+                "sender of {} of the last {} blocks, most recently block {}",
+                evidence.proposed, evidence.recent_blocks, last
+            ),
+            None => format!(
+                "sender of none of the last {} blocks",
+                evidence.recent_blocks
+            ),
+        },
+    ));
+
+    checks.push(Check::new(
+        match evidence.node_is_validator {
+            Some(true) => CheckResult::Pass,
+            Some(false) => CheckResult::Fail,
+            None => CheckResult::Unknown,
+        },
+        "Does the queried node run as a validator?",
+        match evidence.node_is_validator {
+            Some(true) => {
+                "isValidator is true; the node does not report which key it signs with".to_string()
+            }
+            Some(false) => "isValidator is false in /api/status".to_string(),
+            None => "the node did not report isValidator".to_string(),
+        },
+    ));
+
+    let (conclusion, next_action) = if proposing || evidence.recent_blocks == 0 {
+        (
+            "The validator is bonded, active and proposing.".to_string(),
+            "Nothing to do.".to_string(),
+        )
+    } else if evidence.node_is_validator == Some(false) {
+        (
+            "The key is active but the node queried is not running as a validator, and the key proposed none of the recent blocks.".to_string(),
+            "Point --host at the node configured with this validator key, or start that node with its validator key.".to_string(),
+        )
+    } else {
+        (
+            format!(
+                "The key is active but proposed none of the last {} blocks.",
+                evidence.recent_blocks
+            ),
+            "Check that the node holding this key is running, synced and has heartbeat or proposing enabled.".to_string(),
+        )
+    };
+    Explanation {
+        checks,
+        conclusion,
+        next_action,
+    }
+}
+
+/// Bonding block of `public_key` from per-block bonds in ascending height order
+///
+/// Known only when the window shows the key unbonded and then bonded; a key
+/// bonded throughout the window was bonded before it.
+pub fn bonded_at<'a>(
+    public_key: &str,
+    blocks: impl IntoIterator<Item = (i64, &'a [String])>,
+) -> Option<i64> {
+    let mut seen_unbonded = false;
+    let mut bonded_at = None;
+    for (number, bonds) in blocks {
+        if bonds.iter().any(|validator| validator == public_key) {
+            if seen_unbonded && bonded_at.is_none() {
+                bonded_at = Some(number);
+            }
+        } else {
+            seen_unbonded = true;
+            bonded_at = None;
+        }
+    }
+    bonded_at
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn active() -> StatusEvidence {
+        StatusEvidence {
+            reference_block: 500,
+            stake: Some(1000),
+            active: true,
+            quarantine_length: 50,
+            epoch_length: Some(100),
+            recent_blocks: 50,
+            proposed: 17,
+            last_proposed: Some(498),
+            node_is_validator: Some(true),
+            ..Default::default()
+        }
+    }
+
+    fn results(explanation: &Explanation) -> Vec<CheckResult> {
+        explanation.checks.iter().map(|c| c.result).collect()
+    }
+
+    #[test]
+    fn test_not_bonded_stops_at_first_check() {
+        let evidence = StatusEvidence {
+            stake: None,
+            ..active()
+        };
+        let explanation = explain(&evidence);
+        assert_eq!(results(&explanation), [CheckResult::Fail]);
+        assert!(explanation.conclusion.contains("not bonded"));
+        assert!(explanation.next_action.contains("bond-validator"));
+    }
+
+    #[test]
+    fn test_quarantine_with_known_bonding_block() {
+        let evidence = StatusEvidence {
+            active: false,
+            bonded_at: Some(470),
+            ..active()
+        };
+        // 470 + 50 = 520, next boundary 600
+        let progress = quarantine_progress(&evidence).unwrap();
+        assert_eq!(
+            progress,
+            QuarantineProgress {
+                active_at: 600,
+                blocks_remaining: 100,
+                exact: true
+            }
+        );
+        let explanation = explain(&evidence);
+        assert_eq!(
+            results(&explanation),
+            [CheckResult::Pass, CheckResult::Fail, CheckResult::Unknown]
+        );
+        assert!(explanation.conclusion.contains("block 600"));
+        assert!(explanation.checks[2].evidence.contains("100 blocks away"));
+    }
+
+    #[test]
+    fn test_quarantine_with_unknown_bonding_block_gives_earliest() {
+        let evidence = StatusEvidence {
+            active: false,
+            ..active()
+        };
+        let progress = quarantine_progress(&evidence).unwrap();
+        assert_eq!((progress.active_at, progress.exact), (600, false));
+        assert!(explain(&evidence).conclusion.contains("at the earliest"));
+
+        let no_epoch = StatusEvidence {
+            epoch_length: None,
+            ..evidence
+        };
+        assert!(quarantine_progress(&no_epoch).is_none());
+        assert!(explain(&no_epoch).checks[2]
+            .evidence
+            .contains("epoch length unknown"));
+    }
+
+    #[test]
+    fn test_overdue_activation_is_flagged() {
+        let evidence = StatusEvidence {
+            active: false,
+            bonded_at: Some(300),
+            ..active()
+        };
+        let explanation = explain(&evidence);
+        assert_eq!(explanation.checks[2].result, CheckResult::Fail);
+        assert!(explanation.conclusion.contains("should already be active"));
+    }
+
+    #[test]
+    fn test_active_and_proposing() {
+        let explanation = explain(&active());
+        assert_eq!(results(&explanation), [CheckResult::Pass; 4]);
+        assert!(explanation.checks[2].evidence.contains("17 of the last 50"));
+        assert_eq!(explanation.next_action, "Nothing to do.");
+    }
+
+    #[test]
+    fn test_active_but_silent_on_non_validator_node() {
+        let evidence = StatusEvidence {
+            proposed: 0,
+            last_proposed: None,
+            node_is_validator: Some(false),
+            ..active()
+        };
+        let explanation = explain(&evidence);
+        assert_eq!(
+            results(&explanation),
+            [
+                CheckResult::Pass,
+                CheckResult::Pass,
+                CheckResult::Fail,
+                CheckResult::Fail
+            ]
+        );
+        assert!(explanation.next_action.contains("--host"));
+    }
+
+    #[test]
+    fn test_active_but_silent_on_validator_node() {
+        let evidence = StatusEvidence {
+            proposed: 0,
+            last_proposed: None,
+            node_is_validator: None,
+            ..active()
+        };
+        let explanation = explain(&evidence);
+        assert_eq!(explanation.checks[3].result, CheckResult::Unknown);
+        assert!(explanation
+            .conclusion
+            .contains("none of the last 50 blocks"));
+        assert!(explanation.next_action.contains("synced"));
+    }
+
+    #[test]
+    fn test_bonded_at_needs_a_transition() {
+        let key = "04aa".to_string();
+        let without: Vec<String> = vec!["04bb".to_string()];
+        let with: Vec<String> = vec!["04bb".to_string(), key.clone()];
+        let blocks = [
+            (10, without.as_slice()),
+            (11, without.as_slice()),
+            (12, with.as_slice()),
+            (13, with.as_slice()),
+        ];
+        assert_eq!(bonded_at(&key, blocks), Some(12));
+        // Bonded throughout the window: bonded before it
+        assert_eq!(
+            bonded_at(&key, [(12, with.as_slice()), (13, with.as_slice())]),
+            None
+        );
+        // Unbonded again at the end: not currently bonded from any block
+        assert_eq!(
+            bonded_at(
+                &key,
+                [
+                    (11, without.as_slice()),
+                    (12, with.as_slice()),
+                    (13, without.as_slice())
+                ]
+            ),
+            None
+        );
+    }
+}
@@ -7,10 +7,17 @@
 //!
 //! - 1 token = 100,000,000 dust
 //! - All amounts in this module are in dust unless otherwise specified
+//! - Token amounts typed by the user go through [`rev_to_dust`], which is exact
+
+use std::fmt;
+use std::str::FromStr;
 
 /// Token to dust conversion factor (1 token = 100,000,000 dust)
 pub const DUST_FACTOR: u64 = 100_000_000;
 
+/// Decimal places of a token amount; one dust is `0.00000001` tokens
+pub const DUST_DECIMALS: usize = 8;
+
 /// Result of a vault transfer operation
 #[derive(Debug, Clone)]
 pub struct TransferResult {
@@ -40,6 +47,7 @@ impl TransferResult {
 /// * `from_address` - Sender's vault address (1111...)
 /// * `to_address` - Recipient's vault address (1111...)
 /// * `amount_dust` - Amount in dust (1 token = 100,000,000 dust)
+#[deprecated(note = "skips the contract check; use contract_templates::transfer_contract")]
 pub fn build_transfer_rholang(from_address: &str, to_address: &str, amount_dust: u64) -> String {
     format!(
         r#"new
@@ -70,6 +78,57 @@ in {{
     )
 }
 
+/// Build Rholang code that transfers `amount_dust` to each of `to_addresses` in one deploy
+///
+/// The deployer's vault and auth key are looked up once; each recipient's
+/// vault is found or created before its transfer, as in
+/// [`build_transfer_rholang`].
+pub fn build_multi_transfer_rholang(
+    from_address: &str,
+    to_addresses: &[&str],
+    amount_dust: u64,
+) -> String {
+    let transfers: Vec<String> = to_addresses
+        .iter()
+        .map(|to_address| {
+            format!(
+                r#" new toVaultCh in {{
+ @SystemVault!("findOrCreate", "{to_address}", *toVaultCh) |
+ for (@(true, _) <- toVaultCh) {{
+ @vault!("transfer", "{to_address}", {amount_dust}, *key, *resultCh)
+ }} |
+ for (@(false, errorMsg) <- toVaultCh) {{
+ resultCh!(("error", "Recipient vault error", errorMsg))
+ }}
+ }}"#
+            )
+        })
+        .collect();
+    format!(
+        r#"new
+ deployerId(`rho:system:deployerId`),
+ rl(`rho:registry:lookup`),
+ systemVaultCh,
+ vaultCh,
+ systemVaultKeyCh,
+ resultCh
+in {{
+ rl!(`rho:vault:system`, *systemVaultCh) |
+ for (@(_, SystemVault) <- systemVaultCh) {{
+ @SystemVault!("findOrCreate", "{from_address}", *vaultCh) |
+ @SystemVault!("deployerAuthKey", *deployerId, *systemVaultKeyCh) |
+ for (@(true, vault) <- vaultCh; key <- systemVaultKeyCh) {{
+{transfers}
+ }} |
+ for (@(false, errorMsg) <- vaultCh) {{
+ resultCh!(("error", "Sender vault error", errorMsg))
+ }}
+ }}
+}}"#,
+        transfers = transfers.join(" |\n")
+    )
+}
+
 /// Build Rholang code to query vault balance
 ///
 /// # Arguments
@@ -95,6 +154,62 @@ pub fn build_balance_query(address: &str) -> String {
     )
 }
 
+/// Row reported on the deploy ID when the sender's vault cannot be opened
+pub const SENDER_VAULT_ROW: i64 = -1;
+
+/// Build Rholang code that pays each `(to_address, amount_dust)` in one deploy
+///
+/// Like [`build_multi_transfer_rholang`], but with an amount per recipient,
+/// and each transfer's result is sent to the deploy ID as `(row, result)`,
+/// `row` being the payout's index. `result` is the vault's `(true, Nil)` or
+/// `(false, reason)`; a sender vault that cannot be opened is reported once,
+/// on row [`SENDER_VAULT_ROW`].
+pub fn build_payout_rholang(from_address: &str, payouts: &[(&str, u64)]) -> String {
+    let transfers: Vec<String> = payouts
+        .iter()
+        .enumerate()
+        .map(|(row, (to_address, amount_dust))| {
+            format!(
+                r#" new toVaultCh, resultCh in {{
+ @SystemVault!("findOrCreate", "{to_address}", *toVaultCh) |
+ for (@(true, _) <- toVaultCh) {{
+ @vault!("transfer", "{to_address}", {amount_dust}, *key, *resultCh)
+ }} |
+ for (@(false, errorMsg) <- toVaultCh) {{
+ deployId!(({row}, (false, errorMsg)))
+ }} |
+ for (@result <- resultCh) {{
+ deployId!(({row}, result))
+ }}
+ }}"#
+            )
+        })
+        .collect();
+    format!(
+        r#"new
+ deployId(`rho:system:deployId`),
+ deployerId(`rho:system:deployerId`),
+ rl(`rho:registry:lookup`),
+ systemVaultCh,
+ vaultCh,
+ systemVaultKeyCh
+in {{
+ rl!(`rho:vault:system`, *systemVaultCh) |
+ for (@(_, SystemVault) <- systemVaultCh) {{
+ @SystemVault!("findOrCreate", "{from_address}", *vaultCh) |
+ @SystemVault!("deployerAuthKey", *deployerId, *systemVaultKeyCh) |
+ for (@(true, vault) <- vaultCh; key <- systemVaultKeyCh) {{
+{transfers}
+ }} |
+ for (@(false, errorMsg) <- vaultCh) {{
+ deployId!(({SENDER_VAULT_ROW}, (false, errorMsg)))
+ }}
+ }}
+}}"#,
+        transfers = transfers.join(" |\n")
+    )
+}
+
 /// Validate vault address format
 ///
 /// Vault addresses start with "1111" and are base58-encoded.
@@ -111,6 +226,7 @@ pub fn validate_address(address: &str) -> Result<(), String> {
 }
 
 /// Convert token amount to dust
+#[deprecated(note = "goes through f64 and truncates; use rev_to_dust")]
 pub fn tokens_to_dust(tokens: f64) -> u64 {
     (tokens * DUST_FACTOR as f64) as u64
 }
@@ -119,3 +235,158 @@ pub fn tokens_to_dust(tokens: f64) -> u64 {
 pub fn dust_to_tokens(dust: u64) -> f64 {
     dust as f64 / DUST_FACTOR as f64
 }
+
+/// Parse a token amount such as `1`, `0.25` or `1.5` into dust
+///
+/// The conversion is exact. More than eight decimal places is an error
+/// rather than being rounded away (trailing zeros past the eighth are
+/// fine), and an amount beyond `u64::MAX` dust is an error rather than
+/// wrapping.
+pub fn rev_to_dust(amount: &str) -> Result<u64, String> {
+    let amount = amount.trim();
+    let (whole, fraction) = amount.split_once('.').unwrap_or((amount, ""));
+    let digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+    if (whole.is_empty() && fraction.is_empty()) || !digits(whole) || !digits(fraction) {
+        return Err(format!(
+            "'{}' is not a token amount; expected a number such as 1 or 0.25",
+            amount
+        ));
+    }
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.len() > DUST_DECIMALS {
+        return Err(format!(
+            "'{}' has more than {} decimal places; the smallest amount is 0.00000001 \
+             (1 dust). Use --amount-dust for an exact dust amount",
+            amount, DUST_DECIMALS
+        ));
+    }
+    let too_large = || format!("'{}' tokens is more than {} dust", amount, u64::MAX);
+    let whole: u64 = match whole {
+        "" => 0,
+        whole => whole.parse().map_err(|_| too_large())?,
+    };
+    let fraction_dust: u64 = match fraction {
+        "" => 0,
+        fraction => format!("{:0<width$}", fraction, width = DUST_DECIMALS)
+            .parse()
+            .map_err(|_| too_large())?,
+    };
+    whole
+        .checked_mul(DUST_FACTOR)
+        .and_then(|dust| dust.checked_add(fraction_dust))
+        .ok_or_else(too_large)
+}
+
+/// Dust as the shortest exact token amount, e.g. `1`, `0.5` or `0.00000001`
+///
+/// Takes `u128` so sums of balances and amount-plus-fee totals print
+/// without wrapping; unlike [`dust_to_tokens`] it never goes through `f64`.
+pub fn format_rev(dust: u128) -> String {
+    let factor = DUST_FACTOR as u128;
+    let fraction = dust % factor;
+    if fraction == 0 {
+        return (dust / factor).to_string();
+    }
+    let fraction = format!("{:0width$}", fraction, width = DUST_DECIMALS);
+    format!("{}.{}", dust / factor, fraction.trim_end_matches('0'))
+}
+
+/// A token amount given on the command line, held exactly in dust
+///
+/// Parses with [`rev_to_dust`] and displays with [`format_rev`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RevAmount {
+    dust: u64,
+}
+
+impl RevAmount {
+    pub fn from_dust(dust: u64) -> Self {
+        Self { dust }
+    }
+
+    /// Whole tokens, saturating at `u64::MAX` dust
+    pub fn from_tokens(tokens: u64) -> Self {
+        Self::from_dust(tokens.saturating_mul(DUST_FACTOR))
+    }
+
+    pub fn dust(&self) -> u64 {
+        self.dust
+    }
+}
+
+impl FromStr for RevAmount {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        rev_to_dust(s).map(Self::from_dust)
+    }
+}
+
+impl fmt::Display for RevAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&format_rev(self.dust.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rev_to_dust() {
+        assert_eq!(rev_to_dust("0.00000001"), Ok(1));
+        assert_eq!(rev_to_dust("1"), Ok(DUST_FACTOR));
+        assert_eq!(rev_to_dust("0.25"), Ok(25_000_000));
+        assert_eq!(rev_to_dust(" 1.5 "), Ok(150_000_000));
+        assert_eq!(rev_to_dust(".5"), Ok(50_000_000));
+        assert_eq!(rev_to_dust("2."), Ok(200_000_000));
+        assert_eq!(rev_to_dust("1.0000000000"), Ok(DUST_FACTOR));
+
+        let precise = rev_to_dust("1.999999999").unwrap_err();
+        assert!(
+            precise.contains("more than 8 decimal places"),
+            "{}",
+            precise
+        );
+        for bad in ["", ".", "-1", "1e3", "1,5", "abc", "1.2.3"] {
+            assert!(rev_to_dust(bad).is_err(), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn test_rev_to_dust_near_u64_max() {
+        let largest = u64::MAX / DUST_FACTOR;
+        assert_eq!(rev_to_dust(&largest.to_string()), Ok(largest * DUST_FACTOR));
+        assert_eq!(rev_to_dust("184467440737.09551615"), Ok(u64::MAX));
+        assert!(rev_to_dust("184467440737.09551616").is_err());
+        assert!(rev_to_dust(&(largest + 1).to_string()).is_err());
+        assert!(rev_to_dust("99999999999999999999999").is_err());
+    }
+
+    #[test]
+    fn test_payout_term_reports_each_row() {
+        let term = build_payout_rholang("1111from", &[("1111a", 5), ("1111b", 7)]);
+        assert!(term.contains(r#"@vault!("transfer", "1111a", 5, *key, *resultCh)"#));
+        assert!(term.contains(r#"@vault!("transfer", "1111b", 7, *key, *resultCh)"#));
+        assert!(term.contains("deployId!((0, result))"));
+        assert!(term.contains("deployId!((1, result))"));
+        assert!(term.contains("deployId!((-1, (false, errorMsg)))"));
+        assert_eq!(term.matches("findOrCreate").count(), 3);
+    }
+
+    #[test]
+    fn test_rev_amount_round_trips() {
+        for (text, shown) in [
+            ("1", "1"),
+            ("0.50", "0.5"),
+            ("0.00000001", "0.00000001"),
+            ("1234.5678", "1234.5678"),
+        ] {
+            let amount: RevAmount = text.parse().unwrap();
+            assert_eq!(amount.to_string(), shown);
+            assert_eq!(shown.parse::<RevAmount>(), Ok(amount));
+        }
+        assert_eq!(RevAmount::from_tokens(3).dust(), 300_000_000);
+        assert_eq!(RevAmount::from_tokens(u64::MAX).dust(), u64::MAX);
+    }
+}
@@ -0,0 +1,530 @@
+//! Cross-checking finalization on independent nodes
+//!
+//! After the node a deploy was sent to reports its block finalized, each
+//! `--verify-on` node is asked for the same block and the deploy's
+//! finalization state. All of them must have the block, consider it finalized
+//! and agree on its post-state hash; otherwise the outcome is a verification
+//! mismatch naming the nodes that disagreed. A node that cannot be reached
+//! after a few tries leaves the result unverified rather than mismatched.
+
+use crate::error::{NodeCall, NodeCliError, Result};
+use crate::f1r3fly_api::{BlockSummary, DeployDetail};
+use futures_util::future::join_all;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Tries per verification node before it is reported unreachable
+pub const VERIFY_ATTEMPTS: u32 = 3;
+
+/// Pause between tries of an unreachable verification node
+pub const VERIFY_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// A `--verify-on host:httpPort` node
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyTarget {
+    pub host: String,
+    pub http_port: u16,
+}
+
+impl FromStr for VerifyTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (host, port) = s
+            .rsplit_once(':')
+            .ok_or_else(|| format!("expected host:httpPort, got '{}'", s))?;
+        if host.is_empty() {
+            return Err(format!("missing host in '{}'", s));
+        }
+        let http_port = port
+            .parse()
+            .map_err(|_| format!("invalid HTTP port '{}' in '{}'", port, s))?;
+        Ok(Self {
+            host: host.to_string(),
+            http_port,
+        })
+    }
+}
+
+impl fmt::Display for VerifyTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.host, self.http_port)
+    }
+}
+
+/// What one verification node said about the block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeReport {
+    /// The node knows the block
+    Block {
+        finalized: bool,
+        post_state_hash: String,
+    },
+    /// The node answered but does not know the block
+    BlockMissing,
+    /// No answer after retrying
+    Unreachable(String),
+}
+
+/// How a verification node disagreed with the primary
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Disagreement {
+    BlockMissing,
+    NotFinalized,
+    PostStateHash { expected: String, actual: String },
+}
+
+impl fmt::Display for Disagreement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BlockMissing => write!(f, "block not found"),
+            Self::NotFinalized => write!(f, "block not finalized"),
+            Self::PostStateHash { expected, actual } => {
+                write!(f, "post-state hash {} differs from {}", actual, expected)
+            }
+        }
+    }
+}
+
+/// Result of cross-checking on every verification node
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verification {
+    /// No verification nodes were given
+    NotRequested,
+    /// Every node has the block, finalized, with the same post-state hash
+    Verified { nodes: usize },
+    /// The nodes that answered agreed, but these could not be reached
+    Unverified { unreachable: Vec<(String, String)> },
+    /// At least one node disagreed
+    Mismatch {
+        disagreements: Vec<(String, Disagreement)>,
+    },
+}
+
+impl Verification {
+    /// One line for the command output
+    pub fn describe(&self) -> String {
+        match self {
+            Self::NotRequested => "not requested".to_string(),
+            Self::Verified { nodes } => format!("verified on {} node(s)", nodes),
+            Self::Unverified { unreachable } => format!(
+                "unverified ({} unreachable: {})",
+                unreachable.len(),
+                join_pairs(unreachable)
+            ),
+            Self::Mismatch { disagreements } => {
+                let details: Vec<(String, String)> = disagreements
+                    .iter()
+                    .map(|(node, d)| (node.clone(), d.to_string()))
+                    .collect();
+                format!("MISMATCH ({})", join_pairs(&details))
+            }
+        }
+    }
+
+    /// Fail on a mismatch, and on unreachable nodes when verification is required
+    pub fn check(&self, require_verification: bool) -> Result<()> {
+        match self {
+            Self::Mismatch { .. } => Err(NodeCliError::General(format!(
+                "Finalization verification failed: {}",
+                self.describe()
+            ))),
+            Self::Unverified { .. } if require_verification => Err(NodeCliError::General(format!(
+                "Finalization could not be verified: {}. Drop --require-verification to accept it",
+                self.describe()
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn join_pairs(pairs: &[(String, String)]) -> String {
+    pairs
+        .iter()
+        .map(|(node, detail)| format!("{}: {}", node, detail))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Decide whether the verification nodes agree with the primary
+///
+/// `expected_post_state` is the primary's post-state hash for the block. When
+/// the primary's header could not be read, the first node that has the block
+/// is the reference instead. Disagreement outranks unreachable nodes.
+pub fn evaluate(
+    expected_post_state: Option<&str>,
+    reports: &[(VerifyTarget, NodeReport)],
+) -> Verification {
+    if reports.is_empty() {
+        return Verification::NotRequested;
+    }
+
+    let expected = expected_post_state.map(str::to_string).or_else(|| {
+        reports.iter().find_map(|(_, report)| match report {
+            NodeReport::Block {
+                post_state_hash, ..
+            } => Some(post_state_hash.clone()),
+            _ => None,
+        })
+    });
+
+    let mut disagreements = Vec::new();
+    let mut unreachable = Vec::new();
+    for (target, report) in reports {
+        let node = target.to_string();
+        match report {
+            NodeReport::Unreachable(error) => unreachable.push((node, error.clone())),
+            NodeReport::BlockMissing => disagreements.push((node, Disagreement::BlockMissing)),
+            NodeReport::Block {
+                finalized,
+                post_state_hash,
+            } => {
+                if !finalized {
+                    disagreements.push((node.clone(), Disagreement::NotFinalized));
+                }
+                if let Some(expected) = &expected {
+                    if !post_state_hash.eq_ignore_ascii_case(expected) {
+                        disagreements.push((
+                            node,
+                            Disagreement::PostStateHash {
+                                expected: expected.clone(),
+                                actual: post_state_hash.clone(),
+                            },
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    if !disagreements.is_empty() {
+        Verification::Mismatch { disagreements }
+    } else if !unreachable.is_empty() {
+        Verification::Unverified { unreachable }
+    } else {
+        Verification::Verified {
+            nodes: reports.len(),
+        }
+    }
+}
+
+/// Read-only block and deploy queries against one verification node
+#[derive(Debug, Clone)]
+pub struct VerificationNode {
+    target: VerifyTarget,
+    client: reqwest::Client,
+}
+
+impl VerificationNode {
+    pub fn new(target: VerifyTarget) -> Self {
+        Self {
+            target,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    async fn get(&self, path: &str) -> Result<Option<serde_json::Value>> {
+        let url = format!("http://{}{}", self.target, path);
+        let call = NodeCall::http(&url);
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| call.fail(e))?;
+        let status = response.status();
+        if status.is_client_error() {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            return Err(call.fail(NodeCliError::network_http_error(status.as_u16(), &text)));
+        }
+        Ok(Some(response.json().await.map_err(|e| call.fail(e))?))
+    }
+
+    /// One query of the block header and the deploy's finalization state
+    ///
+    /// The deploy counts as finalized only when this node places it in the
+    /// same block and reports that block finalized.
+    pub async fn report(&self, deploy_id: &str, block_hash: &str) -> Result<NodeReport> {
+        let Some(block) = self.get(&format!("/api/block/{}", block_hash)).await? else {
+            return Ok(NodeReport::BlockMissing);
+        };
+        let Some(summary) = BlockSummary::from_json(&block) else {
+            return Ok(NodeReport::BlockMissing);
+        };
+        let finalized = match self.get(&format!("/api/deploy/{}", deploy_id)).await? {
+            Some(json) => serde_json::from_value::<DeployDetail>(json)
+                .map(|d| d.is_finalized && d.block_hash.eq_ignore_ascii_case(block_hash))
+                .unwrap_or(false),
+            None => false,
+        };
+        Ok(NodeReport::Block {
+            finalized,
+            post_state_hash: summary.post_state_hash,
+        })
+    }
+
+    /// [`report`](Self::report), retried up to `attempts` times on network errors
+    pub async fn report_with_retry(
+        &self,
+        deploy_id: &str,
+        block_hash: &str,
+        attempts: u32,
+        retry_delay: Duration,
+    ) -> NodeReport {
+        let mut last_error = String::new();
+        for attempt in 1..=attempts.max(1) {
+            match self.report(deploy_id, block_hash).await {
+                Ok(report) => return report,
+                Err(e) => {
+                    tracing::warn!(node = %self.target, attempt, "Verification query failed: {}", e);
+                    last_error = e.to_string();
+                }
+            }
+            if attempt < attempts {
+                tokio::time::sleep(retry_delay).await;
+            }
+        }
+        NodeReport::Unreachable(last_error)
+    }
+}
+
+/// Query every verification node concurrently
+pub async fn collect_reports(
+    targets: &[VerifyTarget],
+    deploy_id: &str,
+    block_hash: &str,
+    attempts: u32,
+    retry_delay: Duration,
+) -> Vec<(VerifyTarget, NodeReport)> {
+    let queries = targets.iter().map(|target| async move {
+        let node = VerificationNode::new(target.clone());
+        let report = node
+            .report_with_retry(deploy_id, block_hash, attempts, retry_delay)
+            .await;
+        (target.clone(), report)
+    });
+    join_all(queries).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::spawn_http_node;
+
+    const BLOCK: &str = "b1";
+    const DEPLOY: &str = "d1";
+
+    fn target(name: &str) -> VerifyTarget {
+        VerifyTarget {
+            host: name.to_string(),
+            http_port: 40453,
+        }
+    }
+
+    fn block(finalized: bool, post_state_hash: &str) -> NodeReport {
+        NodeReport::Block {
+            finalized,
+            post_state_hash: post_state_hash.to_string(),
+        }
+    }
+
+    fn unreachable() -> NodeReport {
+        NodeReport::Unreachable("connection refused".to_string())
+    }
+
+    #[test]
+    fn test_parse_target() {
+        assert_eq!("obs:40453".parse::<VerifyTarget>().unwrap(), target("obs"));
+        assert_eq!("[::1]:40453".parse::<VerifyTarget>().unwrap().host, "[::1]");
+        assert!("obs".parse::<VerifyTarget>().is_err());
+        assert!(":40453".parse::<VerifyTarget>().is_err());
+        assert!("obs:http".parse::<VerifyTarget>().is_err());
+    }
+
+    #[test]
+    fn test_no_targets_is_not_requested() {
+        assert_eq!(evaluate(Some("s1"), &[]), Verification::NotRequested);
+        assert!(Verification::NotRequested.check(true).is_ok());
+    }
+
+    #[test]
+    fn test_all_agree_is_verified() {
+        let reports = [
+            (target("a"), block(true, "s1")),
+            (target("b"), block(true, "S1")),
+        ];
+        let verification = evaluate(Some("s1"), &reports);
+        assert_eq!(verification, Verification::Verified { nodes: 2 });
+        assert!(verification.check(true).is_ok());
+    }
+
+    #[test]
+    fn test_each_disagreement_is_a_mismatch() {
+        let cases = [
+            (block(false, "s1"), vec![Disagreement::NotFinalized]),
+            (NodeReport::BlockMissing, vec![Disagreement::BlockMissing]),
+            (
+                block(true, "s2"),
+                vec![Disagreement::PostStateHash {
+                    expected: "s1".to_string(),
+                    actual: "s2".to_string(),
+                }],
+            ),
+            (
+                block(false, "s2"),
+                vec![
+                    Disagreement::NotFinalized,
+                    Disagreement::PostStateHash {
+                        expected: "s1".to_string(),
+                        actual: "s2".to_string(),
+                    },
+                ],
+            ),
+        ];
+        for (report, expected) in cases {
+            let reports = [(target("a"), block(true, "s1")), (target("b"), report)];
+            let verification = evaluate(Some("s1"), &reports);
+            let disagreements: Vec<(String, Disagreement)> = expected
+                .into_iter()
+                .map(|d| ("b:40453".to_string(), d))
+                .collect();
+            assert_eq!(verification, Verification::Mismatch { disagreements });
+            // A mismatch fails whether or not verification is required
+            assert!(verification.check(false).is_err());
+        }
+    }
+
+    #[test]
+    fn test_unreachable_is_unverified() {
+        let reports = [
+            (target("a"), block(true, "s1")),
+            (target("b"), unreachable()),
+        ];
+        let verification = evaluate(Some("s1"), &reports);
+        assert_eq!(
+            verification,
+            Verification::Unverified {
+                unreachable: vec![("b:40453".to_string(), "connection refused".to_string())]
+            }
+        );
+        assert!(verification.check(false).is_ok());
+        let err = verification.check(true).unwrap_err().to_string();
+        assert!(err.contains("b:40453"), "{}", err);
+
+        let all_down = [(target("a"), unreachable())];
+        assert!(matches!(
+            evaluate(Some("s1"), &all_down),
+            Verification::Unverified { .. }
+        ));
+    }
+
+    #[test]
+    fn test_mismatch_outranks_unreachable() {
+        let reports = [
+            (target("a"), unreachable()),
+            (target("b"), NodeReport::BlockMissing),
+        ];
+        assert_eq!(
+            evaluate(Some("s1"), &reports),
+            Verification::Mismatch {
+                disagreements: vec![("b:40453".to_string(), Disagreement::BlockMissing)]
+            }
+        );
+    }
+
+    #[test]
+    fn test_without_primary_hash_first_block_is_reference() {
+        let agree = [
+            (target("a"), block(true, "s1")),
+            (target("b"), block(true, "s1")),
+        ];
+        assert_eq!(evaluate(None, &agree), Verification::Verified { nodes: 2 });
+
+        let differ = [
+            (target("a"), NodeReport::BlockMissing),
+            (target("b"), block(true, "s1")),
+            (target("c"), block(true, "s2")),
+        ];
+        match evaluate(None, &differ) {
+            Verification::Mismatch { disagreements } => assert_eq!(
+                disagreements,
+                vec![
+                    ("a:40453".to_string(), Disagreement::BlockMissing),
+                    (
+                        "c:40453".to_string(),
+                        Disagreement::PostStateHash {
+                            expected: "s1".to_string(),
+                            actual: "s2".to_string(),
+                        }
+                    ),
+                ]
+            ),
+            other => panic!("unexpected verification: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_describe() {
+        let mismatch = Verification::Mismatch {
+            disagreements: vec![("b:1".to_string(), Disagreement::NotFinalized)],
+        };
+        assert_eq!(mismatch.describe(), "MISMATCH (b:1: block not finalized)");
+        assert_eq!(
+            Verification::Verified { nodes: 2 }.describe(),
+            "verified on 2 node(s)"
+        );
+    }
+
+    fn node_with(
+        post_state_hash: &'static str,
+        finalized: bool,
+    ) -> impl Fn(&str, &str) -> (&'static str, String) + Send + Sync + 'static {
+        move |line, _body| {
+            if line.contains("/api/block/b1") {
+                (
+                    "200 OK",
+                    format!(
+                        r#"{{"blockInfo":{{"blockHash":"b1","blockNumber":7,"postStateHash":"{}"}}}}"#,
+                        post_state_hash
+                    ),
+                )
+            } else if line.contains("/api/deploy/d1") {
+                (
+                    "200 OK",
+                    format!(
+                        r#"{{"deployId":"d1","blockHash":"b1","blockNumber":7,"timestamp":0,"cost":1,"errored":false,"isFinalized":{}}}"#,
+                        finalized
+                    ),
+                )
+            } else {
+                ("404 Not Found", "{}".to_string())
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_collect_reports_from_mock_nodes() {
+        let good = spawn_http_node(node_with("s1", true)).await;
+        let lagging = spawn_http_node(node_with("s1", false)).await;
+        let empty = spawn_http_node(|_: &str, _: &str| ("404 Not Found", "{}".to_string())).await;
+        let down = {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            listener.local_addr().unwrap().port()
+        };
+        let targets: Vec<VerifyTarget> = [good, lagging, empty, down]
+            .iter()
+            .map(|port| format!("127.0.0.1:{}", port).parse().unwrap())
+            .collect();
+
+        let reports = collect_reports(&targets, DEPLOY, BLOCK, 2, Duration::from_millis(5)).await;
+        let reports: Vec<NodeReport> = reports.into_iter().map(|(_, r)| r).collect();
+        assert_eq!(reports[0], block(true, "s1"));
+        assert_eq!(reports[1], block(false, "s1"));
+        assert_eq!(reports[2], NodeReport::BlockMissing);
+        assert!(matches!(reports[3], NodeReport::Unreachable(_)));
+    }
+}
@@ -0,0 +1,436 @@
+//! Wallet activity matching for `watch-wallet`
+//!
+//! A watch list holds deployer public keys and vault addresses. Each key also
+//! watches its own vault address, and each deploy's deployer key is mapped to
+//! its vault address, so either form of an identity matches both directions.
+//!
+//! Deploys are matched as outgoing by deployer. Incoming transfers are found by
+//! scanning the deploy term for the `@vault!("transfer", "<address>", <amount>`
+//! call made by the transfer contracts this client generates. Transfers made by
+//! other contracts (different channel names, addresses built at runtime) are
+//! not recognised and will be missed.
+
+use crate::error::{NodeCliError, Result};
+use crate::export::ChainSource;
+use crate::f1r3fly_api::{BlockDeploy, BlockDetail};
+use crate::utils::CryptoUtils;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+/// Start of the vault transfer call in generated transfer contracts
+const TRANSFER_CALL: &str = "@vault!(\"transfer\", \"";
+
+/// A key or address given on the command line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WatchTarget {
+    /// Deployer public key as uncompressed hex (compressed keys are expanded)
+    PublicKey(String),
+    /// Vault address
+    Address(String),
+}
+
+impl FromStr for WatchTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.starts_with("1111") {
+            crate::vault::validate_address(s)?;
+            return Ok(WatchTarget::Address(s.to_string()));
+        }
+        s.parse::<secp256k1::PublicKey>()
+            .map(|key| WatchTarget::PublicKey(CryptoUtils::serialize_public_key(&key, false)))
+            .map_err(|_| {
+                format!(
+                    "'{}' is neither a vault address (1111...) nor a hex public key",
+                    s
+                )
+            })
+    }
+}
+
+/// Which side of a deploy the watched wallet is on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    /// The deploy was signed by a watched key
+    Outgoing,
+    /// The deploy transfers to a watched address
+    Incoming,
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Direction::Outgoing => write!(f, "outgoing"),
+            Direction::Incoming => write!(f, "incoming"),
+        }
+    }
+}
+
+/// A deploy that touches a watched wallet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WalletMatch {
+    pub block_hash: String,
+    pub block_number: i64,
+    pub deploy_id: String,
+    pub direction: Direction,
+    /// The watched address: the deployer's for outgoing, the recipient's for incoming
+    pub address: String,
+    /// Transfer amount in dust, when the term is a recognised transfer
+    pub amount: Option<u64>,
+    pub errored: bool,
+}
+
+/// A recognised vault transfer call in a deploy term
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransferCall {
+    pub to_address: String,
+    pub amount: Option<u64>,
+}
+
+/// Transfer calls made by a deploy term, in order of appearance
+pub fn transfer_calls(term: &str) -> Vec<TransferCall> {
+    let mut calls = Vec::new();
+    let mut rest = term;
+    while let Some(start) = rest.find(TRANSFER_CALL) {
+        rest = &rest[start + TRANSFER_CALL.len()..];
+        let Some(end) = rest.find('"') else { break };
+        let to_address = rest[..end].to_string();
+        rest = &rest[end + 1..];
+        let amount = rest
+            .trim_start()
+            .strip_prefix(',')
+            .map(|after| {
+                after
+                    .trim_start()
+                    .chars()
+                    .take_while(|c| c.is_ascii_digit())
+                    .collect::<String>()
+            })
+            .and_then(|digits| digits.parse().ok());
+        calls.push(TransferCall { to_address, amount });
+    }
+    calls
+}
+
+/// The set of watched keys and addresses
+#[derive(Debug, Clone, Default)]
+pub struct WalletWatch {
+    keys: HashSet<String>,
+    addresses: HashSet<String>,
+}
+
+impl WalletWatch {
+    pub fn new(targets: &[WatchTarget]) -> Result<Self> {
+        let mut watch = Self::default();
+        for target in targets {
+            match target {
+                WatchTarget::PublicKey(key) => {
+                    let address = CryptoUtils::generate_vault_address(key).map_err(|e| {
+                        NodeCliError::config_invalid_value("watch", &format!("{}: {}", key, e))
+                    })?;
+                    watch.keys.insert(key.clone());
+                    watch.addresses.insert(address);
+                }
+                WatchTarget::Address(address) => {
+                    watch.addresses.insert(address.clone());
+                }
+            }
+        }
+        Ok(watch)
+    }
+
+    /// Watched vault addresses, including those derived from watched keys
+    pub fn addresses(&self) -> impl Iterator<Item = &str> {
+        self.addresses.iter().map(String::as_str)
+    }
+
+    /// Whether a deploy signed by `deployer` comes from a watched key or address
+    pub fn watches_deployer(&self, deployer: &str) -> bool {
+        self.watched_deployer(deployer).is_some()
+    }
+
+    /// Vault address of a deploy's signer if it is watched
+    fn watched_deployer(&self, deployer: &str) -> Option<String> {
+        let deployer = deployer.to_ascii_lowercase();
+        let address = CryptoUtils::generate_vault_address(&deployer).ok();
+        let watched = self.keys.contains(&deployer)
+            || address
+                .as_ref()
+                .is_some_and(|address| self.addresses.contains(address));
+        watched.then(|| address.unwrap_or(deployer))
+    }
+
+    /// Matches for one deploy in a block, outgoing first
+    pub fn match_deploy(&self, block: &BlockDetail, deploy: &BlockDeploy) -> Vec<WalletMatch> {
+        let calls = transfer_calls(&deploy.term);
+        let matched = |direction, address: String, amount| WalletMatch {
+            block_hash: block.header.block_hash.clone(),
+            block_number: block.header.block_number,
+            deploy_id: deploy.sig.clone(),
+            direction,
+            address,
+            amount,
+            errored: deploy.errored,
+        };
+
+        let mut matches = Vec::new();
+        if let Some(address) = self.watched_deployer(&deploy.deployer) {
+            let amount = calls.iter().filter_map(|c| c.amount).reduce(|a, b| a + b);
+            matches.push(matched(Direction::Outgoing, address, amount));
+        }
+        for call in calls {
+            if self.addresses.contains(&call.to_address) {
+                matches.push(matched(Direction::Incoming, call.to_address, call.amount));
+            }
+        }
+        matches
+    }
+
+    /// Matches for every deploy in a block
+    pub fn match_block(&self, block: &BlockDetail) -> Vec<WalletMatch> {
+        block
+            .deploys
+            .iter()
+            .flat_map(|deploy| self.match_deploy(block, deploy))
+            .collect()
+    }
+}
+
+/// Match every block at heights `start..=end`, oldest first
+///
+/// Returns the matches and the hashes of the blocks scanned, so a caller that
+/// goes on to follow live events can skip blocks it has already seen.
+pub async fn backfill(
+    source: &ChainSource,
+    watch: &WalletWatch,
+    start: i64,
+    end: i64,
+    chunk_size: i64,
+) -> Result<(Vec<WalletMatch>, HashSet<String>)> {
+    let mut matches = Vec::new();
+    let seen = scan_blocks(source, start, end, chunk_size, |block| {
+        matches.extend(watch.match_block(block))
+    })
+    .await?;
+    Ok((matches, seen))
+}
+
+/// Fetch every block at heights `start..=end` with its deploys and pass it to
+/// `visit`, oldest first; returns the hashes of the blocks visited
+pub async fn scan_blocks(
+    source: &ChainSource,
+    start: i64,
+    end: i64,
+    chunk_size: i64,
+    mut visit: impl FnMut(&BlockDetail),
+) -> Result<HashSet<String>> {
+    let mut seen = HashSet::new();
+    let mut chunk_start = start;
+    while chunk_start <= end {
+        let chunk_end = (chunk_start + chunk_size.max(1) - 1).min(end);
+        for summary in source.blocks_in_range(chunk_start, chunk_end).await? {
+            let block = source.block(&summary.block_hash).await?;
+            visit(&block);
+            seen.insert(summary.block_hash);
+        }
+        chunk_start = chunk_end + 1;
+    }
+    Ok(seen)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key_guard::DEV_PRIVATE_KEY;
+    use crate::test_support::spawn_http_node;
+
+    const OTHER_ADDRESS: &str = "1111AtahZeefej4tvVR6ti9TJtv8yxLebvyDoX4tKnbX9DZGQCExL";
+
+    fn dev_key() -> (String, String) {
+        let secret_key = CryptoUtils::decode_private_key(DEV_PRIVATE_KEY).unwrap();
+        let public_key = CryptoUtils::derive_public_key(&secret_key);
+        let public_key_hex = CryptoUtils::serialize_public_key(&public_key, false);
+        let address = CryptoUtils::generate_vault_address(&public_key_hex).unwrap();
+        (public_key_hex, address)
+    }
+
+    /// A third party whose deploys should never match
+    fn stranger_key() -> String {
+        let (_, public_key) = CryptoUtils::generate_key_pair().unwrap();
+        CryptoUtils::serialize_public_key(&public_key, false)
+    }
+
+    fn deploy(sig: &str, deployer: &str, term: String) -> BlockDeploy {
+        serde_json::from_value(serde_json::json!({
+            "sig": sig,
+            "deployer": deployer,
+            "term": term,
+        }))
+        .unwrap()
+    }
+
+    fn block(number: i64, deploys: Vec<BlockDeploy>) -> BlockDetail {
+        let json = serde_json::json!({
+            "blockInfo": {"blockHash": format!("b{}", number), "blockNumber": number},
+        });
+        let mut block = BlockDetail::from_json(&json).unwrap();
+        block.deploys = deploys;
+        block
+    }
+
+    #[test]
+    fn test_parse_targets() {
+        let (key, address) = dev_key();
+        assert_eq!(
+            key.parse::<WatchTarget>(),
+            Ok(WatchTarget::PublicKey(key.clone()))
+        );
+        assert_eq!(
+            address.parse::<WatchTarget>(),
+            Ok(WatchTarget::Address(address.clone()))
+        );
+        let secret_key = CryptoUtils::decode_private_key(DEV_PRIVATE_KEY).unwrap();
+        let compressed =
+            CryptoUtils::serialize_public_key(&CryptoUtils::derive_public_key(&secret_key), true);
+        assert_eq!(
+            compressed.parse::<WatchTarget>(),
+            Ok(WatchTarget::PublicKey(key))
+        );
+        assert!("1111short".parse::<WatchTarget>().is_err());
+        assert!("not-a-key".parse::<WatchTarget>().is_err());
+    }
+
+    #[test]
+    fn test_transfer_calls_from_generated_contract() {
+        let term =
+            crate::contract_templates::transfer_contract(OTHER_ADDRESS, "1111recipient", 250)
+                .unwrap();
+        assert_eq!(
+            transfer_calls(&term),
+            vec![TransferCall {
+                to_address: "1111recipient".to_string(),
+                amount: Some(250),
+            }]
+        );
+        assert!(transfer_calls("new x in { x!(1) }").is_empty());
+    }
+
+    #[test]
+    fn test_outgoing_matched_by_key_or_address() {
+        let (key, address) = dev_key();
+        let term =
+            crate::contract_templates::transfer_contract(&address, OTHER_ADDRESS, 500).unwrap();
+        let fixture = block(10, vec![deploy("d1", &key, term)]);
+
+        for target in [
+            WatchTarget::PublicKey(key.clone()),
+            WatchTarget::Address(address.clone()),
+        ] {
+            let watch = WalletWatch::new(&[target]).unwrap();
+            assert_eq!(
+                watch.match_block(&fixture),
+                vec![WalletMatch {
+                    block_hash: "b10".to_string(),
+                    block_number: 10,
+                    deploy_id: "d1".to_string(),
+                    direction: Direction::Outgoing,
+                    address: address.clone(),
+                    amount: Some(500),
+                    errored: false,
+                }]
+            );
+        }
+    }
+
+    #[test]
+    fn test_incoming_transfer_matched_by_recipient() {
+        let (key, address) = dev_key();
+        let term =
+            crate::contract_templates::transfer_contract(OTHER_ADDRESS, &address, 700).unwrap();
+        let fixture = block(11, vec![deploy("d2", &stranger_key(), term)]);
+
+        let watch = WalletWatch::new(&[WatchTarget::PublicKey(key)]).unwrap();
+        let matches = watch.match_block(&fixture);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].direction, Direction::Incoming);
+        assert_eq!(matches[0].address, address);
+        assert_eq!(matches[0].amount, Some(700));
+    }
+
+    #[test]
+    fn test_self_transfer_matches_both_directions() {
+        let (key, address) = dev_key();
+        let term = crate::contract_templates::transfer_contract(&address, &address, 1).unwrap();
+        let fixture = block(12, vec![deploy("d3", &key, term)]);
+
+        let watch = WalletWatch::new(&[WatchTarget::Address(address)]).unwrap();
+        let directions: Vec<_> = watch
+            .match_block(&fixture)
+            .iter()
+            .map(|m| m.direction)
+            .collect();
+        assert_eq!(directions, vec![Direction::Outgoing, Direction::Incoming]);
+    }
+
+    #[test]
+    fn test_unrelated_deploys_do_not_match() {
+        let (key, _) = dev_key();
+        let term =
+            crate::contract_templates::transfer_contract(OTHER_ADDRESS, "1111someoneelse", 5)
+                .unwrap();
+        let fixture = block(
+            13,
+            vec![
+                deploy("d4", &stranger_key(), term),
+                deploy("d5", &stranger_key(), "Nil".to_string()),
+            ],
+        );
+
+        let watch = WalletWatch::new(&[WatchTarget::PublicKey(key)]).unwrap();
+        assert!(watch.match_block(&fixture).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_backfill_scans_range() {
+        let (key, address) = dev_key();
+        let term =
+            crate::contract_templates::transfer_contract(OTHER_ADDRESS, &address, 42).unwrap();
+        let with_transfer = serde_json::json!({
+            "blockInfo": {"blockHash": "b2", "blockNumber": 2},
+            "deploys": [{"sig": "d6", "deployer": stranger_key(), "term": term}],
+        })
+        .to_string();
+
+        let port = spawn_http_node(move |line, _body| {
+            if line.contains("/api/blocks/1/3") {
+                let list = (1..=3)
+                    .map(|n| format!(r#"{{"blockHash":"b{}","blockNumber":{}}}"#, n, n))
+                    .collect::<Vec<_>>()
+                    .join(",");
+                ("200 OK", format!("[{}]", list))
+            } else if line.contains("/api/block/b2") {
+                ("200 OK", with_transfer.clone())
+            } else if let Some(n) = ["b1", "b3"].iter().find(|h| line.contains(*h)) {
+                (
+                    "200 OK",
+                    format!(r#"{{"blockInfo":{{"blockHash":"{}"}},"deploys":[]}}"#, n),
+                )
+            } else {
+                ("404 Not Found", "{}".to_string())
+            }
+        })
+        .await;
+
+        let watch = WalletWatch::new(&[WatchTarget::PublicKey(key)]).unwrap();
+        let source = ChainSource::new("127.0.0.1", port);
+        let (matches, seen) = backfill(&source, &watch, 1, 3, 50).await.unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].deploy_id, "d6");
+        assert_eq!(matches[0].direction, Direction::Incoming);
+        assert_eq!(seen.len(), 3);
+    }
+}
@@ -0,0 +1,462 @@
+//! Signed receipts POSTed to a webhook when a wait ends (`--webhook-url`)
+//!
+//! When `transfer` or `deploy-and-wait` reaches a terminal state, its outcome
+//! is POSTed as JSON ([`WebhookPayload`]) to the URL from `--webhook-url`, or
+//! the `webhook_url` key of `config.toml` in the config directory. With a
+//! shared secret, from `FIREFLY_WEBHOOK_SECRET` or the `webhook_secret` key,
+//! the request carries `X-Firefly-Signature: sha256=<hex>`: an HMAC-SHA256 of
+//! the exact body, which the receiver recomputes to authenticate it.
+//!
+//! Connection errors and 5xx responses are retried up to 3 times, 1, 2 and 4
+//! seconds apart; any other response is final. As with [`crate::notify`], a
+//! delivery that fails never fails the command that reports through it.
+
+use crate::notify::Completion;
+use crate::utils::config_value;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::time::Duration;
+
+/// `config.toml` key holding the webhook URL
+pub const WEBHOOK_URL_KEY: &str = "webhook_url";
+/// `config.toml` key holding the shared secret
+pub const WEBHOOK_SECRET_KEY: &str = "webhook_secret";
+/// Environment variable holding the shared secret; overrides the config file
+pub const WEBHOOK_SECRET_ENV: &str = "FIREFLY_WEBHOOK_SECRET";
+
+/// Header with the HMAC of the body, `sha256=<hex>`
+pub const SIGNATURE_HEADER: &str = "X-Firefly-Signature";
+/// Header repeating the payload's `event`
+pub const EVENT_HEADER: &str = "X-Firefly-Event";
+
+/// How long one POST may take before it counts as unreachable
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// SHA-256 block size, which HMAC pads the key to
+const HMAC_BLOCK_SIZE: usize = 64;
+
+/// HMAC-SHA256 (RFC 2104) of `message` under `key`
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut block = [0u8; HMAC_BLOCK_SIZE];
+    if key.len() > HMAC_BLOCK_SIZE {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let pad = |byte: u8| block.map(|b| b ^ byte);
+    let inner = Sha256::new()
+        .chain_update(pad(0x36))
+        .chain_update(message)
+        .finalize();
+    Sha256::new()
+        .chain_update(pad(0x5c))
+        .chain_update(inner)
+        .finalize()
+        .into()
+}
+
+/// [`SIGNATURE_HEADER`] value for `body` under `secret`
+pub fn signature(secret: &str, body: &[u8]) -> String {
+    format!(
+        "sha256={}",
+        hex::encode(hmac_sha256(secret.as_bytes(), body))
+    )
+}
+
+/// How many times, and how far apart, a failed delivery is retried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts after the first
+    pub retries: u32,
+    /// Wait before the first retry, doubled for each one after
+    pub base_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Wait before retry `retry` (1 for the first)
+    pub fn delay(&self, retry: u32) -> Duration {
+        self.base_delay
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+    }
+
+    /// Whether attempt number `attempts` ending in `attempt` is followed by another
+    pub fn retries_after(&self, attempts: u32, attempt: &Attempt) -> bool {
+        attempt.retryable() && attempts <= self.retries
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retries: 3,
+            base_delay: Duration::from_secs(1),
+        }
+    }
+}
+
+/// How one POST ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Attempt {
+    /// The receiver answered with this HTTP status
+    Status(u16),
+    /// No answer: the connection failed or timed out
+    Unreachable(String),
+}
+
+impl Attempt {
+    pub fn delivered(&self) -> bool {
+        matches!(self, Attempt::Status(status) if (200..300).contains(status))
+    }
+
+    /// Server errors and connection failures; a 4xx will not change on retry
+    pub fn retryable(&self) -> bool {
+        match self {
+            Attempt::Status(status) => *status >= 500,
+            Attempt::Unreachable(_) => true,
+        }
+    }
+}
+
+impl fmt::Display for Attempt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Attempt::Status(status) => write!(f, "HTTP {}", status),
+            Attempt::Unreachable(reason) => write!(f, "unreachable ({})", reason),
+        }
+    }
+}
+
+/// Result of [`Webhook::deliver`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Delivery {
+    pub attempts: u32,
+    /// How the last attempt ended
+    pub last: Attempt,
+}
+
+impl Delivery {
+    pub fn delivered(&self) -> bool {
+        self.last.delivered()
+    }
+}
+
+/// Body of a webhook request
+///
+/// `receipt` is the command's structured result (a deploy receipt), when it got
+/// that far; `error` says why a failed wait failed.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WebhookPayload<R: Serialize> {
+    /// `deploy.succeeded`, `deploy.failed` or `webhook.test`
+    pub event: &'static str,
+    /// CLI command that waited, e.g. `transfer`
+    pub command: String,
+    /// `success` or `failure`
+    pub status: &'static str,
+    pub deploy_id: Option<String>,
+    pub elapsed_ms: u64,
+    pub receipt: Option<R>,
+    pub error: Option<String>,
+}
+
+impl<R: Serialize> WebhookPayload<R> {
+    pub fn new(completion: &Completion<'_>, receipt: Option<R>, error: Option<String>) -> Self {
+        Self {
+            event: if completion.succeeded {
+                "deploy.succeeded"
+            } else {
+                "deploy.failed"
+            },
+            command: completion.command.to_string(),
+            status: completion.status(),
+            deploy_id: completion.deploy_id.map(str::to_string),
+            elapsed_ms: completion.elapsed.as_millis() as u64,
+            receipt,
+            error,
+        }
+    }
+
+    /// The same payload marked as a test (`webhook-test`)
+    pub fn test(mut self) -> Self {
+        self.event = "webhook.test";
+        self
+    }
+}
+
+/// Where receipts are sent, and how
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Webhook {
+    pub url: String,
+    /// Shared secret the body is signed with; requests are unsigned without one
+    pub secret: Option<String>,
+    pub retry: RetryPolicy,
+}
+
+impl Webhook {
+    pub fn new(url: &str, secret: Option<&str>) -> Self {
+        Self {
+            url: url.to_string(),
+            secret: secret.map(str::to_string),
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    /// `url`, else `webhook_url` in `config.toml`, with the configured secret
+    ///
+    /// `None` when no URL is set anywhere.
+    pub fn resolve(url: Option<&str>) -> Option<Self> {
+        let url = match url {
+            Some(url) => url.to_string(),
+            None => config_value(WEBHOOK_URL_KEY)?,
+        };
+        Some(Self::new(&url, webhook_secret().as_deref()))
+    }
+
+    /// POST `payload`, retrying per [`Self::retry`]
+    pub async fn send<R: Serialize>(&self, payload: &WebhookPayload<R>) -> Delivery {
+        match serde_json::to_vec(payload) {
+            Ok(body) => self.deliver(payload.event, &body).await,
+            Err(e) => Delivery {
+                attempts: 0,
+                last: Attempt::Unreachable(format!("could not encode the payload: {}", e)),
+            },
+        }
+    }
+
+    /// POST `body` as the `event` event, retrying per [`Self::retry`]
+    pub async fn deliver(&self, event: &str, body: &[u8]) -> Delivery {
+        let client = match reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build() {
+            Ok(client) => client,
+            Err(e) => {
+                return Delivery {
+                    attempts: 0,
+                    last: Attempt::Unreachable(e.to_string()),
+                }
+            }
+        };
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            let last = self.post(&client, event, body).await;
+            if last.delivered() || !self.retry.retries_after(attempts, &last) {
+                return Delivery { attempts, last };
+            }
+            tracing::debug!(
+                "Webhook attempt {} to {} failed: {}; retrying",
+                attempts,
+                self.url,
+                last
+            );
+            tokio::time::sleep(self.retry.delay(attempts)).await;
+        }
+    }
+
+    async fn post(&self, client: &reqwest::Client, event: &str, body: &[u8]) -> Attempt {
+        let mut request = client
+            .post(&self.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, event)
+            .body(body.to_vec());
+        if let Some(secret) = &self.secret {
+            request = request.header(SIGNATURE_HEADER, signature(secret, body));
+        }
+        match request.send().await {
+            Ok(response) => Attempt::Status(response.status().as_u16()),
+            Err(e) => Attempt::Unreachable(e.to_string()),
+        }
+    }
+}
+
+/// The shared secret: `FIREFLY_WEBHOOK_SECRET`, else `webhook_secret` in `config.toml`
+pub fn webhook_secret() -> Option<String> {
+    if let Ok(secret) = std::env::var(WEBHOOK_SECRET_ENV) {
+        if !secret.is_empty() {
+            return Some(secret);
+        }
+    }
+    config_value(WEBHOOK_SECRET_KEY)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::read_request_head;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::AsyncWriteExt;
+
+    #[test]
+    fn test_hmac_matches_rfc_4231() {
+        // Test cases 1, 2 and 6: short key, text key, key longer than a block
+        let cases: [(&[u8], &[u8], &str); 3] = [
+            (
+                &[0x0b; 20],
+                b"Hi There",
+                "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7",
+            ),
+            (
+                b"Jefe",
+                b"what do ya want for nothing?",
+                "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+            ),
+            (
+                &[0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First",
+                "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54",
+            ),
+        ];
+        for (key, message, expected) in cases {
+            assert_eq!(hex::encode(hmac_sha256(key, message)), expected);
+        }
+        assert_eq!(
+            signature("Jefe", b"what do ya want for nothing?"),
+            format!("sha256={}", cases[1].2)
+        );
+    }
+
+    #[test]
+    fn test_retry_policy() {
+        let policy = RetryPolicy::default();
+        let delays: Vec<u64> = (1..=3).map(|n| policy.delay(n).as_secs()).collect();
+        assert_eq!(delays, vec![1, 2, 4]);
+
+        let server_error = Attempt::Status(503);
+        assert!((1..=3).all(|n| policy.retries_after(n, &server_error)));
+        assert!(!policy.retries_after(4, &server_error));
+        assert!(policy.retries_after(1, &Attempt::Unreachable("refused".into())));
+        // Success and client errors are final
+        assert!(!policy.retries_after(1, &Attempt::Status(200)));
+        assert!(!policy.retries_after(1, &Attempt::Status(404)));
+        assert!(Attempt::Status(204).delivered());
+        assert!(!Attempt::Status(500).delivered());
+    }
+
+    #[test]
+    fn test_payload_shape() {
+        let completion = Completion {
+            command: "transfer",
+            deploy_id: Some("3045abcd"),
+            succeeded: false,
+            elapsed: Duration::from_millis(1500),
+        };
+        let payload = WebhookPayload::new(
+            &completion,
+            Some(serde_json::json!({"block_hash": "b1"})),
+            Some("Insufficient funds".to_string()),
+        );
+        assert_eq!(
+            serde_json::to_value(&payload).unwrap(),
+            serde_json::json!({
+                "event": "deploy.failed",
+                "command": "transfer",
+                "status": "failure",
+                "deploy_id": "3045abcd",
+                "elapsed_ms": 1500,
+                "receipt": {"block_hash": "b1"},
+                "error": "Insufficient funds"
+            })
+        );
+        assert_eq!(payload.test().event, "webhook.test");
+    }
+
+    /// Receiver answering with `statuses` in turn, recording (head, body) of each request
+    async fn spawn_receiver(statuses: Vec<u16>) -> (String, Arc<Mutex<Vec<(String, String)>>>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hooks/firefly", listener.local_addr().unwrap());
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let log = received.clone();
+        tokio::spawn(async move {
+            for status in statuses {
+                let Ok((mut socket, _)) = listener.accept().await else {
+                    return;
+                };
+                log.lock()
+                    .unwrap()
+                    .push(read_request_head(&mut socket).await);
+                let reply = format!(
+                    "HTTP/1.1 {} Status\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    status
+                );
+                let _ = socket.write_all(reply.as_bytes()).await;
+            }
+        });
+        (url, received)
+    }
+
+    fn fast(mut webhook: Webhook) -> Webhook {
+        webhook.retry.base_delay = Duration::from_millis(10);
+        webhook
+    }
+
+    fn header<'a>(head: &'a str, name: &str) -> Option<&'a str> {
+        head.lines().find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.eq_ignore_ascii_case(name).then(|| value.trim())
+        })
+    }
+
+    #[tokio::test]
+    async fn test_delivery_is_signed_and_retried_on_500() {
+        let (url, received) = spawn_receiver(vec![500, 502, 200]).await;
+        let webhook = fast(Webhook::new(&url, Some("s3cret")));
+        let completion = Completion {
+            command: "deploy-and-wait",
+            deploy_id: Some("3045abcd"),
+            succeeded: true,
+            elapsed: Duration::from_secs(2),
+        };
+        let payload = WebhookPayload::new(&completion, Some(serde_json::json!({"cost": 7})), None);
+
+        let delivery = webhook.send(&payload).await;
+        assert!(delivery.delivered(), "{:?}", delivery);
+        assert_eq!(delivery.attempts, 3);
+
+        let expected = serde_json::to_string(&payload).unwrap();
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 3);
+        for (head, body) in received.iter() {
+            assert!(head.starts_with("POST /hooks/firefly "), "{}", head);
+            assert_eq!(body, &expected);
+            assert_eq!(
+                header(head, SIGNATURE_HEADER),
+                Some(signature("s3cret", body.as_bytes()).as_str())
+            );
+            assert_eq!(header(head, EVENT_HEADER), Some("deploy.succeeded"));
+            assert_eq!(header(head, "content-type"), Some("application/json"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_delivery_gives_up() {
+        // A client error is final
+        let (url, received) = spawn_receiver(vec![400, 200]).await;
+        let delivery = fast(Webhook::new(&url, None))
+            .deliver("deploy.failed", b"{}")
+            .await;
+        assert_eq!(delivery.last, Attempt::Status(400));
+        assert_eq!(delivery.attempts, 1);
+        // Unsigned without a secret
+        assert_eq!(
+            header(&received.lock().unwrap()[0].0, SIGNATURE_HEADER),
+            None
+        );
+
+        // Server errors stop after the retries
+        let (url, received) = spawn_receiver(vec![500; 5]).await;
+        let delivery = fast(Webhook::new(&url, Some("k")))
+            .deliver("x", b"{}")
+            .await;
+        assert_eq!(delivery.last, Attempt::Status(500));
+        assert_eq!(delivery.attempts, 4);
+        assert_eq!(received.lock().unwrap().len(), 4);
+
+        // Nothing listening
+        let port = {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().port()
+        };
+        let url = format!("http://127.0.0.1:{}/", port);
+        let delivery = fast(Webhook::new(&url, None)).deliver("x", b"{}").await;
+        assert!(matches!(delivery.last, Attempt::Unreachable(_)));
+        assert_eq!(delivery.attempts, 4);
+    }
+}